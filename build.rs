@@ -0,0 +1,42 @@
+//! Exposes the resolved `wasi-preview1-component-adapter-provider` version as
+//! `VTX_ADAPTER_VERSION`, so the bundled adapter version cannot drift from
+//! the dependency actually compiled in.
+
+use std::path::Path;
+
+const ADAPTER_CRATE: &str = "wasi-preview1-component-adapter-provider";
+
+fn main() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set");
+    let lock = Path::new(&manifest_dir).join("Cargo.lock");
+    let manifest = Path::new(&manifest_dir).join("Cargo.toml");
+    println!("cargo:rerun-if-changed={}", lock.display());
+    println!("cargo:rerun-if-changed={}", manifest.display());
+
+    let version = locked_version(&lock)
+        .or_else(|| required_version(&manifest))
+        .unwrap_or_else(|| panic!("{ADAPTER_CRATE} not found in Cargo.lock or Cargo.toml"));
+    println!("cargo:rustc-env=VTX_ADAPTER_VERSION={version}");
+}
+
+/// The version Cargo resolved, when a lock file sits next to the manifest.
+fn locked_version(lock: &Path) -> Option<String> {
+    let lock: toml::Value = std::fs::read_to_string(lock).ok()?.parse().ok()?;
+    lock.get("package")?
+        .as_array()?
+        .iter()
+        .find(|package| package.get("name").and_then(toml::Value::as_str) == Some(ADAPTER_CRATE))?
+        .get("version")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// The requirement from Cargo.toml, for builds without a lock file.
+fn required_version(manifest: &Path) -> Option<String> {
+    let manifest: toml::Value = std::fs::read_to_string(manifest).ok()?.parse().ok()?;
+    let dependency = manifest.get("dependencies")?.get(ADAPTER_CRATE)?;
+    let requirement = dependency
+        .as_str()
+        .or_else(|| dependency.get("version")?.as_str())?;
+    Some(requirement.trim_start_matches(['=', '^', '~']).to_string())
+}
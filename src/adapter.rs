@@ -0,0 +1,317 @@
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::str::FromStr;
+use wasmparser::{Encoding, ExternalKind, Parser as WasmParser, Payload, TypeRef};
+
+use wasi_preview1_component_adapter_provider::{
+    WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER,
+    WASI_SNAPSHOT_PREVIEW1_PROXY_ADAPTER, WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER,
+};
+
+/// Names of the adapters bundled with the CLI.
+pub const BUNDLED_ADAPTERS: &[&str] = &["reactor", "command", "proxy"];
+
+/// WASI preview1 adapter injected when encoding a core module.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Adapter {
+    /// Bundled adapter for libraries driven through exports. VTX plugins use this.
+    #[default]
+    Reactor,
+
+    /// Bundled adapter calling the module's `_start` from `wasi:cli/run`.
+    Command,
+
+    /// Bundled adapter for `wasi:http/proxy` hosts, without filesystem or CLI imports.
+    Proxy,
+
+    /// A preview1 adapter module built elsewhere.
+    File(PathBuf),
+}
+
+impl FromStr for Adapter {
+    type Err = anyhow::Error;
+
+    /// `reactor`, `command`, `proxy`, or a path to a `.wasm` adapter.
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "reactor" => Ok(Self::Reactor),
+            "command" => Ok(Self::Command),
+            "proxy" => Ok(Self::Proxy),
+            path if path.ends_with(".wasm") => Ok(Self::File(PathBuf::from(path))),
+            other => anyhow::bail!(
+                "Unknown adapter '{other}' (expected {} or a path to a .wasm adapter)",
+                BUNDLED_ADAPTERS.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Adapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            bundled => f.write_str(bundled.kind()),
+        }
+    }
+}
+
+/// WASI ABI a compiled module targets, which decides the adapter it needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Abi {
+    /// Core module calling `wasi_snapshot_preview1` (`wasm32-wasip1`).
+    Preview1,
+
+    /// Preview1 core module serving the `wasi:http/proxy` world.
+    Preview1Proxy,
+
+    /// Core module without preview1 imports (`wasm32-wasip2`, or no WASI at all).
+    Preview2,
+
+    /// Already a component; nothing to encode.
+    Component,
+}
+
+impl Abi {
+    /// Adapter to encode a module of this ABI with, given the configured one.
+    ///
+    /// Preview2 modules and components get none. A proxy-world module swaps
+    /// the default reactor adapter for the proxy adapter; any other choice is kept.
+    pub fn adapter(self, configured: &Adapter) -> Option<Adapter> {
+        match self {
+            Self::Preview1 => Some(configured.clone()),
+            Self::Preview1Proxy if *configured == Adapter::Reactor => Some(Adapter::Proxy),
+            Self::Preview1Proxy => Some(configured.clone()),
+            Self::Preview2 | Self::Component => None,
+        }
+    }
+}
+
+/// Detect the ABI of a compiled wasm binary from its header, imports and exports.
+///
+/// A core module with preview1 imports serves the proxy world when it imports
+/// `wasi:http/` interfaces or exports `wasi:http/incoming-handler`.
+pub fn detect_abi(bytes: &[u8]) -> Result<Abi> {
+    let mut preview1 = false;
+    let mut http = false;
+    for payload in WasmParser::new(0).parse_all(bytes) {
+        match payload? {
+            Payload::Version {
+                encoding: Encoding::Component,
+                ..
+            } => return Ok(Abi::Component),
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    preview1 |= import.module == WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME;
+                    http |= import.module.starts_with("wasi:http/");
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    http |= export?.name.starts_with("wasi:http/incoming-handler");
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(match (preview1, http) {
+        (false, _) => Abi::Preview2,
+        (true, true) => Abi::Preview1Proxy,
+        (true, false) => Abi::Preview1,
+    })
+}
+
+/// An adapter's bytes and what was detected about it.
+#[derive(Debug, Clone)]
+pub struct LoadedAdapter {
+    pub bytes: Cow<'static, [u8]>,
+
+    /// wit-bindgen version that produced the adapter's bindings, e.g. `0.49.0`.
+    pub wit_bindgen: Option<String>,
+
+    /// WASI 0.2 release the adapter's imports target, e.g. `0.2.6`.
+    pub wasi: Option<String>,
+
+    /// Functions the adapter exports, i.e. the preview1 calls it implements.
+    exports: BTreeSet<String>,
+}
+
+impl LoadedAdapter {
+    /// `0.49.0`/`0.2.6` style description for messages.
+    pub fn describe(&self) -> String {
+        format!(
+            "wit-bindgen {}, WASI {}",
+            self.wit_bindgen.as_deref().unwrap_or("unknown"),
+            self.wasi.as_deref().unwrap_or("unknown")
+        )
+    }
+}
+
+impl Adapter {
+    /// `reactor`, `command`, `proxy` or `custom`, as recorded in `.vtx` metadata.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Reactor => "reactor",
+            Self::Command => "command",
+            Self::Proxy => "proxy",
+            Self::File(_) => "custom",
+        }
+    }
+
+    /// Version recorded as `adapter_version` in `.vtx` metadata: the bundled
+    /// crate version, or the `sha256:` digest of a custom adapter file.
+    pub fn version(&self) -> Result<String> {
+        use crate::hex;
+        use sha2::{Digest, Sha256};
+
+        match self {
+            Self::File(path) => {
+                let bytes = std::fs::read(path)
+                    .with_context(|| format!("Failed to read adapter {}", path.display()))?;
+                Ok(format!("sha256:{}", hex::encode(&Sha256::digest(&bytes))))
+            }
+            _ => Ok(crate::packager::BUNDLED_ADAPTER_VERSION.to_string()),
+        }
+    }
+
+    /// Read the adapter and detect its versions.
+    pub fn load(&self) -> Result<LoadedAdapter> {
+        let bytes: Cow<'static, [u8]> = match self {
+            Self::Reactor => Cow::Borrowed(WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER),
+            Self::Command => Cow::Borrowed(WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER),
+            Self::Proxy => Cow::Borrowed(WASI_SNAPSHOT_PREVIEW1_PROXY_ADAPTER),
+            Self::File(path) => Cow::Owned(
+                std::fs::read(path)
+                    .with_context(|| format!("Failed to read adapter {}", path.display()))?,
+            ),
+        };
+
+        let mut loaded = LoadedAdapter {
+            bytes: Cow::Borrowed(&[]),
+            wit_bindgen: None,
+            wasi: None,
+            exports: BTreeSet::new(),
+        };
+        for payload in WasmParser::new(0).parse_all(&bytes) {
+            match payload.with_context(|| format!("Adapter {self} is not valid wasm"))? {
+                Payload::Version {
+                    encoding: Encoding::Component,
+                    ..
+                } => anyhow::bail!(
+                    "Adapter {self} is a component; expected a preview1 adapter core module"
+                ),
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        let export = export?;
+                        if export.kind == ExternalKind::Func {
+                            loaded.exports.insert(export.name.to_string());
+                        }
+                    }
+                }
+                Payload::CustomSection(reader) => {
+                    if let Some((wit_bindgen, wasi)) = parse_bindgen_section(reader.name()) {
+                        loaded.wit_bindgen.get_or_insert(wit_bindgen);
+                        if wasi.is_some() {
+                            loaded.wasi = wasi;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if loaded.wit_bindgen.is_none() {
+            anyhow::bail!(
+                "Adapter {self} has no component-type section; expected a WASI preview1 component adapter"
+            );
+        }
+        loaded.bytes = bytes;
+        Ok(loaded)
+    }
+}
+
+/// Check that `adapter` can serve `module` before encoding.
+///
+/// Fails when the module imports preview1 functions the adapter does not
+/// implement (the proxy adapter has no filesystem or CLI calls), or when the
+/// command adapter is used for a module without a `_start` export. Returns a
+/// warning when the module imports WASI 0.2 interfaces at a different version
+/// than the adapter targets, which encoding may reject.
+pub fn check_module(
+    module: &[u8],
+    adapter: &Adapter,
+    loaded: &LoadedAdapter,
+) -> Result<Option<String>> {
+    let mut missing = Vec::new();
+    let mut wasi_versions = BTreeSet::new();
+    let mut has_start = false;
+
+    for payload in WasmParser::new(0).parse_all(module) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    if import.module == WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME
+                        && matches!(import.ty, TypeRef::Func(_))
+                        && !loaded.exports.contains(import.name)
+                    {
+                        missing.push(import.name.to_string());
+                    }
+                    if let Some((_, version)) = import
+                        .module
+                        .strip_prefix("wasi:")
+                        .and_then(|m| m.split_once('@'))
+                    {
+                        wasi_versions.insert(version.to_string());
+                    }
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    has_start |= export?.name == "_start";
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "The {adapter} adapter ({}) does not implement {}::{}; choose an adapter that does with --adapter or build.adapter",
+            loaded.describe(),
+            WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME,
+            missing.join(", ")
+        );
+    }
+    if *adapter == Adapter::Command && !has_start {
+        anyhow::bail!(
+            "The command adapter runs the module's `_start` export, which this module does not have; use the reactor adapter"
+        );
+    }
+
+    let mismatched: Vec<String> = wasi_versions
+        .into_iter()
+        .filter(|v| loaded.wasi.as_deref().is_some_and(|a| a != v))
+        .collect();
+    Ok((!mismatched.is_empty()).then(|| {
+        format!(
+            "Module imports WASI {} interfaces but the {adapter} adapter targets WASI {}; if encoding fails, use an adapter built for the module's WASI version",
+            mismatched.join(", "),
+            loaded.wasi.as_deref().unwrap_or("unknown")
+        )
+    }))
+}
+
+/// `component-type:wit-bindgen:0.49.0:wasi:cli@0.2.6:imports:encoded world`
+/// gives `0.49.0` and, for a `wasi:` package, `0.2.6`.
+fn parse_bindgen_section(name: &str) -> Option<(String, Option<String>)> {
+    let rest = name.strip_prefix("component-type:wit-bindgen:")?;
+    let (version, world) = rest.split_once(':')?;
+    let wasi = world
+        .strip_prefix("wasi:")
+        .and_then(|w| w.split(':').next())
+        .and_then(|package| package.split_once('@'))
+        .map(|(_, v)| v.to_string());
+    Some((version.to_string(), wasi))
+}
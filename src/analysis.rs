@@ -0,0 +1,218 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use wasmparser::{Encoding, Name, NameSectionReader, Parser as WasmParser, Payload, TypeRef};
+
+/// Bytes attributed to one section kind, custom section, name prefix or function.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SizeEntry {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Size breakdown of a core module or component, from [`analyze`].
+///
+/// All lists are sorted by size, largest first.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SizeReport {
+    /// Size of the analyzed binary.
+    pub total: u64,
+
+    /// Bytes per section kind, summed over every nested core module.
+    /// `(headers)` covers preambles and section headers.
+    pub sections: Vec<SizeEntry>,
+
+    /// Bytes per custom section name (`name`, `.debug_info`, ...).
+    pub custom_sections: Vec<SizeEntry>,
+
+    /// Code bytes per function name prefix (`core`, `alloc`, `serde_json`, ...).
+    /// Empty when the modules carry no function names.
+    pub prefixes: Vec<SizeEntry>,
+
+    /// Code bytes per function body. Unnamed functions are `func[<index>]`.
+    pub functions: Vec<SizeEntry>,
+}
+
+/// Per core module state while its payloads are walked.
+#[derive(Default)]
+struct ModuleState {
+    imported_functions: u32,
+    bodies: Vec<u64>,
+    names: HashMap<u32, String>,
+}
+
+/// Attribute the bytes of `wasm` to sections, custom sections and functions.
+///
+/// Nested core modules and components are walked recursively; a nested
+/// module counts through its own sections rather than as one blob. Function
+/// names come from the `name` custom section and are grouped by their first
+/// path segment (`core::fmt::write` and `runtime.mallocgc` give `core` and
+/// `runtime`), in the style of twiggy.
+pub fn analyze(wasm: &[u8]) -> Result<SizeReport> {
+    let mut sections: BTreeMap<String, u64> = BTreeMap::new();
+    let mut custom_sections: BTreeMap<String, u64> = BTreeMap::new();
+    let mut functions = Vec::new();
+    let mut encodings = Vec::new();
+    // One entry per open module or component; `None` for components.
+    let mut modules: Vec<Option<ModuleState>> = Vec::new();
+
+    for payload in WasmParser::new(0).parse_all(wasm) {
+        let payload = payload?;
+        match &payload {
+            Payload::Version { encoding, .. } => {
+                encodings.push(*encoding);
+                modules.push((*encoding == Encoding::Module).then(ModuleState::default));
+            }
+            Payload::End(_) => {
+                encodings.pop();
+                if let Some(Some(module)) = modules.pop() {
+                    functions.extend(module_functions(module));
+                }
+            }
+            Payload::ImportSection(reader) => {
+                if let Some(Some(module)) = modules.last_mut() {
+                    for import in reader.clone() {
+                        if matches!(import?.ty, TypeRef::Func(_)) {
+                            module.imported_functions += 1;
+                        }
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                if let Some(Some(module)) = modules.last_mut() {
+                    module.bodies.push(body.range().len() as u64);
+                }
+            }
+            Payload::CustomSection(reader) => {
+                *custom_sections
+                    .entry(reader.name().to_string())
+                    .or_default() += reader.range().len() as u64;
+                if reader.name() == "name" {
+                    if let Some(Some(module)) = modules.last_mut() {
+                        read_function_names(reader.data(), reader.data_offset(), module);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Nested modules and components are counted through their own sections.
+        let nested = matches!(
+            payload,
+            Payload::ModuleSection { .. } | Payload::ComponentSection { .. }
+        );
+        if let (false, Some((id, range))) = (nested, payload.as_section()) {
+            let component = encodings.last() == Some(&Encoding::Component);
+            *sections
+                .entry(section_name(id, component).to_string())
+                .or_default() += range.len() as u64;
+        }
+    }
+
+    let counted: u64 = sections.values().sum();
+    let total = wasm.len() as u64;
+    sections.insert("(headers)".to_string(), total.saturating_sub(counted));
+
+    let mut prefixes: BTreeMap<String, u64> = BTreeMap::new();
+    for function in &functions {
+        if let Some(prefix) = name_prefix(&function.name) {
+            *prefixes.entry(prefix.to_string()).or_default() += function.size;
+        }
+    }
+
+    Ok(SizeReport {
+        total,
+        sections: sorted(sections),
+        custom_sections: sorted(custom_sections),
+        prefixes: sorted(prefixes),
+        functions: {
+            functions.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+            functions
+        },
+    })
+}
+
+fn module_functions(module: ModuleState) -> impl Iterator<Item = SizeEntry> {
+    let ModuleState {
+        imported_functions,
+        bodies,
+        mut names,
+    } = module;
+    bodies.into_iter().enumerate().map(move |(i, size)| {
+        let index = imported_functions + i as u32;
+        SizeEntry {
+            name: names
+                .remove(&index)
+                .unwrap_or_else(|| format!("func[{index}]")),
+            size,
+        }
+    })
+}
+
+/// Collect function names; a malformed name section is ignored.
+fn read_function_names(data: &[u8], offset: usize, module: &mut ModuleState) {
+    for name in NameSectionReader::new(data, offset) {
+        let Ok(Name::Function(map)) = name else {
+            continue;
+        };
+        for naming in map.into_iter().flatten() {
+            module.names.insert(naming.index, naming.name.to_string());
+        }
+    }
+}
+
+/// First path segment of a function name, or `None` for unnamed and flat names.
+fn name_prefix(name: &str) -> Option<&str> {
+    if name.starts_with("func[") {
+        return None;
+    }
+    let name = name.trim_start_matches(['<', '&', '*', ' ']);
+    let name = name.strip_prefix("mut ").unwrap_or(name);
+    let end = match (name.find("::"), name.find('.')) {
+        (Some(a), Some(b)) => a.min(b),
+        (Some(a), None) => a,
+        (None, Some(b)) => b,
+        (None, None) => return None,
+    };
+    let prefix = &name[..end];
+    (!prefix.is_empty()).then_some(prefix)
+}
+
+fn section_name(id: u8, component: bool) -> &'static str {
+    match (component, id) {
+        (_, 0) => "custom",
+        (false, 1) => "type",
+        (false, 2) => "import",
+        (false, 3) => "function",
+        (false, 4) => "table",
+        (false, 5) => "memory",
+        (false, 6) => "global",
+        (false, 7) => "export",
+        (false, 8) => "start",
+        (false, 9) => "element",
+        (false, 10) => "code",
+        (false, 11) => "data",
+        (false, 12) => "data count",
+        (false, 13) => "tag",
+        (true, 2) => "core instance",
+        (true, 3) => "core type",
+        (true, 5) => "component instance",
+        (true, 6) => "component alias",
+        (true, 7) => "component type",
+        (true, 8) => "component canonical",
+        (true, 9) => "component start",
+        (true, 10) => "component import",
+        (true, 11) => "component export",
+        _ => "unknown",
+    }
+}
+
+fn sorted(map: BTreeMap<String, u64>) -> Vec<SizeEntry> {
+    let mut entries: Vec<SizeEntry> = map
+        .into_iter()
+        .filter(|(_, size)| *size > 0)
+        .map(|(name, size)| SizeEntry { name, size })
+        .collect();
+    entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    entries
+}
@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::AssetsConfig;
+use crate::ledger::sha256_digest;
+
+/// Declared asset set with checksums, kept next to vtx.toml and checked in.
+pub const ASSETS_MANIFEST: &str = "vtx-assets.json";
+
+/// Asset root used when `assets.dir` is not set.
+pub const DEFAULT_ASSETS_DIR: &str = "assets";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AssetEntry {
+    /// Path relative to the asset root, with `/` separators.
+    pub path: String,
+    pub size: u64,
+    pub digest: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AssetManifest {
+    /// Entries sorted by path.
+    pub files: Vec<AssetEntry>,
+}
+
+impl AssetManifest {
+    /// Load the manifest. A missing manifest is empty.
+    pub fn load() -> Result<Self> {
+        match std::fs::read(ASSETS_MANIFEST) {
+            Ok(content) => serde_json::from_slice(&content)
+                .with_context(|| format!("Failed to parse {ASSETS_MANIFEST}")),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {ASSETS_MANIFEST}")),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        std::fs::write(ASSETS_MANIFEST, json)
+            .with_context(|| format!("Failed to write {ASSETS_MANIFEST}"))
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    /// Insert or refresh an entry, keeping the list sorted.
+    pub fn upsert(&mut self, entry: AssetEntry) {
+        match self.files.binary_search_by(|f| f.path.cmp(&entry.path)) {
+            Ok(i) => self.files[i] = entry,
+            Err(i) => self.files.insert(i, entry),
+        }
+    }
+
+    /// Remove entries at `path` or below it. Returns how many were removed.
+    pub fn remove(&mut self, path: &str) -> usize {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        let before = self.files.len();
+        self.files
+            .retain(|f| f.path != path && !f.path.starts_with(&prefix));
+        before - self.files.len()
+    }
+}
+
+/// Asset root from `[assets]`, relative to vtx.toml.
+pub fn assets_dir(config: Option<&AssetsConfig>) -> PathBuf {
+    PathBuf::from(
+        config
+            .and_then(|c| c.dir.as_deref())
+            .unwrap_or(DEFAULT_ASSETS_DIR),
+    )
+}
+
+/// Hash a file under `root` into a manifest entry.
+pub fn entry_for(root: &Path, rel: &str) -> Result<AssetEntry> {
+    let path = root.join(rel);
+    let bytes = std::fs::read(&path)
+        .with_context(|| format!("Failed to read asset: {}", path.display()))?;
+    Ok(AssetEntry {
+        path: rel.to_string(),
+        size: bytes.len() as u64,
+        digest: sha256_digest(&bytes),
+    })
+}
+
+/// Files below `path` (or `path` itself), relative to `root`, sorted.
+pub fn collect_files(root: &Path, path: &Path) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        if current.is_dir() {
+            let entries = std::fs::read_dir(&current)
+                .with_context(|| format!("Failed to read dir: {}", current.display()))?;
+            for entry in entries {
+                pending.push(entry?.path());
+            }
+        } else if current.is_file() {
+            out.push(relative_path(root, &current)?);
+        } else {
+            anyhow::bail!("Asset not found: {}", current.display());
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// `path` relative to `root` with `/` separators; fails outside `root`.
+pub fn relative_path(root: &Path, path: &Path) -> Result<String> {
+    let rel = path.strip_prefix(root).map_err(|_| {
+        anyhow::anyhow!(
+            "Asset {} is outside the asset directory {}",
+            path.display(),
+            root.display()
+        )
+    })?;
+    Ok(rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// Per-file and total size budgets from `[assets]`.
+pub fn check_budgets(manifest: &AssetManifest, config: Option<&AssetsConfig>) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(max) = config.and_then(|c| c.max_file_bytes) {
+        for file in manifest.files.iter().filter(|f| f.size > max) {
+            problems.push(format!(
+                "{} is {} bytes, over assets.max_file_bytes ({max})",
+                file.path, file.size
+            ));
+        }
+    }
+    if let Some(max) = config.and_then(|c| c.max_total_bytes) {
+        let total = manifest.total_size();
+        if total > max {
+            problems.push(format!(
+                "Assets total {total} bytes, over assets.max_total_bytes ({max})"
+            ));
+        }
+    }
+    problems
+}
+
+/// Compare declared assets with the files on disk.
+///
+/// Returns problems (missing or modified files, exceeded budgets) and the
+/// files present under the asset root but not declared.
+pub fn verify(
+    manifest: &AssetManifest,
+    config: Option<&AssetsConfig>,
+) -> Result<(Vec<String>, Vec<String>)> {
+    let root = assets_dir(config);
+    let mut problems = Vec::new();
+
+    for file in &manifest.files {
+        let path = root.join(&file.path);
+        if !path.is_file() {
+            problems.push(format!("{} is declared but missing", file.path));
+            continue;
+        }
+        let current = entry_for(&root, &file.path)?;
+        if current.digest != file.digest {
+            problems.push(format!(
+                "{} was modified (declared {}, found {})",
+                file.path, file.digest, current.digest
+            ));
+        }
+    }
+    problems.extend(check_budgets(manifest, config));
+
+    let undeclared = if root.is_dir() {
+        collect_files(&root, &root)?
+            .into_iter()
+            .filter(|p| manifest.files.iter().all(|f| &f.path != p))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok((problems, undeclared))
+}
@@ -0,0 +1,255 @@
+use super::{build_env, extra_args, features, Builder, EnvReport, ToolCheck};
+use crate::config::BuildConfig;
+use crate::toolchain;
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use wasmparser::{Chunk, Parser as WasmParser, Payload};
+
+/// Source extensions compiled as C.
+const C_EXTENSIONS: &[&str] = &["c"];
+
+/// Source extensions compiled as C++; any of them switches the driver to `clang++`.
+const CXX_EXTENSIONS: &[&str] = &["cc", "cpp", "cxx"];
+
+/// Source directory used when `[build.c] sources` is not set.
+const DEFAULT_SOURCE_DIR: &str = "src";
+
+/// Custom section in which wasm-ld records the toolchain that linked the module.
+const PRODUCERS_SECTION: &str = "producers";
+
+/// C/C++ builder using clang from wasi-sdk.
+///
+/// Responsibilities: compile C and C++ sources to a WASI reactor module.
+/// Toolchain resolution:
+/// - `WASI_SDK_PATH` set: `$WASI_SDK_PATH/bin/clang[++]` with its bundled sysroot.
+/// - Otherwise `clang`/`clang++` from PATH, with the sysroot from `WASI_SYSROOT` if set.
+pub struct CBuilder {
+    pub build_config: Option<BuildConfig>,
+}
+
+impl CBuilder {
+    pub fn new(build_config: Option<BuildConfig>) -> Self {
+        Self { build_config }
+    }
+
+    /// Compiler driver: `clang` or `clang++`, inside wasi-sdk when configured.
+    fn compiler(&self, cxx: bool) -> String {
+        let driver = if cxx { "clang++" } else { "clang" };
+        match std::env::var_os("WASI_SDK_PATH") {
+            Some(sdk) => Path::new(&sdk)
+                .join("bin")
+                .join(driver)
+                .display()
+                .to_string(),
+            None => driver.to_string(),
+        }
+    }
+
+    /// `--sysroot` for the WASI libc headers and libraries, if one is configured.
+    fn sysroot(&self) -> Option<PathBuf> {
+        if let Some(sdk) = std::env::var_os("WASI_SDK_PATH") {
+            return Some(Path::new(&sdk).join("share").join("wasi-sysroot"));
+        }
+        std::env::var_os("WASI_SYSROOT").map(PathBuf::from)
+    }
+
+    /// Source files from `[build.c] sources` (files or directories), default `src/`.
+    fn sources(&self) -> Result<Vec<PathBuf>> {
+        let roots: Vec<PathBuf> = self
+            .build_config
+            .as_ref()
+            .and_then(|c| c.c.as_ref())
+            .and_then(|c| c.sources.clone())
+            .unwrap_or_else(|| vec![DEFAULT_SOURCE_DIR.to_string()])
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+
+        let mut sources = Vec::new();
+        for root in roots {
+            if root.is_file() {
+                sources.push(root);
+            } else if root.is_dir() {
+                collect_sources(&root, &mut sources)?;
+            } else {
+                anyhow::bail!("C source path not found: {}", root.display());
+            }
+        }
+        sources.sort();
+        if sources.is_empty() {
+            anyhow::bail!(
+                "No C/C++ sources found (expected .c, .cc, .cpp or .cxx files; set [build.c] sources)"
+            );
+        }
+        Ok(sources)
+    }
+}
+
+fn collect_sources(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_sources(&path, out)?;
+        } else if has_extension(&path, C_EXTENSIONS) || has_extension(&path, CXX_EXTENSIONS) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `module` without its custom sections called `name`, or `None` if it has none.
+fn strip_custom_section(module: &[u8], name: &str) -> Result<Option<Vec<u8>>> {
+    let mut stripped = Vec::with_capacity(module.len());
+    let mut parser = WasmParser::new(0);
+    let mut offset = 0usize;
+    let mut found = false;
+    while offset < module.len() {
+        let (consumed, payload) = match parser.parse(&module[offset..], true)? {
+            Chunk::Parsed { consumed, payload } => (consumed, payload),
+            Chunk::NeedMoreData(_) => break,
+        };
+        let raw = &module[offset..offset + consumed];
+        offset += consumed;
+        if let Payload::CustomSection(reader) = &payload {
+            if reader.name() == name {
+                found = true;
+                continue;
+            }
+        }
+        stripped.extend_from_slice(raw);
+    }
+    Ok(found.then_some(stripped))
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.contains(&e))
+}
+
+impl Builder for CBuilder {
+    /// Check clang and the WASI sysroot.
+    fn check_env(&self) -> EnvReport {
+        let mut checks = vec![ToolCheck::probe(
+            &self.compiler(false),
+            &["--version"],
+            true,
+            "Please install wasi-sdk (https://github.com/WebAssembly/wasi-sdk) and set WASI_SDK_PATH",
+        )];
+        let sysroot = self.sysroot();
+        let present = sysroot.as_ref().is_some_and(|p| p.is_dir());
+        checks.push(ToolCheck {
+            tool: "wasi-sysroot".to_string(),
+            version: sysroot.as_ref().map(|p| p.display().to_string()),
+            required: sysroot.is_some(),
+            minimum_version: None,
+            constraint: "present".to_string(),
+            satisfied: present,
+            remediation: (!present).then(|| {
+                "Set WASI_SDK_PATH to a wasi-sdk install, or WASI_SYSROOT to a wasi-libc sysroot"
+                    .to_string()
+            }),
+        });
+        EnvReport::new("c", checks)
+    }
+
+    /// Compile all sources into one reactor module.
+    ///
+    /// # Side effects
+    /// - Creates build artifacts under target.
+    /// - Invokes the external clang process.
+    fn build(&self, package: &str, target: &str, release: bool) -> Result<()> {
+        let profile = if release { "release" } else { "debug" };
+        let output_dir = Path::new("target").join(target).join(profile);
+        fs::create_dir_all(&output_dir).context("Failed to create target directory for C build")?;
+        let output_path = output_dir.join(format!("{package}.wasm"));
+
+        let sources = self.sources()?;
+        let cxx = sources.iter().any(|s| has_extension(s, CXX_EXTENSIONS));
+        let compiler = self.compiler(cxx);
+
+        // Example: clang --target=wasm32-wasip1 -mexec-model=reactor -O2 -o target/.../pkg.wasm src/plugin.c
+        let mut args = vec![
+            format!("--target={target}"),
+            "-mexec-model=reactor".to_string(),
+        ];
+        if let Some(sysroot) = self.sysroot() {
+            args.push(format!("--sysroot={}", sysroot.display()));
+        }
+        args.push(if release { "-O2" } else { "-O0" }.to_string());
+        if !release {
+            args.push("-g".to_string());
+        }
+        if cxx {
+            // wasi-libc has no unwinder.
+            args.push("-fno-exceptions".to_string());
+        }
+        let config = self.build_config.as_ref();
+        if config.and_then(|c| c.no_simd).unwrap_or(false) {
+            args.push("-mno-simd128".to_string());
+        }
+        args.extend(
+            config
+                .and_then(|c| c.c.as_ref())
+                .and_then(|c| c.flags.clone())
+                .unwrap_or_default(),
+        );
+        args.extend(features(config).iter().map(|f| format!("-D{f}")));
+        args.extend(extra_args(config).iter().cloned());
+        args.push("-o".to_string());
+        args.push(output_path.display().to_string());
+        args.extend(sources.iter().map(|s| s.display().to_string()));
+
+        step!("Executing: {} {}", compiler, args.join(" "));
+
+        let status = Command::new(&compiler)
+            .args(&args)
+            .envs(build_env(config))
+            .status()
+            .with_context(|| format!("Failed to execute {compiler}"))?;
+
+        if !status.success() {
+            anyhow::bail!("{compiler} failed with non-zero exit code");
+        }
+
+        Ok(())
+    }
+
+    /// Locate the clang build artifact at the path passed with `-o`.
+    fn find_output(&self, package: &str, target: &str, release: bool) -> Result<PathBuf> {
+        let profile = if release { "release" } else { "debug" };
+        let path = Path::new("target")
+            .join(target)
+            .join(profile)
+            .join(format!("{package}.wasm"));
+
+        if path.exists() {
+            Ok(path)
+        } else {
+            anyhow::bail!("Expected build artifact not found at: {}", path.display())
+        }
+    }
+
+    /// Drop the `producers` section wasm-ld writes: it names the exact clang
+    /// build, so identical sources linked by two wasi-sdk builds differ. The
+    /// version is recorded under `builder.clang_version` instead.
+    fn post_process(&self, wasm_path: &Path) -> Result<()> {
+        let module = fs::read(wasm_path)
+            .with_context(|| format!("Failed to read {}", wasm_path.display()))?;
+        if let Some(stripped) = strip_custom_section(&module, PRODUCERS_SECTION)? {
+            fs::write(wasm_path, stripped)
+                .with_context(|| format!("Failed to write {}", wasm_path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Record the clang version used for the build.
+    fn metadata(&self, meta: &mut Map<String, Value>) {
+        if let Some(version) = toolchain::probe_version(&self.compiler(false), &["--version"]) {
+            meta.insert("clang_version".into(), Value::String(version));
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use super::{build_env, extra_args, Builder, EnvReport, ToolCheck};
+use crate::config::BuildConfig;
+use crate::toolchain;
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// NuGet package that brings NativeAOT-LLVM and the component tooling into a project.
+const COMPONENTIZE_PACKAGE: &str = "BytecodeAlliance.Componentize.DotNet.Wasm.SDK";
+
+/// .NET runtime identifier for WASI builds.
+const WASI_RID: &str = "wasi-wasm";
+
+/// C# builder based on componentize-dotnet.
+///
+/// Responsibilities: run `dotnet publish` for the wasi-wasm RID.
+/// Dependencies: the .NET SDK, and a project referencing componentize-dotnet
+/// (which pulls in the NativeAOT-LLVM compiler).
+pub struct DotnetBuilder {
+    pub build_config: Option<BuildConfig>,
+}
+
+impl DotnetBuilder {
+    pub fn new(build_config: Option<BuildConfig>) -> Self {
+        Self { build_config }
+    }
+
+    /// `*.csproj` files in the project directory, sorted.
+    fn project_files() -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(".") else {
+            return Vec::new();
+        };
+        let mut projects: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "csproj"))
+            .collect();
+        projects.sort();
+        projects
+    }
+
+    /// `dotnet publish -o` directory: `build.output_dir`, or one per target and profile.
+    fn publish_dir(&self, target: &str, release: bool) -> PathBuf {
+        if let Some(dir) = self
+            .build_config
+            .as_ref()
+            .and_then(|c| c.output_dir.as_ref())
+        {
+            return PathBuf::from(dir);
+        }
+        let profile = if release { "release" } else { "debug" };
+        Path::new("target")
+            .join(target)
+            .join(profile)
+            .join("dotnet")
+    }
+}
+
+impl Builder for DotnetBuilder {
+    /// Check the .NET SDK and the componentize-dotnet package reference.
+    fn check_env(&self) -> EnvReport {
+        let dotnet = ToolCheck::probe(
+            "dotnet",
+            &["--version"],
+            true,
+            "Please install the .NET 9 SDK (https://dotnet.microsoft.com/download)",
+        );
+        let referenced = Self::project_files().iter().any(|p| {
+            fs::read_to_string(p).is_ok_and(|content| content.contains(COMPONENTIZE_PACKAGE))
+        });
+        let componentize = ToolCheck {
+            tool: "componentize-dotnet".to_string(),
+            version: None,
+            required: true,
+            minimum_version: None,
+            constraint: format!("{COMPONENTIZE_PACKAGE} referenced by the .csproj"),
+            satisfied: referenced,
+            remediation: (!referenced)
+                .then(|| format!("Run: dotnet add package {COMPONENTIZE_PACKAGE} --prerelease")),
+        };
+        EnvReport::new("csharp", vec![dotnet, componentize])
+    }
+
+    /// Publish the project with NativeAOT-LLVM into the publish directory
+    /// (`target/<target>/<profile>/dotnet` unless `build.output_dir` is set).
+    ///
+    /// # Side effects
+    /// - Creates build artifacts under target (and bin/obj via MSBuild).
+    /// - Invokes the external dotnet process.
+    fn build(&self, _package: &str, target: &str, release: bool) -> Result<()> {
+        let output_dir = self.publish_dir(target, release);
+        let mut args = vec!["publish".to_string()];
+        let projects = Self::project_files();
+        if let [project] = projects.as_slice() {
+            args.push(project.display().to_string());
+        }
+        args.extend([
+            "-c".to_string(),
+            if release { "Release" } else { "Debug" }.to_string(),
+            "-r".to_string(),
+            WASI_RID.to_string(),
+            "-o".to_string(),
+            output_dir.display().to_string(),
+        ]);
+        let config = self.build_config.as_ref();
+        args.extend(extra_args(config).iter().cloned());
+
+        step!("Executing: dotnet {}", args.join(" "));
+
+        // MSBuild reads VTX_FEATURES as the $(VTX_FEATURES) property.
+        let status = Command::new("dotnet")
+            .args(&args)
+            .envs(build_env(config))
+            .status()
+            .context("Failed to execute dotnet publish")?;
+
+        if !status.success() {
+            anyhow::bail!("dotnet publish failed with non-zero exit code");
+        }
+
+        Ok(())
+    }
+
+    /// Locate the published component: `<package>.wasm`, the assembly named
+    /// after the .csproj, or the only `.wasm` in the publish directory.
+    fn find_output(&self, package: &str, target: &str, release: bool) -> Result<PathBuf> {
+        let output_dir = self.publish_dir(target, release);
+        let mut candidates = vec![output_dir.join(format!("{package}.wasm"))];
+        candidates.extend(Self::project_files().iter().filter_map(|p| {
+            p.file_stem()
+                .map(|stem| output_dir.join(format!("{}.wasm", stem.to_string_lossy())))
+        }));
+        if let Some(path) = candidates.into_iter().find(|p| p.exists()) {
+            return Ok(path);
+        }
+
+        let wasm: Vec<PathBuf> = fs::read_dir(&output_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "wasm"))
+            .collect();
+        match wasm.as_slice() {
+            [only] => Ok(only.clone()),
+            [] => anyhow::bail!(
+                "Expected build artifact not found in: {}",
+                output_dir.display()
+            ),
+            _ => anyhow::bail!(
+                "Multiple .wasm files in {}; set build.artifact to choose one",
+                output_dir.display()
+            ),
+        }
+    }
+
+    /// Record the .NET SDK version used for the build.
+    fn metadata(&self, meta: &mut Map<String, Value>) {
+        if let Some(version) = toolchain::probe_version("dotnet", &["--version"]) {
+            meta.insert("dotnet_version".into(), Value::String(version));
+        }
+    }
+}
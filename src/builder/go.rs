@@ -1,5 +1,8 @@
-﻿use super::Builder;
+﻿use super::{build_env, extra_args, features, Builder, EnvReport, ToolCheck};
+use crate::config::BuildConfig;
+use crate::toolchain;
 use anyhow::{Context, Result};
+use serde_json::{Map, Value};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -8,16 +11,28 @@ use std::process::Command;
 ///
 /// Responsibilities: wrap TinyGo toolchain calls to build Go plugins.
 /// Note: requires the tinygo CLI and usually targets wasi.
-pub struct GoBuilder;
+pub struct GoBuilder {
+    pub build_config: Option<BuildConfig>,
+}
+
+impl GoBuilder {
+    pub fn new(build_config: Option<BuildConfig>) -> Self {
+        Self { build_config }
+    }
+}
 
 impl Builder for GoBuilder {
     /// Check tinygo environment.
-    fn check_env(&self) -> Result<()> {
-        Command::new("tinygo")
-            .arg("version")
-            .output()
-            .context("TinyGo toolchain not found. Please install TinyGo: https://tinygo.org/getting-started/install/")?;
-        Ok(())
+    fn check_env(&self) -> EnvReport {
+        EnvReport::new(
+            "go",
+            vec![ToolCheck::probe(
+                "tinygo",
+                &["version"],
+                true,
+                "Please install TinyGo: https://tinygo.org/getting-started/install/",
+            )],
+        )
     }
 
     /// Run tinygo build.
@@ -47,13 +62,32 @@ impl Builder for GoBuilder {
             args.push("-no-debug");
         }
 
+        // Scalar build: turn off the simd128 LLVM feature TinyGo enables for wasm.
+        if self
+            .build_config
+            .as_ref()
+            .and_then(|c| c.no_simd)
+            .unwrap_or(false)
+        {
+            args.push("-llvm-features=-simd128");
+        }
+
+        // Features become build tags.
+        let config = self.build_config.as_ref();
+        let tags = format!("-tags={}", features(config).join(","));
+        if !features(config).is_empty() {
+            args.push(&tags);
+        }
+        args.extend(extra_args(config).iter().map(String::as_str));
+
         // Assume current working directory is the Go project root.
         args.push(".");
 
-        println!("[VTX] Executing: tinygo {}", args.join(" "));
+        step!("Executing: tinygo {}", args.join(" "));
 
         let status = Command::new("tinygo")
             .args(args)
+            .envs(build_env(config))
             .status()
             .context("Failed to execute tinygo build process")?;
 
@@ -81,4 +115,11 @@ impl Builder for GoBuilder {
             anyhow::bail!("Expected build artifact not found at: {}", path.display())
         }
     }
+
+    /// Record the TinyGo version used for the build.
+    fn metadata(&self, meta: &mut Map<String, Value>) {
+        if let Some(version) = toolchain::probe_version("tinygo", &["version"]) {
+            meta.insert("tinygo_version".into(), Value::String(version));
+        }
+    }
 }
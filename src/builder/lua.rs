@@ -1,4 +1,4 @@
-﻿use super::Builder;
+﻿use super::{build_env, extra_args, Builder, EnvReport, ToolCheck};
 use crate::config::BuildConfig;
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
@@ -19,23 +19,31 @@ impl LuaBuilder {
 }
 
 impl Builder for LuaBuilder {
-    fn check_env(&self) -> Result<()> {
-        Command::new("lua")
-            .arg("-v")
-            .output()
-            .context("Lua interpreter not found.")?;
-        Ok(())
+    fn check_env(&self) -> EnvReport {
+        EnvReport::new(
+            "lua",
+            vec![ToolCheck::probe(
+                "lua",
+                &["-v"],
+                true,
+                "Please install Lua: https://www.lua.org/download.html",
+            )],
+        )
     }
 
     fn build(&self, _package: &str, _target: &str, _release: bool) -> Result<()> {
         // 1. Custom command is required if provided.
-        if let Some(cmd) = self.build_config.as_ref().and_then(|c| c.cmd.as_ref()) {
+        let config = self.build_config.as_ref();
+        if let Some(cmd) = config.and_then(|c| c.cmd.as_ref()) {
             let (shell, arg) = if cfg!(target_os = "windows") {
                 ("cmd", "/C")
             } else {
                 ("sh", "-c")
             };
-            let status = Command::new(shell).args([arg, cmd]).status()?;
+            let status = Command::new(shell)
+                .args([arg, cmd])
+                .envs(build_env(config))
+                .status()?;
             if !status.success() {
                 anyhow::bail!("Custom Lua build command failed");
             }
@@ -44,8 +52,10 @@ impl Builder for LuaBuilder {
 
         // 2. Fallback: check for Makefile.
         if Path::new("Makefile").exists() {
-            println!("[VTX] Makefile detected, running 'make'...");
+            step!("Makefile detected, running 'make'...");
             let status = Command::new("make")
+                .args(extra_args(config))
+                .envs(build_env(config))
                 .status()
                 .context("Failed to run make")?;
             if !status.success() {
@@ -1,7 +1,13 @@
-﻿use crate::config::BuildConfig;
+use crate::config::BuildConfig;
+use crate::{offline, toolchain};
 use anyhow::Result;
-use std::path::PathBuf;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
 
+pub mod c;
+pub mod dotnet;
 pub mod go;
 pub mod lua;
 pub mod php;
@@ -9,6 +15,92 @@ pub mod python;
 pub mod rust;
 pub mod ts;
 
+/// A single toolchain requirement probed by a builder.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct ToolCheck {
+    /// Executable name.
+    pub tool: String,
+
+    /// First line of the tool's version output, if it could be run.
+    pub version: Option<String>,
+
+    /// Whether the default build flow cannot run without this tool.
+    pub required: bool,
+
+    /// Oldest supported release, as checked by `vtx doctor`; `None` when any version works.
+    pub minimum_version: Option<String>,
+
+    /// Human-readable description of what was checked.
+    pub constraint: String,
+
+    /// Whether the constraint is met.
+    pub satisfied: bool,
+
+    /// Installation guidance shown when the constraint is not met.
+    pub remediation: Option<String>,
+}
+
+impl ToolCheck {
+    /// Probe `tool args...` and record whether it is installed, i.e. can be started.
+    pub fn probe(tool: &str, args: &[&str], required: bool, remediation: &str) -> Self {
+        let (satisfied, version) = toolchain::probe_installed(tool, args);
+        Self {
+            tool: tool.to_string(),
+            version,
+            required,
+            minimum_version: toolchain::minimum_version(tool).map(str::to_string),
+            constraint: "installed".to_string(),
+            satisfied,
+            remediation: (!satisfied).then(|| remediation.to_string()),
+        }
+    }
+
+    /// Short status label: ok, missing or optional-missing.
+    pub fn status(&self) -> &'static str {
+        match (self.satisfied, self.required) {
+            (true, _) => "ok",
+            (false, true) => "missing",
+            (false, false) => "optional-missing",
+        }
+    }
+}
+
+/// Structured environment report produced by [`Builder::check_env`].
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct EnvReport {
+    /// Canonical language identifier of the builder.
+    pub language: String,
+    pub checks: Vec<ToolCheck>,
+}
+
+impl EnvReport {
+    pub fn new(language: &str, checks: Vec<ToolCheck>) -> Self {
+        Self {
+            language: language.to_string(),
+            checks,
+        }
+    }
+
+    /// Turn unmet required checks into an error carrying their remediation.
+    pub fn ensure_ready(&self) -> Result<()> {
+        let failures: Vec<String> = self
+            .checks
+            .iter()
+            .filter(|c| c.required && !c.satisfied)
+            .map(|c| match &c.remediation {
+                Some(hint) => format!("{} not {}. {}", c.tool, c.constraint, hint),
+                None => format!("{} not {}.", c.tool, c.constraint),
+            })
+            .collect();
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!(failures.join("\n"))
+        }
+    }
+}
+
 /// Build pipeline interface.
 ///
 /// This trait defines the standard lifecycle for turning source code into
@@ -17,12 +109,13 @@ pub mod ts;
 pub trait Builder {
     /// Stage 1: environment pre-check.
     ///
-    /// Verify the host environment meets build requirements.
+    /// Probe the host environment and report which build requirements are met.
     ///
     /// # Behavior
     /// - Use lightweight commands like `--version` to check toolchain presence.
-    /// - If checks fail, return an error with concrete installation guidance.
-    fn check_env(&self) -> Result<()>;
+    /// - Never fails; unmet requirements carry concrete installation guidance.
+    ///   Callers that must stop the build use [`EnvReport::ensure_ready`].
+    fn check_env(&self) -> EnvReport;
 
     /// Stage 2: build execution.
     ///
@@ -47,6 +140,58 @@ pub trait Builder {
     /// - Success: absolute or execution-relative path.
     /// - Failure: error if file is missing or ambiguous.
     fn find_output(&self, package: &str, target: &str, release: bool) -> Result<PathBuf>;
+
+    /// Stage 4 (optional): artifact post-processing.
+    ///
+    /// Runs on the located Wasm before packaging, for language-specific cleanup.
+    /// The default implementation does nothing.
+    fn post_process(&self, _wasm_path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    /// Contribute language-specific fields to the `.vtx` metadata.
+    ///
+    /// Entries are written under the `builder` key of the metadata JSON.
+    /// The default implementation contributes nothing.
+    fn metadata(&self, _meta: &mut Map<String, Value>) {}
+
+    /// List the members of a multi-package workspace, for `--workspace`: each
+    /// package name with its directory, relative to the project directory.
+    ///
+    /// The default implementation reports that the language has no workspace support.
+    fn workspace_members(&self) -> Result<Vec<(String, PathBuf)>> {
+        anyhow::bail!("Workspaces are not supported for this language")
+    }
+}
+
+/// Environment variable carrying `build.features`, comma-separated, for build scripts.
+pub const FEATURES_ENV: &str = "VTX_FEATURES";
+
+/// `build.features`, empty when unset.
+pub fn features(config: Option<&BuildConfig>) -> &[String] {
+    config
+        .and_then(|c| c.features.as_deref())
+        .unwrap_or_default()
+}
+
+/// `build.extra_args`, empty when unset.
+pub fn extra_args(config: Option<&BuildConfig>) -> &[String] {
+    config
+        .and_then(|c| c.extra_args.as_deref())
+        .unwrap_or_default()
+}
+
+/// Variables to set on toolchain processes: `build.env` plus `VTX_FEATURES`
+/// when features are enabled.
+pub fn build_env(config: Option<&BuildConfig>) -> Vec<(String, String)> {
+    // Offline and vendor settings come first so `build.env` can override them.
+    let mut env = offline::toolchain_env();
+    env.extend(config.and_then(|c| c.env.clone()).unwrap_or_default());
+    let features = features(config);
+    if !features.is_empty() {
+        env.push((FEATURES_ENV.to_string(), features.join(",")));
+    }
+    env
 }
 
 pub fn create_builder(
@@ -54,8 +199,10 @@ pub fn create_builder(
     build_config: Option<BuildConfig>,
 ) -> Result<Box<dyn Builder>> {
     match language.to_lowercase().as_str() {
-        "rust" | "rs" => Ok(Box::new(rust::RustBuilder)),
-        "go" | "tinygo" => Ok(Box::new(go::GoBuilder)),
+        "rust" | "rs" => Ok(Box::new(rust::RustBuilder::new(build_config))),
+        "go" | "tinygo" => Ok(Box::new(go::GoBuilder::new(build_config))),
+        "c" | "cpp" | "c++" | "cxx" => Ok(Box::new(c::CBuilder::new(build_config))),
+        "csharp" | "c#" | "cs" | "dotnet" => Ok(Box::new(dotnet::DotnetBuilder::new(build_config))),
         "ts" | "typescript" | "js" | "node" => Ok(Box::new(ts::TsBuilder::new(build_config))),
         "py" | "python" => Ok(Box::new(python::PythonBuilder::new(build_config))),
         "php" => Ok(Box::new(php::PhpBuilder::new(build_config))),
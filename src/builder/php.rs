@@ -1,4 +1,4 @@
-﻿use super::Builder;
+﻿use super::{build_env, extra_args, Builder, EnvReport, ToolCheck};
 use crate::config::BuildConfig;
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
@@ -18,23 +18,44 @@ impl PhpBuilder {
 }
 
 impl Builder for PhpBuilder {
-    fn check_env(&self) -> Result<()> {
-        Command::new("php")
-            .arg("-v")
-            .output()
-            .context("PHP runtime not found.")?;
-        Ok(())
+    fn check_env(&self) -> EnvReport {
+        let composer = if cfg!(target_os = "windows") {
+            "composer.bat"
+        } else {
+            "composer"
+        };
+        EnvReport::new(
+            "php",
+            vec![
+                ToolCheck::probe(
+                    "php",
+                    &["-v"],
+                    true,
+                    "Please install PHP: https://www.php.net/downloads",
+                ),
+                ToolCheck::probe(
+                    composer,
+                    &["--version"],
+                    false,
+                    "Install Composer to use the default 'composer run build' flow: https://getcomposer.org",
+                ),
+            ],
+        )
     }
 
     fn build(&self, _package: &str, _target: &str, _release: bool) -> Result<()> {
         // 1. Custom command takes priority.
-        if let Some(cmd) = self.build_config.as_ref().and_then(|c| c.cmd.as_ref()) {
+        let config = self.build_config.as_ref();
+        if let Some(cmd) = config.and_then(|c| c.cmd.as_ref()) {
             let (shell, arg) = if cfg!(target_os = "windows") {
                 ("cmd", "/C")
             } else {
                 ("sh", "-c")
             };
-            let status = Command::new(shell).args([arg, cmd]).status()?;
+            let status = Command::new(shell)
+                .args([arg, cmd])
+                .envs(build_env(config))
+                .status()?;
             if !status.success() {
                 anyhow::bail!("Custom PHP build command failed");
             }
@@ -47,11 +68,13 @@ impl Builder for PhpBuilder {
         } else {
             "composer"
         };
-        println!("[VTX] Executing 'composer run build'...");
+        step!("Executing 'composer run build'...");
 
         let status = Command::new(composer)
             .arg("run")
             .arg("build")
+            .args(script_args(extra_args(config)))
+            .envs(build_env(config))
             .status()
             .context(
             "Failed to run 'composer run build'. Please define 'scripts.build' in composer.json",
@@ -91,3 +114,13 @@ impl Builder for PhpBuilder {
         anyhow::bail!("PHP Wasm artifact not found. Please check your build script output.")
     }
 }
+
+/// Extra arguments for a composer script, after the `--` separator.
+fn script_args(extra: &[String]) -> Vec<&str> {
+    match extra {
+        [] => Vec::new(),
+        extra => std::iter::once("--")
+            .chain(extra.iter().map(String::as_str))
+            .collect(),
+    }
+}
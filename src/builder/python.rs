@@ -1,6 +1,8 @@
-﻿use super::Builder;
+﻿use super::{build_env, extra_args, Builder, EnvReport, ToolCheck};
 use crate::config::BuildConfig;
+use crate::{offline, toolchain};
 use anyhow::{Context, Result};
+use serde_json::{Map, Value};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -18,30 +20,39 @@ impl PythonBuilder {
 }
 
 impl Builder for PythonBuilder {
-    fn check_env(&self) -> Result<()> {
-        Command::new("python")
-            .arg("--version")
-            .output()
-            .context("Python not found.")?;
-
-        if self
+    fn check_env(&self) -> EnvReport {
+        // componentize-py is only needed by the default build flow.
+        let needs_componentize = self
             .build_config
             .as_ref()
             .and_then(|c| c.cmd.as_ref())
-            .is_none()
-        {
-            Command::new("componentize-py")
-                .arg("--help")
-                .output()
-                .context("componentize-py not found. Please run: pip install componentize-py")?;
-        }
-        Ok(())
+            .is_none();
+
+        EnvReport::new(
+            "python",
+            vec![
+                ToolCheck::probe(
+                    "python",
+                    &["--version"],
+                    true,
+                    "Please install Python: https://www.python.org/downloads/",
+                ),
+                ToolCheck::probe(
+                    "componentize-py",
+                    &["--help"],
+                    needs_componentize,
+                    &install_hint(),
+                ),
+            ],
+        )
     }
 
     fn build(&self, package: &str, _target: &str, _release: bool) -> Result<()> {
+        let config = self.build_config.as_ref();
+
         // 1. Custom command takes priority.
-        if let Some(cmd) = self.build_config.as_ref().and_then(|c| c.cmd.as_ref()) {
-            println!("[VTX] Executing custom build command: {cmd}");
+        if let Some(cmd) = config.and_then(|c| c.cmd.as_ref()) {
+            step!("Executing custom build command: {cmd}");
             let (shell, arg) = if cfg!(target_os = "windows") {
                 ("cmd", "/C")
             } else {
@@ -50,6 +61,7 @@ impl Builder for PythonBuilder {
 
             let status = Command::new(shell)
                 .args([arg, cmd])
+                .envs(build_env(config))
                 .status()
                 .with_context(|| format!("Failed to execute command: {cmd}"))?;
 
@@ -60,7 +72,7 @@ impl Builder for PythonBuilder {
         }
 
         // 2. Default to componentize-py.
-        println!("[VTX] No 'build.cmd' found, defaulting to 'componentize-py'...");
+        step!("No 'build.cmd' found, defaulting to 'componentize-py'...");
 
         let output_dir = Path::new("dist");
         if !output_dir.exists() {
@@ -75,7 +87,9 @@ impl Builder for PythonBuilder {
             .arg(".")
             .arg("-o")
             .arg(&output_file)
+            .args(extra_args(config))
             .arg(&module_name)
+            .envs(build_env(config))
             .status()
             .context(
                 "Failed to execute componentize-py. Ensure pip install componentize-py is run.",
@@ -124,4 +138,32 @@ impl Builder for PythonBuilder {
 
         anyhow::bail!("Could not find .wasm output. Please specify 'build.output_dir' in vtx.toml")
     }
+
+    /// Record the componentize-py version; its output bundles the CPython interpreter.
+    fn metadata(&self, meta: &mut Map<String, Value>) {
+        if self
+            .build_config
+            .as_ref()
+            .and_then(|c| c.cmd.as_ref())
+            .is_some()
+        {
+            return;
+        }
+        if let Some(version) = toolchain::probe_version("componentize-py", &["--version"]) {
+            meta.insert("componentize_py_version".into(), Value::String(version));
+        }
+        meta.insert("interpreter_bundled".into(), Value::Bool(true));
+    }
+}
+
+/// How to install componentize-py; offline, from the vendored wheels.
+fn install_hint() -> String {
+    match offline::vendored("wheels") {
+        Some(wheels) if offline::is_offline() => format!(
+            "Please run: pip install --no-index --find-links {} componentize-py",
+            wheels.display()
+        ),
+        _ if offline::is_offline() => "Offline: download the componentize-py wheel into <vendor dir>/wheels while online, then pip install --no-index --find-links <vendor dir>/wheels componentize-py".to_string(),
+        _ => "Please run: pip install componentize-py".to_string(),
+    }
 }
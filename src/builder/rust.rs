@@ -1,21 +1,45 @@
-﻿use super::Builder;
+﻿use super::{build_env, extra_args, features, Builder, EnvReport, ToolCheck};
+use crate::config::BuildConfig;
+use crate::{offline, toolchain};
 use anyhow::{Context, Result};
+use serde_json::{Map, Value};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use tracing::warn;
 
 /// Rust builder.
 ///
 /// Responsibilities: wrap Cargo toolchain calls to build Rust plugins.
-pub struct RustBuilder;
+pub struct RustBuilder {
+    pub build_config: Option<BuildConfig>,
+}
+
+impl RustBuilder {
+    pub fn new(build_config: Option<BuildConfig>) -> Self {
+        Self { build_config }
+    }
+}
 
 impl Builder for RustBuilder {
     /// Check cargo toolchain availability.
-    fn check_env(&self) -> Result<()> {
-        Command::new("cargo")
-            .arg("--version")
-            .output()
-            .context("Cargo toolchain not found. Please install Rust and Cargo.")?;
-        Ok(())
+    fn check_env(&self) -> EnvReport {
+        EnvReport::new(
+            "rust",
+            vec![
+                ToolCheck::probe(
+                    "cargo",
+                    &["--version"],
+                    true,
+                    "Please install Rust and Cargo: https://rustup.rs",
+                ),
+                ToolCheck::probe(
+                    "rustup",
+                    &["--version"],
+                    false,
+                    "Install rustup to manage wasm targets: https://rustup.rs",
+                ),
+            ],
+        )
     }
 
     /// Run `cargo build`.
@@ -23,18 +47,46 @@ impl Builder for RustBuilder {
     /// # Complexity
     /// - Depends on the Cargo build process; runtime varies.
     fn build(&self, package: &str, target: &str, release: bool) -> Result<()> {
-        let mut args: Vec<&str> = vec!["build", "--target", target, "-p", package];
+        let config = self.build_config.as_ref();
+        // wasm32-wasip2 emits components directly; the packager then skips the adapter.
+        if toolchain::rustup_has_target(target) == Some(false) {
+            warn!("Rust target {target} is not installed. Run: rustup target add {target}");
+        }
+        let mut args: Vec<String> = ["build", "--target", target, "-p", package]
+            .map(String::from)
+            .to_vec();
         if release {
-            args.push("--release");
+            args.push("--release".to_string());
+        }
+        args.extend(offline::cargo_args().iter().map(|arg| arg.to_string()));
+        let features = features(config);
+        if !features.is_empty() {
+            args.extend(["--features".to_string(), features.join(",")]);
+        }
+        args.extend(extra_args(config).iter().cloned());
+
+        let env = build_env(config);
+        let mut command = Command::new("cargo");
+        command.args(&args).envs(env.iter().cloned());
+
+        // Scalar build: disable the simd128 target feature for every crate.
+        if config.and_then(|c| c.no_simd).unwrap_or(false) {
+            let (key, value) = scalar_rustflags(&env, target);
+            step!("Scalar build: {key}=\"{}\"", value.replace('\x1f', " "));
+            command.env(key, value);
         }
 
         // Run cargo build.
-        let status = Command::new("cargo")
-            .args(args)
+        let status = command
             .status()
             .context("Failed to spawn cargo build process")?;
 
         if !status.success() {
+            if offline::is_offline() {
+                anyhow::bail!(
+                    "cargo build failed in offline mode; run `cargo fetch` (or `cargo vendor`) while online so every dependency is cached locally"
+                );
+            }
             anyhow::bail!("cargo build failed with non-zero exit code");
         }
 
@@ -99,4 +151,135 @@ impl Builder for RustBuilder {
             dir.display()
         );
     }
+
+    /// Record the rustc version used for the build.
+    fn metadata(&self, meta: &mut Map<String, Value>) {
+        if let Some(version) = toolchain::probe_version("rustc", &["--version"]) {
+            meta.insert("rustc_version".into(), Value::String(version));
+        }
+    }
+
+    /// Workspace members as reported by `cargo metadata`.
+    fn workspace_members(&self) -> Result<Vec<(String, PathBuf)>> {
+        let output = Command::new("cargo")
+            .args(["metadata", "--no-deps", "--format-version", "1"])
+            .output()
+            .context("Failed to spawn cargo metadata")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "cargo metadata failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let metadata: Value =
+            serde_json::from_slice(&output.stdout).context("Malformed cargo metadata output")?;
+        let members: Vec<&str> = metadata["workspace_members"]
+            .as_array()
+            .map(|ids| ids.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        Ok(metadata["packages"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|p| p["id"].as_str().is_some_and(|id| members.contains(&id)))
+            .filter_map(|p| {
+                let dir = p["manifest_path"]
+                    .as_str()
+                    .and_then(|path| Path::new(path).parent())
+                    .unwrap_or(Path::new("."));
+                Some((p["name"].as_str()?.to_string(), dir.to_path_buf()))
+            })
+            .collect())
+    }
+}
+
+/// The rustflags variable and value for a scalar build: the flags Cargo
+/// would otherwise use, followed by `-C target-feature=-simd128`.
+///
+/// Setting `RUSTFLAGS` replaces rustflags from `.cargo/config.toml`, so
+/// unless the environment already overrides them, the configured flags are
+/// carried over into `CARGO_ENCODED_RUSTFLAGS`.
+fn scalar_rustflags(env: &[(String, String)], target: &str) -> (&'static str, String) {
+    const SCALAR: [&str; 2] = ["-C", "target-feature=-simd128"];
+    let lookup = |key: &str| {
+        env.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.clone())
+            .or_else(|| std::env::var(key).ok())
+    };
+
+    // Cargo reads CARGO_ENCODED_RUSTFLAGS first, then RUSTFLAGS, then its config.
+    if let Some(mut encoded) = lookup("CARGO_ENCODED_RUSTFLAGS") {
+        for flag in SCALAR {
+            if !encoded.is_empty() {
+                encoded.push('\x1f');
+            }
+            encoded.push_str(flag);
+        }
+        return ("CARGO_ENCODED_RUSTFLAGS", encoded);
+    }
+    if let Some(mut rustflags) = lookup("RUSTFLAGS") {
+        if !rustflags.is_empty() {
+            rustflags.push(' ');
+        }
+        rustflags.push_str(&SCALAR.join(" "));
+        return ("RUSTFLAGS", rustflags);
+    }
+    let mut flags = config_rustflags(target);
+    flags.extend(SCALAR.map(String::from));
+    ("CARGO_ENCODED_RUSTFLAGS", flags.join("\x1f"))
+}
+
+/// Rustflags from Cargo configuration files for `target`.
+///
+/// Follows Cargo's lookup: `.cargo/config.toml` (or `.cargo/config`) in the
+/// current directory and its ancestors, then in Cargo's home. Flags of
+/// `target.<triple>.rustflags` are joined across files; `build.rustflags`
+/// applies only when no file sets them.
+fn config_rustflags(target: &str) -> Vec<String> {
+    let mut dirs: Vec<PathBuf> = std::env::current_dir()
+        .map(|cwd| cwd.ancestors().map(|dir| dir.join(".cargo")).collect())
+        .unwrap_or_default();
+    if let Some(home) = std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")))
+    {
+        if !dirs.contains(&home) {
+            dirs.push(home);
+        }
+    }
+
+    let configs: Vec<toml::Value> = dirs
+        .iter()
+        .filter_map(|dir| {
+            ["config.toml", "config"]
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file())
+        })
+        .filter_map(|path| std::fs::read_to_string(path).ok()?.parse().ok())
+        .collect();
+    let collect = |select: &dyn Fn(&toml::Value) -> Option<&toml::Value>| -> Vec<String> {
+        configs
+            .iter()
+            .filter_map(select)
+            .flat_map(|flags| match flags {
+                toml::Value::String(flags) => {
+                    flags.split_whitespace().map(str::to_string).collect()
+                }
+                toml::Value::Array(flags) => flags
+                    .iter()
+                    .filter_map(|flag| flag.as_str().map(str::to_string))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    };
+
+    let target_flags = collect(&|config| config.get("target")?.get(target)?.get("rustflags"));
+    if !target_flags.is_empty() {
+        return target_flags;
+    }
+    collect(&|config| config.get("build")?.get("rustflags"))
 }
@@ -1,6 +1,8 @@
-﻿use super::Builder;
+﻿use super::{build_env, extra_args, Builder, EnvReport, ToolCheck};
 use crate::config::BuildConfig;
+use crate::{offline, toolchain};
 use anyhow::{Context, Result};
+use serde_json::{Map, Value};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -16,37 +18,109 @@ impl TsBuilder {
     pub fn new(build_config: Option<BuildConfig>) -> Self {
         Self { build_config }
     }
+
+    /// `--offline` for npm in offline mode, so it only uses the local cache.
+    fn offline_args() -> &'static [&'static str] {
+        if offline::is_offline() {
+            &["--offline"]
+        } else {
+            &[]
+        }
+    }
+
+    /// Name and directory of each npm workspace in `package.json`.
+    ///
+    /// Supports exact directories and `dir/*` patterns.
+    fn workspaces() -> Result<Vec<(String, PathBuf)>> {
+        let manifest = match std::fs::read_to_string("package.json") {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).context("Failed to read package.json"),
+        };
+        let manifest: Value = serde_json::from_str(&manifest).context("Malformed package.json")?;
+        let patterns = match &manifest["workspaces"] {
+            Value::Array(patterns) => patterns.clone(),
+            other => other["packages"].as_array().cloned().unwrap_or_default(),
+        };
+
+        let mut dirs = Vec::new();
+        for pattern in patterns.iter().filter_map(Value::as_str) {
+            match pattern.strip_suffix("/*") {
+                Some(parent) => {
+                    let Ok(entries) = std::fs::read_dir(parent) else {
+                        continue;
+                    };
+                    let mut found: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+                    found.sort();
+                    dirs.extend(found);
+                }
+                None => dirs.push(PathBuf::from(pattern)),
+            }
+        }
+
+        let mut members = Vec::new();
+        for dir in dirs {
+            let Ok(content) = std::fs::read_to_string(dir.join("package.json")) else {
+                continue;
+            };
+            let member: Value = serde_json::from_str(&content)
+                .with_context(|| format!("Malformed {}/package.json", dir.display()))?;
+            if let Some(name) = member["name"].as_str() {
+                members.push((name.to_string(), dir));
+            }
+        }
+        Ok(members)
+    }
+
+    /// Directory of the workspace named `package`, if there is one.
+    fn workspace_dir(package: &str) -> Option<PathBuf> {
+        Self::workspaces()
+            .ok()?
+            .into_iter()
+            .find(|(name, _)| name == package)
+            .map(|(_, dir)| dir)
+    }
 }
 
 impl Builder for TsBuilder {
-    fn check_env(&self) -> Result<()> {
+    fn check_env(&self) -> EnvReport {
         let npm_cmd = if cfg!(target_os = "windows") {
             "npm.cmd"
         } else {
             "npm"
         };
-        Command::new(npm_cmd)
-            .arg("-v")
-            .output()
-            .context("npm not found")?;
-        Ok(())
+        EnvReport::new(
+            "ts",
+            vec![ToolCheck::probe(
+                npm_cmd,
+                &["-v"],
+                true,
+                "npm ships with Node.js: https://nodejs.org",
+            )],
+        )
     }
 
-    fn build(&self, _package: &str, _target: &str, _release: bool) -> Result<()> {
+    fn build(&self, package: &str, _target: &str, _release: bool) -> Result<()> {
         let npm_cmd = if cfg!(target_os = "windows") {
             "npm.cmd"
         } else {
             "npm"
         };
 
+        let config = self.build_config.as_ref();
+        let env = build_env(config);
+
         // 1. Run user-provided custom command first.
-        if let Some(cmd) = self.build_config.as_ref().and_then(|c| c.cmd.as_ref()) {
+        if let Some(cmd) = config.and_then(|c| c.cmd.as_ref()) {
             let (shell, arg) = if cfg!(target_os = "windows") {
                 ("cmd", "/C")
             } else {
                 ("sh", "-c")
             };
-            let status = Command::new(shell).args([arg, cmd]).status()?;
+            let status = Command::new(shell)
+                .args([arg, cmd])
+                .envs(env.iter().cloned())
+                .status()?;
             if !status.success() {
                 anyhow::bail!("Custom JS/TS build command failed");
             }
@@ -55,16 +129,35 @@ impl Builder for TsBuilder {
 
         // 2. Ensure dependencies are present (may trigger network IO).
         if Path::new("package.json").exists() && !Path::new("node_modules").exists() {
-            println!("[VTX] node_modules not found, running npm install...");
-            let status = Command::new(npm_cmd).arg("install").status()?;
+            step!("node_modules not found, running npm install...");
+            let status = Command::new(npm_cmd)
+                .arg("install")
+                .args(Self::offline_args())
+                .envs(env.iter().cloned())
+                .status()?;
             if !status.success() {
+                if offline::is_offline() {
+                    anyhow::bail!(
+                        "npm install failed in offline mode; run `npm install` while online, or point --vendor-dir at a directory whose npm/ holds a populated npm cache"
+                    );
+                }
                 anyhow::bail!("npm install failed");
             }
         }
 
-        // 3. Run standard npm build script.
-        println!("[VTX] Executing: {npm_cmd} run build");
-        let status = Command::new(npm_cmd).arg("run").arg("build").status()?;
+        // 3. Run standard npm build script, scoped to the workspace when `package` is one.
+        let mut args = vec!["run", "build"];
+        args.extend(Self::offline_args());
+        if Self::workspace_dir(package).is_some() {
+            args.extend(["--workspace", package]);
+        }
+        let extra = extra_args(config);
+        if !extra.is_empty() {
+            args.push("--");
+            args.extend(extra.iter().map(String::as_str));
+        }
+        step!("Executing: {npm_cmd} {}", args.join(" "));
+        let status = Command::new(npm_cmd).args(&args).envs(env).status()?;
 
         if !status.success() {
             anyhow::bail!("npm run build failed");
@@ -95,7 +188,8 @@ impl Builder for TsBuilder {
             }
         }
 
-        // Strategy 2: heuristic search in standard directories.
+        // Strategy 2: heuristic search in standard directories of the workspace or project.
+        let root = Self::workspace_dir(package).unwrap_or_else(|| PathBuf::from("."));
         let search_dirs = vec!["build", "dist", "target", "."];
         let candidates = vec![
             format!("{package}.wasm"),
@@ -104,7 +198,7 @@ impl Builder for TsBuilder {
         ];
 
         for dir in search_dirs {
-            let dir_path = Path::new(dir);
+            let dir_path = root.join(dir);
             if !dir_path.exists() {
                 continue;
             }
@@ -120,4 +214,16 @@ impl Builder for TsBuilder {
             "Wasm output not found. Please set 'build.output_dir' in vtx.toml or check npm build script."
         )
     }
+
+    /// Record the Node.js version used for the build.
+    fn metadata(&self, meta: &mut Map<String, Value>) {
+        if let Some(version) = toolchain::probe_version("node", &["--version"]) {
+            meta.insert("node_version".into(), Value::String(version));
+        }
+    }
+
+    /// npm workspaces declared in `package.json`.
+    fn workspace_members(&self) -> Result<Vec<(String, PathBuf)>> {
+        Self::workspaces()
+    }
 }
@@ -1,101 +1,613 @@
-﻿use anyhow::{Context, Result};
-use colored::*;
-use std::path::Path;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
 use toml::Table;
+use tracing::{info, warn};
 
-/// Check SDK dependency compatibility for a Rust project.
+use crate::config::{self, SdkConfig};
+use crate::packager::BUNDLED_ADAPTER_VERSION;
+use crate::toolchain;
+
+/// Go module path of the VTX SDK.
+pub const GO_SDK_MODULE: &str = "github.com/vtxdeo/vtx-sdk-go";
+
+/// Check SDK dependency compatibility for a project.
 ///
 /// Responsibilities:
-/// Read Cargo.toml in the project root, parse the `vtx-sdk` dependency version,
-/// and compare it against the SDK metadata version bundled with the CLI.
+/// Read the language's project manifest, resolve the SDK dependency, and
+/// compare it against the SDK metadata version bundled with the CLI.
+///
+/// Sources per language:
+/// - Rust: `vtx-sdk` in Cargo.toml (following `workspace = true` to the
+///   workspace root), locked in Cargo.lock.
+/// - TypeScript: `@vtx/sdk` in package.json, locked in package-lock.json.
+/// - Python: `vtx-sdk` in pyproject.toml (PEP 621 or Poetry) or
+///   requirements.txt, locked in poetry.lock or uv.lock.
+/// - Go: the SDK module required in go.mod.
 ///
 /// Behavior:
-/// - If Cargo.toml is missing, skip silently (not a Rust project).
+/// - If the manifest is missing, skip silently (not a project of that language).
+/// - A locked version must be semver-compatible with the bundled version
+///   (same major, or same minor for 0.x).
+/// - Otherwise the declared requirement (`0.1`, `^0.1.2`, `~0.1`, `>=0.1, <0.3`;
+///   npm ranges and PEP 440 specifiers for TypeScript and Python) must accept
+///   the bundled version. Requirements that name no version (git, path, tags)
+///   are skipped with a warning.
 /// - If versions are incompatible:
 ///   - By default, return an error and stop the build.
 ///   - If `force` is true, print a warning only.
-pub fn check_rust_sdk_version(project_dir: &Path, force: bool) -> Result<()> {
-    let cargo_toml_path = project_dir.join("Cargo.toml");
-    if !cargo_toml_path.exists() {
+pub fn check_project_sdk_version(project_dir: &Path, language: &str, force: bool) -> Result<()> {
+    let cli_target_ver = vtx_sdk::VERSION; // From SDK constant.
+    let dependency = match resolve_sdk_dependency(project_dir, language)? {
+        SdkLookup::NotApplicable => return Ok(()),
+        SdkLookup::Missing { package, manifest } => {
+            // Projects without the SDK might be raw Wasm or indirect deps.
+            warn!(
+                "Warning: '{}' dependency not found in {}.",
+                package, manifest
+            );
+            return Ok(());
+        }
+        SdkLookup::Found(dependency) => dependency,
+    };
+    let SdkDependency {
+        package,
+        manifest,
+        requirement,
+        accepts,
+        locked,
+    } = dependency;
+
+    if let Some((locked, lock_file)) = locked {
+        if is_same_release_line(&locked, cli_target_ver) {
+            info!(
+                "SDK compatibility check passed (v{} locked, requirement {})",
+                locked, requirement
+            );
+        } else {
+            fail_or_warn(
+                format!(
+                    "SDK Version Mismatch: Plugin uses {package} {locked} (locked in {lock_file}), but this CLI is optimized for v{cli_target_ver}."
+                ),
+                &format!("Update {package} in {manifest} and refresh {lock_file}, or use --force to bypass."),
+                force,
+            )?;
+        }
+        return Ok(());
+    }
+
+    let Some(accepts) = accepts else {
+        warn!(
+            "Cannot interpret {} requirement '{}' in {}; skipping SDK check.",
+            package, requirement, manifest
+        );
         return Ok(());
+    };
+    let version = semver::Version::parse(cli_target_ver).context("Invalid bundled SDK version")?;
+    if accepts.iter().any(|req| req.matches(&version)) {
+        info!(
+            "SDK compatibility check passed ({} accepts v{})",
+            requirement, cli_target_ver
+        );
+    } else {
+        fail_or_warn(
+            format!(
+                "SDK Version Mismatch: Plugin uses {package} {requirement}, but this CLI is optimized for v{cli_target_ver}."
+            ),
+            &format!("Update {package} in {manifest} or use --force to bypass."),
+            force,
+        )?;
     }
+    Ok(())
+}
 
-    let content = std::fs::read_to_string(&cargo_toml_path).context("Failed to read Cargo.toml")?;
-    let table: Table = toml::from_str(&content)?;
+/// Check the `[sdk]` requirements declared in vtx.toml.
+///
+/// Behavior:
+/// - `sdk.version` is compared against the SDK version bundled with the CLI,
+///   regardless of project language.
+/// - `sdk.adapter` must match the bundled WASI adapter version.
+/// - Mismatches fail unless `force` is true, in which case only a warning is printed.
+pub fn check_sdk_config(sdk: Option<&SdkConfig>, force: bool) -> Result<()> {
+    let Some(sdk) = sdk else {
+        return Ok(());
+    };
 
-    // Get vtx-sdk version from dependencies, then dev-dependencies.
-    let version = table
-        .get("dependencies")
-        .and_then(|d| d.get("vtx-sdk"))
-        .or_else(|| table.get("dev-dependencies").and_then(|d| d.get("vtx-sdk")));
-
-    match version {
-        Some(v) => {
-            let user_ver = v
-                .as_str()
-                .or_else(|| v.get("version").and_then(|value| value.as_str()))
-                .unwrap_or("unknown");
-            let cli_target_ver = vtx_sdk::VERSION; // From SDK constant.
-
-            // Check version compatibility.
-            if !is_compatible(user_ver, cli_target_ver) {
-                let msg = format!(
-                    "SDK Version Mismatch: Plugin uses vtx-sdk {user_ver}, but this CLI is optimized for v{cli_target_ver}."
-                );
-
-                if force {
-                    println!("{} {} (Force build enabled)", "[WARN]".yellow(), msg);
-                } else {
-                    anyhow::bail!(
-                        "{msg}\nHint: Update vtx-sdk in Cargo.toml or use --force to bypass."
-                    );
-                }
-            } else {
-                println!(
-                    "{} SDK compatibility check passed (v{})",
-                    "[INFO]".cyan(),
-                    user_ver
-                );
-            }
+    if let Some(required) = sdk.version.as_deref() {
+        let cli_target_ver = vtx_sdk::VERSION;
+        if is_compatible(required, cli_target_ver) {
+            info!("sdk.version requirement satisfied (v{})", cli_target_ver);
+        } else {
+            fail_or_warn(
+                format!(
+                    "SDK Version Mismatch: vtx.toml requires vtx-sdk {required}, but this CLI is optimized for v{cli_target_ver}."
+                ),
+                "Update sdk.version in vtx.toml or use --force to bypass.",
+                force,
+            )?;
         }
-        None => {
-            // Rust project without vtx-sdk might be raw Wasm or indirect deps.
-            println!(
-                "{} Warning: 'vtx-sdk' dependency not found in Cargo.toml.",
-                "[WARN]".yellow()
-            );
+    }
+
+    if let Some(pinned) = sdk.adapter.as_deref() {
+        if pinned.trim_start_matches('=') != BUNDLED_ADAPTER_VERSION {
+            fail_or_warn(
+                format!(
+                    "Adapter Version Mismatch: vtx.toml pins WASI adapter {pinned}, but this CLI bundles v{BUNDLED_ADAPTER_VERSION}."
+                ),
+                "Update sdk.adapter in vtx.toml or use --force to bypass.",
+                force,
+            )?;
         }
     }
+
     Ok(())
 }
 
-/// Read the declared vtx-sdk version from Cargo.toml in a Rust project.
-pub fn read_rust_sdk_version(project_dir: &Path) -> Option<String> {
+/// Read the SDK version of a project: the locked version, or else the
+/// declared requirement without its operator.
+pub fn read_sdk_version(project_dir: &Path, language: &str) -> Option<String> {
+    let SdkLookup::Found(dependency) = resolve_sdk_dependency(project_dir, language).ok()? else {
+        return None;
+    };
+    Some(match dependency.locked {
+        Some((locked, _)) => locked,
+        None => dependency
+            .requirement
+            .trim_start_matches(['^', '~', '=', '>', '<', ' ', 'v'])
+            .to_string(),
+    })
+}
+
+/// Outcome of looking up a project's SDK dependency.
+enum SdkLookup {
+    /// The language has no SDK manifest, or the manifest does not exist.
+    NotApplicable,
+    /// The manifest exists but does not declare the SDK.
+    Missing {
+        package: &'static str,
+        manifest: &'static str,
+    },
+    Found(SdkDependency),
+}
+
+/// The SDK dependency of a project.
+struct SdkDependency {
+    /// Package or module name, e.g. `vtx-sdk` or `@vtx/sdk`.
+    package: &'static str,
+    /// File the requirement was read from.
+    manifest: &'static str,
+    /// Requirement as written in the manifest.
+    requirement: String,
+    /// The requirement as semver alternatives; `None` if it names no version range.
+    accepts: Option<Vec<semver::VersionReq>>,
+    /// Exact version and the file that pins it, if the project has been resolved.
+    locked: Option<(String, &'static str)>,
+}
+
+fn resolve_sdk_dependency(project_dir: &Path, language: &str) -> Result<SdkLookup> {
+    match toolchain::canonical_language(language).as_str() {
+        "rust" => resolve_rust_sdk(project_dir),
+        "ts" => resolve_npm_sdk(project_dir),
+        "python" => resolve_python_sdk(project_dir),
+        "go" => resolve_go_sdk(project_dir),
+        _ => Ok(SdkLookup::NotApplicable),
+    }
+}
+
+/// Resolve vtx-sdk from `[dependencies]` or `[dev-dependencies]` of
+/// `project_dir/Cargo.toml`, and its locked version from the Cargo.lock next
+/// to it or at the workspace root.
+fn resolve_rust_sdk(project_dir: &Path) -> Result<SdkLookup> {
     let cargo_toml_path = project_dir.join("Cargo.toml");
     if !cargo_toml_path.exists() {
-        return None;
+        return Ok(SdkLookup::NotApplicable);
+    }
+    let table = read_cargo_toml(&cargo_toml_path)?;
+    let Some(dependency) = sdk_dependency_entry(&table) else {
+        return Ok(SdkLookup::Missing {
+            package: "vtx-sdk",
+            manifest: "Cargo.toml",
+        });
+    };
+    let workspace_root = find_workspace_root(project_dir);
+
+    let inherited = dependency
+        .get("workspace")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    let requirement = if inherited {
+        let root = workspace_root.as_deref().with_context(|| {
+            "vtx-sdk uses workspace = true, but no [workspace] Cargo.toml was found above the project"
+        })?;
+        let root_table = read_cargo_toml(&root.join("Cargo.toml"))?;
+        root_table
+            .get("workspace")
+            .and_then(|w| w.get("dependencies"))
+            .and_then(|d| d.get("vtx-sdk"))
+            .and_then(dependency_version)
+            .with_context(|| {
+                format!(
+                    "vtx-sdk uses workspace = true, but {} has no [workspace.dependencies] vtx-sdk version",
+                    root.join("Cargo.toml").display()
+                )
+            })?
+    } else {
+        dependency_version(dependency).unwrap_or_else(|| "unknown".to_string())
+    };
+
+    let lock_dir = workspace_root.unwrap_or_else(|| project_dir.to_path_buf());
+    let locked = read_locked_version(&lock_dir.join("Cargo.lock"), "vtx-sdk");
+    Ok(SdkLookup::Found(SdkDependency {
+        package: "vtx-sdk",
+        manifest: "Cargo.toml",
+        accepts: semver::VersionReq::parse(&requirement)
+            .ok()
+            .map(|req| vec![req]),
+        requirement,
+        locked: locked.map(|v| (v, "Cargo.lock")),
+    }))
+}
+
+/// Resolve `@vtx/sdk` from package.json and its version from package-lock.json.
+fn resolve_npm_sdk(project_dir: &Path) -> Result<SdkLookup> {
+    const PACKAGE: &str = "@vtx/sdk";
+    let path = project_dir.join("package.json");
+    if !path.exists() {
+        return Ok(SdkLookup::NotApplicable);
     }
+    let content = config::read_text_file(&path).context("Failed to read package.json")?;
+    let manifest: serde_json::Value =
+        serde_json::from_str(&content).context("Failed to parse package.json")?;
+    let Some(requirement) = ["dependencies", "devDependencies", "peerDependencies"]
+        .iter()
+        .find_map(|section| manifest.get(section)?.get(PACKAGE)?.as_str())
+    else {
+        return Ok(SdkLookup::Missing {
+            package: PACKAGE,
+            manifest: "package.json",
+        });
+    };
+
+    let locked = std::fs::read_to_string(project_dir.join("package-lock.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|lock| {
+            let entry = lock
+                .get("packages")
+                .and_then(|p| p.get(format!("node_modules/{PACKAGE}")))
+                .or_else(|| lock.get("dependencies").and_then(|d| d.get(PACKAGE)))?;
+            Some(entry.get("version")?.as_str()?.to_string())
+        });
+    Ok(SdkLookup::Found(SdkDependency {
+        package: PACKAGE,
+        manifest: "package.json",
+        requirement: requirement.to_string(),
+        accepts: npm_requirement(requirement),
+        locked: locked.map(|v| (v, "package-lock.json")),
+    }))
+}
 
-    let content = std::fs::read_to_string(&cargo_toml_path).ok()?;
-    let table: Table = toml::from_str(&content).ok()?;
+/// Resolve `vtx-sdk` from pyproject.toml (`[project] dependencies`, then
+/// `[tool.poetry.dependencies]`) or requirements.txt, and its version from
+/// poetry.lock or uv.lock.
+fn resolve_python_sdk(project_dir: &Path) -> Result<SdkLookup> {
+    const PACKAGE: &str = "vtx-sdk";
+    let pyproject = project_dir.join("pyproject.toml");
+    let requirements = project_dir.join("requirements.txt");
+    let mut manifest = None;
+    let mut found = None;
+
+    if pyproject.exists() {
+        manifest = Some("pyproject.toml");
+        let content =
+            config::read_text_file(&pyproject).context("Failed to read pyproject.toml")?;
+        let table: Table = config::parse_toml(&content, "pyproject.toml")?;
+        found = table
+            .get("project")
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|d| d.as_str())
+            .find_map(|d| pep508_specifier(d, PACKAGE))
+            .map(|spec| (spec.clone(), pep440_requirement(&spec)))
+            .or_else(|| {
+                let poetry = table
+                    .get("tool")?
+                    .get("poetry")?
+                    .get("dependencies")?
+                    .as_table()?;
+                let entry = poetry
+                    .iter()
+                    .find(|(name, _)| normalize_python_name(name) == PACKAGE)?
+                    .1;
+                let spec = dependency_version(entry)?;
+                let accepts = semver::VersionReq::parse(&spec).ok().map(|r| vec![r]);
+                Some((spec, accepts))
+            });
+    }
+    if found.is_none() && requirements.exists() {
+        manifest = Some("requirements.txt");
+        let content =
+            std::fs::read_to_string(&requirements).context("Failed to read requirements.txt")?;
+        found = content
+            .lines()
+            .find_map(|line| pep508_specifier(line, PACKAGE))
+            .map(|spec| (spec.clone(), pep440_requirement(&spec)));
+    }
 
-    let version = table
+    let Some(manifest) = manifest else {
+        return Ok(SdkLookup::NotApplicable);
+    };
+    let Some((requirement, accepts)) = found else {
+        return Ok(SdkLookup::Missing {
+            package: PACKAGE,
+            manifest,
+        });
+    };
+    let locked = ["poetry.lock", "uv.lock"]
+        .into_iter()
+        .find_map(|lock_file| {
+            read_locked_version(&project_dir.join(lock_file), PACKAGE).map(|v| (v, lock_file))
+        });
+    Ok(SdkLookup::Found(SdkDependency {
+        package: PACKAGE,
+        manifest,
+        requirement: if requirement.is_empty() {
+            "*".to_string()
+        } else {
+            requirement
+        },
+        accepts,
+        locked,
+    }))
+}
+
+/// Resolve the SDK module from the `require` directives of go.mod.
+///
+/// Go selects the required version itself, so it is treated as locked.
+fn resolve_go_sdk(project_dir: &Path) -> Result<SdkLookup> {
+    let path = project_dir.join("go.mod");
+    if !path.exists() {
+        return Ok(SdkLookup::NotApplicable);
+    }
+    let content = config::read_text_file(&path).context("Failed to read go.mod")?;
+    let mut in_block = false;
+    let mut required = None;
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        let spec = if in_block {
+            if line == ")" {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if line == "require (" {
+            in_block = true;
+            continue;
+        } else if let Some(rest) = line.strip_prefix("require ") {
+            rest.trim()
+        } else {
+            continue;
+        };
+        let mut parts = spec.split_whitespace();
+        if parts.next() == Some(GO_SDK_MODULE) {
+            required = parts.next().map(str::to_string);
+            break;
+        }
+    }
+
+    let Some(requirement) = required else {
+        return Ok(SdkLookup::Missing {
+            package: GO_SDK_MODULE,
+            manifest: "go.mod",
+        });
+    };
+    let version = requirement
+        .trim_start_matches('v')
+        .trim_end_matches("+incompatible")
+        .to_string();
+    Ok(SdkLookup::Found(SdkDependency {
+        package: GO_SDK_MODULE,
+        manifest: "go.mod",
+        accepts: semver::VersionReq::parse(&version).ok().map(|r| vec![r]),
+        requirement,
+        locked: Some((version, "go.mod")),
+    }))
+}
+
+fn read_cargo_toml(path: &Path) -> Result<Table> {
+    let content = config::read_text_file(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    config::parse_toml(&content, "Cargo.toml")
+}
+
+/// vtx-sdk from dependencies, then dev-dependencies.
+fn sdk_dependency_entry(table: &Table) -> Option<&toml::Value> {
+    table
         .get("dependencies")
         .and_then(|d| d.get("vtx-sdk"))
-        .or_else(|| table.get("dev-dependencies").and_then(|d| d.get("vtx-sdk")))?;
+        .or_else(|| table.get("dev-dependencies").and_then(|d| d.get("vtx-sdk")))
+}
 
-    let user_ver = version
+/// Version requirement of a dependency written as `"0.1"` or `{ version = "0.1" }`.
+pub fn dependency_version(dependency: &toml::Value) -> Option<String> {
+    dependency
         .as_str()
-        .or_else(|| version.get("version").and_then(|value| value.as_str()))?;
+        .or_else(|| dependency.get("version").and_then(|value| value.as_str()))
+        .map(str::to_string)
+}
+
+/// The nearest directory at or above `project_dir` whose Cargo.toml has a `[workspace]` table.
+pub fn find_workspace_root(project_dir: &Path) -> Option<PathBuf> {
+    let start = project_dir
+        .canonicalize()
+        .unwrap_or_else(|_| project_dir.to_path_buf());
+    start
+        .ancestors()
+        .find(|dir| {
+            read_cargo_toml(&dir.join("Cargo.toml"))
+                .is_ok_and(|table| table.contains_key("workspace"))
+        })
+        .map(Path::to_path_buf)
+}
+
+/// Version of `package` in a Cargo.lock; the highest one if several are locked.
+fn read_locked_version(lock_path: &Path, package: &str) -> Option<String> {
+    let content = std::fs::read_to_string(lock_path).ok()?;
+    let lock: Table = toml::from_str(&content).ok()?;
+    lock.get("package")?
+        .as_array()?
+        .iter()
+        .filter(|p| p.get("name").and_then(|n| n.as_str()) == Some(package))
+        .filter_map(|p| p.get("version").and_then(|v| v.as_str()))
+        .filter_map(|v| semver::Version::parse(v).ok())
+        .max()
+        .map(|v| v.to_string())
+}
 
-    Some(user_ver.trim_start_matches(['^', '~', '=']).to_string())
+/// Lowercase a Python distribution name and fold `_` and `.` to `-` (PEP 503).
+pub fn normalize_python_name(name: &str) -> String {
+    name.trim().to_lowercase().replace(['_', '.'], "-")
 }
 
-/// Simple version compatibility check.
+/// Version specifier of `package` in a PEP 508 requirement line
+/// (`vtx-sdk[extra]>=0.1,<0.2 ; python_version>'3.9'`), or `None` for other packages.
+pub fn pep508_specifier(line: &str, package: &str) -> Option<String> {
+    let line = line.split('#').next()?.trim();
+    let name_end = line
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(line.len());
+    if name_end == 0 || normalize_python_name(&line[..name_end]) != package {
+        return None;
+    }
+    let mut rest = line[name_end..].trim_start();
+    if rest.starts_with('[') {
+        rest = rest.split_once(']').map_or("", |(_, r)| r);
+    }
+    let spec = rest.split(';').next().unwrap_or("");
+    Some(
+        spec.trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .trim()
+            .to_string(),
+    )
+}
+
+/// Translate a PEP 440 specifier set into a semver requirement.
 ///
-/// Logic:
-/// Remove semver prefixes (^, ~, =) and require an exact match.
-fn is_compatible(user: &str, system: &str) -> bool {
-    let clean_user = user.trim_start_matches(['^', '~', '=']);
-    clean_user == system
+/// `==` becomes `=` (`==0.1.*` a wildcard), `~=0.1.2` becomes `>=0.1.2, <0.2`,
+/// and `!=` exclusions are ignored. Direct references (`@ url`) yield `None`.
+fn pep440_requirement(spec: &str) -> Option<Vec<semver::VersionReq>> {
+    if spec.is_empty() {
+        return Some(vec![semver::VersionReq::STAR]);
+    }
+    let mut clauses = Vec::new();
+    for clause in spec.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        if clause.starts_with("!=") {
+            continue;
+        }
+        if let Some(version) = clause
+            .strip_prefix("===")
+            .or_else(|| clause.strip_prefix("=="))
+        {
+            let version = version.trim();
+            if version.ends_with(".*") {
+                clauses.push(version.to_string());
+            } else {
+                clauses.push(format!("={version}"));
+            }
+        } else if let Some(version) = clause.strip_prefix("~=") {
+            let version = version.trim();
+            let parts: Vec<u64> = version
+                .split('.')
+                .map(|p| p.parse().ok())
+                .collect::<Option<_>>()?;
+            if parts.len() < 2 {
+                return None;
+            }
+            let mut upper = parts[..parts.len() - 1].to_vec();
+            *upper.last_mut()? += 1;
+            let upper: Vec<String> = upper.iter().map(u64::to_string).collect();
+            clauses.push(format!(">={version}"));
+            clauses.push(format!("<{}", upper.join(".")));
+        } else {
+            clauses.push(clause.to_string());
+        }
+    }
+    if clauses.is_empty() {
+        return Some(vec![semver::VersionReq::STAR]);
+    }
+    semver::VersionReq::parse(&clauses.join(", "))
+        .ok()
+        .map(|r| vec![r])
+}
+
+/// Translate an npm range (`^0.1.2`, `0.1.x`, `>=0.1 <0.3`, `1.0.0 - 1.2.0`,
+/// `a || b`) into semver alternatives. Tags, URLs and `workspace:` yield `None`.
+fn npm_requirement(range: &str) -> Option<Vec<semver::VersionReq>> {
+    range
+        .split("||")
+        .map(|alternative| {
+            let alternative = alternative.trim();
+            let clauses = if let Some((low, high)) = alternative.split_once(" - ") {
+                vec![format!(">={}", low.trim()), format!("<={}", high.trim())]
+            } else {
+                let mut clauses: Vec<String> = Vec::new();
+                let mut pending = String::new();
+                for token in alternative.split_whitespace() {
+                    pending.push_str(token);
+                    // An operator separated from its version (`>= 0.1`) joins the next token.
+                    if !token
+                        .trim_start_matches(['<', '>', '=', '^', '~'])
+                        .is_empty()
+                    {
+                        clauses.push(std::mem::take(&mut pending));
+                    }
+                }
+                clauses
+            };
+            if clauses.is_empty() {
+                return Some(semver::VersionReq::STAR);
+            }
+            semver::VersionReq::parse(&clauses.join(", ")).ok()
+        })
+        .collect()
+}
+
+fn fail_or_warn(msg: String, hint: &str, force: bool) -> Result<()> {
+    if force {
+        warn!("{} (Force build enabled)", msg);
+        Ok(())
+    } else {
+        anyhow::bail!("{msg}\nHint: {hint}")
+    }
+}
+
+/// Whether a version requirement accepts the given version.
+///
+/// Uses Cargo's requirement syntax: a bare version (`0.1`) means `^0.1`, and
+/// `^`, `~`, `=`, wildcards and comma-separated ranges are supported.
+/// Unparsable inputs fall back to comparing the strings without the operator.
+fn is_compatible(requirement: &str, version: &str) -> bool {
+    match (
+        semver::VersionReq::parse(requirement),
+        semver::Version::parse(version),
+    ) {
+        (Ok(requirement), Ok(version)) => requirement.matches(&version),
+        _ => requirement.trim_start_matches(['^', '~', '=']) == version,
+    }
+}
+
+/// Whether two versions are semver-compatible: same major version, or for
+/// `0.x` the same minor (and for `0.0.x` the same patch).
+fn is_same_release_line(a: &str, b: &str) -> bool {
+    match (semver::Version::parse(a), semver::Version::parse(b)) {
+        (Ok(a), Ok(b)) => {
+            a.major == b.major
+                && (a.major != 0 || a.minor == b.minor)
+                && (a.major != 0 || a.minor != 0 || a.patch == b.patch)
+        }
+        _ => a == b,
+    }
 }
@@ -1,10 +1,43 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::adapter::Adapter;
+use crate::config::BuildConfig;
+use crate::container::Compression;
+use crate::output::OutputFormat;
+use crate::signing::{SignatureAlgorithm, DEFAULT_SIGNATURE_ALGORITHM};
 
 /// Main CLI struct
 /// Parses command line arguments and handles command dispatch
 #[derive(Parser)]
-#[command(author, version, about)]
+#[command(name = "vtx", author, version, about)]
 pub struct Cli {
+    /// Output format: human-readable text, or JSON lines on stdout for CI
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+
+    /// Show more log output: -v debug, -vv trace
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Show less log output: -q warnings and errors, -qq errors only
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub quiet: u8,
+
+    /// Also write a full debug log (with -vv, trace) to this file
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Never touch the network: fail fast instead, and pass --offline to cargo and npm (also VTX_OFFLINE=1)
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Local cache of adapters, templates and dependencies (also VTX_VENDOR_DIR)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub vendor_dir: Option<PathBuf>,
+
     /// Subcommands available for the CLI
     #[command(subcommand)]
     pub command: Commands,
@@ -15,9 +48,12 @@ pub struct Cli {
 pub enum Commands {
     /// Build and package the plugin (wasm -> component -> .vtx)
     Build {
-        /// Workspace package name. If not specified, it will be read from vtx.toml.
-        #[arg(short, long)]
-        package: Option<String>,
+        #[command(flatten)]
+        members: WorkspaceArgs,
+
+        /// Build profile from [profiles.<name>] in vtx.toml, merged over [build]
+        #[arg(long)]
+        profile: Option<String>,
 
         /// Build target architecture (default: "wasm32-wasip1")
         #[arg(long, default_value = "wasm32-wasip1")]
@@ -34,13 +70,105 @@ pub enum Commands {
         /// Debug mode: Retain debug symbols and output verbose logs
         #[arg(long, default_value_t = false)]
         debug: bool,
+
+        #[command(flatten)]
+        options: BuildArgs,
+    },
+
+    /// Rebuild automatically when project files change
+    Watch {
+        /// Build target architecture (default: "wasm32-wasip1")
+        #[arg(long, default_value = "wasm32-wasip1")]
+        target: String,
+
+        /// Force mode: Ignore SDK version mismatches or non-fatal contract errors
+        #[arg(long, default_value_t = false)]
+        force: bool,
+
+        /// Debug mode: Retain debug symbols and output verbose logs
+        #[arg(long, default_value_t = false)]
+        debug: bool,
+
+        /// Quiet period after the last change before rebuilding, in milliseconds
+        #[arg(long, default_value_t = 300)]
+        debounce_ms: u64,
+    },
+
+    /// Rebuild on change and hot-deploy to a running kernel, streaming its logs
+    Dev {
+        /// Build target architecture (default: "wasm32-wasip1")
+        #[arg(long, default_value = "wasm32-wasip1")]
+        target: String,
+
+        /// Force mode: Ignore SDK version mismatches or non-fatal contract errors
+        #[arg(long, default_value_t = false)]
+        force: bool,
+
+        /// Debug mode: Retain debug symbols and output verbose logs
+        #[arg(long, default_value_t = false)]
+        debug: bool,
+
+        /// Quiet period after the last change before rebuilding, in milliseconds
+        #[arg(long, default_value_t = 300)]
+        debounce_ms: u64,
+
+        /// Admin API URL of the kernel (overrides dev.kernel_url and VTX_KERNEL_URL)
+        #[arg(long, value_name = "URL")]
+        kernel_url: Option<String>,
     },
 
     /// Check environment and configuration without building
     Check {
+        #[command(flatten)]
+        members: WorkspaceArgs,
+
         /// Debug mode: Output verbose check logs
         #[arg(long, default_value_t = false)]
         debug: bool,
+
+        /// Probe the toolchains of every supported language, not just the project's
+        #[arg(long, default_value_t = false)]
+        all_languages: bool,
+
+        /// Print the toolchain matrix as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+
+        /// Download the latest deprecation/EOL table before checking
+        #[arg(long, default_value_t = false)]
+        refresh_deprecations: bool,
+    },
+
+    /// Diagnose every language toolchain and the project setup, with fixes
+    Doctor {
+        /// Print the diagnosis as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+
+    /// Check the project for common plugin mistakes without building
+    Lint {
+        #[command(flatten)]
+        members: WorkspaceArgs,
+
+        /// Also fail on warnings (`--deny warnings`), e.g. in CI
+        #[arg(long, value_name = "LEVEL", value_parser = ["warnings"])]
+        deny: Option<String>,
+
+        /// Print the findings as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+
+    /// Move the SDK dependency to the version bundled with this CLI
+    Upgrade {
+        /// Print the changes without writing any file
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Also add constants missing from the scaffold's src/config.rs
+        #[arg(long, default_value_t = false)]
+        boilerplate: bool,
     },
 
     /// Package an existing Wasm artifact into .vtx format
@@ -56,6 +184,9 @@ pub enum Commands {
         /// Debug mode: Output verbose packaging logs
         #[arg(long, default_value_t = false)]
         debug: bool,
+
+        #[command(flatten)]
+        packaging: PackagingArgs,
     },
 
     /// Initialize a new plugin project scaffold
@@ -64,12 +195,970 @@ pub enum Commands {
         #[arg(short, long)]
         name: Option<String>,
 
-        /// Language (rust|ts|python)
+        /// Language of the built-in template (rust|ts|python|c|go|php|lua)
         #[arg(short, long)]
         language: Option<String>,
 
         /// Launch interactive initializer
         #[arg(long, default_value_t = false)]
         interactive: bool,
+
+        /// Scaffold from a template directory or git URL (url#branch) instead of a built-in one
+        #[arg(long, value_name = "GIT_URL|PATH")]
+        template: Option<String>,
+
+        /// Author name for the template (default: git config user.name)
+        #[arg(long)]
+        author: Option<String>,
+
+        /// License identifier for the template (default: MIT)
+        #[arg(long)]
+        license: Option<String>,
+
+        /// Also generate release.toml for `vtx release`
+        #[arg(long, default_value_t = false)]
+        with_release_script: bool,
+    },
+
+    /// Run the release steps defined in release.toml
+    Release {
+        /// Continue an interrupted release from its first unfinished step
+        #[arg(long, default_value_t = false)]
+        resume: bool,
+
+        /// Answer yes to every step listed in release.confirm
+        #[arg(long, default_value_t = false)]
+        yes: bool,
+
+        /// Do not run the test step
+        #[arg(long, default_value_t = false)]
+        skip_test: bool,
+
+        /// Do not run the sign step
+        #[arg(long, default_value_t = false)]
+        skip_sign: bool,
+
+        /// Do not run the sbom step
+        #[arg(long, default_value_t = false)]
+        skip_sbom: bool,
+
+        /// Do not run the publish step
+        #[arg(long, default_value_t = false)]
+        skip_publish: bool,
+
+        /// Print the steps that would run, and what each does, without running them
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+
+    /// Find the commit that broke the build, grew the artifact or slowed the plugin, using git bisect
+    #[command(group(clap::ArgGroup::new("predicate").required(true).args(["build_fails", "max_size", "bench"])))]
+    Bisect {
+        /// Last known good revision
+        #[arg(long)]
+        good: String,
+
+        /// First known bad revision
+        #[arg(long, default_value = "HEAD")]
+        bad: String,
+
+        /// A commit is bad when `vtx build` fails
+        #[arg(long, default_value_t = false)]
+        build_fails: bool,
+
+        /// A commit is bad when the artifact is larger than this many bytes
+        #[arg(long, value_name = "BYTES")]
+        max_size: Option<u64>,
+
+        /// A commit is bad when `vtx bench` of the artifact measures a metric above a
+        /// threshold, e.g. p99_ms=5 (cold_start_ms, p50_ms, p99_ms, memory_bytes)
+        #[arg(long, value_name = "METRIC=THRESHOLD")]
+        bench: Option<String>,
+
+        /// JSON request file passed to `vtx bench --request`
+        #[arg(long, value_name = "FILE", requires = "bench")]
+        bench_request: Option<String>,
+
+        /// Build in release mode at each step
+        #[arg(long, default_value_t = false)]
+        release: bool,
+
+        /// Pass --force to each build
+        #[arg(long, default_value_t = false)]
+        force: bool,
+
+        /// Show build output at each step
+        #[arg(long, default_value_t = false)]
+        debug: bool,
+    },
+
+    /// Print the container, payload and metadata digests of .vtx files
+    Hash {
+        /// .vtx files to hash
+        files: Vec<String>,
+
+        /// Digest algorithm
+        #[arg(long, default_value = "sha256", value_parser = ["sha256", "sha512"])]
+        algorithm: String,
+
+        /// Verify the files listed in a SHA256SUMS file instead
+        #[arg(long, value_name = "SHA256SUMS", conflicts_with = "files")]
+        check: Option<String>,
+    },
+
+    /// Show the metadata of a .vtx file
+    Inspect {
+        /// .vtx file to inspect
+        file: String,
+
+        /// Show where the artifact came from: source, builder, toolchain and signatures
+        #[arg(long)]
+        provenance: bool,
+
+        /// Print the metadata, exports and imports as JSON
+        #[arg(long, default_value_t = false, conflicts_with = "provenance")]
+        json: bool,
+
+        /// Key for an encrypted payload; defaults to VTX_PAYLOAD_KEY
+        #[arg(long, value_name = "FILE")]
+        key_file: Option<PathBuf>,
+    },
+
+    /// Check a .vtx or .wasm built elsewhere: well-formedness, contract and metadata
+    Validate {
+        /// .vtx or .wasm file to validate
+        file: String,
+
+        /// Also require schema v1 metadata with every field the CLI writes
+        #[arg(long, default_value_t = false)]
+        strict: bool,
+
+        /// Print the checks as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+
+        /// Key for an encrypted payload; defaults to VTX_PAYLOAD_KEY
+        #[arg(long, value_name = "FILE")]
+        key_file: Option<PathBuf>,
+    },
+
+    /// Compare two artifacts: versions, metadata, exports/imports and section sizes
+    Diff {
+        /// Baseline artifact (.vtx, component or module)
+        old: String,
+
+        /// Artifact to compare against the baseline
+        new: String,
+
+        /// Print the differences as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+
+    /// Extract the metadata and component of a .vtx file
+    Unpack {
+        /// .vtx file to unpack
+        file: String,
+
+        /// Directory to write the extracted files to
+        #[arg(long, default_value = ".")]
+        out_dir: String,
+
+        /// Salvage the intact parts of a damaged file and report what was lost
+        #[arg(long)]
+        repair: bool,
+    },
+
+    /// Print a plugin's get-manifest result as JSON and validate its fields
+    Manifest {
+        /// .vtx file or bare component to load
+        file: String,
+    },
+
+    /// Call the plugin's handle export with a synthetic HTTP request under wasmtime
+    Run {
+        /// .vtx file or bare component to run
+        file: String,
+
+        /// Request method
+        #[arg(long, default_value = "GET")]
+        method: String,
+
+        /// Request path
+        #[arg(long, default_value = "/")]
+        path: String,
+
+        /// Query string, without the leading '?'
+        #[arg(long, default_value = "")]
+        query: String,
+
+        /// JSON file with method, path and query; replaces the flags
+        #[arg(long, conflicts_with_all = ["method", "path", "query"])]
+        request: Option<String>,
+
+        /// Emulate a kernel implementing this vtx:api version (MAJOR.MINOR.PATCH): link only
+        /// the interfaces it provides and fail on any other import
+        #[arg(long, value_name = "KERNEL_API")]
+        api: Option<String>,
+    },
+
+    /// Measure a built plugin's cold start, request latency and memory against bench-baseline.json,
+    /// or show results over time
+    #[command(args_conflicts_with_subcommands = true)]
+    Bench {
+        #[command(subcommand)]
+        action: Option<BenchAction>,
+
+        /// .vtx file or bare component to benchmark
+        file: Option<String>,
+
+        /// Request method
+        #[arg(long, default_value = "GET")]
+        method: String,
+
+        /// Request path
+        #[arg(long, default_value = "/")]
+        path: String,
+
+        /// Query string, without the leading '?'
+        #[arg(long, default_value = "")]
+        query: String,
+
+        /// JSON file with method, path and query; replaces the flags
+        #[arg(long, conflicts_with_all = ["method", "path", "query"])]
+        request: Option<String>,
+
+        /// Emulate a kernel implementing this vtx:api version (MAJOR.MINOR.PATCH)
+        #[arg(long, value_name = "KERNEL_API")]
+        api: Option<String>,
+
+        /// Calls of handle to time (default: [bench] iterations, else 200)
+        #[arg(long)]
+        iterations: Option<u32>,
+
+        /// Fail when a metric regressed beyond its [bench.tolerance] versus the baseline
+        #[arg(long, default_value_t = false, conflicts_with = "update_baseline")]
+        check: bool,
+
+        /// Record this run as bench-baseline.json, after an intentional change
+        #[arg(long, default_value_t = false)]
+        update_baseline: bool,
+    },
+
+    /// Serve a built plugin over HTTP from a local dev host
+    Serve {
+        /// .vtx file or bare component to serve
+        file: String,
+
+        /// Address to listen on; port 0 picks a free port
+        #[arg(long, default_value = "127.0.0.1:8787")]
+        addr: String,
+
+        /// Clock the plugin sees: the host's, or a virtual one advanced by hand
+        #[arg(long, value_enum, default_value_t = ClockMode::System)]
+        clock: ClockMode,
+
+        /// Start the manual clock at an RFC 3339 UTC time such as 2024-01-01T00:00:00Z
+        /// (default: now)
+        #[arg(long, value_name = "TIME")]
+        time: Option<String>,
+
+        /// Emulate a kernel implementing this vtx:api version (see vtx run --api)
+        #[arg(long, value_name = "KERNEL_API")]
+        api: Option<String>,
+    },
+
+    /// Build in debug mode, run cargo test (Rust) and the tests/vtx contract fixtures
+    #[command(args_conflicts_with_subcommands = true)]
+    Test {
+        #[command(subcommand)]
+        action: Option<TestAction>,
+
+        #[command(flatten)]
+        members: WorkspaceArgs,
+
+        /// Build target architecture (default: "wasm32-wasip1")
+        #[arg(long, default_value = "wasm32-wasip1")]
+        target: String,
+
+        /// Force mode: Ignore SDK version mismatches or non-fatal contract errors
+        #[arg(long, default_value_t = false)]
+        force: bool,
+
+        #[command(flatten)]
+        tests: TestArgs,
+    },
+
+    /// Upload a .vtx file to the configured registry
+    Publish {
+        /// .vtx file to publish
+        file: String,
+    },
+
+    /// Download a published artifact and verify its checksum
+    Pull {
+        /// Package to download, as <name>@<version>
+        spec: String,
+
+        /// Directory to write the artifact to
+        #[arg(long, default_value = ".")]
+        out_dir: String,
+    },
+
+    /// Generate a signing key pair (<name>.pk8 and <name>.pub)
+    Keygen {
+        /// Base name of the key files
+        #[arg(default_value = "vtx-signing")]
+        name: String,
+
+        /// Key algorithm: ed25519 or ecdsa-p256
+        #[arg(long, default_value_t = DEFAULT_SIGNATURE_ALGORITHM, value_parser = SignatureAlgorithm::from_str)]
+        algorithm: SignatureAlgorithm,
+    },
+
+    /// Sign a .vtx file in place with an ed25519 or ecdsa-p256 key
+    Sign {
+        /// .vtx file to sign
+        file: String,
+
+        /// PKCS#8 private key written by `vtx keygen`
+        #[arg(long)]
+        key: String,
+    },
+
+    /// Check the signatures of .vtx files against a public key
+    Verify {
+        /// .vtx files to verify, or directories of .vtx files
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Base64 public key or path to a .pub file. Defaults to package.public_key in vtx.toml
+        #[arg(long)]
+        public_key: Option<String>,
+
+        /// Only check the manifest signature over the metadata, without hashing the component
+        #[arg(long)]
+        manifest_only: bool,
+
+        /// Check the interfaces the artifacts require against this vtx:api version
+        /// (MAJOR.MINOR.PATCH); signatures are then only checked with a key
+        #[arg(long, value_name = "KERNEL_API")]
+        against: Option<String>,
+
+        /// Verify up to N files at once (default: available CPUs)
+        #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        jobs: Option<u32>,
+    },
+
+    /// Check the signature, SBOM and provenance of a .vtx together; fails if any is missing or invalid
+    Attest {
+        /// .vtx file to attest
+        file: String,
+
+        /// Base64 public key or path to a .pub file. Defaults to package.public_key in vtx.toml
+        #[arg(long)]
+        public_key: Option<String>,
+
+        /// Print the checks as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+
+    /// Emulate a kernel API version locally
+    Kernel {
+        #[command(subcommand)]
+        action: KernelAction,
+    },
+
+    /// Show or verify the local artifact ledger (.vtx-ledger.jsonl)
+    Ledger {
+        #[command(subcommand)]
+        action: LedgerAction,
+    },
+
+    /// List, declare or verify the plugin's static assets (vtx-assets.json)
+    Assets {
+        #[command(subcommand)]
+        action: AssetsAction,
+    },
+
+    /// Break down an artifact's size by section and function, or show sizes over time
+    #[command(args_conflicts_with_subcommands = true)]
+    Size {
+        #[command(subcommand)]
+        action: Option<SizeAction>,
+
+        /// .vtx file, component or core module to analyze
+        artifact: Option<String>,
+
+        /// Number of function prefixes and functions to list
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+
+    /// Remove .vtx artifacts, build intermediates and the packaging cache
+    Clean {
+        /// Only remove .vtx artifacts and the files written next to them
+        #[arg(long, default_value_t = false, conflicts_with = "all")]
+        artifacts_only: bool,
+
+        /// Also remove the whole .vtx-cache (size and bench history, build summaries, release state)
+        #[arg(long, default_value_t = false)]
+        all: bool,
+    },
+
+    /// Print JSON Schemas for the formats the CLI reads and writes
+    Schema {
+        /// Schema name (vtx-toml|metadata|build-summary|check-report). Prints all if omitted.
+        name: Option<String>,
     },
+
+    /// Print a shell completion script (bash, zsh, fish, powershell, elvish)
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+
+    /// Generate man pages: vtx(1) on stdout, or one page per command into a directory
+    Mangen {
+        /// Write vtx.1 and a page per subcommand here instead of printing vtx(1)
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+/// `vtx kernel` actions
+#[derive(Subcommand)]
+pub enum KernelAction {
+    /// Check whether a kernel implementing a vtx:api version would load a plugin
+    Emulate {
+        /// .vtx file or bare component
+        file: String,
+
+        /// vtx:api version to emulate (MAJOR.MINOR.PATCH)
+        #[arg(long, value_name = "KERNEL_API")]
+        api: String,
+
+        /// Print the report as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+/// `vtx ledger` actions
+#[derive(Subcommand)]
+pub enum LedgerAction {
+    /// List recorded artifacts
+    Show,
+    /// Check that no entry was modified, removed or reordered
+    Verify,
+}
+
+/// `vtx test` actions
+#[derive(Subcommand)]
+pub enum TestAction {
+    /// Write a skeleton tests/vtx fixture for every exported function, keeping edited ones
+    Scaffold {
+        /// Workspace package name. If not specified, it will be read from vtx.toml.
+        #[arg(short, long)]
+        package: Option<String>,
+
+        /// Read the exports from this .vtx file or component instead of building
+        #[arg(long, value_name = "FILE")]
+        artifact: Option<PathBuf>,
+
+        /// Force mode: Ignore SDK version mismatches or non-fatal contract errors
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
+}
+
+/// `vtx assets` actions
+#[derive(Subcommand)]
+pub enum AssetsAction {
+    /// List declared assets with sizes and checksums
+    List,
+    /// Declare files or directories (relative to assets.dir) and record their checksums
+    Add {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Remove files or directories from the declared set
+    Remove {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Check declared assets against disk: missing, modified, over budget or undeclared
+    Verify,
+}
+
+/// `vtx size` actions
+#[derive(Subcommand)]
+pub enum SizeAction {
+    /// List artifact sizes per build with the git commit and a sparkline
+    History {
+        /// Only show this package
+        #[arg(short, long)]
+        package: Option<String>,
+
+        /// Build profile (debug|release)
+        #[arg(long, default_value = "release")]
+        profile: String,
+
+        /// Show only the most recent N builds
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Print CSV instead of a table
+        #[arg(long, default_value_t = false)]
+        csv: bool,
+    },
+}
+
+/// `vtx bench` actions
+#[derive(Subcommand)]
+pub enum BenchAction {
+    /// List bench results per run with the git commit and a sparkline
+    History {
+        /// Metric the deltas and sparkline follow
+        #[arg(long, default_value = "p99_ms", value_parser = ["cold_start_ms", "p50_ms", "p99_ms", "memory_bytes"])]
+        metric: String,
+
+        /// Only show runs of this file
+        #[arg(long)]
+        file: Option<String>,
+
+        /// Show only the most recent N runs
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Print CSV instead of a table
+        #[arg(long, default_value_t = false)]
+        csv: bool,
+    },
+}
+
+/// Workspace member selection, shared by every command that runs per member.
+#[derive(Args, Clone, Debug, Default)]
+pub struct WorkspaceArgs {
+    /// Workspace package name. If not specified, it will be read from vtx.toml.
+    #[arg(short, long)]
+    pub package: Option<String>,
+
+    /// Run for every member of the Cargo or npm workspace
+    #[arg(long, visible_alias = "all", conflicts_with = "package")]
+    pub workspace: bool,
+
+    /// With --workspace, skip this member (repeatable)
+    #[arg(long, value_name = "NAME", requires = "workspace")]
+    pub exclude: Vec<String>,
+
+    /// With --workspace, run up to N members at once (default: available CPUs)
+    #[arg(short, long, value_name = "N", requires = "workspace", value_parser = clap::value_parser!(u32).range(1..))]
+    pub jobs: Option<u32>,
+}
+
+/// Options of `test` that select which tests run.
+#[derive(Args, Clone, Debug, Default)]
+pub struct TestArgs {
+    /// Only run contract fixtures whose name contains TEXT; also passed to cargo test as its
+    /// name filter
+    #[arg(long, value_name = "TEXT")]
+    pub filter: Option<String>,
+
+    /// Run shard N of M (1-based): every M-th contract fixture, starting with the N-th.
+    /// cargo test runs in shard 1 only
+    #[arg(long, value_name = "N/M", value_parser = Shard::from_str)]
+    pub shard: Option<Shard>,
+
+    /// Rerun a failed contract fixture up to N times; one that then passes is reported as flaky
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retries: u32,
+
+    /// Seed the plugin's random number generators with N (overrides `[test] seed`)
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+
+    /// Freeze the plugin's clocks at an RFC 3339 UTC time such as 2024-01-01T00:00:00Z
+    /// (overrides `[test] time`)
+    #[arg(long, value_name = "TIME")]
+    pub time: Option<String>,
+
+    /// Also run mutated variants of each fixture's call (emptied, truncated or oversized
+    /// strings, missing list entries, wrong content types); they pass unless the plugin traps
+    #[arg(long)]
+    pub mutate: bool,
+
+    /// Emulate a kernel implementing this vtx:api version (MAJOR.MINOR.PATCH): link only
+    /// the interfaces it provides and fail every fixture whose plugin imports any other
+    #[arg(long, value_name = "KERNEL_API")]
+    pub api: Option<String>,
+
+    /// Also write the results as a JUnit XML report to FILE
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+
+    /// How fixture results are printed: pretty, or tap (Test Anything Protocol)
+    #[arg(long, value_enum, default_value_t = TestFormat::Pretty)]
+    pub format: TestFormat,
+}
+
+/// Fixture result format selected with `vtx test --format`.
+/// Clock `vtx serve` gives the plugin.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClockMode {
+    /// The host's wall and monotonic clocks.
+    #[default]
+    System,
+    /// A virtual clock that only moves when advanced through `/_vtx/clock`
+    /// or the console.
+    Manual,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TestFormat {
+    /// PASS/FAIL lines as fixtures finish.
+    #[default]
+    Pretty,
+    /// A TAP version 13 stream once all fixtures ran.
+    Tap,
+}
+
+impl TestArgs {
+    /// The command-line flags that reproduce these options in a child `vtx` process.
+    ///
+    /// `--report` and `--format` are left out: the parent writes them from
+    /// the collected reports.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(filter) = &self.filter {
+            args.extend(["--filter".to_string(), filter.clone()]);
+        }
+        if let Some(shard) = &self.shard {
+            args.extend(["--shard".to_string(), shard.to_string()]);
+        }
+        if self.retries > 0 {
+            args.extend(["--retries".to_string(), self.retries.to_string()]);
+        }
+        if let Some(seed) = self.seed {
+            args.extend(["--seed".to_string(), seed.to_string()]);
+        }
+        if let Some(time) = &self.time {
+            args.extend(["--time".to_string(), time.clone()]);
+        }
+        if self.mutate {
+            args.push("--mutate".to_string());
+        }
+        if let Some(api) = &self.api {
+            args.extend(["--api".to_string(), api.clone()]);
+        }
+        args
+    }
+}
+
+/// One of `count` shards, selected by `--shard index/count`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    /// 1-based.
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    /// Whether the item at `position` (0-based) belongs to this shard.
+    pub fn contains(&self, position: usize) -> bool {
+        position % self.count == self.index - 1
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = s
+            .split_once('/')
+            .and_then(|(index, count)| Some((index.parse().ok()?, count.parse().ok()?)));
+        match parsed {
+            Some((index, count)) if count > 0 && (1..=count).contains(&index) => {
+                Ok(Shard { index, count })
+            }
+            _ => Err(format!("expected N/M with 1 <= N <= M, got '{s}'")),
+        }
+    }
+}
+
+impl std::fmt::Display for Shard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// Options of `build`: toolchain flags followed by the shared packaging flags.
+#[derive(Args, Clone, Debug, Default)]
+pub struct BuildArgs {
+    #[command(flatten)]
+    pub toolchain: ToolchainArgs,
+
+    #[command(flatten)]
+    pub packaging: PackagingArgs,
+
+    /// Build reproducibly, rebuild in a child process and fail if the artifacts differ
+    #[arg(long, default_value_t = false, conflicts_with = "workspace")]
+    pub verify_reproducible: bool,
+
+    /// Build both the SIMD artifact and a scalar <name>.scalar.vtx variant
+    #[arg(long, default_value_t = false, conflicts_with_all = ["workspace", "verify_reproducible", "no_simd"])]
+    pub simd_matrix: bool,
+}
+
+impl BuildArgs {
+    /// The command-line flags that reproduce these options in a child `vtx` process.
+    /// `--verify-reproducible` and `--simd-matrix` are not passed on.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = self.toolchain.to_args();
+        args.extend(self.packaging.to_args());
+        args
+    }
+}
+
+/// Toolchain options for `build`, overriding `[build]` in vtx.toml.
+#[derive(Args, Clone, Debug, Default)]
+pub struct ToolchainArgs {
+    /// Enable a feature (cargo --features, TinyGo build tag, C define; VTX_FEATURES for scripts).
+    /// Repeatable; replaces build.features
+    #[arg(long = "feature", value_name = "NAME")]
+    pub features: Vec<String>,
+
+    /// Set an environment variable for the toolchain and build.cmd. Repeatable; overrides the
+    /// same key in build.env
+    #[arg(long = "env", value_name = "KEY=VAL", value_parser = parse_env)]
+    pub env: Vec<(String, String)>,
+
+    /// Compile on this build service instead of with the local toolchain; overrides build.remote
+    #[arg(long, value_name = "URL")]
+    pub remote: Option<String>,
+
+    /// Build without SIMD instructions for kernels on hardware without SIMD; sets build.no_simd
+    #[arg(long, default_value_t = false)]
+    pub no_simd: bool,
+}
+
+impl ToolchainArgs {
+    /// The command-line flags that reproduce these options in a child `vtx` process.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for feature in &self.features {
+            args.extend(["--feature".to_string(), feature.clone()]);
+        }
+        for (key, value) in &self.env {
+            args.extend(["--env".to_string(), format!("{key}={value}")]);
+        }
+        if let Some(url) = &self.remote {
+            args.extend(["--remote".to_string(), url.clone()]);
+        }
+        if self.no_simd {
+            args.push("--no-simd".to_string());
+        }
+        args
+    }
+
+    /// Merge the flags over `[build]`.
+    pub fn apply(&self, build: Option<BuildConfig>) -> Option<BuildConfig> {
+        if self.features.is_empty() && self.env.is_empty() && self.remote.is_none() && !self.no_simd
+        {
+            return build;
+        }
+        let mut build = build.unwrap_or_default();
+        if self.remote.is_some() {
+            build.remote = self.remote.clone();
+        }
+        if self.no_simd {
+            build.no_simd = Some(true);
+        }
+        if !self.features.is_empty() {
+            build.features = Some(self.features.clone());
+        }
+        build
+            .env
+            .get_or_insert_with(Default::default)
+            .extend(self.env.iter().cloned());
+        Some(build)
+    }
+}
+
+fn parse_env(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VAL, got '{s}'")),
+    }
+}
+
+/// Packaging options shared by `build` and `package`.
+#[derive(Args, Clone, Debug, Default)]
+pub struct PackagingArgs {
+    /// Deny a WASI capability (args|environ|clocks|random|stdio|filesystem|poll|proc|sockets).
+    /// Repeatable; merged with build.wasi.deny from vtx.toml
+    #[arg(long = "deny-wasi", value_name = "CAPABILITY")]
+    pub deny_wasi: Vec<String>,
+
+    /// Also write the bare component (<name>.component.wasm) for non-VTX hosts
+    #[arg(long, default_value_t = false)]
+    pub emit_component: bool,
+
+    /// Instantiate the component under wasmtime (WASI only) to confirm imports and exports link. Slower
+    #[arg(long, default_value_t = false)]
+    pub deep_validate: bool,
+
+    /// Accept modules that use wasm threads (shared memory, atomics)
+    #[arg(long, default_value_t = false)]
+    pub allow_threads: bool,
+
+    /// Trim zero padding from data segments and pool identical passive segments before encoding
+    #[arg(long, default_value_t = false)]
+    pub optimize_data: bool,
+
+    /// Optimize the core module with wasm-opt at this level (0-4, s, z); overrides [build.optimize]
+    #[arg(long, value_parser = ["0", "1", "2", "3", "4", "s", "z"])]
+    pub opt_level: Option<String>,
+
+    /// Always repackage, ignoring and not updating the packaging cache
+    #[arg(long, default_value_t = false)]
+    pub no_cache: bool,
+
+    /// Print a size breakdown by section, custom section and function after packaging
+    #[arg(long, default_value_t = false)]
+    pub size_report: bool,
+
+    /// Move DWARF sections into <artifact>.vtx.dbg, linked to the artifact by a build id
+    #[arg(long, default_value_t = false)]
+    pub split_debuginfo: bool,
+
+    /// WASI preview1 adapter: reactor (default), command, proxy, or a path to an adapter .wasm;
+    /// overrides build.adapter
+    #[arg(long, value_name = "ADAPTER", value_parser = Adapter::from_str)]
+    pub adapter: Option<Adapter>,
+
+    /// Write <artifact>.sha256 and an SBOM (cyclonedx, the default, or spdx) from the
+    /// project's lock data; overrides package.sbom
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "cyclonedx",
+          value_parser = ["cyclonedx", "spdx"])]
+    pub sbom: Option<String>,
+
+    /// Strip producers and build-id sections and stamp metadata with SOURCE_DATE_EPOCH, so
+    /// identical inputs give byte-identical artifacts
+    #[arg(long, default_value_t = false)]
+    pub reproducible: bool,
+
+    /// Directory for the .vtx artifact, created if missing; overrides package.out_dir
+    #[arg(long, value_name = "DIR")]
+    pub out_dir: Option<String>,
+
+    /// Artifact file name template, e.g. "{name}-{version}-{target}.vtx"; overrides
+    /// package.artifact_name
+    #[arg(long, value_name = "TEMPLATE")]
+    pub out_name: Option<String>,
+
+    /// Variant name inserted before the artifact's extension (`<name>.<variant>.vtx`),
+    /// set for the scalar build of `--simd-matrix`
+    #[arg(skip)]
+    pub variant: Option<String>,
+
+    /// Compress the payload section: zstd or zstd:<1-22>; overrides package.compress
+    #[arg(long, value_name = "CODEC", value_parser = Compression::from_str)]
+    pub compress: Option<Compression>,
+
+    /// Encrypt the payload with AES-256-GCM using this 32-byte key file (raw or base64);
+    /// overrides VTX_PAYLOAD_KEY and package.payload_key
+    #[arg(long, value_name = "FILE")]
+    pub encrypt_key: Option<PathBuf>,
+
+    /// Write [capabilities] and bundled assets as sections of their own (container v3,
+    /// or v4 with assets), which readers of v1/v2 containers reject
+    #[arg(long, default_value_t = false)]
+    pub container_sections: bool,
+}
+
+impl PackagingArgs {
+    /// The command-line flags that reproduce these options in a child `vtx` process.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for capability in &self.deny_wasi {
+            args.extend(["--deny-wasi".to_string(), capability.clone()]);
+        }
+        let flags = [
+            (self.emit_component, "--emit-component"),
+            (self.deep_validate, "--deep-validate"),
+            (self.allow_threads, "--allow-threads"),
+            (self.optimize_data, "--optimize-data"),
+            (self.no_cache, "--no-cache"),
+            (self.size_report, "--size-report"),
+            (self.split_debuginfo, "--split-debuginfo"),
+            (self.reproducible, "--reproducible"),
+            (self.container_sections, "--container-sections"),
+        ];
+        args.extend(
+            flags
+                .into_iter()
+                .filter(|(set, _)| *set)
+                .map(|(_, flag)| flag.to_string()),
+        );
+        if let Some(dir) = &self.out_dir {
+            args.extend(["--out-dir".to_string(), dir.clone()]);
+        }
+        if let Some(name) = &self.out_name {
+            args.extend(["--out-name".to_string(), name.clone()]);
+        }
+        if let Some(level) = &self.opt_level {
+            args.extend(["--opt-level".to_string(), level.clone()]);
+        }
+        if let Some(adapter) = &self.adapter {
+            args.extend(["--adapter".to_string(), adapter.to_string()]);
+        }
+        if let Some(format) = &self.sbom {
+            args.push(format!("--sbom={format}"));
+        }
+        if let Some(compression) = &self.compress {
+            args.extend(["--compress".to_string(), compression.to_string()]);
+        }
+        if let Some(key) = &self.encrypt_key {
+            args.extend(["--encrypt-key".to_string(), key.display().to_string()]);
+        }
+        args
+    }
+}
+
+impl Commands {
+    /// Whether the command's `--debug` flag is set, which raises log verbosity like `-v`.
+    pub fn debug(&self) -> bool {
+        matches!(
+            self,
+            Commands::Build { debug: true, .. }
+                | Commands::Watch { debug: true, .. }
+                | Commands::Dev { debug: true, .. }
+                | Commands::Check { debug: true, .. }
+                | Commands::Package { debug: true, .. }
+                | Commands::Bisect { debug: true, .. }
+        )
+    }
+
+    /// Whether the command writes machine-readable output to stdout.
+    pub fn machine_output(&self) -> bool {
+        matches!(
+            self,
+            Commands::Check { json: true, .. }
+                | Commands::Doctor { json: true }
+                | Commands::Lint { json: true, .. }
+                | Commands::Inspect { json: true, .. }
+                | Commands::Validate { json: true, .. }
+                | Commands::Attest { json: true, .. }
+                | Commands::Kernel {
+                    action: KernelAction::Emulate { json: true, .. }
+                }
+                | Commands::Diff { json: true, .. }
+                | Commands::Manifest { .. }
+                | Commands::Schema { .. }
+                | Commands::Completions { .. }
+                | Commands::Mangen { out_dir: None }
+        )
+    }
 }
@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::config::CompareConfig;
+use crate::packager;
+
+/// Directory holding per-package summaries of the last successful build.
+const SUMMARY_DIR: &str = ".vtx-cache/builds";
+
+/// Snapshot of a produced artifact, persisted between builds.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ArtifactSummary {
+    pub package: String,
+    pub profile: String,
+    pub artifact_size: u64,
+    pub component_size: u64,
+    pub exports: Vec<String>,
+    pub imports: Vec<String>,
+}
+
+impl ArtifactSummary {
+    pub fn new(
+        package: &str,
+        profile: &str,
+        artifact_path: &Path,
+        component_bytes: &[u8],
+    ) -> Result<Self> {
+        let artifact_size = std::fs::metadata(artifact_path)
+            .with_context(|| format!("Failed to stat artifact: {}", artifact_path.display()))?
+            .len();
+
+        Ok(Self {
+            package: package.to_string(),
+            profile: profile.to_string(),
+            artifact_size,
+            component_size: component_bytes.len() as u64,
+            exports: packager::component_exports(component_bytes)?,
+            imports: packager::component_imports(component_bytes)?,
+        })
+    }
+}
+
+/// Difference between the previous and the current build.
+#[derive(Debug)]
+pub struct ArtifactDelta {
+    pub previous_size: u64,
+    pub current_size: u64,
+    pub exports_added: Vec<String>,
+    pub exports_removed: Vec<String>,
+    pub previous_imports: usize,
+    pub current_imports: usize,
+}
+
+impl ArtifactDelta {
+    pub fn between(previous: &ArtifactSummary, current: &ArtifactSummary) -> Self {
+        let exports_added = current
+            .exports
+            .iter()
+            .filter(|e| !previous.exports.contains(e))
+            .cloned()
+            .collect();
+        let exports_removed = previous
+            .exports
+            .iter()
+            .filter(|e| !current.exports.contains(e))
+            .cloned()
+            .collect();
+
+        Self {
+            previous_size: previous.artifact_size,
+            current_size: current.artifact_size,
+            exports_added,
+            exports_removed,
+            previous_imports: previous.imports.len(),
+            current_imports: current.imports.len(),
+        }
+    }
+
+    pub fn size_change(&self) -> i64 {
+        self.current_size as i64 - self.previous_size as i64
+    }
+
+    pub fn size_change_percent(&self) -> f64 {
+        if self.previous_size == 0 {
+            return 0.0;
+        }
+        self.size_change() as f64 * 100.0 / self.previous_size as f64
+    }
+
+    pub fn exports_changed(&self) -> bool {
+        !self.exports_added.is_empty() || !self.exports_removed.is_empty()
+    }
+
+    /// One-line human summary.
+    pub fn describe(&self) -> String {
+        format!(
+            "size {:+} B ({:+.1}%), exports +{}/-{}, imports {} -> {}",
+            self.size_change(),
+            self.size_change_percent(),
+            self.exports_added.len(),
+            self.exports_removed.len(),
+            self.previous_imports,
+            self.current_imports
+        )
+    }
+
+    /// Return every threshold from `config` that this delta exceeds.
+    pub fn violations(&self, config: &CompareConfig) -> Vec<String> {
+        let mut out = Vec::new();
+        let growth = self.size_change();
+
+        if let Some(max) = config.max_growth_bytes {
+            if growth > max as i64 {
+                out.push(format!("artifact grew by {growth} B (limit {max} B)"));
+            }
+        }
+        if let Some(max) = config.max_growth_percent {
+            let percent = self.size_change_percent();
+            if percent > max {
+                out.push(format!("artifact grew by {percent:.1}% (limit {max:.1}%)"));
+            }
+        }
+        if config.deny_export_changes.unwrap_or(false) && self.exports_changed() {
+            out.push(format!(
+                "export surface changed (added: [{}], removed: [{}])",
+                self.exports_added.join(", "),
+                self.exports_removed.join(", ")
+            ));
+        }
+
+        out
+    }
+}
+
+/// `<package>-<profile>-<hash>.json`, with characters that are not safe in a
+/// file name (such as the `@` and `/` of `@scope/name`) replaced by `_`. The
+/// hash of the unsanitized names keeps `@a/b` and `_a_b` apart.
+fn summary_path(package: &str, profile: &str) -> PathBuf {
+    use sha2::{Digest, Sha256};
+
+    let key = Sha256::digest(format!("{package}\0{profile}"));
+    let name: String = format!("{package}-{profile}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Path::new(SUMMARY_DIR).join(format!("{name}-{}.json", crate::hex::encode(&key[..4])))
+}
+
+fn load_previous(package: &str, profile: &str) -> Option<ArtifactSummary> {
+    let content = std::fs::read(summary_path(package, profile)).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+fn store(summary: &ArtifactSummary) -> Result<()> {
+    let path = summary_path(&summary.package, &summary.profile);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_vec_pretty(summary)?)
+        .with_context(|| format!("Failed to write build summary: {}", path.display()))?;
+    Ok(())
+}
+
+/// Compare the fresh artifact with the previous local build and record it.
+///
+/// Behavior:
+/// - Prints a one-line delta when a previous summary exists.
+/// - If a configured threshold is exceeded, returns an error (warning only with `force`).
+/// - The new summary is only stored when the comparison passes, so a rejected
+///   build never becomes the next baseline.
+pub fn compare_with_previous(
+    current: &ArtifactSummary,
+    config: Option<&CompareConfig>,
+    force: bool,
+) -> Result<()> {
+    if let Some(previous) = load_previous(&current.package, &current.profile) {
+        let delta = ArtifactDelta::between(&previous, current);
+        info!(
+            "Compared to previous {} build: {}",
+            current.profile,
+            delta.describe()
+        );
+
+        let violations = config.map(|c| delta.violations(c)).unwrap_or_default();
+        if !violations.is_empty() {
+            let msg = format!("Build comparison failed: {}", violations.join("; "));
+            if force {
+                warn!("{} (Force build enabled)", msg);
+            } else {
+                anyhow::bail!(
+                    "{msg}\nHint: Adjust [build.compare] thresholds in vtx.toml or use --force to bypass."
+                );
+            }
+        }
+    }
+
+    store(current)
+}
@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use wasm_compose::composer::ComponentComposer;
+use wasm_compose::config::Config;
+
+use crate::container::{self, PayloadKey};
+use crate::packager;
+
+/// A component linked into the packaged plugin (`[dependencies]` in vtx.toml).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dependency {
+    /// Name used in messages.
+    pub name: String,
+
+    /// A `.vtx` artifact or a `.wasm` component.
+    pub path: PathBuf,
+}
+
+impl Dependency {
+    /// The dependency's component, unwrapped from its `.vtx` container if needed.
+    pub fn component(&self) -> Result<Vec<u8>> {
+        let bytes = std::fs::read(&self.path).with_context(|| {
+            format!(
+                "Failed to read dependency '{}' from {}",
+                self.name,
+                self.path.display()
+            )
+        })?;
+        let component = container::unwrap_component(bytes, PayloadKey::load(None)?.as_ref())?;
+        if !packager::is_component(&component).unwrap_or(false) {
+            anyhow::bail!(
+                "Dependency '{}' ({}) is not a WebAssembly component or .vtx artifact",
+                self.name,
+                self.path.display()
+            );
+        }
+        Ok(component)
+    }
+}
+
+/// Compose `component` with its dependencies into one self-contained component.
+///
+/// Each import of `component` that a dependency exports under the same name
+/// (`acme:auth/verify@1.0.0`) is satisfied by an instance of that dependency,
+/// defined inside the result. Dependencies are composed transitively, so a
+/// dependency's own imports can be satisfied by another dependency. Imports
+/// no dependency provides (WASI, kernel interfaces) stay imports. A
+/// dependency that provides none of the imports is an error, as it would be
+/// silently dropped. `work_dir` holds the component files handed to the composer.
+pub fn compose(component: &[u8], dependencies: &[Dependency], work_dir: &Path) -> Result<Vec<u8>> {
+    let mut wanted = packager::component_imports(component)?;
+    let mut definitions = Vec::with_capacity(dependencies.len());
+    for dependency in dependencies {
+        let bytes = dependency.component()?;
+        wanted.extend(packager::component_imports(&bytes)?);
+        // The composer names instances after file stems, which must be kebab case.
+        let path = work_dir.join(format!("dependency{}.wasm", definitions.len()));
+        std::fs::write(&path, &bytes)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        definitions.push((dependency, path, packager::component_exports(&bytes)?));
+    }
+
+    for (dependency, _, exports) in &definitions {
+        if !exports.iter().any(|export| wanted.contains(export)) {
+            anyhow::bail!(
+                "Dependency '{}' exports none of the imported interfaces (it exports: {})",
+                dependency.name,
+                if exports.is_empty() {
+                    "nothing".to_string()
+                } else {
+                    exports.join(", ")
+                }
+            );
+        }
+    }
+
+    let root = work_dir.join("root.wasm");
+    std::fs::write(&root, component)
+        .with_context(|| format!("Failed to write {}", root.display()))?;
+    let config = Config {
+        dir: work_dir.to_path_buf(),
+        definitions: definitions.into_iter().map(|(_, path, _)| path).collect(),
+        ..Default::default()
+    };
+    ComponentComposer::new(&root, &config)
+        .compose()
+        .context("Failed to compose the component with its dependencies")
+}
@@ -1,30 +1,57 @@
-﻿use anyhow::{Context, Result};
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
+use crate::adapter::Adapter;
+use crate::{digest, sbom, signing, timeutil, wasmopt};
+
 /// Project configuration structure.
 /// Maps to vtx.toml in the project root.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 pub struct ProjectConfig {
     pub vtx_version: Option<u32>,
     pub project: ProjectInfo,
     pub build: Option<BuildConfig>,
+    pub sdk: Option<SdkConfig>,
+    pub package: Option<PackageConfig>,
+    pub assets: Option<AssetsConfig>,
+    pub i18n: Option<I18nConfig>,
+    pub settings: Option<SettingsConfig>,
+    pub capabilities: Option<CapabilitiesConfig>,
+    pub registry: Option<RegistryConfig>,
+    pub dev: Option<DevConfig>,
+    pub hooks: Option<HooksConfig>,
+    pub test: Option<TestConfig>,
+    pub bench: Option<BenchConfig>,
+
+    /// Components composed into the plugin, keyed by a name used in messages.
+    pub dependencies: Option<BTreeMap<String, DependencyConfig>>,
+
+    /// Named build profiles selected with `vtx build --profile <name>`.
+    pub profiles: Option<BTreeMap<String, ProfileConfig>>,
 }
 
 /// Project author information.
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
 pub struct ProjectAuthor {
     pub name: Option<String>,
     pub email: Option<String>,
 }
 
 /// Base project metadata.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
 pub struct ProjectInfo {
     /// Plugin package name used for identification and artifact naming.
     pub name: String,
 
+    /// Directory containing the sources to build, relative to vtx.toml.
+    /// Builders run from this directory. Defaults to the vtx.toml directory.
+    pub path: Option<String>,
+
     /// Plugin version declared by the author.
     pub version: Option<String>,
 
@@ -55,7 +82,7 @@ pub struct ProjectInfo {
 }
 
 /// Build configuration.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default)]
 pub struct BuildConfig {
     /// Custom build command to override default build logic.
     pub cmd: Option<String>,
@@ -65,8 +92,451 @@ pub struct BuildConfig {
 
     /// Exact artifact file name.
     pub artifact: Option<String>,
+
+    /// Thresholds applied when comparing against the previous local build.
+    pub compare: Option<CompareConfig>,
+
+    /// Restrictions on the WASI interfaces the artifact may import.
+    pub wasi: Option<WasiConfig>,
+
+    /// Accept modules using wasm threads (shared memory, atomics). Defaults to false.
+    pub allow_threads: Option<bool>,
+
+    /// Build without SIMD instructions for kernels on hardware without SIMD support.
+    /// Applies to Rust (RUSTFLAGS), TinyGo and C/C++ builds. Defaults to false.
+    pub no_simd: Option<bool>,
+
+    /// Trim zero padding from data segments and pool identical passive segments. Defaults to false.
+    pub optimize_data: Option<bool>,
+
+    /// Binaryen `wasm-opt` pass over the core module before encoding.
+    pub optimize: Option<OptimizeConfig>,
+
+    /// Move DWARF sections into a `<artifact>.vtx.dbg` file. Defaults to false.
+    pub split_debuginfo: Option<bool>,
+
+    /// WASI preview1 adapter: "reactor" (default), "command", "proxy", or a
+    /// path to an adapter `.wasm` relative to vtx.toml.
+    pub adapter: Option<String>,
+
+    /// Produce byte-identical artifacts from identical inputs. Defaults to false.
+    pub reproducible: Option<bool>,
+
+    /// Sources and compiler flags for C/C++ projects.
+    pub c: Option<CConfig>,
+
+    /// Environment variables set for the toolchain and `build.cmd`, e.g.
+    /// `{ RUSTFLAGS = "-C opt-level=s" }`. `--env KEY=VAL` overrides a key.
+    pub env: Option<BTreeMap<String, String>>,
+
+    /// Features enabled for the build: `cargo --features` for Rust, build tags
+    /// for TinyGo, `-D` defines for C/C++. Every toolchain and `build.cmd` also
+    /// sees them as `VTX_FEATURES`. `--feature` replaces the list.
+    pub features: Option<Vec<String>>,
+
+    /// Arguments appended to the default toolchain invocation, e.g.
+    /// `["-scheduler=none", "-gc=leaking"]` for TinyGo. Not applied to `build.cmd`.
+    pub extra_args: Option<Vec<String>>,
+
+    /// Build service URL. The project source is compiled there instead of by the
+    /// local toolchain; `--remote` overrides it.
+    pub remote: Option<String>,
+
+    /// Hidden files or directories the remote build uploads anyway, relative to
+    /// the project path, e.g. `[".cargo"]`. Every other dotfile stays local.
+    pub remote_include: Option<Vec<String>>,
+}
+
+/// Build profile (`[profiles.<name>]`).
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct ProfileConfig {
+    /// Same as `--force` when true.
+    pub force: Option<bool>,
+
+    /// Same as `--debug` when true.
+    pub debug: Option<bool>,
+
+    /// `[build]` keys overriding the base build settings. Nested tables such as
+    /// `optimize` merge key by key; arrays replace the base value.
+    #[serde(flatten)]
+    pub build: BuildConfig,
+}
+
+/// C/C++ build settings.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
+pub struct CConfig {
+    /// Source files or directories, relative to the project path. Directories are
+    /// searched recursively for .c, .cc, .cpp and .cxx files. Defaults to ["src"].
+    pub sources: Option<Vec<String>>,
+
+    /// Extra arguments passed to clang verbatim, e.g. ["-Iinclude", "-DNDEBUG"].
+    pub flags: Option<Vec<String>>,
+}
+
+/// `wasm-opt` settings. Requires Binaryen's `wasm-opt` on PATH.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default)]
+pub struct OptimizeConfig {
+    /// Optimization level: 0-4, s or z. Defaults to "s".
+    pub level: Option<String>,
+
+    /// Extra arguments passed to wasm-opt verbatim.
+    pub args: Option<Vec<String>>,
+}
+
+/// WASI capability policy.
+///
+/// Capabilities: args, environ, clocks, random, stdio, filesystem, poll, proc, sockets.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default)]
+pub struct WasiConfig {
+    /// Exhaustive list of permitted capabilities. Unset means all are permitted.
+    pub allow: Option<Vec<String>>,
+
+    /// Capabilities that are always rejected.
+    pub deny: Option<Vec<String>>,
+}
+
+/// Post-build comparison thresholds.
+///
+/// Each threshold is optional; an unset threshold never fails the build.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default)]
+pub struct CompareConfig {
+    /// Maximum allowed artifact growth in bytes.
+    pub max_growth_bytes: Option<u64>,
+
+    /// Maximum allowed artifact growth in percent of the previous size.
+    pub max_growth_percent: Option<f64>,
+
+    /// Fail when the export surface differs from the previous build.
+    pub deny_export_changes: Option<bool>,
+}
+
+/// SDK requirements.
+///
+/// Applies to every language. When `version` is set, it is checked against the
+/// SDK version bundled with the CLI, in addition to any version auto-detected
+/// from the language manifest (e.g. Cargo.toml).
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default)]
+pub struct SdkConfig {
+    /// Required SDK version (e.g. "0.1.8").
+    pub version: Option<String>,
+
+    /// SDK release channel: stable, beta or nightly.
+    pub channel: Option<String>,
+
+    /// Pinned WASI preview1 adapter version (e.g. "40.0.0").
+    pub adapter: Option<String>,
+
+    /// `vtx:api` release line of the target kernel (e.g. "0.1"), which decides
+    /// the accepted Wasm proposals. Defaults to the line of the bundled SDK.
+    pub kernel_api: Option<String>,
+}
+
+/// Static files shipped with the plugin, declared in `vtx-assets.json`.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default)]
+pub struct AssetsConfig {
+    /// Asset root relative to vtx.toml. Defaults to "assets".
+    pub dir: Option<String>,
+
+    /// Maximum size of a single asset in bytes.
+    pub max_file_bytes: Option<u64>,
+
+    /// Maximum combined size of all assets in bytes.
+    pub max_total_bytes: Option<u64>,
+}
+
+/// Localized string catalogs embedded in the artifact metadata.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default)]
+pub struct I18nConfig {
+    /// Directory holding `<locale>.json` catalogs, relative to vtx.toml. Defaults to "locales".
+    pub dir: Option<String>,
+
+    /// Locales shipped with the plugin (e.g. ["en", "zh"]).
+    pub locales: Vec<String>,
+
+    /// Locales that must be present. Defaults to the marketplace set: en, zh.
+    pub required: Option<Vec<String>>,
+}
+
+/// User-facing plugin settings, described by a JSON Schema.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct SettingsConfig {
+    /// Path to the settings JSON Schema, relative to vtx.toml.
+    pub schema: String,
+
+    /// Optional JSON file with default settings, validated against the schema.
+    pub defaults: Option<String>,
+}
+
+/// Permissions the plugin requests from the kernel, embedded in the artifact
+/// metadata so the kernel can enforce them before instantiating the plugin.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct CapabilitiesConfig {
+    /// Kernel permissions: sql, ffmpeg, stream-io, context, event-bus, network, filesystem.
+    pub permissions: Vec<String>,
+
+    /// Hosts the plugin may connect to: `example.com`, `*.example.com`, optionally with `:port`.
+    pub network: Vec<String>,
+
+    /// Absolute paths the plugin may access; a trailing `/**` grants the whole subtree.
+    pub filesystem: Vec<String>,
+
+    /// Event topics the plugin subscribes to.
+    pub subscriptions: Vec<String>,
+}
+
+/// Registry used by `vtx publish` and `vtx pull`.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct RegistryConfig {
+    /// Base URL of the registry, e.g. "https://registry.example.com"; `file://`,
+    /// `s3://` and `oci://` URLs select other storage backends.
+    /// Overridden by the `VTX_REGISTRY_URL` environment variable.
+    pub url: Option<String>,
+}
+
+/// Development kernel used by `vtx dev` and the local host of `vtx serve`.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct DevConfig {
+    /// Admin API base URL of the local kernel, e.g. "http://127.0.0.1:8080".
+    /// Overridden by `VTX_KERNEL_URL` and `vtx dev --kernel-url`.
+    pub kernel_url: Option<String>,
+
+    /// Identities `vtx serve` attaches to requests.
+    pub auth: Option<DevAuthConfig>,
+
+    /// Request rate and quota `vtx serve` holds the plugin to.
+    pub limits: Option<DevLimitsConfig>,
+}
+
+/// Simulated kernel rate limiting of `vtx serve`: requests over either
+/// limit answer 429 without reaching the plugin.
+#[derive(Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct DevLimitsConfig {
+    /// Sustained requests per minute, e.g. 60.
+    pub requests_per_minute: Option<u32>,
+
+    /// Requests allowed at once before the rate applies; defaults to
+    /// `requests_per_minute`.
+    pub burst: Option<u32>,
+
+    /// Requests allowed per `quota_window`, e.g. 1000.
+    pub quota: Option<u64>,
+
+    /// Length of a quota window as a duration such as "1h" or "1d"
+    /// (default "1d").
+    pub quota_window: Option<String>,
+}
+
+/// Simulated request authentication of `vtx serve`. A request picks a user
+/// with the `X-Vtx-User` header, else gets `default_user`.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct DevAuthConfig {
+    /// User attached to requests that name none, e.g. "alice"; without it
+    /// they are anonymous.
+    pub default_user: Option<String>,
+
+    /// Pass the request and user headers to the plugin's `authenticate`
+    /// export and attach the user it returns, as for an auth provider,
+    /// instead of attaching the configured identity directly.
+    pub authenticate: bool,
+
+    /// Users by name.
+    pub users: BTreeMap<String, DevUserConfig>,
+}
+
+/// One simulated user of `[dev.auth]`.
+#[derive(Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct DevUserConfig {
+    /// Kernel user id, e.g. "u-1001".
+    pub user_id: String,
+
+    /// Display name; defaults to the user's key.
+    pub username: Option<String>,
+
+    /// Groups (roles) of the user, e.g. ["admin"].
+    #[serde(default)]
+    pub groups: Vec<String>,
+
+    /// Headers added to each request of this user, e.g.
+    /// { authorization = "Bearer ..." }. Without an authorization header an
+    /// unsigned mock JWT carrying the identity is added.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// Host environment of `vtx test`; fixtures and flags can override each field.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default)]
+pub struct TestConfig {
+    /// Freeze the plugin's wall clock at this RFC 3339 UTC time, e.g.
+    /// "2024-01-01T00:00:00Z"; the monotonic clock is then frozen at zero.
+    pub time: Option<String>,
+
+    /// Seed the plugin's random number generators, e.g. 42.
+    pub seed: Option<u64>,
+}
+
+/// Measurements of `vtx bench` and how far they may regress from
+/// bench-baseline.json under `--check`.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default)]
+pub struct BenchConfig {
+    /// Calls of `handle` measured per run (default 200); `--iterations`
+    /// overrides it.
+    pub iterations: Option<u32>,
+
+    /// Fresh instantiations timed for the cold start (default 5).
+    pub cold_starts: Option<u32>,
+
+    pub tolerance: Option<BenchToleranceConfig>,
+}
+
+/// Allowed growth of each metric over the baseline, in percent.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default)]
+pub struct BenchToleranceConfig {
+    /// Median cold start (default 25).
+    pub cold_start_percent: Option<f64>,
+
+    /// 99th percentile latency of `handle` (default 25).
+    pub p99_percent: Option<f64>,
+
+    /// Linear memory allocated by the plugin (default 10).
+    pub memory_percent: Option<f64>,
+}
+
+/// Shell commands run around `vtx build` and `vtx package`, in order.
+/// A failing command stops the pipeline.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Before compiling, e.g. code generation. Run by `vtx build`.
+    pub pre_build: Vec<String>,
+
+    /// After compiling, before packaging; `VTX_WASM_PATH` names the module. Run by `vtx build`.
+    pub post_build: Vec<String>,
+
+    /// After the `.vtx` is written; `VTX_ARTIFACT_PATH` and `VTX_METADATA_PATH` name it and its
+    /// metadata. Run by `vtx build` and `vtx package`.
+    pub post_package: Vec<String>,
+}
+
+/// A component composed into the plugin (`[dependencies]`).
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct DependencyConfig {
+    /// A `.vtx` artifact or `.wasm` component, relative to vtx.toml.
+    pub path: String,
+}
+
+/// Output artifact settings.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default)]
+pub struct PackageConfig {
+    /// File name template for the `.vtx` artifact, e.g. "{name}-{version}-{target}-{profile}.vtx".
+    /// Placeholders: name, version, target, profile. Defaults to the input name with `.vtx`.
+    pub artifact_name: Option<String>,
+
+    /// Directory the `.vtx` artifact is written to, relative to the project path; created if
+    /// missing. Defaults to the directory of the built `.wasm`.
+    pub out_dir: Option<String>,
+
+    /// Algorithm for the artifact and metadata digests in the ledger and the
+    /// capabilities and asset section digests: "sha256" (default) or "sha512".
+    pub digest: Option<String>,
+
+    /// PKCS#8 ed25519 or ecdsa-p256 key (from `vtx keygen`) used to sign every artifact.
+    /// Overridden by the `VTX_SIGNING_KEY` environment variable.
+    pub signing_key: Option<String>,
+
+    /// Public key `vtx verify` checks against: base64, or a path to a `.pub` file.
+    pub public_key: Option<String>,
+
+    /// Signature algorithms `vtx verify` accepts, e.g. ["ed25519"]. Signatures in any
+    /// other algorithm fail verification. Defaults to all: "ed25519" and "ecdsa-p256".
+    pub signature_algorithms: Option<Vec<String>>,
+
+    /// Compress the payload section: "zstd" or "zstd:<level>" (1-22).
+    pub compress: Option<String>,
+
+    /// 32-byte AES-256-GCM key file (raw or base64) used to encrypt every payload.
+    /// Overridden by the `VTX_PAYLOAD_KEY` environment variable.
+    pub payload_key: Option<String>,
+
+    /// Write `<artifact>.sha256` and an SBOM next to every artifact:
+    /// `true` (CycloneDX), "cyclonedx" or "spdx".
+    pub sbom: Option<SbomSetting>,
+}
+
+/// `package.sbom`: a switch or an SBOM format name.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(untagged)]
+pub enum SbomSetting {
+    Enabled(bool),
+    Format(String),
+}
+
+impl SbomSetting {
+    /// The SBOM format to write, or `None` when disabled.
+    pub fn format(&self) -> Option<&str> {
+        match self {
+            SbomSetting::Enabled(true) => Some(sbom::DEFAULT_SBOM_FORMAT),
+            SbomSetting::Enabled(false) => None,
+            SbomSetting::Format(format) => Some(format),
+        }
+    }
 }
 
+/// Placeholders accepted by `package.artifact_name`.
+pub const ARTIFACT_NAME_PLACEHOLDERS: &[&str] = &["name", "version", "target", "profile"];
+
+/// Fill an artifact name template. `vars` pairs each placeholder with its value;
+/// a `None` value fails with a message naming the missing setting.
+pub fn render_artifact_name(template: &str, vars: &[(&str, Option<&str>)]) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .map(|i| open + i)
+            .with_context(|| format!("Unclosed '{{' in package.artifact_name '{template}'"))?;
+        let key = &rest[open + 1..close];
+        if !ARTIFACT_NAME_PLACEHOLDERS.contains(&key) {
+            anyhow::bail!(
+                "Unknown placeholder '{{{key}}}' in package.artifact_name (expected one of: {})",
+                ARTIFACT_NAME_PLACEHOLDERS.join(", ")
+            );
+        }
+        match vars.iter().find(|(k, _)| *k == key) {
+            Some((_, Some(value))) => out.push_str(value),
+            _ => anyhow::bail!(
+                "package.artifact_name uses '{{{key}}}', but no {key} is set (e.g. project.version)"
+            ),
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+
+    if out.contains(['/', '\\']) || !out.ends_with(".vtx") {
+        anyhow::bail!(
+            "package.artifact_name must be a plain file name ending in .vtx (got '{out}')"
+        );
+    }
+    Ok(out)
+}
+
+/// Known SDK release channels.
+pub const SDK_CHANNELS: &[&str] = &["stable", "beta", "nightly"];
+
+/// Permissions accepted in `[capabilities] permissions`: the kernel
+/// interfaces of the plugin world plus network and filesystem access.
+pub const CAPABILITY_PERMISSIONS: &[&str] = &[
+    "sql",
+    "ffmpeg",
+    "stream-io",
+    "context",
+    "event-bus",
+    "network",
+    "filesystem",
+];
+
 /// Load and parse vtx.toml from the current directory.
 ///
 /// # Boundaries
@@ -79,10 +549,9 @@ pub fn load() -> Result<ProjectConfig> {
         anyhow::bail!("Configuration file 'vtx.toml' not found in current directory.");
     }
 
-    let content = fs::read_to_string(config_path).context("Failed to read vtx.toml file")?;
+    let content = read_text_file(config_path).context("Failed to read vtx.toml file")?;
 
-    let config: ProjectConfig =
-        toml::from_str(&content).context("Failed to parse vtx.toml content")?;
+    let config: ProjectConfig = parse_toml(&content, "vtx.toml")?;
 
     if let Some(version) = config.vtx_version {
         if version != 1 {
@@ -90,5 +559,404 @@ pub fn load() -> Result<ProjectConfig> {
         }
     }
 
+    if let Some(template) = config
+        .package
+        .as_ref()
+        .and_then(|p| p.artifact_name.as_deref())
+    {
+        // Validate placeholders up front; values are filled in when packaging.
+        let vars: Vec<(&str, Option<&str>)> = ARTIFACT_NAME_PLACEHOLDERS
+            .iter()
+            .map(|k| (*k, Some("x")))
+            .collect();
+        render_artifact_name(template, &vars)?;
+    }
+
+    if let Some(algorithm) = config.package.as_ref().and_then(|p| p.digest.as_deref()) {
+        if !digest::DIGEST_ALGORITHMS.contains(&algorithm) {
+            anyhow::bail!(
+                "Unknown package.digest '{algorithm}' in vtx.toml (expected one of: {})",
+                digest::DIGEST_ALGORITHMS.join(", ")
+            );
+        }
+    }
+
+    if let Some(algorithms) = config
+        .package
+        .as_ref()
+        .and_then(|p| p.signature_algorithms.as_deref())
+    {
+        signing::SignaturePolicy::allowing(algorithms).map_err(|e| {
+            anyhow::anyhow!("Invalid package.signature_algorithms in vtx.toml: {e}")
+        })?;
+    }
+
+    if let Some(format) = config
+        .package
+        .as_ref()
+        .and_then(|p| p.sbom.as_ref())
+        .and_then(SbomSetting::format)
+    {
+        if !sbom::SBOM_FORMATS.contains(&format) {
+            anyhow::bail!(
+                "Unknown package.sbom '{format}' in vtx.toml (expected true or one of: {})",
+                sbom::SBOM_FORMATS.join(", ")
+            );
+        }
+    }
+
+    if let Some(build) = config.build.as_ref() {
+        validate_build(build, "build")?;
+    }
+    for (name, profile) in config.profiles.iter().flatten() {
+        validate_build(&profile.build, &format!("profiles.{name}"))?;
+    }
+
+    if let Some(capabilities) = config.capabilities.as_ref() {
+        validate_capabilities(capabilities)?;
+    }
+
+    for (name, dependency) in config.dependencies.iter().flatten() {
+        let extension = Path::new(&dependency.path)
+            .extension()
+            .and_then(|e| e.to_str());
+        if !matches!(extension, Some("vtx" | "wasm")) {
+            anyhow::bail!(
+                "Invalid dependencies.{name}.path '{}' in vtx.toml (expected a .vtx or .wasm file)",
+                dependency.path
+            );
+        }
+    }
+
+    if let Some(url) = config.dev.as_ref().and_then(|d| d.kernel_url.as_deref()) {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            anyhow::bail!(
+                "Invalid dev.kernel_url '{url}' in vtx.toml (expected an http:// or https:// URL)"
+            );
+        }
+    }
+
+    if let Some(auth) = config.dev.as_ref().and_then(|d| d.auth.as_ref()) {
+        validate_dev_auth(auth)?;
+    }
+    if let Some(limits) = config.dev.as_ref().and_then(|d| d.limits.as_ref()) {
+        validate_dev_limits(limits)?;
+    }
+    if let Some(bench) = config.bench.as_ref() {
+        validate_bench(bench)?;
+    }
+
+    if let Some(hooks) = config.hooks.as_ref() {
+        for (stage, commands) in [
+            ("pre_build", &hooks.pre_build),
+            ("post_build", &hooks.post_build),
+            ("post_package", &hooks.post_package),
+        ] {
+            if commands.iter().any(|cmd| cmd.trim().is_empty()) {
+                anyhow::bail!("Empty command in hooks.{stage} in vtx.toml");
+            }
+        }
+    }
+
+    if let Some(channel) = config.sdk.as_ref().and_then(|s| s.channel.as_deref()) {
+        if !SDK_CHANNELS.contains(&channel) {
+            anyhow::bail!(
+                "Unknown sdk.channel '{channel}' in vtx.toml (expected one of: {})",
+                SDK_CHANNELS.join(", ")
+            );
+        }
+    }
+
     Ok(config)
 }
+
+fn validate_dev_auth(auth: &DevAuthConfig) -> Result<()> {
+    if let Some(user) = auth.default_user.as_deref() {
+        if !auth.users.contains_key(user) {
+            anyhow::bail!(
+                "dev.auth.default_user '{user}' in vtx.toml is not one of dev.auth.users"
+            );
+        }
+    }
+    for (name, user) in &auth.users {
+        if user.user_id.trim().is_empty() {
+            anyhow::bail!("Empty dev.auth.users.{name}.user_id in vtx.toml");
+        }
+        if let Some(header) = user.headers.keys().find(|h| !is_header_name(h)) {
+            anyhow::bail!(
+                "Invalid header name '{header}' in dev.auth.users.{name}.headers in vtx.toml"
+            );
+        }
+    }
+    Ok(())
+}
+
+fn validate_dev_limits(limits: &DevLimitsConfig) -> Result<()> {
+    for (field, value) in [
+        (
+            "requests_per_minute",
+            limits.requests_per_minute.map(u64::from),
+        ),
+        ("burst", limits.burst.map(u64::from)),
+        ("quota", limits.quota),
+    ] {
+        if value == Some(0) {
+            anyhow::bail!("dev.limits.{field} in vtx.toml must be at least 1");
+        }
+    }
+    if limits.burst.is_some() && limits.requests_per_minute.is_none() {
+        anyhow::bail!("dev.limits.burst in vtx.toml needs dev.limits.requests_per_minute");
+    }
+    if let Some(window) = limits.quota_window.as_deref() {
+        if limits.quota.is_none() {
+            anyhow::bail!("dev.limits.quota_window in vtx.toml needs dev.limits.quota");
+        }
+        let window = timeutil::parse_duration(window)
+            .map_err(|e| anyhow::anyhow!("Invalid dev.limits.quota_window in vtx.toml: {e}"))?;
+        if window.is_zero() {
+            anyhow::bail!("dev.limits.quota_window in vtx.toml must be longer than zero");
+        }
+    }
+    Ok(())
+}
+
+fn validate_bench(bench: &BenchConfig) -> Result<()> {
+    for (field, value) in [
+        ("iterations", bench.iterations),
+        ("cold_starts", bench.cold_starts),
+    ] {
+        if value == Some(0) {
+            anyhow::bail!("bench.{field} in vtx.toml must be at least 1");
+        }
+    }
+    if let Some(tolerance) = bench.tolerance.as_ref() {
+        for (field, value) in [
+            ("cold_start_percent", tolerance.cold_start_percent),
+            ("p99_percent", tolerance.p99_percent),
+            ("memory_percent", tolerance.memory_percent),
+        ] {
+            if value.is_some_and(|percent| percent.is_nan() || percent < 0.0) {
+                anyhow::bail!(
+                    "bench.tolerance.{field} in vtx.toml must be a percentage of zero or more"
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether `name` is an HTTP header name (an RFC 9110 token).
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+fn validate_capabilities(capabilities: &CapabilitiesConfig) -> Result<()> {
+    for permission in &capabilities.permissions {
+        if !CAPABILITY_PERMISSIONS.contains(&permission.as_str()) {
+            anyhow::bail!(
+                "Unknown capabilities.permissions '{permission}' in vtx.toml (expected one of: {})",
+                CAPABILITY_PERMISSIONS.join(", ")
+            );
+        }
+    }
+    for host in &capabilities.network {
+        if !is_host_pattern(host) {
+            anyhow::bail!(
+                "Invalid capabilities.network host '{host}' in vtx.toml (expected host, *.host or host:port)"
+            );
+        }
+    }
+    for path in &capabilities.filesystem {
+        let subtree = path.strip_suffix("/**").unwrap_or(path);
+        if !subtree.starts_with('/')
+            || subtree.contains('*')
+            || subtree.contains(char::is_whitespace)
+            || subtree.split('/').any(|segment| segment == "..")
+        {
+            anyhow::bail!(
+                "Invalid capabilities.filesystem path '{path}' in vtx.toml (expected an absolute path without '..', optionally ending in /**)"
+            );
+        }
+    }
+    for topic in &capabilities.subscriptions {
+        if topic.is_empty() || topic.contains(char::is_whitespace) {
+            anyhow::bail!("Invalid capabilities.subscriptions topic '{topic}' in vtx.toml");
+        }
+    }
+    Ok(())
+}
+
+/// `host`, `*.host`, either with an optional `:port`; labels are letters, digits and inner hyphens.
+fn is_host_pattern(pattern: &str) -> bool {
+    let (host, port) = match pattern.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (pattern, None),
+    };
+    if port.is_some_and(|p| !p.parse::<u16>().is_ok_and(|p| p > 0)) {
+        return false;
+    }
+    let host = host.strip_prefix("*.").unwrap_or(host);
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+fn validate_build(build: &BuildConfig, section: &str) -> Result<()> {
+    for key in build.env.iter().flat_map(BTreeMap::keys) {
+        if key.is_empty() || key.contains('=') {
+            anyhow::bail!("Invalid {section}.env key '{key}' in vtx.toml");
+        }
+    }
+    for feature in build.features.iter().flatten() {
+        if feature.is_empty() || feature.contains([',', ' ']) {
+            anyhow::bail!(
+                "Invalid {section}.features entry '{feature}' in vtx.toml (one feature per entry)"
+            );
+        }
+    }
+    if let Some(adapter) = build.adapter.as_deref() {
+        adapter
+            .parse::<Adapter>()
+            .with_context(|| format!("Invalid {section}.adapter in vtx.toml"))?;
+    }
+    if let Some(level) = build.optimize.as_ref().and_then(|o| o.level.as_deref()) {
+        if !wasmopt::OPT_LEVELS.contains(&level) {
+            anyhow::bail!(
+                "Unknown {section}.optimize.level '{level}' in vtx.toml (expected one of: {})",
+                wasmopt::OPT_LEVELS.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Merge `[profiles.<name>]` over `[build]` and return the selected profile.
+///
+/// Keys set in the profile replace the base value; nested tables merge
+/// recursively, so a profile can change `optimize.level` and keep `optimize.args`.
+pub fn apply_profile(config: &mut ProjectConfig, name: &str) -> Result<ProfileConfig> {
+    let profiles = config.profiles.as_ref();
+    let Some(profile) = profiles.and_then(|p| p.get(name)).cloned() else {
+        let defined: Vec<&str> = profiles
+            .into_iter()
+            .flat_map(|p| p.keys().map(String::as_str))
+            .collect();
+        anyhow::bail!(
+            "Unknown profile '{name}' (defined in vtx.toml: {})",
+            if defined.is_empty() {
+                "none".to_string()
+            } else {
+                defined.join(", ")
+            }
+        );
+    };
+
+    let mut merged = toml::Value::try_from(config.build.clone().unwrap_or_default())
+        .context("Failed to merge build profile")?;
+    let overrides =
+        toml::Value::try_from(&profile.build).context("Failed to merge build profile")?;
+    merge_toml(&mut merged, overrides);
+    config.build = Some(
+        merged
+            .try_into()
+            .with_context(|| format!("Invalid build settings in profile '{name}'"))?,
+    );
+    Ok(profile)
+}
+
+fn merge_toml(base: &mut toml::Value, overrides: toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// Load vtx.toml if present.
+///
+/// A missing file yields `None`; a file that exists but is malformed is an error,
+/// so parse failures are never mistaken for "no config".
+pub fn load_optional() -> Result<Option<ProjectConfig>> {
+    if Path::new("vtx.toml").exists() {
+        load().map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Read a UTF-8 text file, dropping a leading byte order mark.
+///
+/// Editors on Windows commonly save files with a BOM, which TOML parsers reject.
+pub fn read_text_file(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path)?;
+    Ok(match content.strip_prefix('\u{feff}') {
+        Some(stripped) => stripped.to_string(),
+        None => content,
+    })
+}
+
+/// Parse TOML content, reporting failures as `file:line:column: message`.
+///
+/// CRLF line endings are accepted by the TOML parser itself; this only makes
+/// the error location precise instead of a generic parse failure.
+pub fn parse_toml<T: DeserializeOwned>(content: &str, file_name: &str) -> Result<T> {
+    toml::from_str(content).map_err(|e| {
+        let message = e.message().trim();
+        match e.span() {
+            Some(span) => {
+                let (line, column) = line_column(content, span.start);
+                anyhow::anyhow!("Failed to parse {file_name}:{line}:{column}: {message}")
+            }
+            None => anyhow::anyhow!("Failed to parse {file_name}: {message}"),
+        }
+    })
+}
+
+/// Convert a byte offset into a 1-based line and column.
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+/// Change the working directory to `project.path`, if configured.
+///
+/// Must be called right after [`load`], while the working directory is still
+/// the one containing vtx.toml. Builders and artifact discovery then operate
+/// relative to the project path.
+pub fn enter_project_dir(config: &ProjectConfig) -> Result<()> {
+    let Some(path) = config.project.path.as_deref() else {
+        return Ok(());
+    };
+
+    let dir = Path::new(path);
+    if !dir.is_dir() {
+        anyhow::bail!(
+            "project.path '{}' in vtx.toml is not a directory",
+            dir.display()
+        );
+    }
+    std::env::set_current_dir(dir)
+        .with_context(|| format!("Failed to enter project.path: {}", dir.display()))?;
+
+    Ok(())
+}
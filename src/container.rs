@@ -0,0 +1,730 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{digest, hex};
+
+/// Metadata key holding the [`PayloadEncoding`] of an encoded payload.
+pub const PAYLOAD_KEY: &str = "payload";
+
+/// Path of the payload key used when no key file is given.
+pub const PAYLOAD_KEY_ENV: &str = "VTX_PAYLOAD_KEY";
+
+/// Compression name written into [`PayloadEncoding::compression`].
+pub const ZSTD: &str = "zstd";
+
+/// Encryption name written into [`PayloadEncoding::encryption`].
+pub const AES_256_GCM_NAME: &str = "aes-256-gcm";
+
+/// Domain prefix of the associated data bound to every encrypted payload.
+const PAYLOAD_AAD: &[u8] = b"vtx-payload-v1\0";
+
+/// Largest decoded payload accepted, so a crafted `size` cannot force a huge
+/// allocation.
+pub const MAX_PAYLOAD_SIZE: u64 = 1 << 30;
+
+/// Largest level `zstd` accepts.
+const MAX_ZSTD_LEVEL: i32 = 22;
+
+/// Container version with a capabilities section between the metadata and
+/// the payload, written only with `--container-sections`; otherwise the
+/// capabilities go into the metadata of a v2 container.
+pub const VTX_VERSION_V3: u8 = 0x03;
+
+/// Metadata key holding the digest of the capabilities section, so the
+/// signatures over the metadata cover it.
+pub const CAPABILITIES_DIGEST_KEY: &str = "capabilities_digest";
+
+/// Container version with an asset section between the capabilities section
+/// and the payload. An empty capabilities section stands for none. Assets
+/// are only bundled with `--container-sections`.
+pub const VTX_VERSION_V4: u8 = 0x04;
+
+/// Metadata key holding the digest of the asset section.
+pub const ASSETS_DIGEST_KEY: &str = "assets_digest";
+
+/// Content encoding of an asset stored as is; every asset has it.
+pub const IDENTITY: &str = "identity";
+
+/// Content encoding of a gzip-compressed asset variant.
+pub const GZIP: &str = "gzip";
+
+/// Content encoding of a brotli-compressed asset variant.
+pub const BROTLI: &str = "br";
+
+/// Extensions of text assets that are stored with `gzip` and `br` variants.
+const COMPRESSIBLE_EXTENSIONS: &[&str] = &["js", "mjs", "css", "svg", "json", "html"];
+
+/// Brotli quality and window used for asset variants: the densest setting,
+/// since compression happens once at package time.
+const BROTLI_QUALITY: u32 = 11;
+const BROTLI_WINDOW: u32 = 22;
+
+/// How the payload section of a v2 container is stored.
+///
+/// Absent for plain payloads. The metadata section itself is never encoded,
+/// so hosts can read it without the key.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct PayloadEncoding {
+    /// `zstd` when the component is compressed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<String>,
+
+    /// `aes-256-gcm` when the payload is encrypted. The stored payload is the
+    /// 12-byte nonce followed by the ciphertext and tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<String>,
+
+    /// Identifies the encryption key: `aes-256-gcm:<first 16 hex digits of SHA-256(key)>`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+
+    /// Size of the decoded component in bytes.
+    pub size: u64,
+}
+
+impl PayloadEncoding {
+    /// The applied codecs, e.g. `zstd + aes-256-gcm`.
+    pub fn codecs(&self) -> String {
+        [self.compression.as_deref(), self.encryption.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+/// Payload compression selected by `--compress zstd[:level]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Zstd(i32),
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, level) = match s.split_once(':') {
+            Some((name, level)) => (name, Some(level)),
+            None => (s, None),
+        };
+        if name != ZSTD {
+            return Err(format!(
+                "unknown compression '{name}' (expected zstd[:level])"
+            ));
+        }
+        let level = match level {
+            None => zstd::DEFAULT_COMPRESSION_LEVEL,
+            Some(level) => level
+                .parse()
+                .ok()
+                .filter(|l| (1..=MAX_ZSTD_LEVEL).contains(l))
+                .ok_or_else(|| format!("zstd level must be 1-{MAX_ZSTD_LEVEL}, got '{level}'"))?,
+        };
+        Ok(Compression::Zstd(level))
+    }
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compression::Zstd(level) => write!(f, "{ZSTD}:{level}"),
+        }
+    }
+}
+
+/// A 256-bit AES-GCM payload key.
+pub struct PayloadKey([u8; 32]);
+
+impl PayloadKey {
+    /// Read a key file: 32 raw bytes, or their base64 encoding.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read payload key {}", path.display()))?;
+        Self::parse(&bytes).with_context(|| format!("Invalid payload key {}", path.display()))
+    }
+
+    /// The key named by `path`, or else by `VTX_PAYLOAD_KEY`; `None` if neither is set.
+    pub fn load(path: Option<&Path>) -> Result<Option<Self>> {
+        match path {
+            Some(path) => Self::from_file(path).map(Some),
+            None => std::env::var_os(PAYLOAD_KEY_ENV)
+                .map(|path| Self::from_file(Path::new(&path)))
+                .transpose(),
+        }
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        let raw = match <[u8; 32]>::try_from(bytes) {
+            Ok(raw) => raw.to_vec(),
+            Err(_) => BASE64
+                .decode(String::from_utf8_lossy(bytes).trim())
+                .context("Key is neither 32 raw bytes nor base64")?,
+        };
+        let key = <[u8; 32]>::try_from(raw.as_slice())
+            .map_err(|_| anyhow::anyhow!("Key must be 32 bytes (got {})", raw.len()))?;
+        Ok(PayloadKey(key))
+    }
+
+    /// Key identifier recorded in [`PayloadEncoding::key_id`].
+    pub fn id(&self) -> String {
+        let digest = Sha256::digest(self.0);
+        format!("{AES_256_GCM_NAME}:{}", hex::encode(&digest[..8]))
+    }
+
+    fn aead(&self) -> LessSafeKey {
+        LessSafeKey::new(
+            UnboundKey::new(&AES_256_GCM, &self.0).expect("32-byte key is valid for AES-256"),
+        )
+    }
+}
+
+/// Associated data for an encrypted payload: the domain prefix followed by
+/// the canonical metadata without its signatures, which are added after
+/// encoding. Editing any other metadata field makes decryption fail.
+fn payload_aad(metadata: &Map<String, Value>) -> Result<Vec<u8>> {
+    let mut metadata = metadata.clone();
+    metadata.remove(crate::signing::SIGNATURE_KEY);
+    metadata.remove(crate::signing::MANIFEST_SIGNATURE_KEY);
+    let mut aad = PAYLOAD_AAD.to_vec();
+    aad.extend(serde_json::to_vec(&metadata)?);
+    Ok(aad)
+}
+
+/// Compress and/or encrypt `component` for the payload section.
+///
+/// Returns the metadata with the [`PayloadEncoding`] recorded under
+/// `payload`, and the stored payload. With neither option both are returned
+/// unchanged. The encrypted payload is bound to the returned metadata.
+pub fn encode_payload(
+    metadata_json: &[u8],
+    component: &[u8],
+    compression: Option<Compression>,
+    key: Option<&PayloadKey>,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    if compression.is_none() && key.is_none() {
+        return Ok((metadata_json.to_vec(), component.to_vec()));
+    }
+    if component.len() as u64 > MAX_PAYLOAD_SIZE {
+        anyhow::bail!(
+            "Component is {} bytes; encoded payloads are limited to {MAX_PAYLOAD_SIZE}",
+            component.len()
+        );
+    }
+    let encoding = PayloadEncoding {
+        compression: compression.map(|_| ZSTD.to_string()),
+        encryption: key.map(|_| AES_256_GCM_NAME.to_string()),
+        key_id: key.map(PayloadKey::id),
+        size: component.len() as u64,
+    };
+    let mut metadata: Map<String, Value> =
+        serde_json::from_slice(metadata_json).context("Metadata is not a JSON object")?;
+    metadata.insert(PAYLOAD_KEY.to_string(), serde_json::to_value(&encoding)?);
+
+    let mut payload = match compression {
+        Some(Compression::Zstd(level)) => {
+            zstd::bulk::compress(component, level).context("zstd compression failed")?
+        }
+        None => component.to_vec(),
+    };
+    if let Some(key) = key {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow::anyhow!("Failed to generate a nonce"))?;
+        key.aead()
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(payload_aad(&metadata)?),
+                &mut payload,
+            )
+            .map_err(|_| anyhow::anyhow!("Payload encryption failed"))?;
+        payload.splice(0..0, nonce);
+    }
+    Ok((serde_json::to_vec(&metadata)?, payload))
+}
+
+/// The payload encoding recorded in `metadata_json`, if any.
+pub fn payload_encoding(metadata_json: &[u8]) -> Result<Option<PayloadEncoding>> {
+    let metadata: Value = serde_json::from_slice(metadata_json).context("Malformed metadata")?;
+    match metadata.get(PAYLOAD_KEY) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => Ok(Some(
+            serde_json::from_value(value.clone()).context("Malformed payload encoding")?,
+        )),
+    }
+}
+
+/// Reverse [`encode_payload`]: decrypt with `key`, then decompress.
+///
+/// `metadata_json` is the metadata section `encoding` was read from; an
+/// encrypted payload only decrypts against the metadata it was sealed with.
+pub fn decode_payload(
+    metadata_json: &[u8],
+    encoding: &PayloadEncoding,
+    stored: &[u8],
+    key: Option<&PayloadKey>,
+) -> Result<Vec<u8>> {
+    if encoding.size > MAX_PAYLOAD_SIZE {
+        anyhow::bail!(
+            "Metadata records a {}-byte payload; the limit is {MAX_PAYLOAD_SIZE}",
+            encoding.size
+        );
+    }
+    let mut payload = stored.to_vec();
+    match encoding.encryption.as_deref() {
+        None => {}
+        Some(AES_256_GCM_NAME) => {
+            let recorded = encoding.key_id.as_deref().unwrap_or("unknown key");
+            let key = key.with_context(|| {
+                format!(
+                    "Payload is encrypted with {recorded}; pass --key-file or set {PAYLOAD_KEY_ENV}"
+                )
+            })?;
+            if payload.len() < NONCE_LEN {
+                anyhow::bail!("Encrypted payload is truncated");
+            }
+            let ciphertext = payload.split_off(NONCE_LEN);
+            let nonce = Nonce::try_assume_unique_for_key(&payload)
+                .map_err(|_| anyhow::anyhow!("Encrypted payload has a malformed nonce"))?;
+            let metadata: Map<String, Value> =
+                serde_json::from_slice(metadata_json).context("Metadata is not a JSON object")?;
+            let mut plaintext = ciphertext;
+            let len = key
+                .aead()
+                .open_in_place(nonce, Aad::from(payload_aad(&metadata)?), &mut plaintext)
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "Payload decryption failed: wrong key ({} given, {recorded} expected) or modified payload or metadata",
+                        key.id()
+                    )
+                })?
+                .len();
+            plaintext.truncate(len);
+            payload = plaintext;
+        }
+        Some(other) => anyhow::bail!("Unsupported payload encryption '{other}'"),
+    }
+    match encoding.compression.as_deref() {
+        None => {}
+        Some(ZSTD) => {
+            // The frame header must declare the size the metadata records;
+            // a mismatch is rejected before anything is allocated.
+            match zstd::zstd_safe::get_frame_content_size(&payload) {
+                Err(_) => anyhow::bail!("Payload is not a zstd frame"),
+                Ok(None) => anyhow::bail!("zstd frame does not declare its content size"),
+                Ok(Some(declared)) if declared != encoding.size => anyhow::bail!(
+                    "zstd frame declares {declared} bytes, metadata records {}",
+                    encoding.size
+                ),
+                Ok(Some(_)) => {}
+            }
+            payload = zstd::bulk::decompress(&payload, encoding.size as usize)
+                .context("zstd decompression failed")?;
+        }
+        Some(other) => anyhow::bail!("Unsupported payload compression '{other}'"),
+    }
+    if payload.len() as u64 != encoding.size {
+        anyhow::bail!(
+            "Decoded payload is {} bytes, metadata records {}",
+            payload.len(),
+            encoding.size
+        );
+    }
+    Ok(payload)
+}
+
+/// The sections of a `.vtx` container as stored.
+pub struct Sections<'a> {
+    pub version: u8,
+    pub metadata: Option<&'a [u8]>,
+    /// Capabilities JSON; only v3 and v4 containers have the section.
+    pub capabilities: Option<&'a [u8]>,
+    /// The asset section written by [`encode_assets`]; only v4 containers have it.
+    pub assets: Option<&'a [u8]>,
+    /// The payload section, still compressed or encrypted.
+    pub component: &'a [u8],
+}
+
+/// Encode a container: v2 with neither `capabilities_json` nor `assets`, v3
+/// with capabilities only, v4 with an asset section.
+///
+/// The v3 layout is `VTX\x03`, the metadata length (u32 LE), the metadata
+/// JSON, the capabilities length (u32 LE), the capabilities JSON and the
+/// payload. v4 (`VTX\x04`) adds the asset length (u32 LE) and the asset
+/// section before the payload, and writes an empty capabilities section when
+/// there are none.
+pub fn encode(
+    component: &[u8],
+    metadata_json: &[u8],
+    capabilities_json: Option<&[u8]>,
+    assets: Option<&[u8]>,
+) -> Vec<u8> {
+    let (version, sections) = match (capabilities_json, assets) {
+        (None, None) => return vtx_format::encode_v2(component, metadata_json),
+        (Some(capabilities), None) => (VTX_VERSION_V3, vec![metadata_json, capabilities]),
+        (capabilities, Some(assets)) => (
+            VTX_VERSION_V4,
+            vec![metadata_json, capabilities.unwrap_or_default(), assets],
+        ),
+    };
+    let size = sections.iter().map(|s| 4 + s.len()).sum::<usize>() + 4 + component.len();
+    let mut out = Vec::with_capacity(size);
+    out.extend_from_slice(&vtx_format::VTX_PREFIX);
+    out.push(version);
+    for section in sections {
+        out.extend_from_slice(&(section.len() as u32).to_le_bytes());
+        out.extend_from_slice(section);
+    }
+    out.extend_from_slice(component);
+    out
+}
+
+/// Split a v1, v2, v3 or v4 container into its sections without decoding
+/// the payload.
+pub fn decode_sections(bytes: &[u8]) -> Result<Sections<'_>> {
+    let version = match bytes {
+        [a, b, c, version, ..] if [*a, *b, *c] == vtx_format::VTX_PREFIX => *version,
+        _ => 0,
+    };
+    if version != VTX_VERSION_V3 && version != VTX_VERSION_V4 {
+        let decoded = vtx_format::decode_with_metadata(bytes)?;
+        return Ok(Sections {
+            version: decoded.version,
+            metadata: decoded.metadata,
+            capabilities: None,
+            assets: None,
+            component: decoded.component,
+        });
+    }
+    let (metadata, rest) = length_prefixed(&bytes[4..], version, "metadata")?;
+    let (capabilities, rest) = length_prefixed(rest, version, "capabilities")?;
+    if version == VTX_VERSION_V3 {
+        return Ok(Sections {
+            version,
+            metadata: Some(metadata),
+            capabilities: Some(capabilities),
+            assets: None,
+            component: rest,
+        });
+    }
+    let (assets, component) = length_prefixed(rest, version, "assets")?;
+    Ok(Sections {
+        version,
+        metadata: Some(metadata),
+        capabilities: (!capabilities.is_empty()).then_some(capabilities),
+        assets: Some(assets),
+        component,
+    })
+}
+
+/// Split a u32 LE length-prefixed section off the front of `bytes`.
+fn length_prefixed<'a>(
+    bytes: &'a [u8],
+    version: u8,
+    section: &str,
+) -> Result<(&'a [u8], &'a [u8])> {
+    let Some((len, rest)) = bytes.split_first_chunk::<4>() else {
+        anyhow::bail!("vtx v{version} {section} length is truncated");
+    };
+    let len = u32::from_le_bytes(*len) as usize;
+    if len > rest.len() {
+        anyhow::bail!(
+            "vtx v{version} {section} section declares {len} bytes, {} present",
+            rest.len()
+        );
+    }
+    Ok(rest.split_at(len))
+}
+
+/// Digest recorded under [`CAPABILITIES_DIGEST_KEY`]: `<algorithm>:<hex>`
+/// with one of [`digest::DIGEST_ALGORITHMS`].
+pub fn capabilities_digest(algorithm: &str, capabilities_json: &[u8]) -> Result<String> {
+    digest::digest(algorithm, capabilities_json)
+}
+
+/// Digest recorded under [`ASSETS_DIGEST_KEY`], like [`capabilities_digest`].
+pub fn assets_digest(algorithm: &str, assets: &[u8]) -> Result<String> {
+    digest::digest(algorithm, assets)
+}
+
+/// `sha256:<hex>` of an asset file, as recorded in its [`AssetRecord`].
+fn file_digest(contents: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(&Sha256::digest(contents)))
+}
+
+/// The capabilities section of `sections`, checked against the digest its
+/// metadata records. Fails when either is present without the other.
+pub fn verified_capabilities<'a>(sections: &Sections<'a>) -> Result<Option<&'a [u8]>> {
+    verified_section(
+        sections.metadata,
+        sections.capabilities,
+        CAPABILITIES_DIGEST_KEY,
+        "Capabilities",
+    )
+}
+
+/// The asset section of `sections`, checked against the digest its metadata
+/// records like [`verified_capabilities`].
+pub fn verified_assets<'a>(sections: &Sections<'a>) -> Result<Option<&'a [u8]>> {
+    verified_section(
+        sections.metadata,
+        sections.assets,
+        ASSETS_DIGEST_KEY,
+        "Asset",
+    )
+}
+
+fn verified_section<'a>(
+    metadata: Option<&[u8]>,
+    section: Option<&'a [u8]>,
+    key: &str,
+    label: &str,
+) -> Result<Option<&'a [u8]>> {
+    let recorded = match metadata {
+        Some(metadata) => serde_json::from_slice::<Value>(metadata)
+            .context("Malformed metadata")?
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        None => None,
+    };
+    match (section, recorded) {
+        (None, None) => Ok(None),
+        (Some(section), Some(recorded)) if digest::digest_matches(&recorded, section)? => {
+            Ok(Some(section))
+        }
+        (Some(_), Some(recorded)) => {
+            anyhow::bail!("{label} section was modified (metadata records {recorded})")
+        }
+        (Some(_), None) => anyhow::bail!("{label} section has no digest in the metadata"),
+        (None, Some(_)) => anyhow::bail!(
+            "Metadata records a {key} but the container has no {} section",
+            label.to_lowercase()
+        ),
+    }
+}
+
+/// Index at the front of the asset section: one record per bundled file.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetIndex {
+    /// Records sorted by path.
+    pub files: Vec<AssetRecord>,
+}
+
+/// A bundled asset and the variants stored for it.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct AssetRecord {
+    /// Path relative to the asset root, with `/` separators.
+    pub path: String,
+
+    /// Size of the file in bytes.
+    pub size: u64,
+
+    /// `sha256:<hex>` of the file.
+    pub digest: String,
+
+    /// Stored variants by content encoding: always `identity`, plus `gzip`
+    /// and `br` for js, mjs, css, svg, json and html files when they are
+    /// smaller. Kernels can serve a variant directly as `Content-Encoding`.
+    pub encodings: BTreeMap<String, AssetBlob>,
+}
+
+/// Location of one stored variant in the data following the index.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetBlob {
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// An asset section split into its index and data.
+pub struct AssetBundle<'a> {
+    pub index: AssetIndex,
+    data: &'a [u8],
+}
+
+impl<'a> AssetBundle<'a> {
+    /// The stored `encoding` variant of the asset at `path`.
+    pub fn get(&self, path: &str, encoding: &str) -> Option<&'a [u8]> {
+        let record = self.index.files.iter().find(|f| f.path == path)?;
+        let blob = record.encodings.get(encoding)?;
+        Some(&self.data[blob.offset as usize..(blob.offset + blob.size) as usize])
+    }
+
+    /// Combined size of the stored variants.
+    pub fn stored_size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+/// Encode `(path, contents)` pairs as an asset section: the index length
+/// (u32 LE), the [`AssetIndex`] JSON, then every stored variant back to back.
+///
+/// Compressible types are pre-compressed with gzip and brotli at package
+/// time, so kernels can serve them without spending CPU per request.
+pub fn encode_assets(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut files: Vec<&(String, Vec<u8>)> = files.iter().collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut index = AssetIndex::default();
+    let mut data = Vec::new();
+    for (path, contents) in files {
+        let mut variants = vec![(IDENTITY, contents.clone())];
+        if is_compressible(path) {
+            for (encoding, compressed) in [(GZIP, gzip(contents)?), (BROTLI, brotli(contents)?)] {
+                if compressed.len() < contents.len() {
+                    variants.push((encoding, compressed));
+                }
+            }
+        }
+        let mut encodings = BTreeMap::new();
+        for (encoding, bytes) in variants {
+            let blob = AssetBlob {
+                offset: data.len() as u64,
+                size: bytes.len() as u64,
+            };
+            data.extend(bytes);
+            encodings.insert(encoding.to_string(), blob);
+        }
+        index.files.push(AssetRecord {
+            path: path.clone(),
+            size: contents.len() as u64,
+            digest: file_digest(contents),
+            encodings,
+        });
+    }
+    let index = serde_json::to_vec(&index)?;
+    let mut section = Vec::with_capacity(4 + index.len() + data.len());
+    section.extend_from_slice(&(index.len() as u32).to_le_bytes());
+    section.extend(index);
+    section.extend(data);
+    Ok(section)
+}
+
+/// Split an asset section into its index and data, checking that every path
+/// stays below the asset root, every variant lies within the data and every
+/// `identity` variant matches the recorded size and digest.
+pub fn decode_assets(section: &[u8]) -> Result<AssetBundle<'_>> {
+    let (index, data) = length_prefixed(section, VTX_VERSION_V4, "asset index")?;
+    let index: AssetIndex = serde_json::from_slice(index).context("Malformed asset index")?;
+    for record in &index.files {
+        let relative = !record.path.is_empty()
+            && Path::new(&record.path)
+                .components()
+                .all(|c| matches!(c, std::path::Component::Normal(_)));
+        if !relative {
+            anyhow::bail!("Asset path '{}' leaves the asset root", record.path);
+        }
+        for (encoding, blob) in &record.encodings {
+            let in_bounds = blob
+                .offset
+                .checked_add(blob.size)
+                .is_some_and(|end| end <= data.len() as u64);
+            if !in_bounds {
+                anyhow::bail!(
+                    "Asset {} ({encoding}) lies outside the asset section",
+                    record.path
+                );
+            }
+        }
+        let identity = record
+            .encodings
+            .get(IDENTITY)
+            .with_context(|| format!("Asset {} has no {IDENTITY} variant", record.path))?;
+        let start = identity.offset as usize;
+        let contents = &data[start..start + identity.size as usize];
+        if identity.size != record.size || file_digest(contents) != record.digest {
+            anyhow::bail!("Asset {} does not match its recorded digest", record.path);
+        }
+    }
+    Ok(AssetBundle { index, data })
+}
+
+fn is_compressible(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| COMPRESSIBLE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+fn gzip(contents: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(contents)?;
+    encoder.finish().context("gzip compression failed")
+}
+
+fn brotli(contents: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    {
+        let mut encoder =
+            brotli::CompressorWriter::new(&mut out, 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+        encoder
+            .write_all(contents)
+            .context("brotli compression failed")?;
+    }
+    Ok(out)
+}
+
+/// A decoded `.vtx` container with the component restored.
+pub struct Decoded {
+    pub version: u8,
+    pub metadata: Option<Vec<u8>>,
+    /// The capabilities section, verified against the metadata.
+    pub capabilities: Option<Vec<u8>>,
+    /// The asset section, verified against the metadata; read it with [`decode_assets`].
+    pub assets: Option<Vec<u8>>,
+    pub component: Vec<u8>,
+    /// How the payload was stored; `None` for plain payloads.
+    pub encoding: Option<PayloadEncoding>,
+    /// Size of the payload section as stored.
+    pub stored_size: usize,
+}
+
+/// Decode a `.vtx` container, decrypting and decompressing the payload.
+pub fn decode(bytes: &[u8], key: Option<&PayloadKey>) -> Result<Decoded> {
+    let decoded = decode_sections(bytes)?;
+    let capabilities = verified_capabilities(&decoded)?;
+    let assets = verified_assets(&decoded)?;
+    if let Some(section) = assets {
+        decode_assets(section)?;
+    }
+    let encoding = decoded
+        .metadata
+        .map(payload_encoding)
+        .transpose()?
+        .flatten();
+    let component = match (&encoding, decoded.metadata) {
+        (Some(encoding), Some(metadata)) => {
+            decode_payload(metadata, encoding, decoded.component, key)?
+        }
+        _ => decoded.component.to_vec(),
+    };
+    Ok(Decoded {
+        version: decoded.version,
+        metadata: decoded.metadata.map(<[u8]>::to_vec),
+        capabilities: capabilities.map(<[u8]>::to_vec),
+        assets: assets.map(<[u8]>::to_vec),
+        component,
+        encoding,
+        stored_size: decoded.component.len(),
+    })
+}
+
+/// The component in `bytes`: the decoded payload of a `.vtx` container, or
+/// `bytes` unchanged when they are not one (a bare component or module).
+pub fn unwrap_component(bytes: Vec<u8>, key: Option<&PayloadKey>) -> Result<Vec<u8>> {
+    match decode(&bytes, key) {
+        Ok(decoded) => Ok(decoded.component),
+        Err(e) if e.is::<vtx_format::VtxFormatError>() => Ok(bytes),
+        Err(e) => Err(e),
+    }
+}
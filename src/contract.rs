@@ -0,0 +1,433 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+use wit_parser::decoding::{decode, DecodedWasm};
+use wit_parser::{
+    Function, Handle, InterfaceId, Resolve, Type, TypeDefKind, TypeOwner, UnresolvedPackage,
+    WorldId, WorldItem, WorldKey,
+};
+
+/// World every plugin must conform to.
+pub const PLUGIN_WORLD: &str = "vtx:api/plugin";
+
+/// Namespace and package of the kernel interfaces.
+const KERNEL_PACKAGE: (&str, &str) = ("vtx", "api");
+
+/// Check a component against the canonical `vtx:api/plugin` world.
+///
+/// Checks:
+/// 1. Every function exported by the world is exported with the same signature.
+/// 2. Every `vtx:api` interface the component imports exists in the world's
+///    package, and each imported function matches its definition there.
+///
+/// Types are compared structurally (records, variants, enums, flags, tuples,
+/// options, results, lists); resources are matched by name and interface.
+/// Parameter names and extra exports are not checked.
+pub fn check_plugin_world(component_bytes: &[u8]) -> Result<()> {
+    let mut expected = Resolve::default();
+    let package = UnresolvedPackage::parse(Path::new("vtx.wit"), vtx_protocol::WIT_CONTENT)
+        .context("Failed to parse the bundled vtx:api WIT")?;
+    let package = expected.push(package)?;
+    let expected_world = expected.select_world(package, Some("plugin"))?;
+
+    let (actual, actual_world) =
+        match decode(component_bytes).context("Failed to decode the component's WIT world")? {
+            DecodedWasm::Component(resolve, world) => (resolve, world),
+            DecodedWasm::WitPackage(..) => {
+                anyhow::bail!("Contract Violation: input is a WIT package, not a component")
+            }
+        };
+    let checker = Checker {
+        expected: &expected,
+        actual: &actual,
+    };
+
+    checker.check_exports(expected_world, actual_world)?;
+    checker.check_imports(actual_world)?;
+    Ok(())
+}
+
+struct Checker<'a> {
+    expected: &'a Resolve,
+    actual: &'a Resolve,
+}
+
+impl Checker<'_> {
+    fn check_exports(&self, expected_world: WorldId, actual_world: WorldId) -> Result<()> {
+        for (key, item) in &self.expected.worlds[expected_world].exports {
+            let WorldItem::Function(expected) = item else {
+                continue;
+            };
+            let name = self.expected.name_world_key(key);
+            let Some(actual) = self.exported_function(actual_world, &name) else {
+                anyhow::bail!(
+                    "Contract Violation: Missing required export '{name}' of {PLUGIN_WORLD}."
+                );
+            };
+            self.check_function(expected, actual).map_err(|detail| {
+                anyhow::anyhow!(
+                    "Contract Violation: export '{name}' does not match {PLUGIN_WORLD}: {detail}"
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// A world-level export, or the same function inside an exported `vtx:api/plugin` interface.
+    fn exported_function(&self, world: WorldId, name: &str) -> Option<&Function> {
+        self.actual.worlds[world]
+            .exports
+            .iter()
+            .find_map(|(key, item)| match (key, item) {
+                (WorldKey::Name(n), WorldItem::Function(f)) if n == name => Some(f),
+                (WorldKey::Interface(id), WorldItem::Interface(_))
+                    if kernel_interface(self.actual, *id) == Some("plugin") =>
+                {
+                    self.actual.interfaces[*id].functions.get(name)
+                }
+                _ => None,
+            })
+    }
+
+    fn check_imports(&self, actual_world: WorldId) -> Result<()> {
+        for (key, item) in &self.actual.worlds[actual_world].imports {
+            let WorldItem::Interface(actual_id) = item else {
+                continue;
+            };
+            let Some(interface_name) = kernel_interface(self.actual, *actual_id) else {
+                continue;
+            };
+            let import = self.actual.name_world_key(key);
+            let Some(expected_id) = self.expected.interfaces.iter().find_map(|(id, _)| {
+                (kernel_interface(self.expected, id) == Some(interface_name)).then_some(id)
+            }) else {
+                anyhow::bail!(
+                    "Contract Violation: import '{import}' is not an interface of the vtx:api package"
+                );
+            };
+            let expected_functions = &self.expected.interfaces[expected_id].functions;
+            for (name, actual) in &self.actual.interfaces[*actual_id].functions {
+                let Some(expected) = expected_functions.get(name) else {
+                    anyhow::bail!(
+                        "Contract Violation: import '{import}' has no function '{name}' in vtx:api"
+                    );
+                };
+                self.check_function(expected, actual).map_err(|detail| {
+                    anyhow::anyhow!(
+                        "Contract Violation: import '{import}#{name}' does not match vtx:api: {detail}"
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_function(&self, expected: &Function, actual: &Function) -> Result<(), String> {
+        if expected.params.len() != actual.params.len() {
+            return Err(format!(
+                "expected {} parameter(s), found {}",
+                expected.params.len(),
+                actual.params.len()
+            ));
+        }
+        for ((name, expected), (_, actual)) in expected.params.iter().zip(&actual.params) {
+            self.check_type(*expected, *actual)
+                .map_err(|detail| format!("parameter '{name}': {detail}"))?;
+        }
+
+        let expected_results: Vec<&Type> = expected.results.iter_types().collect();
+        let actual_results: Vec<&Type> = actual.results.iter_types().collect();
+        if expected_results.len() != actual_results.len() {
+            return Err(format!(
+                "expected {} result(s), found {}",
+                expected_results.len(),
+                actual_results.len()
+            ));
+        }
+        for (expected, actual) in expected_results.into_iter().zip(actual_results) {
+            self.check_type(*expected, *actual)
+                .map_err(|detail| format!("result: {detail}"))?;
+        }
+        Ok(())
+    }
+
+    /// Structural comparison; the error names the first mismatching item.
+    fn check_type(&self, expected: Type, actual: Type) -> Result<(), String> {
+        let expected = dealias(self.expected, expected);
+        let actual = dealias(self.actual, actual);
+        let mismatch = || {
+            Err(format!(
+                "expected {}, found {}",
+                describe(self.expected, expected),
+                describe(self.actual, actual)
+            ))
+        };
+        let (Type::Id(expected_id), Type::Id(actual_id)) = (expected, actual) else {
+            return if expected == actual {
+                Ok(())
+            } else {
+                mismatch()
+            };
+        };
+
+        let expected_def = &self.expected.types[expected_id];
+        let actual_def = &self.actual.types[actual_id];
+        let scope = expected_def
+            .name
+            .as_deref()
+            .map(|name| format!("{name}: "))
+            .unwrap_or_default();
+        let nested = |label: String, e: Type, a: Type| {
+            self.check_type(e, a)
+                .map_err(|detail| format!("{scope}{label}: {detail}"))
+        };
+
+        match (&expected_def.kind, &actual_def.kind) {
+            (TypeDefKind::Record(e), TypeDefKind::Record(a)) => {
+                let e_names: Vec<&str> = e.fields.iter().map(|f| f.name.as_str()).collect();
+                let a_names: Vec<&str> = a.fields.iter().map(|f| f.name.as_str()).collect();
+                if e_names != a_names {
+                    return Err(format!(
+                        "{scope}expected fields {e_names:?}, found {a_names:?}"
+                    ));
+                }
+                for (e, a) in e.fields.iter().zip(&a.fields) {
+                    nested(format!("field '{}'", e.name), e.ty, a.ty)?;
+                }
+                Ok(())
+            }
+            (TypeDefKind::Variant(e), TypeDefKind::Variant(a)) => {
+                let e_names: Vec<&str> = e.cases.iter().map(|c| c.name.as_str()).collect();
+                let a_names: Vec<&str> = a.cases.iter().map(|c| c.name.as_str()).collect();
+                if e_names != a_names {
+                    return Err(format!(
+                        "{scope}expected cases {e_names:?}, found {a_names:?}"
+                    ));
+                }
+                for (e, a) in e.cases.iter().zip(&a.cases) {
+                    self.check_optional(e.ty, a.ty)
+                        .map_err(|detail| format!("{scope}case '{}': {detail}", e.name))?;
+                }
+                Ok(())
+            }
+            (TypeDefKind::Enum(e), TypeDefKind::Enum(a)) => {
+                let e_names: Vec<&str> = e.cases.iter().map(|c| c.name.as_str()).collect();
+                let a_names: Vec<&str> = a.cases.iter().map(|c| c.name.as_str()).collect();
+                if e_names != a_names {
+                    return Err(format!(
+                        "{scope}expected cases {e_names:?}, found {a_names:?}"
+                    ));
+                }
+                Ok(())
+            }
+            (TypeDefKind::Flags(e), TypeDefKind::Flags(a)) => {
+                let e_names: Vec<&str> = e.flags.iter().map(|f| f.name.as_str()).collect();
+                let a_names: Vec<&str> = a.flags.iter().map(|f| f.name.as_str()).collect();
+                if e_names != a_names {
+                    return Err(format!(
+                        "{scope}expected flags {e_names:?}, found {a_names:?}"
+                    ));
+                }
+                Ok(())
+            }
+            (TypeDefKind::Tuple(e), TypeDefKind::Tuple(a)) => {
+                if e.types.len() != a.types.len() {
+                    return mismatch();
+                }
+                for (index, (e, a)) in e.types.iter().zip(&a.types).enumerate() {
+                    nested(format!("element {index}"), *e, *a)?;
+                }
+                Ok(())
+            }
+            (TypeDefKind::Option(e), TypeDefKind::Option(a)) => {
+                nested("option".to_string(), *e, *a)
+            }
+            (TypeDefKind::List(e), TypeDefKind::List(a)) => nested("list".to_string(), *e, *a),
+            (TypeDefKind::Result(e), TypeDefKind::Result(a)) => {
+                self.check_optional(e.ok, a.ok)
+                    .map_err(|detail| format!("{scope}ok: {detail}"))?;
+                self.check_optional(e.err, a.err)
+                    .map_err(|detail| format!("{scope}err: {detail}"))
+            }
+            (TypeDefKind::Handle(Handle::Own(e)), TypeDefKind::Handle(Handle::Own(a)))
+            | (TypeDefKind::Handle(Handle::Borrow(e)), TypeDefKind::Handle(Handle::Borrow(a))) => {
+                self.check_type(Type::Id(*e), Type::Id(*a))
+            }
+            (TypeDefKind::Resource, TypeDefKind::Resource) => {
+                if resource_path(self.expected, expected_id)
+                    == resource_path(self.actual, actual_id)
+                {
+                    Ok(())
+                } else {
+                    mismatch()
+                }
+            }
+            (TypeDefKind::Future(_), TypeDefKind::Future(_))
+            | (TypeDefKind::Stream(_), TypeDefKind::Stream(_)) => Ok(()),
+            _ => mismatch(),
+        }
+    }
+
+    fn check_optional(&self, expected: Option<Type>, actual: Option<Type>) -> Result<(), String> {
+        match (expected, actual) {
+            (None, None) => Ok(()),
+            (Some(e), Some(a)) => self.check_type(e, a),
+            (Some(e), None) => Err(format!(
+                "expected {}, found no payload",
+                describe(self.expected, e)
+            )),
+            (None, Some(a)) => Err(format!(
+                "expected no payload, found {}",
+                describe(self.actual, a)
+            )),
+        }
+    }
+}
+
+/// Check the `requires` map of an artifact's metadata against a kernel
+/// implementing `vtx:api@<kernel_api>` (`MAJOR.MINOR.PATCH`), without
+/// decoding the component.
+///
+/// Every `vtx:api` interface must be caret-compatible with the kernel: same
+/// major version (same minor below 1.0) and no newer than it. Unversioned
+/// imports (`*`) match any kernel, and `wasi:` interfaces are left to the
+/// kernel's WASI host. Imports from any other package fail, since no kernel
+/// provides them. Returns every mismatch, one per line, in the error.
+pub fn check_requires(requires: &BTreeMap<String, String>, kernel_api: &str) -> Result<()> {
+    let kernel = parse_kernel_api(kernel_api)?;
+    let mismatches = mismatches(requires, &kernel)?;
+    if !mismatches.is_empty() {
+        anyhow::bail!(
+            "Incompatible with vtx:api@{kernel}:\n  {}",
+            mismatches.join("\n  ")
+        );
+    }
+    Ok(())
+}
+
+/// Why a kernel implementing `vtx:api@<kernel_api>` does not provide the
+/// imported interface `import` (`<package>/<name>[@<version>]`), by the rules
+/// of [`check_requires`]; `None` when it does.
+pub fn missing_import(import: &str, kernel_api: &str) -> Result<Option<String>> {
+    let kernel = parse_kernel_api(kernel_api)?;
+    let (interface, version) = import.split_once('@').unwrap_or((import, "*"));
+    let requires = BTreeMap::from([(interface.to_string(), version.to_string())]);
+    Ok(mismatches(&requires, &kernel)?.pop())
+}
+
+/// A kernel API version, `MAJOR.MINOR.PATCH` with an optional `v` or `=`.
+pub fn parse_kernel_api(kernel_api: &str) -> Result<semver::Version> {
+    semver::Version::parse(kernel_api.trim_start_matches(['v', '=']))
+        .map_err(|e| anyhow::anyhow!("Invalid kernel API version '{kernel_api}': {e}"))
+}
+
+/// One line per interface of `requires` that `kernel` does not provide.
+fn mismatches(
+    requires: &BTreeMap<String, String>,
+    kernel: &semver::Version,
+) -> Result<Vec<String>> {
+    let kernel_prefix = format!("{}:{}/", KERNEL_PACKAGE.0, KERNEL_PACKAGE.1);
+
+    let mut mismatches = Vec::new();
+    for (interface, version) in requires {
+        if interface.starts_with("wasi:") {
+            continue;
+        }
+        if !interface.starts_with(&kernel_prefix) {
+            mismatches.push(format!("{interface}: not provided by vtx kernels"));
+            continue;
+        }
+        if version == "*" {
+            continue;
+        }
+        let compatible = semver::VersionReq::parse(&format!("^{version}"))
+            .map_err(|e| {
+                anyhow::anyhow!("Invalid version '{version}' required for {interface}: {e}")
+            })?
+            .matches(kernel);
+        if !compatible {
+            mismatches.push(format!("{interface}@{version}: kernel provides {kernel}"));
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Interface name when `id` belongs to the `vtx:api` package (any version).
+fn kernel_interface(resolve: &Resolve, id: InterfaceId) -> Option<&str> {
+    let interface = &resolve.interfaces[id];
+    let package = &resolve.packages[interface.package?].name;
+    ((package.namespace.as_str(), package.name.as_str()) == KERNEL_PACKAGE)
+        .then_some(interface.name.as_deref())
+        .flatten()
+}
+
+/// Follow `type x = y` aliases, including `use` of types from other interfaces.
+fn dealias(resolve: &Resolve, mut ty: Type) -> Type {
+    while let Type::Id(id) = ty {
+        match resolve.types[id].kind {
+            TypeDefKind::Type(inner) => ty = inner,
+            _ => break,
+        }
+    }
+    ty
+}
+
+/// `<interface>.<resource>` identity of a resource, ignoring the package version.
+fn resource_path(resolve: &Resolve, id: wit_parser::TypeId) -> (Option<String>, Option<String>) {
+    let def = &resolve.types[id];
+    let interface = match def.owner {
+        TypeOwner::Interface(owner) => resolve.interfaces[owner].name.clone(),
+        _ => None,
+    };
+    (interface, def.name.clone())
+}
+
+/// WIT-like spelling of a type for diagnostics.
+fn describe(resolve: &Resolve, ty: Type) -> String {
+    let id = match dealias(resolve, ty) {
+        Type::Id(id) => id,
+        Type::Bool => return "bool".into(),
+        Type::U8 => return "u8".into(),
+        Type::U16 => return "u16".into(),
+        Type::U32 => return "u32".into(),
+        Type::U64 => return "u64".into(),
+        Type::S8 => return "s8".into(),
+        Type::S16 => return "s16".into(),
+        Type::S32 => return "s32".into(),
+        Type::S64 => return "s64".into(),
+        Type::F32 => return "f32".into(),
+        Type::F64 => return "f64".into(),
+        Type::Char => return "char".into(),
+        Type::String => return "string".into(),
+    };
+    let def = &resolve.types[id];
+    let optional = |ty: Option<Type>| ty.map_or("_".to_string(), |t| describe(resolve, t));
+    match &def.kind {
+        TypeDefKind::Option(t) => format!("option<{}>", describe(resolve, *t)),
+        TypeDefKind::List(t) => format!("list<{}>", describe(resolve, *t)),
+        TypeDefKind::Result(r) => format!("result<{}, {}>", optional(r.ok), optional(r.err)),
+        TypeDefKind::Tuple(t) => format!(
+            "tuple<{}>",
+            t.types
+                .iter()
+                .map(|t| describe(resolve, *t))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Handle(Handle::Own(r)) => describe(resolve, Type::Id(*r)),
+        TypeDefKind::Handle(Handle::Borrow(r)) => {
+            format!("borrow<{}>", describe(resolve, Type::Id(*r)))
+        }
+        kind => match &def.name {
+            Some(name) => name.clone(),
+            None => match kind {
+                TypeDefKind::Record(_) => "record".into(),
+                TypeDefKind::Variant(_) => "variant".into(),
+                TypeDefKind::Enum(_) => "enum".into(),
+                TypeDefKind::Flags(_) => "flags".into(),
+                TypeDefKind::Resource => "resource".into(),
+                _ => "type".into(),
+            },
+        },
+    }
+}
@@ -0,0 +1,414 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use wasmparser::{Chunk, DataKind, Operator, Parser as WasmParser, Payload, TypeRef};
+
+use crate::cancel::CancelToken;
+
+/// Segments and strings shorter than this are not worth merging or reporting
+/// as duplicates.
+const MIN_DUPLICATE_BYTES: usize = 16;
+
+const CODE_SECTION_ID: u8 = 10;
+const DATA_SECTION_ID: u8 = 11;
+
+/// Outcome of [`optimize`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataReport {
+    /// Data segments in the module.
+    pub segments: usize,
+
+    /// Zero bytes removed from the edges of active segments.
+    pub trimmed_bytes: u64,
+
+    /// Bytes removed by pointing passive segments at an identical one.
+    pub deduplicated_bytes: u64,
+
+    /// Bytes in segments identical to an earlier segment that stay in place.
+    pub duplicate_segment_bytes: u64,
+
+    /// Bytes in NUL-separated strings repeated across segments.
+    pub repeated_string_bytes: u64,
+
+    /// Why trimming was skipped, if it was.
+    pub skipped: Option<&'static str>,
+}
+
+impl DataReport {
+    /// Data bytes removed from the module.
+    pub fn saved_bytes(&self) -> u64 {
+        self.trimmed_bytes + self.deduplicated_bytes
+    }
+
+    /// One-line summary for the packaging report.
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "{} segments, {} bytes saved ({} zero bytes trimmed, {} bytes in merged segments)",
+            self.segments,
+            self.saved_bytes(),
+            self.trimmed_bytes,
+            self.deduplicated_bytes
+        );
+        if let Some(reason) = self.skipped {
+            out.push_str(&format!(" (trimming skipped: {reason})"));
+        }
+        if self.duplicate_segment_bytes > 0 || self.repeated_string_bytes > 0 {
+            out.push_str(&format!(
+                "; duplicates left in place: {} bytes in identical segments, {} bytes in repeated strings",
+                self.duplicate_segment_bytes, self.repeated_string_bytes
+            ));
+        }
+        out
+    }
+}
+
+struct Segment<'a> {
+    passive: bool,
+    memory_index: u32,
+    offset: Option<u32>,
+    data: &'a [u8],
+}
+
+/// Shrink the data section of a core module and report duplicated data.
+///
+/// Zero bytes at the edges of active segments are dropped and the offset moved
+/// forward, since linear memory starts zeroed. This is only done when the
+/// memory is defined by the module and every active segment has a constant,
+/// non-overlapping offset.
+///
+/// Passive segments identical to another passive segment are pooled: every
+/// `memory.init` of a duplicate is pointed at the kept segment and the
+/// duplicate is emptied. Segment indices are preserved, so `data.drop` of a
+/// duplicate stays valid; the kept segment is one no `data.drop` targets.
+///
+/// Identical active segments and repeated strings are reported but not
+/// merged: code addresses them by absolute offset, and pooling them needs the
+/// linker's relocation information.
+pub fn optimize(module: &[u8], cancel: &CancelToken) -> Result<(Vec<u8>, DataReport)> {
+    let mut segments = Vec::new();
+    let mut imported_memory = false;
+    // Segments `data.drop` empties, and segments GC array instructions read.
+    let mut dropped = HashSet::new();
+    let mut array_data = HashSet::new();
+
+    for payload in WasmParser::new(0).parse_all(module) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    imported_memory |= matches!(import?.ty, TypeRef::Memory(_));
+                }
+            }
+            Payload::DataSection(reader) => {
+                for data in reader {
+                    let data = data?;
+                    segments.push(match data.kind {
+                        DataKind::Passive => Segment {
+                            passive: true,
+                            memory_index: 0,
+                            offset: None,
+                            data: data.data,
+                        },
+                        DataKind::Active {
+                            memory_index,
+                            offset_expr,
+                        } => {
+                            let mut ops = offset_expr.get_operators_reader();
+                            let offset = match (ops.read()?, ops.read()?) {
+                                (Operator::I32Const { value }, Operator::End) => Some(value as u32),
+                                _ => None,
+                            };
+                            Segment {
+                                passive: false,
+                                memory_index,
+                                offset,
+                                data: data.data,
+                            }
+                        }
+                    });
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut ops = body.get_operators_reader()?;
+                while !ops.eof() {
+                    match ops.read()? {
+                        Operator::DataDrop { data_index } => {
+                            dropped.insert(data_index);
+                        }
+                        Operator::ArrayNewData {
+                            array_data_index, ..
+                        }
+                        | Operator::ArrayInitData {
+                            array_data_index, ..
+                        } => {
+                            array_data.insert(array_data_index);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => {
+                anyhow::bail!("Data optimization only applies to core modules")
+            }
+            _ => {}
+        }
+    }
+
+    let mut report = DataReport {
+        segments: segments.len(),
+        ..Default::default()
+    };
+    if segments.is_empty() {
+        return Ok((module.to_vec(), report));
+    }
+    let merged = merge_passive_duplicates(&segments, &dropped, &array_data);
+    report.deduplicated_bytes = merged
+        .keys()
+        .map(|index| segments[*index as usize].data.len() as u64)
+        .sum();
+    count_duplicates(&segments, &merged, &mut report);
+
+    report.skipped = if imported_memory {
+        Some("memory is imported")
+    } else if segments.iter().any(|s| !s.passive && s.offset.is_none()) {
+        Some("non-constant segment offsets")
+    } else if overlapping(&segments) {
+        Some("overlapping segments")
+    } else {
+        None
+    };
+    let section = encode_data_section(&segments, report.skipped.is_none(), &merged, &mut report);
+    if report.saved_bytes() == 0 {
+        return Ok((module.to_vec(), report));
+    }
+    let code = if merged.is_empty() {
+        None
+    } else {
+        Some(encode_remapped_code(module, &merged)?)
+    };
+
+    // Copy every other section verbatim and swap in the rewritten data section.
+    let mut out = Vec::with_capacity(module.len());
+    let mut parser = WasmParser::new(0);
+    let mut offset = 0usize;
+    while offset < module.len() {
+        cancel.check()?;
+        let (consumed, payload) = match parser.parse(&module[offset..], true)? {
+            Chunk::Parsed { consumed, payload } => (consumed, payload),
+            _ => break,
+        };
+        match (&payload, &code) {
+            (Payload::DataSection(_), _) => out.extend_from_slice(&section),
+            (Payload::CodeSectionStart { .. }, Some(code)) => out.extend_from_slice(code),
+            (Payload::CodeSectionEntry(_), Some(_)) => {}
+            _ => out.extend_from_slice(&module[offset..offset + consumed]),
+        }
+        offset += consumed;
+    }
+
+    Ok((out, report))
+}
+
+/// Map each passive segment identical to a kept one onto it.
+///
+/// The kept segment is the first of its contents that no `data.drop`
+/// targets, since `memory.init` of the merged ones would trap once it is
+/// dropped. Segments read by GC array instructions are left alone.
+fn merge_passive_duplicates(
+    segments: &[Segment<'_>],
+    dropped: &HashSet<u32>,
+    array_data: &HashSet<u32>,
+) -> HashMap<u32, u32> {
+    let candidates: Vec<(u32, &[u8])> = segments
+        .iter()
+        .enumerate()
+        .map(|(index, segment)| (index as u32, segment))
+        .filter(|(index, segment)| {
+            segment.passive
+                && segment.data.len() >= MIN_DUPLICATE_BYTES
+                && !array_data.contains(index)
+        })
+        .map(|(index, segment)| (index, segment.data))
+        .collect();
+
+    let mut kept = HashMap::new();
+    for (index, data) in &candidates {
+        if !dropped.contains(index) {
+            kept.entry(*data).or_insert(*index);
+        }
+    }
+    candidates
+        .iter()
+        .filter_map(|(index, data)| {
+            let target = *kept.get(data)?;
+            (target != *index).then_some((*index, target))
+        })
+        .collect()
+}
+
+fn count_duplicates(segments: &[Segment<'_>], merged: &HashMap<u32, u32>, report: &mut DataReport) {
+    let mut seen_segments = HashSet::new();
+    let mut seen_strings = HashSet::new();
+
+    for (index, segment) in segments.iter().enumerate() {
+        if merged.contains_key(&(index as u32)) {
+            continue;
+        }
+        if segment.data.len() >= MIN_DUPLICATE_BYTES && !seen_segments.insert(segment.data) {
+            report.duplicate_segment_bytes += segment.data.len() as u64;
+            continue;
+        }
+        for string in segment.data.split(|b| *b == 0) {
+            if string.len() >= MIN_DUPLICATE_BYTES && !seen_strings.insert(string) {
+                report.repeated_string_bytes += string.len() as u64;
+            }
+        }
+    }
+}
+
+fn overlapping(segments: &[Segment<'_>]) -> bool {
+    let mut ranges: Vec<(u32, u64, u64)> = segments
+        .iter()
+        .filter(|s| !s.passive)
+        .filter_map(|s| {
+            let start = u64::from(s.offset?);
+            Some((s.memory_index, start, start + s.data.len() as u64))
+        })
+        .collect();
+    ranges.sort_unstable();
+    ranges
+        .windows(2)
+        .any(|w| w[0].0 == w[1].0 && w[1].1 < w[0].2)
+}
+
+/// Re-encode the data section, emptying merged segments and, with `trim`,
+/// trimming zero padding from active ones.
+fn encode_data_section(
+    segments: &[Segment<'_>],
+    trim: bool,
+    merged: &HashMap<u32, u32>,
+    report: &mut DataReport,
+) -> Vec<u8> {
+    let mut body = Vec::new();
+    write_u32(&mut body, segments.len() as u32);
+
+    for (index, segment) in segments.iter().enumerate() {
+        if segment.passive {
+            body.push(0x01);
+            if merged.contains_key(&(index as u32)) {
+                write_bytes(&mut body, &[]);
+            } else {
+                write_bytes(&mut body, segment.data);
+            }
+            continue;
+        }
+        if !trim {
+            encode_active(
+                &mut body,
+                segment,
+                segment.offset.unwrap_or_default(),
+                segment.data,
+            );
+            continue;
+        }
+
+        let leading = segment.data.iter().take_while(|b| **b == 0).count();
+        let trailing = segment.data[leading..]
+            .iter()
+            .rev()
+            .take_while(|b| **b == 0)
+            .count();
+        let data = &segment.data[leading..segment.data.len() - trailing];
+        report.trimmed_bytes += (leading + trailing) as u64;
+        let offset = segment
+            .offset
+            .unwrap_or_default()
+            .wrapping_add(leading as u32);
+        encode_active(&mut body, segment, offset, data);
+    }
+
+    let mut section = vec![DATA_SECTION_ID];
+    write_bytes(&mut section, &body);
+    section
+}
+
+fn encode_active(body: &mut Vec<u8>, segment: &Segment<'_>, offset: u32, data: &[u8]) {
+    if segment.memory_index == 0 {
+        body.push(0x00);
+    } else {
+        body.push(0x02);
+        write_u32(body, segment.memory_index);
+    }
+    body.push(0x41); // i32.const
+    write_i32(body, offset as i32);
+    body.push(0x0b); // end
+    write_bytes(body, data);
+}
+
+/// Re-encode the code section with every `memory.init` of a merged segment
+/// pointed at the kept one. Everything else is copied verbatim.
+fn encode_remapped_code(module: &[u8], merged: &HashMap<u32, u32>) -> Result<Vec<u8>> {
+    let mut count = 0u32;
+    let mut bodies = Vec::new();
+    for payload in WasmParser::new(0).parse_all(module) {
+        let Payload::CodeSectionEntry(function) = payload? else {
+            continue;
+        };
+        count += 1;
+        let range = function.range();
+        let mut ops = function.get_operators_reader()?;
+        let mut out = Vec::with_capacity(range.len());
+        let mut copied = range.start;
+        while !ops.eof() {
+            let (op, start) = ops.read_with_offset()?;
+            let Operator::MemoryInit { data_index, mem } = op else {
+                continue;
+            };
+            if let Some(target) = merged.get(&data_index) {
+                out.extend_from_slice(&module[copied..start]);
+                out.push(0xfc);
+                write_u32(&mut out, 8); // memory.init
+                write_u32(&mut out, *target);
+                write_u32(&mut out, mem);
+                copied = ops.original_position();
+            }
+        }
+        out.extend_from_slice(&module[copied..range.end]);
+        write_bytes(&mut bodies, &out);
+    }
+
+    let mut body = Vec::new();
+    write_u32(&mut body, count);
+    body.extend_from_slice(&bodies);
+    let mut section = vec![CODE_SECTION_ID];
+    write_bytes(&mut section, &body);
+    Ok(section)
+}
+
+pub(crate) fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_i32(out: &mut Vec<u8>, mut value: i32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+pub(crate) fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_u32(out, bytes.len() as u32);
+    out.extend_from_slice(bytes);
+}
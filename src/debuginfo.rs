@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use wasmparser::{BinaryReader, Chunk, Parser as WasmParser, Payload};
+
+use crate::cancel::CancelToken;
+use crate::dataopt::write_bytes;
+use crate::hex;
+
+/// Custom section carrying the build id, per the wasm tool conventions.
+pub const BUILD_ID_SECTION: &str = "build_id";
+
+/// Extension of the companion debug artifact, appended to the `.vtx` path.
+pub const DEBUGINFO_EXTENSION: &str = "dbg";
+
+/// Bytes of the generated build id (a truncated SHA-256 of the module).
+const BUILD_ID_LEN: usize = 16;
+
+/// Debug sections moved out of a module by [`split`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugInfo {
+    /// Build id shared by the stripped module and [`DebugInfo::module`].
+    pub build_id: Vec<u8>,
+
+    /// Custom-section-only core module holding the build id and every DWARF section.
+    pub module: Vec<u8>,
+
+    /// Number of DWARF sections moved.
+    pub sections: usize,
+
+    /// Size of the DWARF sections moved (names and contents).
+    pub stripped_bytes: u64,
+}
+
+impl DebugInfo {
+    /// Describe a debug artifact previously produced by [`split`].
+    pub fn parse(module: &[u8]) -> Result<Self> {
+        let mut build_id = None;
+        let mut sections = 0;
+        let mut stripped_bytes = 0;
+        for payload in WasmParser::new(0).parse_all(module) {
+            if let Payload::CustomSection(reader) = payload? {
+                if reader.name() == BUILD_ID_SECTION {
+                    build_id = Some(read_build_id(reader.data())?);
+                } else if reader.name().starts_with(".debug_") {
+                    sections += 1;
+                    stripped_bytes += reader.range().len() as u64;
+                }
+            }
+        }
+        Ok(Self {
+            build_id: build_id.context("Debug artifact has no build_id section")?,
+            module: module.to_vec(),
+            sections,
+            stripped_bytes,
+        })
+    }
+
+    /// Lowercase hex form of the build id.
+    pub fn build_id_hex(&self) -> String {
+        hex::encode(&self.build_id)
+    }
+
+    /// One-line summary for the packaging report.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} DWARF sections ({} bytes) split out, build id {}",
+            self.sections,
+            self.stripped_bytes,
+            self.build_id_hex()
+        )
+    }
+}
+
+/// Move the DWARF (`.debug_*`) custom sections of a core module into a
+/// separate debug module.
+///
+/// Both modules carry the same `build_id` custom section so a kernel can
+/// match a stack trace from the deployed plugin with its debug artifact. An
+/// existing `build_id` (from `wasm-ld --build-id`) is kept; otherwise one is
+/// derived from the module's contents. The `name` section stays in place, so
+/// function names still appear in traces without the debug artifact.
+///
+/// Returns `None` when the module has no DWARF sections.
+pub fn split(module: &[u8], cancel: &CancelToken) -> Result<Option<(Vec<u8>, DebugInfo)>> {
+    let mut stripped = Vec::with_capacity(module.len());
+    let mut debug_sections = Vec::new();
+    let mut build_id = None;
+    let mut stripped_bytes = 0;
+    let mut parser = WasmParser::new(0);
+    let mut offset = 0usize;
+
+    while offset < module.len() {
+        cancel.check()?;
+        let (consumed, payload) = match parser.parse(&module[offset..], true)? {
+            Chunk::Parsed { consumed, payload } => (consumed, payload),
+            Chunk::NeedMoreData(_) => break,
+        };
+        let raw = &module[offset..offset + consumed];
+        offset += consumed;
+
+        if let Payload::CustomSection(reader) = &payload {
+            if reader.name().starts_with(".debug_") {
+                debug_sections.push(raw);
+                stripped_bytes += reader.range().len() as u64;
+                continue;
+            }
+            if reader.name() == BUILD_ID_SECTION {
+                build_id = Some(read_build_id(reader.data())?);
+            }
+        }
+        stripped.extend_from_slice(raw);
+    }
+
+    if debug_sections.is_empty() {
+        return Ok(None);
+    }
+
+    let build_id = match build_id {
+        Some(id) => id,
+        None => {
+            let id = Sha256::digest(module)[..BUILD_ID_LEN].to_vec();
+            stripped.extend(build_id_section(&id));
+            id
+        }
+    };
+
+    let mut debug_module = module[..8].to_vec();
+    debug_module.extend(build_id_section(&build_id));
+    for section in &debug_sections {
+        debug_module.extend_from_slice(section);
+    }
+
+    let info = DebugInfo {
+        build_id,
+        module: debug_module,
+        sections: debug_sections.len(),
+        stripped_bytes,
+    };
+    Ok(Some((stripped, info)))
+}
+
+/// Read the build id of a module, component or debug artifact, if it has one.
+pub fn build_id(module: &[u8]) -> Result<Option<Vec<u8>>> {
+    for payload in WasmParser::new(0).parse_all(module) {
+        if let Payload::CustomSection(reader) = payload? {
+            if reader.name() == BUILD_ID_SECTION {
+                return read_build_id(reader.data()).map(Some);
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn read_build_id(data: &[u8]) -> Result<Vec<u8>> {
+    let mut reader = BinaryReader::new(data);
+    let len = reader.read_var_u32()? as usize;
+    Ok(reader.read_bytes(len)?.to_vec())
+}
+
+fn build_id_section(id: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    write_bytes(&mut body, BUILD_ID_SECTION.as_bytes());
+    write_bytes(&mut body, id);
+    let mut section = vec![0];
+    write_bytes(&mut section, &body);
+    section
+}
@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::warn;
+
+use crate::offline;
+use crate::timeutil::today;
+
+/// Table shipped with this CLI release.
+const EMBEDDED_TABLE: &str = include_str!("deprecations.json");
+
+/// Refreshed copy written by `vtx check --refresh-deprecations`.
+const CACHED_TABLE: &str = ".vtx-cache/deprecations.json";
+
+/// Where the table is fetched from; overridable with `VTX_DEPRECATIONS_URL`.
+const DEFAULT_TABLE_URL: &str =
+    "https://raw.githubusercontent.com/Vtxdeo/vtx-cli/main/src/deprecations.json";
+
+/// Deprecated targets and SDK versions with their end-of-life dates.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DeprecationTable {
+    /// Monotonic revision; a cached table is only used when it is newer than the embedded one.
+    pub revision: u32,
+    #[serde(default)]
+    pub targets: Vec<Deprecation>,
+    #[serde(default)]
+    pub sdk: Vec<Deprecation>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Deprecation {
+    /// Target triple, or SDK version (`0.1` matches every `0.1.x`).
+    pub subject: String,
+    pub replacement: Option<String>,
+    /// End-of-life date (`YYYY-MM-DD`). Builds fail from this day on unless forced.
+    pub eol: Option<String>,
+    pub note: Option<String>,
+}
+
+impl DeprecationTable {
+    /// Embedded table, replaced by the cached one when that has a higher revision.
+    pub fn load() -> Self {
+        let embedded: Self =
+            serde_json::from_str(EMBEDDED_TABLE).expect("embedded deprecation table is valid");
+        match Self::read_cached() {
+            Some(cached) if cached.revision > embedded.revision => cached,
+            _ => embedded,
+        }
+    }
+
+    fn read_cached() -> Option<Self> {
+        let content = std::fs::read(CACHED_TABLE).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    fn find_target(&self, target: &str) -> Option<&Deprecation> {
+        self.targets.iter().find(|d| d.subject == target)
+    }
+
+    fn find_sdk(&self, version: &str) -> Option<&Deprecation> {
+        let version = version.trim_start_matches(['^', '~', '=']);
+        self.sdk
+            .iter()
+            .find(|d| version == d.subject || version.starts_with(&format!("{}.", d.subject)))
+    }
+}
+
+/// Download the latest table into `.vtx-cache/deprecations.json`.
+pub fn refresh() -> Result<DeprecationTable> {
+    offline::ensure_online(
+        "Refreshing the deprecation table",
+        "The cached or bundled table is used without --refresh-deprecations.",
+    )?;
+    let url = std::env::var("VTX_DEPRECATIONS_URL").unwrap_or_else(|_| DEFAULT_TABLE_URL.into());
+    let body = ureq::get(&url)
+        .call()
+        .with_context(|| format!("Failed to fetch deprecation table from {url}"))?
+        .body_mut()
+        .read_to_string()
+        .context("Failed to read deprecation table response")?;
+    let table: DeprecationTable =
+        serde_json::from_str(&body).context("Fetched deprecation table is not valid JSON")?;
+
+    if let Some(parent) = Path::new(CACHED_TABLE).parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(CACHED_TABLE, &body)
+        .with_context(|| format!("Failed to write {CACHED_TABLE}"))?;
+
+    Ok(table)
+}
+
+/// Warn about deprecated targets and SDK versions; fail past EOL unless `force`.
+pub fn check(
+    table: &DeprecationTable,
+    target: Option<&str>,
+    sdk_version: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    if let Some(entry) = target.and_then(|t| table.find_target(t)) {
+        report("Target", entry, force)?;
+    }
+    if let Some(entry) = sdk_version.and_then(|v| table.find_sdk(v)) {
+        report("SDK version", entry, force)?;
+    }
+    Ok(())
+}
+
+fn report(kind: &str, entry: &Deprecation, force: bool) -> Result<()> {
+    let mut msg = format!("{kind} '{}' is deprecated", entry.subject);
+    if let Some(eol) = &entry.eol {
+        msg.push_str(&format!(" (end of life {eol})"));
+    }
+    let mut hint = String::new();
+    if let Some(replacement) = &entry.replacement {
+        hint.push_str(&format!("Migrate to '{replacement}'. "));
+    }
+    if let Some(note) = &entry.note {
+        hint.push_str(note);
+    }
+
+    let expired = entry
+        .eol
+        .as_deref()
+        .is_some_and(|eol| today().as_str() >= eol);
+    if expired && !force {
+        anyhow::bail!("{msg}\nHint: {hint} Use --force to build anyway.");
+    }
+
+    if hint.is_empty() {
+        warn!("{}", msg);
+    } else {
+        warn!("{}\n       {}", msg, hint.trim_end());
+    }
+    Ok(())
+}
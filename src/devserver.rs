@@ -0,0 +1,369 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::config::DevLimitsConfig;
+use crate::timeutil;
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest request head (request line plus headers) accepted.
+const MAX_HEAD_BYTES: u64 = 64 << 10;
+
+/// Largest request body read and discarded; `http-request` carries none.
+const MAX_BODY_BYTES: u64 = 1 << 20;
+
+/// One HTTP/1.1 request received by the dev host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Query string without the leading '?'.
+    pub query: String,
+    /// Header names lowercased, in the order received.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// First value of the header `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Percent-decoded value of the query parameter `name`.
+    pub fn param(&self, name: &str) -> Option<String> {
+        self.query
+            .split('&')
+            .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| percent_decode(value))
+    }
+}
+
+/// Response written back by the dev host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    /// A JSON response.
+    pub fn json(status: u16, body: &serde_json::Value) -> Self {
+        Self::new(status, body.to_string().into_bytes())
+            .with_header("Content-Type", "application/json")
+    }
+
+    /// A plain-text response, ending with a newline.
+    pub fn text(status: u16, body: &str) -> Self {
+        Self::new(status, format!("{body}\n").into_bytes())
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+    }
+
+    pub fn with_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+}
+
+/// Read one request from `stream`, discarding any body.
+pub fn read_request(stream: &TcpStream) -> Result<Request> {
+    stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .context("Failed to configure the connection")?;
+    let mut reader = BufReader::new(stream.take(MAX_HEAD_BYTES));
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .context("Failed to read the request line")?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        anyhow::bail!("Malformed request line {:?}", line.trim_end());
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query.to_string(),
+        headers: Vec::new(),
+    };
+    loop {
+        line.clear();
+        if reader
+            .read_line(&mut line)
+            .context("Failed to read headers")?
+            == 0
+        {
+            anyhow::bail!("Connection closed before the end of the headers");
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header
+            .split_once(':')
+            .with_context(|| format!("Malformed header {header:?}"))?;
+        request
+            .headers
+            .push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+    let length: u64 = request
+        .header("content-length")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+    reader.get_mut().set_limit(MAX_BODY_BYTES);
+    std::io::copy(
+        &mut reader.take(length.min(MAX_BODY_BYTES)),
+        &mut std::io::sink(),
+    )
+    .context("Failed to read the request body")?;
+    Ok(request)
+}
+
+/// Write `response` and close the connection.
+pub fn write_response(mut stream: &TcpStream, response: &Response) -> Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    stream
+        .write_all(head.as_bytes())
+        .and_then(|()| stream.write_all(&response.body))
+        .and_then(|()| stream.flush())
+        .context("Failed to send the response")
+}
+
+/// Reason phrase of the common status codes.
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+/// Decode `%XX` escapes and `+` as a space; malformed escapes stay as they are.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => match text
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+                None => decoded.push(b'%'),
+            },
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Quota window when `[dev.limits]` sets a quota without one.
+const DEFAULT_QUOTA_WINDOW: Duration = Duration::from_secs(86_400);
+
+/// `[dev.limits]` enforced on the dev host's requests: a token bucket for the
+/// rate and a fixed window for the quota, both timed by the plugin's clock,
+/// so a manual clock refills them when advanced.
+#[derive(Debug)]
+pub struct RateLimiter {
+    policy: DevLimitsConfig,
+    window: Duration,
+    tokens: f64,
+    refilled_at: Duration,
+    window_start: Duration,
+    used: u64,
+}
+
+/// What is left of each configured limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counters {
+    /// Requests the bucket allows right now, and its size.
+    pub rate: Option<(u32, u32)>,
+    /// Requests left in the quota window, the quota, and when the window
+    /// ends.
+    pub quota: Option<(u64, u64, Duration)>,
+}
+
+impl std::fmt::Display for Counters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some((left, limit)) = self.rate {
+            parts.push(format!("rate {left}/{limit} left"));
+        }
+        if let Some((left, limit, _)) = self.quota {
+            parts.push(format!("quota {left}/{limit} left"));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// A request refused by a [`RateLimiter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Denied {
+    /// `rate_limited` or `quota_exceeded`.
+    pub reason: &'static str,
+    /// When a retry can succeed.
+    pub retry_after: Duration,
+    pub counters: Counters,
+}
+
+impl RateLimiter {
+    /// A limiter with a full bucket and a quota window starting at `now`.
+    pub fn new(policy: DevLimitsConfig, now: Duration) -> Self {
+        let window = policy
+            .quota_window
+            .as_deref()
+            .and_then(|window| timeutil::parse_duration(window).ok())
+            .unwrap_or(DEFAULT_QUOTA_WINDOW);
+        let tokens = f64::from(burst(&policy));
+        Self {
+            policy,
+            window,
+            tokens,
+            refilled_at: now,
+            window_start: now,
+            used: 0,
+        }
+    }
+
+    pub fn policy(&self) -> &DevLimitsConfig {
+        &self.policy
+    }
+
+    /// Count a request at `now`, or refuse it when a limit is used up.
+    /// Refused requests count against neither limit.
+    pub fn admit(&mut self, now: Duration) -> Result<Counters, Denied> {
+        self.advance(now);
+        if let Some(quota) = self.policy.quota {
+            if self.used >= quota {
+                return Err(Denied {
+                    reason: "quota_exceeded",
+                    retry_after: (self.window_start + self.window).saturating_sub(now),
+                    counters: self.counters(now),
+                });
+            }
+        }
+        if let Some(per_minute) = self.policy.requests_per_minute {
+            if self.tokens < 1.0 {
+                let per_second = f64::from(per_minute) / 60.0;
+                return Err(Denied {
+                    reason: "rate_limited",
+                    retry_after: Duration::from_secs_f64((1.0 - self.tokens) / per_second),
+                    counters: self.counters(now),
+                });
+            }
+            self.tokens -= 1.0;
+        }
+        self.used += 1;
+        Ok(self.counters(now))
+    }
+
+    /// What is left of each limit at `now`.
+    pub fn counters(&mut self, now: Duration) -> Counters {
+        self.advance(now);
+        Counters {
+            rate: self
+                .policy
+                .requests_per_minute
+                .map(|_| (self.tokens.floor() as u32, burst(&self.policy))),
+            quota: self.policy.quota.map(|quota| {
+                (
+                    quota.saturating_sub(self.used),
+                    quota,
+                    (self.window_start + self.window).saturating_sub(now),
+                )
+            }),
+        }
+    }
+
+    /// Refill the bucket and start a new quota window as time passed.
+    fn advance(&mut self, now: Duration) {
+        if let Some(per_minute) = self.policy.requests_per_minute {
+            let elapsed = now.saturating_sub(self.refilled_at).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * f64::from(per_minute) / 60.0)
+                .min(f64::from(burst(&self.policy)));
+        }
+        self.refilled_at = self.refilled_at.max(now);
+        if now.saturating_sub(self.window_start) >= self.window {
+            self.window_start = now;
+            self.used = 0;
+        }
+    }
+}
+
+/// Bucket size: `burst`, else one minute's worth of requests.
+fn burst(policy: &DevLimitsConfig) -> u32 {
+    policy
+        .burst
+        .or(policy.requests_per_minute)
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// `[dev.limits]` in one line.
+pub fn describe_limits(policy: &DevLimitsConfig) -> String {
+    let mut parts = Vec::new();
+    if let Some(per_minute) = policy.requests_per_minute {
+        parts.push(format!(
+            "{per_minute} requests/min (burst {})",
+            burst(policy)
+        ));
+    }
+    if let Some(quota) = policy.quota {
+        parts.push(format!(
+            "quota {quota} per {}",
+            policy.quota_window.as_deref().unwrap_or("1d")
+        ));
+    }
+    if parts.is_empty() {
+        "none".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
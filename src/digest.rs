@@ -0,0 +1,31 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::hex;
+
+/// Algorithms accepted in `<algorithm>:<hex>` digests.
+pub const DIGEST_ALGORITHMS: &[&str] = &["sha256", "sha512"];
+
+/// Algorithm used when `package.digest` is not set.
+pub const DEFAULT_DIGEST_ALGORITHM: &str = "sha256";
+
+/// `<algorithm>:<hex>` digest of `bytes`.
+pub fn digest(algorithm: &str, bytes: &[u8]) -> Result<String> {
+    let hex = match algorithm {
+        "sha256" => hex::encode(&Sha256::digest(bytes)),
+        "sha512" => hex::encode(&Sha512::digest(bytes)),
+        other => anyhow::bail!(
+            "Unsupported digest algorithm '{other}' (expected one of: {})",
+            DIGEST_ALGORITHMS.join(", ")
+        ),
+    };
+    Ok(format!("{algorithm}:{hex}"))
+}
+
+/// Check `bytes` against an `<algorithm>:<hex>` digest, using the algorithm it names.
+pub fn digest_matches(expected: &str, bytes: &[u8]) -> Result<bool> {
+    let (algorithm, _) = expected
+        .split_once(':')
+        .with_context(|| format!("Digest '{expected}' does not name its algorithm"))?;
+    Ok(digest(algorithm, bytes)? == expected.to_ascii_lowercase())
+}
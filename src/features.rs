@@ -0,0 +1,252 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use wasmparser::{MemoryType, Operator, Parser as WasmParser, Payload, TypeRef};
+
+use crate::progress::Progress;
+
+/// Proposals beyond the MVP that kernels accept, per `vtx:api` release line,
+/// oldest first.
+const KERNEL_API_FEATURES: &[(&str, &[&str])] = &[(
+    "0.1",
+    &[
+        "sign_extension",
+        "saturating_float_to_int",
+        "bulk_memory",
+        "reference_types",
+        "simd",
+    ],
+)];
+
+/// Proposals newer toolchains enable by default, with guidance on turning them off.
+const DRIFTING_PROPOSALS: &[(&str, &str, &str)] = &[
+    (
+        "exceptions",
+        "exception handling",
+        "Remove `+exception-handling` from RUSTFLAGS (Rust) or `-fwasm-exceptions` from C/C++ flags linked into the plugin.",
+    ),
+    (
+        "tail_call",
+        "tail calls",
+        "Remove `+tail-call` from RUSTFLAGS (Rust), or pass `-llvm-features=-tail-call` (TinyGo).",
+    ),
+    (
+        "relaxed_simd",
+        "relaxed SIMD",
+        "Remove `+relaxed-simd` from RUSTFLAGS (Rust), or pass `-llvm-features=-relaxed-simd` (TinyGo).",
+    ),
+];
+
+/// WebAssembly proposals used by a module or by any core module inside a component.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureReport {
+    /// A linear memory is declared or imported with 64-bit indices.
+    pub memory64: bool,
+
+    /// A linear memory is declared or imported as `shared` (threads proposal).
+    pub shared_memory: bool,
+
+    /// Number of instructions per non-MVP proposal, keyed by wasmparser's
+    /// proposal name (`threads`, `simd`, `exceptions`, `tail_call`, ...).
+    pub proposals: BTreeMap<&'static str, u64>,
+}
+
+impl FeatureReport {
+    fn record_memory(&mut self, memory: &MemoryType) {
+        self.memory64 |= memory.memory64;
+        self.shared_memory |= memory.shared;
+    }
+
+    /// Instructions from `proposal` found in function bodies.
+    pub fn count(&self, proposal: &str) -> u64 {
+        self.proposals.get(proposal).copied().unwrap_or(0)
+    }
+
+    /// Whether the threads proposal is used (shared memory or atomic instructions).
+    pub fn uses_threads(&self) -> bool {
+        self.shared_memory || self.count("threads") > 0
+    }
+
+    /// SIMD instructions, including relaxed SIMD.
+    pub fn simd_instructions(&self) -> u64 {
+        self.count("simd") + self.count("relaxed_simd")
+    }
+
+    /// Human-readable list of the detected features, for the packaging report.
+    pub fn describe(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        if self.memory64 {
+            out.push("memory64".to_string());
+        }
+        if self.shared_memory {
+            out.push("shared memory".to_string());
+        }
+        for (proposal, count) in &self.proposals {
+            out.push(format!("{proposal} ({count} instructions)"));
+        }
+        out
+    }
+}
+
+/// Which optional proposals packaging accepts.
+#[derive(Debug, Clone, Default)]
+pub struct FeaturePolicy {
+    /// Accept shared memories and atomics. Most kernels run without wasm threads.
+    pub allow_threads: bool,
+
+    /// `vtx:api` release line of the target kernel (e.g. `0.1`).
+    /// Defaults to the line of the bundled SDK (see [`default_kernel_api`]).
+    pub kernel_api: Option<String>,
+
+    /// Reject SIMD instructions; set for scalar builds (`build.no_simd`).
+    pub deny_simd: bool,
+}
+
+impl FeaturePolicy {
+    pub(crate) fn kernel_api(&self) -> String {
+        match self.kernel_api.as_deref() {
+            Some(api) => api.trim_start_matches(['^', '~', '=', 'v']).to_string(),
+            None => default_kernel_api(vtx_sdk::VERSION).to_string(),
+        }
+    }
+}
+
+/// Release line (`MAJOR.MINOR`) of a version.
+fn release_line(version: &str) -> String {
+    version.splitn(3, '.').take(2).collect::<Vec<_>>().join(".")
+}
+
+/// Kernel API line used when `sdk.kernel_api` is unset: the line of
+/// `sdk_version` when it has a feature table, otherwise the newest line that
+/// does, so an SDK bump alone never breaks packaging.
+pub fn default_kernel_api(sdk_version: &str) -> &'static str {
+    let line = release_line(sdk_version);
+    KERNEL_API_FEATURES
+        .iter()
+        .map(|(api, _)| *api)
+        .find(|api| *api == line)
+        .unwrap_or(KERNEL_API_FEATURES[KERNEL_API_FEATURES.len() - 1].0)
+}
+
+/// Proposals accepted by kernels implementing `kernel_api`.
+pub fn kernel_api_features(kernel_api: &str) -> Option<&'static [&'static str]> {
+    KERNEL_API_FEATURES
+        .iter()
+        .find(|(api, _)| *api == kernel_api)
+        .map(|(_, features)| *features)
+}
+
+macro_rules! define_proposal_of {
+    ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
+        /// Name of the proposal that introduced an instruction (`mvp` for the base set).
+        fn proposal_of(op: &Operator<'_>) -> &'static str {
+            match op {
+                $( Operator::$op { .. } => stringify!($proposal), )*
+            }
+        }
+    };
+}
+wasmparser::for_each_operator!(define_proposal_of);
+
+/// Scan memories and instructions of a module or component, including nested core modules.
+pub fn scan(bytes: &[u8]) -> Result<FeatureReport> {
+    let mut report = FeatureReport::default();
+
+    for payload in WasmParser::new(0).parse_all(bytes) {
+        match payload? {
+            Payload::MemorySection(reader) => {
+                for memory in reader {
+                    report.record_memory(&memory?);
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    if let TypeRef::Memory(memory) = import?.ty {
+                        report.record_memory(&memory);
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut reader = body.get_operators_reader()?;
+                while !reader.eof() {
+                    let proposal = proposal_of(&reader.read()?);
+                    if proposal != "mvp" {
+                        *report.proposals.entry(proposal).or_default() += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(report)
+}
+
+/// Fail early on features the bundled adapter, kernel API or policy reject.
+///
+/// Component encoding would otherwise fail deep inside validation with a
+/// generic "feature not enabled" error, or the kernel would refuse the plugin at load time.
+/// Proposals outside the kernel API feature set fail when a toolchain is known to
+/// enable them by default (exceptions, tail calls, relaxed SIMD) and warn otherwise.
+pub fn ensure_supported(
+    report: &FeatureReport,
+    policy: &FeaturePolicy,
+    adapter_version: &str,
+    progress: &Progress,
+) -> Result<()> {
+    if report.memory64 {
+        anyhow::bail!(
+            "Unsupported Feature: the module uses memory64 (64-bit linear memory).\n\
+             The bundled WASI preview1 adapter ({adapter_version}) and the vtx:api canonical ABI only support 32-bit memories.\n\
+             Hint: Build for wasm32 (e.g. wasm32-wasip1); memory64 plugins need kernel support that is still experimental."
+        );
+    }
+    if report.uses_threads() && !policy.allow_threads {
+        anyhow::bail!(
+            "Feature Policy Violation: the module uses wasm threads (shared memory: {}, atomic instructions: {}).\n\
+             Most kernels run plugins without wasm threads and will refuse to load it.\n\
+             Hint: Build without the `atomics` target feature, or set build.allow_threads / --allow-threads if your kernel enables threads.",
+            if report.shared_memory { "yes" } else { "no" },
+            report.count("threads")
+        );
+    }
+
+    if policy.deny_simd && report.simd_instructions() > 0 {
+        anyhow::bail!(
+            "Feature Policy Violation: build.no_simd is set, but the module still contains {} SIMD instructions.\n\
+             They usually come from a prebuilt dependency or a custom build command that ignores the scalar flags.\n\
+             Hint: Rebuild dependencies with `-C target-feature=-simd128`, or unset build.no_simd.",
+            report.simd_instructions()
+        );
+    }
+
+    let kernel_api = policy.kernel_api();
+    let sdk_line = release_line(vtx_sdk::VERSION);
+    if policy.kernel_api.is_none() && kernel_api != sdk_line {
+        progress.warning(&format!(
+            "The bundled SDK targets vtx:api {sdk_line}, which has no known feature set; checking against {kernel_api}. Set sdk.kernel_api to choose the kernel line."
+        ));
+    }
+    let Some(supported) = kernel_api_features(&kernel_api) else {
+        let known: Vec<&str> = KERNEL_API_FEATURES.iter().map(|(api, _)| *api).collect();
+        anyhow::bail!(
+            "Unknown kernel API version '{kernel_api}' (known: {})",
+            known.join(", ")
+        );
+    };
+
+    for (&proposal, count) in &report.proposals {
+        if proposal == "threads" || supported.contains(&proposal) {
+            continue;
+        }
+        match DRIFTING_PROPOSALS.iter().find(|(p, _, _)| *p == proposal) {
+            Some((_, name, hint)) => anyhow::bail!(
+                "Unsupported Feature: the module uses {name} ({count} instructions), which kernels implementing vtx:api {kernel_api} do not accept.\n\
+                 Hint: {hint}"
+            ),
+            None => progress.warning(&format!(
+                "The module uses the `{proposal}` proposal ({count} instructions), which is not in the vtx:api {kernel_api} feature set; the kernel may refuse to load it."
+            )),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1347 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{
+    Component, ComponentType, Instance, Lift, Linker, LinkerInstance, Lower, Resource,
+    ResourceTable, ResourceType, Type, Val,
+};
+use wasmtime::{Config, Engine, ResourceLimiter, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::p2::pipe::MemoryOutputPipe;
+use wasmtime_wasi::{HostMonotonicClock, HostWallClock, WasiCtx, WasiCtxView, WasiView};
+
+use crate::cancel::CancelToken;
+use crate::kernel_api::{KernelLimits, KernelProfile};
+use crate::manifest::Manifest;
+
+/// Kernel interface whose buffers the harness implements in memory.
+const STREAM_IO_INTERFACE: &str = "vtx:api/stream-io";
+
+/// Kernel interface answering who sent the request being handled.
+const CONTEXT_INTERFACE: &str = "vtx:api/context";
+
+/// Most plugin output kept by [`HarnessOptions::capture_output`]; further
+/// writes fail inside the plugin.
+const CAPTURE_LIMIT: usize = 16 << 20;
+
+/// How long a [`FaultKind::Timeout`] blocks when the fault gives no `delay_ms`.
+const DEFAULT_TIMEOUT_MS: u64 = 100;
+
+/// How the harness sets up the plugin's environment.
+#[derive(Debug, Clone, Default)]
+pub struct HarnessOptions {
+    /// Collect the plugin's stdout and stderr in [`Invocation::output`]
+    /// instead of inheriting them.
+    pub capture_output: bool,
+    /// Freeze `wasi:clocks/wall-clock` at this time since the Unix epoch and
+    /// `wasi:clocks/monotonic-clock` at zero, instead of the host clocks.
+    pub frozen_time: Option<Duration>,
+    /// Drive both clocks from this clock, which only moves when advanced by
+    /// hand. Takes precedence over `frozen_time`.
+    pub clock: Option<ManualClock>,
+    /// Seed the generators behind `wasi:random` instead of drawing from the
+    /// host's entropy. Also seeds the draws of probabilistic [`Fault`]s.
+    pub random_seed: Option<u64>,
+    /// Faults injected into the stubbed kernel functions.
+    pub faults: Vec<Fault>,
+    /// User `vtx:api/context#get-current-user` returns; none by default.
+    pub user: Option<CurrentUser>,
+    /// Emulate this kernel: link only the interfaces it provides, fail
+    /// instantiation on any import left unlinked (instead of stubbing it as a
+    /// trap) or required export missing, and apply its limits.
+    pub kernel_api: Option<KernelProfile>,
+}
+
+/// A failure injected into a stubbed kernel function, so tests can check
+/// that the plugin degrades gracefully.
+///
+/// A fault fires on every call, on the `call`-th call only, or on each call
+/// with `probability`. A firing call returns the fault's error where the
+/// function's result type allows one and traps otherwise.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Fault {
+    /// Kernel function as `<interface>#<function>`, e.g.
+    /// `vtx:api/event-bus#publish-event`; the interface version may be left out.
+    pub function: String,
+    /// How the call fails.
+    #[serde(default)]
+    pub kind: FaultKind,
+    /// Error message the call returns; defaults to one naming the function.
+    pub message: Option<String>,
+    /// Fail only this call of the function, counting from 1.
+    pub call: Option<u32>,
+    /// Fail each call with this probability (0 to 1), drawn from the seed.
+    pub probability: Option<f64>,
+    /// Block this long before failing; a timeout defaults to 100 ms.
+    pub delay_ms: Option<u64>,
+}
+
+/// How a [`Fault`] makes a call fail.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FaultKind {
+    /// Return an error right away.
+    #[default]
+    Error,
+    /// Block, then return a timeout error.
+    Timeout,
+}
+
+impl Fault {
+    /// Check that the fault names a function and fires on a valid condition.
+    pub fn validate(&self) -> Result<()> {
+        let named = self
+            .function
+            .split_once('#')
+            .is_some_and(|(interface, function)| !interface.is_empty() && !function.is_empty());
+        if !named {
+            anyhow::bail!(
+                "fault function '{}' must be <interface>#<function>",
+                self.function
+            );
+        }
+        match (self.call, self.probability) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!("fault on {} sets both call and probability", self.function)
+            }
+            (Some(0), None) => anyhow::bail!("fault on {}: calls count from 1", self.function),
+            (None, Some(p)) if !(0.0..=1.0).contains(&p) => {
+                anyhow::bail!(
+                    "fault on {}: probability {p} is not between 0 and 1",
+                    self.function
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the fault targets `function` of the imported `interface`
+    /// (`<package>/<name>@<version>`).
+    fn targets(&self, interface: &str, function: &str) -> bool {
+        let Some((target, target_function)) = self.function.split_once('#') else {
+            return false;
+        };
+        let unversioned = interface
+            .split_once('@')
+            .map_or(interface, |(name, _)| name);
+        target_function == function && (target == interface || target == unversioned)
+    }
+
+    /// The error a call fails with, after any delay.
+    fn error(&self, qualified: &str, call: u32) -> (Option<Duration>, String) {
+        match self.kind {
+            FaultKind::Error => (
+                self.delay_ms.map(Duration::from_millis),
+                self.message
+                    .clone()
+                    .unwrap_or_else(|| format!("{qualified} failed (injected fault, call {call})")),
+            ),
+            FaultKind::Timeout => {
+                let ms = self.delay_ms.unwrap_or(DEFAULT_TIMEOUT_MS);
+                (
+                    Some(Duration::from_millis(ms)),
+                    self.message
+                        .clone()
+                        .unwrap_or_else(|| format!("{qualified} timed out after {ms} ms")),
+                )
+            }
+        }
+    }
+}
+
+/// [`HarnessOptions::faults`] with the calls made so far to each stubbed function.
+struct FaultInjector {
+    faults: Vec<Fault>,
+    calls: HashMap<String, u32>,
+    rng: StdRng,
+}
+
+impl FaultInjector {
+    /// Count a call to `function` of `interface` and return the delay and
+    /// error of the first fault that fires on it, if any.
+    fn on_call(&mut self, interface: &str, function: &str) -> Option<(Option<Duration>, String)> {
+        let qualified = format!("{interface}#{function}");
+        let call = self.calls.entry(qualified.clone()).or_default();
+        *call += 1;
+        let call = *call;
+        let rng = &mut self.rng;
+        self.faults
+            .iter()
+            .filter(|fault| fault.targets(interface, function))
+            .find(|fault| match (fault.call, fault.probability) {
+                (Some(n), _) => n == call,
+                (None, Some(p)) => rng.random_bool(p),
+                (None, None) => true,
+            })
+            .map(|fault| fault.error(&qualified, call))
+    }
+}
+
+/// Clock that always reads the same time, for [`HarnessOptions::frozen_time`].
+struct FrozenClock(Duration);
+
+impl HostWallClock for FrozenClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self) -> Duration {
+        self.0
+    }
+}
+
+impl HostMonotonicClock for FrozenClock {
+    fn resolution(&self) -> u64 {
+        1
+    }
+
+    fn now(&self) -> u64 {
+        0
+    }
+}
+
+/// A virtual clock that stands still until advanced, shared by every clone.
+///
+/// The wall clock reads the current time; the monotonic clock reads how far
+/// the clock has been advanced since it was created.
+#[derive(Debug, Clone)]
+pub struct ManualClock(Arc<Mutex<ManualTime>>);
+
+#[derive(Debug)]
+struct ManualTime {
+    wall: Duration,
+    advanced: Duration,
+}
+
+impl ManualClock {
+    /// A clock reading `start` since the Unix epoch.
+    pub fn new(start: Duration) -> Self {
+        Self(Arc::new(Mutex::new(ManualTime {
+            wall: start,
+            advanced: Duration::ZERO,
+        })))
+    }
+
+    /// Current time since the Unix epoch.
+    pub fn now(&self) -> Duration {
+        self.time().wall
+    }
+
+    /// Move the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        let mut time = self.time();
+        time.wall += by;
+        time.advanced += by;
+    }
+
+    /// Move the clock forward to `to`; a clock never runs backwards.
+    pub fn set(&self, to: Duration) -> Result<()> {
+        let now = self.now();
+        let by = to.checked_sub(now).with_context(|| {
+            format!(
+                "Cannot set the clock back by {:.3}s; it only moves forward",
+                (now - to).as_secs_f64()
+            )
+        })?;
+        self.advance(by);
+        Ok(())
+    }
+
+    fn time(&self) -> std::sync::MutexGuard<'_, ManualTime> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl HostWallClock for ManualClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self) -> Duration {
+        ManualClock::now(self)
+    }
+}
+
+impl HostMonotonicClock for ManualClock {
+    fn resolution(&self) -> u64 {
+        1
+    }
+
+    fn now(&self) -> u64 {
+        u64::try_from(self.time().advanced.as_nanos()).unwrap_or(u64::MAX)
+    }
+}
+
+/// Result of [`invoke_handle_with`] or [`invoke_export`].
+#[derive(Debug)]
+pub struct Invocation<T = HttpResponse> {
+    pub result: Result<T>,
+    /// Everything the plugin wrote to stdout and stderr, when captured. Kept
+    /// when `handle` fails, since the output often explains why.
+    pub output: Vec<u8>,
+}
+
+/// `http-request` record of the `vtx:api` plugin world.
+#[derive(ComponentType, Lower, Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[component(record)]
+#[serde(default)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+}
+
+impl Default for HttpRequest {
+    fn default() -> Self {
+        Self {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            query: String::new(),
+        }
+    }
+}
+
+/// `current-user` record of `vtx:api/auth-types`: who sent the request.
+#[derive(ComponentType, Lift, Lower, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[component(record)]
+pub struct CurrentUser {
+    #[component(name = "user-id")]
+    pub user_id: String,
+    pub username: String,
+    pub groups: Vec<String>,
+}
+
+/// `user-context` record of `vtx:api/auth-types`, returned by `authenticate`.
+#[derive(ComponentType, Lift, Lower, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[component(record)]
+pub struct UserContext {
+    #[component(name = "user-id")]
+    pub user_id: String,
+    pub username: String,
+    pub groups: Vec<String>,
+    pub metadata: String,
+}
+
+impl From<UserContext> for CurrentUser {
+    fn from(user: UserContext) -> Self {
+        Self {
+            user_id: user.user_id,
+            username: user.username,
+            groups: user.groups,
+        }
+    }
+}
+
+/// Response returned by the plugin's `handle` export, with the body buffer read out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Option<Vec<u8>>,
+}
+
+/// `http-response` as lifted from the component.
+#[derive(ComponentType, Lift)]
+#[component(record)]
+struct RawResponse {
+    status: u16,
+    body: Option<Resource<MemoryBuffer>>,
+}
+
+/// Host side of a `stream-io` buffer.
+struct MemoryBuffer(Vec<u8>);
+
+struct HarnessState {
+    ctx: WasiCtx,
+    table: ResourceTable,
+    faults: FaultInjector,
+    user: Option<CurrentUser>,
+    limits: MeteredLimits,
+    /// Interrupts the plugin at the emulated kernel's time limit; replaced for
+    /// each call and stopped with the store.
+    _deadline: Option<Deadline>,
+}
+
+/// [`StoreLimits`] that also count the linear memory the plugin has
+/// allocated. Linear memory never shrinks, so the count is also the peak.
+struct MeteredLimits {
+    limits: StoreLimits,
+    memory_bytes: usize,
+}
+
+impl ResourceLimiter for MeteredLimits {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        let allow = self.limits.memory_growing(current, desired, maximum)?;
+        if allow {
+            self.memory_bytes += desired.saturating_sub(current);
+        }
+        Ok(allow)
+    }
+
+    fn memory_grow_failed(&mut self, error: wasmtime::Error) -> wasmtime::Result<()> {
+        self.limits.memory_grow_failed(error)
+    }
+
+    fn table_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        self.limits.table_growing(current, desired, maximum)
+    }
+
+    fn table_grow_failed(&mut self, error: wasmtime::Error) -> wasmtime::Result<()> {
+        self.limits.table_grow_failed(error)
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
+}
+
+/// Bumps the engine's epoch once `timeout` passes, unless dropped first.
+struct Deadline {
+    /// Dropping it wakes the timer thread, which then exits.
+    _stop: std::sync::mpsc::Sender<()>,
+}
+
+impl Deadline {
+    fn start(engine: &Engine, timeout: Duration) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+        let engine = engine.clone();
+        std::thread::spawn(move || {
+            if let Err(std::sync::mpsc::RecvTimeoutError::Timeout) = receiver.recv_timeout(timeout)
+            {
+                engine.increment_epoch();
+            }
+        });
+        Self { _stop: sender }
+    }
+}
+
+impl WasiView for HarnessState {
+    fn ctx(&mut self) -> WasiCtxView<'_> {
+        WasiCtxView {
+            ctx: &mut self.ctx,
+            table: &mut self.table,
+        }
+    }
+}
+
+/// Instantiate the component under wasmtime and call its `handle` export once.
+///
+/// Host environment:
+/// - WASI is provided by wasmtime with the plugin's stdout and stderr inherited.
+/// - `stream-io` memory buffers work; `open-file` fails since there is no media store.
+/// - Other kernel functions fail gracefully where their signature allows
+///   (`result` returns an error, `option` returns none, `list` is empty) and trap otherwise.
+/// - [`HarnessOptions::faults`] make chosen calls of those functions return
+///   their own error, after a delay for timeouts, or trap where no error fits.
+pub fn invoke_handle(
+    component_bytes: &[u8],
+    request: &HttpRequest,
+    cancel: &CancelToken,
+) -> Result<HttpResponse> {
+    invoke_handle_with(component_bytes, request, &HarnessOptions::default(), cancel).result
+}
+
+/// [`invoke_handle`] with the environment set up as `options` asks.
+pub fn invoke_handle_with(
+    component_bytes: &[u8],
+    request: &HttpRequest,
+    options: &HarnessOptions,
+    cancel: &CancelToken,
+) -> Invocation {
+    let capture = options
+        .capture_output
+        .then(|| MemoryOutputPipe::new(CAPTURE_LIMIT));
+    let result = call_handle(component_bytes, request, options, capture.clone(), cancel);
+    Invocation {
+        result,
+        output: capture
+            .map(|pipe| pipe.contents().to_vec())
+            .unwrap_or_default(),
+    }
+}
+
+/// Call the export `name` with `args`, JSON values keyed by parameter name,
+/// and return its result as JSON (`null` for a function without one).
+///
+/// Values map to WIT types as in [`json_to_val`]. Exports taking or
+/// returning resources cannot be called this way; `handle` has
+/// [`invoke_handle_with`].
+pub fn invoke_export(
+    component_bytes: &[u8],
+    name: &str,
+    args: &Map<String, Value>,
+    options: &HarnessOptions,
+    cancel: &CancelToken,
+) -> Invocation<Value> {
+    let capture = options
+        .capture_output
+        .then(|| MemoryOutputPipe::new(CAPTURE_LIMIT));
+    let result = call_export(
+        component_bytes,
+        name,
+        args,
+        options,
+        capture.clone(),
+        cancel,
+    );
+    Invocation {
+        result,
+        output: capture
+            .map(|pipe| pipe.contents().to_vec())
+            .unwrap_or_default(),
+    }
+}
+
+fn call_export(
+    component_bytes: &[u8],
+    name: &str,
+    args: &Map<String, Value>,
+    options: &HarnessOptions,
+    capture: Option<MemoryOutputPipe>,
+    cancel: &CancelToken,
+) -> Result<Value> {
+    let (mut store, instance) = instantiate(component_bytes, options, capture, cancel)?;
+    let func = instance
+        .get_func(&mut store, name)
+        .with_context(|| format!("Component has no '{name}' export"))?;
+    let ty = func.ty(&store);
+    if let Some(unknown) = args
+        .keys()
+        .find(|arg| !ty.params().any(|(param, _)| param == arg.as_str()))
+    {
+        anyhow::bail!("'{name}' has no parameter '{unknown}'");
+    }
+    let params = ty
+        .params()
+        .map(|(param, param_ty)| {
+            let value = args
+                .get(param)
+                .with_context(|| format!("Missing argument '{param}'"))?;
+            json_to_val(value, &param_ty).with_context(|| format!("Invalid argument '{param}'"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut results = vec![Val::Bool(false); ty.results().len()];
+
+    cancel.check()?;
+    func.call(&mut store, &params, &mut results)
+        .map_err(|e| over_limits(anyhow::Error::from(e), options))
+        .with_context(|| format!("Plugin trapped in '{name}'"))?;
+    match results.first() {
+        Some(result) => val_to_json(result),
+        None => Ok(Value::Null),
+    }
+}
+
+/// A function the component exports, with a placeholder for each parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedFunction {
+    pub name: String,
+    /// Parameter names with the simplest value of their type; see [`placeholder`].
+    pub params: Map<String, Value>,
+    types: Vec<(String, Type)>,
+}
+
+impl ExportedFunction {
+    /// Whether [`invoke_export`] could convert `args` to the parameters.
+    pub fn accepts(&self, args: &Map<String, Value>) -> bool {
+        args.len() == self.types.len()
+            && self.types.iter().all(|(param, ty)| {
+                args.get(param)
+                    .is_some_and(|value| json_to_val(value, ty).is_ok())
+            })
+    }
+}
+
+/// The functions the component exports at the top level.
+pub fn exported_functions(component_bytes: &[u8]) -> Result<Vec<ExportedFunction>> {
+    let engine = Engine::new(Config::new().wasm_component_model(true))
+        .map_err(anyhow::Error::from)
+        .context("Failed to create wasmtime engine")?;
+    let component = Component::from_binary(&engine, component_bytes)
+        .map_err(anyhow::Error::from)
+        .context("wasmtime could not compile the component")?;
+    Ok(component
+        .component_type()
+        .exports(&engine)
+        .filter_map(|(name, export)| match export.ty {
+            ComponentItem::ComponentFunc(func) => Some(ExportedFunction {
+                name: name.to_string(),
+                params: func
+                    .params()
+                    .map(|(param, ty)| (param.to_string(), placeholder(&ty)))
+                    .collect(),
+                types: func
+                    .params()
+                    .map(|(param, ty)| (param.to_string(), ty))
+                    .collect(),
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+/// The simplest JSON value of `ty`: `false`, `0`, `""`, empty lists, the
+/// first case of a variant or enum, `null` options and `{"ok": ...}` results.
+/// Resources have none and give `null`.
+pub fn placeholder(ty: &Type) -> Value {
+    match ty {
+        Type::Bool => Value::Bool(false),
+        Type::S8
+        | Type::U8
+        | Type::S16
+        | Type::U16
+        | Type::S32
+        | Type::U32
+        | Type::S64
+        | Type::U64 => Value::from(0),
+        Type::Float32 | Type::Float64 => Value::from(0.0),
+        Type::Char => Value::from("a"),
+        Type::String => Value::from(""),
+        Type::List(_) | Type::Map(_) | Type::Flags(_) => Value::Array(Vec::new()),
+        Type::FixedLengthList(list) => {
+            Value::Array(vec![placeholder(&list.ty()); list.len() as usize])
+        }
+        Type::Record(record) => Value::Object(
+            record
+                .fields()
+                .map(|field| (field.name.to_string(), placeholder(&field.ty)))
+                .collect(),
+        ),
+        Type::Tuple(tuple) => Value::Array(tuple.types().map(|ty| placeholder(&ty)).collect()),
+        Type::Variant(variant) => match variant.cases().next() {
+            Some(case) => match case.ty {
+                Some(ty) => single(case.name, placeholder(&ty)),
+                None => Value::from(case.name),
+            },
+            None => Value::Null,
+        },
+        Type::Enum(names) => names.names().next().map_or(Value::Null, Value::from),
+        Type::Option(_) => Value::Null,
+        Type::Result(result) => {
+            single("ok", result.ok().map_or(Value::Null, |ty| placeholder(&ty)))
+        }
+        Type::Own(_) | Type::Borrow(_) | Type::Future(_) | Type::Stream(_) | Type::ErrorContext => {
+            Value::Null
+        }
+    }
+}
+
+/// `{"<key>": value}`.
+fn single(key: &str, value: Value) -> Value {
+    Value::Object(Map::from_iter([(key.to_string(), value)]))
+}
+
+/// Convert JSON to a value of `ty`.
+///
+/// Numbers, strings and booleans map to the primitive types (a `char` is a
+/// one-character string); lists, tuples and fixed-length lists are arrays;
+/// records are objects keyed by field name; enum cases and payload-less
+/// variant cases are strings, other variant cases `{"<case>": payload}`;
+/// options are `null` or the value; results are `{"ok": ...}` or
+/// `{"err": ...}`; flags are arrays of names and maps arrays of `[key, value]`.
+pub fn json_to_val(value: &Value, ty: &Type) -> Result<Val> {
+    let expected = |what: &str| anyhow::anyhow!("expected {what}, got {value}");
+    let int = || value.as_i64().ok_or_else(|| expected("an integer"));
+    let uint = || {
+        value
+            .as_u64()
+            .ok_or_else(|| expected("a non-negative integer"))
+    };
+    let range = |_| anyhow::anyhow!("{value} is out of range");
+    let number = || value.as_f64().ok_or_else(|| expected("a number"));
+    let items = |ty: Type| -> Result<Vec<Val>> {
+        value
+            .as_array()
+            .ok_or_else(|| expected("an array"))?
+            .iter()
+            .map(|item| json_to_val(item, &ty))
+            .collect()
+    };
+    Ok(match ty {
+        Type::Bool => Val::Bool(value.as_bool().ok_or_else(|| expected("a boolean"))?),
+        Type::S8 => Val::S8(int()?.try_into().map_err(range)?),
+        Type::U8 => Val::U8(uint()?.try_into().map_err(range)?),
+        Type::S16 => Val::S16(int()?.try_into().map_err(range)?),
+        Type::U16 => Val::U16(uint()?.try_into().map_err(range)?),
+        Type::S32 => Val::S32(int()?.try_into().map_err(range)?),
+        Type::U32 => Val::U32(uint()?.try_into().map_err(range)?),
+        Type::S64 => Val::S64(int()?),
+        Type::U64 => Val::U64(uint()?),
+        Type::Float32 => Val::Float32(number()? as f32),
+        Type::Float64 => Val::Float64(number()?),
+        Type::Char => {
+            let mut chars = value
+                .as_str()
+                .ok_or_else(|| expected("a character"))?
+                .chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Val::Char(c),
+                _ => return Err(expected("a single character")),
+            }
+        }
+        Type::String => Val::String(
+            value
+                .as_str()
+                .ok_or_else(|| expected("a string"))?
+                .to_string(),
+        ),
+        Type::List(list) => Val::List(items(list.ty())?),
+        Type::FixedLengthList(list) => {
+            let items = items(list.ty())?;
+            if items.len() != list.len() as usize {
+                anyhow::bail!("expected {} items, got {}", list.len(), items.len());
+            }
+            Val::FixedLengthList(items)
+        }
+        Type::Map(map) => Val::Map(
+            value
+                .as_array()
+                .ok_or_else(|| expected("an array of [key, value] pairs"))?
+                .iter()
+                .map(|entry| match entry.as_array().map(Vec::as_slice) {
+                    Some([key, value]) => Ok((
+                        json_to_val(key, &map.key())?,
+                        json_to_val(value, &map.value())?,
+                    )),
+                    _ => anyhow::bail!("expected a [key, value] pair, got {entry}"),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        Type::Record(record) => {
+            let object = value.as_object().ok_or_else(|| expected("an object"))?;
+            if let Some(unknown) = object
+                .keys()
+                .find(|key| !record.fields().any(|field| field.name == key.as_str()))
+            {
+                anyhow::bail!("unknown field '{unknown}'");
+            }
+            Val::Record(
+                record
+                    .fields()
+                    .map(|field| {
+                        let value = object
+                            .get(field.name)
+                            .with_context(|| format!("missing field '{}'", field.name))?;
+                        let value = json_to_val(value, &field.ty)
+                            .with_context(|| format!("in field '{}'", field.name))?;
+                        Ok((field.name.to_string(), value))
+                    })
+                    .collect::<Result<_>>()?,
+            )
+        }
+        Type::Tuple(tuple) => {
+            let items = value.as_array().ok_or_else(|| expected("an array"))?;
+            if items.len() != tuple.types().len() {
+                anyhow::bail!(
+                    "expected {} items, got {}",
+                    tuple.types().len(),
+                    items.len()
+                );
+            }
+            Val::Tuple(
+                items
+                    .iter()
+                    .zip(tuple.types())
+                    .map(|(item, ty)| json_to_val(item, &ty))
+                    .collect::<Result<_>>()?,
+            )
+        }
+        Type::Variant(variant) => {
+            let (name, payload) = case(value)?;
+            let ty = variant
+                .cases()
+                .find(|case| case.name == name)
+                .with_context(|| format!("unknown case '{name}'"))?
+                .ty;
+            Val::Variant(name.to_string(), payload_val(name, ty, payload)?)
+        }
+        Type::Enum(names) => {
+            let name = value.as_str().ok_or_else(|| expected("an enum case"))?;
+            if !names.names().any(|case| case == name) {
+                anyhow::bail!("unknown case '{name}'");
+            }
+            Val::Enum(name.to_string())
+        }
+        Type::Option(option) => Val::Option(match value {
+            Value::Null => None,
+            value => Some(Box::new(json_to_val(value, &option.ty())?)),
+        }),
+        Type::Result(result) => match case(value)? {
+            ("ok", payload) => Val::Result(Ok(payload_val("ok", result.ok(), payload)?)),
+            ("err", payload) => Val::Result(Err(payload_val("err", result.err(), payload)?)),
+            _ => return Err(expected(r#"{"ok": ...} or {"err": ...}"#)),
+        },
+        Type::Flags(flags) => Val::Flags(
+            value
+                .as_array()
+                .ok_or_else(|| expected("an array of flag names"))?
+                .iter()
+                .map(|flag| match flag.as_str() {
+                    Some(name) if flags.names().any(|known| known == name) => Ok(name.to_string()),
+                    _ => anyhow::bail!("unknown flag {flag}"),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        Type::Own(_) | Type::Borrow(_) | Type::Future(_) | Type::Stream(_) | Type::ErrorContext => {
+            anyhow::bail!("resources, futures and streams cannot be given as JSON")
+        }
+    })
+}
+
+/// The case name and payload of a variant or result written as `"<case>"`
+/// or `{"<case>": payload}`.
+fn case(value: &Value) -> Result<(&str, Option<&Value>)> {
+    match value {
+        Value::String(name) => Ok((name, None)),
+        Value::Object(object) if object.len() == 1 => {
+            let (name, payload) = object.iter().next().expect("one entry");
+            Ok((name, Some(payload)))
+        }
+        _ => anyhow::bail!(r#"expected "<case>" or {{"<case>": payload}}, got {value}"#),
+    }
+}
+
+/// The payload of case `name`, which has a value of type `ty` if any.
+fn payload_val(name: &str, ty: Option<Type>, payload: Option<&Value>) -> Result<Option<Box<Val>>> {
+    match (ty, payload) {
+        (Some(ty), Some(payload)) => Ok(Some(Box::new(json_to_val(payload, &ty)?))),
+        (None, None | Some(Value::Null)) => Ok(None),
+        (Some(_), None) => anyhow::bail!("case '{name}' needs a value"),
+        (None, Some(_)) => anyhow::bail!("case '{name}' has no value"),
+    }
+}
+
+/// Convert a value to JSON, the inverse of [`json_to_val`].
+pub fn val_to_json(val: &Val) -> Result<Value> {
+    let float = |f: f64| {
+        Number::from_f64(f)
+            .map(Value::Number)
+            .with_context(|| format!("{f} has no JSON representation"))
+    };
+    let payload = |payload: &Option<Box<Val>>| match payload {
+        Some(payload) => val_to_json(payload),
+        None => Ok(Value::Null),
+    };
+    Ok(match val {
+        Val::Bool(b) => Value::Bool(*b),
+        Val::S8(n) => Value::from(*n),
+        Val::U8(n) => Value::from(*n),
+        Val::S16(n) => Value::from(*n),
+        Val::U16(n) => Value::from(*n),
+        Val::S32(n) => Value::from(*n),
+        Val::U32(n) => Value::from(*n),
+        Val::S64(n) => Value::from(*n),
+        Val::U64(n) => Value::from(*n),
+        Val::Float32(f) => float(f64::from(*f))?,
+        Val::Float64(f) => float(*f)?,
+        Val::Char(c) => Value::from(c.to_string()),
+        Val::String(s) => Value::from(s.as_str()),
+        Val::List(items) | Val::Tuple(items) | Val::FixedLengthList(items) => {
+            Value::Array(items.iter().map(val_to_json).collect::<Result<_>>()?)
+        }
+        Val::Map(entries) => Value::Array(
+            entries
+                .iter()
+                .map(|(key, value)| Ok(Value::Array(vec![val_to_json(key)?, val_to_json(value)?])))
+                .collect::<Result<_>>()?,
+        ),
+        Val::Record(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), val_to_json(value)?)))
+                .collect::<Result<_>>()?,
+        ),
+        Val::Variant(name, None) | Val::Enum(name) => Value::from(name.as_str()),
+        Val::Variant(name, value) => single(name, payload(value)?),
+        Val::Option(value) => payload(value)?,
+        Val::Result(Ok(value)) => single("ok", payload(value)?),
+        Val::Result(Err(value)) => single("err", payload(value)?),
+        Val::Flags(names) => Value::Array(names.iter().map(|n| Value::from(n.as_str())).collect()),
+        Val::Resource(_) | Val::Future(_) | Val::Stream(_) | Val::ErrorContext(_) => {
+            anyhow::bail!("resources, futures and streams have no JSON form")
+        }
+    })
+}
+
+fn call_handle(
+    component_bytes: &[u8],
+    request: &HttpRequest,
+    options: &HarnessOptions,
+    capture: Option<MemoryOutputPipe>,
+    cancel: &CancelToken,
+) -> Result<HttpResponse> {
+    let (store, instance) = instantiate(component_bytes, options, capture, cancel)?;
+    Plugin {
+        store,
+        instance,
+        options: options.clone(),
+    }
+    .handle(request, cancel)
+}
+
+/// A component instantiated once and then called for many requests, the way
+/// a kernel keeps a plugin loaded. Store state, such as the plugin's globals
+/// and buffers, carries over from one call to the next.
+pub struct Plugin {
+    store: Store<HarnessState>,
+    instance: Instance,
+    options: HarnessOptions,
+}
+
+impl Plugin {
+    /// Instantiate the component like [`invoke_handle_with`], with the
+    /// plugin's stdout and stderr inherited.
+    pub fn instantiate(
+        component_bytes: &[u8],
+        options: &HarnessOptions,
+        cancel: &CancelToken,
+    ) -> Result<Self> {
+        let (store, instance) = instantiate(component_bytes, options, None, cancel)?;
+        Ok(Self {
+            store,
+            instance,
+            options: options.clone(),
+        })
+    }
+
+    /// Make `get-current-user` return `user` from the next call on.
+    pub fn set_user(&mut self, user: Option<CurrentUser>) {
+        self.store.data_mut().user = user;
+    }
+
+    /// Call the `authenticate` export with `headers`, or return `None` when
+    /// the plugin has none. `Err` carries the status the plugin rejected the
+    /// request with.
+    pub fn authenticate(
+        &mut self,
+        headers: &[(String, String)],
+        cancel: &CancelToken,
+    ) -> Result<Option<std::result::Result<UserContext, u16>>> {
+        let store = &mut self.store;
+        if self
+            .instance
+            .get_export_index(&mut *store, None, "authenticate")
+            .is_none()
+        {
+            return Ok(None);
+        }
+        let authenticate = self
+            .instance
+            .get_typed_func::<(Vec<(String, String)>,), (std::result::Result<UserContext, u16>,)>(
+                &mut *store,
+                "authenticate",
+            )
+            .map_err(anyhow::Error::from)
+            .context("Component has no 'authenticate' export matching the vtx:api plugin world")?;
+
+        cancel.check()?;
+        restart_deadline(store, &self.options);
+        let (result,) = authenticate
+            .call(&mut *store, (headers.to_vec(),))
+            .map_err(|e| over_limits(anyhow::Error::from(e), &self.options))
+            .context("Plugin trapped in 'authenticate'")?;
+        Ok(Some(result))
+    }
+
+    /// Bytes of linear memory the plugin has allocated so far, across all of
+    /// its memories.
+    pub fn memory_bytes(&self) -> usize {
+        self.store.data().limits.memory_bytes
+    }
+
+    /// Call `handle` with `request`. An emulated kernel's time limit applies
+    /// to each call. After a trap the instance must not be called again.
+    pub fn handle(&mut self, request: &HttpRequest, cancel: &CancelToken) -> Result<HttpResponse> {
+        let options = &self.options;
+        let store = &mut self.store;
+        let handle = self
+            .instance
+            .get_typed_func::<(HttpRequest,), (RawResponse,)>(&mut *store, "handle")
+            .map_err(anyhow::Error::from)
+            .context("Component has no 'handle' export matching the vtx:api plugin world")?;
+
+        cancel.check()?;
+        restart_deadline(store, options);
+        let (response,) = handle
+            .call(&mut *store, (request.clone(),))
+            .map_err(|e| over_limits(anyhow::Error::from(e), options))
+            .context("Plugin trapped while handling the request")?;
+        let body = match response.body {
+            Some(buffer) => Some(
+                store
+                    .data_mut()
+                    .table
+                    .delete(buffer)
+                    .map_err(anyhow::Error::from)
+                    .context("Response body is not a live buffer")?
+                    .0,
+            ),
+            None => None,
+        };
+        let limit = options
+            .kernel_api
+            .as_ref()
+            .and_then(|p| p.limits.max_response_bytes.map(|max| (max, &p.version)));
+        if let (Some(body), Some((max, version))) = (&body, limit) {
+            if body.len() > max {
+                anyhow::bail!(
+                    "Response body of {} bytes exceeds the {max}-byte limit of vtx:api@{version}",
+                    body.len()
+                );
+            }
+        }
+
+        Ok(HttpResponse {
+            status: response.status,
+            body,
+        })
+    }
+}
+
+/// Give the next call the emulated kernel's full time limit again.
+fn restart_deadline(store: &mut Store<HarnessState>, options: &HarnessOptions) {
+    let timeout = options
+        .kernel_api
+        .as_ref()
+        .and_then(|p| p.limits.timeout_ms);
+    if let Some(ms) = timeout {
+        let deadline = Deadline::start(store.engine(), Duration::from_millis(ms));
+        store.data_mut()._deadline = Some(deadline);
+        store.set_epoch_deadline(1);
+    }
+}
+
+/// Instantiate the component as [`invoke_handle_with`] would, without calling
+/// any export: checks that the host `options` set up, an emulated kernel in
+/// particular, loads it.
+pub fn check_instantiation(
+    component_bytes: &[u8],
+    options: &HarnessOptions,
+    cancel: &CancelToken,
+) -> Result<()> {
+    instantiate(component_bytes, options, None, cancel).map(drop)
+}
+
+/// Instantiate the component like [`invoke_handle`] and call its `get-manifest` export.
+pub fn invoke_get_manifest(component_bytes: &[u8], cancel: &CancelToken) -> Result<Manifest> {
+    let (mut store, instance) =
+        instantiate(component_bytes, &HarnessOptions::default(), None, cancel)?;
+    let get_manifest = instance
+        .get_typed_func::<(), (Manifest,)>(&mut store, "get-manifest")
+        .map_err(anyhow::Error::from)
+        .context("Component has no 'get-manifest' export matching the vtx:api plugin world")?;
+
+    cancel.check()?;
+    let (manifest,) = get_manifest
+        .call(&mut store, ())
+        .map_err(anyhow::Error::from)
+        .context("Plugin trapped while returning its manifest")?;
+    Ok(manifest)
+}
+
+/// Compile the component and instantiate it with WASI and the stubbed kernel
+/// imports. The plugin's stdout and stderr go to `capture` if given, else to
+/// ours; clocks, random and faults are set up as `options` asks. Fails when a
+/// fault targets no stubbed kernel function the component imports.
+fn instantiate(
+    component_bytes: &[u8],
+    options: &HarnessOptions,
+    capture: Option<MemoryOutputPipe>,
+    cancel: &CancelToken,
+) -> Result<(Store<HarnessState>, Instance)> {
+    let profile = options.kernel_api.as_ref();
+    let timeout = profile.and_then(|p| p.limits.timeout_ms);
+    let mut config = Config::new();
+    config.wasm_component_model(true);
+    config.epoch_interruption(timeout.is_some());
+    let engine = Engine::new(&config)
+        .map_err(anyhow::Error::from)
+        .context("Failed to create wasmtime engine")?;
+    let component = Component::from_binary(&engine, component_bytes)
+        .map_err(anyhow::Error::from)
+        .context("wasmtime could not compile the component")?;
+    cancel.check()?;
+
+    let mut linker: Linker<HarnessState> = Linker::new(&engine);
+    wasmtime_wasi::p2::add_to_linker_sync(&mut linker)
+        .map_err(anyhow::Error::from)
+        .context("Failed to link WASI into the harness")?;
+    let kernel_imports: Vec<(String, ComponentItem)> = component
+        .component_type()
+        .imports(&engine)
+        .filter(|(name, _)| name.starts_with("vtx:"))
+        .map(|(name, import)| (name.to_string(), import.ty))
+        .collect();
+    let mut stubbed = Vec::new();
+    let mut missing = Vec::new();
+    for (name, item) in &kernel_imports {
+        if let Some(profile) = profile {
+            if let Some(reason) = profile.missing_import(name)? {
+                missing.push(reason);
+                continue;
+            }
+        }
+        let mut instance = linker.instance(name).map_err(anyhow::Error::from)?;
+        if name.starts_with(STREAM_IO_INTERFACE) {
+            link_stream_io(&mut instance).map_err(anyhow::Error::from)?;
+        } else if name.starts_with(CONTEXT_INTERFACE) {
+            link_context(&mut instance, name).map_err(anyhow::Error::from)?;
+            stubbed.push((name.as_str(), "get-current-user"));
+        } else if let ComponentItem::ComponentInstance(interface) = item {
+            for (function, export) in interface.exports(&engine) {
+                if matches!(export.ty, ComponentItem::ComponentFunc(_)) {
+                    stub_kernel_function(&mut instance, name, function)
+                        .map_err(anyhow::Error::from)?;
+                    stubbed.push((name.as_str(), function));
+                }
+            }
+        }
+    }
+    for fault in &options.faults {
+        fault.validate()?;
+        // With imports missing, instantiation fails before any fault fires.
+        if missing.is_empty()
+            && !stubbed
+                .iter()
+                .any(|(interface, function)| fault.targets(interface, function))
+        {
+            anyhow::bail!(
+                "Fault on {} matches no stubbed kernel function the plugin imports",
+                fault.function
+            );
+        }
+    }
+    match profile {
+        Some(profile) => {
+            let exports: Vec<String> = component
+                .component_type()
+                .exports(&engine)
+                .map(|(name, _)| name.to_string())
+                .collect();
+            missing.extend(profile.missing_exports(exports.iter().map(String::as_str)));
+            if !missing.is_empty() {
+                anyhow::bail!(
+                    "Component did not instantiate under a vtx:api@{} kernel:\n  {}",
+                    profile.version,
+                    missing.join("\n  ")
+                );
+            }
+        }
+        None => linker
+            .define_unknown_imports_as_traps(&component)
+            .map_err(anyhow::Error::from)
+            .context("Failed to stub remaining imports")?,
+    }
+
+    let mut ctx = WasiCtx::builder();
+    match capture {
+        Some(pipe) => ctx.stdout(pipe.clone()).stderr(pipe),
+        None => ctx.inherit_stdout().inherit_stderr(),
+    };
+    if let Some(clock) = &options.clock {
+        ctx.wall_clock(clock.clone()).monotonic_clock(clock.clone());
+    } else if let Some(time) = options.frozen_time {
+        ctx.wall_clock(FrozenClock(time))
+            .monotonic_clock(FrozenClock(time));
+    }
+    if let Some(seed) = options.random_seed {
+        ctx.secure_random(StdRng::seed_from_u64(seed))
+            .insecure_random(StdRng::seed_from_u64(seed.wrapping_add(1)))
+            .insecure_random_seed(u128::from(seed));
+    }
+    let state = HarnessState {
+        ctx: ctx.build(),
+        table: ResourceTable::new(),
+        faults: FaultInjector {
+            faults: options.faults.clone(),
+            calls: HashMap::new(),
+            rng: StdRng::seed_from_u64(options.random_seed.unwrap_or_default()),
+        },
+        user: options.user.clone(),
+        limits: MeteredLimits {
+            limits: store_limits(profile.map(|p| &p.limits)),
+            memory_bytes: 0,
+        },
+        _deadline: timeout.map(|ms| Deadline::start(&engine, Duration::from_millis(ms))),
+    };
+    let mut store = Store::new(&engine, state);
+    store.limiter(|state| &mut state.limits);
+    if timeout.is_some() {
+        store.set_epoch_deadline(1);
+    }
+    let instance = linker
+        .instantiate(&mut store, &component)
+        .map_err(|e| over_limits(anyhow::Error::from(e), options))
+        .with_context(|| match profile {
+            Some(profile) => format!(
+                "Component did not instantiate under a vtx:api@{} kernel",
+                profile.version
+            ),
+            None => "Component did not instantiate under wasmtime".to_string(),
+        })?;
+    Ok((store, instance))
+}
+
+/// Store limits of an emulated kernel; without one, only wasmtime's defaults.
+fn store_limits(limits: Option<&KernelLimits>) -> StoreLimits {
+    let mut builder = StoreLimitsBuilder::new();
+    if let Some(limits) = limits {
+        if let Some(bytes) = limits.max_memory_bytes {
+            builder = builder.memory_size(bytes);
+        }
+        if let Some(elements) = limits.max_table_elements {
+            builder = builder.table_elements(elements);
+        }
+    }
+    builder.build()
+}
+
+/// Name the emulated kernel's time limit when it interrupted the plugin.
+fn over_limits(error: anyhow::Error, options: &HarnessOptions) -> anyhow::Error {
+    let Some(profile) = &options.kernel_api else {
+        return error;
+    };
+    match (
+        error.downcast_ref::<wasmtime::Trap>(),
+        profile.limits.timeout_ms,
+    ) {
+        (Some(wasmtime::Trap::Interrupt), Some(ms)) => error.context(format!(
+            "Plugin exceeded the {ms} ms time limit of vtx:api@{}",
+            profile.version
+        )),
+        _ => error,
+    }
+}
+
+fn link_stream_io(instance: &mut LinkerInstance<'_, HarnessState>) -> wasmtime::Result<()> {
+    instance.resource(
+        "buffer",
+        ResourceType::host::<MemoryBuffer>(),
+        |mut store, rep| {
+            store
+                .data_mut()
+                .table
+                .delete(Resource::<MemoryBuffer>::new_own(rep))?;
+            Ok(())
+        },
+    )?;
+    instance.func_wrap(
+        "[method]buffer.size",
+        |store, (this,): (Resource<MemoryBuffer>,)| {
+            Ok((store.data().table.get(&this)?.0.len() as u64,))
+        },
+    )?;
+    instance.func_wrap(
+        "[method]buffer.read",
+        |store, (this, offset, max_bytes): (Resource<MemoryBuffer>, u64, u64)| {
+            let data = &store.data().table.get(&this)?.0;
+            let start = usize::try_from(offset)
+                .unwrap_or(usize::MAX)
+                .min(data.len());
+            let len = usize::try_from(max_bytes).unwrap_or(usize::MAX);
+            let end = start.saturating_add(len).min(data.len());
+            Ok((data[start..end].to_vec(),))
+        },
+    )?;
+    instance.func_wrap(
+        "[method]buffer.write",
+        |mut store, (this, data): (Resource<MemoryBuffer>, Vec<u8>)| {
+            let buffer = store.data_mut().table.get_mut(&this)?;
+            buffer.0.extend_from_slice(&data);
+            Ok((data.len() as u64,))
+        },
+    )?;
+    instance.func_wrap("create-memory-buffer", |mut store, (data,): (Vec<u8>,)| {
+        Ok((store.data_mut().table.push(MemoryBuffer(data))?,))
+    })?;
+    instance.func_wrap("open-file", |_store, (uuid,): (String,)| {
+        let result: Result<Resource<MemoryBuffer>, String> =
+            Err(format!("open-file('{uuid}') is not available in vtx run"));
+        Ok((result,))
+    })?;
+    Ok(())
+}
+
+/// Answer `get-current-user` with [`HarnessOptions::user`], or with the user
+/// set by [`Plugin::set_user`]; an injected fault traps, since an option
+/// cannot carry an error.
+fn link_context(
+    instance: &mut LinkerInstance<'_, HarnessState>,
+    interface: &str,
+) -> wasmtime::Result<()> {
+    let interface = interface.to_string();
+    instance.func_wrap("get-current-user", move |mut store, (): ()| {
+        let state = store.data_mut();
+        if let Some((delay, message)) = state.faults.on_call(&interface, "get-current-user") {
+            if let Some(delay) = delay {
+                std::thread::sleep(delay);
+            }
+            wasmtime::bail!("{message}");
+        }
+        Ok((state.user.clone(),))
+    })
+}
+
+/// Define `function` of a kernel interface to fail without trapping when its
+/// result type allows, or with the error of an injected fault.
+fn stub_kernel_function(
+    instance: &mut LinkerInstance<'_, HarnessState>,
+    interface: &str,
+    function: &str,
+) -> wasmtime::Result<()> {
+    let interface = interface.to_string();
+    let name = function.to_string();
+    instance.func_new(function, move |mut store, ty, _params, results| {
+        let fault = store.data_mut().faults.on_call(&interface, &name);
+        let injected = fault.is_some();
+        let message = match fault {
+            Some((delay, message)) => {
+                if let Some(delay) = delay {
+                    std::thread::sleep(delay);
+                }
+                message
+            }
+            None => format!("{interface}#{name} is not available in vtx run"),
+        };
+        for (slot, result_ty) in results.iter_mut().zip(ty.results()) {
+            *slot = match result_ty {
+                Type::Result(result) => match result.err() {
+                    None => Val::Result(Err(None)),
+                    Some(Type::String) => {
+                        Val::Result(Err(Some(Box::new(Val::String(message.clone())))))
+                    }
+                    Some(_) => wasmtime::bail!("{message}"),
+                },
+                Type::Option(_) if !injected => Val::Option(None),
+                Type::List(_) if !injected => Val::List(Vec::new()),
+                _ => wasmtime::bail!("{message}"),
+            };
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,11 @@
+use std::fmt::Write;
+
+/// Lowercase hex form of `bytes`, two digits per byte.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        // Writing to a String cannot fail.
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+
+use crate::compare::ArtifactSummary;
+use crate::{timeutil, toolchain};
+
+/// Size of every successful build, one JSON record per line.
+pub const SIZE_HISTORY_FILE: &str = ".vtx-cache/size-history.jsonl";
+
+/// Result of every `vtx bench` run, one JSON record per line.
+pub const BENCH_HISTORY_FILE: &str = ".vtx-cache/bench-history.jsonl";
+
+const SPARK_BARS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SizeRecord {
+    pub timestamp: String,
+    /// Short git commit of the sources, if built inside a git checkout.
+    pub commit: Option<String>,
+    /// Tracked files had uncommitted changes at build time.
+    pub dirty: bool,
+    pub package: String,
+    pub profile: String,
+    pub artifact_size: u64,
+    pub component_size: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchRecord {
+    pub timestamp: String,
+    /// Short git commit checked out during the run, if any.
+    pub commit: Option<String>,
+    /// Tracked files had uncommitted changes during the run.
+    pub dirty: bool,
+    /// Benchmarked file as given on the command line.
+    pub file: String,
+    /// Request method and target, e.g. `GET /items?page=2`.
+    pub request: String,
+    pub iterations: u32,
+    pub cold_start_ms: f64,
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+    pub memory_bytes: u64,
+}
+
+impl BenchRecord {
+    /// Value of `metric` (`cold_start_ms`, `p50_ms`, `p99_ms` or
+    /// `memory_bytes`).
+    pub fn metric(&self, metric: &str) -> Option<f64> {
+        match metric {
+            "cold_start_ms" => Some(self.cold_start_ms),
+            "p50_ms" => Some(self.p50_ms),
+            "p99_ms" => Some(self.p99_ms),
+            "memory_bytes" => Some(self.memory_bytes as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Metrics of a [`BenchRecord`], as accepted by [`BenchRecord::metric`].
+pub const BENCH_METRICS: &[&str] = &["cold_start_ms", "p50_ms", "p99_ms", "memory_bytes"];
+
+/// Append the sizes of a finished build.
+pub fn record(summary: &ArtifactSummary) -> Result<()> {
+    let (commit, dirty) = git_state();
+    let entry = SizeRecord {
+        timestamp: timeutil::now_rfc3339(),
+        commit,
+        dirty,
+        package: summary.package.clone(),
+        profile: summary.profile.clone(),
+        artifact_size: summary.artifact_size,
+        component_size: summary.component_size,
+    };
+    append(SIZE_HISTORY_FILE, &entry)
+}
+
+/// Append a finished bench run; the timestamp and commit are filled in.
+pub fn record_bench(mut entry: BenchRecord) -> Result<()> {
+    (entry.commit, entry.dirty) = git_state();
+    entry.timestamp = timeutil::now_rfc3339();
+    append(BENCH_HISTORY_FILE, &entry)
+}
+
+/// Read the size history. A missing file is empty; unparsable lines are skipped.
+pub fn read() -> Result<Vec<SizeRecord>> {
+    read_lines(SIZE_HISTORY_FILE)
+}
+
+/// Read the bench history, like [`read`].
+pub fn read_bench() -> Result<Vec<BenchRecord>> {
+    read_lines(BENCH_HISTORY_FILE)
+}
+
+/// Short commit of HEAD, and whether tracked files are modified.
+fn git_state() -> (Option<String>, bool) {
+    let commit = toolchain::probe_version("git", &["rev-parse", "--short", "HEAD"]);
+    let dirty = commit.is_some()
+        && toolchain::probe_version("git", &["status", "--porcelain", "--untracked-files=no"])
+            .is_some();
+    (commit, dirty)
+}
+
+fn append(file: &str, entry: &impl Serialize) -> Result<()> {
+    let path = Path::new(file);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut out = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {file}"))?;
+    writeln!(out, "{}", serde_json::to_string(entry)?)
+        .with_context(|| format!("Failed to append to {file}"))
+}
+
+fn read_lines<T: DeserializeOwned>(file: &str) -> Result<Vec<T>> {
+    match std::fs::read_to_string(file) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {file}")),
+    }
+}
+
+/// Commit column of a history listing: `-` outside git, `+` when dirty.
+pub fn describe_commit(commit: Option<&str>, dirty: bool) -> String {
+    match (commit, dirty) {
+        (Some(c), true) => format!("{c}+"),
+        (Some(c), false) => c.to_string(),
+        (None, _) => "-".to_string(),
+    }
+}
+
+/// One bar per value, scaled between the smallest and largest value.
+pub fn sparkline(values: &[f64]) -> String {
+    if values.is_empty() {
+        return String::new();
+    }
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let span = max - min;
+    let top = (SPARK_BARS.len() - 1) as f64;
+    values
+        .iter()
+        .map(|v| {
+            let level = if span > 0.0 {
+                ((v - min) * top / span).floor()
+            } else {
+                0.0
+            };
+            SPARK_BARS[level as usize]
+        })
+        .collect()
+}
@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::config::{self, I18nConfig};
+
+/// Catalog directory used when `i18n.dir` is not set.
+pub const DEFAULT_I18N_DIR: &str = "locales";
+
+/// Locales the marketplace requires when `i18n.required` is not set.
+pub const MARKETPLACE_LOCALES: &[&str] = &["en", "zh"];
+
+/// Missing keys listed per locale before the error is truncated.
+const MAX_LISTED_KEYS: usize = 10;
+
+/// String catalogs keyed by locale, then by dotted message key.
+pub type Catalogs = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Read `<dir>/<locale>.json` for every declared locale.
+///
+/// Nested objects are flattened into dotted keys (`{"menu": {"open": ".."}}`
+/// becomes `menu.open`); every leaf must be a string.
+pub fn load(i18n: &I18nConfig) -> Result<Catalogs> {
+    let dir = Path::new(i18n.dir.as_deref().unwrap_or(DEFAULT_I18N_DIR));
+    let mut catalogs = Catalogs::new();
+
+    for locale in &i18n.locales {
+        let path = dir.join(format!("{locale}.json"));
+        if !path.is_file() {
+            anyhow::bail!(
+                "Catalog for locale '{locale}' not found: {}\nHint: Create it or remove '{locale}' from i18n.locales.",
+                path.display()
+            );
+        }
+        let content = config::read_text_file(&path)
+            .with_context(|| format!("Failed to read catalog: {}", path.display()))?;
+        let value: Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse catalog: {}", path.display()))?;
+
+        let mut messages = BTreeMap::new();
+        flatten("", &value, &mut messages)
+            .with_context(|| format!("Invalid catalog: {}", path.display()))?;
+        catalogs.insert(locale.clone(), messages);
+    }
+
+    Ok(catalogs)
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, String>) -> Result<()> {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten(&key, value, out)?;
+            }
+        }
+        Value::String(text) if !prefix.is_empty() => {
+            out.insert(prefix.to_string(), text.clone());
+        }
+        _ if prefix.is_empty() => anyhow::bail!("the top level must be a JSON object"),
+        _ => anyhow::bail!("'{prefix}' must be a string"),
+    }
+    Ok(())
+}
+
+/// Check required locales are declared and every catalog has the same keys.
+pub fn validate(i18n: &I18nConfig, catalogs: &Catalogs) -> Result<()> {
+    let required: Vec<&str> = match i18n.required.as_ref() {
+        Some(required) => required.iter().map(String::as_str).collect(),
+        None => MARKETPLACE_LOCALES.to_vec(),
+    };
+    let missing_locales: Vec<&str> = required
+        .into_iter()
+        .filter(|locale| !catalogs.contains_key(*locale))
+        .collect();
+    if !missing_locales.is_empty() {
+        anyhow::bail!(
+            "I18n Validation Failed: required locales missing from i18n.locales: {}",
+            missing_locales.join(", ")
+        );
+    }
+
+    let all_keys: BTreeSet<&String> = catalogs.values().flat_map(|c| c.keys()).collect();
+    let mut problems = Vec::new();
+    for (locale, messages) in catalogs {
+        let missing: Vec<&str> = all_keys
+            .iter()
+            .filter(|key| !messages.contains_key(key.as_str()))
+            .map(|key| key.as_str())
+            .collect();
+        if missing.is_empty() {
+            continue;
+        }
+        let mut listed = missing
+            .iter()
+            .take(MAX_LISTED_KEYS)
+            .copied()
+            .collect::<Vec<_>>()
+            .join(", ");
+        if missing.len() > MAX_LISTED_KEYS {
+            listed.push_str(&format!(", ... ({} more)", missing.len() - MAX_LISTED_KEYS));
+        }
+        problems.push(format!(
+            "{locale} is missing {} keys: {listed}",
+            missing.len()
+        ));
+    }
+
+    if !problems.is_empty() {
+        anyhow::bail!(
+            "I18n Validation Failed: catalogs have mismatched keys.\n  {}",
+            problems.join("\n  ")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::io::BufRead;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::ProjectConfig;
+use crate::output;
+
+/// Environment variable overriding `dev.kernel_url`.
+pub const KERNEL_URL_ENV: &str = "VTX_KERNEL_URL";
+
+/// Admin API of a kernel started with its default settings.
+pub const DEFAULT_KERNEL_URL: &str = "http://127.0.0.1:8080";
+
+/// Delay before reconnecting to the log stream after it ends or fails.
+const LOG_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Client for the admin HTTP API of a running VTX kernel.
+///
+/// Plugins are addressed as `{url}/admin/plugins/{id}`:
+/// - `PUT` with a `.vtx` body installs or hot-reloads the plugin.
+/// - `GET .../logs?follow=true` streams the plugin's log, one line (plain
+///   text or a JSON object with `level` and `message`) per entry.
+#[derive(Clone)]
+pub struct Kernel {
+    base_url: String,
+}
+
+impl Kernel {
+    /// Kernel from `url`, else `VTX_KERNEL_URL`, else `dev.kernel_url`, else the default.
+    pub fn resolve(url: Option<&str>, config: Option<&ProjectConfig>) -> Self {
+        let base_url = url
+            .map(str::to_string)
+            .or_else(|| std::env::var(KERNEL_URL_ENV).ok())
+            .or_else(|| {
+                config
+                    .and_then(|c| c.dev.as_ref())
+                    .and_then(|d| d.kernel_url.clone())
+            })
+            .unwrap_or_else(|| DEFAULT_KERNEL_URL.to_string());
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Upload an artifact; the kernel replaces a loaded plugin with the same id.
+    pub fn deploy(&self, id: &str, artifact: &[u8]) -> Result<()> {
+        let url = self.plugin_url(id)?;
+        ureq::put(&url)
+            .header("Content-Type", "application/octet-stream")
+            .send(artifact)
+            .map(drop)
+            .with_context(|| format!("Failed to deploy to {url}"))
+    }
+
+    /// Log the plugin's log lines as `[kernel] ...` from a background thread;
+    /// JSON output turns each into a `kernel_log` event.
+    ///
+    /// The stream is reopened whenever it ends or the kernel is unreachable,
+    /// so logs resume after the kernel restarts. The thread runs until the
+    /// process exits.
+    pub fn follow_logs(&self, id: &str) -> Result<()> {
+        let url = format!("{}/logs?follow=true", self.plugin_url(id)?);
+        std::thread::spawn(move || {
+            let mut reported = false;
+            loop {
+                match ureq::get(&url).call() {
+                    Ok(mut response) => {
+                        reported = false;
+                        let reader = std::io::BufReader::new(response.body_mut().as_reader());
+                        for line in reader.lines().map_while(|l| l.ok()) {
+                            if line.trim().is_empty() {
+                                continue;
+                            }
+                            if output::is_json() {
+                                output::emit("kernel_log", serde_json::json!({ "line": line }));
+                            } else {
+                                info!(tag = "KERNEL", "{}", format_log(&line));
+                            }
+                        }
+                    }
+                    Err(e) if !reported => {
+                        reported = true;
+                        warn!("Kernel log stream unavailable ({e}); retrying");
+                    }
+                    Err(_) => {}
+                }
+                std::thread::sleep(LOG_RECONNECT_DELAY);
+            }
+        });
+        Ok(())
+    }
+
+    fn plugin_url(&self, id: &str) -> Result<String> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c));
+        if !valid {
+            anyhow::bail!("Invalid plugin id '{id}' for the kernel admin API");
+        }
+        Ok(format!("{}/admin/plugins/{id}", self.base_url))
+    }
+}
+
+/// Render a JSON log entry as `LEVEL message`; other lines are kept as-is.
+fn format_log(line: &str) -> String {
+    let Ok(Value::Object(entry)) = serde_json::from_str::<Value>(line) else {
+        return line.to_string();
+    };
+    let message = entry.get("message").and_then(Value::as_str).unwrap_or(line);
+    match entry.get("level").and_then(Value::as_str) {
+        Some(level) => format!("{} {message}", level.to_uppercase()),
+        None => message.to_string(),
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::contract;
+
+/// Table shipped with this CLI release.
+const EMBEDDED_TABLE: &str = include_str!("kernel_api.json");
+
+/// Environment variable naming a JSON table used instead of the embedded
+/// one, for kernels this CLI release does not describe.
+pub const KERNEL_API_TABLE_ENV: &str = "VTX_KERNEL_API_TABLE";
+
+/// What each released kernel API version provides and enforces.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct KernelApiTable {
+    pub versions: Vec<KernelApiEntry>,
+}
+
+/// One `vtx:api` version of the table.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct KernelApiEntry {
+    /// `MAJOR.MINOR.PATCH`.
+    pub version: String,
+    /// `vtx:api` interfaces the kernel links, by name (`sql`, `event-bus`).
+    pub interfaces: Vec<String>,
+    /// Exports the kernel requires before it instantiates a plugin.
+    pub exports: Vec<String>,
+    #[serde(default)]
+    pub limits: KernelLimits,
+}
+
+/// Resource limits a kernel applies to every plugin instance; unset fields
+/// are unlimited.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct KernelLimits {
+    /// Largest size any linear memory may have or grow to.
+    pub max_memory_bytes: Option<usize>,
+    /// Largest number of elements any table may have or grow to.
+    pub max_table_elements: Option<usize>,
+    /// Wall time for instantiation and for each call, after which the plugin
+    /// is interrupted.
+    pub timeout_ms: Option<u64>,
+    /// Largest `handle` response body.
+    pub max_response_bytes: Option<usize>,
+}
+
+impl KernelApiTable {
+    /// The table named by `VTX_KERNEL_API_TABLE`, else the embedded one.
+    pub fn load() -> Result<Self> {
+        let table: Self = match std::env::var(KERNEL_API_TABLE_ENV) {
+            Ok(path) => {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read kernel API table: {path}"))?;
+                serde_json::from_str(&content)
+                    .with_context(|| format!("Malformed kernel API table: {path}"))?
+            }
+            Err(_) => {
+                serde_json::from_str(EMBEDDED_TABLE).expect("embedded kernel API table is valid")
+            }
+        };
+        for entry in &table.versions {
+            contract::parse_kernel_api(&entry.version)
+                .context("Invalid version in the kernel API table")?;
+        }
+        Ok(table)
+    }
+
+    /// Profile of a kernel implementing `kernel_api`: the newest entry of the
+    /// same major version that is not newer. Releases missing from the table
+    /// behave like the entry before them.
+    pub fn profile(&self, kernel_api: &str) -> Result<KernelProfile> {
+        let version = contract::parse_kernel_api(kernel_api)?;
+        let entry = self
+            .versions
+            .iter()
+            .filter_map(|entry| {
+                let entry_version = contract::parse_kernel_api(&entry.version).ok()?;
+                (entry_version.major == version.major && entry_version <= version)
+                    .then_some((entry_version, entry))
+            })
+            .max_by(|(a, _), (b, _)| a.cmp(b));
+        let Some((entry_version, entry)) = entry else {
+            let known: Vec<&str> = self.versions.iter().map(|e| e.version.as_str()).collect();
+            anyhow::bail!(
+                "vtx:api {version} is not in the kernel API table (known versions: {}); set {KERNEL_API_TABLE_ENV} to a table describing it",
+                known.join(", ")
+            );
+        };
+        Ok(KernelProfile {
+            version,
+            entry: entry_version,
+            interfaces: entry.interfaces.clone(),
+            exports: entry.exports.clone(),
+            limits: entry.limits.clone(),
+        })
+    }
+}
+
+/// A kernel implementing one `vtx:api` version, as the harness emulates it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelProfile {
+    /// Emulated version.
+    pub version: semver::Version,
+    /// Table entry the profile comes from.
+    pub entry: semver::Version,
+    pub interfaces: Vec<String>,
+    pub exports: Vec<String>,
+    pub limits: KernelLimits,
+}
+
+impl KernelProfile {
+    /// [`KernelApiTable::profile`] of the loaded table.
+    pub fn resolve(kernel_api: &str) -> Result<Self> {
+        KernelApiTable::load()?.profile(kernel_api)
+    }
+
+    /// Why this kernel does not link the imported interface `import`
+    /// (`<package>/<name>[@<version>]`), or `None` when it does: the interface
+    /// must be in the profile and its version compatible by the rules of
+    /// [`contract::check_requires`].
+    pub fn missing_import(&self, import: &str) -> Result<Option<String>> {
+        if let Some(reason) = contract::missing_import(import, &self.version.to_string())? {
+            return Ok(Some(reason));
+        }
+        let interface = import.split_once('@').map_or(import, |(name, _)| name);
+        let name = interface
+            .rsplit_once('/')
+            .map_or(interface, |(_, name)| name);
+        if self.interfaces.iter().any(|provided| provided == name) {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "{import}: vtx:api@{} has no {name} interface",
+            self.version
+        )))
+    }
+
+    /// Required exports missing from `exports`, one line each.
+    pub fn missing_exports<'a>(
+        &self,
+        exports: impl Iterator<Item = &'a str> + Clone,
+    ) -> Vec<String> {
+        self.exports
+            .iter()
+            .filter(|required| !exports.clone().any(|export| export == required.as_str()))
+            .map(|required| format!("export {required}: required by vtx:api@{}", self.version))
+            .collect()
+    }
+}
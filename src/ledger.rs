@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::Path;
+use tracing::info;
+
+use crate::{digest, hex, signing, timeutil, toolchain};
+
+/// Append-only ledger of produced artifacts, one JSON entry per line.
+pub const LEDGER_FILE: &str = ".vtx-ledger.jsonl";
+
+/// One notarization record. `hash` covers every other field, including
+/// `prev_hash`, so editing or removing an entry breaks the chain.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LedgerEntry {
+    pub seq: u64,
+    pub timestamp: String,
+    pub artifact: String,
+    pub artifact_digest: String,
+    pub metadata_digest: String,
+    /// Key id of the embedded signature; `None` for unsigned artifacts.
+    pub signer: Option<String>,
+    pub builder_host: String,
+    /// Git commit of the sources (`-dirty` when tracked files were modified).
+    /// Omitted when absent so entries written before the field still verify.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_revision: Option<String>,
+    pub prev_hash: Option<String>,
+    pub hash: String,
+}
+
+impl LedgerEntry {
+    fn compute_hash(&self) -> Result<String> {
+        let mut unsealed = self.clone();
+        unsealed.hash = String::new();
+        Ok(sha256_digest(&serde_json::to_vec(&unsealed)?))
+    }
+}
+
+/// `sha256:<hex>` digest of `bytes`.
+pub fn sha256_digest(bytes: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(&Sha256::digest(bytes)))
+}
+
+fn builder_host() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .filter(|h| !h.is_empty())
+        .or_else(|| toolchain::probe_version("hostname", &[]))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn source_revision() -> Option<String> {
+    let commit = toolchain::probe_version("git", &["rev-parse", "HEAD"])?;
+    let dirty = toolchain::probe_version("git", &["status", "--porcelain", "--untracked-files=no"])
+        .is_some();
+    Some(if dirty {
+        format!("{commit}-dirty")
+    } else {
+        commit
+    })
+}
+
+/// Read all entries. A missing ledger is empty.
+pub fn read_entries(path: &Path) -> Result<Vec<LedgerEntry>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read ledger: {}", path.display()))
+        }
+    };
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Malformed ledger entry at {}:{}", path.display(), i + 1))
+        })
+        .collect()
+}
+
+/// Append a record for a freshly written artifact, digesting it with `algorithm`.
+///
+/// The chain hashes linking entries are always SHA-256.
+pub fn record(artifact_path: &Path, metadata_json: &[u8], algorithm: &str) -> Result<LedgerEntry> {
+    let path = Path::new(LEDGER_FILE);
+    let previous = read_entries(path)?;
+    let artifact_bytes = std::fs::read(artifact_path)
+        .with_context(|| format!("Failed to read artifact: {}", artifact_path.display()))?;
+
+    let mut entry = LedgerEntry {
+        seq: previous.len() as u64,
+        timestamp: timeutil::now_rfc3339(),
+        artifact: artifact_path.display().to_string(),
+        artifact_digest: digest::digest(algorithm, &artifact_bytes)?,
+        metadata_digest: digest::digest(algorithm, metadata_json)?,
+        signer: signing::embedded_signature(metadata_json)
+            .ok()
+            .flatten()
+            .map(|s| s.key_id),
+        builder_host: builder_host(),
+        source_revision: source_revision(),
+        prev_hash: previous.last().map(|e| e.hash.clone()),
+        hash: String::new(),
+    };
+    entry.hash = entry.compute_hash()?;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open ledger: {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)
+        .with_context(|| format!("Failed to append to ledger: {}", path.display()))?;
+
+    info!(
+        "Ledger entry #{} recorded ({})",
+        entry.seq, entry.artifact_digest
+    );
+    Ok(entry)
+}
+
+/// Check sequence numbers, hash links and entry hashes.
+///
+/// Returns the number of verified entries, or an error naming the first broken entry.
+pub fn verify(entries: &[LedgerEntry]) -> Result<usize> {
+    let mut prev_hash: Option<&str> = None;
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.seq != i as u64 {
+            anyhow::bail!(
+                "Ledger Verification Failed: entry {} has sequence number {}",
+                i,
+                entry.seq
+            );
+        }
+        if entry.prev_hash.as_deref() != prev_hash {
+            anyhow::bail!(
+                "Ledger Verification Failed: entry {} does not link to the previous entry",
+                i
+            );
+        }
+        if entry.compute_hash()? != entry.hash {
+            anyhow::bail!(
+                "Ledger Verification Failed: entry {} was modified after it was recorded",
+                i
+            );
+        }
+        prev_hash = Some(&entry.hash);
+    }
+    Ok(entries.len())
+}
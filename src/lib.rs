@@ -1 +1,22 @@
+pub mod adapter;
+pub mod analysis;
+pub mod cancel;
+pub mod compose;
+pub mod container;
+pub mod contract;
+pub mod dataopt;
+pub mod debuginfo;
+pub mod digest;
+pub mod features;
+pub mod harness;
+pub mod hex;
+pub mod kernel_api;
+pub mod manifest;
 pub mod packager;
+pub mod progress;
+pub mod runtime;
+pub mod signing;
+pub mod startup;
+#[cfg(unix)]
+pub mod testing;
+pub mod wasmopt;
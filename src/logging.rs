@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+/// `[OK]` line: a check or step succeeded.
+macro_rules! ok {
+    ($($arg:tt)+) => { tracing::info!(tag = "OK", $($arg)+) };
+}
+
+/// `[DONE]` line: a command produced its result.
+macro_rules! done {
+    ($($arg:tt)+) => { tracing::info!(tag = "DONE", $($arg)+) };
+}
+
+/// `[VTX]` line: a command or major step starts.
+macro_rules! step {
+    ($($arg:tt)+) => { tracing::info!(tag = "VTX", $($arg)+) };
+}
+
+/// Crates whose events are shown at the selected verbosity; dependencies
+/// (wasmtime, cranelift, ...) only contribute warnings and errors.
+const OWN_TARGETS: &[&str] = &["vtx", "vtx_cli"];
+
+/// Terminal level for `-q`/`-v` counts: `-qq` errors, `-q` warnings, default
+/// info, `-v` debug, `-vv` trace. `debug` (the `--debug` flag) implies `-v`.
+pub fn terminal_level(verbose: u8, quiet: u8, debug: bool) -> LevelFilter {
+    let verbosity = i16::from(verbose.max(u8::from(debug))) - i16::from(quiet);
+    match verbosity {
+        i16::MIN..=-2 => LevelFilter::ERROR,
+        -1 => LevelFilter::WARN,
+        0 => LevelFilter::INFO,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Install the global subscriber.
+///
+/// Terminal lines keep the CLI's colored tags and go to stdout (stderr in
+/// JSON mode, see [`crate::output::init`]). With `log_file`, every debug
+/// event (trace with `-vv`) is also written there with timestamps, levels and
+/// the enclosing `build`/`package`/`validate` spans, whatever the terminal level.
+pub fn init(terminal: LevelFilter, log_file: Option<&Path>) -> Result<()> {
+    let terminal_layer = tracing_subscriber::fmt::layer()
+        .event_format(TagFormat)
+        .with_writer(|| crate::stages::TerminalWriter)
+        .with_filter(own_targets(terminal));
+
+    let file_layer = match log_file {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create log file {}", path.display()))?;
+            let level = terminal.max(LevelFilter::DEBUG);
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file))
+                    .with_filter(own_targets(level)),
+            )
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(terminal_layer)
+        .with(file_layer)
+        .try_init()
+        .context("Failed to initialize logging")
+}
+
+fn own_targets(level: LevelFilter) -> Targets {
+    OWN_TARGETS
+        .iter()
+        .fold(Targets::new(), |targets, target| {
+            targets.with_target(*target, level)
+        })
+        .with_default(level.min(LevelFilter::WARN))
+}
+
+/// Formats events as `[TAG] message`, the CLI's human output style.
+struct TagFormat;
+
+impl<S, N> FormatEvent<S, N> for TagFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut fields = TagFields::default();
+        event.record(&mut fields);
+        let tag = match (fields.tag.as_deref(), *event.metadata().level()) {
+            (Some("OK"), _) => "[OK]".green().bold(),
+            (Some("DONE"), _) => "[DONE]".green().bold(),
+            (Some("VTX"), _) => "[VTX]".green().bold(),
+            (Some("KERNEL"), _) => "[kernel]".magenta().bold(),
+            (_, Level::ERROR) => "[ERROR]".red().bold(),
+            (_, Level::WARN) => "[WARN]".yellow(),
+            (_, Level::INFO) => "[INFO]".cyan(),
+            (_, Level::DEBUG) => "[DEBUG]".dimmed(),
+            (_, Level::TRACE) => "[TRACE]".dimmed(),
+        };
+        writeln!(writer, "{} {}", tag, fields.message)
+    }
+}
+
+/// The `message` and `tag` fields of an event; others are left to the log file.
+#[derive(Default)]
+struct TagFields {
+    message: String,
+    tag: Option<String>,
+}
+
+impl Visit for TagFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "tag" => self.tag = Some(value.to_string()),
+            "message" => self.message = value.to_string(),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        }
+    }
+}
@@ -1,17 +1,57 @@
+#[macro_use]
+mod logging;
+
+mod assets;
 mod builder;
 mod checker;
 mod cli;
+mod compare;
 mod config;
-mod packager;
+mod deprecations;
+mod devserver;
+mod history;
+mod i18n;
+mod kernel;
+mod ledger;
+mod metadata;
+mod offline;
+mod output;
 mod pipelines;
+mod registry;
+mod release;
+mod remote;
+mod sbom;
+mod scratch;
+mod settings;
+mod stages;
+mod storage;
 mod templates;
+mod timeutil;
+mod toolchain;
 
 use anyhow::Result;
-use clap::Parser;
-use cli::{Cli, Commands};
+use clap::{CommandFactory, FromArgMatches};
+use cli::{Cli, Commands, TestAction};
 use colored::*;
+use tracing_subscriber::filter::LevelFilter;
+use vtx_cli::{
+    adapter, cancel, compose, container, digest, features, harness, hex, packager, progress,
+    runtime, signing, wasmopt,
+};
+
 use pipelines::{
-    execute_build_pipeline, execute_check_pipeline, execute_init_pipeline, execute_package_pipeline,
+    execute_assets_pipeline, execute_attest_pipeline, execute_bench_pipeline,
+    execute_bisect_pipeline, execute_build_pipeline, execute_check_pipeline,
+    execute_clean_pipeline, execute_completions_pipeline, execute_dev_pipeline,
+    execute_diff_pipeline, execute_doctor_pipeline, execute_hash_pipeline, execute_init_pipeline,
+    execute_inspect_pipeline, execute_kernel_pipeline, execute_keygen_pipeline,
+    execute_ledger_pipeline, execute_lint_pipeline, execute_mangen_pipeline,
+    execute_manifest_pipeline, execute_package_pipeline, execute_publish_pipeline,
+    execute_pull_pipeline, execute_release_pipeline, execute_run_pipeline, execute_schema_pipeline,
+    execute_serve_pipeline, execute_sign_pipeline, execute_size_pipeline, execute_test_pipeline,
+    execute_test_scaffold_pipeline, execute_unpack_pipeline, execute_upgrade_pipeline,
+    execute_validate_pipeline, execute_verify_pipeline, execute_watch_pipeline,
+    execute_workspace_build_pipeline, BaselineMode, BisectPredicate, CleanScope, InitOptions,
 };
 
 /// VTX CLI Banner
@@ -26,16 +66,55 @@ __      __  _______  __   __
 
 /// CLI Entry Point
 fn main() -> Result<()> {
-    // Print the ASCII art banner first
-    println!("{}", BANNER.green().bold());
+    let matches = Cli::command().get_matches();
+    let command = matches.subcommand_name().unwrap_or_default().to_string();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    output::init(cli.output)?;
+    offline::init(cli.offline, cli.vendor_dir.clone());
+    let level = logging::terminal_level(cli.verbose, cli.quiet, cli.command.debug());
+    logging::init(level, cli.log_file.as_deref())?;
 
-    let cli = Cli::parse();
+    // Print the ASCII art banner first, unless stdout carries machine-readable output
+    if !cli.command.machine_output() && !output::is_json() && level >= LevelFilter::INFO {
+        println!("{}", BANNER.green().bold());
+    }
+
+    scratch::install_interrupt_handler();
 
     // Catch top-level errors to format them nicely and avoid showing Rust stack traces
-    if let Err(e) = run(cli) {
+    let start_time = std::time::Instant::now();
+    let result = run(cli);
+    let duration_ms = start_time.elapsed().as_millis() as u64;
+    if let Err(e) = result {
+        stages::clear();
+        let cancelled = e.downcast_ref::<cancel::Cancelled>().is_some();
+        output::emit(
+            "error",
+            serde_json::json!({
+                "command": command,
+                "message": e.to_string(),
+                "causes": e.chain().skip(1).map(ToString::to_string).collect::<Vec<_>>(),
+            }),
+        );
+        output::emit(
+            "result",
+            serde_json::json!({
+                "command": command,
+                "status": if cancelled { "interrupted" } else { "error" },
+                "duration_ms": duration_ms,
+            }),
+        );
+        if cancelled {
+            eprintln!("{} Interrupted", "[ERROR]".red().bold());
+            std::process::exit(130);
+        }
         eprintln!("{} {}", "[ERROR]".red().bold(), e);
         std::process::exit(1);
     }
+    output::emit(
+        "result",
+        serde_json::json!({ "command": command, "status": "ok", "duration_ms": duration_ms }),
+    );
 
     Ok(())
 }
@@ -44,22 +123,264 @@ fn main() -> Result<()> {
 fn run(cli: Cli) -> Result<()> {
     match cli.command {
         Commands::Build {
-            package,
+            members,
+            profile,
+            target,
+            release,
+            force,
+            debug,
+            options,
+        } if members.workspace => execute_workspace_build_pipeline(
+            &members,
+            profile.as_deref(),
+            &target,
+            release,
+            force,
+            debug,
+            &options,
+        ),
+        Commands::Build {
+            members,
+            profile,
             target,
             release,
             force,
             debug,
-        } => execute_build_pipeline(package, &target, release, force, debug),
-        Commands::Check { debug } => execute_check_pipeline(debug),
+            options,
+        } => execute_build_pipeline(
+            members.package,
+            profile.as_deref(),
+            &target,
+            release,
+            force,
+            debug,
+            &options,
+        ),
+        Commands::Watch {
+            target,
+            force,
+            debug,
+            debounce_ms,
+        } => execute_watch_pipeline(&target, force, debug, debounce_ms),
+        Commands::Dev {
+            target,
+            force,
+            debug,
+            debounce_ms,
+            kernel_url,
+        } => execute_dev_pipeline(&target, force, debug, debounce_ms, kernel_url.as_deref()),
+        Commands::Check {
+            members,
+            all_languages,
+            json,
+            refresh_deprecations,
+            ..
+        } => execute_check_pipeline(&members, all_languages, json, refresh_deprecations),
+        Commands::Doctor { json } => execute_doctor_pipeline(json),
+        Commands::Lint {
+            members,
+            deny,
+            json,
+        } => execute_lint_pipeline(&members, deny.is_some(), json),
+        Commands::Upgrade {
+            dry_run,
+            boilerplate,
+        } => execute_upgrade_pipeline(dry_run, boilerplate),
         Commands::Package {
             input,
             force,
             debug,
-        } => execute_package_pipeline(&input, debug, force),
+            packaging,
+        } => execute_package_pipeline(&input, debug, force, &packaging),
         Commands::Init {
             name,
             language,
             interactive,
-        } => execute_init_pipeline(name.as_deref(), language.as_deref(), interactive),
+            template,
+            author,
+            license,
+            with_release_script,
+        } => execute_init_pipeline(
+            name.as_deref(),
+            language.as_deref(),
+            interactive,
+            &InitOptions {
+                template: template.as_deref(),
+                author: author.as_deref(),
+                license: license.as_deref(),
+                with_release_script,
+            },
+        ),
+        Commands::Release {
+            resume,
+            yes,
+            skip_test,
+            skip_sign,
+            skip_sbom,
+            skip_publish,
+            dry_run,
+        } => {
+            let skip: Vec<&str> = [
+                ("test", skip_test),
+                ("sign", skip_sign),
+                ("sbom", skip_sbom),
+                ("publish", skip_publish),
+            ]
+            .into_iter()
+            .filter_map(|(step, skipped)| skipped.then_some(step))
+            .collect();
+            execute_release_pipeline(resume, yes, &skip, dry_run)
+        }
+        Commands::Bench {
+            action,
+            file,
+            method,
+            path,
+            query,
+            request,
+            api,
+            iterations,
+            check,
+            update_baseline,
+        } => execute_bench_pipeline(
+            action.as_ref(),
+            file.as_deref(),
+            harness::HttpRequest {
+                method,
+                path,
+                query,
+            },
+            request.as_deref(),
+            api.as_deref(),
+            iterations,
+            match (check, update_baseline) {
+                (true, _) => BaselineMode::Check,
+                (_, true) => BaselineMode::Update,
+                _ => BaselineMode::Compare,
+            },
+        ),
+        Commands::Bisect {
+            good,
+            bad,
+            build_fails: _,
+            max_size,
+            bench,
+            bench_request,
+            release,
+            force,
+            debug,
+        } => {
+            let predicate = match (max_size, bench) {
+                (Some(max), _) => BisectPredicate::MaxSize(max),
+                (_, Some(bench)) => BisectPredicate::bench(&bench, bench_request.as_deref())?,
+                _ => BisectPredicate::BuildFails,
+            };
+            execute_bisect_pipeline(&good, &bad, predicate, release, force, debug)
+        }
+        Commands::Hash {
+            files,
+            algorithm,
+            check,
+        } => execute_hash_pipeline(&files, &algorithm, check.as_deref()),
+        Commands::Inspect {
+            file,
+            provenance,
+            json,
+            key_file,
+        } => execute_inspect_pipeline(&file, provenance, json, key_file.as_deref()),
+        Commands::Validate {
+            file,
+            strict,
+            json,
+            key_file,
+        } => execute_validate_pipeline(&file, strict, json, key_file.as_deref()),
+        Commands::Diff { old, new, json } => execute_diff_pipeline(&old, &new, json),
+        Commands::Unpack {
+            file,
+            out_dir,
+            repair,
+        } => execute_unpack_pipeline(&file, &out_dir, repair),
+        Commands::Manifest { file } => execute_manifest_pipeline(&file),
+        Commands::Test {
+            action:
+                Some(TestAction::Scaffold {
+                    package,
+                    artifact,
+                    force,
+                }),
+            ..
+        } => execute_test_scaffold_pipeline(package.as_deref(), artifact.as_deref(), force),
+        Commands::Test {
+            action: None,
+            members,
+            target,
+            force,
+            tests,
+        } => execute_test_pipeline(&members, &target, force, &tests),
+        Commands::Run {
+            file,
+            method,
+            path,
+            query,
+            request,
+            api,
+        } => execute_run_pipeline(
+            &file,
+            harness::HttpRequest {
+                method,
+                path,
+                query,
+            },
+            request.as_deref(),
+            api.as_deref(),
+        ),
+        Commands::Serve {
+            file,
+            addr,
+            clock,
+            time,
+            api,
+        } => execute_serve_pipeline(&file, &addr, clock, time.as_deref(), api.as_deref()),
+        Commands::Publish { file } => execute_publish_pipeline(&file),
+        Commands::Pull { spec, out_dir } => execute_pull_pipeline(&spec, &out_dir),
+        Commands::Keygen { name, algorithm } => execute_keygen_pipeline(&name, algorithm),
+        Commands::Sign { file, key } => execute_sign_pipeline(&file, &key),
+        Commands::Verify {
+            files,
+            public_key,
+            manifest_only,
+            against,
+            jobs,
+        } => execute_verify_pipeline(
+            &files,
+            public_key.as_deref(),
+            manifest_only,
+            against.as_deref(),
+            jobs,
+        ),
+        Commands::Attest {
+            file,
+            public_key,
+            json,
+        } => execute_attest_pipeline(&file, public_key.as_deref(), json),
+        Commands::Kernel { action } => execute_kernel_pipeline(&action),
+        Commands::Ledger { action } => execute_ledger_pipeline(&action),
+        Commands::Assets { action } => execute_assets_pipeline(&action),
+        Commands::Size {
+            action,
+            artifact,
+            top,
+        } => execute_size_pipeline(action.as_ref(), artifact.as_deref(), top),
+        Commands::Clean {
+            artifacts_only,
+            all,
+        } => execute_clean_pipeline(match (artifacts_only, all) {
+            (true, _) => CleanScope::Artifacts,
+            (_, true) => CleanScope::All,
+            _ => CleanScope::Build,
+        }),
+        Commands::Schema { name } => execute_schema_pipeline(name.as_deref()),
+        Commands::Completions { shell } => execute_completions_pipeline(shell),
+        Commands::Mangen { out_dir } => execute_mangen_pipeline(out_dir.as_deref()),
     }
 }
@@ -0,0 +1,72 @@
+use serde::Serialize;
+use wasmtime::component::{ComponentType, Lift};
+
+/// `manifest` record returned by the plugin's `get-manifest` export.
+#[derive(ComponentType, Lift, Serialize, Debug, Clone, PartialEq, Eq)]
+#[component(record)]
+pub struct Manifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub entrypoint: String,
+}
+
+impl Manifest {
+    /// Check the fields the kernel relies on when loading the plugin.
+    ///
+    /// Rules:
+    /// - `id`: dot-separated segments of lowercase letters, digits, `-` and `_`,
+    ///   each starting with a letter (e.g. `vtx.my-plugin`).
+    /// - `name`: not blank.
+    /// - `version`: a semantic version (`1.2.3`, `1.0.0-beta.1`).
+    /// - `entrypoint`: an absolute URL path without query, fragment, `..` segments or whitespace.
+    ///
+    /// Returns one message per problem; empty when the manifest is valid.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !is_valid_id(&self.id) {
+            problems.push(format!(
+                "id '{}' must be dot-separated lowercase segments (letters, digits, '-', '_'), e.g. vtx.my-plugin",
+                self.id
+            ));
+        }
+        if self.name.trim().is_empty() {
+            problems.push("name must not be empty".to_string());
+        }
+        if let Err(e) = semver::Version::parse(&self.version) {
+            problems.push(format!(
+                "version '{}' is not a semantic version: {e}",
+                self.version
+            ));
+        }
+        if let Some(reason) = entrypoint_problem(&self.entrypoint) {
+            problems.push(format!("entrypoint '{}' {reason}", self.entrypoint));
+        }
+        problems
+    }
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.split('.').all(|segment| {
+            segment.starts_with(|c: char| c.is_ascii_lowercase())
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        })
+}
+
+fn entrypoint_problem(entrypoint: &str) -> Option<&'static str> {
+    if !entrypoint.starts_with('/') {
+        Some("must be an absolute path starting with '/'")
+    } else if entrypoint.contains(char::is_whitespace) {
+        Some("must not contain whitespace")
+    } else if entrypoint.contains(['?', '#']) {
+        Some("must not contain a query or fragment")
+    } else if entrypoint.split('/').any(|segment| segment == "..") {
+        Some("must not contain '..' segments")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,117 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::config::{CapabilitiesConfig, ProjectAuthor};
+use crate::container::PayloadEncoding;
+use crate::signing::Signature;
+
+/// Current metadata schema version written into `.vtx` containers.
+pub const METADATA_SCHEMA: u32 = 1;
+
+/// Metadata JSON embedded in `.vtx` v2 containers.
+///
+/// Fields are optional when reading so artifacts from older CLI versions still decode.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+#[serde(default)]
+pub struct VtxMetadata {
+    /// Metadata schema version.
+    pub schema: u32,
+
+    /// Plugin package name.
+    pub package: String,
+
+    /// Project language identifier.
+    pub language: String,
+
+    /// Plugin version declared by the author.
+    pub version: Option<String>,
+
+    /// Legacy single author field.
+    pub author: Option<String>,
+
+    /// Author list (PEP 621 style).
+    pub authors: Option<Vec<ProjectAuthor>>,
+
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub homepage: Option<String>,
+    pub repository: Option<String>,
+    pub keywords: Option<Vec<String>>,
+
+    /// SDK version detected from the language manifest or `[sdk] version`.
+    pub sdk_version: Option<String>,
+
+    /// SDK release channel from `[sdk] channel`.
+    pub sdk_channel: Option<String>,
+
+    /// WASI adapter injected when encoding: `reactor`, `command`, `proxy` or
+    /// `custom`. Absent when the input already was a component.
+    pub adapter: Option<String>,
+
+    /// Version of the injected WASI adapter: the bundled adapter's version, or
+    /// `sha256:<digest>` of a custom adapter. Absent when no adapter was injected.
+    pub adapter_version: Option<String>,
+
+    /// Localized string catalogs from `[i18n]`, keyed by locale, then by message key.
+    pub i18n: Option<BTreeMap<String, BTreeMap<String, String>>>,
+
+    /// JSON Schema of the plugin's user-facing settings, from `[settings] schema`.
+    pub settings_schema: Option<Value>,
+
+    /// Permissions requested in `[capabilities]`, enforced by the kernel
+    /// before the plugin is instantiated. Absent when they are stored in a
+    /// capabilities section instead.
+    pub capabilities: Option<CapabilitiesConfig>,
+
+    /// Digest of the capabilities section of v3 and v4 containers, which
+    /// holds the `[capabilities]` instead of the metadata.
+    pub capabilities_digest: Option<String>,
+
+    /// Digest of the asset section of v4 containers, which holds the files
+    /// declared in `vtx-assets.json` with pre-compressed variants.
+    pub assets_digest: Option<String>,
+
+    /// Host interfaces imported by the component, mapped to the required version
+    /// (`*` when unversioned). Lets kernels reject incompatible plugins without
+    /// decoding the component.
+    pub requires: Option<BTreeMap<String, String>>,
+
+    /// Signature over the rest of the metadata and the component.
+    /// Set by `vtx sign` or when `package.signing_key` is configured.
+    pub signature: Option<Signature>,
+
+    /// Signature over the rest of the metadata alone, set alongside
+    /// `signature`, so it can be verified without reading the component.
+    pub manifest_signature: Option<Signature>,
+
+    /// How the payload section is compressed or encrypted; absent for plain payloads.
+    pub payload: Option<PayloadEncoding>,
+
+    /// Build time (RFC 3339), recorded by reproducible builds from
+    /// `SOURCE_DATE_EPOCH` or the last commit time.
+    pub build_time: Option<String>,
+
+    /// Language-specific fields contributed by the builder.
+    pub builder: Option<Map<String, Value>>,
+
+    /// Tool that produced the artifact.
+    pub tool: ToolInfo,
+}
+
+/// Name and version of the producing tool.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ToolInfo {
+    pub name: String,
+    pub version: String,
+}
+
+impl Default for ToolInfo {
+    fn default() -> Self {
+        Self {
+            name: "vtx-cli".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Enables offline mode like `--offline` when set to a non-empty value other than `0`/`false`.
+pub const OFFLINE_ENV: &str = "VTX_OFFLINE";
+
+/// Vendor directory used when `--vendor-dir` is not given.
+pub const VENDOR_DIR_ENV: &str = "VTX_VENDOR_DIR";
+
+/// Offline mode and vendor directory, chosen once per process.
+#[derive(Debug, Default)]
+struct Settings {
+    offline: bool,
+    vendor_dir: Option<PathBuf>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Select offline mode and the vendor directory from the global flags,
+/// falling back to `VTX_OFFLINE` and `VTX_VENDOR_DIR`.
+pub fn init(offline: bool, vendor_dir: Option<PathBuf>) {
+    let offline = offline
+        || std::env::var(OFFLINE_ENV).is_ok_and(|v| !matches!(v.trim(), "" | "0" | "false"));
+    let vendor_dir = vendor_dir.or_else(|| std::env::var_os(VENDOR_DIR_ENV).map(PathBuf::from));
+    let _ = SETTINGS.set(Settings {
+        offline,
+        vendor_dir,
+    });
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+/// Whether network access is disabled for vtx and the toolchains it runs.
+pub fn is_offline() -> bool {
+    settings().offline
+}
+
+/// `<vendor dir>/<name>`, if a vendor directory is configured and has it.
+pub fn vendored(name: &str) -> Option<PathBuf> {
+    let path = settings().vendor_dir.as_ref()?.join(name);
+    path.exists().then_some(path)
+}
+
+/// Fail with an actionable message when `action` would need the network in offline mode.
+pub fn ensure_online(action: &str, hint: &str) -> Result<()> {
+    if is_offline() {
+        anyhow::bail!(
+            "{action} needs network access, but offline mode is on (--offline or {OFFLINE_ENV}). {hint}"
+        );
+    }
+    Ok(())
+}
+
+/// Environment for toolchain processes.
+///
+/// Offline: `CARGO_NET_OFFLINE`, `npm_config_offline`, `GOPROXY=off` and
+/// `PIP_NO_INDEX`, so package managers fail instead of downloading. From the
+/// vendor directory, when present: `npm/` as the npm cache, `wheels/` as pip
+/// find-links and `go/` as the Go module cache.
+pub fn toolchain_env() -> Vec<(String, String)> {
+    let mut env = Vec::new();
+    if is_offline() {
+        env.extend([
+            ("CARGO_NET_OFFLINE", "true".to_string()),
+            ("npm_config_offline", "true".to_string()),
+            ("GOPROXY", "off".to_string()),
+            ("PIP_NO_INDEX", "1".to_string()),
+        ]);
+    }
+    for (dir, key) in [
+        ("npm", "npm_config_cache"),
+        ("wheels", "PIP_FIND_LINKS"),
+        ("go", "GOMODCACHE"),
+    ] {
+        if let Some(path) = vendored(dir) {
+            env.push((key, absolute(&path)));
+        }
+    }
+    env.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+/// `cargo` arguments that keep it off the network in offline mode.
+pub fn cargo_args() -> &'static [&'static str] {
+    if is_offline() {
+        &["--offline"]
+    } else {
+        &[]
+    }
+}
+
+fn absolute(path: &Path) -> String {
+    std::path::absolute(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string()
+}
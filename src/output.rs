@@ -0,0 +1,187 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::progress::{ProgressSink, Stage};
+use crate::timeutil;
+
+/// Format selected with the global `--output` flag.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Colored, human-oriented messages.
+    #[default]
+    Human,
+    /// One JSON object per line on stdout; human messages move to stderr.
+    Json,
+}
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Where JSON lines go: the original stdout, saved before it is pointed at stderr.
+static JSON_SINK: Mutex<Option<File>> = Mutex::new(None);
+
+/// Select the output format for the rest of the process.
+///
+/// In JSON mode colors are disabled and, on Unix, file descriptor 1 is
+/// redirected to stderr so decorative prints and compiler output cannot
+/// interleave with the JSON lines. On other platforms human messages stay on stdout.
+pub fn init(format: OutputFormat) -> Result<()> {
+    let _ = FORMAT.set(format);
+    if format == OutputFormat::Json {
+        colored::control::set_override(false);
+        #[cfg(unix)]
+        {
+            *JSON_SINK.lock().unwrap_or_else(|e| e.into_inner()) = Some(redirect_stdout()?);
+        }
+    }
+    Ok(())
+}
+
+/// Whether `--output json` is active.
+pub fn is_json() -> bool {
+    FORMAT.get() == Some(&OutputFormat::Json)
+}
+
+/// One line of the `--output json` stream, as written by [`emit`].
+#[derive(Serialize, JsonSchema)]
+pub struct Event {
+    /// Event name, e.g. `stage`, `info`, `warning`, `artifact`, `report`,
+    /// `workspace`, `error` or `result`.
+    pub event: String,
+
+    /// When the event was emitted (RFC 3339, UTC).
+    pub time: String,
+
+    /// Fields of the event, which depend on its name.
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+/// Emit `{"event": <event>, "time": ..., <fields>}` as one line. No-op in human mode.
+pub fn emit(event: &str, fields: Value) {
+    if !is_json() {
+        return;
+    }
+    let line = Event {
+        event: event.to_string(),
+        time: timeutil::now_rfc3339(),
+        fields: match fields {
+            Value::Object(fields) => fields,
+            _ => Map::new(),
+        },
+    };
+    let Ok(line) = serde_json::to_string(&line) else {
+        return;
+    };
+    let line = format!("{line}\n");
+
+    let mut sink = JSON_SINK.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = match sink.as_mut() {
+        Some(file) => file.write_all(line.as_bytes()),
+        None => std::io::stdout().write_all(line.as_bytes()),
+    };
+}
+
+/// Print a report document: pretty JSON in human mode, a `report` event in JSON mode.
+pub fn document(command: &str, data: &impl Serialize) -> Result<()> {
+    if is_json() {
+        emit(
+            "report",
+            json!({ "command": command, "data": serde_json::to_value(data)? }),
+        );
+    } else {
+        println!("{}", serde_json::to_string_pretty(data)?);
+    }
+    Ok(())
+}
+
+/// Duplicate stdout for JSON lines and point file descriptor 1 at stderr.
+#[cfg(unix)]
+fn redirect_stdout() -> Result<File> {
+    use std::os::fd::FromRawFd;
+
+    std::io::stdout().flush()?;
+    // SAFETY: dup/dup2 on the process's standard descriptors; the duplicate is
+    // owned by the returned File and nothing else closes it.
+    unsafe {
+        let saved = libc::dup(libc::STDOUT_FILENO);
+        if saved < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        if libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+            let error = std::io::Error::last_os_error();
+            libc::close(saved);
+            return Err(error.into());
+        }
+        Ok(File::from_raw_fd(saved))
+    }
+}
+
+/// Reports library progress as `stage`, `info`, `warning` and `debug` events.
+#[derive(Default)]
+pub struct JsonProgress {
+    pub debug: bool,
+    started: Mutex<Vec<(Stage, Instant)>>,
+}
+
+impl JsonProgress {
+    pub fn new(debug: bool) -> Self {
+        Self {
+            debug,
+            ..Self::default()
+        }
+    }
+}
+
+impl ProgressSink for JsonProgress {
+    fn stage_started(&self, stage: Stage) {
+        if let Ok(mut started) = self.started.lock() {
+            started.push((stage, Instant::now()));
+        }
+        emit(
+            "stage",
+            json!({ "stage": stage.name(), "status": "started" }),
+        );
+    }
+
+    fn stage_finished(&self, stage: Stage) {
+        let elapsed = self.started.lock().ok().and_then(|mut started| {
+            let index = started.iter().rposition(|(s, _)| *s == stage)?;
+            Some(started.remove(index).1.elapsed())
+        });
+        emit(
+            "stage",
+            json!({
+                "stage": stage.name(),
+                "status": "finished",
+                "duration_ms": elapsed.map(|d| d.as_millis() as u64),
+            }),
+        );
+    }
+
+    fn bytes_processed(&self, stage: Stage, bytes: u64) {
+        if self.debug {
+            emit("bytes", json!({ "stage": stage.name(), "bytes": bytes }));
+        }
+    }
+
+    fn info(&self, message: &str) {
+        emit("info", json!({ "message": message }));
+    }
+
+    fn warning(&self, message: &str) {
+        emit("warning", json!({ "message": message }));
+    }
+
+    fn debug(&self, message: &str) {
+        if self.debug {
+            emit("debug", json!({ "message": message }));
+        }
+    }
+}
@@ -1,12 +1,156 @@
 use anyhow::{Context, Result};
-use colored::*;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use wasmparser::{Chunk, Encoding, Parser as WasmParser, Payload};
 use wit_component::ComponentEncoder;
 
-use wasi_preview1_component_adapter_provider::{
-    WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER,
-};
+use crate::adapter::{self, Adapter};
+use crate::cancel::CancelToken;
+use crate::compose::{self, Dependency};
+use crate::container;
+use crate::contract;
+use crate::dataopt;
+use crate::debuginfo::{self, DebugInfo};
+use crate::features::{self, FeaturePolicy};
+use crate::hex;
+use crate::progress::{Progress, Stage};
+use crate::startup;
+use crate::wasmopt::{self, WasmOpt};
+
+use wasi_preview1_component_adapter_provider::WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME;
+
+/// Version of the bundled `wasi-preview1-component-adapter-provider` crate,
+/// as resolved by Cargo (see build.rs).
+pub const BUNDLED_ADAPTER_VERSION: &str = env!("VTX_ADAPTER_VERSION");
+
+/// Options controlling [`process_wasm_with`].
+#[derive(Debug, Clone, Default)]
+pub struct PackageOptions {
+    /// Write intermediate modules into `scratch_dir`.
+    pub debug: bool,
+
+    /// Continue on contract validation failures.
+    pub force: bool,
+
+    /// Directory for intermediate files. The caller owns its lifetime.
+    pub scratch_dir: Option<PathBuf>,
+
+    /// Which WASI interfaces the artifact may depend on.
+    pub wasi_policy: WasiPolicy,
+
+    /// Receives stage, warning and debug events. Silent by default.
+    pub progress: Progress,
+
+    /// Checked between stages and while rewriting the module.
+    pub cancel: CancelToken,
+
+    /// Optional wasm proposals the artifact may use.
+    pub feature_policy: FeaturePolicy,
+
+    /// Trim zero padding from data segments before encoding.
+    pub optimize_data: bool,
+
+    /// Run `wasm-opt` on the core module before encoding.
+    pub wasm_opt: Option<WasmOpt>,
+
+    /// Move DWARF sections out of the core module into [`Packaged::debuginfo`].
+    pub split_debuginfo: bool,
+
+    /// Components composed into the encoded component before validation.
+    pub dependencies: Vec<Dependency>,
+
+    /// WASI preview1 adapter injected when encoding a core module.
+    pub adapter: Adapter,
+
+    /// Drop [`REPRODUCIBLE_STRIPPED_SECTIONS`] from the module and the
+    /// component so identical inputs give byte-identical output.
+    pub reproducible: bool,
+
+    /// Directory of packaged components keyed by input and options. When set,
+    /// an identical input skips every stage after reading. `None` disables caching.
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// Result of [`package_wasm`].
+#[derive(Debug, Clone)]
+pub struct Packaged {
+    pub component: Vec<u8>,
+
+    /// Split debug info, with `split_debuginfo` and a module that had DWARF sections.
+    pub debuginfo: Option<DebugInfo>,
+
+    /// Adapter injected during encoding; `None` when the input was a component.
+    pub adapter: Option<Adapter>,
+}
+
+/// Custom sections removed in reproducible mode: toolchain `producers` and a
+/// linker `build_id`, which can be random. A content-derived `build_id` is
+/// added back when debug info is split.
+pub const REPRODUCIBLE_STRIPPED_SECTIONS: &[&str] = &["producers", debuginfo::BUILD_ID_SECTION];
+
+/// WASI capability groups that can be allowed or denied.
+pub const WASI_CAPABILITIES: &[&str] = &[
+    "args",
+    "environ",
+    "clocks",
+    "random",
+    "stdio",
+    "filesystem",
+    "poll",
+    "proc",
+    "sockets",
+];
+
+/// Least-privilege policy over the WASI interfaces an artifact may import.
+///
+/// `allow`, when set, is an exhaustive list; `deny` always wins.
+#[derive(Debug, Clone, Default)]
+pub struct WasiPolicy {
+    pub allow: Option<Vec<String>>,
+    pub deny: Vec<String>,
+}
+
+impl WasiPolicy {
+    fn is_unrestricted(&self) -> bool {
+        self.allow.is_none() && self.deny.is_empty()
+    }
+
+    fn permits(&self, capability: &str) -> bool {
+        let allowed = self
+            .allow
+            .as_ref()
+            .is_none_or(|allow| allow.iter().any(|c| c == capability));
+        allowed && !self.deny.iter().any(|c| c == capability)
+    }
+
+    /// Reject capability names that are not in [`WASI_CAPABILITIES`].
+    pub fn validate(&self) -> Result<()> {
+        let names = self.allow.iter().flatten().chain(self.deny.iter());
+        for name in names {
+            if !WASI_CAPABILITIES.contains(&name.as_str()) {
+                anyhow::bail!(
+                    "Unknown WASI capability '{name}' (expected one of: {})",
+                    WASI_CAPABILITIES.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Core packaging flow: Wasm -> VTX Component.
+///
+/// Convenience wrapper around [`process_wasm_with`] without a scratch directory.
+pub fn process_wasm(input_wasm_path: &Path, debug: bool, force: bool) -> Result<Vec<u8>> {
+    process_wasm_with(
+        input_wasm_path,
+        &PackageOptions {
+            debug,
+            force,
+            ..Default::default()
+        },
+    )
+}
 
 /// Core packaging flow: Wasm -> VTX Component.
 ///
@@ -14,95 +158,529 @@ use wasi_preview1_component_adapter_provider::{
 /// 1. Read raw Wasm bytes.
 /// 2. Strip non-essential metadata.
 /// 3. Check user imports (warnings only).
-/// 4. Inject Reactor Adapter.
-/// 5. Encode into WebAssembly Component Model.
-/// 6. Validate exports contract.
+/// 4. Optimize with `wasm-opt` when `options.wasm_opt` is set.
+/// 5. Inject the WASI adapter the module's ABI needs (see [`adapter::detect_abi`]).
+/// 6. Encode into WebAssembly Component Model.
+/// 7. Validate exports contract.
 ///
 /// Parameters:
 /// - `input_wasm_path`: Raw Wasm file path.
-/// - `debug`: Whether to emit verbose logs.
-/// - `force`: Whether to continue on contract validation failures.
-pub fn process_wasm(input_wasm_path: &Path, debug: bool, force: bool) -> Result<Vec<u8>> {
+/// - `options`: Logging, force and scratch directory settings.
+///
+/// When `options.scratch_dir` is set and `debug` is enabled, intermediate
+/// modules are written there for inspection. A hit in `options.cache_dir`
+/// returns the cached component right after step 1, without intermediates.
+pub fn process_wasm_with(input_wasm_path: &Path, options: &PackageOptions) -> Result<Vec<u8>> {
+    package_wasm(input_wasm_path, options).map(|packaged| packaged.component)
+}
+
+/// [`process_wasm_with`], also returning the debug info split out of the module.
+pub fn package_wasm(input_wasm_path: &Path, options: &PackageOptions) -> Result<Packaged> {
+    let _span = tracing::info_span!("package", input = %input_wasm_path.display()).entered();
+    let progress = &options.progress;
+    options.wasi_policy.validate()?;
+
+    progress.stage_started(Stage::Read);
     let module_bytes = std::fs::read(input_wasm_path).with_context(|| {
         format!(
             "Failed to read raw wasm from: {}",
             input_wasm_path.display()
         )
     })?;
+    progress.bytes_processed(Stage::Read, module_bytes.len() as u64);
+    progress.stage_finished(Stage::Read);
+    options.cancel.check()?;
+
+    let cache_entry = options
+        .cache_dir
+        .as_ref()
+        .map(|dir| dir.join(format!("{}.wasm", cache_key(&module_bytes, options))));
+    if let Some(entry) = &cache_entry {
+        let adapter = adapter::detect_abi(&module_bytes)
+            .map_or(Some(options.adapter.clone()), |abi| {
+                abi.adapter(&options.adapter)
+            });
+        if let Some(cached) = read_cached(entry, adapter) {
+            progress.info(&format!(
+                "Packaging cache hit: {}; skipping packaging",
+                entry.display()
+            ));
+            return Ok(cached);
+        }
+    }
+
+    let packaged = package_module(module_bytes, options)?;
+    if let Some(entry) = &cache_entry {
+        if let Err(e) = store_cached(entry, &packaged) {
+            progress.warning(&format!("Failed to cache packaged component: {e:#}"));
+        }
+    }
+    Ok(packaged)
+}
+
+/// Steps 2-7 of [`process_wasm_with`].
+fn package_module(module_bytes: Vec<u8>, options: &PackageOptions) -> Result<Packaged> {
+    let force = options.force;
+    let progress = &options.progress;
+    let input_is_component = is_component(&module_bytes)
+        .with_context(|| "Failed to parse wasm header for component detection")?;
+
+    // Reject proposals the adapter and kernel cannot run before encoding trips over them.
+    let features = features::scan(&module_bytes)?;
+    let used = features.describe();
+    if !used.is_empty() {
+        progress.info(&format!("Wasm features: {}", used.join(", ")));
+    }
+    progress.info(&match features.simd_instructions() {
+        0 => "SIMD: not used (scalar)".to_string(),
+        n => format!("SIMD: used ({n} instructions)"),
+    });
+    features::ensure_supported(
+        &features,
+        &options.feature_policy,
+        BUNDLED_ADAPTER_VERSION,
+        progress,
+    )?;
+
+    // Instantiation-time work counts against the kernel's cold-start budget.
+    let startup = startup::audit(&module_bytes)?;
+    progress.info(&format!("Startup: {}", startup.summary()));
+    for warning in startup.warnings() {
+        progress.warning(&warning);
+    }
 
     // Fast path: already a component, skip adapter injection and encoding.
-    if is_component(&module_bytes)
-        .with_context(|| "Failed to parse wasm header for component detection")?
-    {
-        println!(
-            "{} Input is already a WebAssembly component; skipping adapter injection and encoding.",
-            "[INFO]".cyan()
+    if input_is_component {
+        progress.info(
+            "Input is already a WebAssembly component; skipping adapter injection and encoding.",
         );
 
-        validate_contract_with_force(&module_bytes, debug, force)?;
+        let component_bytes = compose_dependencies(module_bytes, options)?;
+        progress.stage_started(Stage::Validate);
+        tracing::info_span!("validate").in_scope(|| {
+            enforce_wasi_policy(&component_bytes, &options.wasi_policy)?;
+            validate_contract_with_force(&component_bytes, progress, force)
+        })?;
+        progress.stage_finished(Stage::Validate);
 
-        return Ok(module_bytes);
+        return Ok(Packaged {
+            component: component_bytes,
+            debuginfo: None,
+            adapter: None,
+        });
     }
 
     // Step 1: metadata cleanup.
     // The cleaned module represents the user's compiled core logic.
-    let cleaned_module = strip_exports_removed_bindgen_section(&module_bytes)?;
+    progress.stage_started(Stage::Clean);
+    let mut cleaned_module = strip_custom_sections(&module_bytes, &options.cancel, |name| {
+        (name.starts_with("component-type:wit-bindgen:")
+            && name.contains("with-all-of-its-exports-removed"))
+            || (options.reproducible && REPRODUCIBLE_STRIPPED_SECTIONS.contains(&name))
+    })?;
+    if options.optimize_data {
+        let (optimized, report) = dataopt::optimize(&cleaned_module, &options.cancel)?;
+        progress.info(&format!("Data optimization: {}", report.summary()));
+        cleaned_module = optimized;
+    }
+    write_intermediate(options, "cleaned.wasm", &cleaned_module)?;
 
     // Step 2: dependency safety scan (Import Check).
     // Even with force=false, this only warns to keep builds open.
-    validate_user_imports(&cleaned_module, debug);
+    validate_user_imports(&cleaned_module, progress);
+    enforce_wasi_policy(&cleaned_module, &options.wasi_policy)?;
+    progress.stage_finished(Stage::Clean);
+    options.cancel.check()?;
+
+    // Step 3: size optimization with Binaryen.
+    if let Some(opt) = &options.wasm_opt {
+        progress.stage_started(Stage::Optimize);
+        let temp_dir;
+        let work_dir = match &options.scratch_dir {
+            Some(dir) => dir.as_path(),
+            None => {
+                temp_dir = tempfile::tempdir().context("Failed to create optimizer work dir")?;
+                temp_dir.path()
+            }
+        };
+        let (optimized, report) = wasmopt::optimize(
+            &cleaned_module,
+            opt,
+            &options.feature_policy,
+            work_dir,
+            &options.cancel,
+        )?;
+        progress.info(&format!("wasm-opt -O{}: {}", opt.level, report.summary()));
+        progress.bytes_processed(Stage::Optimize, optimized.len() as u64);
+        progress.stage_finished(Stage::Optimize);
+        cleaned_module = optimized;
+        write_intermediate(options, "optimized.wasm", &cleaned_module)?;
+    }
 
-    // Step 3: adapter injection.
-    // VTX plugins must run in reactor mode, so inject the reactor adapter.
-    let adapter_bytes = WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER;
-    if debug {
-        println!("{} Injecting WASI Reactor Adapter", "[DEBUG]".dimmed());
+    // Step 4: debug info split. After wasm-opt, which drops DWARF unless run with -g.
+    let mut split_debuginfo = None;
+    if options.split_debuginfo {
+        match debuginfo::split(&cleaned_module, &options.cancel)? {
+            Some((stripped, info)) => {
+                progress.info(&format!("Debug info: {}", info.summary()));
+                cleaned_module = stripped;
+                split_debuginfo = Some(info);
+                write_intermediate(options, "stripped.wasm", &cleaned_module)?;
+            }
+            None => progress.info("Debug info: no DWARF sections to split"),
+        }
     }
 
-    // Step 4: component encoding.
-    let component_bytes = ComponentEncoder::default()
+    // Step 5: adapter injection.
+    // VTX plugins run in reactor mode unless another adapter is selected or
+    // the module's ABI calls for a different one (or none).
+    progress.stage_started(Stage::Encode);
+    let selected = adapter::detect_abi(&cleaned_module)?.adapter(&options.adapter);
+    let mut encoder = ComponentEncoder::default()
         .module(&cleaned_module)
-        .context("Failed to encode module into component")?
-        .adapter(WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, adapter_bytes)
-        .context("Failed to inject WASI preview1 adapter")?
-        .validate(true)
-        .encode()
-        .map_err(|e| {
-            anyhow::anyhow!(
-                "Component encoding error: {e}\nEnsure wit-bindgen version matches adapter requirements."
-            )
+        .context("Failed to encode module into component")?;
+    let loaded = match &selected {
+        Some(selected) => {
+            if *selected != options.adapter {
+                progress.info(&format!(
+                    "Module serves wasi:http/proxy; using the {selected} adapter instead of {}",
+                    options.adapter
+                ));
+            }
+            let loaded = selected.load()?;
+            progress.info(&format!(
+                "WASI adapter: {} ({})",
+                selected,
+                loaded.describe()
+            ));
+            if let Some(warning) = adapter::check_module(&cleaned_module, selected, &loaded)? {
+                progress.warning(&warning);
+            }
+            encoder = encoder
+                .adapter(WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, &loaded.bytes)
+                .context("Failed to inject WASI preview1 adapter")?;
+            Some(loaded)
+        }
+        None => {
+            progress.info(
+                "Module has no wasi_snapshot_preview1 imports (wasm32-wasip2); encoding without a WASI adapter.",
+            );
+            None
+        }
+    };
+
+    // Step 6: component encoding.
+    let component_bytes = encoder.validate(true).encode().map_err(|e| match (&selected, &loaded) {
+        (Some(selected), Some(loaded)) => anyhow::anyhow!(
+            "Component encoding error with the {} adapter ({}): {e}\nEnsure the module's wit-bindgen version matches the adapter, or select a matching adapter with --adapter.",
+            selected,
+            loaded.describe()
+        ),
+        _ => anyhow::anyhow!("Component encoding error: {e}"),
+    })?;
+
+    progress.bytes_processed(Stage::Encode, component_bytes.len() as u64);
+    progress.stage_finished(Stage::Encode);
+    options.cancel.check()?;
+    write_intermediate(options, "component.wasm", &component_bytes)?;
+
+    // Step 7: dependency composition.
+    let mut component_bytes = compose_dependencies(component_bytes, options)?;
+    if options.reproducible {
+        component_bytes = strip_custom_sections(&component_bytes, &options.cancel, |name| {
+            REPRODUCIBLE_STRIPPED_SECTIONS.contains(&name)
         })?;
+    }
 
-    // Step 5: contract validation (Export Check).
+    // Step 8: contract validation (Export Check).
     // Ensure the generated component matches VTX Kernel interfaces.
-    validate_contract_with_force(&component_bytes, debug, force)?;
+    // Dependencies may bring their own WASI imports, so the policy is checked again.
+    progress.stage_started(Stage::Validate);
+    tracing::info_span!("validate").in_scope(|| {
+        if !options.dependencies.is_empty() {
+            enforce_wasi_policy(&component_bytes, &options.wasi_policy)?;
+        }
+        validate_contract_with_force(&component_bytes, progress, force)
+    })?;
+    progress.stage_finished(Stage::Validate);
+
+    Ok(Packaged {
+        component: component_bytes,
+        debuginfo: split_debuginfo,
+        adapter: selected,
+    })
+}
+
+/// Link `options.dependencies` into the component; a no-op without dependencies.
+fn compose_dependencies(component_bytes: Vec<u8>, options: &PackageOptions) -> Result<Vec<u8>> {
+    if options.dependencies.is_empty() {
+        return Ok(component_bytes);
+    }
+    let progress = &options.progress;
+    progress.stage_started(Stage::Compose);
+    let temp_dir;
+    let work_dir = match &options.scratch_dir {
+        Some(dir) => dir.as_path(),
+        None => {
+            temp_dir = tempfile::tempdir().context("Failed to create composition work dir")?;
+            temp_dir.path()
+        }
+    };
+    let composed = compose::compose(&component_bytes, &options.dependencies, work_dir)?;
+    let names: Vec<&str> = options
+        .dependencies
+        .iter()
+        .map(|d| d.name.as_str())
+        .collect();
+    progress.info(&format!(
+        "Composed with {}: {} -> {} bytes",
+        names.join(", "),
+        component_bytes.len(),
+        composed.len()
+    ));
+    progress.bytes_processed(Stage::Compose, composed.len() as u64);
+    progress.stage_finished(Stage::Compose);
+    options.cancel.check()?;
+    write_intermediate(options, "composed.wasm", &composed)?;
+    Ok(composed)
+}
+
+/// Cache key: SHA-256 over the CLI and adapter versions, every option that
+/// affects the output, and the input module.
+fn cache_key(module_bytes: &[u8], options: &PackageOptions) -> String {
+    use sha2::{Digest, Sha256};
+
+    let settings = format!(
+        "{:?}",
+        (
+            env!("CARGO_PKG_VERSION"),
+            BUNDLED_ADAPTER_VERSION,
+            options.force,
+            &options.wasi_policy,
+            &options.feature_policy,
+            options.optimize_data,
+            &options.wasm_opt,
+            options.split_debuginfo,
+            &options.dependencies,
+            &options.adapter,
+            options.reproducible,
+        )
+    );
+    let mut hasher = Sha256::new();
+    hasher.update((settings.len() as u64).to_le_bytes());
+    hasher.update(settings.as_bytes());
+    // Dependencies and adapter files are keyed by content; an unreadable one
+    // fails packaging anyway.
+    let adapter_file = match &options.adapter {
+        Adapter::File(path) => Some(path),
+        _ => None,
+    };
+    let files = options.dependencies.iter().map(|d| &d.path);
+    for path in files.chain(adapter_file) {
+        let bytes = std::fs::read(path).unwrap_or_default();
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+    }
+    hasher.update(module_bytes);
+    hex::encode(&hasher.finalize())
+}
 
-    Ok(component_bytes)
+/// Read a cache entry: the component and, when one was split, its debug info.
+/// `adapter` is the one packaging would inject, as it is part of the cache key.
+fn read_cached(entry: &Path, adapter: Option<Adapter>) -> Option<Packaged> {
+    let component = std::fs::read(entry).ok()?;
+    if !is_component(&component).unwrap_or(false) {
+        return None;
+    }
+    let debuginfo = match std::fs::read(debuginfo_entry(entry)) {
+        Ok(bytes) => Some(DebugInfo::parse(&bytes).ok()?),
+        Err(_) => None,
+    };
+    Some(Packaged {
+        component,
+        debuginfo,
+        adapter,
+    })
+}
+
+/// Write a cache entry so concurrent builds never read a partial file.
+///
+/// The debug info is stored first, so a complete component entry always has
+/// its companion.
+fn store_cached(entry: &Path, packaged: &Packaged) -> Result<()> {
+    if let Some(info) = &packaged.debuginfo {
+        store_cache_file(&debuginfo_entry(entry), &info.module)?;
+    }
+    store_cache_file(entry, &packaged.component)
+}
+
+fn debuginfo_entry(entry: &Path) -> PathBuf {
+    entry.with_extension(debuginfo::DEBUGINFO_EXTENSION)
+}
+
+fn store_cache_file(entry: &Path, bytes: &[u8]) -> Result<()> {
+    let dir = entry.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let mut staged = tempfile::NamedTempFile::new_in(dir).context("Failed to stage cache entry")?;
+    std::io::Write::write_all(&mut staged, bytes).context("Failed to write cache entry")?;
+    staged
+        .persist(entry)
+        .with_context(|| format!("Failed to store {}", entry.display()))?;
+    Ok(())
 }
 
 /// Write a VTX format file.
+///
+/// The container is v4 with an `assets` section, v3 when only
+/// `capabilities_json` is given and v2 otherwise (see [`container::encode`]). With a scratch directory, the
+/// artifact is staged there first and moved into place, so an interrupted
+/// write never leaves a truncated `.vtx` behind.
 pub fn write_vtx_file(
     input_path: &Path,
     component_bytes: &[u8],
     metadata_json: &[u8],
+    capabilities_json: Option<&[u8]>,
+    assets: Option<&[u8]>,
+    scratch_dir: Option<&Path>,
 ) -> Result<PathBuf> {
     let out_path = input_path.with_extension("vtx");
-    let buf = vtx_format::encode_v2(component_bytes, metadata_json);
+    write_vtx_file_to(
+        &out_path,
+        component_bytes,
+        metadata_json,
+        capabilities_json,
+        assets,
+        scratch_dir,
+    )?;
+    Ok(out_path)
+}
 
-    std::fs::write(&out_path, buf)
-        .with_context(|| format!("Failed to write vtx artifact: {}", out_path.display()))?;
+/// Write a VTX format file at an explicit path, staged like [`write_vtx_file`].
+pub fn write_vtx_file_to(
+    out_path: &Path,
+    component_bytes: &[u8],
+    metadata_json: &[u8],
+    capabilities_json: Option<&[u8]>,
+    assets: Option<&[u8]>,
+    scratch_dir: Option<&Path>,
+) -> Result<()> {
+    let buf = container::encode(component_bytes, metadata_json, capabilities_json, assets);
+    write_output(out_path, &buf, scratch_dir)
+}
 
+/// Write the bare encoded component (no vtx container) next to the input.
+///
+/// The `.component.wasm` file can be loaded by any component host
+/// (wasmtime serve, Spin, wasmCloud) for interop testing.
+pub fn write_component_file(
+    input_path: &Path,
+    component_bytes: &[u8],
+    scratch_dir: Option<&Path>,
+) -> Result<PathBuf> {
+    let out_path = input_path.with_extension("component.wasm");
+    write_output(&out_path, component_bytes, scratch_dir)?;
     Ok(out_path)
 }
 
+/// Write split debug info next to the artifact as `<artifact>.dbg`.
+pub fn write_debuginfo_file(
+    vtx_path: &Path,
+    debuginfo: &DebugInfo,
+    scratch_dir: Option<&Path>,
+) -> Result<PathBuf> {
+    let mut out_path = vtx_path.as_os_str().to_owned();
+    out_path.push(".");
+    out_path.push(debuginfo::DEBUGINFO_EXTENSION);
+    let out_path = PathBuf::from(out_path);
+    write_output(&out_path, &debuginfo.module, scratch_dir)?;
+    Ok(out_path)
+}
+
+/// List the top-level export names of a component.
+pub fn component_exports(component_bytes: &[u8]) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for_each_top_level_payload(component_bytes, |payload| {
+        if let Payload::ComponentExportSection(reader) = payload {
+            for export in reader {
+                names.push(export?.name.0.to_string());
+            }
+        }
+        Ok(())
+    })?;
+    Ok(names)
+}
+
+/// List the top-level import names of a component.
+pub fn component_imports(component_bytes: &[u8]) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for_each_top_level_payload(component_bytes, |payload| {
+        if let Payload::ComponentImportSection(reader) = payload {
+            for import in reader {
+                names.push(import?.name.0.to_string());
+            }
+        }
+        Ok(())
+    })?;
+    Ok(names)
+}
+
+/// Map each imported interface of a component to its version.
+///
+/// `vtx:api/sql@1.0.0` becomes `vtx:api/sql -> 1.0.0`; unversioned imports map to `*`.
+pub fn required_interfaces(component_bytes: &[u8]) -> Result<BTreeMap<String, String>> {
+    Ok(component_imports(component_bytes)?
+        .into_iter()
+        .map(|name| match name.rsplit_once('@') {
+            Some((interface, version)) => (interface.to_string(), version.to_string()),
+            None => (name, "*".to_string()),
+        })
+        .collect())
+}
+
 // --- Internal helpers ---
 
+/// Write an output file, staging it in the scratch directory when available.
+///
+/// Staging and renaming means an interrupted write never leaves a truncated file.
+fn write_output(out_path: &Path, bytes: &[u8], scratch_dir: Option<&Path>) -> Result<()> {
+    let Some(dir) = scratch_dir else {
+        return std::fs::write(out_path, bytes)
+            .with_context(|| format!("Failed to write artifact: {}", out_path.display()));
+    };
+
+    let file_name = out_path.file_name().unwrap_or_default();
+    let staged = dir.join(file_name);
+    std::fs::write(&staged, bytes)
+        .with_context(|| format!("Failed to stage artifact: {}", staged.display()))?;
+    if std::fs::rename(&staged, out_path).is_err() {
+        // Cross-device moves cannot be renamed; fall back to a copy.
+        std::fs::copy(&staged, out_path)
+            .with_context(|| format!("Failed to write artifact: {}", out_path.display()))?;
+    }
+    Ok(())
+}
+
+/// Write an intermediate module into the scratch directory (debug mode only).
+fn write_intermediate(options: &PackageOptions, name: &str, bytes: &[u8]) -> Result<()> {
+    let (true, Some(dir)) = (options.debug, options.scratch_dir.as_ref()) else {
+        return Ok(());
+    };
+
+    let path = dir.join(name);
+    std::fs::write(&path, bytes)
+        .with_context(|| format!("Failed to write intermediate file: {}", path.display()))?;
+    options
+        .progress
+        .debug(&format!("Intermediate written: {}", path.display()));
+    Ok(())
+}
+
 /// Validate that user module imports are in the trusted allowlist.
 ///
 /// Purpose:
 /// Detect host function dependencies that the kernel may not support.
 /// Use a trust-but-verify approach and warn on unknown imports.
-fn validate_user_imports(module_bytes: &[u8], debug: bool) {
+fn validate_user_imports(module_bytes: &[u8], progress: &Progress) {
     let parser = WasmParser::new(0);
 
     // Trusted namespace prefixes for import modules.
@@ -125,36 +703,110 @@ fn validate_user_imports(module_bytes: &[u8], debug: bool) {
                 let is_trusted = trusted_namespaces.iter().any(|ns| module.starts_with(ns));
 
                 if !is_trusted {
-                    println!(
-                        "{} Unknown Import Detected: '{}::{}'\n  \
-                            {} This interface is not part of the standard VTX Kernel or WASI spec.\n  \
-                            If the kernel does not provide this host function, the plugin will crash at runtime.",
-                        "[WARN]".yellow(),
-                        module,
-                        field,
-                        "->".yellow()
-                    );
-                } else if debug {
-                    println!(
-                        "{} Trusted import: {}::{}",
-                        "[DEBUG]".dimmed(),
-                        module,
-                        field
-                    );
+                    progress.warning(&format!(
+                        "Unknown Import Detected: '{module}::{field}'\n  \
+                            -> This interface is not part of the standard VTX Kernel or WASI spec.\n  \
+                            If the kernel does not provide this host function, the plugin will crash at runtime."
+                    ));
+                } else {
+                    progress.debug(&format!("Trusted import: {module}::{field}"));
                 }
             }
         }
     }
 }
 
-fn validate_contract_with_force(component_bytes: &[u8], debug: bool, force: bool) -> Result<()> {
-    if let Err(e) = validate_contract(component_bytes, debug) {
+/// Map a WASI import to its capability group.
+///
+/// Core modules import preview1 functions (`wasi_snapshot_preview1::fd_write`);
+/// components import preview2 interfaces (`wasi:filesystem/types@0.2.0`).
+fn wasi_capability(module: &str, field: &str) -> Option<&'static str> {
+    if module == "wasi_snapshot_preview1" {
+        return Some(match field {
+            "args_get" | "args_sizes_get" => "args",
+            "environ_get" | "environ_sizes_get" => "environ",
+            "clock_res_get" | "clock_time_get" => "clocks",
+            "random_get" => "random",
+            "fd_read" | "fd_write" | "fd_close" | "fd_fdstat_get" => "stdio",
+            "poll_oneoff" => "poll",
+            "proc_exit" | "proc_raise" | "sched_yield" => "proc",
+            f if f.starts_with("sock_") => "sockets",
+            f if f.starts_with("fd_") || f.starts_with("path_") => "filesystem",
+            _ => return None,
+        });
+    }
+
+    let interface = module.strip_prefix("wasi:")?;
+    let (package, rest) = interface.split_once('/').unwrap_or((interface, ""));
+    let name = rest.split('@').next().unwrap_or_default();
+    Some(match (package, name) {
+        ("clocks", _) => "clocks",
+        ("random", _) => "random",
+        ("filesystem", _) => "filesystem",
+        ("sockets", _) => "sockets",
+        ("io", "poll") => "poll",
+        ("io", _) => "stdio",
+        ("cli", "environment") => "environ",
+        ("cli", "exit") => "proc",
+        ("cli", _) => "stdio",
+        _ => return None,
+    })
+}
+
+/// Fail when the module or component imports a WASI interface the policy denies.
+fn enforce_wasi_policy(bytes: &[u8], policy: &WasiPolicy) -> Result<()> {
+    if policy.is_unrestricted() {
+        return Ok(());
+    }
+
+    let mut violations = Vec::new();
+    for_each_top_level_payload(bytes, |payload| {
+        match payload {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    if let Some(cap) = wasi_capability(import.module, import.name) {
+                        if !policy.permits(cap) {
+                            violations.push(format!("{}::{} ({cap})", import.module, import.name));
+                        }
+                    }
+                }
+            }
+            Payload::ComponentImportSection(reader) => {
+                for import in reader {
+                    let name = import?.name.0;
+                    if let Some(cap) = wasi_capability(name, "") {
+                        if !policy.permits(cap) {
+                            violations.push(format!("{name} ({cap})"));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    if !violations.is_empty() {
+        anyhow::bail!(
+            "WASI Policy Violation: the artifact requires denied interfaces:\n  {}",
+            violations.join("\n  ")
+        );
+    }
+
+    Ok(())
+}
+
+fn validate_contract_with_force(
+    component_bytes: &[u8],
+    progress: &Progress,
+    force: bool,
+) -> Result<()> {
+    if let Err(e) = validate_contract(component_bytes, progress) {
         if force {
-            println!(
-                "{} Contract validation failed but --force is enabled: {}",
-                "[WARN]".yellow(),
-                e
-            );
+            progress.warning(&format!(
+                "Contract validation failed but --force is enabled: {e}"
+            ));
             return Ok(());
         }
         return Err(e);
@@ -164,7 +816,7 @@ fn validate_contract_with_force(component_bytes: &[u8], debug: bool, force: bool
 }
 
 /// Determine whether the input is already a WebAssembly Component.
-fn is_component(bytes: &[u8]) -> Result<bool> {
+pub fn is_component(bytes: &[u8]) -> Result<bool> {
     let parser = WasmParser::new(0);
 
     for payload in parser.parse_all(bytes) {
@@ -177,94 +829,75 @@ fn is_component(bytes: &[u8]) -> Result<bool> {
     Ok(false)
 }
 
-/// Validate that the generated component exports required kernel interfaces.
+/// Validate that the generated component conforms to the `vtx:api/plugin` world.
 ///
-/// Checks:
-/// 1. Export `handle` (HTTP entrypoint).
-/// 2. Export `get-manifest` (metadata entrypoint).
-fn validate_contract(component_bytes: &[u8], debug: bool) -> Result<()> {
-    let parser = WasmParser::new(0);
-    let mut found_handle = false;
-    let mut found_manifest = false;
-    let mut found_capabilities = false;
-
-    // Parse component exports.
-    for payload in parser.parse_all(component_bytes).flatten() {
-        if let Payload::ComponentExportSection(reader) = payload {
-            for export in reader {
-                let export = export?;
-                // Access the first tuple field to get the name.
-                let name = export.name.0;
-
-                if debug {
-                    println!("{} Found export: {}", "[DEBUG]".dimmed(), name);
-                }
-
-                // Check WIT-defined entrypoints.
-                // These names map to exports in the SDK `world plugin` definition.
-                match name {
-                    "handle" | "vtx:api/plugin/handle" | "vtx:api/plugin#handle" => {
-                        found_handle = true
-                    }
-                    "get-manifest"
-                    | "vtx:api/plugin/get-manifest"
-                    | "vtx:api/plugin#get-manifest" => found_manifest = true,
-                    "get-capabilities"
-                    | "vtx:api/plugin/get-capabilities"
-                    | "vtx:api/plugin#get-capabilities" => found_capabilities = true,
-                    _ => {}
-                }
-            }
-        }
+/// Export names are logged for `--debug`; signatures, resource types and kernel
+/// imports are checked against the bundled WIT by [`contract::check_plugin_world`].
+fn validate_contract(component_bytes: &[u8], progress: &Progress) -> Result<()> {
+    for name in component_exports(component_bytes)? {
+        progress.debug(&format!("Found export: {name}"));
     }
 
-    if !found_handle {
-        anyhow::bail!("Contract Violation: Missing required export 'handle'.\nEnsure you have implemented the Plugin trait and used 'vtx_sdk::export_plugin!(...)' macro.");
-    }
-    if !found_manifest {
-        anyhow::bail!("Contract Violation: Missing required export 'get-manifest'.");
-    }
-    if !found_capabilities {
-        anyhow::bail!("Contract Violation: Missing required export 'get-capabilities'.");
-    }
-
-    if debug {
-        println!("{} Contract validation passed.", "[INFO]".cyan());
-    }
+    contract::check_plugin_world(component_bytes)?;
+    progress.debug("Contract validation passed.");
 
     Ok(())
 }
 
-/// Remove specific custom sections generated by wit-bindgen.
-fn strip_exports_removed_bindgen_section(module: &[u8]) -> Result<Vec<u8>> {
+/// Remove the outermost custom sections whose name matches `drop`.
+///
+/// Nested modules and components of a component are copied unchanged; the
+/// parser already steps over their bodies.
+fn strip_custom_sections(
+    module: &[u8],
+    cancel: &CancelToken,
+    drop: impl Fn(&str) -> bool,
+) -> Result<Vec<u8>> {
     let mut out = Vec::with_capacity(module.len());
     let mut parser = WasmParser::new(0);
     let mut offset = 0usize;
 
     while offset < module.len() {
+        cancel.check()?;
         let chunk = parser.parse(&module[offset..], true)?;
         let (consumed, payload) = match chunk {
             Chunk::Parsed { consumed, payload } => (consumed, payload),
             _ => break,
         };
 
-        let raw = &module[offset..offset + consumed];
-        let mut keep = true;
-
-        if let Payload::CustomSection(cs) = &payload {
-            let name = cs.name();
-            if name.starts_with("component-type:wit-bindgen:")
-                && name.contains("with-all-of-its-exports-removed")
-            {
-                keep = false;
+        let end = match &payload {
+            Payload::ModuleSection {
+                unchecked_range, ..
             }
-        }
-
+            | Payload::ComponentSection {
+                unchecked_range, ..
+            } => unchecked_range.end,
+            _ => offset + consumed,
+        };
+        let keep = !matches!(&payload, Payload::CustomSection(cs) if drop(cs.name()));
         if keep {
-            out.extend_from_slice(raw);
+            out.extend_from_slice(&module[offset..end]);
         }
-        offset += consumed;
+        offset = end;
     }
 
     Ok(out)
 }
+
+/// Visit payloads of the outermost module/component, skipping nested ones.
+fn for_each_top_level_payload<'a>(
+    bytes: &'a [u8],
+    mut visit: impl FnMut(Payload<'a>) -> Result<()>,
+) -> Result<()> {
+    let mut depth = 0usize;
+    for payload in WasmParser::new(0).parse_all(bytes) {
+        let payload = payload?;
+        match payload {
+            Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => depth += 1,
+            Payload::End(_) => depth = depth.saturating_sub(1),
+            other if depth == 0 => visit(other)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::assets::{self, AssetManifest, ASSETS_MANIFEST};
+use crate::cli::AssetsAction;
+use crate::config;
+
+/// Manage the declared asset set in `vtx-assets.json`.
+///
+/// Runs from the vtx.toml directory; asset paths are relative to `assets.dir`.
+pub fn execute_assets_pipeline(action: &AssetsAction) -> Result<()> {
+    let project_config = config::load()?;
+    let assets_config = project_config.assets.as_ref();
+    let root = assets::assets_dir(assets_config);
+    let mut manifest = AssetManifest::load()?;
+
+    match action {
+        AssetsAction::List => {
+            if manifest.files.is_empty() {
+                info!("No assets declared in {}", ASSETS_MANIFEST);
+                return Ok(());
+            }
+            for file in &manifest.files {
+                println!("{:>10}  {}  {}", file.size, file.digest, file.path);
+            }
+            info!(
+                "{} assets, {} bytes total",
+                manifest.files.len(),
+                manifest.total_size()
+            );
+        }
+        AssetsAction::Add { paths } => {
+            let mut added = 0;
+            for path in paths {
+                let path = Path::new(path);
+                // Accept paths relative to the asset root or to vtx.toml.
+                let path = if path.starts_with(&root) {
+                    path.to_path_buf()
+                } else {
+                    root.join(path)
+                };
+                for rel in assets::collect_files(&root, &path)? {
+                    manifest.upsert(assets::entry_for(&root, &rel)?);
+                    added += 1;
+                }
+            }
+            let problems = assets::check_budgets(&manifest, assets_config);
+            if !problems.is_empty() {
+                anyhow::bail!("Asset Budget Exceeded:\n  {}", problems.join("\n  "));
+            }
+            manifest.save()?;
+            ok!(
+                "{} assets declared ({} bytes total)",
+                added,
+                manifest.total_size()
+            );
+        }
+        AssetsAction::Remove { paths } => {
+            for path in paths {
+                let rel = path
+                    .trim_start_matches(&format!("{}/", root.display()))
+                    .to_string();
+                if manifest.remove(&rel) == 0 {
+                    anyhow::bail!("Asset '{rel}' is not declared in {ASSETS_MANIFEST}");
+                }
+            }
+            manifest.save()?;
+            ok!(
+                "{} assets remain ({} bytes total)",
+                manifest.files.len(),
+                manifest.total_size()
+            );
+        }
+        AssetsAction::Verify => verify(&manifest, assets_config)?,
+    }
+
+    Ok(())
+}
+
+/// Fail on missing, modified or over-budget assets; warn on undeclared files.
+pub fn verify(
+    manifest: &AssetManifest,
+    assets_config: Option<&config::AssetsConfig>,
+) -> Result<()> {
+    let (problems, undeclared) = assets::verify(manifest, assets_config)?;
+    for path in &undeclared {
+        warn!(
+            "{} is in the asset directory but not declared (run `vtx assets add`)",
+            path
+        );
+    }
+    if !problems.is_empty() {
+        anyhow::bail!(
+            "Asset Verification Failed:\n  {}\nHint: Run `vtx assets add <path>` to re-declare changed files.",
+            problems.join("\n  ")
+        );
+    }
+    ok!(
+        "Assets intact: {} files, {} bytes",
+        manifest.files.len(),
+        manifest.total_size()
+    );
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+use super::sign::verification_key;
+use crate::digest::digest_matches;
+use crate::ledger::{self, sha256_digest, LEDGER_FILE};
+use crate::signing::SignaturePolicy;
+use crate::{container, output, sbom, signing};
+
+/// Outcome of one attestation check.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Fail,
+}
+
+/// One line of the attestation report.
+#[derive(Serialize, Debug)]
+struct Check {
+    check: &'static str,
+    status: Status,
+    detail: String,
+}
+
+impl Check {
+    fn from_result(check: &'static str, result: Result<String>) -> Self {
+        let (status, detail) = match result {
+            Ok(detail) => (Status::Pass, detail),
+            Err(e) => (Status::Fail, format!("{e:#}")),
+        };
+        Self {
+            check,
+            status,
+            detail,
+        }
+    }
+}
+
+/// JSON document printed by `vtx attest --json`.
+#[derive(Serialize)]
+struct AttestReport<'a> {
+    file: &'a str,
+    verified: bool,
+    checks: Vec<Check>,
+}
+
+/// Check the signature, SBOM and provenance of a `.vtx` together and give one
+/// verdict, so a deployment gate runs a single command.
+///
+/// Checks, each of which fails when its attestation is missing:
+/// - `signature`: the full and manifest signatures verify against
+///   `public_key` (or `package.public_key`) under the
+///   `package.signature_algorithms` policy, as with `vtx verify`.
+/// - `sbom`: an SBOM next to the artifact describes it, and the artifact
+///   still matches `<file>.sha256`.
+/// - `provenance`: the local ledger verifies and its newest entry for the
+///   artifact records the embedded metadata and signer.
+///
+/// Fails closed: any failed check fails the command.
+pub fn execute_attest_pipeline(file: &str, public_key: Option<&str>, json: bool) -> Result<()> {
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let (public_key, policy) = verification_key(public_key)?;
+    let metadata = container::decode_sections(&bytes)
+        .ok()
+        .and_then(|decoded| decoded.metadata);
+
+    let checks = vec![
+        Check::from_result(
+            "signature",
+            signature_check(&bytes, public_key.as_deref(), &policy),
+        ),
+        Check::from_result("sbom", sbom_check(Path::new(file), &bytes)),
+        Check::from_result("provenance", provenance_check(&bytes, metadata)),
+    ];
+    finish(file, json, checks)
+}
+
+/// Verify both signatures of the artifact; returns the signer.
+fn signature_check(
+    bytes: &[u8],
+    public_key: Option<&[u8]>,
+    policy: &SignaturePolicy,
+) -> Result<String> {
+    let public_key = public_key
+        .context("no public key: pass --public-key or set package.public_key in vtx.toml")?;
+    let decoded = container::decode_sections(bytes)
+        .map_err(|e| anyhow::anyhow!("not a valid .vtx container: {e}"))?;
+    let metadata = decoded
+        .metadata
+        .context("v1 container without metadata; it cannot be signed")?;
+    // The signatures cover the capabilities and asset sections through their digests.
+    container::verified_capabilities(&decoded)?;
+    if let Some(assets) = container::verified_assets(&decoded)? {
+        container::decode_assets(assets)?;
+    }
+    let signature = signing::verify(metadata, decoded.component, public_key, policy)?;
+    signing::verify_manifest(metadata, public_key, policy)?;
+    Ok(format!("signed by {}", signature.key_id))
+}
+
+/// Check that the SBOM next to `file` and `<file>.sha256` describe `bytes`.
+fn sbom_check(file: &Path, bytes: &[u8]) -> Result<String> {
+    let (document, format) =
+        sbom::find(file).context("no SBOM next to the artifact; package it with --sbom")?;
+    let content = std::fs::read(&document)
+        .with_context(|| format!("Failed to read {}", document.display()))?;
+    let parsed: Value = serde_json::from_slice(&content)
+        .with_context(|| format!("{} is not valid JSON", document.display()))?;
+    let artifact = sha256_digest(bytes);
+    let described = sbom::subject_digest(&parsed, format).with_context(|| {
+        format!(
+            "{} does not record the digest of the plugin",
+            document.display()
+        )
+    })?;
+    if described != artifact {
+        anyhow::bail!(
+            "{} describes {described}, but the artifact is {artifact}",
+            document.display()
+        );
+    }
+    let recorded = sbom::recorded_digest(file)?
+        .context("no .sha256 checksum next to the artifact; package it with --sbom")?;
+    if recorded != artifact {
+        anyhow::bail!("the .sha256 checksum records {recorded}, but the artifact is {artifact}");
+    }
+    Ok(format!(
+        "{} ({format}, {})",
+        document.display(),
+        sha256_digest(&content)
+    ))
+}
+
+/// Check that the ledger verifies and records where the artifact came from.
+fn provenance_check(bytes: &[u8], metadata: Option<&[u8]>) -> Result<String> {
+    let entries = ledger::read_entries(Path::new(LEDGER_FILE))?;
+    if entries.is_empty() {
+        anyhow::bail!("no ledger ({LEDGER_FILE}) records where the artifact came from");
+    }
+    ledger::verify(&entries)?;
+    let mut found = None;
+    for entry in entries.iter().rev() {
+        if digest_matches(&entry.artifact_digest, bytes)? {
+            found = Some(entry);
+            break;
+        }
+    }
+    let entry = found.with_context(|| format!("no entry in {LEDGER_FILE} records the artifact"))?;
+    let metadata = metadata.context("the artifact has no metadata to match the ledger entry")?;
+    if !digest_matches(&entry.metadata_digest, metadata)? {
+        anyhow::bail!(
+            "ledger entry #{} records other metadata than the artifact embeds",
+            entry.seq
+        );
+    }
+    let signer = signing::embedded_signature(metadata)?.map(|s| s.key_id);
+    if entry.signer != signer {
+        anyhow::bail!(
+            "ledger entry #{} records signer {}, but the artifact is signed by {}",
+            entry.seq,
+            entry.signer.as_deref().unwrap_or("none"),
+            signer.as_deref().unwrap_or("none")
+        );
+    }
+    Ok(format!(
+        "ledger #{} ({}, revision {})",
+        entry.seq,
+        entry.timestamp,
+        entry.source_revision.as_deref().unwrap_or("not recorded")
+    ))
+}
+
+fn finish(file: &str, json: bool, checks: Vec<Check>) -> Result<()> {
+    let failures = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if json || output::is_json() {
+        output::document(
+            "attest",
+            &AttestReport {
+                file,
+                verified: failures == 0,
+                checks,
+            },
+        )?;
+    } else {
+        step!("Attesting {}", file);
+        println!("\n{:<6} {:<12} DETAIL", "STATUS", "CHECK");
+        for c in &checks {
+            let status = match c.status {
+                Status::Pass => "PASS".green().bold(),
+                Status::Fail => "FAIL".red().bold(),
+            };
+            println!("{:<6} {:<12} {}", status, c.check, c.detail);
+        }
+        println!();
+    }
+
+    if failures > 0 {
+        anyhow::bail!("{file} failed {failures} attestation check(s)");
+    }
+    if !json && !output::is_json() {
+        done!(
+            "{} is attested: signature, SBOM and provenance verified",
+            file
+        );
+    }
+    Ok(())
+}
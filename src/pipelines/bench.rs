@@ -0,0 +1,456 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use super::run;
+use crate::cli::BenchAction;
+use crate::config::{self, BenchToleranceConfig};
+use crate::container::{self, PayloadKey};
+use crate::harness::{HarnessOptions, HttpRequest, Plugin};
+use crate::history::{self, BenchRecord, BENCH_HISTORY_FILE};
+use crate::{output, scratch};
+use vtx_cli::kernel_api::KernelProfile;
+
+/// Results `vtx bench --check` compares against, next to vtx.toml.
+pub const BASELINE_FILE: &str = "bench-baseline.json";
+
+const DEFAULT_ITERATIONS: u32 = 200;
+const DEFAULT_COLD_STARTS: u32 = 5;
+
+/// Calls made before timing, so one-time setup inside the plugin does not
+/// count as request latency.
+const WARMUP_CALLS: u32 = 5;
+
+const DEFAULT_COLD_START_TOLERANCE: f64 = 25.0;
+const DEFAULT_P99_TOLERANCE: f64 = 25.0;
+const DEFAULT_MEMORY_TOLERANCE: f64 = 10.0;
+
+/// What a `vtx bench` run does with bench-baseline.json.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaselineMode {
+    /// Compare with it, if present, and warn about regressions (the default).
+    Compare,
+    /// Fail on any regression, or when there is no baseline.
+    Check,
+    /// Replace it with this run.
+    Update,
+}
+
+/// One `vtx bench` run; bench-baseline.json holds one as well.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchResult {
+    pub request: HttpRequest,
+    pub iterations: u32,
+    /// Median time to compile and instantiate the component.
+    pub cold_start_ms: f64,
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+    /// Linear memory the plugin allocated by the last call.
+    pub memory_bytes: u64,
+}
+
+/// One metric compared with the baseline.
+#[derive(Serialize, Debug)]
+struct MetricCheck {
+    metric: &'static str,
+    baseline: f64,
+    current: f64,
+    change_percent: f64,
+    tolerance_percent: f64,
+    regressed: bool,
+}
+
+/// JSON document printed by `vtx bench`.
+#[derive(Serialize)]
+struct BenchReport<'a> {
+    file: &'a str,
+    result: &'a BenchResult,
+    /// Empty without a baseline.
+    checks: &'a [MetricCheck],
+}
+
+/// Measure a built plugin and compare it with bench-baseline.json, or list
+/// earlier runs with `vtx bench history`.
+///
+/// Behavior:
+/// - The cold start is the median of `[bench] cold_starts` fresh
+///   instantiations in the `vtx run` harness; latency is timed over
+///   `iterations` calls of `handle` on the last instance, after
+///   [`WARMUP_CALLS`] untimed ones; memory is what that instance allocated.
+/// - A baseline, if present, is compared metric by metric against the
+///   `[bench.tolerance]` percentages (see [`BaselineMode`]).
+/// - Every run is appended to the bench history with the git commit.
+pub fn execute_bench_pipeline(
+    action: Option<&BenchAction>,
+    file: Option<&str>,
+    request: HttpRequest,
+    request_file: Option<&str>,
+    kernel_api: Option<&str>,
+    iterations: Option<u32>,
+    mode: BaselineMode,
+) -> Result<()> {
+    if let Some(BenchAction::History {
+        metric,
+        file,
+        limit,
+        csv,
+    }) = action
+    {
+        return print_history(metric, file.as_deref(), *limit, *csv);
+    }
+    let Some(file) = file else {
+        anyhow::bail!("Expected a file to benchmark or a subcommand (see vtx bench --help)");
+    };
+    let check = mode == BaselineMode::Check;
+    let update_baseline = mode == BaselineMode::Update;
+    let settings = config::load_optional()?
+        .and_then(|config| config.bench)
+        .unwrap_or_default();
+    let request = run::load_request(request, request_file)?;
+    let kernel_api = kernel_api.map(KernelProfile::resolve).transpose()?;
+    let iterations = iterations
+        .or(settings.iterations)
+        .unwrap_or(DEFAULT_ITERATIONS);
+    if iterations == 0 {
+        anyhow::bail!("--iterations must be at least 1");
+    }
+    let cold_starts = settings.cold_starts.unwrap_or(DEFAULT_COLD_STARTS);
+
+    let baseline = read_baseline()?;
+    if check && baseline.is_none() {
+        anyhow::bail!(
+            "No {BASELINE_FILE} to check against; record one with `vtx bench {file} --update-baseline`"
+        );
+    }
+    if let (true, Some(baseline)) = (check, &baseline) {
+        if baseline.request != request {
+            anyhow::bail!(
+                "{BASELINE_FILE} was recorded for {} {}, not {} {}; bench the same request or update the baseline",
+                baseline.request.method,
+                run::target(&baseline.request),
+                request.method,
+                run::target(&request)
+            );
+        }
+    }
+
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let component = container::unwrap_component(bytes, PayloadKey::load(None)?.as_ref())?;
+    let options = HarnessOptions {
+        kernel_api,
+        ..HarnessOptions::default()
+    };
+    if !output::is_json() {
+        step!(
+            "Benchmarking {} {} on {} ({} cold starts, {} calls)",
+            request.method,
+            run::target(&request),
+            file,
+            cold_starts,
+            iterations
+        );
+    }
+    let result = measure(&component, request, &options, cold_starts, iterations)?;
+    history::record_bench(BenchRecord {
+        timestamp: String::new(),
+        commit: None,
+        dirty: false,
+        file: file.to_string(),
+        request: format!("{} {}", result.request.method, run::target(&result.request)),
+        iterations: result.iterations,
+        cold_start_ms: result.cold_start_ms,
+        p50_ms: result.p50_ms,
+        p99_ms: result.p99_ms,
+        memory_bytes: result.memory_bytes,
+    })?;
+
+    let checks = match (&baseline, update_baseline) {
+        (Some(baseline), false) => {
+            compare(baseline, &result, &settings.tolerance.unwrap_or_default())
+        }
+        _ => Vec::new(),
+    };
+    if output::is_json() {
+        output::document(
+            "bench",
+            &BenchReport {
+                file,
+                result: &result,
+                checks: &checks,
+            },
+        )?;
+    } else {
+        print_report(&result, &checks);
+        if baseline.is_none() && !update_baseline {
+            info!("No {BASELINE_FILE} to compare with; record one with --update-baseline");
+        }
+    }
+
+    if update_baseline {
+        let json = serde_json::to_string_pretty(&result)?;
+        std::fs::write(BASELINE_FILE, format!("{json}\n"))
+            .with_context(|| format!("Failed to write {BASELINE_FILE}"))?;
+        done!("Recorded {} as the bench baseline", file);
+        return Ok(());
+    }
+    let regressed: Vec<String> = checks
+        .iter()
+        .filter(|c| c.regressed)
+        .map(describe_regression)
+        .collect();
+    match (regressed.is_empty(), check) {
+        (true, true) => done!("{} is within its bench budgets", file),
+        (true, false) => {}
+        (false, true) => anyhow::bail!(
+            "{} regressed beyond tolerance versus {BASELINE_FILE}: {}",
+            file,
+            regressed.join("; ")
+        ),
+        (false, false) => warn!("Regressed versus {BASELINE_FILE}: {}", regressed.join("; ")),
+    }
+    Ok(())
+}
+
+/// List recorded runs with the change of `metric` and its sparkline.
+fn print_history(metric: &str, file: Option<&str>, limit: Option<usize>, csv: bool) -> Result<()> {
+    let mut records: Vec<_> = history::read_bench()?
+        .into_iter()
+        .filter(|r| file.is_none_or(|f| r.file == f))
+        .collect();
+    if let Some(limit) = limit {
+        let skip = records.len().saturating_sub(limit);
+        records.drain(..skip);
+    }
+
+    if csv {
+        println!("timestamp,commit,dirty,file,request,iterations,cold_start_ms,p50_ms,p99_ms,memory_bytes");
+        for r in &records {
+            println!(
+                "{},{},{},{},{},{},{},{},{},{}",
+                r.timestamp,
+                r.commit.as_deref().unwrap_or_default(),
+                r.dirty,
+                r.file,
+                r.request,
+                r.iterations,
+                r.cold_start_ms,
+                r.p50_ms,
+                r.p99_ms,
+                r.memory_bytes
+            );
+        }
+        return Ok(());
+    }
+
+    if records.is_empty() {
+        info!("No bench runs recorded in {}", BENCH_HISTORY_FILE);
+        return Ok(());
+    }
+
+    println!(
+        "{:<20}  {:<10} {:>12} {:>10} {:>10} {:>10} {:>10}",
+        "TIMESTAMP", "COMMIT", "COLD START", "P50", "P99", "MEMORY", "CHANGE"
+    );
+    let values: Vec<f64> = records
+        .iter()
+        .map(|r| r.metric(metric).unwrap_or_default())
+        .collect();
+    let mut previous: Option<f64> = None;
+    for (r, value) in records.iter().zip(&values) {
+        let change = previous
+            .filter(|p| *p > 0.0)
+            .map(|p| format!("{:+.1}%", (value - p) * 100.0 / p))
+            .unwrap_or_default();
+        println!(
+            "{:<20}  {:<10} {:>9.3} ms {:>7.3} ms {:>7.3} ms {:>8} B {:>10}",
+            r.timestamp,
+            history::describe_commit(r.commit.as_deref(), r.dirty),
+            r.cold_start_ms,
+            r.p50_ms,
+            r.p99_ms,
+            r.memory_bytes,
+            change
+        );
+        previous = Some(*value);
+    }
+    info!(
+        "{} runs, {}: {}",
+        records.len(),
+        metric,
+        history::sparkline(&values)
+    );
+    Ok(())
+}
+
+/// The committed baseline, if any.
+fn read_baseline() -> Result<Option<BenchResult>> {
+    match std::fs::read_to_string(BASELINE_FILE) {
+        Ok(content) => serde_json::from_str(&content)
+            .map(Some)
+            .with_context(|| format!("Malformed {BASELINE_FILE}")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {BASELINE_FILE}")),
+    }
+}
+
+fn measure(
+    component: &[u8],
+    request: HttpRequest,
+    options: &HarnessOptions,
+    cold_starts: u32,
+    iterations: u32,
+) -> Result<BenchResult> {
+    let cancel = scratch::interrupt_token();
+    let mut instantiations = Vec::new();
+    let mut plugin = None;
+    for _ in 0..cold_starts {
+        cancel.check()?;
+        let started = Instant::now();
+        plugin = Some(Plugin::instantiate(component, options, &cancel)?);
+        instantiations.push(started.elapsed());
+    }
+    let mut plugin = plugin.context("No cold start was measured")?;
+
+    for _ in 0..WARMUP_CALLS {
+        let response = plugin.handle(&request, &cancel)?;
+        if response.status >= 400 {
+            warn!(
+                "handle answered {}; the timings are of that error response",
+                response.status
+            );
+            break;
+        }
+    }
+    let mut latencies = Vec::new();
+    for _ in 0..iterations {
+        let started = Instant::now();
+        plugin.handle(&request, &cancel)?;
+        latencies.push(started.elapsed());
+    }
+
+    instantiations.sort();
+    latencies.sort();
+    Ok(BenchResult {
+        request,
+        iterations,
+        cold_start_ms: millis(percentile(&instantiations, 50.0)),
+        p50_ms: millis(percentile(&latencies, 50.0)),
+        p99_ms: millis(percentile(&latencies, 99.0)),
+        memory_bytes: plugin.memory_bytes() as u64,
+    })
+}
+
+/// Nearest-rank percentile of sorted, non-empty `values`.
+fn percentile(values: &[Duration], percent: f64) -> Duration {
+    let rank = (percent / 100.0 * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Each budgeted metric of `current` against `baseline`.
+fn compare(
+    baseline: &BenchResult,
+    current: &BenchResult,
+    tolerance: &BenchToleranceConfig,
+) -> Vec<MetricCheck> {
+    [
+        (
+            "cold_start_ms",
+            baseline.cold_start_ms,
+            current.cold_start_ms,
+            tolerance
+                .cold_start_percent
+                .unwrap_or(DEFAULT_COLD_START_TOLERANCE),
+        ),
+        (
+            "p99_ms",
+            baseline.p99_ms,
+            current.p99_ms,
+            tolerance.p99_percent.unwrap_or(DEFAULT_P99_TOLERANCE),
+        ),
+        (
+            "memory_bytes",
+            baseline.memory_bytes as f64,
+            current.memory_bytes as f64,
+            tolerance.memory_percent.unwrap_or(DEFAULT_MEMORY_TOLERANCE),
+        ),
+    ]
+    .into_iter()
+    .map(|(metric, baseline, current, tolerance)| MetricCheck {
+        metric,
+        baseline,
+        current,
+        change_percent: if baseline > 0.0 {
+            (current - baseline) * 100.0 / baseline
+        } else {
+            0.0
+        },
+        tolerance_percent: tolerance,
+        regressed: current > baseline * (1.0 + tolerance / 100.0),
+    })
+    .collect()
+}
+
+/// A metric value with its unit.
+fn format_value(metric: &str, value: f64) -> String {
+    if metric == "memory_bytes" {
+        format!("{value:.0} B")
+    } else {
+        format!("{value:.3} ms")
+    }
+}
+
+fn describe_regression(check: &MetricCheck) -> String {
+    format!(
+        "{} {} -> {} ({:+.1}%, tolerance {}%)",
+        check.metric,
+        format_value(check.metric, check.baseline),
+        format_value(check.metric, check.current),
+        check.change_percent,
+        check.tolerance_percent
+    )
+}
+
+fn print_report(result: &BenchResult, checks: &[MetricCheck]) {
+    println!(
+        "\n{:<14} {:>14} {:>14} {:>9} {:>9}",
+        "METRIC", "CURRENT", "BASELINE", "CHANGE", "BUDGET"
+    );
+    let rows = [
+        ("cold_start_ms", result.cold_start_ms),
+        ("p50_ms", result.p50_ms),
+        ("p99_ms", result.p99_ms),
+        ("memory_bytes", result.memory_bytes as f64),
+    ];
+    for (metric, value) in rows {
+        let current = format_value(metric, value);
+        match checks.iter().find(|c| c.metric == metric) {
+            Some(check) => {
+                let change = format!("{:+.1}%", check.change_percent);
+                let budget = format!("+{}%", check.tolerance_percent);
+                println!(
+                    "{:<14} {:>14} {:>14} {:>9} {:>9}",
+                    metric,
+                    current,
+                    format_value(metric, check.baseline),
+                    if check.regressed {
+                        change.red().bold()
+                    } else {
+                        change.green()
+                    },
+                    budget
+                );
+            }
+            None => println!(
+                "{:<14} {:>14} {:>14} {:>9} {:>9}",
+                metric, current, "-", "", ""
+            ),
+        }
+    }
+    println!();
+}
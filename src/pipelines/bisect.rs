@@ -0,0 +1,265 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::info;
+
+use crate::history::{self, BENCH_HISTORY_FILE, SIZE_HISTORY_FILE};
+use crate::ledger::{self, LEDGER_FILE};
+use crate::{config, scratch};
+
+/// What makes a commit "bad" during `vtx bisect`.
+pub enum BisectPredicate {
+    /// `vtx build` fails.
+    BuildFails,
+    /// The built artifact is larger than this many bytes.
+    MaxSize(u64),
+    /// `vtx bench` of the built artifact measures `metric` above `threshold`.
+    Bench {
+        metric: String,
+        threshold: f64,
+        /// Request file passed to `vtx bench --request`.
+        request: Option<PathBuf>,
+    },
+}
+
+impl BisectPredicate {
+    /// The predicate of `--bench <metric>=<threshold>`, e.g. `p99_ms=5`.
+    pub fn bench(spec: &str, request: Option<&str>) -> Result<Self> {
+        let (metric, threshold) = spec.split_once('=').with_context(|| {
+            format!("--bench expects <metric>=<threshold>, e.g. p99_ms=5, got '{spec}'")
+        })?;
+        if !history::BENCH_METRICS.contains(&metric) {
+            anyhow::bail!(
+                "Unknown bench metric '{metric}' (expected one of: {})",
+                history::BENCH_METRICS.join(", ")
+            );
+        }
+        let threshold: f64 = threshold
+            .parse()
+            .ok()
+            .filter(|t: &f64| t.is_finite())
+            .with_context(|| format!("--bench threshold '{threshold}' is not a number"))?;
+        // Children run in project.path, possibly at another checkout.
+        let request = request
+            .map(|path| {
+                std::fs::canonicalize(path)
+                    .with_context(|| format!("Failed to resolve request file: {path}"))
+            })
+            .transpose()?;
+        Ok(Self::Bench {
+            metric: metric.to_string(),
+            threshold,
+            request,
+        })
+    }
+}
+
+enum Verdict {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl Verdict {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Good => "good",
+            Verdict::Bad => "bad",
+            Verdict::Skip => "skip",
+        }
+    }
+}
+
+/// Drive `git bisect` between `good` and `bad`, running `vtx build` at each step.
+///
+/// Behavior:
+/// - Runs from the vtx.toml directory; tracked files must be unmodified.
+/// - Each step builds in a child `vtx build` process, so config changes between
+///   commits are picked up. Build output is hidden unless `debug` is set.
+/// - With [`BisectPredicate::MaxSize`] and [`BisectPredicate::Bench`], commits
+///   that fail to build are skipped. A bench runs in a child `vtx bench` on
+///   the artifact the build recorded in the ledger; commits whose plugin
+///   fails the bench are skipped too.
+/// - `git bisect reset` always runs at the end, restoring the original checkout.
+pub fn execute_bisect_pipeline(
+    good: &str,
+    bad: &str,
+    predicate: BisectPredicate,
+    release: bool,
+    force: bool,
+    debug: bool,
+) -> Result<()> {
+    if !git_output(&["status", "--porcelain", "--untracked-files=no"])?
+        .trim()
+        .is_empty()
+    {
+        anyhow::bail!(
+            "Working tree has uncommitted changes; commit or stash them before `vtx bisect`."
+        );
+    }
+
+    let start = git_output(&["bisect", "start", bad, good])?;
+    step!(
+        "Bisecting {}..{}: {}",
+        good,
+        bad,
+        start.lines().last().unwrap_or_default()
+    );
+
+    let result = bisect_loop(&predicate, release, force, debug);
+    let reset = git_output(&["bisect", "reset"]);
+    let culprit = result?;
+    reset?;
+
+    match culprit {
+        Some(report) => {
+            done!("First bad commit:");
+            println!("{report}");
+            Ok(())
+        }
+        None => {
+            anyhow::bail!("Bisect could not isolate a single commit (too many skipped commits).")
+        }
+    }
+}
+
+/// Test commits until git names the first bad one. Returns git's report.
+fn bisect_loop(
+    predicate: &BisectPredicate,
+    release: bool,
+    force: bool,
+    debug: bool,
+) -> Result<Option<String>> {
+    loop {
+        scratch::interrupt_token().check()?;
+        let head = git_output(&["rev-parse", "--short", "HEAD"])?;
+        let verdict = evaluate(predicate, release, force, debug)?;
+        info!("{} -> {}", head.trim(), verdict.as_str());
+
+        let output = git_output(&["bisect", verdict.as_str()])?;
+        if output.contains("is the first bad commit") {
+            return Ok(Some(output.trim_end().to_string()));
+        }
+        if output.contains("only 'skip'ped commits left") {
+            return Ok(None);
+        }
+    }
+}
+
+fn evaluate(
+    predicate: &BisectPredicate,
+    release: bool,
+    force: bool,
+    debug: bool,
+) -> Result<Verdict> {
+    let exe = std::env::current_exe().context("Failed to locate the vtx executable")?;
+    let mut build = Command::new(exe);
+    build.arg("build");
+    if release {
+        build.arg("--release");
+    }
+    if force {
+        build.arg("--force");
+    }
+    if !debug {
+        build.stdout(Stdio::null()).stderr(Stdio::null());
+    }
+    let success = build.status().context("Failed to run vtx build")?.success();
+
+    Ok(match (predicate, success) {
+        (BisectPredicate::BuildFails, true) => Verdict::Good,
+        (BisectPredicate::BuildFails, false) => Verdict::Bad,
+        (BisectPredicate::MaxSize(_), false) => Verdict::Skip,
+        (BisectPredicate::MaxSize(max), true) => match last_artifact_size()? {
+            Some(size) if size > *max => Verdict::Bad,
+            Some(_) => Verdict::Good,
+            None => Verdict::Skip,
+        },
+        (BisectPredicate::Bench { .. }, false) => Verdict::Skip,
+        (
+            BisectPredicate::Bench {
+                metric,
+                threshold,
+                request,
+            },
+            true,
+        ) => match bench_metric(metric, request.as_deref(), debug)? {
+            Some(value) if value > *threshold => Verdict::Bad,
+            Some(_) => Verdict::Good,
+            None => Verdict::Skip,
+        },
+    })
+}
+
+/// Bench the artifact of the build that just ran and return `metric`, or
+/// `None` when there is no artifact or the bench failed.
+fn bench_metric(metric: &str, request: Option<&Path>, debug: bool) -> Result<Option<f64>> {
+    let entries = in_project_dir(|| ledger::read_entries(Path::new(LEDGER_FILE)))
+        .with_context(|| format!("Failed to read {LEDGER_FILE}"))?;
+    let Some(artifact) = entries.last().map(|entry| entry.artifact.clone()) else {
+        return Ok(None);
+    };
+
+    let exe = std::env::current_exe().context("Failed to locate the vtx executable")?;
+    let mut bench = Command::new(exe);
+    bench.current_dir(project_dir()?).args(["bench", &artifact]);
+    if let Some(request) = request {
+        bench.arg("--request").arg(request);
+    }
+    if !debug {
+        bench.stdout(Stdio::null()).stderr(Stdio::null());
+    }
+    if !bench.status().context("Failed to run vtx bench")?.success() {
+        return Ok(None);
+    }
+
+    let records = in_project_dir(history::read_bench)
+        .with_context(|| format!("Failed to read {BENCH_HISTORY_FILE}"))?;
+    let value = records.last().and_then(|r| r.metric(metric));
+    if let Some(value) = value {
+        info!("{artifact}: {metric} {value:.3}");
+    }
+    Ok(value)
+}
+
+/// Size recorded by the build that just ran, read from the project's size history.
+fn last_artifact_size() -> Result<Option<u64>> {
+    let records = in_project_dir(history::read)
+        .with_context(|| format!("Failed to read {SIZE_HISTORY_FILE}"))?;
+    Ok(records.last().map(|r| r.artifact_size))
+}
+
+/// `project.path` of the checked-out vtx.toml, where builds write their
+/// caches.
+fn project_dir() -> Result<String> {
+    Ok(config::load_optional()?
+        .and_then(|c| c.project.path)
+        .unwrap_or_else(|| ".".to_string()))
+}
+
+/// Run `read` from [`project_dir`], then return.
+fn in_project_dir<T>(read: impl FnOnce() -> Result<T>) -> Result<T> {
+    let project_dir = project_dir()?;
+    let root = std::env::current_dir().context("Failed to resolve current directory")?;
+    std::env::set_current_dir(Path::new(&project_dir))
+        .with_context(|| format!("Failed to enter project.path: {project_dir}"))?;
+    let result = read();
+    std::env::set_current_dir(&root)
+        .with_context(|| format!("Failed to return to {}", root.display()))?;
+    result
+}
+
+fn git_output(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Failed to run git; is it installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -1,41 +1,346 @@
 use anyhow::{Context, Result};
-use colored::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
+use tracing::{debug, info};
 
-use crate::{builder::create_builder, checker, config, packager};
+use crate::{
+    builder::create_builder,
+    checker, compare, config, container, deprecations, history, ledger, output, packager, runtime,
+    scratch::{self, ScratchDir},
+    stages,
+};
 
 use super::common::{
-    build_vtx_metadata_json, execute_custom_build, resolve_sdk_version, resolve_wasm_path,
+    absolute, add_artifact_hook_env, artifact_output_path, build_vtx_metadata_json, bundle_assets,
+    digest_algorithm, encode_payload_if_configured, execute_custom_build, hook_env,
+    load_embedded_resources, package_options, reproducible_build_time, resolve_sdk_version,
+    resolve_wasm_path, run_hooks, sign_if_configured, write_sbom_if_requested, HookStage,
 };
+use super::jobs;
+use super::workspace::{self, Outcome, Workspace};
+use crate::adapter::Adapter;
+use crate::cli::{BuildArgs, WorkspaceArgs};
+use crate::config::BuildConfig;
+use crate::remote::{RemoteBuildRequest, RemoteBuilder};
 
 /// Execute standard build pipeline
 ///
 /// Flow:
 /// 1. Initialize config and context
-/// 2. SDK compatibility check (for Rust)
+/// 2. SDK compatibility and deprecation checks
 /// 3. Environment pre-check
 /// 4. Compile source code
 /// 5. Resolve artifact path
 /// 6. Encode and package VTX component
+/// 7. Compare against the previous local build
 pub fn execute_build_pipeline(
     package_arg: Option<String>,
+    profile: Option<&str>,
+    target: &str,
+    release: bool,
+    force: bool,
+    debug: bool,
+    options: &BuildArgs,
+) -> Result<()> {
+    if options.simd_matrix {
+        return build_simd_matrix(package_arg, profile, target, release, force, debug, options);
+    }
+    if !options.verify_reproducible {
+        return build_package(package_arg, profile, target, release, force, debug, options)
+            .map(|_| ());
+    }
+
+    let mut options = options.clone();
+    options.packaging.reproducible = true;
+    let root = std::env::current_dir().context("Failed to resolve current directory")?;
+    let path = build_package(
+        package_arg.clone(),
+        profile,
+        target,
+        release,
+        force,
+        debug,
+        &options,
+    )?;
+    let project_dir = std::env::current_dir().context("Failed to resolve project directory")?;
+    let first =
+        std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    std::env::set_current_dir(&root)
+        .with_context(|| format!("Failed to return to {}", root.display()))?;
+
+    // The rebuild repackages from scratch; the compiler's own caches still apply.
+    step!("Verifying reproducibility: rebuilding {}", path.display());
+    let mut args = vec!["--target".to_string(), target.to_string()];
+    if let Some(package) = package_arg {
+        args.extend(["--package".to_string(), package]);
+    }
+    if let Some(name) = profile {
+        args.extend(["--profile".to_string(), name.to_string()]);
+    }
+    if force {
+        args.push("--force".to_string());
+    }
+    if debug {
+        args.push("--debug".to_string());
+    }
+    options.packaging.no_cache = true;
+    args.extend(options.to_args());
+    let exe = std::env::current_exe().context("Failed to locate the vtx executable")?;
+    let (rebuilt_path, _) = jobs::run_child_build(&exe, "[rebuild]", None, &args, &project_dir)?;
+    let second = std::fs::read(&rebuilt_path)
+        .with_context(|| format!("Failed to read {}", rebuilt_path.display()))?;
+
+    if first == second {
+        ok!(
+            "Reproducible: rebuild is byte-identical ({})",
+            ledger::sha256_digest(&first)
+        );
+        return Ok(());
+    }
+    anyhow::bail!(
+        "Build is not reproducible: {} differs between two builds:\n  {}",
+        path.display(),
+        describe_differences(&first, &second)?.join("\n  ")
+    )
+}
+
+/// Build the SIMD artifact, then the scalar variant as `<name>.scalar.vtx`.
+fn build_simd_matrix(
+    package_arg: Option<String>,
+    profile: Option<&str>,
+    target: &str,
+    release: bool,
+    force: bool,
+    debug: bool,
+    options: &BuildArgs,
+) -> Result<()> {
+    let root = std::env::current_dir().context("Failed to resolve current directory")?;
+    step!("SIMD matrix: building the SIMD variant");
+    let simd = build_package(
+        package_arg.clone(),
+        profile,
+        target,
+        release,
+        force,
+        debug,
+        options,
+    )?;
+    std::env::set_current_dir(&root)
+        .with_context(|| format!("Failed to return to {}", root.display()))?;
+
+    let mut scalar_options = options.clone();
+    scalar_options.toolchain.no_simd = true;
+    scalar_options.packaging.variant = Some("scalar".to_string());
+    step!("SIMD matrix: building the scalar variant");
+    let scalar = build_package(
+        package_arg,
+        profile,
+        target,
+        release,
+        force,
+        debug,
+        &scalar_options,
+    )?;
+    ok!(
+        "SIMD matrix: {} (SIMD), {} (scalar)",
+        simd.display(),
+        scalar.display()
+    );
+    Ok(())
+}
+
+/// What differs between two builds of the same artifact: metadata fields
+/// and the first differing byte of the component.
+fn describe_differences(first: &[u8], second: &[u8]) -> Result<Vec<String>> {
+    let decode = |bytes| {
+        container::decode_sections(bytes)
+            .map_err(|e| anyhow::anyhow!("Rebuilt artifact is not a valid .vtx container: {e}"))
+    };
+    let (a, b) = (decode(first)?, decode(second)?);
+    let metadata = |json: Option<&[u8]>| -> Result<serde_json::Map<String, serde_json::Value>> {
+        Ok(json
+            .map(serde_json::from_slice)
+            .transpose()
+            .context("Malformed metadata")?
+            .unwrap_or_default())
+    };
+    let (meta_a, meta_b) = (metadata(a.metadata)?, metadata(b.metadata)?);
+
+    let mut differences = Vec::new();
+    let mut keys: Vec<&String> = meta_a.keys().chain(meta_b.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let (va, vb) = (meta_a.get(key), meta_b.get(key));
+        if va != vb {
+            let show =
+                |v: Option<&serde_json::Value>| v.map_or("absent".to_string(), |v| v.to_string());
+            differences.push(format!("metadata.{key}: {} -> {}", show(va), show(vb)));
+        }
+    }
+    if a.component != b.component {
+        let offset = a
+            .component
+            .iter()
+            .zip(b.component)
+            .position(|(x, y)| x != y)
+            .unwrap_or(a.component.len().min(b.component.len()));
+        differences.push(format!(
+            "component: {} -> {} bytes, first difference at byte {offset}",
+            a.component.len(),
+            b.component.len()
+        ));
+    }
+    if differences.is_empty() {
+        differences.push("container framing".to_string());
+    }
+    Ok(differences)
+}
+
+/// Build every member of the project's Cargo or npm workspace.
+///
+/// Each member runs the full build pipeline; a failing member does not stop
+/// the others. With more than one job, members build concurrently in child
+/// processes (see [`Workspace::run`]); otherwise they build in turn in this
+/// process. A summary table lists the produced artifacts and failures, and
+/// the command fails if any member failed.
+pub fn execute_workspace_build_pipeline(
+    members: &WorkspaceArgs,
+    profile: Option<&str>,
     target: &str,
     release: bool,
     force: bool,
     debug: bool,
+    options: &BuildArgs,
 ) -> Result<()> {
+    let workspace = Workspace::discover(members)?;
+    let names = workspace.names();
+    step!(
+        "Building workspace: {} packages ({})",
+        names.len(),
+        names.join(", ")
+    );
+
+    let mut args = vec!["--target".to_string(), target.to_string()];
+    if let Some(name) = profile {
+        args.extend(["--profile".to_string(), name.to_string()]);
+    }
+    if force {
+        args.push("--force".to_string());
+    }
+    if debug {
+        args.push("--debug".to_string());
+    }
+    args.extend(options.to_args());
+    let project_dir = workspace.project_dir();
+    let results = workspace.run(
+        "build",
+        &args,
+        |member, _| {
+            let path = build_package(
+                Some(member.to_string()),
+                profile,
+                target,
+                release,
+                force,
+                debug,
+                options,
+            )?;
+            let size = std::fs::metadata(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .len();
+            Ok((project_dir.join(path), size))
+        },
+        |_, child| jobs::built_artifact(child, project_dir),
+    )?;
+
+    let failed = workspace::summarize("build", &results, |(path, size)| Outcome {
+        passed: true,
+        detail: format!("{:>10} B  {}", size, path.display()),
+        fields: serde_json::json!({ "path": path, "size": size }),
+    });
+    if failed > 0 {
+        anyhow::bail!(
+            "{failed} of {} workspace packages failed to build",
+            results.len()
+        );
+    }
+    done!("Built {} workspace packages", results.len());
+    Ok(())
+}
+
+/// Where a remotely compiled module is written: `build.output_dir`/`build.artifact`
+/// when both are set, else `target/<target>/<profile>/<package>.wasm`.
+fn remote_output_path(
+    package: &str,
+    target: &str,
+    profile: &str,
+    build_config: Option<&BuildConfig>,
+) -> PathBuf {
+    match build_config.and_then(|c| c.output_dir.as_ref().zip(c.artifact.as_ref())) {
+        Some((dir, artifact)) => Path::new(dir).join(artifact),
+        None => Path::new("target")
+            .join(target)
+            .join(profile)
+            .join(format!("{package}.wasm")),
+    }
+}
+
+/// Run the build pipeline for one package and return the `.vtx` path,
+/// relative to the project directory.
+pub(super) fn build_package(
+    package_arg: Option<String>,
+    profile: Option<&str>,
+    target: &str,
+    release: bool,
+    force: bool,
+    debug: bool,
+    options: &BuildArgs,
+) -> Result<PathBuf> {
     let start_time = Instant::now();
+    let cancel = scratch::interrupt_token();
+    let packaging = &options.packaging;
+    stages::reset();
 
     // --- 1. Initialize Config ---
-    let config = config::load().ok(); // Config is optional allows pure CLI usage
+    let mut config = config::load_optional()?; // Config is optional allows pure CLI usage
+    let (force, debug) = match (profile, config.as_mut()) {
+        (Some(name), Some(cfg)) => {
+            let selected = config::apply_profile(cfg, name)?;
+            info!("Using build profile: {}", name);
+            (
+                force || selected.force.unwrap_or(false),
+                debug || selected.debug.unwrap_or(false),
+            )
+        }
+        (Some(name), None) => {
+            anyhow::bail!("Build profile '{name}' requires a vtx.toml with [profiles.{name}]")
+        }
+        (None, _) => (force, debug),
+    };
+    let mut resources = load_embedded_resources(config.as_ref())?;
+    resources.container_sections = packaging.container_sections;
+    bundle_assets(config.as_ref(), &mut resources)?;
+    if let Some(cfg) = config.as_ref() {
+        config::enter_project_dir(cfg)?;
+    }
     let project_info = config.as_ref().map(|c| c.project.clone());
-    let build_config = config.as_ref().and_then(|c| c.build.clone());
+    let build_config = options
+        .toolchain
+        .apply(config.as_ref().and_then(|c| c.build.clone()));
+    let sdk_config = config.as_ref().and_then(|c| c.sdk.clone());
+    if options.simd_matrix
+        && packaging.variant.is_none()
+        && build_config.as_ref().and_then(|c| c.no_simd) == Some(true)
+    {
+        anyhow::bail!("--simd-matrix builds a SIMD variant; unset build.no_simd to use it");
+    }
 
     // Package name priority: CLI arg > Config file > Error
     let package_name = package_arg
         .or_else(|| project_info.as_ref().map(|p| p.name.clone()))
         .context("Unable to resolve package name. Please specify via --package or vtx.toml.")?;
+    let _span = tracing::info_span!("build", package = %package_name).entered();
 
     // Language detection: Default to Rust
     let language = project_info
@@ -43,105 +348,272 @@ pub fn execute_build_pipeline(
         .map(|p| p.language.as_str())
         .unwrap_or("rust");
 
-    println!(
-        "{} Building package: {} [{}]",
-        "[VTX]".green().bold(),
-        package_name,
-        language
-    );
+    step!("Building package: {} [{}]", package_name, language);
 
     // --- 2. SDK Compatibility Check ---
-    if language.to_lowercase() == "rust" || language.to_lowercase() == "rs" {
-        if debug {
-            println!("{} Checking SDK compatibility...", "[DEBUG]".dimmed());
-        }
-        checker::check_rust_sdk_version(Path::new("."), force)?;
-    } else if debug {
-        println!(
-            "{} Skipping SDK check for non-Rust project.",
-            "[DEBUG]".dimmed()
-        );
-    }
+    debug!("Checking SDK compatibility...");
+    checker::check_project_sdk_version(Path::new("."), language, force)?;
+    checker::check_sdk_config(sdk_config.as_ref(), force)?;
+
+    let sdk_version = resolve_sdk_version(Path::new("."), language, sdk_config.as_ref());
+    deprecations::check(
+        &deprecations::DeprecationTable::load(),
+        Some(target),
+        sdk_version.as_deref(),
+        force,
+    )?;
 
     // Instantiate language-specific builder strategy
     let builder = create_builder(language, build_config.clone())?;
 
+    let remote = build_config.as_ref().and_then(|c| c.remote.clone());
+
     // --- 3. Environment Pre-check ---
-    if build_config.as_ref().and_then(|c| c.cmd.as_ref()).is_none() {
+    // Remote builds run build.cmd or the toolchain on the service.
+    if remote.is_none() && build_config.as_ref().and_then(|c| c.cmd.as_ref()).is_none() {
+        stages::begin("env check");
         builder
             .check_env()
+            .ensure_ready()
             .context("Environment validation failed")?;
     }
 
     // --- 4. Compilation Stage ---
+    cancel.check()?;
     // If in debug mode, force non-release build to keep symbols
     let actual_release = if debug {
-        println!(
-            "{} Debug mode enabled: forcing non-release build.",
-            "[INFO]".cyan()
-        );
+        info!("Debug mode enabled: forcing non-release build.");
         false
     } else {
         release
     };
+    let profile = if actual_release { "release" } else { "debug" };
+    let mut hook_env = hook_env(&package_name, language, project_info.as_ref());
+    hook_env.extend([
+        ("VTX_PROFILE", profile.to_string()),
+        ("VTX_TARGET", target.to_string()),
+    ]);
+    run_hooks(config.as_ref(), HookStage::PreBuild, &hook_env)?;
 
-    if let Some(cmd) = build_config.as_ref().and_then(|c| c.cmd.as_ref()).cloned() {
-        execute_custom_build(&cmd)?;
-    } else {
-        println!(
-            "{} Compiling target: {} (release={})",
-            "[INFO]".cyan(),
+    stages::begin("compile");
+    output::emit(
+        "stage",
+        serde_json::json!({ "stage": "compile", "status": "started", "package": package_name }),
+    );
+    let compile_start = Instant::now();
+    let mut remote_wasm = None;
+    if let Some(url) = &remote {
+        info!(
+            "Compiling target: {} (release={}) on {}",
+            target, actual_release, url
+        );
+        let mut request = RemoteBuildRequest::new(
+            &package_name,
+            language,
             target,
-            actual_release
+            actual_release,
+            build_config.as_ref(),
         );
+        let wasm = RemoteBuilder::new(url)?
+            .build(
+                &mut request,
+                Path::new("."),
+                build_config
+                    .as_ref()
+                    .and_then(|c| c.remote_include.as_deref())
+                    .unwrap_or_default(),
+                &cancel,
+            )
+            .context("Remote compilation failed")?;
+        let path = remote_output_path(&package_name, target, profile, build_config.as_ref());
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&path, wasm)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        remote_wasm = Some(path);
+    } else if let Some(cmd) = build_config.as_ref().and_then(|c| c.cmd.as_ref()).cloned() {
+        execute_custom_build(&cmd, build_config.as_ref())?;
+    } else {
+        info!("Compiling target: {} (release={})", target, actual_release);
         builder
             .build(&package_name, target, actual_release)
             .context("Source compilation failed")?;
     }
+    output::emit(
+        "stage",
+        serde_json::json!({
+            "stage": "compile",
+            "status": "finished",
+            "package": package_name,
+            "duration_ms": compile_start.elapsed().as_millis() as u64,
+        }),
+    );
 
     // --- 5. Artifact Resolution ---
-    let wasm_path = resolve_wasm_path(
-        &package_name,
-        target,
-        actual_release,
-        build_config.as_ref(),
-        builder.as_ref(),
-    )?;
+    cancel.check()?;
+    let wasm_path = match remote_wasm {
+        Some(path) => path,
+        None => resolve_wasm_path(
+            &package_name,
+            target,
+            actual_release,
+            build_config.as_ref(),
+            builder.as_ref(),
+        )?,
+    };
 
-    println!(
-        "{} Artifact located at: {}",
-        "[INFO]".cyan(),
-        wasm_path.display()
-    );
+    info!("Artifact located at: {}", wasm_path.display());
+
+    builder
+        .post_process(&wasm_path)
+        .context("Artifact post-processing failed")?;
+    hook_env.push(("VTX_WASM_PATH", absolute(&wasm_path)));
+    run_hooks(config.as_ref(), HookStage::PostBuild, &hook_env)?;
 
     // --- 6. Encoding and Packaging ---
-    println!(
-        "{} Encoding and validating VTX component...",
-        "[INFO]".cyan()
-    );
+    cancel.check()?;
+    info!("Encoding and validating VTX component...");
 
-    // Pass debug and force flags for internal logic control
-    let component_bytes = packager::process_wasm(&wasm_path, debug, force)
+    // Intermediate files live in a scratch directory removed on exit
+    let scratch = ScratchDir::create()?;
+    let options = package_options(
+        build_config.as_ref(),
+        sdk_config.as_ref(),
+        config.as_ref().and_then(|c| c.dependencies.as_ref()),
+        packaging,
+        &scratch,
+        debug,
+        force,
+    );
+    if options.reproducible {
+        resources.build_time = reproducible_build_time()?;
+    }
+    let packaged = packager::package_wasm(&wasm_path, &options)
         .context("Component packaging or validation failed")?;
+    let component_bytes = packaged.component;
+    resources.requires = Some(packager::required_interfaces(&component_bytes)?);
+    resources.adapter = packaged.adapter.as_ref().map(Adapter::kind);
+    resources.adapter_version = packaged
+        .adapter
+        .as_ref()
+        .map(Adapter::version)
+        .transpose()?;
+
+    if packaging.deep_validate {
+        stages::begin("validate");
+        info!("Deep validation: instantiating under wasmtime...");
+        runtime::deep_validate(&component_bytes, &options.progress, &options.cancel)?;
+    }
 
-    let sdk_version = resolve_sdk_version(language);
+    // The local toolchain version says nothing about a remote build.
+    let mut builder_meta = serde_json::Map::new();
+    if remote.is_none() {
+        builder.metadata(&mut builder_meta);
+    }
     let metadata_json = build_vtx_metadata_json(
         &package_name,
         language,
         project_info.as_ref(),
         sdk_version.as_deref(),
+        sdk_config.as_ref(),
+        Some(&builder_meta),
+        &resources,
+    )?;
+
+    stages::begin("write");
+    let vtx_path = artifact_output_path(
+        config.as_ref(),
+        packaging,
+        &wasm_path,
+        &package_name,
+        target,
+        profile,
+    )?;
+    let (metadata_json, payload) =
+        encode_payload_if_configured(config.as_ref(), packaging, metadata_json, &component_bytes)?;
+    let metadata_json = sign_if_configured(config.as_ref(), metadata_json, &payload)?;
+    packager::write_vtx_file_to(
+        &vtx_path,
+        &payload,
+        &metadata_json,
+        resources.capabilities_section()?.as_deref(),
+        resources.assets.as_deref(),
+        Some(scratch.path()),
+    )
+    .context("Failed to write final artifact")?;
+    let debuginfo_path = packaged
+        .debuginfo
+        .map(|info| packager::write_debuginfo_file(&vtx_path, &info, Some(scratch.path())))
+        .transpose()
+        .context("Failed to write debug info")?;
+    if let Some(path) = &debuginfo_path {
+        info!("Debug info written: {}", path.display());
+    }
+    let sbom_paths = write_sbom_if_requested(
+        config.as_ref(),
+        packaging,
+        &vtx_path,
+        &package_name,
+        language,
+        Path::new("."),
     )?;
+    if packaging.size_report {
+        stages::suspend(|| super::size::report_component(&component_bytes))?;
+    }
 
-    let vtx_path = packager::write_vtx_file(&wasm_path, &component_bytes, &metadata_json)
-        .context("Failed to write final artifact")?;
+    if packaging.emit_component {
+        let component_path =
+            packager::write_component_file(&wasm_path, &component_bytes, Some(scratch.path()))
+                .context("Failed to write bare component")?;
+        info!("Bare component written: {}", component_path.display());
+    }
+
+    // --- 7. Compare with Previous Build ---
+    cancel.check()?;
+    // Variants are compared with earlier builds of the same variant.
+    let summary_profile = match &packaging.variant {
+        Some(variant) => format!("{profile}-{variant}"),
+        None => profile.to_string(),
+    };
+    let summary = compare::ArtifactSummary::new(
+        &package_name,
+        &summary_profile,
+        &vtx_path,
+        &component_bytes,
+    )?;
+    compare::compare_with_previous(
+        &summary,
+        build_config.as_ref().and_then(|c| c.compare.as_ref()),
+        force,
+    )?;
+    // Only artifacts that passed the comparison are notarized.
+    ledger::record(&vtx_path, &metadata_json, digest_algorithm(config.as_ref()))?;
+    history::record(&summary)?;
+    add_artifact_hook_env(&mut hook_env, &vtx_path, &metadata_json, &scratch)?;
+    run_hooks(config.as_ref(), HookStage::PostPackage, &hook_env)?;
 
     let duration = start_time.elapsed();
-    println!(
-        "{} Build completed in {:.2}s 鈫?{}",
-        "[DONE]".green().bold(),
+    output::emit(
+        "artifact",
+        serde_json::json!({
+            "package": package_name,
+            "path": vtx_path,
+            "debuginfo": debuginfo_path,
+            "checksum": sbom_paths.as_ref().map(|(checksum, _)| checksum),
+            "sbom": sbom_paths.as_ref().map(|(_, sbom)| sbom),
+            "wasm": wasm_path,
+            "size": summary.artifact_size,
+            "duration_ms": duration.as_millis() as u64,
+        }),
+    );
+    stages::summary();
+    done!(
+        "Build completed in {:.2}s 鈫?{}",
         duration.as_secs_f64(),
         vtx_path.display()
     );
 
-    Ok(())
+    Ok(vtx_path)
 }
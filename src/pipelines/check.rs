@@ -1,40 +1,218 @@
 use anyhow::{Context, Result};
 use colored::*;
+use schemars::JsonSchema;
+use serde::Serialize;
 use std::path::Path;
+use tracing::{debug, info, warn};
 
-use crate::{builder::create_builder, checker, config};
+use super::common::{load_embedded_resources, resolve_sdk_version};
+use super::workspace::{self, Outcome, Workspace};
+use crate::builder::{create_builder, EnvReport};
+use crate::cli::WorkspaceArgs;
+use crate::{assets, checker, config, deprecations, output, toolchain};
+
+/// JSON document printed by `vtx check --json`.
+#[derive(Serialize, JsonSchema)]
+pub struct CheckReport {
+    /// Canonical language of the project in the current directory, if any.
+    pub project_language: Option<String>,
+
+    /// One environment report per probed language.
+    pub reports: Vec<EnvReport>,
+}
+
+pub fn execute_check_pipeline(
+    members: &WorkspaceArgs,
+    all_languages: bool,
+    json: bool,
+    refresh_deprecations: bool,
+) -> Result<()> {
+    let json = json || output::is_json();
+    if all_languages || json {
+        return execute_toolchain_matrix(all_languages, json);
+    }
 
-pub fn execute_check_pipeline(debug: bool) -> Result<()> {
     let config = config::load()?;
+    if members.jobs.is_some() {
+        anyhow::bail!("vtx check runs workspace members in turn; --jobs is not supported");
+    }
+    let workspace = if members.workspace {
+        Some(Workspace::discover(members)?)
+    } else {
+        None
+    };
+    let package_dir = match members.package.as_deref() {
+        Some(package) => Some(workspace::member_dir(&config, package)?),
+        None => None,
+    };
+    if Path::new(assets::ASSETS_MANIFEST).exists() {
+        super::assets::verify(&assets::AssetManifest::load()?, config.assets.as_ref())?;
+    }
+    load_embedded_resources(Some(&config))?;
+    config::enter_project_dir(&config)?;
     let project_info = config.project;
     let build_config = config.build;
+    let sdk_config = config.sdk;
 
     let language = project_info.language;
 
-    if language.to_lowercase() == "rust" || language.to_lowercase() == "rs" {
-        if debug {
-            println!("{} Checking SDK compatibility...", "[DEBUG]".dimmed());
-        }
-        checker::check_rust_sdk_version(Path::new("."), false)?;
-    } else if debug {
-        println!(
-            "{} Skipping SDK check for non-Rust project.",
-            "[DEBUG]".dimmed()
+    debug!("Checking SDK compatibility...");
+    match workspace.as_ref() {
+        Some(workspace) => check_members(workspace, &language)?,
+        None => checker::check_project_sdk_version(
+            package_dir.as_deref().unwrap_or(Path::new(".")),
+            &language,
+            false,
+        )?,
+    }
+
+    if let Some(sdk) = sdk_config.as_ref() {
+        info!(
+            "Resolved [sdk]: version={}, channel={}, adapter={}",
+            sdk.version.as_deref().unwrap_or("auto"),
+            sdk.channel.as_deref().unwrap_or("stable"),
+            sdk.adapter.as_deref().unwrap_or("bundled")
         );
     }
+    checker::check_sdk_config(sdk_config.as_ref(), false)?;
+
+    let table = if refresh_deprecations {
+        let table = deprecations::refresh()?;
+        info!("Deprecation table refreshed (revision {})", table.revision);
+        table
+    } else {
+        deprecations::DeprecationTable::load()
+    };
+    let sdk_version = resolve_sdk_version(Path::new("."), &language, sdk_config.as_ref());
+    deprecations::check(&table, None, sdk_version.as_deref(), false)?;
 
     let builder = create_builder(&language, build_config.clone())?;
     if build_config.as_ref().and_then(|c| c.cmd.as_ref()).is_none() {
-        builder
-            .check_env()
+        let report = builder.check_env();
+        for check in &report.checks {
+            if check.satisfied {
+                ok!(
+                    "{}: {}",
+                    check.tool,
+                    check.version.as_deref().unwrap_or(&check.constraint)
+                );
+            } else if !check.required {
+                warn!(
+                    "{} not {} (optional). {}",
+                    check.tool,
+                    check.constraint,
+                    check.remediation.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        report
+            .ensure_ready()
             .context("Environment validation failed")?;
     }
 
-    println!(
-        "{} Environment check passed for language: {}",
-        "[OK]".green().bold(),
-        language
-    );
+    ok!("Environment check passed for language: {}", language);
+
+    Ok(())
+}
+
+/// Check the SDK compatibility of every workspace member, then return to the
+/// project directory for the checks that apply to the whole project.
+fn check_members(workspace: &Workspace, language: &str) -> Result<()> {
+    let results = workspace.run_each(|member, dir| {
+        info!("Checking member {}...", member);
+        checker::check_project_sdk_version(&workspace.project_dir().join(dir), language, false)
+    })?;
+    let failed = workspace::summarize("check", &results, |_| Outcome {
+        passed: true,
+        detail: "SDK compatible".to_string(),
+        fields: serde_json::json!({}),
+    });
+    std::env::set_current_dir(workspace.project_dir())
+        .with_context(|| format!("Failed to return to {}", workspace.project_dir().display()))?;
+    if failed > 0 {
+        anyhow::bail!(
+            "{} of {} workspace members failed the check",
+            failed,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+/// Probe toolchains and print a capability matrix.
+///
+/// With `all_languages`, every supported language is probed and vtx.toml is optional.
+/// Fails only when a required tool for the project's own language is missing.
+fn execute_toolchain_matrix(all_languages: bool, json: bool) -> Result<()> {
+    let config = if all_languages {
+        config::load_optional()?
+    } else {
+        Some(config::load()?)
+    };
+    let project_language = config
+        .as_ref()
+        .map(|c| toolchain::canonical_language(&c.project.language));
+    let custom_cmd = config
+        .as_ref()
+        .and_then(|c| c.build.as_ref())
+        .is_some_and(|b| b.cmd.is_some());
+
+    let languages: Vec<String> = if all_languages {
+        toolchain::LANGUAGES.iter().map(|l| l.to_string()).collect()
+    } else {
+        project_language.iter().cloned().collect()
+    };
+
+    let mut reports = Vec::new();
+    for language in &languages {
+        // Probe the default flow; build.cmd only applies to the project's own language.
+        reports.push(create_builder(language, None)?.check_env());
+    }
+
+    if json {
+        let report = CheckReport {
+            project_language: project_language.clone(),
+            reports: reports.clone(),
+        };
+        output::document("check", &report)?;
+    } else {
+        println!(
+            "{:<8} {:<16} {:<17} {:<10} VERSION",
+            "LANGUAGE", "TOOL", "STATUS", "REQUIRES"
+        );
+        for report in &reports {
+            for check in &report.checks {
+                let status = match check.status() {
+                    "ok" => check.status().green(),
+                    "missing" => check.status().red(),
+                    other => other.yellow(),
+                };
+                let requires = match &check.minimum_version {
+                    Some(minimum) => format!(">= {}", minimum.trim_end_matches(".0")),
+                    None => "any".to_string(),
+                };
+                println!(
+                    "{:<8} {:<16} {:<17} {:<10} {}",
+                    report.language,
+                    check.tool,
+                    status,
+                    requires,
+                    check.version.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+
+    let project_report = reports
+        .iter()
+        .find(|r| Some(&r.language) == project_language.as_ref());
+    if let Some(report) = project_report {
+        if !custom_cmd {
+            report
+                .ensure_ready()
+                .context("Required toolchain missing for project language")?;
+        }
+    }
 
     Ok(())
 }
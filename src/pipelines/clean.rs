@@ -0,0 +1,266 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+use super::common::PACKAGING_CACHE_DIR;
+use crate::{config, ledger, toolchain};
+
+/// Cache root holding the packaging cache, size and bench history, build summaries and release state.
+const CACHE_ROOT: &str = ".vtx-cache";
+
+/// Files written next to a `.vtx` by `--split-debuginfo` and `--sbom`.
+const COMPANION_SUFFIXES: &[&str] = &[".dbg", ".sha256", ".cdx.json", ".spdx.json"];
+
+/// What `vtx clean` removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanScope {
+    /// `.vtx` artifacts and the files written next to them.
+    Artifacts,
+    /// Artifacts, build intermediates and the packaging cache (the default).
+    Build,
+    /// Everything in `Build` plus the whole `.vtx-cache`.
+    All,
+}
+
+/// Remove build outputs, reporting how much space was reclaimed.
+///
+/// Requires a vtx.toml and works in `project.path`, where builds write their
+/// outputs. Only files this tool produced are removed:
+/// - Artifacts: every `.vtx` recorded in the artifact ledger, and every `.vtx`
+///   in `package.out_dir` and `build.output_dir`, with their `.dbg`,
+///   `.sha256` and SBOM companions; `*.component.wasm` next to them.
+/// - Intermediates: `cargo clean` for Rust; `target/` for Go and C/C++, plus
+///   `bin/` and `obj/` for C#; `dist/` and `build/` for TypeScript and Python;
+///   and `build.output_dir` when set.
+/// - The packaging cache. With [`CleanScope::All`], the whole `.vtx-cache`
+///   (size and bench history, build summaries, release state) goes too. The artifact
+///   ledger is always kept.
+///
+/// Paths declared under `[dependencies]` are never removed, nor is any
+/// directory containing one.
+pub fn execute_clean_pipeline(scope: CleanScope) -> Result<()> {
+    let config = config::load()?;
+    let dependencies = dependency_paths(&config);
+    config::enter_project_dir(&config)?;
+    let mut reclaimed = 0;
+
+    let output_dir = config
+        .build
+        .as_ref()
+        .and_then(|b| b.output_dir.as_deref())
+        .filter(|d| inside_project(Path::new(d)));
+    let out_dir = config
+        .package
+        .as_ref()
+        .and_then(|p| p.out_dir.as_deref())
+        .filter(|d| inside_project(Path::new(d)));
+    for path in find_artifacts(output_dir.into_iter().chain(out_dir))? {
+        if !keeps_dependency(&path, &dependencies) {
+            reclaimed += remove(&path)?;
+        }
+    }
+
+    if scope != CleanScope::Artifacts {
+        let language = toolchain::canonical_language(&config.project.language);
+        if language == "rust" && !keeps_dependency(Path::new("target"), &dependencies) {
+            reclaimed += cargo_clean(Path::new("."))?;
+        }
+        for dir in intermediate_dirs(&language, output_dir) {
+            if !keeps_dependency(Path::new(dir), &dependencies) {
+                reclaimed += remove(Path::new(dir))?;
+            }
+        }
+        reclaimed += clean_packaging_cache()?;
+    }
+
+    if scope == CleanScope::All {
+        reclaimed += remove(Path::new(CACHE_ROOT))?;
+    }
+
+    if reclaimed == 0 {
+        info!("Nothing to clean");
+    } else {
+        ok!("Reclaimed {} bytes", reclaimed);
+    }
+    Ok(())
+}
+
+/// Canonical paths of the `[dependencies]`, resolved from the vtx.toml directory.
+fn dependency_paths(config: &config::ProjectConfig) -> Vec<PathBuf> {
+    config
+        .dependencies
+        .iter()
+        .flatten()
+        .filter_map(|(_, dependency)| std::fs::canonicalize(&dependency.path).ok())
+        .collect()
+}
+
+/// Whether removing `path` would take a declared dependency with it.
+fn keeps_dependency(path: &Path, dependencies: &[PathBuf]) -> bool {
+    let Ok(path) = std::fs::canonicalize(path) else {
+        return false;
+    };
+    let Some(dependency) = dependencies.iter().find(|d| d.starts_with(&path)) else {
+        return false;
+    };
+    warn!(
+        "Kept {}: it holds the declared dependency {}",
+        path.display(),
+        dependency.display()
+    );
+    true
+}
+
+/// Relative paths without `..`; absolute paths and parents are left alone.
+fn inside_project(path: &Path) -> bool {
+    path.components().all(|c| {
+        matches!(
+            c,
+            std::path::Component::Normal(_) | std::path::Component::CurDir
+        )
+    })
+}
+
+/// Build directories of `language` besides Cargo's, relative to the project path.
+fn intermediate_dirs<'a>(language: &str, output_dir: Option<&'a str>) -> Vec<&'a str> {
+    let mut dirs = match language {
+        "go" | "c" => vec!["target"],
+        "csharp" => vec!["target", "bin", "obj"],
+        "ts" | "python" => vec!["dist", "build"],
+        _ => Vec::new(),
+    };
+    dirs.extend(output_dir);
+    dirs
+}
+
+/// Run `cargo clean` and return the size `target/` had.
+fn cargo_clean(project_dir: &Path) -> Result<u64> {
+    let target = project_dir.join("target");
+    if !target.exists() {
+        return Ok(0);
+    }
+    let size = dir_size(&target);
+    let status = Command::new("cargo")
+        .arg("clean")
+        .current_dir(project_dir)
+        .status()
+        .context("Failed to spawn cargo clean")?;
+    if !status.success() {
+        anyhow::bail!("cargo clean failed with non-zero exit code");
+    }
+    ok!("cargo clean: removed {} ({} bytes)", target.display(), size);
+    Ok(size)
+}
+
+fn clean_packaging_cache() -> Result<u64> {
+    let cache = Path::new(PACKAGING_CACHE_DIR);
+    if !cache.exists() {
+        return Ok(0);
+    }
+
+    let mut entries = 0;
+    let mut bytes = 0;
+    for entry in std::fs::read_dir(cache)
+        .with_context(|| format!("Failed to read {PACKAGING_CACHE_DIR}"))?
+        .flatten()
+    {
+        if let Ok(meta) = entry.metadata() {
+            entries += 1;
+            bytes += meta.len();
+        }
+    }
+    std::fs::remove_dir_all(cache)
+        .with_context(|| format!("Failed to remove {PACKAGING_CACHE_DIR}"))?;
+    ok!(
+        "Removed {} cached components ({} bytes) from {}",
+        entries,
+        bytes,
+        PACKAGING_CACHE_DIR
+    );
+    Ok(bytes)
+}
+
+/// Artifacts this tool wrote: the `.vtx` files recorded in the ledger or
+/// found directly in `output_dirs`, their companions, and the bare
+/// components next to them.
+fn find_artifacts<'a>(output_dirs: impl Iterator<Item = &'a str>) -> Result<Vec<PathBuf>> {
+    let mut artifacts: Vec<PathBuf> = ledger::read_entries(Path::new(ledger::LEDGER_FILE))?
+        .into_iter()
+        .map(|entry| PathBuf::from(entry.artifact))
+        .filter(|path| inside_project(path))
+        .collect();
+    let mut components = Vec::new();
+    for dir in output_dirs {
+        for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".component.wasm") {
+                components.push(path);
+            } else if path.extension().and_then(|e| e.to_str()) == Some("vtx") {
+                artifacts.push(path);
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    for artifact in artifacts {
+        if artifact.extension().and_then(|e| e.to_str()) != Some("vtx") {
+            continue;
+        }
+        let mut candidates: Vec<PathBuf> = COMPANION_SUFFIXES
+            .iter()
+            .map(|suffix| {
+                let mut companion = artifact.as_os_str().to_owned();
+                companion.push(suffix);
+                PathBuf::from(companion)
+            })
+            .collect();
+        candidates.push(artifact.with_extension("component.wasm"));
+        candidates.push(artifact);
+        found.extend(candidates);
+    }
+    found.extend(components);
+    found.sort();
+    found.dedup();
+    Ok(found
+        .into_iter()
+        .filter(|path| path.is_file())
+        .map(|path| {
+            path.strip_prefix(".")
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+        .collect())
+}
+
+/// Remove a file or directory tree and return its size; missing paths count as zero.
+fn remove(path: &Path) -> Result<u64> {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return Ok(0);
+    };
+    let size = if meta.is_dir() {
+        let size = dir_size(path);
+        std::fs::remove_dir_all(path)
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+        size
+    } else {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+        meta.len()
+    };
+    info!("Removed {} ({} bytes)", path.display(), size);
+    Ok(size)
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            _ => entry.metadata().map(|m| m.len()).unwrap_or(0),
+        })
+        .sum()
+}
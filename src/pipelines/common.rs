@@ -1,29 +1,303 @@
 use anyhow::{Context, Result};
-use serde_json::json;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use tracing::{debug, info, warn};
 
-use crate::{builder::Builder, checker, config};
+use crate::adapter::Adapter;
+use crate::cli::PackagingArgs;
+use crate::compose::Dependency;
+use crate::container::{self, Compression, PayloadKey};
+use crate::features::FeaturePolicy;
+use crate::metadata::{ToolInfo, VtxMetadata, METADATA_SCHEMA};
+use crate::progress::{Progress, ProgressSink, Stage};
+use crate::scratch::{self, ScratchDir};
+use crate::wasmopt::WasmOpt;
+use crate::{
+    assets,
+    builder::{self, Builder},
+    checker, config, digest, i18n, ledger, offline, output, packager, sbom, settings, signing,
+    stages, timeutil,
+};
 
-pub fn execute_custom_build(cmd: &str) -> Result<()> {
+/// A relative adapter file that does not exist falls back to
+/// `<vendor dir>/adapters/<file name>` when that is present.
+fn vendored_adapter(adapter: Adapter) -> Adapter {
+    match adapter {
+        Adapter::File(path) if path.is_relative() && !path.exists() => path
+            .file_name()
+            .and_then(|name| offline::vendored(&format!("adapters/{}", name.to_string_lossy())))
+            .map_or(Adapter::File(path), Adapter::File),
+        other => other,
+    }
+}
+
+pub fn execute_custom_build(cmd: &str, build_config: Option<&config::BuildConfig>) -> Result<()> {
+    let status = shell_command(cmd)
+        .envs(builder::build_env(build_config))
+        .status()
+        .with_context(|| format!("Failed to execute build command: {cmd}"))?;
+
+    if !status.success() {
+        anyhow::bail!("Custom build command failed");
+    }
+
+    Ok(())
+}
+
+/// `cmd` run through the platform shell.
+fn shell_command(cmd: &str) -> Command {
     let (shell, arg) = if cfg!(target_os = "windows") {
         ("cmd", "/C")
     } else {
         ("sh", "-c")
     };
+    let mut command = Command::new(shell);
+    command.args([arg, cmd]);
+    command
+}
 
-    let status = Command::new(shell)
-        .args([arg, cmd])
-        .status()
-        .with_context(|| format!("Failed to execute build command: {cmd}"))?;
+/// Stage of the `[hooks]` commands.
+#[derive(Clone, Copy, Debug)]
+pub enum HookStage {
+    PreBuild,
+    PostBuild,
+    PostPackage,
+}
 
-    if !status.success() {
-        anyhow::bail!("Custom build command failed");
+impl HookStage {
+    fn name(self) -> &'static str {
+        match self {
+            HookStage::PreBuild => "pre_build",
+            HookStage::PostBuild => "post_build",
+            HookStage::PostPackage => "post_package",
+        }
+    }
+}
+
+/// Run the `[hooks]` commands of `stage` in order through the shell, with
+/// `VTX_HOOK` set to the stage name and `env` added to the environment.
+/// Stops at the first command that fails.
+pub fn run_hooks(
+    config: Option<&config::ProjectConfig>,
+    stage: HookStage,
+    env: &[(&str, String)],
+) -> Result<()> {
+    let Some(hooks) = config.and_then(|c| c.hooks.as_ref()) else {
+        return Ok(());
+    };
+    let commands = match stage {
+        HookStage::PreBuild => &hooks.pre_build,
+        HookStage::PostBuild => &hooks.post_build,
+        HookStage::PostPackage => &hooks.post_package,
+    };
+    if !commands.is_empty() {
+        stages::begin("hooks");
+    }
+    for cmd in commands {
+        info!("Running {} hook: {}", stage.name(), cmd);
+        let status = shell_command(cmd)
+            .env("VTX_HOOK", stage.name())
+            .envs(env.iter().map(|(key, value)| (key, value)))
+            .status()
+            .with_context(|| format!("Failed to run {} hook: {cmd}", stage.name()))?;
+        if !status.success() {
+            anyhow::bail!("{} hook failed ({status}): {cmd}", stage.name());
+        }
+    }
+    Ok(())
+}
+
+/// `VTX_PACKAGE`, `VTX_LANGUAGE` and, when set, `VTX_VERSION` for hook commands.
+pub fn hook_env(
+    package_name: &str,
+    language: &str,
+    project: Option<&config::ProjectInfo>,
+) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("VTX_PACKAGE", package_name.to_string()),
+        ("VTX_LANGUAGE", language.to_string()),
+    ];
+    if let Some(version) = project.and_then(|p| p.version.clone()) {
+        env.push(("VTX_VERSION", version));
     }
+    env
+}
 
+/// Add the written artifact to the hook environment: `VTX_ARTIFACT_PATH`,
+/// `VTX_ARTIFACT_SHA256` and `VTX_METADATA_PATH`, a copy of the embedded
+/// metadata in the scratch directory.
+pub fn add_artifact_hook_env(
+    env: &mut Vec<(&'static str, String)>,
+    vtx_path: &Path,
+    metadata_json: &[u8],
+    scratch: &ScratchDir,
+) -> Result<()> {
+    let artifact = std::fs::read(vtx_path)
+        .with_context(|| format!("Failed to read {}", vtx_path.display()))?;
+    let metadata_path = scratch.path().join("metadata.json");
+    std::fs::write(&metadata_path, metadata_json)
+        .with_context(|| format!("Failed to write {}", metadata_path.display()))?;
+    env.extend([
+        ("VTX_ARTIFACT_PATH", absolute(vtx_path)),
+        (
+            "VTX_ARTIFACT_SHA256",
+            ledger::sha256_digest(&artifact)
+                .trim_start_matches("sha256:")
+                .to_string(),
+        ),
+        ("VTX_METADATA_PATH", absolute(&metadata_path)),
+    ]);
     Ok(())
 }
 
+/// `path` made absolute for hook commands, which may change directory.
+pub fn absolute(path: &Path) -> String {
+    std::path::absolute(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Merge vtx.toml `[build]` and `[sdk]` settings with CLI packaging flags.
+/// Packaged components reused across builds; purged by `vtx clean`.
+pub const PACKAGING_CACHE_DIR: &str = ".vtx-cache/packaging";
+
+pub fn package_options(
+    build_config: Option<&config::BuildConfig>,
+    sdk_config: Option<&config::SdkConfig>,
+    dependencies: Option<&BTreeMap<String, config::DependencyConfig>>,
+    args: &PackagingArgs,
+    scratch: &ScratchDir,
+    debug: bool,
+    force: bool,
+) -> packager::PackageOptions {
+    let wasi = build_config.and_then(|c| c.wasi.as_ref());
+    let mut deny = wasi.and_then(|w| w.deny.clone()).unwrap_or_default();
+    deny.extend(args.deny_wasi.iter().cloned());
+
+    packager::PackageOptions {
+        debug,
+        force,
+        scratch_dir: Some(scratch.path().to_path_buf()),
+        wasi_policy: packager::WasiPolicy {
+            allow: wasi.and_then(|w| w.allow.clone()),
+            deny,
+        },
+        progress: if output::is_json() {
+            Progress::new(output::JsonProgress::new(debug))
+        } else {
+            Progress::new(TerminalProgress)
+        },
+        cancel: scratch::interrupt_token(),
+        feature_policy: FeaturePolicy {
+            allow_threads: args.allow_threads
+                || build_config.and_then(|c| c.allow_threads).unwrap_or(false),
+            kernel_api: sdk_config.and_then(|s| s.kernel_api.clone()),
+            deny_simd: build_config.and_then(|c| c.no_simd).unwrap_or(false),
+        },
+        optimize_data: args.optimize_data
+            || build_config.and_then(|c| c.optimize_data).unwrap_or(false),
+        wasm_opt: wasm_opt_settings(build_config, args),
+        split_debuginfo: args.split_debuginfo
+            || build_config
+                .and_then(|c| c.split_debuginfo)
+                .unwrap_or(false),
+        adapter: args
+            .adapter
+            .clone()
+            .or_else(|| build_config.and_then(|c| c.adapter.as_deref()?.parse().ok()))
+            .map(vendored_adapter)
+            .unwrap_or_default(),
+        dependencies: dependencies
+            .into_iter()
+            .flatten()
+            .map(|(name, dependency)| Dependency {
+                name: name.clone(),
+                path: PathBuf::from(&dependency.path),
+            })
+            .collect(),
+        reproducible: args.reproducible
+            || build_config.and_then(|c| c.reproducible).unwrap_or(false),
+        cache_dir: (!args.no_cache).then(|| PathBuf::from(PACKAGING_CACHE_DIR)),
+    }
+}
+
+/// Environment variable fixing the build time of reproducible builds, in seconds since the epoch.
+pub const SOURCE_DATE_EPOCH_ENV: &str = "SOURCE_DATE_EPOCH";
+
+/// Build time for reproducible metadata: `SOURCE_DATE_EPOCH`, else the time
+/// of the last git commit, else none.
+pub fn reproducible_build_time() -> Result<Option<String>> {
+    if let Ok(value) = std::env::var(SOURCE_DATE_EPOCH_ENV) {
+        let secs = value.trim().parse::<u64>().with_context(|| {
+            format!("Invalid {SOURCE_DATE_EPOCH_ENV} '{value}' (expected seconds since the epoch)")
+        })?;
+        return Ok(Some(timeutil::rfc3339(secs)));
+    }
+    let commit_time = Command::new("git")
+        .args(["log", "-1", "--format=%ct"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .trim()
+                .parse::<u64>()
+                .ok()
+        });
+    if commit_time.is_none() {
+        warn!("Reproducible build without {SOURCE_DATE_EPOCH_ENV} or a git commit; no build time recorded");
+    }
+    Ok(commit_time.map(timeutil::rfc3339))
+}
+
+/// `--opt-level` wins over `[build.optimize] level`; a bare `[build.optimize]` means `-Os`.
+fn wasm_opt_settings(
+    build_config: Option<&config::BuildConfig>,
+    args: &PackagingArgs,
+) -> Option<WasmOpt> {
+    let optimize = build_config.and_then(|c| c.optimize.as_ref());
+    let level = args
+        .opt_level
+        .clone()
+        .or_else(|| optimize.map(|o| o.level.clone().unwrap_or_else(|| "s".to_string())))?;
+    Some(WasmOpt {
+        level,
+        args: optimize.and_then(|o| o.args.clone()).unwrap_or_default(),
+    })
+}
+
+/// Reports library stages to the terminal progress UI and messages as log
+/// events; byte counts are debug level.
+pub struct TerminalProgress;
+
+impl ProgressSink for TerminalProgress {
+    fn stage_started(&self, stage: Stage) {
+        stages::begin(stage.name());
+    }
+
+    fn stage_finished(&self, stage: Stage) {
+        stages::end(stage.name());
+    }
+
+    fn bytes_processed(&self, stage: Stage, bytes: u64) {
+        debug!("{}: {} bytes", stage.name(), bytes);
+    }
+
+    fn info(&self, message: &str) {
+        info!("{}", message);
+    }
+
+    fn warning(&self, message: &str) {
+        warn!("{}", message);
+    }
+
+    fn debug(&self, message: &str) {
+        debug!("{}", message);
+    }
+}
+
 pub fn resolve_wasm_path(
     package: &str,
     target: &str,
@@ -46,12 +320,312 @@ pub fn resolve_wasm_path(
         .context("Unable to locate compiled artifact")
 }
 
-pub fn resolve_sdk_version(language: &str) -> Option<String> {
-    if language.eq_ignore_ascii_case("rust") || language.eq_ignore_ascii_case("rs") {
-        checker::read_rust_sdk_version(Path::new("."))
-    } else {
-        None
+/// Resolve the SDK version recorded in metadata.
+///
+/// Auto-detection from the language manifest in `project_dir` wins;
+/// `sdk.version` is the fallback.
+pub fn resolve_sdk_version(
+    project_dir: &Path,
+    language: &str,
+    sdk: Option<&config::SdkConfig>,
+) -> Option<String> {
+    checker::read_sdk_version(project_dir, language).or_else(|| {
+        sdk.and_then(|s| s.version.as_deref())
+            .map(|v| v.trim_start_matches(['^', '~', '=']).to_string())
+    })
+}
+
+/// Path of the `.vtx` artifact for `wasm_path`.
+///
+/// The directory is `--out-dir`, else `package.out_dir`, else the directory of
+/// `wasm_path`, and is created if missing. The file name comes from the
+/// `--out-name` or `package.artifact_name` template, else `wasm_path` with a
+/// `.vtx` extension. A packaging variant goes before the extension.
+pub fn artifact_output_path(
+    config: Option<&config::ProjectConfig>,
+    packaging: &PackagingArgs,
+    wasm_path: &Path,
+    package_name: &str,
+    target: &str,
+    profile: &str,
+) -> Result<PathBuf> {
+    let package = config.and_then(|c| c.package.as_ref());
+    let template = packaging
+        .out_name
+        .as_deref()
+        .or_else(|| package.and_then(|p| p.artifact_name.as_deref()));
+    let name = match template {
+        Some(template) => {
+            let version = config.and_then(|c| c.project.version.as_deref());
+            config::render_artifact_name(
+                template,
+                &[
+                    ("name", Some(package_name)),
+                    ("version", version),
+                    ("target", Some(target)),
+                    ("profile", Some(profile)),
+                ],
+            )?
+        }
+        None => wasm_path
+            .with_extension("vtx")
+            .file_name()
+            .context("Artifact path has no file name")?
+            .to_string_lossy()
+            .into_owned(),
+    };
+    let name = match (&packaging.variant, name.rsplit_once('.')) {
+        (Some(variant), Some((stem, extension))) => format!("{stem}.{variant}.{extension}"),
+        (Some(variant), None) => format!("{name}.{variant}"),
+        (None, _) => name,
+    };
+
+    let Some(dir) = packaging
+        .out_dir
+        .as_deref()
+        .or_else(|| package.and_then(|p| p.out_dir.as_deref()))
+    else {
+        return Ok(wasm_path.with_file_name(name));
+    };
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create output directory: {dir}"))?;
+    Ok(Path::new(dir).join(name))
+}
+
+/// Digest algorithm for ledger entries, from `package.digest`.
+pub fn digest_algorithm(config: Option<&config::ProjectConfig>) -> &str {
+    config
+        .and_then(|c| c.package.as_ref())
+        .and_then(|p| p.digest.as_deref())
+        .unwrap_or(digest::DEFAULT_DIGEST_ALGORITHM)
+}
+
+/// Environment variable naming a signing key; takes precedence over `package.signing_key`.
+pub const SIGNING_KEY_ENV: &str = "VTX_SIGNING_KEY";
+
+/// Sign the metadata when a signing key is configured; otherwise return it unchanged.
+pub fn sign_if_configured(
+    config: Option<&config::ProjectConfig>,
+    metadata_json: Vec<u8>,
+    component_bytes: &[u8],
+) -> Result<Vec<u8>> {
+    let key_path = std::env::var(SIGNING_KEY_ENV).ok().or_else(|| {
+        config
+            .and_then(|c| c.package.as_ref())
+            .and_then(|p| p.signing_key.clone())
+    });
+    let Some(key_path) = key_path else {
+        return Ok(metadata_json);
+    };
+    let pkcs8 = std::fs::read(&key_path)
+        .with_context(|| format!("Failed to read signing key: {key_path}"))?;
+    let signed = signing::sign(&metadata_json, component_bytes, &pkcs8)?;
+    if let Some(signature) = signing::embedded_signature(&signed)? {
+        info!("Signed with {}", signature.key_id);
     }
+    Ok(signed)
+}
+
+/// Compress and encrypt the payload as `--compress`/`package.compress` and
+/// `--encrypt-key`/`VTX_PAYLOAD_KEY`/`package.payload_key` ask.
+///
+/// Returns the metadata and the payload to store; both unchanged when neither is set.
+pub fn encode_payload_if_configured(
+    config: Option<&config::ProjectConfig>,
+    packaging: &PackagingArgs,
+    metadata_json: Vec<u8>,
+    component_bytes: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let package = config.and_then(|c| c.package.as_ref());
+    let compression = match (
+        &packaging.compress,
+        package.and_then(|p| p.compress.as_deref()),
+    ) {
+        (Some(compression), _) => Some(*compression),
+        (None, Some(setting)) => Some(
+            setting
+                .parse::<Compression>()
+                .map_err(|e| anyhow::anyhow!("Invalid package.compress: {e}"))?,
+        ),
+        (None, None) => None,
+    };
+    let key = match packaging.encrypt_key.as_deref() {
+        Some(path) => Some(PayloadKey::from_file(path)?),
+        None => match PayloadKey::load(None)? {
+            Some(key) => Some(key),
+            None => package
+                .and_then(|p| p.payload_key.as_deref())
+                .map(|path| PayloadKey::from_file(Path::new(path)))
+                .transpose()?,
+        },
+    };
+    if compression.is_none() && key.is_none() {
+        return Ok((metadata_json, component_bytes.to_vec()));
+    }
+    let (metadata_json, payload) =
+        container::encode_payload(&metadata_json, component_bytes, compression, key.as_ref())?;
+    info!(
+        "Payload stored as {} bytes ({}{})",
+        payload.len(),
+        compression.map_or_else(|| "uncompressed".to_string(), |c| c.to_string()),
+        key.map_or_else(String::new, |k| format!(", encrypted with {}", k.id()))
+    );
+    Ok((metadata_json, payload))
+}
+
+/// Write the checksum and SBOM when `--sbom` or `package.sbom` asks for them.
+///
+/// `project_dir` holds the language's lock data. Returns the written paths.
+pub fn write_sbom_if_requested(
+    config: Option<&config::ProjectConfig>,
+    packaging: &PackagingArgs,
+    vtx_path: &Path,
+    package_name: &str,
+    language: &str,
+    project_dir: &Path,
+) -> Result<Option<(PathBuf, PathBuf)>> {
+    let format = packaging.sbom.as_deref().or_else(|| {
+        config
+            .and_then(|c| c.package.as_ref())
+            .and_then(|p| p.sbom.as_ref())
+            .and_then(config::SbomSetting::format)
+    });
+    let Some(format) = format else {
+        return Ok(None);
+    };
+    let subject = sbom::SbomSubject {
+        name: package_name,
+        version: config.and_then(|c| c.project.version.as_deref()),
+        language,
+        project_dir,
+    };
+    let (checksum, document) =
+        sbom::write(vtx_path, &subject, format).context("Failed to write the SBOM")?;
+    info!("Checksum written: {}", checksum.display());
+    info!("SBOM written: {}", document.display());
+    Ok(Some((checksum, document)))
+}
+
+/// Project files and component facts embedded in the artifact metadata.
+#[derive(Default)]
+pub struct EmbeddedResources {
+    pub i18n: Option<i18n::Catalogs>,
+    pub settings_schema: Option<serde_json::Value>,
+    pub capabilities: Option<config::CapabilitiesConfig>,
+    /// The asset section from [`bundle_assets`].
+    pub assets: Option<Vec<u8>>,
+    /// Whether capabilities and assets get container sections of their own
+    /// (`--container-sections`); otherwise the container stays v2.
+    pub container_sections: bool,
+    /// Set from [`packager::required_interfaces`] once the component is encoded.
+    pub requires: Option<BTreeMap<String, String>>,
+    /// Kind of the WASI adapter injected during encoding, if one was.
+    pub adapter: Option<&'static str>,
+    /// [`Adapter::version`] of that adapter.
+    pub adapter_version: Option<String>,
+    /// Set from [`reproducible_build_time`] for reproducible builds.
+    pub build_time: Option<String>,
+    /// `package.digest`, used for the section digests in the metadata.
+    pub digest_algorithm: Option<String>,
+}
+
+impl EmbeddedResources {
+    /// The `[capabilities]` as written to the container's capabilities
+    /// section; `None` when they go into the metadata instead.
+    pub fn capabilities_section(&self) -> Result<Option<Vec<u8>>> {
+        self.capabilities
+            .as_ref()
+            .filter(|_| self.container_sections)
+            // Through `Value` so keys are sorted and the digest is stable.
+            .map(|c| Ok(serde_json::to_vec(&serde_json::to_value(c)?)?))
+            .transpose()
+    }
+}
+
+/// Load and validate `[i18n]` catalogs and the `[settings]` schema, and take
+/// the `[capabilities]` (validated with vtx.toml). Paths are relative to vtx.toml.
+pub fn load_embedded_resources(
+    config: Option<&config::ProjectConfig>,
+) -> Result<EmbeddedResources> {
+    let mut resources = EmbeddedResources {
+        digest_algorithm: Some(digest_algorithm(config).to_string()),
+        ..Default::default()
+    };
+
+    if let Some(i18n_config) = config.and_then(|c| c.i18n.as_ref()) {
+        let catalogs = i18n::load(i18n_config)?;
+        i18n::validate(i18n_config, &catalogs)?;
+
+        let keys = catalogs.values().next().map_or(0, |c| c.len());
+        info!(
+            "I18n catalogs validated: {} ({} keys)",
+            i18n_config.locales.join(", "),
+            keys
+        );
+        resources.i18n = Some(catalogs);
+    }
+
+    if let Some(settings_config) = config.and_then(|c| c.settings.as_ref()) {
+        let schema = settings::load(settings_config)?;
+        info!("Settings schema validated: {}", settings_config.schema);
+        resources.settings_schema = Some(schema);
+    }
+
+    if let Some(capabilities) = config.and_then(|c| c.capabilities.as_ref()) {
+        info!(
+            "Capabilities requested: {} permissions, {} hosts, {} paths, {} subscriptions",
+            capabilities.permissions.len(),
+            capabilities.network.len(),
+            capabilities.filesystem.len(),
+            capabilities.subscriptions.len()
+        );
+        resources.capabilities = Some(capabilities.clone());
+    }
+
+    Ok(resources)
+}
+
+/// Bundle the assets declared in `vtx-assets.json` into `resources`, after
+/// checking them against the files on disk. Nothing is bundled when no
+/// assets are declared, or without `--container-sections`, since a v2
+/// container has no asset section.
+pub fn bundle_assets(
+    config: Option<&config::ProjectConfig>,
+    resources: &mut EmbeddedResources,
+) -> Result<()> {
+    let manifest = assets::AssetManifest::load()?;
+    if manifest.files.is_empty() {
+        return Ok(());
+    }
+    if !resources.container_sections {
+        warn!(
+            "{} declared assets not bundled: pass --container-sections to write an asset section (container v4)",
+            manifest.files.len()
+        );
+        return Ok(());
+    }
+    let assets_config = config.and_then(|c| c.assets.as_ref());
+    super::assets::verify(&manifest, assets_config)?;
+    let root = assets::assets_dir(assets_config);
+    let files = manifest
+        .files
+        .iter()
+        .map(|file| {
+            let path = root.join(&file.path);
+            let bytes = std::fs::read(&path)
+                .with_context(|| format!("Failed to read asset: {}", path.display()))?;
+            Ok((file.path.clone(), bytes))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let section = container::encode_assets(&files)?;
+    info!(
+        "Assets bundled: {} files, {} bytes ({} bytes stored with compressed variants)",
+        files.len(),
+        manifest.total_size(),
+        section.len()
+    );
+    resources.assets = Some(section);
+    Ok(())
 }
 
 pub fn build_vtx_metadata_json(
@@ -59,31 +633,54 @@ pub fn build_vtx_metadata_json(
     language: &str,
     project_info: Option<&config::ProjectInfo>,
     sdk_version: Option<&str>,
+    sdk_config: Option<&config::SdkConfig>,
+    builder_meta: Option<&serde_json::Map<String, serde_json::Value>>,
+    resources: &EmbeddedResources,
 ) -> Result<Vec<u8>> {
-    let author = project_info.and_then(|p| p.author.as_deref());
-    let authors = project_info.and_then(|p| p.authors.as_ref());
-    let description = project_info.and_then(|p| p.description.as_deref());
-    let license = project_info.and_then(|p| p.license.as_deref());
-    let homepage = project_info.and_then(|p| p.homepage.as_deref());
-    let repository = project_info.and_then(|p| p.repository.as_deref());
-    let keywords = project_info.and_then(|p| p.keywords.as_ref());
-    let version = project_info.and_then(|p| p.version.as_deref());
-
-    let meta = json!({
-        "schema": 1,
-        "author": author,
-        "authors": authors,
-        "description": description,
-        "license": license,
-        "homepage": homepage,
-        "repository": repository,
-        "keywords": keywords,
-        "version": version,
-        "sdk_version": sdk_version,
-        "package": package_name,
-        "language": language,
-        "tool": { "name": "vtx-cli", "version": env!("CARGO_PKG_VERSION") }
-    });
+    let algorithm = resources
+        .digest_algorithm
+        .as_deref()
+        .unwrap_or(digest::DEFAULT_DIGEST_ALGORITHM);
+    let meta = VtxMetadata {
+        schema: METADATA_SCHEMA,
+        package: package_name.to_string(),
+        language: language.to_string(),
+        version: project_info.and_then(|p| p.version.clone()),
+        author: project_info.and_then(|p| p.author.clone()),
+        authors: project_info.and_then(|p| p.authors.clone()),
+        description: project_info.and_then(|p| p.description.clone()),
+        license: project_info.and_then(|p| p.license.clone()),
+        homepage: project_info.and_then(|p| p.homepage.clone()),
+        repository: project_info.and_then(|p| p.repository.clone()),
+        keywords: project_info.and_then(|p| p.keywords.clone()),
+        sdk_version: sdk_version.map(str::to_string),
+        sdk_channel: sdk_config.and_then(|s| s.channel.clone()),
+        adapter: resources.adapter.map(str::to_string),
+        adapter_version: resources.adapter_version.clone(),
+        i18n: resources.i18n.clone(),
+        settings_schema: resources.settings_schema.clone(),
+        capabilities: resources
+            .capabilities
+            .clone()
+            .filter(|_| !resources.container_sections),
+        capabilities_digest: resources
+            .capabilities_section()?
+            .map(|section| container::capabilities_digest(algorithm, &section))
+            .transpose()?,
+        assets_digest: resources
+            .assets
+            .as_deref()
+            .map(|section| container::assets_digest(algorithm, section))
+            .transpose()?,
+        requires: resources.requires.clone(),
+        signature: None,
+        manifest_signature: None,
+        payload: None,
+        build_time: resources.build_time.clone(),
+        builder: builder_meta.cloned(),
+        tool: ToolInfo::default(),
+    };
 
-    Ok(serde_json::to_vec(&meta)?)
+    // Through `Value` so every object, including builder fields, has sorted keys.
+    Ok(serde_json::to_vec(&serde_json::to_value(&meta)?)?)
 }
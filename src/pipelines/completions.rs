@@ -0,0 +1,17 @@
+use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::io::Write;
+
+use crate::cli::Cli;
+
+/// Print the completion script for `shell`, generated from the clap definitions.
+pub fn execute_completions_pipeline(shell: Shell) -> Result<()> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    // clap_complete panics on write errors; buffer so a closed pipe is an ordinary error.
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    std::io::stdout().write_all(&script)?;
+    Ok(())
+}
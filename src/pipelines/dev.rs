@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::warn;
+
+use super::jobs;
+use super::watch::watch_loop;
+use crate::config;
+use crate::kernel::Kernel;
+
+/// Rebuild on change and hot-deploy each successful build to a local kernel.
+///
+/// Behavior:
+/// - Rebuilds like `vtx watch`, running `vtx build` in a child process.
+/// - After each successful build, uploads the `.vtx` to the kernel's admin
+///   API under the project name. A failed build or deployment is reported and
+///   the loop keeps watching.
+/// - Streams the plugin's kernel logs as `[kernel] ...` lines.
+/// - Runs until interrupted with Ctrl-C.
+pub fn execute_dev_pipeline(
+    target: &str,
+    force: bool,
+    debug: bool,
+    debounce_ms: u64,
+    kernel_url: Option<&str>,
+) -> Result<()> {
+    let config = config::load()?;
+    let kernel = Kernel::resolve(kernel_url, Some(&config));
+    let id = config.project.name.clone();
+    let project_dir = PathBuf::from(config.project.path.as_deref().unwrap_or("."));
+    let exe = std::env::current_exe().context("Failed to locate the vtx executable")?;
+
+    let mut args = vec!["--target".to_string(), target.to_string()];
+    if force {
+        args.push("--force".to_string());
+    }
+    if debug {
+        args.push("--debug".to_string());
+    }
+
+    step!(
+        "Dev server for '{}' deploying to {} (Ctrl-C to stop)",
+        id,
+        kernel.base_url()
+    );
+    kernel.follow_logs(&id)?;
+
+    watch_loop(debounce_ms, || {
+        let started = Instant::now();
+        match jobs::run_child_build(&exe, "[build]", None, &args, &project_dir) {
+            Ok((path, _)) => deploy(&kernel, &id, &path, started),
+            Err(e) => warn!(
+                "Rebuild failed after {:.2}s: {:#}; keeping the deployed version",
+                started.elapsed().as_secs_f64(),
+                e
+            ),
+        }
+        Ok(())
+    })
+}
+
+fn deploy(kernel: &Kernel, id: &str, path: &Path, started: Instant) {
+    let result = std::fs::read(path)
+        .with_context(|| format!("Failed to read {}", path.display()))
+        .and_then(|bytes| kernel.deploy(id, &bytes));
+    match result {
+        Ok(()) => ok!(
+            "Deployed {} in {:.2}s; watching for changes",
+            path.display(),
+            started.elapsed().as_secs_f64()
+        ),
+        Err(e) => warn!("Built {} but could not deploy it: {:#}", path.display(), e),
+    }
+}
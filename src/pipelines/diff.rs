@@ -0,0 +1,321 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use vtx_cli::analysis::{self, SizeEntry};
+
+use crate::container::{self, PayloadKey};
+use crate::{output, packager};
+
+/// Metadata fields summarized under "versions", as `(label, JSON pointer)`.
+const VERSION_FIELDS: &[(&str, &str)] = &[
+    ("version", "/version"),
+    ("sdk", "/sdk_version"),
+    ("sdk channel", "/sdk_channel"),
+    ("adapter", "/adapter_version"),
+    ("tool", "/tool/version"),
+];
+
+/// One side of the comparison.
+struct Artifact {
+    size: u64,
+    /// Metadata flattened to dotted paths; empty for raw wasm.
+    metadata: BTreeMap<String, Value>,
+    raw_metadata: Value,
+    exports: Vec<String>,
+    imports: Vec<String>,
+    sections: BTreeMap<String, u64>,
+}
+
+/// A field's value in the old and the new artifact; `None` where it is absent.
+#[derive(Serialize, Debug)]
+struct FieldChange {
+    field: String,
+    old: Option<Value>,
+    new: Option<Value>,
+}
+
+/// Names only in the old (`removed`) or only in the new (`added`) artifact.
+#[derive(Serialize, Debug, Default)]
+struct NameChanges {
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct SizeChange {
+    name: String,
+    old: u64,
+    new: u64,
+    delta: i64,
+}
+
+/// JSON document printed by `vtx diff --json`.
+#[derive(Serialize)]
+struct DiffReport<'a> {
+    old: &'a str,
+    new: &'a str,
+    size: SizeChange,
+    versions: Vec<FieldChange>,
+    metadata: Vec<FieldChange>,
+    exports: NameChanges,
+    imports: NameChanges,
+    sections: Vec<SizeChange>,
+}
+
+/// Compare two artifacts: embedded SDK/adapter/tool versions, every other
+/// metadata field, exported and imported interfaces, and section sizes
+/// (custom sections as `custom:<name>`).
+///
+/// Accepts `.vtx` containers, bare components and core modules. The command
+/// only reports; it succeeds whether or not the artifacts differ.
+pub fn execute_diff_pipeline(old: &str, new: &str, json: bool) -> Result<()> {
+    let key = PayloadKey::load(None)?;
+    let before = load(old, key.as_ref())?;
+    let after = load(new, key.as_ref())?;
+
+    let versions = VERSION_FIELDS
+        .iter()
+        .map(|(label, pointer)| FieldChange {
+            field: label.to_string(),
+            old: before.raw_metadata.pointer(pointer).cloned(),
+            new: after.raw_metadata.pointer(pointer).cloned(),
+        })
+        .collect();
+    let summarized: Vec<String> = VERSION_FIELDS
+        .iter()
+        .map(|(_, pointer)| pointer[1..].replace('/', "."))
+        .collect();
+    let metadata = changed(&before.metadata, &after.metadata)
+        .into_iter()
+        .filter(|change| !summarized.contains(&change.field))
+        .collect();
+    let sections = before
+        .sections
+        .keys()
+        .chain(after.sections.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|name| {
+            size_change(
+                name,
+                before.sections.get(name).copied().unwrap_or(0),
+                after.sections.get(name).copied().unwrap_or(0),
+            )
+        })
+        .filter(|change| change.delta != 0)
+        .collect();
+
+    let report = DiffReport {
+        old,
+        new,
+        size: size_change("artifact", before.size, after.size),
+        versions,
+        metadata,
+        exports: name_changes(&before.exports, &after.exports),
+        imports: name_changes(&before.imports, &after.imports),
+        sections,
+    };
+    if json || output::is_json() {
+        output::document("diff", &report)
+    } else {
+        print_report(&report);
+        Ok(())
+    }
+}
+
+fn load(file: &str, key: Option<&PayloadKey>) -> Result<Artifact> {
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let (wasm, raw_metadata) = match container::decode(&bytes, key) {
+        Ok(decoded) => {
+            let metadata = match &decoded.metadata {
+                Some(json) => serde_json::from_slice(json)
+                    .with_context(|| format!("{file} has malformed metadata"))?,
+                None => Value::Null,
+            };
+            (decoded.component, metadata)
+        }
+        Err(_) if bytes.starts_with(b"\0asm") => (bytes.clone(), Value::Null),
+        Err(e) => return Err(e.context(format!("{file} is neither a .vtx container nor wasm"))),
+    };
+
+    let mut metadata = BTreeMap::new();
+    flatten("", &raw_metadata, &mut metadata);
+    let (exports, imports) = if packager::is_component(&wasm)? {
+        (
+            packager::component_exports(&wasm)?,
+            packager::component_imports(&wasm)?,
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    let report = analysis::analyze(&wasm).with_context(|| format!("Failed to parse {file}"))?;
+    let custom = report
+        .custom_sections
+        .iter()
+        .map(|SizeEntry { name, size }| (format!("custom:{name}"), *size));
+    let sections = report
+        .sections
+        .iter()
+        .map(|SizeEntry { name, size }| (name.clone(), *size))
+        .chain(custom)
+        .collect();
+
+    Ok(Artifact {
+        size: bytes.len() as u64,
+        metadata,
+        raw_metadata,
+        exports,
+        imports,
+        sections,
+    })
+}
+
+/// Flatten nested objects to dotted paths; arrays and scalars are leaves.
+/// Null fields count as absent.
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(fields) => flatten_object(prefix, fields, out),
+        Value::Null => {}
+        leaf => {
+            out.insert(prefix.to_string(), leaf.clone());
+        }
+    }
+}
+
+fn flatten_object(prefix: &str, fields: &Map<String, Value>, out: &mut BTreeMap<String, Value>) {
+    for (key, value) in fields {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        flatten(&path, value, out);
+    }
+}
+
+/// Fields whose value differs, including fields present on one side only.
+fn changed(old: &BTreeMap<String, Value>, new: &BTreeMap<String, Value>) -> Vec<FieldChange> {
+    old.keys()
+        .chain(new.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|k| old.get(*k) != new.get(*k))
+        .map(|k| FieldChange {
+            field: k.clone(),
+            old: old.get(k).cloned(),
+            new: new.get(k).cloned(),
+        })
+        .collect()
+}
+
+fn name_changes(old: &[String], new: &[String]) -> NameChanges {
+    NameChanges {
+        added: new.iter().filter(|n| !old.contains(n)).cloned().collect(),
+        removed: old.iter().filter(|n| !new.contains(n)).cloned().collect(),
+    }
+}
+
+fn size_change(name: &str, old: u64, new: u64) -> SizeChange {
+    SizeChange {
+        name: name.to_string(),
+        old,
+        new,
+        delta: new as i64 - old as i64,
+    }
+}
+
+fn print_report(report: &DiffReport) {
+    step!("{} -> {}", report.old, report.new);
+    println!(
+        "  size:     {} -> {} bytes ({})",
+        report.size.old,
+        report.size.new,
+        signed(report.size.delta)
+    );
+
+    println!("\nVERSIONS");
+    for change in &report.versions {
+        let old = display(change.old.as_ref());
+        let new = display(change.new.as_ref());
+        if old == new {
+            println!("  {:<12} {}", change.field, old);
+        } else {
+            println!("  {:<12} {} -> {}", change.field, old.red(), new.green());
+        }
+    }
+
+    println!("\nMETADATA");
+    if report.metadata.is_empty() {
+        println!("  no changes");
+    }
+    for change in &report.metadata {
+        match (&change.old, &change.new) {
+            (None, new) => println!(
+                "  {} {} = {}",
+                "+".green(),
+                change.field,
+                display(new.as_ref())
+            ),
+            (old, None) => println!(
+                "  {} {} = {}",
+                "-".red(),
+                change.field,
+                display(old.as_ref())
+            ),
+            (old, new) => println!(
+                "  {} {}: {} -> {}",
+                "~".yellow(),
+                change.field,
+                display(old.as_ref()),
+                display(new.as_ref())
+            ),
+        }
+    }
+
+    for (title, names) in [("EXPORTS", &report.exports), ("IMPORTS", &report.imports)] {
+        println!("\n{title}");
+        if names.added.is_empty() && names.removed.is_empty() {
+            println!("  no changes");
+        }
+        for name in &names.added {
+            println!("  {} {}", "+".green(), name);
+        }
+        for name in &names.removed {
+            println!("  {} {}", "-".red(), name);
+        }
+    }
+
+    println!("\nSECTIONS");
+    if report.sections.is_empty() {
+        println!("  no changes");
+    }
+    for change in &report.sections {
+        println!(
+            "  {:<28} {:>10} -> {:>10} {:>10}",
+            change.name,
+            change.old,
+            change.new,
+            signed(change.delta)
+        );
+    }
+}
+
+/// A size delta, red when the artifact grew and green when it shrank.
+fn signed(delta: i64) -> ColoredString {
+    let text = format!("{delta:+}");
+    match delta {
+        d if d > 0 => text.red(),
+        d if d < 0 => text.green(),
+        _ => text.normal(),
+    }
+}
+
+fn display(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "-".to_string(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
@@ -0,0 +1,302 @@
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::builder::{create_builder, ToolCheck};
+use crate::packager::BUNDLED_ADAPTER_VERSION;
+use crate::toolchain::{self, MIN_COMPONENTIZE_PY, MIN_NODE, MIN_PYTHON, MIN_TINYGO};
+use crate::{config, output};
+
+/// Outcome of one diagnosis item.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// One line of the doctor report.
+#[derive(Serialize, Debug)]
+struct Diagnosis {
+    /// Language the item belongs to, or `project` for vtx.toml checks.
+    scope: String,
+    item: String,
+    status: Status,
+    detail: String,
+    /// Command or instruction that fixes a warning or failure.
+    remediation: Option<String>,
+}
+
+/// JSON document printed by `vtx doctor --json`.
+#[derive(Serialize)]
+struct DoctorReport {
+    project_language: Option<String>,
+    diagnoses: Vec<Diagnosis>,
+}
+
+/// Diagnose the toolchains of every supported language and the project setup.
+///
+/// Unmet requirements of the project's own language are failures; those of
+/// other languages are only warnings. Fails if any failure was found.
+pub fn execute_doctor_pipeline(json: bool) -> Result<()> {
+    let json = json || output::is_json();
+    let config = config::load_optional()?;
+    let project_language = config
+        .as_ref()
+        .map(|c| toolchain::canonical_language(&c.project.language));
+    let custom_cmd = config
+        .as_ref()
+        .and_then(|c| c.build.as_ref())
+        .is_some_and(|b| b.cmd.is_some());
+
+    let mut diagnoses = Vec::new();
+    for language in toolchain::LANGUAGES {
+        // A custom build.cmd replaces the default toolchain of the project's language.
+        let enforced = project_language.as_deref() == Some(*language) && !custom_cmd;
+        let report = create_builder(language, None)?.check_env();
+        for check in &report.checks {
+            diagnoses.push(from_tool_check(language, check, enforced));
+        }
+        diagnoses.extend(extra_probes(language, &report.checks, enforced));
+    }
+
+    if let Some(config) = &config {
+        let pinned = config.sdk.as_ref().and_then(|s| s.adapter.as_deref());
+        diagnoses.push(adapter_diagnosis(pinned));
+    }
+
+    let failures = diagnoses
+        .iter()
+        .filter(|d| d.status == Status::Fail)
+        .count();
+    let warnings = diagnoses
+        .iter()
+        .filter(|d| d.status == Status::Warn)
+        .count();
+
+    if json {
+        output::document(
+            "doctor",
+            &DoctorReport {
+                project_language: project_language.clone(),
+                diagnoses,
+            },
+        )?;
+    } else {
+        match &project_language {
+            Some(language) => step!("Diagnosing environment (project language: {})", language),
+            None => step!("Diagnosing environment (no vtx.toml; nothing is required)"),
+        }
+        print_report(&diagnoses);
+        println!(
+            "\n{} passed, {} warning(s), {} failure(s)",
+            diagnoses.len() - warnings - failures,
+            warnings,
+            failures
+        );
+    }
+
+    if failures > 0 {
+        anyhow::bail!(
+            "{} required component(s) missing for language '{}'. Run the suggested commands and try again.",
+            failures,
+            project_language.as_deref().unwrap_or("unknown")
+        );
+    }
+    Ok(())
+}
+
+fn print_report(diagnoses: &[Diagnosis]) {
+    println!("\n{:<6} {:<8} {:<22} DETAIL", "STATUS", "SCOPE", "ITEM");
+    for d in diagnoses {
+        let status = match d.status {
+            Status::Pass => "PASS".green().bold(),
+            Status::Warn => "WARN".yellow().bold(),
+            Status::Fail => "FAIL".red().bold(),
+        };
+        println!("{:<6} {:<8} {:<22} {}", status, d.scope, d.item, d.detail);
+        if let Some(fix) = &d.remediation {
+            println!("{:<6} {:<8} {:<22} {} {}", "", "", "", "->".dimmed(), fix);
+        }
+    }
+}
+
+fn from_tool_check(language: &str, check: &ToolCheck, enforced: bool) -> Diagnosis {
+    let status = if check.satisfied {
+        Status::Pass
+    } else if check.required && enforced {
+        Status::Fail
+    } else {
+        Status::Warn
+    };
+    Diagnosis {
+        scope: language.to_string(),
+        item: check.tool.clone(),
+        status,
+        detail: match &check.version {
+            Some(version) => version.clone(),
+            None if check.required => format!("not {}", check.constraint),
+            None => format!("not {} (optional)", check.constraint),
+        },
+        remediation: check.remediation.clone(),
+    }
+}
+
+/// Checks beyond tool presence: wasm targets and minimum tool versions.
+fn extra_probes(language: &str, checks: &[ToolCheck], enforced: bool) -> Vec<Diagnosis> {
+    let installed = |tool: &str| {
+        checks
+            .iter()
+            .find(|c| c.tool == tool)
+            .and_then(|c| c.version.clone())
+    };
+    let mut diagnoses = Vec::new();
+    match language {
+        "rust" if installed("rustup").is_some() => {
+            let (status, detail) = match toolchain::rustup_has_target("wasm32-wasip1") {
+                Some(true) => (Status::Pass, "installed".to_string()),
+                Some(false) if enforced => (Status::Fail, "not installed".to_string()),
+                Some(false) => (Status::Warn, "not installed".to_string()),
+                None => (Status::Warn, "unable to list rustup targets".to_string()),
+            };
+            diagnoses.push(Diagnosis {
+                scope: language.to_string(),
+                item: "wasm32-wasip1 target".to_string(),
+                status,
+                detail,
+                remediation: (status != Status::Pass)
+                    .then(|| "rustup target add wasm32-wasip1".to_string()),
+            });
+        }
+        "go" => {
+            if let Some(version) = installed("tinygo") {
+                diagnoses.push(minimum_version(
+                    language,
+                    "tinygo",
+                    &version,
+                    MIN_TINYGO,
+                    enforced,
+                    "Upgrade TinyGo: https://tinygo.org/getting-started/install/",
+                ));
+            }
+        }
+        "python" => {
+            if let Some(version) = installed("python") {
+                diagnoses.push(minimum_version(
+                    language,
+                    "python",
+                    &version,
+                    MIN_PYTHON,
+                    enforced,
+                    "Install Python 3.10 or newer: https://www.python.org/downloads/",
+                ));
+            }
+            // The presence probe runs `--help`, which prints no version.
+            if let Some(version) = toolchain::probe_version("componentize-py", &["--version"]) {
+                diagnoses.push(minimum_version(
+                    language,
+                    "componentize-py",
+                    &version,
+                    MIN_COMPONENTIZE_PY,
+                    enforced,
+                    "pip install --upgrade componentize-py",
+                ));
+            }
+        }
+        "ts" => {
+            // npm is what the build needs; Node.js is only checked when present.
+            if let Some(version) = toolchain::probe_version("node", &["--version"]) {
+                diagnoses.push(minimum_version(
+                    language,
+                    "node",
+                    &version,
+                    MIN_NODE,
+                    enforced,
+                    "Install Node.js 18 or newer: https://nodejs.org",
+                ));
+            }
+        }
+        _ => {}
+    }
+    diagnoses
+}
+
+/// Compare the version found in a tool's `--version` line against `minimum`.
+fn minimum_version(
+    language: &str,
+    tool: &str,
+    version_line: &str,
+    minimum: &str,
+    enforced: bool,
+    remediation: &str,
+) -> Diagnosis {
+    let item = format!("{tool} >= {}", minimum.trim_end_matches(".0"));
+    let minimum = semver::Version::parse(minimum).expect("valid minimum version");
+    let (status, detail) = match parse_tool_version(version_line) {
+        Some(found) if found >= minimum => (Status::Pass, found.to_string()),
+        Some(found) if enforced => (Status::Fail, format!("found {found}")),
+        Some(found) => (Status::Warn, format!("found {found}")),
+        None => (
+            Status::Warn,
+            format!("unable to read version from '{version_line}'"),
+        ),
+    };
+    Diagnosis {
+        scope: language.to_string(),
+        item,
+        status,
+        detail,
+        remediation: (status != Status::Pass).then(|| remediation.to_string()),
+    }
+}
+
+/// First `x.y[.z]` token of a version line, e.g. `tinygo version 0.33.0 linux/amd64`.
+fn parse_tool_version(line: &str) -> Option<semver::Version> {
+    line.split_whitespace().find_map(|token| {
+        let token = token.trim_start_matches('v');
+        let numeric: String = token
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        let parts: Vec<u64> = numeric
+            .trim_end_matches('.')
+            .split('.')
+            .map(|p| p.parse().ok())
+            .collect::<Option<_>>()?;
+        match parts.as_slice() {
+            [major, minor] => Some(semver::Version::new(*major, *minor, 0)),
+            [major, minor, patch, ..] => Some(semver::Version::new(*major, *minor, *patch)),
+            _ => None,
+        }
+    })
+}
+
+fn adapter_diagnosis(pinned: Option<&str>) -> Diagnosis {
+    let (status, detail, remediation) = match pinned {
+        None => (
+            Status::Pass,
+            format!("bundled v{BUNDLED_ADAPTER_VERSION}"),
+            None,
+        ),
+        Some(pinned) if pinned.trim_start_matches('=') == BUNDLED_ADAPTER_VERSION => (
+            Status::Pass,
+            format!("pinned {pinned} matches bundled v{BUNDLED_ADAPTER_VERSION}"),
+            None,
+        ),
+        Some(pinned) => (
+            Status::Fail,
+            format!("pinned {pinned}, but this CLI bundles v{BUNDLED_ADAPTER_VERSION}"),
+            Some(format!(
+                "Set sdk.adapter = \"{BUNDLED_ADAPTER_VERSION}\" in vtx.toml or remove it"
+            )),
+        ),
+    };
+    Diagnosis {
+        scope: "project".to_string(),
+        item: "wasi adapter".to_string(),
+        status,
+        detail,
+        remediation,
+    }
+}
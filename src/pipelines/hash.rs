@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::path::Path;
+
+use crate::container;
+use crate::digest::{self, digest_matches};
+
+/// Print the digests of `.vtx` files, or verify files listed in a SHA256SUMS file.
+///
+/// Digests use the `<algorithm>:<hex>` form recorded in the ledger:
+/// - container: the whole `.vtx` file, as published;
+/// - payload: the embedded component;
+/// - metadata: the embedded metadata JSON (v2 and v3 containers).
+pub fn execute_hash_pipeline(files: &[String], algorithm: &str, check: Option<&str>) -> Result<()> {
+    if let Some(sums) = check {
+        return check_sums(Path::new(sums));
+    }
+    if files.is_empty() {
+        anyhow::bail!("No input files; pass one or more .vtx files or --check <SHA256SUMS>");
+    }
+
+    for file in files {
+        let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+        let decoded = container::decode_sections(&bytes)
+            .map_err(|e| anyhow::anyhow!("{file} is not a valid .vtx container: {e}"))?;
+
+        println!("{} (vtx v{})", file, decoded.version);
+        println!("  container: {}", digest::digest(algorithm, &bytes)?);
+        println!(
+            "  payload:   {}",
+            digest::digest(algorithm, decoded.component)?
+        );
+        if let Some(metadata) = decoded.metadata {
+            println!("  metadata:  {}", digest::digest(algorithm, metadata)?);
+        }
+    }
+    Ok(())
+}
+
+/// Verify `<hex>  <path>` lines (sha256sum/sha512sum format); paths are relative
+/// to the current directory. The algorithm comes from an `<algorithm>:` prefix
+/// on the digest, or else from the digest length.
+fn check_sums(sums_path: &Path) -> Result<()> {
+    let content = std::fs::read_to_string(sums_path)
+        .with_context(|| format!("Failed to read {}", sums_path.display()))?;
+
+    let mut failed = 0;
+    let mut checked = 0;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (expected, path) = line
+            .split_once(char::is_whitespace)
+            .map(|(hex, path)| (hex, path.trim_start().trim_start_matches('*')))
+            .with_context(|| format!("Malformed line {} in {}", i + 1, sums_path.display()))?;
+        let expected = match expected.len() {
+            64 => format!("sha256:{expected}"),
+            128 => format!("sha512:{expected}"),
+            _ => expected.to_string(),
+        };
+        checked += 1;
+
+        let status = match std::fs::read(path) {
+            Ok(bytes) if digest_matches(&expected, &bytes)? => "OK".green().to_string(),
+            Ok(_) => {
+                failed += 1;
+                "FAILED".red().to_string()
+            }
+            Err(_) => {
+                failed += 1;
+                "MISSING".red().to_string()
+            }
+        };
+        println!("{path}: {status}");
+    }
+
+    if failed > 0 {
+        anyhow::bail!(
+            "{failed} of {checked} files did not match {}",
+            sums_path.display()
+        );
+    }
+    ok!("{} files match {}", checked, sums_path.display());
+    Ok(())
+}
@@ -1,10 +1,12 @@
-use anyhow::Result;
-use colored::*;
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use tracing::{info, warn};
 
-use crate::templates;
+use crate::templates::{self, TemplateVars};
+use crate::{offline, release, toolchain};
 
 #[derive(Clone)]
 struct InitContext {
@@ -12,19 +14,30 @@ struct InitContext {
     language: String,
 }
 
+/// Options for the files `vtx init` generates.
+pub struct InitOptions<'a> {
+    /// Template directory or git URL; the built-in template for the language otherwise.
+    pub template: Option<&'a str>,
+    pub author: Option<&'a str>,
+    pub license: Option<&'a str>,
+    pub with_release_script: bool,
+}
+
 pub fn execute_init_pipeline(
     name: Option<&str>,
     language: Option<&str>,
     interactive: bool,
+    options: &InitOptions,
 ) -> Result<()> {
     let mut ctx = InitContext {
         name: name.unwrap_or_default().trim().to_string(),
         language: language.unwrap_or_default().trim().to_string(),
     };
 
-    if interactive || ctx.name.is_empty() || ctx.language.is_empty() {
-        println!("{} Interactive init", "[VTX]".green().bold());
-        ctx = prompt_init(ctx)?;
+    let needs_language = options.template.is_none() && ctx.language.is_empty();
+    if interactive || ctx.name.is_empty() || needs_language {
+        step!("Interactive init");
+        ctx = prompt_init(ctx, options.template.is_none())?;
     }
 
     let language = normalize_language(&ctx.language);
@@ -32,6 +45,15 @@ pub fn execute_init_pipeline(
     if name.is_empty() {
         anyhow::bail!("Project name cannot be empty.");
     }
+    let builtin = match options.template {
+        Some(_) => None,
+        None => Some(templates::builtin(&language).with_context(|| {
+            format!(
+                "Unsupported language identifier: {language} (built-in templates: {})",
+                templates::BUILTIN_LANGUAGES.join(", ")
+            )
+        })?),
+    };
 
     probe_environment(&language);
 
@@ -40,76 +62,146 @@ pub fn execute_init_pipeline(
         anyhow::bail!("Target directory already exists: {}", project_dir.display());
     }
 
-    std::fs::create_dir_all(project_dir)?;
+    let vars = template_vars(name, &language, options);
+    let unknown = match (builtin, options.template) {
+        (Some(template), _) => {
+            std::fs::create_dir_all(project_dir)?;
+            templates::write_builtin(template, &vars, project_dir)?
+        }
+        (None, Some(source)) => {
+            let fetched = fetch_template(source)?;
+            info!("Using template: {}", source);
+            std::fs::create_dir_all(project_dir)?;
+            templates::write_dir(fetched.path(), &vars, project_dir)?
+        }
+        (None, None) => unreachable!("a built-in template is selected without --template"),
+    };
+    let unknown: BTreeSet<String> = unknown.into_iter().collect();
+    if !unknown.is_empty() {
+        warn!(
+            "Template placeholders left unrendered (unknown variables): {}",
+            unknown.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+    if !project_dir.join("vtx.toml").exists() {
+        warn!("Template has no vtx.toml; add one before running vtx build.");
+    }
 
-    match language.as_str() {
-        "rust" => init_rust(project_dir, name),
-        "ts" => init_ts(project_dir, name),
-        "python" => init_python(project_dir, name),
-        unsupported => anyhow::bail!("Unsupported language identifier: {unsupported}"),
-    }?;
+    if options.with_release_script {
+        std::fs::write(
+            project_dir.join(release::RELEASE_FILE),
+            templates::release_toml(),
+        )?;
+    }
 
-    println!(
-        "{} Project initialized at: {}",
-        "[DONE]".green().bold(),
-        project_dir.display()
-    );
+    done!("Project initialized at: {}", project_dir.display());
 
     Ok(())
 }
 
-fn init_rust(project_dir: &Path, name: &str) -> Result<()> {
-    let src_dir = project_dir.join("src");
-    std::fs::create_dir_all(&src_dir)?;
-
-    std::fs::write(
-        project_dir.join("Cargo.toml"),
-        templates::rust_cargo_toml(name),
-    )?;
-    std::fs::write(src_dir.join("lib.rs"), templates::rust_lib_rs())?;
-    std::fs::write(src_dir.join("config.rs"), templates::rust_config_rs(name))?;
-    std::fs::write(project_dir.join("vtx.toml"), templates::rust_vtx_toml(name))?;
-
-    Ok(())
+/// Variables available to templates: name, name_snake, language, author, email, license.
+///
+/// The author and email default to `git config user.name`/`user.email`.
+fn template_vars(name: &str, language: &str, options: &InitOptions) -> TemplateVars {
+    let mut vars = TemplateVars::new();
+    vars.insert("name", name.to_string());
+    vars.insert("name_snake", name.replace('-', "_"));
+    if !language.is_empty() {
+        vars.insert("language", language.to_string());
+    }
+    vars.insert(
+        "author",
+        options
+            .author
+            .map(str::to_string)
+            .or_else(|| git_config("user.name"))
+            .unwrap_or_else(|| "Your Name".to_string()),
+    );
+    vars.insert(
+        "email",
+        git_config("user.email").unwrap_or_else(|| "you@example.com".to_string()),
+    );
+    vars.insert("license", options.license.unwrap_or("MIT").to_string());
+    vars
 }
 
-fn init_ts(project_dir: &Path, name: &str) -> Result<()> {
-    let src_dir = project_dir.join("src");
-    let dist_dir = project_dir.join("dist");
-    std::fs::create_dir_all(&src_dir)?;
-    std::fs::create_dir_all(&dist_dir)?;
-
-    std::fs::write(
-        project_dir.join("package.json"),
-        templates::ts_package_json(name),
-    )?;
-    std::fs::write(src_dir.join("index.ts"), templates::ts_index_ts())?;
-    std::fs::write(project_dir.join("vtx.toml"), templates::ts_vtx_toml(name))?;
+fn git_config(key: &str) -> Option<String> {
+    let output = Command::new("git").args(["config", key]).output().ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
 
-    Ok(())
+/// A template directory: the local path, or a shallow clone removed on drop.
+enum FetchedTemplate {
+    Local(PathBuf),
+    Cloned(tempfile::TempDir),
 }
 
-fn init_python(project_dir: &Path, name: &str) -> Result<()> {
-    let src_dir = project_dir.join("src");
-    let module_dir = src_dir.join(name.replace('-', "_"));
-    let dist_dir = project_dir.join("dist");
-    std::fs::create_dir_all(&module_dir)?;
-    std::fs::create_dir_all(&dist_dir)?;
+impl FetchedTemplate {
+    fn path(&self) -> &Path {
+        match self {
+            FetchedTemplate::Local(path) => path,
+            FetchedTemplate::Cloned(dir) => dir.path(),
+        }
+    }
+}
 
-    std::fs::write(
-        project_dir.join("pyproject.toml"),
-        templates::pyproject_toml(name),
-    )?;
-    std::fs::write(module_dir.join("__init__.py"), templates::python_init_py())?;
-    std::fs::write(
-        project_dir.join("vtx.toml"),
-        templates::python_vtx_toml(name),
-    )?;
+/// Resolve `--template`: an existing directory, or a git URL (`https://`,
+/// `ssh://`, `git@host:`, `file://` or `*.git`), optionally with `#<branch>`.
+/// `<vendor dir>/templates/<name>` takes precedence over cloning.
+fn fetch_template(source: &str) -> Result<FetchedTemplate> {
+    let local = Path::new(source);
+    if local.is_dir() {
+        return Ok(FetchedTemplate::Local(local.to_path_buf()));
+    }
+    let (url, branch) = match source.split_once('#') {
+        Some((url, branch)) => (url, Some(branch)),
+        None => (source, None),
+    };
+    // Vendored templates are looked up by name, or by repository name for git URLs.
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or(url);
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    if let Some(vendored) = offline::vendored(&format!("templates/{name}")) {
+        info!("Using vendored template {}", vendored.display());
+        return Ok(FetchedTemplate::Local(vendored));
+    }
+    let is_git_url = ["https://", "http://", "ssh://", "git://", "file://", "git@"]
+        .iter()
+        .any(|prefix| url.starts_with(prefix))
+        || url.ends_with(".git");
+    if !is_git_url {
+        anyhow::bail!("Template not found: {source} is neither a directory nor a git URL");
+    }
+    if !url.starts_with("file://") {
+        offline::ensure_online(
+            &format!("Cloning template {source}"),
+            &format!("Copy the template to <vendor dir>/templates/{name} (--vendor-dir or VTX_VENDOR_DIR)."),
+        )?;
+    }
 
-    Ok(())
+    let dir = tempfile::tempdir().context("Failed to create a directory for the template")?;
+    let mut clone = Command::new("git");
+    clone.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(branch) = branch {
+        clone.args(["--branch", branch]);
+    }
+    info!("Cloning template {}...", source);
+    let status = clone
+        .arg(url)
+        .arg(dir.path())
+        .status()
+        .context("Failed to run git clone (is git installed?)")?;
+    if !status.success() {
+        anyhow::bail!("git clone of template {source} failed");
+    }
+    Ok(FetchedTemplate::Cloned(dir))
 }
 
-fn prompt_init(mut ctx: InitContext) -> Result<InitContext> {
+fn prompt_init(mut ctx: InitContext, ask_language: bool) -> Result<InitContext> {
     let default_name = if ctx.name.is_empty() {
         "vtx-demo"
     } else {
@@ -118,6 +210,9 @@ fn prompt_init(mut ctx: InitContext) -> Result<InitContext> {
 
     let name = prompt_text("Project name", default_name)?;
     ctx.name = name;
+    if !ask_language {
+        return Ok(ctx);
+    }
 
     let default_language = if ctx.language.is_empty() {
         "rust"
@@ -146,6 +241,10 @@ fn prompt_language(default: &str) -> Result<String> {
     println!("  1) Rust");
     println!("  2) TypeScript");
     println!("  3) Python");
+    println!("  4) C/C++");
+    println!("  5) Go");
+    println!("  6) PHP");
+    println!("  7) Lua");
     print!("Enter choice (default: {default}): ");
     io::stdout().flush()?;
     let input = read_line()?;
@@ -156,6 +255,10 @@ fn prompt_language(default: &str) -> Result<String> {
         "1" => "rust".to_string(),
         "2" => "ts".to_string(),
         "3" => "python".to_string(),
+        "4" => "c".to_string(),
+        "5" => "go".to_string(),
+        "6" => "php".to_string(),
+        "7" => "lua".to_string(),
         other => other.to_string(),
     };
 
@@ -173,10 +276,13 @@ fn normalize_language(language: &str) -> String {
         "rust" | "rs" => "rust".to_string(),
         "ts" | "typescript" | "js" | "node" => "ts".to_string(),
         "py" | "python" => "python".to_string(),
+        "c" | "cpp" | "c++" | "cxx" => "c".to_string(),
+        "go" | "tinygo" => "go".to_string(),
         other => other.to_string(),
     }
 }
 
+#[allow(clippy::single_match)] // One arm per language with checks.
 fn probe_environment(language: &str) {
     match language {
         "rust" => probe_rust_environment(),
@@ -187,34 +293,22 @@ fn probe_environment(language: &str) {
 fn probe_rust_environment() {
     let cargo_ok = command_ok("cargo", &["--version"]);
     if cargo_ok {
-        println!("{} Cargo detected.", "[OK]".green().bold());
+        ok!("Cargo detected.");
     } else {
-        println!(
-            "{} Cargo not found. Install Rust from https://rustup.rs",
-            "[WARN]".yellow()
-        );
+        warn!("Cargo not found. Install Rust from https://rustup.rs");
         return;
     }
 
     let rustup_ok = command_ok("rustup", &["--version"]);
     if !rustup_ok {
-        println!(
-            "{} rustup not found. Target checks skipped.",
-            "[WARN]".yellow()
-        );
+        warn!("rustup not found. Target checks skipped.");
         return;
     }
 
-    match rustup_has_target("wasm32-wasip1") {
-        Some(true) => println!("{} wasm32-wasip1 target installed.", "[OK]".green().bold()),
-        Some(false) => println!(
-            "{} wasm32-wasip1 target missing. Run: rustup target add wasm32-wasip1",
-            "[WARN]".yellow()
-        ),
-        None => println!(
-            "{} Unable to inspect installed targets.",
-            "[WARN]".yellow()
-        ),
+    match toolchain::rustup_has_target("wasm32-wasip1") {
+        Some(true) => ok!("wasm32-wasip1 target installed."),
+        Some(false) => warn!("wasm32-wasip1 target missing. Run: rustup target add wasm32-wasip1"),
+        None => warn!("Unable to inspect installed targets."),
     }
 }
 
@@ -225,17 +319,3 @@ fn command_ok(cmd: &str, args: &[&str]) -> bool {
         .map(|out| out.status.success())
         .unwrap_or(false)
 }
-
-fn rustup_has_target(target: &str) -> Option<bool> {
-    let output = Command::new("rustup")
-        .args(["target", "list", "--installed"])
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Some(stdout.lines().any(|line| line.trim() == target))
-}
@@ -0,0 +1,320 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::config::CapabilitiesConfig;
+use crate::container::{self, AssetIndex, PayloadKey};
+use crate::digest::digest_matches;
+use crate::ledger::{self, sha256_digest, LedgerEntry, LEDGER_FILE};
+use crate::metadata::VtxMetadata;
+use crate::{output, packager, sbom};
+
+/// `vtx inspect --json` output.
+#[derive(Serialize)]
+struct InspectReport<'a> {
+    file: &'a str,
+    version: u8,
+    container_size: usize,
+    /// Size of the component after decompression and decryption.
+    payload_size: usize,
+    /// Size of the payload section as stored.
+    stored_payload_size: usize,
+    /// Embedded metadata as stored, including fields this CLI does not know.
+    metadata: Option<Value>,
+    /// The capabilities section of v3 containers.
+    capabilities: Option<CapabilitiesConfig>,
+    /// The asset index of v4 containers.
+    assets: Option<AssetIndex>,
+    exports: Vec<String>,
+    imports: Vec<String>,
+}
+
+/// Print the metadata, exports and imports of a `.vtx` file, or with
+/// `provenance` the chain of where it came from as a tree.
+///
+/// Provenance combines the embedded metadata with the newest entry in the
+/// local ledger whose artifact digest matches the file.
+pub fn execute_inspect_pipeline(
+    file: &str,
+    provenance: bool,
+    json: bool,
+    key_file: Option<&Path>,
+) -> Result<()> {
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    container::decode_sections(&bytes)
+        .map_err(|e| anyhow::anyhow!("{file} is not a valid .vtx container: {e}"))?;
+    let key = PayloadKey::load(key_file)?;
+    let decoded = container::decode(&bytes, key.as_ref())?;
+    let meta: VtxMetadata = match &decoded.metadata {
+        Some(json) => serde_json::from_slice(json)
+            .with_context(|| format!("{file} has malformed metadata"))?,
+        None => VtxMetadata::default(),
+    };
+
+    if !provenance {
+        let report = InspectReport {
+            file,
+            version: decoded.version,
+            container_size: bytes.len(),
+            payload_size: decoded.component.len(),
+            stored_payload_size: decoded.stored_size,
+            metadata: decoded
+                .metadata
+                .as_deref()
+                .map(serde_json::from_slice)
+                .transpose()?,
+            capabilities: decoded
+                .capabilities
+                .as_deref()
+                .map(serde_json::from_slice)
+                .transpose()
+                .with_context(|| format!("{file} has a malformed capabilities section"))?
+                .or_else(|| meta.capabilities.clone()),
+            assets: decoded
+                .assets
+                .as_deref()
+                .map(|section| container::decode_assets(section).map(|bundle| bundle.index))
+                .transpose()?,
+            exports: packager::component_exports(&decoded.component)?,
+            imports: packager::component_imports(&decoded.component)?,
+        };
+        if json || output::is_json() {
+            output::document("inspect", &report)?;
+        } else {
+            print_summary(&report, &meta);
+        }
+        return Ok(());
+    }
+
+    let mut entry = None;
+    for candidate in ledger::read_entries(Path::new(LEDGER_FILE))?
+        .into_iter()
+        .rev()
+    {
+        if digest_matches(&candidate.artifact_digest, &bytes)? {
+            entry = Some(candidate);
+            break;
+        }
+    }
+    let digest = entry
+        .as_ref()
+        .map_or_else(|| sha256_digest(&bytes), |e| e.artifact_digest.clone());
+    let sbom = sbom_provenance(Path::new(file), &bytes)?;
+    print_provenance(file, &digest, &meta, entry.as_ref(), sbom);
+    Ok(())
+}
+
+/// The SBOM next to `file`, its digest, and whether the artifact digest
+/// recorded in `<file>.sha256` still matches `bytes`.
+fn sbom_provenance(file: &Path, bytes: &[u8]) -> Result<Vec<String>> {
+    let Some((document, format)) = sbom::find(file) else {
+        return Ok(vec!["not recorded".to_string()]);
+    };
+    let content = std::fs::read(&document)
+        .with_context(|| format!("Failed to read {}", document.display()))?;
+    let checksum = match sbom::recorded_digest(file)? {
+        Some(recorded) if recorded == sha256_digest(bytes) => format!("{recorded} (matches)"),
+        Some(recorded) => {
+            format!("{recorded} (does not match; the artifact changed after the SBOM was written)")
+        }
+        None => "not recorded".to_string(),
+    };
+    Ok(vec![
+        format!("document: {} ({format})", document.display()),
+        format!("digest: {}", sha256_digest(&content)),
+        format!("artifact checksum: {checksum}"),
+    ])
+}
+
+fn print_summary(report: &InspectReport, meta: &VtxMetadata) {
+    step!(
+        "{} (vtx v{}, {} bytes)",
+        report.file,
+        report.version,
+        report.container_size
+    );
+    println!(
+        "  package:  {} {}",
+        or_unknown(&meta.package),
+        meta.version.as_deref().unwrap_or_default()
+    );
+    println!("  language: {}", or_unknown(&meta.language));
+    println!(
+        "  sdk:      {}",
+        meta.sdk_version.as_deref().unwrap_or("unknown")
+    );
+    println!(
+        "  adapter:  {}",
+        meta.adapter_version.as_deref().unwrap_or("unknown")
+    );
+    println!("  tool:     {} {}", meta.tool.name, meta.tool.version);
+    if let Some(time) = &meta.build_time {
+        println!("  built:    {time} (reproducible)");
+    }
+    match &meta.payload {
+        Some(encoding) => println!(
+            "  payload:  {} bytes, stored as {} bytes ({})",
+            report.payload_size,
+            report.stored_payload_size,
+            encoding.codecs()
+        ),
+        None => println!("  payload:  {} bytes", report.payload_size),
+    }
+    print_names("exports", &report.exports);
+    print_names("imports", &report.imports);
+    match &meta.requires {
+        Some(requires) if !requires.is_empty() => {
+            println!("  requires:");
+            for (interface, version) in requires {
+                println!("    {interface} {version}");
+            }
+        }
+        Some(_) => println!("  requires: none"),
+        None => println!("  requires: not recorded"),
+    }
+    if let Some(capabilities) = &report.capabilities {
+        println!("  capabilities:");
+        for (label, values) in [
+            ("permissions", &capabilities.permissions),
+            ("network", &capabilities.network),
+            ("filesystem", &capabilities.filesystem),
+            ("subscriptions", &capabilities.subscriptions),
+        ] {
+            if !values.is_empty() {
+                println!("    {label}: {}", values.join(", "));
+            }
+        }
+    }
+    if let Some(assets) = &report.assets {
+        println!("  assets:");
+        for file in &assets.files {
+            let encodings: Vec<&str> = file.encodings.keys().map(String::as_str).collect();
+            println!(
+                "    {} ({} bytes; {})",
+                file.path,
+                file.size,
+                encodings.join(", ")
+            );
+        }
+    }
+}
+
+fn print_names(label: &str, names: &[String]) {
+    if names.is_empty() {
+        println!("  {label}:  none");
+        return;
+    }
+    println!("  {label}:");
+    for name in names {
+        println!("    {name}");
+    }
+}
+
+fn print_provenance(
+    file: &str,
+    digest: &str,
+    meta: &VtxMetadata,
+    entry: Option<&LedgerEntry>,
+    sbom: Vec<String>,
+) {
+    let not_recorded = || "not recorded".to_string();
+
+    let source = vec![
+        format!(
+            "package: {} {}",
+            or_unknown(&meta.package),
+            meta.version.as_deref().unwrap_or_default()
+        ),
+        format!(
+            "revision: {}",
+            entry
+                .and_then(|e| e.source_revision.clone())
+                .unwrap_or_else(not_recorded)
+        ),
+        format!(
+            "repository: {}",
+            meta.repository.clone().unwrap_or_else(not_recorded)
+        ),
+    ];
+
+    let mut builder = vec![format!("tool: {} {}", meta.tool.name, meta.tool.version)];
+    if let Some(time) = &meta.build_time {
+        builder.push(format!("build time: {time} (reproducible)"));
+    }
+    match entry {
+        Some(e) => {
+            builder.push(format!("host: {}", e.builder_host));
+            builder.push(format!("built: {} (ledger #{})", e.timestamp, e.seq));
+        }
+        None => builder.push(format!("ledger: no entry in {LEDGER_FILE}")),
+    }
+
+    let mut toolchain = vec![
+        format!(
+            "sdk: {}",
+            meta.sdk_version.clone().unwrap_or_else(not_recorded)
+        ),
+        format!(
+            "adapter: {}",
+            meta.adapter_version.clone().unwrap_or_else(not_recorded)
+        ),
+    ];
+    for (key, value) in meta.builder.iter().flatten() {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        toolchain.push(format!("{key}: {value}"));
+    }
+
+    let signer = meta
+        .signature
+        .as_ref()
+        .map(|s| format!("{} (embedded, check with vtx verify)", s.key_id))
+        .or_else(|| entry.and_then(|e| e.signer.clone()))
+        .unwrap_or_else(|| "none (unsigned)".to_string());
+    let mut signatures = vec![signer];
+    if let Some(manifest) = &meta.manifest_signature {
+        signatures.push(format!("manifest: {}", manifest.key_id));
+    }
+
+    println!("{file}");
+    let sections: [(&str, Vec<String>); 6] = [
+        ("artifact", vec![digest.to_string()]),
+        ("source", source),
+        ("builder", builder),
+        ("toolchain", toolchain),
+        ("signatures", signatures),
+        ("sbom", sbom),
+    ];
+    let last_section = sections.len() - 1;
+    for (i, (name, lines)) in sections.iter().enumerate() {
+        let (branch, indent) = if i == last_section {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        if let [only] = lines.as_slice() {
+            println!("{branch}{name}: {only}");
+            continue;
+        }
+        println!("{branch}{name}");
+        for (j, line) in lines.iter().enumerate() {
+            let leaf = if j + 1 == lines.len() {
+                "└── "
+            } else {
+                "├── "
+            };
+            println!("{indent}{leaf}{line}");
+        }
+    }
+}
+
+fn or_unknown(value: &str) -> &str {
+    if value.is_empty() {
+        "unknown"
+    } else {
+        value
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde_json::Value;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::{output, scratch};
+
+/// A finished member build: the `.vtx` path and its size, or why it failed.
+pub(super) type MemberResult = Result<(PathBuf, u64)>;
+
+/// What a child `vtx --output json <command>` reported.
+#[derive(Default)]
+pub(super) struct ChildReport {
+    /// Path and size from the `artifact` event.
+    pub artifact: Option<(PathBuf, Option<u64>)>,
+    /// `data` of the `report` event.
+    pub report: Option<Value>,
+    /// Why the child failed: the `error` event, or else its exit status.
+    pub error: Option<String>,
+}
+
+/// Run `run` for every member on up to `jobs` threads.
+///
+/// A failing member does not stop the others. After an interrupt no new
+/// members are started and running ones are awaited.
+///
+/// Results are returned in the order of `members`.
+pub(super) fn run_parallel<T: Send>(
+    members: &[String],
+    jobs: usize,
+    run: impl Fn(&str) -> Result<T> + Sync,
+) -> Result<Vec<Result<T>>> {
+    let cancel = scratch::interrupt_token();
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<T>>>> = Mutex::new(members.iter().map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, members.len().max(1)) {
+            scope.spawn(|| loop {
+                if cancel.is_cancelled() {
+                    break;
+                }
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(member) = members.get(index) else {
+                    break;
+                };
+                let result = run(member);
+                results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+            });
+        }
+    });
+    cancel.check()?;
+
+    Ok(results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|| Err(anyhow::anyhow!("Member was not started"))))
+        .collect())
+}
+
+/// Run `vtx --output json build <args>` and return the artifact it reports.
+///
+/// The artifact path is resolved against `project_dir`.
+pub(super) fn run_child_build(
+    exe: &Path,
+    prefix: &str,
+    package: Option<&str>,
+    args: &[String],
+    project_dir: &Path,
+) -> MemberResult {
+    built_artifact(run_child(exe, "build", prefix, package, args)?, project_dir)
+}
+
+/// The artifact a child build reported, resolved against `project_dir`,
+/// or why the build failed.
+pub(super) fn built_artifact(child: ChildReport, project_dir: &Path) -> MemberResult {
+    if let Some(error) = child.error {
+        anyhow::bail!(error);
+    }
+    let (path, size) = child.artifact.context("vtx build reported no artifact")?;
+    let path = project_dir.join(path);
+    let size = match size {
+        Some(size) => size,
+        None => std::fs::metadata(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .len(),
+    };
+    Ok((path, size))
+}
+
+/// Run `vtx --output json <command> <args>` and collect what it reports.
+///
+/// Child log lines are printed under `prefix`. Events other than the
+/// artifact, report, error and result are forwarded, tagged with `package`
+/// if given. A child that exits non-zero is not an error here; its reason is
+/// left in [`ChildReport::error`].
+pub(super) fn run_child(
+    exe: &Path,
+    command: &str,
+    prefix: &str,
+    package: Option<&str>,
+    args: &[String],
+) -> Result<ChildReport> {
+    let mut child = Command::new(exe)
+        .args(["--output", "json", command])
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run vtx {command}"))?;
+    let stdout = child.stdout.take().context("Child stdout unavailable")?;
+    let stderr = child.stderr.take().context("Child stderr unavailable")?;
+
+    let mut report = ChildReport::default();
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+                relay(prefix, &line);
+            }
+        });
+        // On Unix stdout carries only JSON events; elsewhere human lines are mixed in.
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            let Ok(Value::Object(mut event)) = serde_json::from_str::<Value>(&line) else {
+                relay(prefix, &line);
+                continue;
+            };
+            let Some(Value::String(name)) = event.remove("event") else {
+                relay(prefix, &line);
+                continue;
+            };
+            match name.as_str() {
+                "artifact" => {
+                    let path = event.get("path").and_then(Value::as_str).map(PathBuf::from);
+                    let size = event.get("size").and_then(Value::as_u64);
+                    report.artifact = path.map(|path| (path, size));
+                }
+                "report" => {
+                    report.report = event.remove("data");
+                    continue;
+                }
+                "error" => {
+                    report.error = event
+                        .get("message")
+                        .and_then(Value::as_str)
+                        .map(str::to_string);
+                }
+                "result" => continue,
+                _ => {}
+            }
+            if let Some(package) = package {
+                event
+                    .entry("package")
+                    .or_insert_with(|| Value::String(package.to_string()));
+            }
+            output::emit(&name, Value::Object(event));
+        }
+    });
+
+    let status = child
+        .wait()
+        .with_context(|| format!("Failed to wait for vtx {command}"))?;
+    if status.success() {
+        report.error = None;
+    } else if report.error.is_none() {
+        report.error = Some(format!("vtx {command} exited with {status}"));
+    }
+    Ok(report)
+}
+
+/// Print a child log line under the member's prefix.
+fn relay(prefix: &str, line: &str) {
+    println!("{} {}", prefix.bold(), line);
+}
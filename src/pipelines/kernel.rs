@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+
+use crate::cli::KernelAction;
+use crate::container::{self, PayloadKey};
+use crate::harness::{self, HarnessOptions};
+use crate::{output, packager, scratch};
+use vtx_cli::kernel_api::{KernelLimits, KernelProfile};
+
+/// Outcome of one import or export check.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Linked,
+    Present,
+    Missing,
+}
+
+/// One line of the emulation report.
+#[derive(Serialize, Debug)]
+struct Item {
+    name: String,
+    status: Status,
+    detail: String,
+}
+
+/// JSON document printed by `vtx kernel emulate --json`.
+#[derive(Serialize)]
+struct EmulationReport<'a> {
+    file: &'a str,
+    api: String,
+    /// Kernel API table entry the profile comes from.
+    entry: String,
+    loads: bool,
+    imports: Vec<Item>,
+    exports: Vec<Item>,
+    limits: &'a KernelLimits,
+    /// Why instantiation failed.
+    error: Option<String>,
+}
+
+pub fn execute_kernel_pipeline(action: &KernelAction) -> Result<()> {
+    match action {
+        KernelAction::Emulate { file, api, json } => emulate(file, api, *json),
+    }
+}
+
+/// Load a plugin the way a kernel implementing `api` would.
+///
+/// The kernel comes from the kernel API table (see [`KernelProfile`]). Every
+/// `vtx:api` import is reported as linked or missing, every export the kernel
+/// requires as present or missing, and the component is then instantiated
+/// in the harness under that kernel's interfaces and limits. Fails when it
+/// would not load.
+fn emulate(file: &str, api: &str, json: bool) -> Result<()> {
+    let profile = KernelProfile::resolve(api)?;
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let component = container::unwrap_component(bytes, PayloadKey::load(None)?.as_ref())?;
+
+    let mut imports = Vec::new();
+    for import in packager::component_imports(&component)? {
+        if !import.starts_with("vtx:") {
+            continue;
+        }
+        let item = match profile.missing_import(&import)? {
+            Some(reason) => Item {
+                name: import,
+                status: Status::Missing,
+                detail: reason,
+            },
+            None => Item {
+                name: import,
+                status: Status::Linked,
+                detail: String::new(),
+            },
+        };
+        imports.push(item);
+    }
+    let exported = packager::component_exports(&component)?;
+    let exports = profile
+        .exports
+        .iter()
+        .map(|export| {
+            let present = exported.contains(export);
+            Item {
+                name: export.clone(),
+                status: if present {
+                    Status::Present
+                } else {
+                    Status::Missing
+                },
+                detail: if present {
+                    String::new()
+                } else {
+                    format!("required by vtx:api@{}", profile.version)
+                },
+            }
+        })
+        .collect();
+
+    let options = HarnessOptions {
+        capture_output: true,
+        kernel_api: Some(profile.clone()),
+        ..HarnessOptions::default()
+    };
+    let error = harness::check_instantiation(&component, &options, &scratch::interrupt_token())
+        .err()
+        .map(|e| format!("{e:#}"));
+
+    let report = EmulationReport {
+        file,
+        api: profile.version.to_string(),
+        entry: profile.entry.to_string(),
+        loads: error.is_none(),
+        imports,
+        exports,
+        limits: &profile.limits,
+        error,
+    };
+    if json || output::is_json() {
+        output::document("kernel-emulate", &report)?;
+    } else {
+        print_report(&report);
+    }
+
+    if let Some(error) = &report.error {
+        anyhow::bail!(
+            "{file} would not load on a vtx:api@{} kernel: {error}",
+            report.api
+        );
+    }
+    if !json && !output::is_json() {
+        done!("{} loads on a vtx:api@{} kernel", file, report.api);
+    }
+    Ok(())
+}
+
+fn print_report(report: &EmulationReport) {
+    step!(
+        "Emulating vtx:api@{} (kernel API table entry {})",
+        report.api,
+        report.entry
+    );
+    println!("\n{:<8} {:<40} DETAIL", "STATUS", "IMPORT / EXPORT");
+    for item in report.imports.iter().chain(&report.exports) {
+        let status = match item.status {
+            Status::Linked => "LINKED".green().bold(),
+            Status::Present => "PRESENT".green().bold(),
+            Status::Missing => "MISSING".red().bold(),
+        };
+        println!("{:<8} {:<40} {}", status, item.name, item.detail);
+    }
+
+    let limits = report.limits;
+    let describe = |value: Option<String>| value.unwrap_or_else(|| "unlimited".to_string());
+    println!("\nLimits:");
+    println!(
+        "  memory:    {}",
+        describe(limits.max_memory_bytes.map(|b| format!("{b} bytes")))
+    );
+    println!(
+        "  table:     {}",
+        describe(limits.max_table_elements.map(|n| format!("{n} elements")))
+    );
+    println!(
+        "  time:      {}",
+        describe(limits.timeout_ms.map(|ms| format!("{ms} ms")))
+    );
+    println!(
+        "  response:  {}",
+        describe(limits.max_response_bytes.map(|b| format!("{b} bytes")))
+    );
+    println!();
+}
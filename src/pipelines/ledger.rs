@@ -0,0 +1,39 @@
+use anyhow::Result;
+use std::path::Path;
+use tracing::info;
+
+use crate::cli::LedgerAction;
+use crate::ledger::{self, LEDGER_FILE};
+
+pub fn execute_ledger_pipeline(action: &LedgerAction) -> Result<()> {
+    let entries = ledger::read_entries(Path::new(LEDGER_FILE))?;
+
+    match action {
+        LedgerAction::Show => {
+            if entries.is_empty() {
+                info!("No ledger entries in {}", LEDGER_FILE);
+                return Ok(());
+            }
+            for entry in &entries {
+                println!(
+                    "#{:<4} {}  {}  {}  host={}",
+                    entry.seq,
+                    entry.timestamp,
+                    entry.artifact_digest,
+                    entry.artifact,
+                    entry.builder_host
+                );
+            }
+        }
+        LedgerAction::Verify => {
+            let count = ledger::verify(&entries)?;
+            ok!(
+                "Ledger intact: {} entries verified in {}",
+                count,
+                LEDGER_FILE
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,414 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::workspace::{self, Outcome, Workspace};
+use crate::cli::WorkspaceArgs;
+use crate::config::{self, ProjectConfig, CAPABILITY_PERMISSIONS};
+use crate::{output, toolchain};
+
+/// Severity of a lint finding.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Warning,
+    Error,
+}
+
+/// One problem found in the project.
+#[derive(Serialize, Deserialize, Debug)]
+struct Finding {
+    rule: String,
+    severity: Severity,
+    /// File the finding refers to, relative to the vtx.toml directory.
+    file: String,
+    message: String,
+}
+
+/// JSON document printed by `vtx lint --json`.
+#[derive(Serialize, Deserialize)]
+struct LintReport {
+    deny_warnings: bool,
+    errors: usize,
+    warnings: usize,
+    findings: Vec<Finding>,
+}
+
+/// Collects findings for one lint run.
+struct Lints {
+    project_dir: PathBuf,
+    findings: Vec<Finding>,
+}
+
+impl Lints {
+    fn push(&mut self, rule: &'static str, severity: Severity, file: &str, message: String) {
+        let file = if file == "vtx.toml" {
+            file.to_string()
+        } else {
+            self.project_dir.join(file).display().to_string()
+        };
+        self.findings.push(Finding {
+            rule: rule.to_string(),
+            severity,
+            file,
+            message,
+        });
+    }
+}
+
+/// Inspect the project for common plugin mistakes without building it.
+///
+/// Rules:
+/// - `required-field`: `project.name`, `project.language` or `project.version` is blank.
+/// - `missing-version`: `project.version` is not set, so artifacts carry no version.
+/// - `empty-field`: an optional `[project]` field is present but blank.
+/// - `semver`: `project.version` is not a semantic version.
+/// - `version-mismatch`: Cargo.toml or package.json declares a different version.
+/// - `crate-type`: a Rust project does not build a `cdylib`.
+/// - `broad-permission`: capabilities that grant far more than a plugin needs.
+/// - `scaffold-build`: the package.json/composer.json build script is the scaffold placeholder.
+///
+/// Fails on any error, or on any warning when `deny_warnings` is set.
+/// `--package` lints a workspace member's manifest instead of the root one;
+/// `--workspace` lints every member (see [`lint_workspace`]).
+pub fn execute_lint_pipeline(
+    members: &WorkspaceArgs,
+    deny_warnings: bool,
+    json: bool,
+) -> Result<()> {
+    let json = json || output::is_json();
+    if members.workspace {
+        return lint_workspace(members, deny_warnings, json);
+    }
+    let config = config::load()?;
+    let project_dir = PathBuf::from(config.project.path.as_deref().unwrap_or(""));
+    let (name, dir) = match &members.package {
+        Some(package) => (
+            package.as_str(),
+            project_dir.join(workspace::member_dir(&config, package)?),
+        ),
+        None => (config.project.name.as_str(), project_dir),
+    };
+    let report = lint(&config, dir, deny_warnings)?;
+
+    if json {
+        output::document("lint", &report)?;
+    } else {
+        print_report(name, &report);
+    }
+    if let Some(failure) = failure(&report) {
+        anyhow::bail!(failure);
+    }
+    if !json {
+        if report.warnings > 0 {
+            done!("Lint passed with {} warning(s)", report.warnings);
+        } else {
+            done!("No lint findings");
+        }
+    }
+    Ok(())
+}
+
+/// Lint every workspace member, then print each member's findings and a
+/// summary table. `--json` prints one document with a report per member.
+fn lint_workspace(members: &WorkspaceArgs, deny_warnings: bool, json: bool) -> Result<()> {
+    let workspace = Workspace::discover(members)?;
+    let config = config::load()?;
+    let project_dir = PathBuf::from(config.project.path.as_deref().unwrap_or(""));
+    let names = workspace.names();
+    step!(
+        "Linting workspace: {} packages ({})",
+        names.len(),
+        names.join(", ")
+    );
+
+    let args = if deny_warnings {
+        vec!["--deny".to_string(), "warnings".to_string()]
+    } else {
+        Vec::new()
+    };
+    let results = workspace.run(
+        "lint",
+        &args,
+        |_, dir| lint(&config, project_dir.join(dir), deny_warnings),
+        |_, child| match child.report {
+            Some(report) => Ok(serde_json::from_value(report)?),
+            None => anyhow::bail!(child
+                .error
+                .unwrap_or_else(|| "vtx lint reported nothing".into())),
+        },
+    )?;
+
+    if json {
+        let reports: Vec<_> = results
+            .iter()
+            .filter_map(|(member, result)| {
+                let report = serde_json::to_value(result.as_ref().ok()?).ok()?;
+                Some(serde_json::json!({ "package": member, "report": report }))
+            })
+            .collect();
+        output::document("lint", &serde_json::json!({ "members": reports }))?;
+    } else {
+        for (member, result) in &results {
+            if let Ok(report) = result {
+                print_report(member, report);
+            }
+        }
+    }
+
+    let failed = workspace::summarize("lint", &results, |report| Outcome {
+        passed: failure(report).is_none(),
+        detail: format!("{} error(s), {} warning(s)", report.errors, report.warnings),
+        fields: serde_json::json!({ "errors": report.errors, "warnings": report.warnings }),
+    });
+    if failed > 0 {
+        anyhow::bail!(
+            "{failed} of {} workspace packages failed lint",
+            results.len()
+        );
+    }
+    done!("Linted {} workspace packages", results.len());
+    Ok(())
+}
+
+/// Run every rule against vtx.toml and the manifest in `project_dir`.
+fn lint(config: &ProjectConfig, project_dir: PathBuf, deny_warnings: bool) -> Result<LintReport> {
+    let mut lints = Lints {
+        project_dir,
+        findings: Vec::new(),
+    };
+
+    lint_project_fields(config, &mut lints);
+    lint_manifests(config, &mut lints)?;
+    if let Some(capabilities) = &config.capabilities {
+        lint_capabilities(capabilities, &mut lints);
+    }
+
+    let findings = lints.findings;
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    Ok(LintReport {
+        deny_warnings,
+        errors,
+        warnings: findings.len() - errors,
+        findings,
+    })
+}
+
+fn print_report(name: &str, report: &LintReport) {
+    step!("Linting {}", name);
+    for f in &report.findings {
+        let severity = match f.severity {
+            Severity::Warning => "warning".yellow().bold(),
+            Severity::Error => "error".red().bold(),
+        };
+        println!("{}[{}] {}: {}", severity, f.rule, f.file, f.message);
+    }
+}
+
+/// Why `report` fails the lint, if it does.
+fn failure(report: &LintReport) -> Option<String> {
+    let denied = report.deny_warnings && report.warnings > 0;
+    if report.errors == 0 && !denied {
+        return None;
+    }
+    Some(format!(
+        "Lint failed: {} error(s), {} warning(s){}",
+        report.errors,
+        report.warnings,
+        if denied { " (warnings denied)" } else { "" }
+    ))
+}
+
+fn lint_project_fields(config: &ProjectConfig, lints: &mut Lints) {
+    let project = &config.project;
+    for (field, value) in [("name", &project.name), ("language", &project.language)] {
+        if value.trim().is_empty() {
+            lints.push(
+                "required-field",
+                Severity::Error,
+                "vtx.toml",
+                format!("project.{field} is empty"),
+            );
+        }
+    }
+
+    match project.version.as_deref().map(str::trim) {
+        None => lints.push(
+            "missing-version",
+            Severity::Warning,
+            "vtx.toml",
+            "project.version is not set; artifacts will carry no version".to_string(),
+        ),
+        Some("") => lints.push(
+            "required-field",
+            Severity::Error,
+            "vtx.toml",
+            "project.version is empty".to_string(),
+        ),
+        Some(version) => {
+            if let Err(e) = semver::Version::parse(version) {
+                lints.push(
+                    "semver",
+                    Severity::Error,
+                    "vtx.toml",
+                    format!("project.version '{version}' is not a semantic version ({e})"),
+                );
+            }
+        }
+    }
+
+    for (field, value) in [
+        ("author", &project.author),
+        ("description", &project.description),
+        ("license", &project.license),
+        ("homepage", &project.homepage),
+        ("repository", &project.repository),
+    ] {
+        if value.as_deref().is_some_and(|v| v.trim().is_empty()) {
+            lints.push(
+                "empty-field",
+                Severity::Warning,
+                "vtx.toml",
+                format!("project.{field} is set but empty; remove it or fill it in"),
+            );
+        }
+    }
+}
+
+/// Checks against the language's own manifest (Cargo.toml, package.json, composer.json).
+fn lint_manifests(config: &ProjectConfig, lints: &mut Lints) -> Result<()> {
+    let language = toolchain::canonical_language(&config.project.language);
+    let custom_cmd = config.build.as_ref().is_some_and(|b| b.cmd.is_some());
+    let declared = config.project.version.as_deref().map(str::trim);
+
+    match language.as_str() {
+        "rust" => {
+            let path = lints.project_dir.join("Cargo.toml");
+            let Some(cargo) = read_manifest(&path, |c| {
+                config::parse_toml::<toml::Value>(c, "Cargo.toml")
+            })?
+            else {
+                return Ok(());
+            };
+            let Some(package) = cargo.get("package") else {
+                return Ok(());
+            };
+            let crate_types: Vec<&str> = cargo
+                .get("lib")
+                .and_then(|lib| lib.get("crate-type"))
+                .and_then(toml::Value::as_array)
+                .map(|types| types.iter().filter_map(toml::Value::as_str).collect())
+                .unwrap_or_default();
+            if !crate_types.contains(&"cdylib") {
+                lints.push(
+                    "crate-type",
+                    Severity::Error,
+                    "Cargo.toml",
+                    "[lib] crate-type does not include \"cdylib\"; cargo will not produce a .wasm plugin (add crate-type = [\"cdylib\"])".to_string(),
+                );
+            }
+            let version = package.get("version").and_then(toml::Value::as_str);
+            version_mismatch(lints, "Cargo.toml", declared, version);
+        }
+        "ts" | "php" => {
+            let file = if language == "ts" {
+                "package.json"
+            } else {
+                "composer.json"
+            };
+            let path = lints.project_dir.join(file);
+            let Some(manifest) = read_manifest(&path, |c| {
+                serde_json::from_str::<Value>(c).with_context(|| format!("Malformed {file}"))
+            })?
+            else {
+                return Ok(());
+            };
+            let script = manifest
+                .pointer("/scripts/build")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if !custom_cmd && script.trim_start().starts_with("echo TODO") {
+                lints.push(
+                    "scaffold-build",
+                    Severity::Error,
+                    file,
+                    format!("the build script is still the scaffold placeholder ('{script}'); replace it with a command that writes the .wasm"),
+                );
+            }
+            if language == "ts" {
+                let version = manifest.get("version").and_then(Value::as_str);
+                version_mismatch(lints, file, declared, version);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Read and parse a manifest; `None` if the file does not exist.
+fn read_manifest<T>(path: &Path, parse: impl Fn(&str) -> Result<T>) -> Result<Option<T>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = config::read_text_file(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse(&content).map(Some)
+}
+
+fn version_mismatch(lints: &mut Lints, file: &str, declared: Option<&str>, found: Option<&str>) {
+    if let (Some(declared), Some(found)) = (declared, found) {
+        if !declared.is_empty() && declared != found {
+            lints.push(
+                "version-mismatch",
+                Severity::Warning,
+                file,
+                format!("version '{found}' differs from project.version '{declared}' in vtx.toml"),
+            );
+        }
+    }
+}
+
+fn lint_capabilities(capabilities: &config::CapabilitiesConfig, lints: &mut Lints) {
+    if CAPABILITY_PERMISSIONS
+        .iter()
+        .all(|p| capabilities.permissions.iter().any(|q| q == p))
+    {
+        lints.push(
+            "broad-permission",
+            Severity::Warning,
+            "vtx.toml",
+            "capabilities.permissions requests every kernel permission; list only what the plugin uses".to_string(),
+        );
+    }
+    for host in &capabilities.network {
+        let host_only = host.rsplit_once(':').map_or(host.as_str(), |(h, _)| h);
+        if let Some(domain) = host_only.strip_prefix("*.") {
+            if !domain.contains('.') {
+                lints.push(
+                    "broad-permission",
+                    Severity::Warning,
+                    "vtx.toml",
+                    format!(
+                        "capabilities.network '{host}' matches every host under a top-level domain"
+                    ),
+                );
+            }
+        }
+    }
+    for path in &capabilities.filesystem {
+        if path == "/" {
+            lints.push(
+                "broad-permission",
+                Severity::Warning,
+                "vtx.toml",
+                format!("capabilities.filesystem '{path}' grants the whole filesystem"),
+            );
+        }
+    }
+}
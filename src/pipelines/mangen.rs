@@ -0,0 +1,49 @@
+use anyhow::{Context, Result};
+use clap::{Command, CommandFactory};
+use clap_mangen::Man;
+use std::path::Path;
+
+use crate::cli::Cli;
+
+/// Generate man pages from the clap definitions.
+///
+/// Without `out_dir`, prints `vtx(1)` to stdout. With it, writes `vtx.1` and
+/// one page per subcommand, nested ones included (`vtx-build.1`,
+/// `vtx-ledger-show.1`, ...), creating the directory if needed.
+pub fn execute_mangen_pipeline(out_dir: Option<&Path>) -> Result<()> {
+    let mut command = Cli::command();
+    // Propagate global flags and the version into every subcommand.
+    command.build();
+    let Some(out_dir) = out_dir else {
+        Man::new(command).render(&mut std::io::stdout())?;
+        return Ok(());
+    };
+
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    let mut pages = Vec::new();
+    collect_pages(command.get_name().to_string(), command, &mut pages);
+    for (name, page) in &pages {
+        let path = out_dir.join(format!("{name}.1"));
+        let mut buf = Vec::new();
+        Man::new(page.clone()).render(&mut buf)?;
+        std::fs::write(&path, buf)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    ok!("Wrote {} man pages to {}", pages.len(), out_dir.display());
+    Ok(())
+}
+
+/// `command` and its subcommands (except `help`), each named after its full
+/// path and carrying the CLI version for the page footer.
+fn collect_pages(name: String, command: Command, pages: &mut Vec<(String, Command)>) {
+    for sub in command.get_subcommands().filter(|s| s.get_name() != "help") {
+        let sub_name = format!("{name}-{}", sub.get_name());
+        let page = sub
+            .clone()
+            .name(sub_name.clone())
+            .version(env!("CARGO_PKG_VERSION"));
+        collect_pages(sub_name, page, pages);
+    }
+    pages.insert(0, (name, command));
+}
@@ -0,0 +1,24 @@
+use anyhow::{Context, Result};
+
+use crate::container::{self, PayloadKey};
+use crate::{harness, output, scratch};
+
+/// Print the manifest returned by a built plugin and validate it.
+///
+/// `file` is a `.vtx` or a bare component. The component is instantiated
+/// under wasmtime (as in `vtx run`) and its `get-manifest` export is called.
+/// The manifest is printed as pretty JSON; the command fails if any field
+/// breaks the rules of [`vtx_cli::manifest::Manifest::validate`].
+pub fn execute_manifest_pipeline(file: &str) -> Result<()> {
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let component = container::unwrap_component(bytes, PayloadKey::load(None)?.as_ref())?;
+
+    let manifest = harness::invoke_get_manifest(&component, &scratch::interrupt_token())?;
+    output::document("manifest", &manifest)?;
+
+    let problems = manifest.validate();
+    if !problems.is_empty() {
+        anyhow::bail!("Invalid Manifest:\n  {}", problems.join("\n  "));
+    }
+    Ok(())
+}
@@ -1,10 +1,68 @@
+mod assets;
+mod attest;
+mod bench;
+mod bisect;
 mod build;
 mod check;
+mod clean;
 mod common;
+mod completions;
+mod dev;
+mod diff;
+mod doctor;
+mod hash;
 mod init;
+mod inspect;
+mod jobs;
+mod kernel;
+mod ledger;
+mod lint;
+mod mangen;
+mod manifest;
 mod package;
+mod registry;
+mod release;
+mod run;
+mod schema;
+mod serve;
+mod sign;
+mod size;
+mod test;
+mod unpack;
+mod upgrade;
+mod validate;
+mod watch;
+mod workspace;
 
-pub use build::execute_build_pipeline;
+pub use assets::execute_assets_pipeline;
+pub use attest::execute_attest_pipeline;
+pub use bench::{execute_bench_pipeline, BaselineMode};
+pub use bisect::{execute_bisect_pipeline, BisectPredicate};
+pub use build::{execute_build_pipeline, execute_workspace_build_pipeline};
 pub use check::execute_check_pipeline;
-pub use init::execute_init_pipeline;
+pub use clean::{execute_clean_pipeline, CleanScope};
+pub use completions::execute_completions_pipeline;
+pub use dev::execute_dev_pipeline;
+pub use diff::execute_diff_pipeline;
+pub use doctor::execute_doctor_pipeline;
+pub use hash::execute_hash_pipeline;
+pub use init::{execute_init_pipeline, InitOptions};
+pub use inspect::execute_inspect_pipeline;
+pub use kernel::execute_kernel_pipeline;
+pub use ledger::execute_ledger_pipeline;
+pub use lint::execute_lint_pipeline;
+pub use mangen::execute_mangen_pipeline;
+pub use manifest::execute_manifest_pipeline;
 pub use package::execute_package_pipeline;
+pub use registry::{execute_publish_pipeline, execute_pull_pipeline};
+pub use release::execute_release_pipeline;
+pub use run::execute_run_pipeline;
+pub use schema::execute_schema_pipeline;
+pub use serve::execute_serve_pipeline;
+pub use sign::{execute_keygen_pipeline, execute_sign_pipeline, execute_verify_pipeline};
+pub use size::execute_size_pipeline;
+pub use test::{execute_test_pipeline, execute_test_scaffold_pipeline};
+pub use unpack::execute_unpack_pipeline;
+pub use upgrade::execute_upgrade_pipeline;
+pub use validate::execute_validate_pipeline;
+pub use watch::execute_watch_pipeline;
@@ -1,27 +1,69 @@
 use anyhow::{Context, Result};
-use colored::*;
 use std::path::Path;
+use tracing::info;
 
-use crate::{config, packager};
+use crate::{config, ledger, output, packager, runtime, scratch::ScratchDir, stages};
 
-use super::common::{build_vtx_metadata_json, resolve_sdk_version};
+use super::common::{
+    absolute, add_artifact_hook_env, artifact_output_path, build_vtx_metadata_json, bundle_assets,
+    digest_algorithm, encode_payload_if_configured, hook_env, load_embedded_resources,
+    package_options, reproducible_build_time, resolve_sdk_version, run_hooks, sign_if_configured,
+    write_sbom_if_requested, HookStage,
+};
+use crate::adapter::Adapter;
+use crate::cli::PackagingArgs;
 
-pub fn execute_package_pipeline(input: &str, debug: bool, force: bool) -> Result<()> {
+pub fn execute_package_pipeline(
+    input: &str,
+    debug: bool,
+    force: bool,
+    packaging: &PackagingArgs,
+) -> Result<()> {
     let wasm_path = Path::new(input);
     if !wasm_path.exists() {
         anyhow::bail!("Input file not found: {}", wasm_path.display());
     }
 
-    println!(
-        "{} Packaging input: {}",
-        "[INFO]".cyan(),
-        wasm_path.display()
-    );
+    info!("Packaging input: {}", wasm_path.display());
+    stages::reset();
+
+    let config = config::load_optional()?;
+    let mut resources = load_embedded_resources(config.as_ref())?;
+    resources.container_sections = packaging.container_sections;
+    bundle_assets(config.as_ref(), &mut resources)?;
+    let build_config = config.as_ref().and_then(|c| c.build.as_ref());
+    let sdk_config = config.as_ref().and_then(|c| c.sdk.as_ref());
 
-    let component_bytes = packager::process_wasm(wasm_path, debug, force)
+    let scratch = ScratchDir::create()?;
+    let options = package_options(
+        build_config,
+        sdk_config,
+        config.as_ref().and_then(|c| c.dependencies.as_ref()),
+        packaging,
+        &scratch,
+        debug,
+        force,
+    );
+    if options.reproducible {
+        resources.build_time = reproducible_build_time()?;
+    }
+    let packaged = packager::package_wasm(wasm_path, &options)
         .context("Component packaging or validation failed")?;
+    let component_bytes = packaged.component;
+    resources.requires = Some(packager::required_interfaces(&component_bytes)?);
+    resources.adapter = packaged.adapter.as_ref().map(Adapter::kind);
+    resources.adapter_version = packaged
+        .adapter
+        .as_ref()
+        .map(Adapter::version)
+        .transpose()?;
+
+    if packaging.deep_validate {
+        stages::begin("validate");
+        info!("Deep validation: instantiating under wasmtime...");
+        runtime::deep_validate(&component_bytes, &options.progress, &options.cancel)?;
+    }
 
-    let config = config::load().ok();
     let package_name = config
         .as_ref()
         .map(|c| c.project.name.clone())
@@ -36,22 +78,91 @@ pub fn execute_package_pipeline(input: &str, debug: bool, force: bool) -> Result
         .as_ref()
         .map(|c| c.project.language.as_str())
         .unwrap_or("unknown");
-    let sdk_version = resolve_sdk_version(language);
+    // Packaging runs from the vtx.toml directory; SDK detection uses project.path.
+    let project_dir = config
+        .as_ref()
+        .and_then(|c| c.project.path.as_deref())
+        .unwrap_or(".");
+    let sdk_version = resolve_sdk_version(Path::new(project_dir), language, sdk_config);
     let metadata_json = build_vtx_metadata_json(
         &package_name,
         language,
         config.as_ref().map(|c| &c.project),
         sdk_version.as_deref(),
+        sdk_config,
+        None,
+        &resources,
+    )?;
+
+    // A prebuilt input has no build target or profile; name it as a default release build.
+    stages::begin("write");
+    let vtx_path = artifact_output_path(
+        config.as_ref(),
+        packaging,
+        wasm_path,
+        &package_name,
+        "wasm32-wasip1",
+        "release",
     )?;
+    let (metadata_json, payload) =
+        encode_payload_if_configured(config.as_ref(), packaging, metadata_json, &component_bytes)?;
+    let metadata_json = sign_if_configured(config.as_ref(), metadata_json, &payload)?;
+    packager::write_vtx_file_to(
+        &vtx_path,
+        &payload,
+        &metadata_json,
+        resources.capabilities_section()?.as_deref(),
+        resources.assets.as_deref(),
+        Some(scratch.path()),
+    )
+    .context("Failed to write final artifact")?;
+    let debuginfo_path = packaged
+        .debuginfo
+        .map(|info| packager::write_debuginfo_file(&vtx_path, &info, Some(scratch.path())))
+        .transpose()
+        .context("Failed to write debug info")?;
+    if let Some(path) = &debuginfo_path {
+        info!("Debug info written: {}", path.display());
+    }
+    let sbom_paths = write_sbom_if_requested(
+        config.as_ref(),
+        packaging,
+        &vtx_path,
+        &package_name,
+        language,
+        Path::new(project_dir),
+    )?;
+    ledger::record(&vtx_path, &metadata_json, digest_algorithm(config.as_ref()))?;
+    if packaging.size_report {
+        stages::suspend(|| super::size::report_component(&component_bytes))?;
+    }
+
+    if packaging.emit_component {
+        let component_path =
+            packager::write_component_file(wasm_path, &component_bytes, Some(scratch.path()))
+                .context("Failed to write bare component")?;
+        info!("Bare component written: {}", component_path.display());
+    }
 
-    let vtx_path = packager::write_vtx_file(wasm_path, &component_bytes, &metadata_json)
-        .context("Failed to write final artifact")?;
+    let mut hook_env = hook_env(&package_name, language, config.as_ref().map(|c| &c.project));
+    hook_env.push(("VTX_WASM_PATH", absolute(wasm_path)));
+    add_artifact_hook_env(&mut hook_env, &vtx_path, &metadata_json, &scratch)?;
+    run_hooks(config.as_ref(), HookStage::PostPackage, &hook_env)?;
 
-    println!(
-        "{} Package completed:{}",
-        "[DONE]".green().bold(),
-        vtx_path.display()
+    output::emit(
+        "artifact",
+        serde_json::json!({
+            "package": package_name,
+            "path": vtx_path,
+            "debuginfo": debuginfo_path,
+            "checksum": sbom_paths.as_ref().map(|(checksum, _)| checksum),
+            "sbom": sbom_paths.as_ref().map(|(_, sbom)| sbom),
+            "wasm": wasm_path,
+            "size": std::fs::metadata(&vtx_path).map(|m| m.len()).ok(),
+        }),
     );
+    stages::summary();
+    done!("Package completed:{}", vtx_path.display());
 
     Ok(())
 }
@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::metadata::VtxMetadata;
+use crate::registry::{self, Registry, RegistryEntry};
+use crate::{config, container, digest};
+
+/// Upload a `.vtx` and its metadata to the configured registry.
+///
+/// The package name and version come from the embedded metadata, so only v2
+/// containers built with `project.version` set can be published. The index
+/// entry lists a digest per [`registry::CHUNK_SIZE`] chunk for `vtx pull`.
+pub fn execute_publish_pipeline(file: &str) -> Result<()> {
+    let config = config::load().ok();
+    let registry = Registry::from_config(config.as_ref())?;
+
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let decoded = container::decode_sections(&bytes)
+        .map_err(|e| anyhow::anyhow!("{file} is not a valid .vtx container: {e}"))?;
+    let metadata_json = decoded.metadata.with_context(|| {
+        format!("{file} is a v1 container without metadata; repackage it first")
+    })?;
+    let meta: VtxMetadata = serde_json::from_slice(metadata_json)
+        .with_context(|| format!("{file} has malformed metadata"))?;
+    let version = meta.version.clone().with_context(|| {
+        format!("{file} has no version; set project.version in vtx.toml and rebuild")
+    })?;
+
+    let entry = RegistryEntry {
+        name: meta.package.clone(),
+        version,
+        digest: digest::digest(digest::DEFAULT_DIGEST_ALGORITHM, &bytes)?,
+        size: bytes.len() as u64,
+        metadata: Some(serde_json::from_slice(metadata_json)?),
+        chunk_size: Some(registry::CHUNK_SIZE),
+        chunks: registry::chunk_digests(
+            digest::DEFAULT_DIGEST_ALGORITHM,
+            &bytes,
+            registry::CHUNK_SIZE,
+        )?,
+    };
+    step!(
+        "Publishing {}@{} ({} bytes) to {}",
+        entry.name,
+        entry.version,
+        entry.size,
+        registry.base_url()
+    );
+    registry.publish(&entry, &bytes)?;
+    done!(
+        "Published {}@{} ({})",
+        entry.name,
+        entry.version,
+        entry.digest
+    );
+    Ok(())
+}
+
+/// Download `<name>@<version>` into `out_dir` as `<name>-<version>.vtx`,
+/// verifying its size and digest against the registry index. An interrupted
+/// pull leaves `<name>-<version>.vtx.part`, which the next pull resumes.
+pub fn execute_pull_pipeline(spec: &str, out_dir: &str) -> Result<()> {
+    let (name, version) = spec
+        .split_once('@')
+        .with_context(|| format!("Expected <name>@<version>, got '{spec}'"))?;
+    let config = config::load().ok();
+    let registry = Registry::from_config(config.as_ref())?;
+
+    let entry = registry.entry(name, version)?;
+    step!(
+        "Pulling {}@{} ({} bytes) from {}",
+        entry.name,
+        entry.version,
+        entry.size,
+        registry.base_url()
+    );
+    let out_dir = Path::new(out_dir);
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    let path = out_dir.join(format!("{name}-{version}.vtx"));
+    registry.download(&entry, &path)?;
+    done!("Checksum verified; wrote {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,341 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::cli::BuildArgs;
+use crate::{config, release, sbom, scratch, toolchain};
+use vtx_cli::cancel::Cancelled;
+
+use super::build::build_package;
+use super::common::SIGNING_KEY_ENV;
+use super::{
+    execute_check_pipeline, execute_publish_pipeline, execute_sign_pipeline, execute_test_pipeline,
+};
+
+/// Outcome of one release step, listed in the final summary.
+struct StepOutcome {
+    step: String,
+    status: &'static str,
+    seconds: f64,
+}
+
+/// Run the steps listed in `release.toml`, in order.
+///
+/// Behavior:
+/// - Each step starts from the directory holding `release.toml`, since `check`
+///   and `build` switch into `project.path`.
+/// - Steps in `skip` (from `--skip-test`, `--skip-sign`, ...) are not run.
+/// - Steps listed in `release.confirm` wait for a yes/no answer unless `yes` is set.
+/// - `sign`, `sbom` and `publish` act on the artifact of the `build` step.
+/// - Completed steps are recorded in `.vtx-cache/release-state.json`; with `resume`,
+///   a failed or declined release continues from the first unfinished step.
+/// - A per-step summary is printed whether the release succeeds or stops.
+/// - With `dry_run`, the plan is printed and nothing runs.
+pub fn execute_release_pipeline(
+    resume: bool,
+    yes: bool,
+    skip: &[&str],
+    dry_run: bool,
+) -> Result<()> {
+    let release_config = release::load()?;
+    let root = std::env::current_dir().context("Failed to resolve current directory")?;
+
+    let mut state = if resume {
+        release::ReleaseState::load(&release_config.steps)?
+    } else {
+        release::ReleaseState::new(&release_config.steps)
+    };
+
+    step!("Release steps: {}", release_config.steps.join(" -> "));
+    if dry_run {
+        print_plan(&release_config, &state, yes, skip);
+        return Ok(());
+    }
+
+    let mut outcomes = Vec::new();
+    let mut failure = None;
+
+    for step in &release_config.steps {
+        if state.completed.contains(step) {
+            outcomes.push(StepOutcome {
+                step: step.clone(),
+                status: "skipped (done)",
+                seconds: 0.0,
+            });
+            continue;
+        }
+        if failure.is_some() {
+            outcomes.push(StepOutcome {
+                step: step.clone(),
+                status: "pending",
+                seconds: 0.0,
+            });
+            continue;
+        }
+        if skip.contains(&step.as_str()) {
+            outcomes.push(StepOutcome {
+                step: step.clone(),
+                status: "skipped (flag)",
+                seconds: 0.0,
+            });
+            continue;
+        }
+
+        if let Err(e) = scratch::interrupt_token().check() {
+            print_summary(&outcomes);
+            return Err(e);
+        }
+        std::env::set_current_dir(&root)
+            .with_context(|| format!("Failed to return to {}", root.display()))?;
+
+        if release_config.requires_confirmation(step) && !yes && !confirm(step)? {
+            outcomes.push(StepOutcome {
+                step: step.clone(),
+                status: "declined",
+                seconds: 0.0,
+            });
+            failure = Some(format!("Release stopped: step '{step}' was declined"));
+            continue;
+        }
+
+        info!("Release step: {}", step);
+        let started = Instant::now();
+        let result = run_step(step, &release_config, &mut state);
+        let seconds = started.elapsed().as_secs_f64();
+
+        match result {
+            Ok(()) => {
+                outcomes.push(StepOutcome {
+                    step: step.clone(),
+                    status: "ok",
+                    seconds,
+                });
+                state.completed.push(step.clone());
+                std::env::set_current_dir(&root)
+                    .with_context(|| format!("Failed to return to {}", root.display()))?;
+                state.save()?;
+            }
+            Err(e) if e.downcast_ref::<Cancelled>().is_some() => {
+                print_summary(&outcomes);
+                return Err(e);
+            }
+            Err(e) => {
+                outcomes.push(StepOutcome {
+                    step: step.clone(),
+                    status: "failed",
+                    seconds,
+                });
+                failure = Some(format!("Release step '{step}' failed: {e:#}"));
+            }
+        }
+    }
+
+    std::env::set_current_dir(&root)
+        .with_context(|| format!("Failed to return to {}", root.display()))?;
+    print_summary(&outcomes);
+
+    match failure {
+        Some(msg) => {
+            anyhow::bail!("{msg}\nHint: Fix the problem and continue with `vtx release --resume`.")
+        }
+        None => {
+            release::ReleaseState::clear();
+            done!("Release completed");
+            Ok(())
+        }
+    }
+}
+
+fn run_step(
+    step: &str,
+    release_config: &release::ReleaseConfig,
+    state: &mut release::ReleaseState,
+) -> Result<()> {
+    if release::ARTIFACT_STEPS.contains(&step) && state.artifact.is_none() {
+        anyhow::bail!("No artifact from the build step; run the release without --resume");
+    }
+    let artifact = || state.artifact.as_deref().unwrap_or(Path::new(""));
+    match step {
+        "bump" => bump(release_config.bump.as_deref().unwrap_or("patch")),
+        "check" => execute_check_pipeline(&Default::default(), false, false, false),
+        "test" => execute_test_pipeline(
+            &Default::default(),
+            "wasm32-wasip1",
+            false,
+            &Default::default(),
+        ),
+        "build" => {
+            let artifact = build_package(
+                None,
+                release_config.profile.as_deref(),
+                "wasm32-wasip1",
+                true,
+                false,
+                false,
+                &BuildArgs::default(),
+            )?;
+            state.artifact = Some(std::path::absolute(artifact)?);
+            Ok(())
+        }
+        "sign" => sign(artifact()),
+        "sbom" => sbom(artifact()),
+        "publish" => execute_publish_pipeline(&artifact().display().to_string()),
+        other => anyhow::bail!("Unknown release step '{other}'"),
+    }
+}
+
+/// Sign the artifact with `VTX_SIGNING_KEY` or `package.signing_key`.
+fn sign(artifact: &Path) -> Result<()> {
+    let config = config::load()?;
+    let key = std::env::var(SIGNING_KEY_ENV)
+        .ok()
+        .or_else(|| config.package.and_then(|p| p.signing_key))
+        .with_context(|| {
+            format!("The sign step needs a key: set package.signing_key or {SIGNING_KEY_ENV}")
+        })?;
+    execute_sign_pipeline(&artifact.display().to_string(), &key)
+}
+
+/// Write the checksum and SBOM of the artifact, in the `package.sbom` format
+/// (default cyclonedx).
+fn sbom(artifact: &Path) -> Result<()> {
+    let config = config::load()?;
+    let format = config
+        .package
+        .as_ref()
+        .and_then(|p| p.sbom.as_ref())
+        .and_then(config::SbomSetting::format)
+        .unwrap_or(sbom::DEFAULT_SBOM_FORMAT);
+    let subject = sbom::SbomSubject {
+        name: &config.project.name,
+        version: config.project.version.as_deref(),
+        language: &config.project.language,
+        project_dir: Path::new(config.project.path.as_deref().unwrap_or(".")),
+    };
+    let (checksum, document) =
+        sbom::write(artifact, &subject, format).context("Failed to write the SBOM")?;
+    info!("Checksum written: {}", checksum.display());
+    ok!("SBOM written: {}", document.display());
+    Ok(())
+}
+
+/// Print what every step would do, and whether it would run.
+fn print_plan(
+    release_config: &release::ReleaseConfig,
+    state: &release::ReleaseState,
+    yes: bool,
+    skip: &[&str],
+) {
+    info!("Release plan (dry run):");
+    for step in &release_config.steps {
+        let status = if state.completed.contains(step) {
+            "skipped (done)".dimmed()
+        } else if skip.contains(&step.as_str()) {
+            "skipped (flag)".dimmed()
+        } else if release_config.requires_confirmation(step) && !yes {
+            "asks first".yellow()
+        } else {
+            "runs".green()
+        };
+        println!(
+            "  {:<8} {:<16} {}",
+            step,
+            status,
+            describe(step, release_config)
+        );
+    }
+}
+
+/// What `step` does, for the plan.
+fn describe(step: &str, release_config: &release::ReleaseConfig) -> String {
+    match step {
+        "bump" => format!(
+            "raise the {} version in vtx.toml and the language manifest",
+            release_config.bump.as_deref().unwrap_or("patch")
+        ),
+        "check" => "vtx check".to_string(),
+        "test" => "vtx test".to_string(),
+        "build" => match release_config.profile.as_deref() {
+            Some(profile) => format!("vtx build --profile {profile}"),
+            None => "vtx build".to_string(),
+        },
+        "sign" => format!("vtx sign <artifact> with {SIGNING_KEY_ENV} or package.signing_key"),
+        "sbom" => "write <artifact>.sha256 and the SBOM".to_string(),
+        "publish" => "vtx publish <artifact>".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn confirm(step: &str) -> Result<bool> {
+    print!("Run release step '{step}'? [y/N]: ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn print_summary(outcomes: &[StepOutcome]) {
+    info!("Release summary:");
+    for outcome in outcomes {
+        let status = match outcome.status {
+            "ok" => outcome.status.green().to_string(),
+            "failed" | "declined" => outcome.status.red().to_string(),
+            _ => outcome.status.dimmed().to_string(),
+        };
+        println!(
+            "  {:<8} {:<16} {:.2}s",
+            outcome.step, status, outcome.seconds
+        );
+    }
+}
+
+/// Bump the plugin version in vtx.toml and the language manifest.
+fn bump(part: &str) -> Result<()> {
+    let config = config::load()?;
+    let current = config
+        .project
+        .version
+        .as_deref()
+        .context("project.version is not set in vtx.toml")?;
+    let next = release::bump_version(current, part)?;
+
+    release::set_toml_version(Path::new("vtx.toml"), "project", &next)?;
+
+    let project_dir = Path::new(config.project.path.as_deref().unwrap_or("."));
+    match toolchain::canonical_language(&config.project.language).as_str() {
+        "rust" => bump_manifest(&project_dir.join("Cargo.toml"), "package", &next)?,
+        "python" => bump_manifest(&project_dir.join("pyproject.toml"), "project", &next)?,
+        "ts" => {
+            let path = project_dir.join("package.json");
+            if path.exists() {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let updated = content.replacen(
+                    &format!("\"version\": \"{current}\""),
+                    &format!("\"version\": \"{next}\""),
+                    1,
+                );
+                std::fs::write(&path, updated)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+        }
+        _ => {}
+    }
+
+    ok!("Version bumped: {} -> {}", current, next);
+    Ok(())
+}
+
+fn bump_manifest(path: &Path, section: &str, version: &str) -> Result<()> {
+    if path.exists() && !release::set_toml_version(path, section, version)? {
+        warn!(
+            "No [{}] version in {}; left unchanged",
+            section,
+            path.display()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+
+use crate::container::{self, PayloadKey};
+use crate::harness::{self, HarnessOptions, HttpRequest};
+use crate::scratch;
+use vtx_cli::kernel_api::KernelProfile;
+
+/// Call the `handle` export of a built plugin with one synthetic request.
+///
+/// `file` is a `.vtx` or a bare component. The request comes from
+/// `request_file` (JSON with `method`, `path` and `query`) or the flags.
+/// The response status goes to the log and the body to stdout. With
+/// `kernel_api` the harness emulates that kernel version's imports (see
+/// [`HarnessOptions::kernel_api`]).
+pub fn execute_run_pipeline(
+    file: &str,
+    request: HttpRequest,
+    request_file: Option<&str>,
+    kernel_api: Option<&str>,
+) -> Result<()> {
+    let kernel_api = kernel_api.map(KernelProfile::resolve).transpose()?;
+    let request = load_request(request, request_file)?;
+
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let component = container::unwrap_component(bytes, PayloadKey::load(None)?.as_ref())?;
+
+    step!("{} {} -> {}", request.method, target(&request), file);
+    let options = HarnessOptions {
+        kernel_api,
+        ..HarnessOptions::default()
+    };
+    let response =
+        harness::invoke_handle_with(&component, &request, &options, &scratch::interrupt_token())
+            .result?;
+
+    let body_len = response.body.as_ref().map_or(0, Vec::len);
+    if response.status < 400 {
+        ok!("Status {} ({body_len} bytes)", response.status);
+    } else {
+        warn!("Status {} ({body_len} bytes)", response.status);
+    }
+    if let Some(body) = response.body {
+        match String::from_utf8(body) {
+            Ok(text) => println!("{text}"),
+            Err(e) => info!("Body is binary; {} bytes not shown", e.as_bytes().len()),
+        }
+    }
+    Ok(())
+}
+
+/// The request in `request_file` (JSON with `method`, `path` and `query`),
+/// else `request` as built from the flags.
+pub(super) fn load_request(
+    request: HttpRequest,
+    request_file: Option<&str>,
+) -> Result<HttpRequest> {
+    let Some(path) = request_file else {
+        return Ok(request);
+    };
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read request file: {path}"))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("{path} is not a valid request (expected method, path, query)"))
+}
+
+/// Path and query of `request`, as in a request line.
+pub(super) fn target(request: &HttpRequest) -> String {
+    if request.query.is_empty() {
+        request.path.clone()
+    } else {
+        format!("{}?{}", request.path, request.query)
+    }
+}
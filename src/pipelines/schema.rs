@@ -0,0 +1,74 @@
+use anyhow::Result;
+use schemars::{schema_for, Schema};
+use serde_json::{json, Map, Value};
+
+use crate::{compare::ArtifactSummary, config::ProjectConfig, metadata::VtxMetadata, output};
+
+use super::check::CheckReport;
+use super::test::Fixture;
+
+/// Version of the published schema set. Bump when any schema changes shape.
+const SCHEMA_SET_VERSION: u32 = 1;
+
+/// Names accepted by `vtx schema <name>`, in output order.
+pub const SCHEMA_NAMES: &[&str] = &[
+    "vtx-toml",
+    "metadata",
+    "build-summary",
+    "check-report",
+    "test-fixture",
+    "event",
+];
+
+fn schema_by_name(name: &str) -> Option<Schema> {
+    match name {
+        "vtx-toml" => Some(schema_for!(ProjectConfig)),
+        "metadata" => Some(schema_for!(VtxMetadata)),
+        "build-summary" => Some(schema_for!(ArtifactSummary)),
+        "check-report" => Some(schema_for!(CheckReport)),
+        "test-fixture" => Some(schema_for!(Fixture)),
+        "event" => Some(schema_for!(output::Event)),
+        _ => None,
+    }
+}
+
+/// Render a schema with a versioned `$id`.
+fn versioned(name: &str, schema: Schema) -> Value {
+    let mut value = schema.to_value();
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert(
+            "$id".to_string(),
+            json!(format!("urn:vtx:schema:{name}:v{SCHEMA_SET_VERSION}")),
+        );
+    }
+    value
+}
+
+/// Print JSON Schemas generated from the CLI's Rust types.
+///
+/// Without a name, prints every schema keyed by name together with the set version.
+pub fn execute_schema_pipeline(name: Option<&str>) -> Result<()> {
+    let output = match name {
+        Some(name) => {
+            let schema = schema_by_name(name).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown schema '{name}' (expected one of: {})",
+                    SCHEMA_NAMES.join(", ")
+                )
+            })?;
+            versioned(name, schema)
+        }
+        None => {
+            let mut schemas = Map::new();
+            for name in SCHEMA_NAMES {
+                if let Some(schema) = schema_by_name(name) {
+                    schemas.insert(name.to_string(), versioned(name, schema));
+                }
+            }
+            json!({ "version": SCHEMA_SET_VERSION, "schemas": schemas })
+        }
+    };
+
+    output::document("schema", &output)?;
+    Ok(())
+}
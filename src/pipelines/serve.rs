@@ -0,0 +1,698 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL;
+use base64::Engine;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde_json::json;
+use std::io::BufRead;
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::cancel::CancelToken;
+use crate::cli::ClockMode;
+use crate::config::{self, DevAuthConfig, DevUserConfig};
+use crate::container::{self, PayloadKey};
+use crate::devserver::{self, Counters, Denied, RateLimiter, Request, Response};
+use crate::harness::{CurrentUser, HarnessOptions, HttpRequest, HttpResponse, ManualClock, Plugin};
+use crate::{scratch, timeutil};
+use vtx_cli::kernel_api::KernelProfile;
+
+/// Paths under this prefix control the dev host and never reach the plugin.
+const CONTROL_PREFIX: &str = "/_vtx/";
+
+/// Request header naming the `[dev.auth]` user a request comes from; an
+/// empty value sends it anonymously.
+const USER_HEADER: &str = "x-vtx-user";
+
+/// How long a mock JWT stays valid after it is issued.
+const MOCK_TOKEN_LIFETIME: Duration = Duration::from_secs(3_600);
+
+/// Quiet period after the last write to vtx.toml before it is reloaded.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// How often the accept loop wakes up to notice Ctrl-C and console input.
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+/// Serve a built plugin over HTTP, calling its `handle` export per request.
+///
+/// Behavior:
+/// - `file` is a `.vtx` or a bare component, instantiated once in the
+///   harness (see `vtx run`) and kept across requests; after a failed call
+///   it is instantiated again for the next one.
+/// - Requests carry a `[dev.auth]` user from vtx.toml, if any, chosen with
+///   the `X-Vtx-User` header (see [`DevHost::user`]).
+/// - `[dev.limits]` answers requests over its rate or quota with 429, as
+///   the kernel would, before they reach the plugin (see
+///   [`DevHost::admit`]).
+/// - Edits to vtx.toml are applied to the host state above without
+///   instantiating the plugin again (see [`DevHost::reload`]).
+/// - With `ClockMode::Manual` the plugin's clocks stand still at `time`
+///   (default: now) until advanced through `/_vtx/clock` or by typing
+///   `+<duration>` on the console.
+/// - Runs until interrupted with Ctrl-C.
+pub fn execute_serve_pipeline(
+    file: &str,
+    addr: &str,
+    clock: ClockMode,
+    time: Option<&str>,
+    kernel_api: Option<&str>,
+) -> Result<()> {
+    let kernel_api = kernel_api.map(KernelProfile::resolve).transpose()?;
+    let clock = match (clock, time) {
+        (ClockMode::System, Some(_)) => anyhow::bail!("--time needs --clock manual"),
+        (ClockMode::System, None) => None,
+        (ClockMode::Manual, time) => Some(ManualClock::new(match time {
+            Some(time) => timeutil::parse_rfc3339(time)?,
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+        })),
+    };
+    let dev = config::load_optional()?.and_then(|config| config.dev);
+    let auth = dev
+        .as_ref()
+        .and_then(|dev| dev.auth.clone())
+        .unwrap_or_default();
+    let limits = dev.and_then(|dev| dev.limits);
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let component = container::unwrap_component(bytes, PayloadKey::load(None)?.as_ref())?;
+
+    let cancel = scratch::interrupt_token();
+    let options = HarnessOptions {
+        clock: clock.clone(),
+        kernel_api,
+        ..HarnessOptions::default()
+    };
+    let plugin = Plugin::instantiate(&component, &options, &cancel)?;
+    let mut host = DevHost {
+        file,
+        component,
+        options,
+        plugin: Some(plugin),
+        clock,
+        auth,
+        limiter: None,
+        cancel: cancel.clone(),
+    };
+    host.limiter = limits.map(|limits| RateLimiter::new(limits, host.now()));
+
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {addr}"))?;
+    listener
+        .set_nonblocking(true)
+        .context("Failed to configure the listener")?;
+    let local = listener
+        .local_addr()
+        .context("Failed to read the bound address")?;
+    step!("Serving {} at http://{} (Ctrl-C to stop)", file, local);
+    if !host.auth.users.is_empty() {
+        info!(
+            "Simulated users: {}; pick one with the X-Vtx-User header",
+            describe_auth(&host.auth)
+        );
+    }
+    if let Some(limiter) = &host.limiter {
+        info!(
+            "Request limits: {}; see {CONTROL_PREFIX}limits",
+            devserver::describe_limits(limiter.policy())
+        );
+    }
+    let (_watcher, config_changes) = watch_config()?;
+    let console = host.clock.as_ref().map(|clock| {
+        info!(
+            "Manual clock at {}; advance it with POST {CONTROL_PREFIX}clock/advance?by=1h or by typing +1h",
+            timeutil::rfc3339(clock.now().as_secs())
+        );
+        console_lines()
+    });
+
+    loop {
+        cancel.check()?;
+        if let Some(console) = &console {
+            while let Ok(line) = console.try_recv() {
+                host.console(line.trim());
+            }
+        }
+        if config_changed(&config_changes) {
+            host.reload();
+        }
+        match listener.accept() {
+            Ok((stream, _)) => host.serve_connection(&stream),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL),
+            Err(e) => warn!("Failed to accept a connection: {e}"),
+        }
+    }
+}
+
+/// Watch the current directory for settled changes to vtx.toml. Editors
+/// often replace the file rather than write it, so the directory is watched.
+fn watch_config() -> Result<(Debouncer<RecommendedWatcher>, Receiver<DebounceEventResult>)> {
+    let (events, received) = mpsc::channel();
+    let mut debouncer =
+        new_debouncer(RELOAD_DEBOUNCE, events).context("Failed to start the file watcher")?;
+    debouncer
+        .watcher()
+        .watch(Path::new("."), RecursiveMode::NonRecursive)
+        .context("Failed to watch vtx.toml")?;
+    Ok((debouncer, received))
+}
+
+/// Whether any pending file event touched vtx.toml.
+fn config_changed(events: &Receiver<DebounceEventResult>) -> bool {
+    let mut changed = false;
+    while let Ok(batch) = events.try_recv() {
+        match batch {
+            Ok(batch) => {
+                changed |= batch.iter().any(|event| {
+                    event
+                        .path
+                        .file_name()
+                        .is_some_and(|name| name == "vtx.toml")
+                })
+            }
+            Err(e) => warn!("File watcher error: {e}"),
+        }
+    }
+    changed
+}
+
+/// `[dev.auth]` in one line: its users and the default one.
+fn describe_auth(auth: &DevAuthConfig) -> String {
+    if auth.users.is_empty() {
+        return "none".to_string();
+    }
+    let users: Vec<&str> = auth.users.keys().map(String::as_str).collect();
+    format!(
+        "{} (default: {}{})",
+        users.join(", "),
+        auth.default_user.as_deref().unwrap_or("anonymous"),
+        if auth.authenticate {
+            "; through authenticate"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Lines typed on stdin, read on a background thread until stdin closes.
+fn console_lines() -> Receiver<String> {
+    let (lines, received) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(|l| l.ok()) {
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    received
+}
+
+/// State of a running `vtx serve`.
+struct DevHost<'a> {
+    file: &'a str,
+    component: Vec<u8>,
+    options: HarnessOptions,
+    /// `None` after a failed call, until the next request instantiates it.
+    plugin: Option<Plugin>,
+    clock: Option<ManualClock>,
+    auth: DevAuthConfig,
+    /// `[dev.limits]`, if set, with its counters.
+    limiter: Option<RateLimiter>,
+    cancel: CancelToken,
+}
+
+/// What became of a request passed to the plugin.
+enum Outcome {
+    Handled(HttpResponse),
+    /// `authenticate` refused it with this status.
+    Rejected(u16),
+}
+
+impl DevHost<'_> {
+    fn serve_connection(&mut self, stream: &TcpStream) {
+        let result = stream
+            .set_nonblocking(false)
+            .context("Failed to configure the connection")
+            .and_then(|()| devserver::read_request(stream));
+        let response = match result {
+            Ok(request) => self.respond(&request),
+            Err(e) => {
+                warn!("Bad request: {e:#}");
+                Response::text(400, &format!("{e:#}"))
+            }
+        };
+        if let Err(e) = devserver::write_response(stream, &response) {
+            warn!("{e:#}");
+        }
+    }
+
+    fn respond(&mut self, request: &Request) -> Response {
+        match request.path.strip_prefix(CONTROL_PREFIX) {
+            Some(control) => self.control(request, control),
+            None => self.call_plugin(request),
+        }
+    }
+
+    /// Attach the request's user, call `handle` and log the outcome with the
+    /// `[dev.limits]` counters.
+    fn call_plugin(&mut self, request: &Request) -> Response {
+        let target = if request.query.is_empty() {
+            request.path.clone()
+        } else {
+            format!("{}?{}", request.path, request.query)
+        };
+        let user = match self.user(request) {
+            Ok(user) => user,
+            Err(message) => {
+                warn!("{} {} -> 400: {}", request.method, target, message);
+                return Response::text(400, &message);
+            }
+        };
+        let caller = match &user {
+            Some((name, _)) => format!(" as {name}"),
+            None => String::new(),
+        };
+        let counters = match self.admit() {
+            Ok(counters) => counters,
+            Err(denied) => {
+                warn!(
+                    "{} {}{} -> 429 ({}, retry after {}s) [{}]",
+                    request.method,
+                    target,
+                    caller,
+                    denied.reason,
+                    retry_after_secs(&denied),
+                    denied.counters
+                );
+                return limited(&denied);
+            }
+        };
+        let trace = counters
+            .map(|counters| format!(" [{counters}]"))
+            .unwrap_or_default();
+        let started = Instant::now();
+        let result = self.dispatch(request, user);
+        let elapsed = started.elapsed().as_secs_f64() * 1000.0;
+        match result {
+            Ok(Outcome::Handled(response)) => {
+                let body = response.body.unwrap_or_default();
+                info!(
+                    "{} {}{} -> {} ({} bytes, {:.1} ms){}",
+                    request.method,
+                    target,
+                    caller,
+                    response.status,
+                    body.len(),
+                    elapsed,
+                    trace
+                );
+                with_counters(Response::new(response.status, body), counters)
+            }
+            Ok(Outcome::Rejected(status)) => {
+                info!(
+                    "{} {}{} -> {} (rejected by authenticate, {:.1} ms){}",
+                    request.method, target, caller, status, elapsed, trace
+                );
+                with_counters(Response::new(status, Vec::new()), counters)
+            }
+            Err(e) => {
+                // A trapped instance cannot be entered again.
+                self.plugin = None;
+                warn!(
+                    "{} {}{} -> 500 ({:.1} ms){}: {:#}",
+                    request.method, target, caller, elapsed, trace, e
+                );
+                Response::text(500, &format!("{e:#}"))
+            }
+        }
+    }
+
+    /// Count the request against `[dev.limits]`, if set. Like the kernel,
+    /// the limits apply to the plugin as a whole, whichever user calls it.
+    fn admit(&mut self) -> Result<Option<Counters>, Denied> {
+        let now = self.now();
+        match &mut self.limiter {
+            Some(limiter) => limiter.admit(now).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The `[dev.auth]` user named by the `X-Vtx-User` header, else the
+    /// default user; `None` for an anonymous request.
+    fn user(&self, request: &Request) -> Result<Option<(String, DevUserConfig)>, String> {
+        let name = match request.header(USER_HEADER) {
+            Some("") => return Ok(None),
+            Some(name) => name,
+            None => match &self.auth.default_user {
+                Some(name) => name,
+                None => return Ok(None),
+            },
+        };
+        match self.auth.users.get(name) {
+            Some(user) => Ok(Some((name.to_string(), user.clone()))),
+            None => {
+                let known: Vec<&str> = self.auth.users.keys().map(String::as_str).collect();
+                Err(format!(
+                    "Unknown dev.auth user '{name}' (known: {})",
+                    if known.is_empty() {
+                        "none".to_string()
+                    } else {
+                        known.join(", ")
+                    }
+                ))
+            }
+        }
+    }
+
+    /// Identify the caller as the kernel would, then call `handle`.
+    ///
+    /// The configured identity is attached directly, unless
+    /// `[dev.auth] authenticate` passes the request headers, with the user's
+    /// added, to the plugin's `authenticate` export first.
+    fn dispatch(
+        &mut self,
+        request: &Request,
+        user: Option<(String, DevUserConfig)>,
+    ) -> Result<Outcome> {
+        let headers = self.headers(request, user.as_ref());
+        let authenticate = self.auth.authenticate;
+        let cancel = self.cancel.clone();
+        let plugin = self.plugin()?;
+        let identity = if authenticate {
+            match plugin.authenticate(&headers, &cancel)? {
+                Some(Ok(context)) => Some(CurrentUser::from(context)),
+                Some(Err(status)) => return Ok(Outcome::Rejected(status)),
+                None => anyhow::bail!(
+                    "dev.auth.authenticate is set, but the plugin has no 'authenticate' export"
+                ),
+            }
+        } else {
+            user.map(|(name, user)| CurrentUser {
+                user_id: user.user_id,
+                username: user.username.unwrap_or(name),
+                groups: user.groups,
+            })
+        };
+        plugin.set_user(identity);
+        let response = plugin.handle(
+            &HttpRequest {
+                method: request.method.clone(),
+                path: request.path.clone(),
+                query: request.query.clone(),
+            },
+            &cancel,
+        )?;
+        Ok(Outcome::Handled(response))
+    }
+
+    /// The request's headers, without `X-Vtx-User`, plus those of `user` it
+    /// does not set itself; with a user and no `authorization` header, a mock
+    /// JWT is added as a bearer token.
+    fn headers(
+        &self,
+        request: &Request,
+        user: Option<&(String, DevUserConfig)>,
+    ) -> Vec<(String, String)> {
+        let mut headers: Vec<(String, String)> = request
+            .headers
+            .iter()
+            .filter(|(name, _)| name != USER_HEADER)
+            .cloned()
+            .collect();
+        let Some((name, user)) = user else {
+            return headers;
+        };
+        for (header, value) in &user.headers {
+            let header = header.to_ascii_lowercase();
+            if !headers.iter().any(|(name, _)| *name == header) {
+                headers.push((header, value.clone()));
+            }
+        }
+        if !headers.iter().any(|(name, _)| name == "authorization") {
+            let token = mock_jwt(name, user, self.now());
+            headers.push(("authorization".to_string(), format!("Bearer {token}")));
+        }
+        headers
+    }
+
+    /// The plugin instance, instantiated again after a failed call.
+    fn plugin(&mut self) -> Result<&mut Plugin> {
+        if self.plugin.is_none() {
+            info!("Instantiating {} again after the failed call", self.file);
+            let plugin = Plugin::instantiate(&self.component, &self.options, &self.cancel)?;
+            self.plugin = Some(plugin);
+        }
+        Ok(self.plugin.as_mut().expect("instantiated above"))
+    }
+
+    /// The time the plugin sees.
+    fn now(&self) -> Duration {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Read vtx.toml again and apply its `[dev]` settings, logging each one
+    /// that changed. The plugin instance, its state and the clock are kept;
+    /// a file that no longer loads leaves the previous settings in place.
+    fn reload(&mut self) {
+        let dev = match config::load_optional() {
+            Ok(config) => config.and_then(|config| config.dev),
+            Err(e) => {
+                warn!("Ignoring the vtx.toml change, keeping the previous settings: {e:#}");
+                return;
+            }
+        };
+        let auth = dev
+            .as_ref()
+            .and_then(|dev| dev.auth.clone())
+            .unwrap_or_default();
+        let limits = dev.and_then(|dev| dev.limits);
+        let mut reloaded = Vec::new();
+        if auth != self.auth {
+            reloaded.push(format!("dev.auth users: {}", describe_auth(&auth)));
+            self.auth = auth;
+        }
+        if limits.as_ref() != self.limiter.as_ref().map(RateLimiter::policy) {
+            reloaded.push(format!(
+                "dev.limits: {} (counters reset)",
+                limits
+                    .as_ref()
+                    .map_or_else(|| "none".to_string(), devserver::describe_limits)
+            ));
+            let now = self.now();
+            self.limiter = limits.map(|limits| RateLimiter::new(limits, now));
+        }
+        if reloaded.is_empty() {
+            info!("vtx.toml changed; no dev host settings changed");
+        }
+        for setting in reloaded {
+            ok!("Reloaded {}", setting);
+        }
+    }
+
+    /// Answer a request to `/_vtx/<path>`.
+    fn control(&mut self, request: &Request, path: &str) -> Response {
+        let method = request.method.as_str();
+        match (method, path) {
+            ("GET", "clock") => self.clock_state(),
+            ("POST", "clock/advance") => {
+                let by = match request.param("by").as_deref().map(timeutil::parse_duration) {
+                    Some(Ok(by)) => by,
+                    Some(Err(e)) => return Response::text(400, &format!("by: {e}")),
+                    None => return Response::text(400, "by: missing, e.g. ?by=1h"),
+                };
+                self.advance_clock(by)
+            }
+            ("POST", "clock/set") => {
+                let to = match request.param("to").as_deref().map(timeutil::parse_rfc3339) {
+                    Some(Ok(to)) => to,
+                    Some(Err(e)) => return Response::text(400, &format!("to: {e}")),
+                    None => {
+                        return Response::text(400, "to: missing, e.g. ?to=2024-01-01T00:00:00Z")
+                    }
+                };
+                self.set_clock(to)
+            }
+            ("GET", "limits") => self.limits_state(),
+            ("POST", "limits/reset") => {
+                let now = self.now();
+                match &mut self.limiter {
+                    Some(limiter) => {
+                        *limiter = RateLimiter::new(limiter.policy().clone(), now);
+                        info!("Request limit counters reset");
+                        self.limits_state()
+                    }
+                    None => Response::text(409, "vtx.toml sets no [dev.limits]"),
+                }
+            }
+            (_, "clock" | "clock/advance" | "clock/set" | "limits" | "limits/reset") => {
+                Response::text(405, &format!("{method} is not allowed on {}", request.path))
+            }
+            _ => Response::text(404, &format!("No dev host endpoint at {}", request.path)),
+        }
+    }
+
+    /// The plugin's current time as JSON.
+    fn clock_state(&self) -> Response {
+        let mode = if self.clock.is_some() {
+            "manual"
+        } else {
+            "system"
+        };
+        let now = self.now();
+        Response::json(
+            200,
+            &json!({
+                "mode": mode,
+                "now": timeutil::rfc3339(now.as_secs()),
+                "unix_ms": now.as_millis() as u64,
+            }),
+        )
+    }
+
+    /// The `[dev.limits]` counters as JSON.
+    fn limits_state(&mut self) -> Response {
+        let now = self.now();
+        let Some(limiter) = &mut self.limiter else {
+            return Response::json(200, &json!({ "limits": null }));
+        };
+        let policy = limiter.policy().clone();
+        let counters = limiter.counters(now);
+        Response::json(
+            200,
+            &json!({
+                "limits": {
+                    "requests_per_minute": policy.requests_per_minute,
+                    "burst": counters.rate.map(|(_, burst)| burst),
+                    "quota": policy.quota,
+                    "quota_window": counters.quota.map(|_| policy.quota_window.as_deref().unwrap_or("1d")),
+                },
+                "rate_remaining": counters.rate.map(|(left, _)| left),
+                "quota_remaining": counters.quota.map(|(left, _, _)| left),
+                "quota_resets_in": counters.quota.map(|(_, _, resets)| resets.as_secs()),
+            }),
+        )
+    }
+
+    fn advance_clock(&mut self, by: Duration) -> Response {
+        let Some(clock) = &self.clock else {
+            return Response::text(
+                409,
+                "The clock is not manual; start vtx serve with --clock manual",
+            );
+        };
+        clock.advance(by);
+        info!(
+            "Clock advanced by {}s to {}",
+            by.as_secs(),
+            timeutil::rfc3339(clock.now().as_secs())
+        );
+        self.clock_state()
+    }
+
+    fn set_clock(&mut self, to: Duration) -> Response {
+        let Some(clock) = &self.clock else {
+            return Response::text(
+                409,
+                "The clock is not manual; start vtx serve with --clock manual",
+            );
+        };
+        if let Err(e) = clock.set(to) {
+            return Response::text(400, &format!("{e:#}"));
+        }
+        info!("Clock set to {}", timeutil::rfc3339(clock.now().as_secs()));
+        self.clock_state()
+    }
+
+    /// Run a console command: `+<duration>` advances the manual clock and
+    /// `=<time>` sets it.
+    fn console(&mut self, line: &str) {
+        let response = if let Some(by) = line.strip_prefix('+') {
+            match timeutil::parse_duration(by) {
+                Ok(by) => self.advance_clock(by),
+                Err(e) => Response::text(400, &e.to_string()),
+            }
+        } else if let Some(to) = line.strip_prefix('=') {
+            match timeutil::parse_rfc3339(to.trim()) {
+                Ok(to) => self.set_clock(to),
+                Err(e) => Response::text(400, &e.to_string()),
+            }
+        } else if line.is_empty() {
+            return;
+        } else {
+            Response::text(
+                400,
+                &format!(
+                    "Unknown command '{line}'; type +<duration> (e.g. +1h) or =<RFC 3339 time>"
+                ),
+            )
+        };
+        if response.status >= 400 {
+            warn!("{}", String::from_utf8_lossy(&response.body).trim_end());
+        }
+    }
+}
+
+/// Whole seconds until `denied` can be retried, at least one.
+fn retry_after_secs(denied: &Denied) -> u64 {
+    denied.retry_after.as_secs_f64().ceil().max(1.0) as u64
+}
+
+/// The 429 the kernel answers a request over its limits with.
+fn limited(denied: &Denied) -> Response {
+    let retry_after = retry_after_secs(denied);
+    let message = match denied.reason {
+        "quota_exceeded" => "Request quota exceeded",
+        _ => "Too many requests",
+    };
+    let response = Response::json(
+        429,
+        &json!({
+            "error": denied.reason,
+            "message": message,
+            "retry_after": retry_after,
+        }),
+    )
+    .with_header("Retry-After", retry_after.to_string());
+    with_counters(response, Some(denied.counters))
+}
+
+/// `response` with `X-RateLimit-Remaining` and `X-Quota-Remaining` headers
+/// for the limits that are set.
+fn with_counters(mut response: Response, counters: Option<Counters>) -> Response {
+    let Some(counters) = counters else {
+        return response;
+    };
+    if let Some((left, _)) = counters.rate {
+        response = response.with_header("X-RateLimit-Remaining", left.to_string());
+    }
+    if let Some((left, _, _)) = counters.quota {
+        response = response.with_header("X-Quota-Remaining", left.to_string());
+    }
+    response
+}
+
+/// An unsigned (`alg: none`) JWT for `user`, issued at `now`: `sub` is the
+/// user id, `name` the username and `groups` the user's groups.
+fn mock_jwt(name: &str, user: &DevUserConfig, now: Duration) -> String {
+    let header = json!({ "alg": "none", "typ": "JWT" });
+    let claims = json!({
+        "sub": user.user_id,
+        "name": user.username.as_deref().unwrap_or(name),
+        "groups": user.groups,
+        "iat": now.as_secs(),
+        "exp": (now + MOCK_TOKEN_LIFETIME).as_secs(),
+    });
+    format!(
+        "{}.{}.",
+        BASE64URL.encode(header.to_string()),
+        BASE64URL.encode(claims.to_string())
+    )
+}
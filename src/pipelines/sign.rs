@@ -0,0 +1,461 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::common::digest_algorithm;
+use super::jobs;
+use crate::metadata::VtxMetadata;
+use crate::signing::{SignatureAlgorithm, SignaturePolicy};
+use crate::{config, container, ledger, output, packager, signing};
+use vtx_cli::contract;
+
+/// Generate an `algorithm` key pair as `<name>.pk8` (private, PKCS#8) and
+/// `<name>.pub` (base64).
+///
+/// Refuses to overwrite an existing key.
+pub fn execute_keygen_pipeline(name: &str, algorithm: SignatureAlgorithm) -> Result<()> {
+    let private_path = format!("{name}.pk8");
+    let public_path = format!("{name}.pub");
+    for path in [&private_path, &public_path] {
+        if Path::new(path).exists() {
+            anyhow::bail!("{path} already exists; refusing to overwrite a key");
+        }
+    }
+
+    let (pkcs8, public_key) = signing::generate_key(algorithm)?;
+    std::fs::write(&private_path, &pkcs8)
+        .with_context(|| format!("Failed to write {private_path}"))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&private_path, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to restrict permissions of {private_path}"))?;
+    }
+    std::fs::write(
+        &public_path,
+        format!("{}\n", signing::encode_public_key(&public_key)),
+    )
+    .with_context(|| format!("Failed to write {public_path}"))?;
+
+    ok!(
+        "Generated {} ({})",
+        signing::key_id(algorithm, &public_key),
+        private_path
+    );
+    info!(
+        "Keep {} secret; distribute {} to verifiers",
+        private_path, public_path
+    );
+    Ok(())
+}
+
+/// Sign an existing `.vtx` in place, replacing any previous signature.
+///
+/// Behavior:
+/// - Only v2 containers carry metadata and can be signed.
+/// - When a ledger exists in the current directory, the signed artifact is recorded.
+pub fn execute_sign_pipeline(file: &str, key: &str) -> Result<()> {
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let decoded = container::decode_sections(&bytes)
+        .map_err(|e| anyhow::anyhow!("{file} is not a valid .vtx container: {e}"))?;
+    let metadata = decoded.metadata.with_context(|| {
+        format!("{file} is a v1 container without metadata; repackage it first")
+    })?;
+
+    let capabilities =
+        container::verified_capabilities(&decoded).map_err(|e| anyhow::anyhow!("{file}: {e}"))?;
+    let assets =
+        container::verified_assets(&decoded).map_err(|e| anyhow::anyhow!("{file}: {e}"))?;
+
+    let pkcs8 = std::fs::read(key).with_context(|| format!("Failed to read signing key: {key}"))?;
+    let signed = signing::sign(metadata, decoded.component, &pkcs8)?;
+    packager::write_vtx_file_to(
+        Path::new(file),
+        decoded.component,
+        &signed,
+        capabilities,
+        assets,
+        None,
+    )
+    .context("Failed to write signed artifact")?;
+
+    if let Some(signature) = signing::embedded_signature(&signed)? {
+        ok!("Signed {} with {}", file, signature.key_id);
+    }
+    if Path::new(ledger::LEDGER_FILE).exists() {
+        let config = config::load().ok();
+        ledger::record(Path::new(file), &signed, digest_algorithm(config.as_ref()))?;
+    }
+    Ok(())
+}
+
+/// Verify the embedded signatures of `.vtx` files against a public key, and
+/// their required interfaces against a kernel API version.
+///
+/// `inputs` are `.vtx` files or directories, which stand for the `.vtx` files
+/// directly inside them. The key is `public_key` (base64 or a path to a `.pub`
+/// file), falling back to `package.public_key` in vtx.toml. Signatures must
+/// use an algorithm allowed by `package.signature_algorithms` (default: all).
+///
+/// Behavior:
+/// - Files are verified on up to `jobs` threads (default: available CPUs)
+///   and each result is printed as soon as it is known.
+/// - The manifest signature and the full signature over the metadata and
+///   component are checked independently. Artifacts signed before manifest
+///   signatures existed pass on the full signature alone, with a warning.
+/// - With `manifest_only`, only the manifest signature is checked and the
+///   component is never hashed.
+/// - With `against`, the `requires` metadata is checked with
+///   [`contract::check_requires`]; signatures are then only checked when a
+///   key is given or configured.
+/// - With several files, a pass/fail matrix follows and the command fails if
+///   any file did; a single file fails with its own error.
+pub fn execute_verify_pipeline(
+    inputs: &[String],
+    public_key: Option<&str>,
+    manifest_only: bool,
+    against: Option<&str>,
+    jobs: Option<u32>,
+) -> Result<()> {
+    let (public_key, policy) = verification_key(public_key)?;
+    let public_key = match public_key {
+        Some(public_key) => Some(public_key),
+        None if against.is_some() => None,
+        None => {
+            anyhow::bail!("No public key: pass --public-key or set package.public_key in vtx.toml")
+        }
+    };
+    if let Some(kernel_api) = against {
+        // Reject a malformed version once rather than for every file.
+        contract::check_requires(&BTreeMap::new(), kernel_api)?;
+    }
+
+    let files = expand_inputs(inputs)?;
+    let jobs = jobs.map(|n| n as usize).unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+    let results = jobs::run_parallel(&files, jobs, |file| {
+        let verification =
+            verify_file(file, public_key.as_deref(), &policy, manifest_only, against);
+        report_file(file, &verification, files.len() > 1);
+        Ok(verification)
+    })?;
+    let mut verifications: Vec<Verification> = results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(Verification::unreadable))
+        .collect();
+
+    if let [single] = verifications.as_mut_slice() {
+        return match single.errors.drain(..).next() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        };
+    }
+
+    let failed = summarize(&files, &verifications);
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} artifacts failed verification", files.len());
+    }
+    done!("Verified {} artifacts", files.len());
+    Ok(())
+}
+
+/// The key to verify signatures against, `public_key` (base64 or a path to a
+/// `.pub` file) or else `package.public_key`, and the `package.signature_algorithms`
+/// policy from vtx.toml.
+pub(super) fn verification_key(
+    public_key: Option<&str>,
+) -> Result<(Option<Vec<u8>>, SignaturePolicy)> {
+    // A broken vtx.toml must not fall back to accepting every algorithm.
+    let package = config::load_optional()?.and_then(|c| c.package);
+    let policy = match package
+        .as_ref()
+        .and_then(|p| p.signature_algorithms.as_deref())
+    {
+        Some(algorithms) => SignaturePolicy::allowing(algorithms)?,
+        None => SignaturePolicy::default(),
+    };
+    let configured = public_key
+        .map(str::to_string)
+        .or_else(|| package.and_then(|p| p.public_key));
+    let public_key = match configured {
+        Some(configured) if Path::new(&configured).is_file() => {
+            let content = std::fs::read_to_string(&configured)
+                .with_context(|| format!("Failed to read public key: {configured}"))?;
+            Some(signing::decode_public_key(&content)?)
+        }
+        Some(configured) => Some(signing::decode_public_key(&configured)?),
+        None => None,
+    };
+    Ok((public_key, policy))
+}
+
+/// State of one signature of an artifact, for the matrix.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Check {
+    /// Verified.
+    Passed,
+    /// Absent from an artifact signed before manifest signatures existed.
+    Missing,
+    /// Not checked (`--manifest-only`, no key or `--against`, or the file
+    /// could not be read).
+    Skipped,
+    Failed,
+}
+
+impl Check {
+    fn label(self) -> ColoredString {
+        match self {
+            Check::Passed => "ok".green(),
+            Check::Missing => "missing".yellow(),
+            Check::Skipped => "-".normal(),
+            Check::Failed => "failed".red(),
+        }
+    }
+}
+
+/// How one file fared.
+struct Verification {
+    manifest: Check,
+    payload: Check,
+    /// `requires` against `--against`.
+    kernel: Check,
+    /// Why checks failed, in the order they ran.
+    errors: Vec<anyhow::Error>,
+}
+
+impl Verification {
+    fn unreadable(e: anyhow::Error) -> Self {
+        Self {
+            manifest: Check::Skipped,
+            payload: Check::Skipped,
+            kernel: Check::Skipped,
+            errors: vec![e],
+        }
+    }
+
+    fn passed(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// `.vtx` files named by `inputs`: files as given, and the `.vtx` files
+/// directly inside directories, sorted.
+fn expand_inputs(inputs: &[String]) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if !path.is_dir() {
+            files.push(input.clone());
+            continue;
+        }
+        let mut found: Vec<String> = std::fs::read_dir(path)
+            .with_context(|| format!("Failed to read directory {input}"))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "vtx"))
+            .map(|p| p.display().to_string())
+            .collect();
+        if found.is_empty() {
+            anyhow::bail!("No .vtx files in {input}");
+        }
+        found.sort();
+        files.extend(found);
+    }
+    if files.is_empty() {
+        anyhow::bail!("No input files; pass one or more .vtx files or directories");
+    }
+    Ok(files)
+}
+
+/// Check the manifest signature and, unless `manifest_only`, the full
+/// signature of `file` under `policy` when there is a `public_key`, and its
+/// `requires` against the kernel API `against`.
+fn verify_file(
+    file: &str,
+    public_key: Option<&[u8]>,
+    policy: &SignaturePolicy,
+    manifest_only: bool,
+    against: Option<&str>,
+) -> Verification {
+    let bytes = match std::fs::read(file).with_context(|| format!("Failed to read {file}")) {
+        Ok(bytes) => bytes,
+        Err(e) => return Verification::unreadable(e),
+    };
+    let decoded = match container::decode_sections(&bytes) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            return Verification::unreadable(anyhow::anyhow!(
+                "{file} is not a valid .vtx container: {e}"
+            ))
+        }
+    };
+    let Some(metadata) = decoded.metadata else {
+        return Verification::unreadable(anyhow::anyhow!(
+            "{file} is a v1 container without metadata; it cannot be signed"
+        ));
+    };
+    // The signatures cover the capabilities and asset sections through their digests.
+    let sections = container::verified_capabilities(&decoded).and_then(|_| {
+        container::verified_assets(&decoded)?
+            .map(container::decode_assets)
+            .transpose()
+    });
+    if let Err(e) = sections {
+        return Verification::unreadable(anyhow::anyhow!("{file}: {e}"));
+    }
+
+    let mut verification = Verification {
+        manifest: Check::Skipped,
+        payload: Check::Skipped,
+        kernel: Check::Skipped,
+        errors: Vec::new(),
+    };
+    if let Some(public_key) = public_key {
+        verify_signatures(
+            file,
+            metadata,
+            decoded.component,
+            public_key,
+            policy,
+            manifest_only,
+            &mut verification,
+        );
+    }
+    if let Some(kernel_api) = against {
+        verification.kernel = match check_kernel(file, metadata, kernel_api) {
+            Ok(()) => {
+                ok!("{} is compatible with vtx:api@{}", file, kernel_api);
+                Check::Passed
+            }
+            Err(e) => {
+                verification.errors.push(e);
+                Check::Failed
+            }
+        };
+    }
+    verification
+}
+
+fn verify_signatures(
+    file: &str,
+    metadata: &[u8],
+    component: &[u8],
+    public_key: &[u8],
+    policy: &SignaturePolicy,
+    manifest_only: bool,
+    verification: &mut Verification,
+) {
+    let has_manifest_signature =
+        !matches!(signing::embedded_manifest_signature(metadata), Ok(None));
+    if !manifest_only && !has_manifest_signature {
+        // Unsigned artifacts are reported once, by the full signature check.
+        if matches!(signing::embedded_signature(metadata), Ok(Some(_))) {
+            verification.manifest = Check::Missing;
+        }
+    } else {
+        verification.manifest = match signing::verify_manifest(metadata, public_key, policy) {
+            Ok(signature) => {
+                ok!("{} manifest is signed by {}", file, signature.key_id);
+                Check::Passed
+            }
+            Err(e) => {
+                verification.errors.push(e);
+                Check::Failed
+            }
+        };
+    }
+    if !manifest_only {
+        verification.payload = match signing::verify(metadata, component, public_key, policy) {
+            Ok(signature) => {
+                ok!("{} is signed by {}", file, signature.key_id);
+                Check::Passed
+            }
+            Err(e) => {
+                verification.errors.push(e);
+                Check::Failed
+            }
+        };
+    }
+}
+
+/// Check the `requires` metadata of `file` against `kernel_api`.
+fn check_kernel(file: &str, metadata: &[u8], kernel_api: &str) -> Result<()> {
+    let meta: VtxMetadata = serde_json::from_slice(metadata)
+        .with_context(|| format!("{file} has malformed metadata"))?;
+    let requires = meta.requires.with_context(|| {
+        format!("{file} does not record the interfaces it requires; rebuild it")
+    })?;
+    contract::check_requires(&requires, kernel_api).map_err(|e| anyhow::anyhow!("{file}: {e}"))
+}
+
+/// Print what went wrong with `file` as soon as it is known. Errors are left
+/// to the caller unless `several` files are verified.
+fn report_file(file: &str, verification: &Verification, several: bool) {
+    if verification.manifest == Check::Missing {
+        warn!("{file} has no manifest signature; run vtx sign again to add one");
+    }
+    if several {
+        for e in &verification.errors {
+            warn!("{}: {:#}", file, e);
+        }
+    }
+}
+
+/// Print the pass/fail matrix, emit the `verify` event and return how many
+/// files failed.
+fn summarize(files: &[String], verifications: &[Verification]) -> usize {
+    info!("Verification summary:");
+    let width = files.iter().map(String::len).max().unwrap_or(0).max(4);
+    println!(
+        "  {:<width$}  {:<8}  {:<8}  {:<8}  RESULT",
+        "FILE", "MANIFEST", "PAYLOAD", "KERNEL"
+    );
+    for (file, verification) in files.iter().zip(verifications) {
+        let result = if verification.passed() {
+            "ok".green()
+        } else {
+            "failed".red()
+        };
+        println!(
+            "  {:<width$}  {:<8}  {:<8}  {:<8}  {}",
+            file,
+            verification.manifest.label(),
+            verification.payload.label(),
+            verification.kernel.label(),
+            result
+        );
+    }
+
+    let status = |check: Check| match check {
+        Check::Passed => "ok",
+        Check::Missing => "missing",
+        Check::Skipped => "skipped",
+        Check::Failed => "failed",
+    };
+    output::emit(
+        "verify",
+        serde_json::json!({
+            "files": files
+                .iter()
+                .zip(verifications)
+                .map(|(file, verification)| serde_json::json!({
+                    "file": file,
+                    "manifest": status(verification.manifest),
+                    "payload": status(verification.payload),
+                    "kernel": status(verification.kernel),
+                    "status": if verification.passed() { "ok" } else { "failed" },
+                    "errors": verification
+                        .errors
+                        .iter()
+                        .map(|e| format!("{e:#}"))
+                        .collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    );
+    verifications.iter().filter(|v| !v.passed()).count()
+}
@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use tracing::info;
+use vtx_cli::analysis::{self, SizeEntry, SizeReport};
+
+use crate::cli::SizeAction;
+use crate::container::{self, PayloadKey};
+use crate::history::{self, SIZE_HISTORY_FILE};
+use crate::output;
+
+pub fn execute_size_pipeline(
+    action: Option<&SizeAction>,
+    artifact: Option<&str>,
+    top: usize,
+) -> Result<()> {
+    let Some(action) = action else {
+        let Some(file) = artifact else {
+            anyhow::bail!("Expected an artifact to analyze or a subcommand (see vtx size --help)");
+        };
+        return analyze_file(file, top);
+    };
+    match action {
+        SizeAction::History {
+            package,
+            profile,
+            limit,
+            csv,
+        } => {
+            let mut records: Vec<_> = history::read()?
+                .into_iter()
+                .filter(|r| package.as_ref().is_none_or(|p| &r.package == p))
+                .filter(|r| &r.profile == profile)
+                .collect();
+            if let Some(limit) = limit {
+                let skip = records.len().saturating_sub(*limit);
+                records.drain(..skip);
+            }
+
+            if *csv {
+                println!("timestamp,commit,dirty,package,profile,artifact_size,component_size");
+                for r in &records {
+                    println!(
+                        "{},{},{},{},{},{},{}",
+                        r.timestamp,
+                        r.commit.as_deref().unwrap_or_default(),
+                        r.dirty,
+                        r.package,
+                        r.profile,
+                        r.artifact_size,
+                        r.component_size
+                    );
+                }
+                return Ok(());
+            }
+
+            if records.is_empty() {
+                info!("No {} builds recorded in {}", profile, SIZE_HISTORY_FILE);
+                return Ok(());
+            }
+
+            let mut previous: Option<u64> = None;
+            for r in &records {
+                let commit = history::describe_commit(r.commit.as_deref(), r.dirty);
+                let delta = previous
+                    .map(|p| format!("{:+}", r.artifact_size as i64 - p as i64))
+                    .unwrap_or_default();
+                println!(
+                    "{}  {:<10} {:<16} {:>10} B {:>10}",
+                    r.timestamp, commit, r.package, r.artifact_size, delta
+                );
+                previous = Some(r.artifact_size);
+            }
+
+            let sizes: Vec<f64> = records.iter().map(|r| r.artifact_size as f64).collect();
+            info!("{} builds: {}", records.len(), history::sparkline(&sizes));
+        }
+    }
+    Ok(())
+}
+
+/// Analyze a `.vtx` (its component), a bare component or a core module.
+fn analyze_file(file: &str, top: usize) -> Result<()> {
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let wasm = container::unwrap_component(bytes, PayloadKey::load(None)?.as_ref())?;
+    let report = analysis::analyze(&wasm).with_context(|| format!("Failed to parse {file}"))?;
+    if output::is_json() {
+        output::document("size", &report)
+    } else {
+        print_report(&report, top);
+        Ok(())
+    }
+}
+
+/// Print the size breakdown of packaged component bytes (`--size-report`).
+pub(super) fn report_component(component: &[u8]) -> Result<()> {
+    let report = analysis::analyze(component).context("Failed to analyze component size")?;
+    if output::is_json() {
+        output::emit("size_report", serde_json::to_value(&report)?);
+    } else {
+        print_report(&report, 10);
+    }
+    Ok(())
+}
+
+fn print_report(report: &SizeReport, top: usize) {
+    info!("Size breakdown of {} bytes", report.total);
+    print_table("SECTION", &report.sections, report.total, usize::MAX);
+    print_table(
+        "CUSTOM SECTION",
+        &report.custom_sections,
+        report.total,
+        usize::MAX,
+    );
+    if report.prefixes.is_empty() {
+        println!();
+        info!("No function names found; build with debug info or keep the name section for a per-function breakdown");
+    } else {
+        print_table("FUNCTION PREFIX", &report.prefixes, report.total, top);
+    }
+    print_table("FUNCTION", &report.functions, report.total, top);
+}
+
+fn print_table(title: &str, entries: &[SizeEntry], total: u64, limit: usize) {
+    if entries.is_empty() {
+        return;
+    }
+    println!("\n{:>10} {:>7}  {}", "BYTES", "%", title);
+    for entry in entries.iter().take(limit) {
+        let percent = (entry.size * 1000).checked_div(total).unwrap_or(0) as f64 / 10.0;
+        println!("{:>10} {:>6.1}%  {}", entry.size, percent, entry.name);
+    }
+    if entries.len() > limit {
+        let rest: u64 = entries[limit..].iter().map(|e| e.size).sum();
+        println!("{:>10} {:>7}  ... {} more", rest, "", entries.len() - limit);
+    }
+}
@@ -0,0 +1,1135 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use colored::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
+
+use super::build::build_package;
+use super::workspace::{self, Outcome, Workspace};
+use crate::cli::{BuildArgs, TestArgs, TestFormat, WorkspaceArgs};
+use crate::container::{self, PayloadKey};
+use crate::harness::{self, Fault, HarnessOptions, HttpRequest, HttpResponse};
+use crate::hex;
+use crate::{config, offline, output, scratch, stages, timeutil, toolchain};
+use vtx_cli::kernel_api::KernelProfile;
+
+/// Directory of contract fixtures, relative to the project directory.
+const FIXTURE_DIR: &str = "tests/vtx";
+
+/// File in the fixture directory with the digest of each fixture `vtx test
+/// scaffold` wrote, so regenerating can tell untouched fixtures from edited ones.
+const SCAFFOLD_DIGESTS: &str = ".scaffold.sha256";
+
+/// Length of the strings `--mutate` substitutes as oversized input.
+const OVERSIZED_LEN: usize = 1 << 20;
+
+/// Most mutated variants `--mutate` derives from one fixture.
+const MAX_MUTATIONS: usize = 32;
+
+/// A contract fixture: one call and what it must return.
+///
+/// `handle` fixtures give a `request` and `response` checks; fixtures for
+/// other exports name the `export` and give its `args` and expected `result`.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(super) struct Fixture {
+    /// Export to call; `handle` when absent.
+    export: Option<String>,
+    /// Request passed to `handle`; `GET /` when absent.
+    request: Option<HttpRequest>,
+    /// Checks on the response of `handle`.
+    response: Option<Expected>,
+    /// Arguments by parameter name.
+    #[serde(default)]
+    args: Map<String, Value>,
+    /// Expected return value, compared as JSON; unchecked when absent.
+    result: Option<Value>,
+    /// Frozen clock time for this fixture, overriding `--time` and `[test] time`.
+    time: Option<String>,
+    /// Random seed for this fixture, overriding `--seed` and `[test] seed`.
+    seed: Option<u64>,
+    /// Failures injected into the stubbed kernel functions for this fixture.
+    #[serde(default)]
+    faults: Vec<Fault>,
+}
+
+/// Expected response; unset fields are not checked.
+#[derive(Deserialize, JsonSchema, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+struct Expected {
+    status: Option<u16>,
+    /// Exact body, compared as UTF-8 text.
+    body: Option<String>,
+    /// Text the body must contain.
+    body_contains: Option<String>,
+}
+
+/// Outcome of one fixture.
+#[derive(Serialize, Deserialize, Debug)]
+struct FixtureResult {
+    name: String,
+    passed: bool,
+    /// Status returned by the plugin; absent if `handle` failed.
+    status: Option<u16>,
+    failures: Vec<String>,
+    /// Wall time of the `handle` call, including instantiation.
+    duration_ms: u64,
+    /// What the plugin wrote to stdout and stderr; kept for failed fixtures only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+    /// Runs of the fixture: 1 plus the retries `--retries` allowed it.
+    attempts: u32,
+    /// Failed, then passed on a retry.
+    flaky: bool,
+    /// For a flaky fixture, the failures of its first attempt.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    flaky_failures: Vec<String>,
+}
+
+/// Report emitted by `vtx --output json test`.
+#[derive(Serialize, Deserialize)]
+struct TestReport {
+    artifact: String,
+    /// `cargo test` outcome for Rust projects.
+    cargo_test: Option<bool>,
+    passed: usize,
+    failed: usize,
+    /// Passed fixtures that needed a retry.
+    flaky: usize,
+    fixtures: Vec<FixtureResult>,
+}
+
+/// Build the plugin in debug mode and run its tests.
+///
+/// Flow:
+/// 1. Build and package the component (non-release).
+/// 2. Rust projects: run `cargo test -p <package>` for the unit tests.
+/// 3. Call `handle` with every `tests/vtx/*.json` fixture under wasmtime and
+///    compare the response with the fixture's expectations.
+///
+/// Fails when `cargo test` or any fixture fails; `--retries` reruns failed
+/// fixtures and `--mutate` adds their mutated variants (see [`mutations`]).
+/// `--time`, `--seed` and `[test]` freeze the plugin's clocks and seed its
+/// random numbers; a fixture's `faults` make stubbed kernel calls fail.
+/// `--api` runs the fixtures against an emulated kernel version's imports.
+/// `--filter` and `--shard` select the fixtures (see
+/// [`select_fixtures`]); `--format tap` prints the
+/// results as TAP and `--report` also writes them as JUnit XML. `--package`
+/// tests a workspace member with the fixtures in its own directory;
+/// `--workspace` tests every member (see [`test_workspace`]).
+pub fn execute_test_pipeline(
+    members: &WorkspaceArgs,
+    target: &str,
+    force: bool,
+    tests: &TestArgs,
+) -> Result<()> {
+    let json = output::is_json();
+    if json && tests.format == TestFormat::Tap {
+        anyhow::bail!("--format tap cannot be combined with --output json");
+    }
+    // The build changes the current directory; resolve the report path first.
+    let report_path = tests
+        .report
+        .as_deref()
+        .map(std::path::absolute)
+        .transpose()?;
+    if members.workspace {
+        return test_workspace(members, target, force, tests, report_path.as_deref());
+    }
+    let config = config::load()?;
+    let fixture_dir = match &members.package {
+        Some(package) => std::env::current_dir()?
+            .join(config.project.path.as_deref().unwrap_or(""))
+            .join(workspace::member_dir(&config, package)?)
+            .join(FIXTURE_DIR),
+        None => PathBuf::from(FIXTURE_DIR),
+    };
+    let report = test(
+        &config,
+        members.package.as_deref(),
+        &fixture_dir,
+        target,
+        force,
+        tests,
+    )?;
+    let suite = members.package.as_deref().unwrap_or(&config.project.name);
+    if tests.format == TestFormat::Tap {
+        print_tap(&[(suite, &report)], false);
+    }
+    if let Some(path) = &report_path {
+        write_junit(path, &[(suite, &report)])?;
+    }
+    if json {
+        output::document("test", &report)?;
+    }
+    if let Some(failure) = failure(&report) {
+        anyhow::bail!(failure);
+    }
+    if !json {
+        done!(
+            "All tests passed ({} contract fixture(s){})",
+            report.passed,
+            flaky_note(&report)
+        );
+    }
+    Ok(())
+}
+
+/// How `vtx test scaffold` treated one fixture file.
+#[derive(Serialize)]
+struct ScaffoldedFixture {
+    file: String,
+    /// `created`, `updated` (untouched since generated), `unchanged`, or
+    /// `kept` (edited since generated, or not generated).
+    status: &'static str,
+}
+
+/// Write a skeleton fixture for every function the plugin exports.
+///
+/// The component is `artifact` or else the debug build of `package` (default:
+/// `project.name`). Each `tests/vtx/<export>.json` calls the export with a
+/// placeholder of each parameter's type (see [`harness::placeholder`]);
+/// `handle` fixtures get a default request. Fixtures are regenerated only
+/// while they still match the digest recorded in `tests/vtx/.scaffold.sha256`,
+/// so edited fixtures are kept.
+pub fn execute_test_scaffold_pipeline(
+    package: Option<&str>,
+    artifact: Option<&Path>,
+    force: bool,
+) -> Result<()> {
+    let config = config::load()?;
+    let mut fixture_dir = std::path::absolute(config.project.path.as_deref().unwrap_or("."))?;
+    if let Some(package) = package {
+        fixture_dir.push(workspace::member_dir(&config, package)?);
+    }
+    fixture_dir.push(FIXTURE_DIR);
+    let artifact = match artifact {
+        Some(path) => path.to_path_buf(),
+        None => build_package(
+            package.map(str::to_string),
+            None,
+            "wasm32-wasip1",
+            false,
+            force,
+            false,
+            &BuildArgs::default(),
+        )?,
+    };
+    let bytes = std::fs::read(&artifact)
+        .with_context(|| format!("Failed to read {}", artifact.display()))?;
+    let component = container::unwrap_component(bytes, PayloadKey::load(None)?.as_ref())?;
+    let exports = harness::exported_functions(&component)
+        .with_context(|| format!("Failed to read the exports of {}", artifact.display()))?;
+    if exports.is_empty() {
+        anyhow::bail!("{} exports no functions", artifact.display());
+    }
+
+    std::fs::create_dir_all(&fixture_dir)
+        .with_context(|| format!("Failed to create {}", fixture_dir.display()))?;
+    let digests_path = fixture_dir.join(SCAFFOLD_DIGESTS);
+    let mut digests = read_scaffold_digests(&digests_path)?;
+    let mut report = Vec::new();
+    for export in &exports {
+        let fixture = match export.name.as_str() {
+            "handle" => json!({ "request": HttpRequest::default(), "response": {} }),
+            name => json!({ "export": name, "args": export.params }),
+        };
+        let content = format!("{}\n", serde_json::to_string_pretty(&fixture)?);
+        let file = format!("{}.json", export.name);
+        let path = fixture_dir.join(&file);
+        let digest = sha256_hex(content.as_bytes());
+        let status = match std::fs::read(&path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => "created",
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+            Ok(existing) if existing == content.as_bytes() => "unchanged",
+            Ok(existing) if digests.get(&file) == Some(&sha256_hex(&existing)) => "updated",
+            Ok(_) => {
+                digests.remove(&file);
+                "kept"
+            }
+        };
+        if matches!(status, "created" | "updated") {
+            std::fs::write(&path, &content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        if status != "kept" {
+            digests.insert(file.clone(), digest);
+        }
+        report.push(ScaffoldedFixture { file, status });
+    }
+    let listing: String = digests
+        .iter()
+        .map(|(file, digest)| format!("{digest}  {file}\n"))
+        .collect();
+    std::fs::write(&digests_path, listing)
+        .with_context(|| format!("Failed to write {}", digests_path.display()))?;
+
+    if output::is_json() {
+        return output::document("test scaffold", &report);
+    }
+    for fixture in &report {
+        println!("  {:<9} {FIXTURE_DIR}/{}", fixture.status, fixture.file);
+    }
+    let written = report
+        .iter()
+        .filter(|f| matches!(f.status, "created" | "updated"))
+        .count();
+    done!(
+        "Scaffolded {written} of {} fixture(s) in {}",
+        report.len(),
+        fixture_dir.display()
+    );
+    Ok(())
+}
+
+/// `file -> digest` from a `sha256sum`-style listing; empty if missing.
+fn read_scaffold_digests(path: &Path) -> Result<std::collections::BTreeMap<String, String>> {
+    let listing = match std::fs::read_to_string(path) {
+        Ok(listing) => listing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    Ok(listing
+        .lines()
+        .filter_map(|line| line.split_once("  "))
+        .map(|(digest, file)| (file.to_string(), digest.to_string()))
+        .collect())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(&Sha256::digest(bytes))
+}
+
+/// Test every workspace member, each with the fixtures in its own
+/// directory, and print a summary table. TAP and JUnit reports cover every
+/// member, with one suite per member.
+fn test_workspace(
+    members: &WorkspaceArgs,
+    target: &str,
+    force: bool,
+    tests: &TestArgs,
+    report_path: Option<&Path>,
+) -> Result<()> {
+    let workspace = Workspace::discover(members)?;
+    let config = config::load()?;
+    let names = workspace.names();
+    step!(
+        "Testing workspace: {} packages ({})",
+        names.len(),
+        names.join(", ")
+    );
+
+    let mut args = vec!["--target".to_string(), target.to_string()];
+    if force {
+        args.push("--force".to_string());
+    }
+    args.extend(tests.to_args());
+    let results = workspace.run(
+        "test",
+        &args,
+        |member, dir| {
+            let fixture_dir = workspace.project_dir().join(dir).join(FIXTURE_DIR);
+            test(&config, Some(member), &fixture_dir, target, force, tests)
+        },
+        |member, child| match child.report {
+            Some(report) => {
+                let report: TestReport = serde_json::from_value(report)?;
+                if !output::is_json()
+                    && tests.format == TestFormat::Pretty
+                    && !report.fixtures.is_empty()
+                {
+                    stages::suspend(|| {
+                        println!("{} contract fixtures:", format!("[{member}]").bold());
+                        report.fixtures.iter().for_each(print_result);
+                    });
+                }
+                Ok(report)
+            }
+            None => anyhow::bail!(child
+                .error
+                .unwrap_or_else(|| "vtx test reported nothing".into())),
+        },
+    )?;
+
+    let suites: Vec<(&str, &TestReport)> = results
+        .iter()
+        .filter_map(|(member, result)| Some((member.as_str(), result.as_ref().ok()?)))
+        .collect();
+    if tests.format == TestFormat::Tap {
+        print_tap(&suites, true);
+    }
+    if let Some(path) = report_path {
+        write_junit(path, &suites)?;
+    }
+
+    let failed = workspace::summarize("test", &results, |report| {
+        let cargo_test = match report.cargo_test {
+            Some(true) => "cargo test passed, ",
+            Some(false) => "cargo test failed, ",
+            None => "",
+        };
+        Outcome {
+            passed: failure(report).is_none(),
+            detail: format!(
+                "{cargo_test}{} of {} contract fixture(s) passed{}",
+                report.passed,
+                report.passed + report.failed,
+                flaky_note(report)
+            ),
+            fields: serde_json::json!({
+                "artifact": report.artifact,
+                "cargo_test": report.cargo_test,
+                "passed": report.passed,
+                "failed": report.failed,
+                "flaky": report.flaky,
+            }),
+        }
+    });
+    if failed > 0 {
+        anyhow::bail!(
+            "{failed} of {} workspace packages failed their tests",
+            results.len()
+        );
+    }
+    done!("Tested {} workspace packages", results.len());
+    Ok(())
+}
+
+/// Build `package` (default: `project.name`) and run its unit tests and the
+/// fixtures in `fixture_dir`, relative to `project.path` unless absolute.
+///
+/// Leaves the current directory at `project.path`.
+fn test(
+    config: &config::ProjectConfig,
+    package: Option<&str>,
+    fixture_dir: &Path,
+    target: &str,
+    force: bool,
+    tests: &TestArgs,
+) -> Result<TestReport> {
+    let json = output::is_json();
+    let language = toolchain::canonical_language(&config.project.language);
+    let settings = config.test.clone().unwrap_or_default();
+    let defaults = HarnessOptions {
+        capture_output: true,
+        frozen_time: tests
+            .time
+            .as_deref()
+            .or(settings.time.as_deref())
+            .map(timeutil::parse_rfc3339)
+            .transpose()?,
+        clock: None,
+        random_seed: tests.seed.or(settings.seed),
+        faults: Vec::new(),
+        user: None,
+        kernel_api: tests
+            .api
+            .as_deref()
+            .map(KernelProfile::resolve)
+            .transpose()?,
+    };
+    let artifact = build_package(
+        package.map(str::to_string),
+        None,
+        target,
+        false,
+        force,
+        false,
+        &BuildArgs::default(),
+    )?;
+    // The build leaves the current directory at project.path.
+
+    let first_shard = tests.shard.is_none_or(|shard| shard.index == 1);
+    let cargo_test = if language == "rust" && first_shard {
+        Some(run_cargo_test(
+            package.unwrap_or(&config.project.name),
+            tests.filter.as_deref(),
+        )?)
+    } else {
+        None
+    };
+
+    let fixtures = select_fixtures(fixture_files(fixture_dir)?, tests);
+    let mut results = Vec::new();
+    if fixtures.is_empty() {
+        info!("No contract fixtures selected in {}", fixture_dir.display());
+    } else {
+        let bytes = std::fs::read(&artifact)
+            .with_context(|| format!("Failed to read {}", artifact.display()))?;
+        let component = container::decode(&bytes, PayloadKey::load(None)?.as_ref())
+            .map(|decoded| decoded.component)
+            .with_context(|| format!("{} is not a valid .vtx file", artifact.display()))?;
+        if !json {
+            step!(
+                "Running {} contract fixture(s) from {}",
+                fixtures.len(),
+                fixture_dir.display()
+            );
+        }
+        let exports = match tests.mutate {
+            true => harness::exported_functions(&component)?,
+            false => Vec::new(),
+        };
+        for path in &fixtures {
+            let name = fixture_name(path);
+            let fixture = load_fixture(path)?;
+            let mut runs = Vec::new();
+            if tests.mutate {
+                runs.extend(
+                    mutations(&fixture, &exports)
+                        .into_iter()
+                        .map(|(mutation, mutant)| (format!("{name} [{mutation}]"), mutant)),
+                );
+            }
+            runs.insert(0, (name, fixture));
+            for (name, fixture) in runs {
+                let options = HarnessOptions {
+                    frozen_time: match &fixture.time {
+                        Some(time) => Some(timeutil::parse_rfc3339(time)?),
+                        None => defaults.frozen_time,
+                    },
+                    random_seed: fixture.seed.or(defaults.random_seed),
+                    faults: fixture.faults.clone(),
+                    ..defaults.clone()
+                };
+                let result = run_with_retries(&component, name, &fixture, &options, tests.retries)?;
+                if !json && tests.format == TestFormat::Pretty {
+                    print_result(&result);
+                }
+                results.push(result);
+            }
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.passed).count();
+    Ok(TestReport {
+        artifact: artifact.display().to_string(),
+        cargo_test,
+        passed: results.len() - failed,
+        failed,
+        flaky: results.iter().filter(|r| r.flaky).count(),
+        fixtures: results,
+    })
+}
+
+/// `, N flaky` when fixtures passed only on a retry.
+fn flaky_note(report: &TestReport) -> String {
+    match report.flaky {
+        0 => String::new(),
+        n => format!(", {n} flaky"),
+    }
+}
+
+/// Why `report` fails the run, if it does.
+fn failure(report: &TestReport) -> Option<String> {
+    let cargo_failed = report.cargo_test == Some(false);
+    match (cargo_failed, report.failed) {
+        (false, 0) => None,
+        (true, 0) => Some("cargo test failed".to_string()),
+        (true, n) => Some(format!("cargo test failed; {n} contract fixture(s) failed")),
+        (false, n) => Some(format!(
+            "{n} of {} contract fixture(s) failed",
+            report.passed + n
+        )),
+    }
+}
+
+/// `cargo test` for the plugin crate on the host, limited to tests whose
+/// name contains `filter`; `true` if it passed.
+fn run_cargo_test(package: &str, filter: Option<&str>) -> Result<bool> {
+    step!("Running cargo test -p {}", package);
+    let status = Command::new("cargo")
+        .args(["test", "-p", package])
+        .args(filter)
+        .args(offline::cargo_args())
+        .status()
+        .context("Failed to spawn cargo test")?;
+    if !status.success() {
+        warn!("cargo test failed ({})", status);
+    }
+    Ok(status.success())
+}
+
+/// `*.json` files in `dir`, sorted by name; empty if the directory does not exist.
+fn fixture_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// The fixture name: the file name without `.json`.
+fn fixture_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// The fixtures whose name contains `--filter`, then every M-th of those
+/// for `--shard N/M`, so shards split the filtered set evenly.
+fn select_fixtures(files: Vec<PathBuf>, tests: &TestArgs) -> Vec<PathBuf> {
+    files
+        .into_iter()
+        .filter(|path| {
+            tests
+                .filter
+                .as_deref()
+                .is_none_or(|filter| fixture_name(path).contains(filter))
+        })
+        .enumerate()
+        .filter(|(position, _)| tests.shard.is_none_or(|shard| shard.contains(*position)))
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// Run the fixture at `path`, rerunning a failure up to `retries` times. A
+/// fixture that passes on a retry is flaky and keeps its first failures.
+fn run_with_retries(
+    component: &[u8],
+    name: String,
+    fixture: &Fixture,
+    options: &HarnessOptions,
+    retries: u32,
+) -> Result<FixtureResult> {
+    let mut result = run_fixture(component, name.clone(), fixture, options);
+    let first_failures = result.failures.clone();
+    let mut attempts = 1;
+    while !result.passed && attempts <= retries {
+        scratch::interrupt_token().check()?;
+        attempts += 1;
+        debug!("Retrying fixture {} (attempt {attempts})", result.name);
+        // A retry with the same seed would fail the same way.
+        let options = HarnessOptions {
+            random_seed: options
+                .random_seed
+                .map(|seed| seed.wrapping_add(u64::from(attempts - 1))),
+            ..options.clone()
+        };
+        result = run_fixture(component, name.clone(), fixture, &options);
+    }
+    result.attempts = attempts;
+    if result.passed && attempts > 1 {
+        result.flaky = true;
+        result.flaky_failures = first_failures;
+    }
+    Ok(result)
+}
+
+fn load_fixture(path: &Path) -> Result<Fixture> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let fixture: Fixture = serde_json::from_str(&content).with_context(|| {
+        format!(
+            "{} is not a valid fixture (expected request {{method, path, query}} and response {{status, body, body_contains}}, or export, args and result)",
+            path.display()
+        )
+    })?;
+    if let Some(time) = &fixture.time {
+        timeutil::parse_rfc3339(time).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+    }
+    for fault in &fixture.faults {
+        fault
+            .validate()
+            .map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+    }
+    match fixture.export.as_deref() {
+        None | Some("handle") => {
+            if !fixture.args.is_empty() || fixture.result.is_some() {
+                anyhow::bail!(
+                    "{}: handle fixtures take request and response, not args and result",
+                    path.display()
+                );
+            }
+        }
+        Some(export) => {
+            if fixture.request.is_some() || fixture.response.is_some() {
+                anyhow::bail!(
+                    "{}: request and response are for handle fixtures; '{export}' takes args and result",
+                    path.display()
+                );
+            }
+        }
+    }
+    Ok(fixture)
+}
+
+fn run_fixture(
+    component: &[u8],
+    name: String,
+    fixture: &Fixture,
+    options: &HarnessOptions,
+) -> FixtureResult {
+    let cancel = scratch::interrupt_token();
+    let started = Instant::now();
+    let (status, failures, output) = match fixture.export.as_deref() {
+        None | Some("handle") => {
+            let request = fixture.request.clone().unwrap_or_default();
+            let invocation = harness::invoke_handle_with(component, &request, options, &cancel);
+            let (status, failures) = match &invocation.result {
+                Ok(response) => (
+                    Some(response.status),
+                    compare(&fixture.response.clone().unwrap_or_default(), response),
+                ),
+                Err(e) => (None, vec![format!("handle failed: {e:#}")]),
+            };
+            (status, failures, invocation.output)
+        }
+        Some(export) => {
+            let invocation =
+                harness::invoke_export(component, export, &fixture.args, options, &cancel);
+            let failures = match (&invocation.result, &fixture.result) {
+                (Ok(actual), Some(expected)) if actual != expected => {
+                    vec![format!("expected result {expected}, got {actual}")]
+                }
+                (Ok(_), _) => Vec::new(),
+                (Err(e), _) => vec![format!("{export} failed: {e:#}")],
+            };
+            (None, failures, invocation.output)
+        }
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let passed = failures.is_empty();
+    FixtureResult {
+        name,
+        passed,
+        status,
+        failures,
+        duration_ms,
+        output: (!passed && !output.is_empty())
+            .then(|| String::from_utf8_lossy(&output).into_owned()),
+        attempts: 1,
+        flaky: false,
+        flaky_failures: Vec::new(),
+    }
+}
+
+/// Variants of `fixture` for `--mutate`, labelled by what was changed.
+///
+/// The variants keep the call and drop the expectations: the plugin may
+/// answer them however it likes but must not trap. `handle` requests get an
+/// empty or unknown method and an empty, truncated, malformed or oversized
+/// path and query. Other exports get every string in their arguments
+/// emptied, truncated and oversized, list entries removed one at a time
+/// (a missing header for `[name, value]` pairs) and `content-type` pairs
+/// given a wrong value; variants that no longer fit the parameter types are
+/// dropped.
+fn mutations(fixture: &Fixture, exports: &[harness::ExportedFunction]) -> Vec<(String, Fixture)> {
+    let unchecked = Fixture {
+        response: None,
+        result: None,
+        ..fixture.clone()
+    };
+    let oversized = "a".repeat(OVERSIZED_LEN);
+    let mut variants = Vec::new();
+    match fixture.export.as_deref() {
+        None | Some("handle") => {
+            let request = fixture.request.clone().unwrap_or_default();
+            let mut requests = vec![
+                (
+                    "method:empty",
+                    HttpRequest {
+                        method: String::new(),
+                        ..request.clone()
+                    },
+                ),
+                (
+                    "method:unknown",
+                    HttpRequest {
+                        method: "BREW".to_string(),
+                        ..request.clone()
+                    },
+                ),
+                (
+                    "path:empty",
+                    HttpRequest {
+                        path: String::new(),
+                        ..request.clone()
+                    },
+                ),
+                (
+                    "path:oversized",
+                    HttpRequest {
+                        path: format!("{}/{oversized}", request.path.trim_end_matches('/')),
+                        ..request.clone()
+                    },
+                ),
+                (
+                    "query:malformed",
+                    HttpRequest {
+                        query: "%zz&=&%".to_string(),
+                        ..request.clone()
+                    },
+                ),
+                (
+                    "query:oversized",
+                    HttpRequest {
+                        query: format!("q={oversized}"),
+                        ..request.clone()
+                    },
+                ),
+            ];
+            if let Some(path) = truncated(&request.path) {
+                requests.push((
+                    "path:truncated",
+                    HttpRequest {
+                        path,
+                        ..request.clone()
+                    },
+                ));
+            }
+            if let Some(query) = truncated(&request.query) {
+                requests.push((
+                    "query:truncated",
+                    HttpRequest {
+                        query,
+                        ..request.clone()
+                    },
+                ));
+            }
+            for (mutation, request) in requests {
+                variants.push((
+                    mutation.to_string(),
+                    Fixture {
+                        request: Some(request),
+                        ..unchecked.clone()
+                    },
+                ));
+            }
+        }
+        Some(export) => {
+            let Some(function) = exports.iter().find(|function| function.name == export) else {
+                return Vec::new();
+            };
+            let args = Value::Object(fixture.args.clone());
+            let mut replacements = Vec::new();
+            mutate_value(String::new(), "", &args, &oversized, &mut replacements);
+            for (pointer, mutation, replacement) in replacements {
+                let mut mutated = args.clone();
+                if let Some(slot) = mutated.pointer_mut(&pointer) {
+                    *slot = replacement;
+                }
+                let Value::Object(mutated) = mutated else {
+                    continue;
+                };
+                if function.accepts(&mutated) {
+                    variants.push((
+                        mutation,
+                        Fixture {
+                            args: mutated,
+                            ..unchecked.clone()
+                        },
+                    ));
+                }
+            }
+        }
+    }
+    variants.truncate(MAX_MUTATIONS);
+    variants
+}
+
+/// Mutations of `value` and the values nested in it, as (JSON pointer,
+/// label, replacement); `label` names `value` in the labels.
+fn mutate_value(
+    pointer: String,
+    label: &str,
+    value: &Value,
+    oversized: &str,
+    out: &mut Vec<(String, String, Value)>,
+) {
+    let mut push = |what: String, replacement: Value| {
+        out.push((pointer.clone(), format!("{label}:{what}"), replacement));
+    };
+    match value {
+        Value::String(text) => {
+            if !text.is_empty() {
+                push("empty".to_string(), Value::from(""));
+            }
+            if let Some(text) = truncated(text) {
+                push("truncated".to_string(), Value::from(text));
+            }
+            push(
+                "oversized".to_string(),
+                Value::from(format!("{text}{oversized}")),
+            );
+        }
+        Value::Array(items) => {
+            if !items.is_empty() {
+                push("empty".to_string(), Value::Array(Vec::new()));
+            }
+            let key = |index: usize| match items[index].get(0) {
+                Some(Value::String(name)) => name.clone(),
+                _ => index.to_string(),
+            };
+            for index in 0..items.len() {
+                let mut rest = items.clone();
+                rest.remove(index);
+                push(format!("missing {}", key(index)), Value::Array(rest));
+                if key(index).eq_ignore_ascii_case("content-type")
+                    && items[index].get(1).is_some_and(Value::is_string)
+                {
+                    let mut wrong = items.clone();
+                    wrong[index][1] = Value::from("application/x-vtx-mutated");
+                    push("wrong content-type".to_string(), Value::Array(wrong));
+                }
+            }
+            for (index, item) in items.iter().enumerate() {
+                let label = format!("{label}[{}]", key(index));
+                mutate_value(format!("{pointer}/{index}"), &label, item, oversized, out);
+            }
+        }
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                let label = match label {
+                    "" => name.clone(),
+                    _ => format!("{label}.{name}"),
+                };
+                let pointer = format!("{pointer}/{}", name.replace('~', "~0").replace('/', "~1"));
+                mutate_value(pointer, &label, field, oversized, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The first half of `text`, if it has at least two characters.
+fn truncated(text: &str) -> Option<String> {
+    let chars = text.chars().count();
+    (chars >= 2).then(|| text.chars().take(chars / 2).collect())
+}
+
+/// Differences between the expected and the actual response.
+fn compare(expected: &Expected, response: &HttpResponse) -> Vec<String> {
+    let mut failures = Vec::new();
+    if let Some(status) = expected.status {
+        if status != response.status {
+            failures.push(format!("expected status {status}, got {}", response.status));
+        }
+    }
+    let body = response
+        .body
+        .as_deref()
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    if let Some(expected_body) = &expected.body {
+        if *expected_body != body {
+            failures.push(format!("expected body {expected_body:?}, got {body:?}"));
+        }
+    }
+    if let Some(needle) = &expected.body_contains {
+        if !body.contains(needle.as_str()) {
+            failures.push(format!("expected body to contain {needle:?}, got {body:?}"));
+        }
+    }
+    failures
+}
+
+fn print_result(result: &FixtureResult) {
+    if result.flaky {
+        println!(
+            "  {} {} (flaky: passed on attempt {})",
+            "PASS".yellow().bold(),
+            result.name,
+            result.attempts
+        );
+        for failure in &result.flaky_failures {
+            println!("       first attempt: {failure}");
+        }
+    } else if result.passed {
+        println!("  {} {}", "PASS".green().bold(), result.name);
+    } else {
+        match result.attempts {
+            1 => println!("  {} {}", "FAIL".red().bold(), result.name),
+            n => println!("  {} {} ({n} attempts)", "FAIL".red().bold(), result.name),
+        }
+        for failure in &result.failures {
+            println!("       {failure}");
+        }
+        if let Some(output) = &result.output {
+            println!("       plugin output:");
+            for line in output.lines() {
+                println!("         {line}");
+            }
+        }
+    }
+}
+
+/// One entry of a TAP or JUnit report: the `cargo test` run or a fixture.
+struct Case<'a> {
+    name: String,
+    passed: bool,
+    /// Absent for `cargo test`, which is not timed.
+    duration_ms: Option<u64>,
+    failures: &'a [String],
+    output: Option<&'a str>,
+    /// Runs of a fixture that needed retries; 1 otherwise.
+    attempts: u32,
+    flaky: bool,
+}
+
+/// `cargo test` (when it ran) followed by the fixtures of `report`.
+fn cases<'a>(suite: &str, report: &'a TestReport) -> Vec<Case<'a>> {
+    let cargo = report.cargo_test.map(|passed| Case {
+        name: format!("{suite}: cargo test"),
+        passed,
+        duration_ms: None,
+        failures: &[],
+        output: None,
+        attempts: 1,
+        flaky: false,
+    });
+    let fixtures = report.fixtures.iter().map(|f| Case {
+        name: f.name.clone(),
+        passed: f.passed,
+        duration_ms: Some(f.duration_ms),
+        failures: if f.flaky {
+            &f.flaky_failures
+        } else {
+            &f.failures
+        },
+        output: f.output.as_deref(),
+        attempts: f.attempts,
+        flaky: f.flaky,
+    });
+    cargo.into_iter().chain(fixtures).collect()
+}
+
+/// Print the results as a TAP version 13 stream. `qualified` prefixes each
+/// fixture with its suite, for workspaces.
+fn print_tap(suites: &[(&str, &TestReport)], qualified: bool) {
+    let cases: Vec<(&str, Case)> = suites
+        .iter()
+        .flat_map(|(suite, report)| cases(suite, report).into_iter().map(|c| (*suite, c)))
+        .collect();
+    println!("TAP version 13");
+    println!("1..{}", cases.len());
+    for (n, (suite, case)) in cases.iter().enumerate() {
+        let name = match (qualified, case.duration_ms) {
+            (true, Some(_)) => format!("{suite}/{}", case.name),
+            _ => case.name.clone(),
+        };
+        let time = case
+            .duration_ms
+            .map_or_else(String::new, |ms| format!(" # time={ms}ms"));
+        let status = if case.passed { "ok" } else { "not ok" };
+        println!("{status} {} - {name}{time}", n + 1);
+        if (case.passed && !case.flaky) || (case.failures.is_empty() && case.output.is_none()) {
+            continue;
+        }
+        // YAML diagnostics; JSON strings are valid YAML scalars.
+        println!("  ---");
+        if case.flaky {
+            println!("  flaky: true");
+        }
+        if case.attempts > 1 {
+            println!("  attempts: {}", case.attempts);
+        }
+        if !case.failures.is_empty() {
+            println!("  failures:");
+            for failure in case.failures {
+                println!("    - {}", Value::from(failure.as_str()));
+            }
+        }
+        if let Some(output) = case.output {
+            println!("  output: |");
+            for line in output.lines() {
+                println!("    {line}");
+            }
+        }
+        println!("  ...");
+    }
+}
+
+/// Write the results as JUnit XML, one `<testsuite>` per suite.
+fn write_junit(path: &Path, suites: &[(&str, &TestReport)]) -> Result<()> {
+    let seconds = |ms: u64| format!("{:.3}", ms as f64 / 1000.0);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+    for (suite, report) in suites {
+        let cases = cases(suite, report);
+        let failed = cases.iter().filter(|case| !case.passed).count();
+        let total_ms = cases.iter().filter_map(|case| case.duration_ms).sum();
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failed}\" time=\"{}\">",
+            xml_escape(suite),
+            cases.len(),
+            seconds(total_ms)
+        );
+        for case in &cases {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{}\"",
+                xml_escape(&case.name),
+                xml_escape(suite),
+                seconds(case.duration_ms.unwrap_or(0))
+            );
+            if case.flaky {
+                // Surefire's rerun format: the case passed, the first attempt failed.
+                let _ = writeln!(
+                    xml,
+                    ">\n      <flakyFailure message=\"passed on attempt {}\">{}</flakyFailure>\n    </testcase>",
+                    case.attempts,
+                    xml_escape(&case.failures.join("\n"))
+                );
+                continue;
+            }
+            if case.passed {
+                xml.push_str("/>\n");
+                continue;
+            }
+            xml.push_str(">\n");
+            let message = case.failures.first().map_or("failed", String::as_str);
+            let _ = writeln!(
+                xml,
+                "      <failure message=\"{}\">{}</failure>",
+                xml_escape(message),
+                xml_escape(&case.failures.join("\n"))
+            );
+            if let Some(output) = case.output {
+                let _ = writeln!(xml, "      <system-out>{}</system-out>", xml_escape(output));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, xml).with_context(|| format!("Failed to write {}", path.display()))?;
+    info!("JUnit report written: {}", path.display());
+    Ok(())
+}
+
+/// `text` with the XML special characters escaped and characters XML 1.0
+/// cannot hold dropped.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
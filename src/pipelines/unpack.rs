@@ -0,0 +1,269 @@
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::container::{self, PayloadKey};
+
+/// Length of the Wasm preamble (magic + version/layer) preceding the sections.
+const WASM_PREAMBLE_LEN: usize = 8;
+
+/// How much of the component payload is structurally intact.
+struct PayloadScan {
+    /// Bytes covered by the preamble and complete top-level sections.
+    intact_len: usize,
+    sections: usize,
+    /// Why scanning stopped before the end of the payload, if it did.
+    damage: Option<String>,
+}
+
+/// Extract the metadata and component of a `.vtx` into `out_dir`.
+///
+/// Behavior:
+/// - Writes `<stem>.metadata.json` (v2 and later), `<stem>.capabilities.json`
+///   (v3 and v4), the bundled assets under `<stem>.assets/` (v4 only) and
+///   `<stem>.wasm`, decompressing and decrypting an encoded payload (key from
+///   `VTX_PAYLOAD_KEY`).
+/// - The payload is checked section by section; a damaged file fails unless
+///   `repair` is set.
+/// - With `repair`, intact parts are salvaged: the metadata and capabilities
+///   when their length and JSON are valid, and the payload up to the last
+///   complete section as `<stem>.partial.wasm`. Everything lost is reported.
+///   Asset sections are skipped, not salvaged.
+pub fn execute_unpack_pipeline(file: &str, out_dir: &str, repair: bool) -> Result<()> {
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let stem = Path::new(file)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("artifact");
+    let out_dir = Path::new(out_dir);
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    if let Ok(decoded) = container::decode_sections(&bytes) {
+        let encoding = decoded
+            .metadata
+            .and_then(|raw| container::payload_encoding(raw).ok())
+            .flatten();
+        let component = match &encoding {
+            Some(encoding) => Cow::Owned(
+                container::decode_payload(
+                    decoded.metadata.unwrap_or_default(),
+                    encoding,
+                    decoded.component,
+                    PayloadKey::load(None)?.as_ref(),
+                )
+                .with_context(|| {
+                    format!(
+                        "Failed to decode the {} payload of {file}; encoded payloads cannot be repaired",
+                        encoding.codecs()
+                    )
+                })?,
+            ),
+            None => Cow::Borrowed(decoded.component),
+        };
+        let scan = scan_payload(&component);
+        if scan.damage.is_none() {
+            if let Some(metadata) = decoded.metadata {
+                write(&out_dir.join(format!("{stem}.metadata.json")), metadata)?;
+            }
+            if let Some(capabilities) = decoded.capabilities {
+                write(
+                    &out_dir.join(format!("{stem}.capabilities.json")),
+                    capabilities,
+                )?;
+            }
+            if let Some(section) = container::verified_assets(&decoded)? {
+                let bundle = container::decode_assets(section)?;
+                let assets_dir = out_dir.join(format!("{stem}.assets"));
+                for record in &bundle.index.files {
+                    let path = assets_dir.join(&record.path);
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)
+                            .with_context(|| format!("Failed to create {}", parent.display()))?;
+                    }
+                    let contents = bundle
+                        .get(&record.path, container::IDENTITY)
+                        .unwrap_or_default();
+                    write(&path, contents)?;
+                }
+            }
+            write(&out_dir.join(format!("{stem}.wasm")), &component)?;
+            ok!(
+                "Unpacked {} (vtx v{}, {} sections)",
+                file,
+                decoded.version,
+                scan.sections
+            );
+            return Ok(());
+        }
+    }
+
+    if !repair {
+        anyhow::bail!("{file} is damaged; rerun with --repair to salvage the intact parts");
+    }
+    repair_container(&bytes, stem, out_dir)
+}
+
+/// Write the length-prefixed JSON section at `start` to `path` when it is
+/// intact, and return where it ends.
+fn salvage_section(bytes: &[u8], start: usize, name: &str, path: &Path) -> Result<usize> {
+    let Some(len_bytes) = bytes.get(start..start + 4) else {
+        anyhow::bail!(
+            "Header truncated before the {} length; payload unreachable",
+            name.to_lowercase()
+        );
+    };
+    let len = u32::from_le_bytes(len_bytes.try_into()?) as usize;
+    let section_start = start + 4;
+    let end = section_start.saturating_add(len);
+    let Some(section) = bytes.get(section_start..end) else {
+        warn!(
+            "{} lost: declared {} bytes, {} present; payload unreachable",
+            name,
+            len,
+            bytes.len() - section_start
+        );
+        anyhow::bail!("Payload not salvaged");
+    };
+    if serde_json::from_slice::<serde_json::Value>(section).is_ok() {
+        write(path, section)?;
+        ok!("{} intact ({} bytes)", name, len);
+    } else {
+        warn!("{} damaged: {} bytes are not valid JSON", name, len);
+    }
+    Ok(end)
+}
+
+/// Step over the length-prefixed section at `start` without salvaging it, and
+/// return where it ends.
+fn skip_section(bytes: &[u8], start: usize, name: &str) -> Result<usize> {
+    let Some(len_bytes) = bytes.get(start..start + 4) else {
+        anyhow::bail!(
+            "Header truncated before the {} length; payload unreachable",
+            name.to_lowercase()
+        );
+    };
+    let len = u32::from_le_bytes(len_bytes.try_into()?) as usize;
+    let end = (start + 4).saturating_add(len);
+    if end > bytes.len() {
+        warn!(
+            "{} lost: declared {} bytes, {} present; payload unreachable",
+            name,
+            len,
+            bytes.len() - (start + 4)
+        );
+        anyhow::bail!("Payload not salvaged");
+    }
+    warn!(
+        "{} section skipped ({} bytes); it is not salvaged",
+        name, len
+    );
+    Ok(end)
+}
+
+fn repair_container(bytes: &[u8], stem: &str, out_dir: &Path) -> Result<()> {
+    info!("Salvaging {} bytes", bytes.len());
+    if bytes.len() < 4 || bytes[0..3] != vtx_format::VTX_PREFIX {
+        anyhow::bail!("Not a .vtx container: missing VTX header; nothing can be salvaged");
+    }
+
+    let metadata_path = out_dir.join(format!("{stem}.metadata.json"));
+    let payload_start = match bytes[3] {
+        vtx_format::VTX_VERSION_V1 => 4,
+        vtx_format::VTX_VERSION_V2 => salvage_section(bytes, 4, "Metadata", &metadata_path)?,
+        container::VTX_VERSION_V3 => {
+            let meta_end = salvage_section(bytes, 4, "Metadata", &metadata_path)?;
+            let capabilities_path = out_dir.join(format!("{stem}.capabilities.json"));
+            salvage_section(bytes, meta_end, "Capabilities", &capabilities_path)?
+        }
+        container::VTX_VERSION_V4 => {
+            let meta_end = salvage_section(bytes, 4, "Metadata", &metadata_path)?;
+            let capabilities_path = out_dir.join(format!("{stem}.capabilities.json"));
+            let capabilities_end = match bytes.get(meta_end..meta_end + 4) {
+                Some([0, 0, 0, 0]) => meta_end + 4,
+                _ => salvage_section(bytes, meta_end, "Capabilities", &capabilities_path)?,
+            };
+            skip_section(bytes, capabilities_end, "Assets")?
+        }
+        other => anyhow::bail!("Unsupported .vtx version {other}; nothing salvaged"),
+    };
+
+    let payload = &bytes[payload_start..];
+    let scan = scan_payload(payload);
+    if scan.intact_len == 0 {
+        warn!("Payload lost: Wasm preamble missing");
+    } else {
+        write(
+            &out_dir.join(format!("{stem}.partial.wasm")),
+            &payload[..scan.intact_len],
+        )?;
+        info!(
+            "Payload: salvaged {} of {} bytes ({} complete sections)",
+            scan.intact_len,
+            payload.len(),
+            scan.sections
+        );
+    }
+    if let Some(damage) = &scan.damage {
+        warn!(
+            "Lost {} payload bytes: {}",
+            payload.len() - scan.intact_len,
+            damage
+        );
+    }
+    Ok(())
+}
+
+/// Walk the top-level sections of a Wasm binary without validating their contents.
+fn scan_payload(payload: &[u8]) -> PayloadScan {
+    let mut scan = PayloadScan {
+        intact_len: 0,
+        sections: 0,
+        damage: None,
+    };
+    if payload.len() < WASM_PREAMBLE_LEN || payload[0..4] != *b"\0asm" {
+        scan.damage = Some("missing or truncated Wasm preamble".to_string());
+        return scan;
+    }
+
+    let mut offset = WASM_PREAMBLE_LEN;
+    scan.intact_len = offset;
+    while offset < payload.len() {
+        let id = payload[offset];
+        let Some((size, leb_len)) = read_u32_leb(&payload[offset + 1..]) else {
+            scan.damage = Some(format!("section header at offset {offset} is truncated"));
+            return scan;
+        };
+        let end = offset + 1 + leb_len + size as usize;
+        if end > payload.len() {
+            scan.damage = Some(format!(
+                "section {id} at offset {offset} needs {size} bytes, {} present",
+                payload.len() - (offset + 1 + leb_len)
+            ));
+            return scan;
+        }
+        offset = end;
+        scan.intact_len = end;
+        scan.sections += 1;
+    }
+    scan
+}
+
+/// Decode an unsigned LEB128 u32; returns the value and its encoded length.
+fn read_u32_leb(bytes: &[u8]) -> Option<(u32, usize)> {
+    let mut value: u32 = 0;
+    for (i, byte) in bytes.iter().take(5).enumerate() {
+        value |= u32::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn write(path: &Path, bytes: &[u8]) -> Result<()> {
+    std::fs::write(path, bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+    info!("Wrote {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,445 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::*;
+use toml::Table;
+use tracing::{info, warn};
+
+use crate::checker::{self, GO_SDK_MODULE};
+use crate::config::{self, ProjectConfig};
+use crate::packager::BUNDLED_ADAPTER_VERSION;
+use crate::release::replace_toml_string;
+use crate::templates::{self, TemplateVars};
+use crate::toolchain;
+
+/// First line of the scaffold's src/config.rs; files without it are left alone.
+const CONFIG_MARKER: &str = "// Centralized plugin configuration.";
+
+/// One rewrite, as printed in the change summary.
+struct Change {
+    file: PathBuf,
+    summary: String,
+}
+
+/// Collects rewrites for one upgrade run; nothing is written until the end.
+#[derive(Default)]
+struct Upgrade {
+    /// New contents of every file with at least one change.
+    files: BTreeMap<PathBuf, String>,
+    changes: Vec<Change>,
+}
+
+impl Upgrade {
+    /// Current contents of `path`, including rewrites recorded so far.
+    fn content(&self, path: &Path) -> Result<String> {
+        match self.files.get(path) {
+            Some(content) => Ok(content.clone()),
+            None => config::read_text_file(path)
+                .with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Apply `edit` to `path`; warns when the text could not be rewritten.
+    fn edit(
+        &mut self,
+        path: &Path,
+        summary: String,
+        edit: impl FnOnce(&str) -> Option<String>,
+    ) -> Result<()> {
+        let content = self.content(path)?;
+        match edit(&content) {
+            Some(updated) => {
+                self.files.insert(path.to_path_buf(), updated);
+                self.changes.push(Change {
+                    file: path.to_path_buf(),
+                    summary,
+                });
+            }
+            None => warn!(
+                "Could not rewrite {} in {}; update it by hand",
+                summary,
+                path.display()
+            ),
+        }
+        Ok(())
+    }
+
+    /// Rewrite a requirement written as `from` to `to`, unless it already is `to`.
+    fn requirement(
+        &mut self,
+        path: &Path,
+        package: &str,
+        from: &str,
+        to: &str,
+        edit: impl FnOnce(&str) -> Option<String>,
+    ) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+        self.edit(path, format!("{package} \"{from}\" -> \"{to}\""), edit)
+    }
+}
+
+/// Move the project to the SDK version bundled with the CLI.
+///
+/// Rewrites the SDK requirement in the language manifest (Cargo.toml and the
+/// workspace root for `workspace = true`, package.json, pyproject.toml or
+/// requirements.txt, go.mod) and the `[sdk]` pins in vtx.toml. With
+/// `boilerplate`, constants missing from a scaffold-generated src/config.rs
+/// are appended. Edits are textual, so formatting and comments are kept.
+/// With `dry_run`, only the change summary is printed.
+pub fn execute_upgrade_pipeline(dry_run: bool, boilerplate: bool) -> Result<()> {
+    let config = config::load()?;
+    let project_dir = PathBuf::from(config.project.path.as_deref().unwrap_or("."));
+    let language = toolchain::canonical_language(&config.project.language);
+    let target = vtx_sdk::VERSION;
+    let mut upgrade = Upgrade::default();
+
+    step!("Upgrading {} to vtx-sdk {}", config.project.name, target);
+    match language.as_str() {
+        "rust" => upgrade_rust(&project_dir, target, &mut upgrade)?,
+        "ts" => upgrade_npm(&project_dir, target, &mut upgrade)?,
+        "python" => upgrade_python(&project_dir, target, &mut upgrade)?,
+        "go" => upgrade_go(&project_dir, target, &mut upgrade)?,
+        _ => info!("{} projects have no SDK dependency to upgrade", language),
+    }
+    let manifests_changed = !upgrade.changes.is_empty();
+    upgrade_sdk_pins(&config, target, &mut upgrade)?;
+    if boilerplate {
+        upgrade_boilerplate(&config, &project_dir, &language, &mut upgrade)?;
+    }
+
+    if upgrade.changes.is_empty() {
+        done!("Already up to date (vtx-sdk {})", target);
+        return Ok(());
+    }
+    for change in &upgrade.changes {
+        let file = change.file.strip_prefix(".").unwrap_or(&change.file);
+        println!(
+            "  {}: {}",
+            file.display().to_string().bold(),
+            change.summary
+        );
+    }
+    if dry_run {
+        done!(
+            "Dry run: {} change(s) in {} file(s) not written",
+            upgrade.changes.len(),
+            upgrade.files.len()
+        );
+        return Ok(());
+    }
+
+    for (path, content) in &upgrade.files {
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    ok!("Updated {} file(s)", upgrade.files.len());
+    if manifests_changed {
+        let refresh = match language.as_str() {
+            "rust" => "cargo update -p vtx-sdk",
+            "ts" => "npm install",
+            "python" => "your lock tool (e.g. uv lock or poetry lock)",
+            _ => "go mod tidy",
+        };
+        info!("Refresh the lock file with {}", refresh);
+    }
+    Ok(())
+}
+
+/// `vtx-sdk` in `[dependencies]` or `[dev-dependencies]`, following
+/// `workspace = true` to `[workspace.dependencies]` of the workspace root.
+fn upgrade_rust(project_dir: &Path, target: &str, upgrade: &mut Upgrade) -> Result<()> {
+    let mut path = project_dir.join("Cargo.toml");
+    if !path.is_file() {
+        return Ok(());
+    }
+    let table = read_toml(&path, "Cargo.toml")?;
+    let Some((mut section, mut entry)) = ["dependencies", "dev-dependencies"]
+        .into_iter()
+        .find_map(|section| Some((section, table.get(section)?.get("vtx-sdk")?.clone())))
+    else {
+        warn!("Cargo.toml does not depend on vtx-sdk");
+        return Ok(());
+    };
+
+    if entry.get("workspace").and_then(toml::Value::as_bool) == Some(true) {
+        let root = checker::find_workspace_root(project_dir).with_context(|| {
+            "vtx-sdk uses workspace = true, but no [workspace] Cargo.toml was found above the project"
+        })?;
+        path = root.join("Cargo.toml");
+        section = "workspace.dependencies";
+        entry = read_toml(&path, "Cargo.toml")?
+            .get("workspace")
+            .and_then(|w| w.get("dependencies"))
+            .and_then(|d| d.get("vtx-sdk"))
+            .cloned()
+            .with_context(|| {
+                format!("{} has no [workspace.dependencies] vtx-sdk", path.display())
+            })?;
+    }
+    if entry.get("git").is_some() || entry.get("path").is_some() {
+        warn!("vtx-sdk is a git or path dependency; leaving it unchanged");
+        return Ok(());
+    }
+
+    let from = checker::dependency_version(&entry).unwrap_or_default();
+    let dotted = format!("{section}.vtx-sdk");
+    upgrade.requirement(&path, "vtx-sdk", &from, target, |content| {
+        replace_toml_string(content, section, "vtx-sdk", target)
+            .or_else(|| replace_toml_string(content, &dotted, "version", target))
+    })
+}
+
+/// `@vtx/sdk` in package.json, as a caret range.
+fn upgrade_npm(project_dir: &Path, target: &str, upgrade: &mut Upgrade) -> Result<()> {
+    const PACKAGE: &str = "@vtx/sdk";
+    let path = project_dir.join("package.json");
+    if !path.is_file() {
+        return Ok(());
+    }
+    let manifest: serde_json::Value =
+        serde_json::from_str(&upgrade.content(&path)?).context("Failed to parse package.json")?;
+    let Some(from) = ["dependencies", "devDependencies", "peerDependencies"]
+        .iter()
+        .find_map(|section| manifest.get(section)?.get(PACKAGE)?.as_str())
+    else {
+        warn!("package.json does not depend on {}", PACKAGE);
+        return Ok(());
+    };
+
+    let to = format!("^{target}");
+    upgrade.requirement(&path, PACKAGE, from, &to, |content| {
+        let key = content.find(&format!("\"{PACKAGE}\""))? + PACKAGE.len() + 2;
+        let colon = key + content[key..].find(':')?;
+        let start = colon + content[colon..].find('"')? + 1;
+        let end = start + content[start..].find('"')?;
+        Some(format!("{}{to}{}", &content[..start], &content[end..]))
+    })
+}
+
+/// `vtx-sdk` in pyproject.toml (`[project] dependencies`, then
+/// `[tool.poetry.dependencies]`) or requirements.txt.
+fn upgrade_python(project_dir: &Path, target: &str, upgrade: &mut Upgrade) -> Result<()> {
+    const PACKAGE: &str = "vtx-sdk";
+    let version = semver::Version::parse(target)?;
+    let pep440 = format!(">={target},<{}", caret_upper_bound(&version));
+
+    let pyproject = project_dir.join("pyproject.toml");
+    if pyproject.is_file() {
+        let table = read_toml(&pyproject, "pyproject.toml")?;
+        let pep621 = table
+            .get("project")
+            .and_then(|p| p.get("dependencies"))
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_str)
+            .find_map(|d| Some((d, checker::pep508_specifier(d, PACKAGE)?)));
+        if let Some((line, from)) = pep621 {
+            let updated = with_specifier(line, &from, &pep440);
+            return upgrade.requirement(&pyproject, PACKAGE, &from, &pep440, |content| {
+                ['"', '\''].into_iter().find_map(|quote| {
+                    let old = format!("{quote}{line}{quote}");
+                    content
+                        .contains(&old)
+                        .then(|| content.replacen(&old, &format!("{quote}{updated}{quote}"), 1))
+                })
+            });
+        }
+
+        let poetry = table
+            .get("tool")
+            .and_then(|t| t.get("poetry"))
+            .and_then(|p| p.get("dependencies"))
+            .and_then(toml::Value::as_table)
+            .and_then(|deps| {
+                deps.iter()
+                    .find(|(name, _)| checker::normalize_python_name(name) == PACKAGE)
+            });
+        if let Some((key, entry)) = poetry {
+            if entry.get("git").is_some() || entry.get("path").is_some() {
+                warn!("vtx-sdk is a git or path dependency; leaving it unchanged");
+                return Ok(());
+            }
+            let from = checker::dependency_version(entry).unwrap_or_default();
+            let to = format!("^{target}");
+            return upgrade.requirement(&pyproject, PACKAGE, &from, &to, |content| {
+                replace_toml_string(content, "tool.poetry.dependencies", key, &to)
+            });
+        }
+    }
+
+    let requirements = project_dir.join("requirements.txt");
+    if requirements.is_file() {
+        let content = upgrade.content(&requirements)?;
+        if let Some((line, from)) = content
+            .lines()
+            .find_map(|line| Some((line, checker::pep508_specifier(line, PACKAGE)?)))
+        {
+            let updated = with_specifier(line, &from, &pep440);
+            return upgrade.requirement(&requirements, PACKAGE, &from, &pep440, |content| {
+                Some(content.replacen(line, &updated, 1))
+            });
+        }
+    }
+    if pyproject.is_file() || requirements.is_file() {
+        warn!("No Python manifest depends on {}", PACKAGE);
+    }
+    Ok(())
+}
+
+/// `github.com/vtxdeo/vtx-sdk-go` in the `require` directives of go.mod.
+fn upgrade_go(project_dir: &Path, target: &str, upgrade: &mut Upgrade) -> Result<()> {
+    let path = project_dir.join("go.mod");
+    if !path.is_file() {
+        return Ok(());
+    }
+    let content = upgrade.content(&path)?;
+    let Some((line, from)) = content.lines().find_map(|line| {
+        let mut parts = line.split("//").next()?.split_whitespace();
+        let first = parts.next()?;
+        let module = if first == "require" {
+            parts.next()?
+        } else {
+            first
+        };
+        if module != GO_SDK_MODULE {
+            return None;
+        }
+        Some((line, parts.next()?.to_string()))
+    }) else {
+        warn!("go.mod does not require {}", GO_SDK_MODULE);
+        return Ok(());
+    };
+
+    let to = format!("v{target}");
+    let updated = line.replacen(
+        &format!("{GO_SDK_MODULE} {from}"),
+        &format!("{GO_SDK_MODULE} {to}"),
+        1,
+    );
+    upgrade.requirement(&path, GO_SDK_MODULE, &from, &to, |content| {
+        (updated != line).then(|| content.replacen(line, &updated, 1))
+    })
+}
+
+/// `[sdk] version` and `[sdk] adapter` in vtx.toml, when they no longer match the CLI.
+fn upgrade_sdk_pins(config: &ProjectConfig, target: &str, upgrade: &mut Upgrade) -> Result<()> {
+    let Some(sdk) = &config.sdk else {
+        return Ok(());
+    };
+    let path = Path::new("vtx.toml");
+    if let Some(version) = sdk.version.as_deref() {
+        upgrade.requirement(path, "sdk.version", version, target, |content| {
+            replace_toml_string(content, "sdk", "version", target)
+        })?;
+    }
+    if let Some(adapter) = sdk.adapter.as_deref() {
+        if adapter.trim_start_matches('=') != BUNDLED_ADAPTER_VERSION {
+            upgrade.requirement(
+                path,
+                "sdk.adapter",
+                adapter,
+                BUNDLED_ADAPTER_VERSION,
+                |content| replace_toml_string(content, "sdk", "adapter", BUNDLED_ADAPTER_VERSION),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Append constants of the current Rust template that a scaffold-generated
+/// src/config.rs does not define yet. Existing constants are never touched.
+fn upgrade_boilerplate(
+    config: &ProjectConfig,
+    project_dir: &Path,
+    language: &str,
+    upgrade: &mut Upgrade,
+) -> Result<()> {
+    let scaffold = templates::builtin(language)
+        .and_then(|template| template.files.iter().find(|f| f.path == "src/config.rs"));
+    let Some(scaffold) = scaffold else {
+        info!(
+            "No scaffold boilerplate to update for {} projects",
+            language
+        );
+        return Ok(());
+    };
+    let path = project_dir.join(scaffold.path);
+    if !path.is_file() {
+        info!("{} does not exist; skipping boilerplate", path.display());
+        return Ok(());
+    }
+    let current = upgrade.content(&path)?;
+    if !current.starts_with(CONFIG_MARKER) {
+        info!(
+            "{} was not generated by vtx init; leaving it unchanged",
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let name = &config.project.name;
+    let mut vars = TemplateVars::new();
+    vars.insert("name", name.to_string());
+    vars.insert("name_snake", name.replace('-', "_"));
+    let (rendered, _) = templates::render(scaffold.content, &vars);
+    let missing: Vec<(&str, &str)> = rendered
+        .lines()
+        .filter_map(|line| {
+            let constant = line.strip_prefix("pub const ")?.split(':').next()?.trim();
+            let defined = current.lines().any(|l| {
+                l.trim_start()
+                    .trim_start_matches("pub ")
+                    .strip_prefix("const ")
+                    .and_then(|rest| rest.split(':').next())
+                    .is_some_and(|c| c.trim() == constant)
+            });
+            (!defined).then_some((constant, line))
+        })
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let names: Vec<&str> = missing.iter().map(|(name, _)| *name).collect();
+    upgrade.edit(&path, format!("added {}", names.join(", ")), |content| {
+        let mut updated = content.trim_end().to_string();
+        updated.push_str("\n\n");
+        for (_, line) in &missing {
+            updated.push_str(line);
+            updated.push('\n');
+        }
+        Some(updated)
+    })
+}
+
+fn read_toml(path: &Path, name: &str) -> Result<Table> {
+    let content = config::read_text_file(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    config::parse_toml(&content, name)
+}
+
+/// Exclusive upper bound of a Cargo caret requirement: `1.2.3` -> `2.0`,
+/// `0.1.8` -> `0.2`, `0.0.3` -> `0.0.4`.
+fn caret_upper_bound(version: &semver::Version) -> String {
+    match (version.major, version.minor) {
+        (0, 0) => format!("0.0.{}", version.patch + 1),
+        (0, minor) => format!("0.{}", minor + 1),
+        (major, _) => format!("{}.0", major + 1),
+    }
+}
+
+/// A PEP 508 requirement line with its version specifier replaced; extras,
+/// markers and comments are kept.
+fn with_specifier(line: &str, from: &str, to: &str) -> String {
+    if !from.is_empty() {
+        return line.replacen(from, to, 1);
+    }
+    let end = line.find([';', '#']).unwrap_or(line.len());
+    let (requirement, rest) = line.split_at(end);
+    let separator = if rest.is_empty() { "" } else { " " };
+    format!("{}{to}{separator}{rest}", requirement.trim_end())
+}
@@ -0,0 +1,263 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::metadata::METADATA_SCHEMA;
+use crate::{output, packager};
+use vtx_cli::container::{self, PayloadEncoding, PayloadKey};
+use vtx_cli::contract;
+
+/// Metadata fields every schema v1 artifact written by the CLI carries.
+/// `adapter_version` is required as well when an `adapter` is recorded.
+const REQUIRED_METADATA_FIELDS: &[&str] = &["schema", "package", "language", "requires", "tool"];
+
+/// Outcome of one validation check.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// One line of the validation report.
+#[derive(Serialize, Debug)]
+struct Check {
+    check: &'static str,
+    status: Status,
+    detail: String,
+}
+
+impl Check {
+    fn new(check: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            check,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// JSON document printed by `vtx validate --json`.
+#[derive(Serialize)]
+struct ValidateReport<'a> {
+    file: &'a str,
+    strict: bool,
+    valid: bool,
+    checks: Vec<Check>,
+}
+
+/// Vet a `.vtx` container or raw `.wasm` file without a project checkout.
+///
+/// Checks:
+/// - `container`: the `.vtx` container decodes (raw wasm is accepted as is).
+/// - `component`: the payload is a well-formed WebAssembly component.
+/// - `contract`: the component implements the `vtx:api/plugin` world.
+/// - `metadata`: a `.vtx` embeds metadata that parses as a JSON object.
+///   Missing metadata, and any raw wasm, is a warning, or a failure with `strict`.
+/// - `metadata schema` (`strict` only): the metadata is schema v1 and carries
+///   every field the CLI writes.
+///
+/// Fails if any check failed.
+pub fn execute_validate_pipeline(
+    file: &str,
+    strict: bool,
+    json: bool,
+    key_file: Option<&Path>,
+) -> Result<()> {
+    let bytes = std::fs::read(file).with_context(|| format!("Failed to read {file}"))?;
+    let key = PayloadKey::load(key_file)?;
+    let mut checks = Vec::new();
+
+    let (component, metadata) = match container::decode_sections(&bytes) {
+        Ok(decoded) => {
+            checks.push(match container::verified_capabilities(&decoded) {
+                Ok(_) => Check::new(
+                    "container",
+                    Status::Pass,
+                    format!(".vtx v{}", decoded.version),
+                ),
+                Err(e) => Check::new("container", Status::Fail, e.to_string()),
+            });
+            // Malformed metadata is reported by the metadata check below.
+            let encoding = decoded
+                .metadata
+                .and_then(|raw| container::payload_encoding(raw).ok())
+                .flatten();
+            let component = match encoding {
+                Some(encoding) => {
+                    match container::decode_payload(
+                        decoded.metadata.unwrap_or_default(),
+                        &encoding,
+                        decoded.component,
+                        key.as_ref(),
+                    ) {
+                        Ok(component) => {
+                            checks.push(payload_check(&encoding, decoded.component.len()));
+                            Cow::Owned(component)
+                        }
+                        Err(e) => {
+                            checks.push(Check::new("payload", Status::Fail, format!("{e:#}")));
+                            return finish(file, strict, json, checks);
+                        }
+                    }
+                }
+                None => Cow::Borrowed(decoded.component),
+            };
+            (component, Some(decoded.metadata))
+        }
+        Err(_) if bytes.starts_with(b"\0asm") => {
+            checks.push(Check::new("container", Status::Pass, "raw wasm"));
+            (Cow::Borrowed(bytes.as_slice()), None)
+        }
+        Err(e) => {
+            checks.push(Check::new("container", Status::Fail, e.to_string()));
+            return finish(file, strict, json, checks);
+        }
+    };
+
+    let well_formed = component_check(&component);
+    let is_component = well_formed.status == Status::Pass;
+    checks.push(well_formed);
+    if is_component {
+        checks.push(match contract::check_plugin_world(&component) {
+            Ok(()) => Check::new("contract", Status::Pass, "implements vtx:api/plugin"),
+            Err(e) => Check::new("contract", Status::Fail, format!("{e:#}")),
+        });
+    }
+
+    let missing = if strict { Status::Fail } else { Status::Warn };
+    match metadata {
+        None => checks.push(Check::new(
+            "metadata",
+            missing,
+            "raw wasm carries no metadata",
+        )),
+        Some(None) => checks.push(Check::new("metadata", missing, "container has no metadata")),
+        Some(Some(raw)) => match serde_json::from_slice::<Value>(raw) {
+            Ok(Value::Object(fields)) => {
+                checks.push(Check::new(
+                    "metadata",
+                    Status::Pass,
+                    format!("{} fields", fields.len()),
+                ));
+                if strict {
+                    checks.push(schema_check(&fields));
+                }
+            }
+            Ok(_) => checks.push(Check::new(
+                "metadata",
+                Status::Fail,
+                "metadata is not a JSON object",
+            )),
+            Err(e) => checks.push(Check::new(
+                "metadata",
+                Status::Fail,
+                format!("malformed JSON: {e}"),
+            )),
+        },
+    }
+
+    finish(file, strict, json, checks)
+}
+
+fn payload_check(encoding: &PayloadEncoding, stored: usize) -> Check {
+    Check::new(
+        "payload",
+        Status::Pass,
+        format!("{}: {stored} -> {} bytes", encoding.codecs(), encoding.size),
+    )
+}
+
+fn component_check(payload: &[u8]) -> Check {
+    match packager::is_component(payload) {
+        Ok(true) => match wasmparser::Validator::new().validate_all(payload) {
+            Ok(_) => Check::new("component", Status::Pass, "well-formed component"),
+            Err(e) => Check::new("component", Status::Fail, format!("invalid component: {e}")),
+        },
+        Ok(false) => Check::new(
+            "component",
+            Status::Fail,
+            "core module, not a component; package it with `vtx package`",
+        ),
+        Err(e) => Check::new("component", Status::Fail, format!("malformed wasm: {e}")),
+    }
+}
+
+fn schema_check(fields: &serde_json::Map<String, Value>) -> Check {
+    let missing = |field: &str| fields.get(field).is_none_or(Value::is_null);
+    let mut absent: Vec<&str> = REQUIRED_METADATA_FIELDS
+        .iter()
+        .copied()
+        .filter(|field| missing(field))
+        .collect();
+    if !missing("adapter") && missing("adapter_version") {
+        absent.push("adapter_version");
+    }
+    let schema = fields.get("schema").and_then(Value::as_u64);
+    if schema.is_some_and(|s| s != u64::from(METADATA_SCHEMA)) {
+        return Check::new(
+            "metadata schema",
+            Status::Fail,
+            format!(
+                "schema {} (expected {METADATA_SCHEMA})",
+                schema.unwrap_or_default()
+            ),
+        );
+    }
+    if absent.is_empty() {
+        Check::new(
+            "metadata schema",
+            Status::Pass,
+            format!("schema v{METADATA_SCHEMA}"),
+        )
+    } else {
+        Check::new(
+            "metadata schema",
+            Status::Fail,
+            format!("missing {}", absent.join(", ")),
+        )
+    }
+}
+
+fn finish(file: &str, strict: bool, json: bool, checks: Vec<Check>) -> Result<()> {
+    let failures = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if json || output::is_json() {
+        output::document(
+            "validate",
+            &ValidateReport {
+                file,
+                strict,
+                valid: failures == 0,
+                checks,
+            },
+        )?;
+    } else {
+        step!(
+            "Validating {}{}",
+            file,
+            if strict { " (strict)" } else { "" }
+        );
+        println!("\n{:<6} {:<16} DETAIL", "STATUS", "CHECK");
+        for c in &checks {
+            let status = match c.status {
+                Status::Pass => "PASS".green().bold(),
+                Status::Warn => "WARN".yellow().bold(),
+                Status::Fail => "FAIL".red().bold(),
+            };
+            println!("{:<6} {:<16} {}", status, c.check, c.detail);
+        }
+        println!();
+    }
+
+    if failures > 0 {
+        anyhow::bail!("{file} failed {failures} validation check(s)");
+    }
+    if !json && !output::is_json() {
+        done!("{} is valid", file);
+    }
+    Ok(())
+}
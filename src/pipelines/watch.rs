@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use notify_debouncer_mini::notify::{self, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::scratch;
+
+/// Directories never watched for changes: build outputs, dependencies and VCS data.
+const IGNORED_DIRS: &[&str] = &[
+    ".git",
+    ".vtx-cache",
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    "__pycache__",
+];
+
+/// Files written by builds themselves.
+const IGNORED_EXTENSIONS: &[&str] = &["vtx", "wasm", "jsonl"];
+
+/// How often the loop wakes up to notice Ctrl-C while no events arrive.
+const CANCEL_POLL: Duration = Duration::from_millis(200);
+
+/// Rebuild whenever a file under the vtx.toml directory changes.
+///
+/// Behavior:
+/// - Subscribes to file system events other than reads; a burst of changes
+///   triggers one rebuild once files have been quiet for `debounce_ms`.
+/// - Each rebuild runs `vtx build` in a child process, so vtx.toml changes
+///   are picked up. A failed rebuild leaves the last `.vtx` in place.
+/// - Runs until interrupted with Ctrl-C.
+pub fn execute_watch_pipeline(
+    target: &str,
+    force: bool,
+    debug: bool,
+    debounce_ms: u64,
+) -> Result<()> {
+    if !Path::new("vtx.toml").exists() {
+        anyhow::bail!("Configuration file 'vtx.toml' not found in current directory.");
+    }
+    step!("Watching for changes (Ctrl-C to stop)");
+    watch_loop(debounce_ms, || rebuild(target, force, debug))
+}
+
+/// Run `on_change` once, then again after every settled burst of changes
+/// under the current directory, until interrupted.
+pub(super) fn watch_loop(
+    debounce_ms: u64,
+    mut on_change: impl FnMut() -> Result<()>,
+) -> Result<()> {
+    let cancel = scratch::interrupt_token();
+    let root = std::env::current_dir()
+        .and_then(|dir| dir.canonicalize())
+        .context("Failed to resolve the current directory")?;
+    let (events, received) = mpsc::channel();
+    // Builds read vtx.toml and the sources; inotify reports those opens,
+    // which would trigger a rebuild after every rebuild.
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if !matches!(&event, Ok(e) if matches!(e.kind, EventKind::Access(_))) {
+            let _ = events.send(event);
+        }
+    })
+    .context("Failed to start the file watcher")?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {}", root.display()))?;
+    let quiet = Duration::from_millis(debounce_ms.max(50));
+
+    on_change()?;
+
+    loop {
+        cancel.check()?;
+        let mut changed = BTreeSet::new();
+        let mut next = received.recv_timeout(CANCEL_POLL);
+        loop {
+            match next {
+                Ok(Ok(event)) => {
+                    changed.extend(event.paths.iter().filter_map(|path| watched(&root, path)))
+                }
+                Ok(Err(e)) => warn!("File watcher error: {e}"),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => anyhow::bail!("File watcher stopped"),
+            }
+            next = received.recv_timeout(quiet);
+        }
+        if changed.is_empty() {
+            continue;
+        }
+        for path in changed.iter().take(5) {
+            info!("Changed: {}", path.display());
+        }
+        on_change()?;
+    }
+}
+
+/// Run one `vtx build` and report its duration and result.
+fn rebuild(target: &str, force: bool, debug: bool) -> Result<()> {
+    let exe = std::env::current_exe().context("Failed to locate the vtx executable")?;
+    let mut build = Command::new(exe);
+    build.args(["build", "--target", target]);
+    if force {
+        build.arg("--force");
+    }
+    if debug {
+        build.arg("--debug");
+    }
+
+    let started = Instant::now();
+    let status = build.status().context("Failed to run vtx build")?;
+    let elapsed = started.elapsed().as_secs_f64();
+    if status.success() {
+        ok!("Rebuilt in {:.2}s; watching for changes", elapsed);
+    } else {
+        warn!(
+            "Rebuild failed after {:.2}s; keeping the last successful artifact",
+            elapsed
+        );
+    }
+    Ok(())
+}
+
+/// `path` relative to `root`, unless it lies in an ignored directory or is
+/// a build output.
+fn watched(root: &Path, path: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(root).ok()?;
+    let ignored_dir = relative.components().any(|c| match c {
+        Component::Normal(name) => IGNORED_DIRS.iter().any(|d| name == *d),
+        _ => false,
+    });
+    let ignored_file = relative
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IGNORED_EXTENSIONS.contains(&e));
+    (!ignored_dir && !ignored_file).then(|| relative.to_path_buf())
+}
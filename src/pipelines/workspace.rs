@@ -0,0 +1,230 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use super::jobs::{self, ChildReport};
+use crate::builder::create_builder;
+use crate::cli::WorkspaceArgs;
+use crate::{config, output, scratch};
+
+/// The members selected by `--workspace` and `--exclude`.
+///
+/// Shared by every command that accepts `--workspace`: members are
+/// discovered once, run in turn in this process or concurrently in child
+/// processes, and reported in one summary table.
+pub(super) struct Workspace {
+    /// Directory the command was started in; restored after every member.
+    root: PathBuf,
+    /// `project.path`, which member directories are relative to.
+    project_dir: PathBuf,
+    members: Vec<(String, PathBuf)>,
+    jobs: usize,
+}
+
+/// How one member fared, for the summary table and the `workspace` event.
+pub(super) struct Outcome {
+    pub passed: bool,
+    /// Rest of the summary row.
+    pub detail: String,
+    /// Fields of the member's entry in the `workspace` event.
+    pub fields: Value,
+}
+
+impl Workspace {
+    /// Discover the members of the project's Cargo or npm workspace.
+    ///
+    /// Fails when none are found, or when `--exclude` names a package that
+    /// is not a member.
+    pub fn discover(args: &WorkspaceArgs) -> Result<Self> {
+        let root = std::env::current_dir().context("Failed to resolve current directory")?;
+        let config = config::load_optional()?;
+        if let Some(cfg) = config.as_ref() {
+            config::enter_project_dir(cfg)?;
+        }
+        let language = config
+            .as_ref()
+            .map(|c| c.project.language.as_str())
+            .unwrap_or("rust");
+        let builder = create_builder(language, config.as_ref().and_then(|c| c.build.clone()))?;
+        let members = builder
+            .workspace_members()
+            .context("Failed to discover workspace members")?;
+        let project_dir = std::env::current_dir().context("Failed to resolve project directory")?;
+        std::env::set_current_dir(&root)
+            .with_context(|| format!("Failed to return to {}", root.display()))?;
+
+        if members.is_empty() {
+            anyhow::bail!("No workspace members found for language '{language}'");
+        }
+        for name in &args.exclude {
+            if !members.iter().any(|(member, _)| member == name) {
+                anyhow::bail!("--exclude {name}: no workspace member has that name");
+            }
+        }
+        let members: Vec<_> = members
+            .into_iter()
+            .filter(|(name, _)| !args.exclude.contains(name))
+            .collect();
+        if members.is_empty() {
+            anyhow::bail!("Every workspace member is excluded");
+        }
+
+        let jobs = args.jobs.map(|n| n as usize).unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        });
+        Ok(Self {
+            root,
+            project_dir,
+            members,
+            jobs,
+        })
+    }
+
+    pub fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.members.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Run `vtx <command>` for every member.
+    ///
+    /// With more than one job, each member runs as a child
+    /// `vtx --output json <command> --package <member> <args>` started from
+    /// the original directory, its log lines prefixed with `[member]`, and
+    /// `from_child` turns what the member reported into a result. Otherwise
+    /// members run in turn in this process (see [`Workspace::run_each`]).
+    pub fn run<T: Send>(
+        &self,
+        command: &str,
+        args: &[String],
+        in_process: impl FnMut(&str, &Path) -> Result<T>,
+        from_child: impl Fn(&str, ChildReport) -> Result<T> + Sync,
+    ) -> Result<Vec<(String, Result<T>)>> {
+        if self.jobs <= 1 {
+            return self.run_each(in_process);
+        }
+        let names = self.names();
+        info!(
+            "Running up to {} {}s in parallel",
+            self.jobs.min(names.len()),
+            command
+        );
+        let exe = std::env::current_exe().context("Failed to locate the vtx executable")?;
+        let results = jobs::run_parallel(&names, self.jobs, |member| {
+            let mut member_args = vec!["--package".to_string(), member.to_string()];
+            member_args.extend_from_slice(args);
+            let result = jobs::run_child(
+                &exe,
+                command,
+                &format!("[{member}]"),
+                Some(member),
+                &member_args,
+            )
+            .and_then(|child| from_child(member, child));
+            if let Err(e) = &result {
+                warn!("{}: {:#}", member, e);
+            }
+            result
+        })?;
+        Ok(names.into_iter().zip(results).collect())
+    }
+
+    /// Call `run` with each member's name and directory in turn.
+    ///
+    /// The current directory is restored after every member, and a failing
+    /// member does not stop the others. After Ctrl-C no new members start.
+    pub fn run_each<T>(
+        &self,
+        mut run: impl FnMut(&str, &Path) -> Result<T>,
+    ) -> Result<Vec<(String, Result<T>)>> {
+        let mut results = Vec::new();
+        for (member, dir) in &self.members {
+            scratch::interrupt_token().check()?;
+            let result = run(member, dir);
+            std::env::set_current_dir(&self.root)
+                .with_context(|| format!("Failed to return to {}", self.root.display()))?;
+            if let Err(e) = &result {
+                warn!("{}: {:#}", member, e);
+            }
+            results.push((member.clone(), result));
+        }
+        Ok(results)
+    }
+}
+
+/// Directory of the workspace member `package`, relative to `project.path`.
+pub(super) fn member_dir(config: &config::ProjectConfig, package: &str) -> Result<PathBuf> {
+    let root = std::env::current_dir().context("Failed to resolve current directory")?;
+    config::enter_project_dir(config)?;
+    let members = create_builder(&config.project.language, config.build.clone())
+        .and_then(|builder| builder.workspace_members())
+        .context("Failed to discover workspace members");
+    std::env::set_current_dir(&root)
+        .with_context(|| format!("Failed to return to {}", root.display()))?;
+    members?
+        .into_iter()
+        .find(|(name, _)| name == package)
+        .map(|(_, dir)| dir)
+        .with_context(|| format!("No workspace member named '{package}'"))
+}
+
+/// Print the summary table, emit the `workspace` event and return how many
+/// members failed.
+pub(super) fn summarize<T>(
+    command: &str,
+    results: &[(String, Result<T>)],
+    outcome: impl Fn(&T) -> Outcome,
+) -> usize {
+    let outcomes: Vec<_> = results
+        .iter()
+        .map(|(member, result)| {
+            let outcome = match result {
+                Ok(value) => outcome(value),
+                Err(e) => Outcome {
+                    passed: false,
+                    detail: format!("{e:#}"),
+                    fields: serde_json::json!({ "error": format!("{e:#}") }),
+                },
+            };
+            (member, outcome)
+        })
+        .collect();
+
+    info!("Workspace summary:");
+    let width = results.iter().map(|(m, _)| m.len()).max().unwrap_or(0);
+    for (member, outcome) in &outcomes {
+        let status = if outcome.passed {
+            "ok    ".green()
+        } else {
+            "failed".red()
+        };
+        println!("  {:<width$}  {}  {}", member, status, outcome.detail);
+    }
+
+    output::emit(
+        "workspace",
+        serde_json::json!({
+            "command": command,
+            "members": outcomes
+                .iter()
+                .map(|(member, outcome)| {
+                    let mut entry = serde_json::json!({
+                        "package": member,
+                        "status": if outcome.passed { "ok" } else { "failed" },
+                    });
+                    if let (Value::Object(entry), Value::Object(fields)) = (&mut entry, &outcome.fields) {
+                        entry.extend(fields.clone());
+                    }
+                    entry
+                })
+                .collect::<Vec<_>>(),
+        }),
+    );
+    outcomes.iter().filter(|(_, o)| !o.passed).count()
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Packaging stages reported to a [`ProgressSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Reading the input Wasm file.
+    Read,
+    /// Stripping bindgen metadata and scanning imports.
+    Clean,
+    /// Binaryen `wasm-opt` size optimization of the core module.
+    Optimize,
+    /// Adapter injection and component encoding.
+    Encode,
+    /// Linking dependency components into the plugin component.
+    Compose,
+    /// Contract, WASI policy and runtime validation.
+    Validate,
+}
+
+impl Stage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Read => "read",
+            Stage::Clean => "clean",
+            Stage::Optimize => "optimize",
+            Stage::Encode => "encode",
+            Stage::Compose => "compose",
+            Stage::Validate => "validate",
+        }
+    }
+}
+
+/// Receives progress from library operations instead of printing to stdout.
+///
+/// All methods default to no-ops so embedders only implement what they display.
+/// Debug messages are always emitted; sinks decide whether to show them.
+pub trait ProgressSink: Send + Sync {
+    fn stage_started(&self, _stage: Stage) {}
+    fn stage_finished(&self, _stage: Stage) {}
+    fn bytes_processed(&self, _stage: Stage, _bytes: u64) {}
+    fn info(&self, _message: &str) {}
+    fn warning(&self, _message: &str) {}
+    fn debug(&self, _message: &str) {}
+}
+
+/// Lets callers keep a handle to their sink after passing it to [`Progress::new`].
+impl<T: ProgressSink + ?Sized> ProgressSink for Arc<T> {
+    fn stage_started(&self, stage: Stage) {
+        (**self).stage_started(stage)
+    }
+    fn stage_finished(&self, stage: Stage) {
+        (**self).stage_finished(stage)
+    }
+    fn bytes_processed(&self, stage: Stage, bytes: u64) {
+        (**self).bytes_processed(stage, bytes)
+    }
+    fn info(&self, message: &str) {
+        (**self).info(message)
+    }
+    fn warning(&self, message: &str) {
+        (**self).warning(message)
+    }
+    fn debug(&self, message: &str) {
+        (**self).debug(message)
+    }
+}
+
+struct Silent;
+
+impl ProgressSink for Silent {}
+
+/// Cloneable handle to a [`ProgressSink`]. Defaults to a silent sink.
+#[derive(Clone)]
+pub struct Progress(Arc<dyn ProgressSink>);
+
+impl Progress {
+    pub fn new(sink: impl ProgressSink + 'static) -> Self {
+        Self(Arc::new(sink))
+    }
+}
+
+impl Deref for Progress {
+    type Target = dyn ProgressSink;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self::new(Silent)
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Progress")
+    }
+}
@@ -0,0 +1,296 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::{self, ProjectConfig};
+use crate::storage::{self, Storage};
+
+/// Environment variable overriding `registry.url`.
+pub const REGISTRY_URL_ENV: &str = "VTX_REGISTRY_URL";
+
+/// Environment variable holding the registry token; takes precedence over the credentials file.
+pub const REGISTRY_TOKEN_ENV: &str = "VTX_REGISTRY_TOKEN";
+
+/// Attempts per request before giving up.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubled for each further retry.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Size of the chunks `vtx publish` lists digests for.
+pub const CHUNK_SIZE: u64 = 4 << 20;
+
+/// Index entry stored for each published version.
+///
+/// Served at `GET {url}/v1/packages/{name}/{version}`; the artifact itself is
+/// at `.../{version}/artifact`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RegistryEntry {
+    pub name: String,
+    pub version: String,
+    /// Digest of the whole `.vtx` file, as `<algorithm>:<hex>`.
+    pub digest: String,
+    pub size: u64,
+    /// Metadata embedded in the artifact.
+    pub metadata: Option<Value>,
+    /// Size of the chunks listed in `chunks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_size: Option<u64>,
+    /// Digest of each `chunk_size` slice of the file in order, the last one
+    /// possibly shorter. Entries without it are downloaded in one piece.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<String>,
+}
+
+/// `<algorithm>:<hex>` digest of each `chunk_size` slice of `bytes`.
+pub fn chunk_digests(algorithm: &str, bytes: &[u8], chunk_size: u64) -> Result<Vec<String>> {
+    bytes
+        .chunks(chunk_size as usize)
+        .map(|chunk| crate::digest::digest(algorithm, chunk))
+        .collect()
+}
+
+/// `~/.vtx/credentials`
+#[derive(Deserialize, Default)]
+struct Credentials {
+    token: Option<String>,
+}
+
+/// Client for a VTX registry, over the [`Storage`] backend its URL selects.
+pub struct Registry {
+    base_url: String,
+    storage: Box<dyn Storage>,
+}
+
+impl Registry {
+    /// Registry from `VTX_REGISTRY_URL` or `registry.url`, with the token from
+    /// `VTX_REGISTRY_TOKEN` or `~/.vtx/credentials` when available. The URL
+    /// scheme picks the backend, see [`storage::open`].
+    pub fn from_config(config: Option<&ProjectConfig>) -> Result<Self> {
+        let base_url = std::env::var(REGISTRY_URL_ENV)
+            .ok()
+            .or_else(|| {
+                config
+                    .and_then(|c| c.registry.as_ref())
+                    .and_then(|r| r.url.clone())
+            })
+            .with_context(|| {
+                format!(
+                    "No registry configured: set registry.url in vtx.toml or {REGISTRY_URL_ENV}"
+                )
+            })?;
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let storage = storage::open(&base_url, load_token()?)?;
+        Ok(Self { base_url, storage })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Upload an artifact, then its index entry, so a version only becomes
+    /// visible once the artifact is complete.
+    pub fn publish(&self, entry: &RegistryEntry, artifact: &[u8]) -> Result<()> {
+        self.storage.put_artifact(entry, artifact)?;
+        self.storage.put_entry(entry)
+    }
+
+    /// Fetch the index entry of a published version.
+    pub fn entry(&self, name: &str, version: &str) -> Result<RegistryEntry> {
+        self.storage.get_entry(name, version)
+    }
+
+    /// Download the artifact of `entry` to `dest` and check its size and digest.
+    ///
+    /// Bytes are written to `<dest>.part` as they arrive, and a retry or a
+    /// later call fetches only what is missing, so an interrupted transfer
+    /// resumes where it stopped; see [`storage::fetch_ranges`]. The finished
+    /// file is checked before it is renamed to `dest`; a `.part` that fails
+    /// the check is deleted.
+    pub fn download(&self, entry: &RegistryEntry, dest: &Path) -> Result<()> {
+        let mut part_name = dest.as_os_str().to_owned();
+        part_name.push(".part");
+        let part = PathBuf::from(part_name);
+        self.storage.fetch_artifact(entry, &part)?;
+
+        let bytes =
+            std::fs::read(&part).with_context(|| format!("Failed to read {}", part.display()))?;
+        let problem = if bytes.len() as u64 != entry.size {
+            Some(format!(
+                "Downloaded {} bytes, but the registry lists {} for {}@{}",
+                bytes.len(),
+                entry.size,
+                entry.name,
+                entry.version
+            ))
+        } else if !crate::digest::digest_matches(&entry.digest, &bytes)? {
+            Some(format!(
+                "Checksum mismatch for {}@{}: expected {}",
+                entry.name, entry.version, entry.digest
+            ))
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            let _ = std::fs::remove_file(&part);
+            anyhow::bail!(problem);
+        }
+        std::fs::rename(&part, dest)
+            .with_context(|| format!("Failed to move {} to {}", part.display(), dest.display()))
+    }
+}
+
+/// Token from `VTX_REGISTRY_TOKEN` or the `token` key of `~/.vtx/credentials`.
+fn load_token() -> Result<Option<String>> {
+    if let Ok(token) = std::env::var(REGISTRY_TOKEN_ENV) {
+        return Ok(Some(token));
+    }
+    let Some(path) = credentials_path() else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = config::read_text_file(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let credentials: Credentials = config::parse_toml(&content, &path.display().to_string())?;
+    Ok(credentials.token)
+}
+
+fn credentials_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".vtx").join("credentials"))
+}
+
+/// Run `request` up to [`MAX_ATTEMPTS`] times with exponential backoff.
+///
+/// Connection errors, 429 and 5xx responses are retried; other HTTP errors fail at once.
+pub fn with_retries<T>(
+    action: &str,
+    mut request: impl FnMut() -> Result<T, ureq::Error>,
+) -> Result<T> {
+    let mut attempt = 1;
+    loop {
+        let error = match request() {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        let retryable = match &error {
+            ureq::Error::StatusCode(status) => *status == 429 || *status >= 500,
+            _ => true,
+        };
+        if !retryable || attempt == MAX_ATTEMPTS {
+            anyhow::bail!("Failed to {action} (attempt {attempt} of {MAX_ATTEMPTS}): {error}");
+        }
+        let delay = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+        warn!(
+            "Failed to {action}: {error}; retrying in {:.1}s",
+            delay.as_secs_f64()
+        );
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// Progress bar on stderr for a transfer of `total` bytes, which several
+/// threads may advance. Nothing is drawn when stderr is not a terminal.
+pub struct Progress {
+    label: &'static str,
+    total: u64,
+    done: AtomicU64,
+    draw: AtomicBool,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: u64) -> Self {
+        Self {
+            label,
+            total,
+            done: AtomicU64::new(0),
+            draw: AtomicBool::new(std::io::stderr().is_terminal()),
+        }
+    }
+
+    /// Count `done` bytes as already transferred, for a resumed download.
+    pub fn resumed_at(self, done: u64) -> Self {
+        self.done.store(done, Ordering::Relaxed);
+        self
+    }
+
+    /// Count `n` more bytes.
+    pub fn advance(&self, n: u64) {
+        self.done.fetch_add(n, Ordering::Relaxed);
+        if self.draw.load(Ordering::Relaxed) {
+            self.render(false);
+        }
+    }
+
+    /// Draw the final state and end the line.
+    pub fn finish(&self) {
+        if self.draw.swap(false, Ordering::Relaxed) {
+            self.render(true);
+        }
+    }
+
+    fn render(&self, finished: bool) {
+        const WIDTH: u64 = 30;
+        let done = self.done.load(Ordering::Relaxed);
+        let filled = (done * WIDTH).checked_div(self.total).unwrap_or(WIDTH);
+        let filled = filled.min(WIDTH) as usize;
+        let percent = (done * 100).checked_div(self.total).unwrap_or(100);
+        let mut stderr = std::io::stderr();
+        let _ = write!(
+            stderr,
+            "\r{:>8} [{}{}] {:>3}% {}/{} KB",
+            self.label,
+            "#".repeat(filled),
+            "-".repeat(WIDTH as usize - filled),
+            percent.min(100),
+            done / 1024,
+            self.total / 1024
+        );
+        if finished {
+            let _ = writeln!(stderr);
+        }
+        let _ = stderr.flush();
+    }
+}
+
+/// Draws a [`Progress`] bar while the wrapped reader is consumed.
+pub struct ProgressReader<R> {
+    inner: R,
+    progress: Progress,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub fn new(inner: R, label: &'static str, total: u64) -> Self {
+        Self {
+            inner,
+            progress: Progress::new(label, total),
+        }
+    }
+
+    /// Count `done` bytes as already transferred, for a resumed download.
+    pub fn resumed_at(mut self, done: u64) -> Self {
+        self.progress = self.progress.resumed_at(done);
+        self
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n == 0 {
+            self.progress.finish();
+        } else {
+            self.progress.advance(n as u64);
+        }
+        Ok(n)
+    }
+}
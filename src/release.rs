@@ -0,0 +1,224 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config;
+
+/// Release driver read by `vtx release`, generated by `vtx init --with-release-script`.
+pub const RELEASE_FILE: &str = "release.toml";
+
+/// Completed steps of an unfinished release.
+const RELEASE_STATE: &str = ".vtx-cache/release-state.json";
+
+/// Steps `vtx release` knows how to run.
+pub const RELEASE_STEPS: &[&str] = &["bump", "check", "test", "build", "sign", "sbom", "publish"];
+
+/// Steps that act on the artifact of the `build` step.
+pub const ARTIFACT_STEPS: &[&str] = &["sign", "sbom", "publish"];
+
+#[derive(Deserialize, Debug)]
+pub struct ReleaseFile {
+    pub release: ReleaseConfig,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReleaseConfig {
+    /// Steps to run, in order.
+    pub steps: Vec<String>,
+
+    /// Version component raised by the `bump` step: `major`, `minor` or `patch` (default).
+    pub bump: Option<String>,
+
+    /// Steps that ask for confirmation before running (skipped with `--yes`).
+    pub confirm: Option<Vec<String>>,
+
+    /// Build profile used by the `build` step, as with `vtx build --profile`.
+    pub profile: Option<String>,
+}
+
+impl ReleaseConfig {
+    pub fn requires_confirmation(&self, step: &str) -> bool {
+        self.confirm
+            .as_ref()
+            .is_some_and(|steps| steps.iter().any(|s| s == step))
+    }
+}
+
+/// Progress of an interrupted release, stored between `vtx release --resume` runs.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReleaseState {
+    /// Step list the state was recorded for; a changed list invalidates it.
+    pub steps: Vec<String>,
+    pub completed: Vec<String>,
+    /// Artifact written by the `build` step, for the steps after it.
+    #[serde(default)]
+    pub artifact: Option<PathBuf>,
+}
+
+impl ReleaseState {
+    pub fn new(steps: &[String]) -> Self {
+        Self {
+            steps: steps.to_vec(),
+            completed: Vec::new(),
+            artifact: None,
+        }
+    }
+
+    /// Load the saved state for `steps`.
+    pub fn load(steps: &[String]) -> Result<Self> {
+        let content = std::fs::read(RELEASE_STATE).with_context(|| {
+            format!("No interrupted release to resume ({RELEASE_STATE} not found)")
+        })?;
+        let state: Self = serde_json::from_slice(&content)
+            .with_context(|| format!("Failed to parse {RELEASE_STATE}"))?;
+        if state.steps != steps {
+            anyhow::bail!(
+                "release.toml steps changed since the interrupted release; run `vtx release` without --resume"
+            );
+        }
+        Ok(state)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = Path::new(RELEASE_STATE).parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(RELEASE_STATE, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {RELEASE_STATE}"))
+    }
+
+    pub fn clear() {
+        let _ = std::fs::remove_file(RELEASE_STATE);
+    }
+}
+
+/// Load and validate `release.toml` from the current directory.
+pub fn load() -> Result<ReleaseConfig> {
+    let path = Path::new(RELEASE_FILE);
+    if !path.exists() {
+        anyhow::bail!(
+            "Release file '{RELEASE_FILE}' not found in current directory.\nHint: Generate one with `vtx init --with-release-script` or write it by hand."
+        );
+    }
+    let content = config::read_text_file(path).context("Failed to read release.toml file")?;
+    let file: ReleaseFile = config::parse_toml(&content, RELEASE_FILE)?;
+
+    for step in &file.release.steps {
+        if !RELEASE_STEPS.contains(&step.as_str()) {
+            anyhow::bail!(
+                "Unknown release step '{step}' in {RELEASE_FILE} (expected one of: {})",
+                RELEASE_STEPS.join(", ")
+            );
+        }
+    }
+    // Signing rewrites the artifact, so it must come before the checksum and the upload.
+    let position = |step: &str| file.release.steps.iter().position(|s| s == step);
+    for step in ARTIFACT_STEPS {
+        let Some(at) = position(step) else {
+            continue;
+        };
+        if position("build").is_none_or(|build| build > at) {
+            anyhow::bail!("Release step '{step}' needs the artifact of a 'build' step before it");
+        }
+        if *step != "sign" && position("sign").is_some_and(|sign| sign > at) {
+            anyhow::bail!("Release step 'sign' must come before '{step}'");
+        }
+    }
+    if let Some(bump) = file.release.bump.as_deref() {
+        if !["major", "minor", "patch"].contains(&bump) {
+            anyhow::bail!(
+                "Unknown release.bump '{bump}' in {RELEASE_FILE} (expected major, minor or patch)"
+            );
+        }
+    }
+
+    for step in file.release.confirm.iter().flatten() {
+        if !file.release.steps.contains(step) {
+            anyhow::bail!("release.confirm lists '{step}', which is not in release.steps");
+        }
+    }
+
+    Ok(file.release)
+}
+
+/// Raise one component of a `MAJOR.MINOR.PATCH` version, resetting the lower ones.
+pub fn bump_version(version: &str, part: &str) -> Result<String> {
+    let parts: Vec<u64> = version
+        .split('.')
+        .map(|p| p.parse::<u64>())
+        .collect::<std::result::Result<_, _>>()
+        .ok()
+        .filter(|p: &Vec<u64>| p.len() == 3)
+        .with_context(|| format!("Cannot bump version '{version}': expected MAJOR.MINOR.PATCH"))?;
+
+    let (major, minor, patch) = (parts[0], parts[1], parts[2]);
+    Ok(match part {
+        "major" => format!("{}.0.0", major + 1),
+        "minor" => format!("{major}.{}.0", minor + 1),
+        _ => format!("{major}.{minor}.{}", patch + 1),
+    })
+}
+
+/// Replace `version = "..."` inside `[section]` of a TOML file, keeping the rest verbatim.
+///
+/// Returns `false` when the section has no version key.
+pub fn set_toml_version(path: &Path, section: &str, version: &str) -> Result<bool> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let Some(updated) = replace_toml_string(&content, section, "version", version) else {
+        return Ok(false);
+    };
+    std::fs::write(path, updated).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// Replace the quoted value of `key = ...` inside `[section]`, keeping the
+/// rest of `content` verbatim. For an inline table such as
+/// `key = { version = "0.1" }` the `version` field is replaced.
+///
+/// Returns `None` when the section has no such key with a quoted value.
+pub fn replace_toml_string(content: &str, section: &str, key: &str, value: &str) -> Option<String> {
+    let header = format!("[{section}]");
+    let mut in_section = false;
+    let mut replaced = false;
+
+    let lines: Vec<String> = content
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_section = trimmed == header;
+            } else if in_section && !replaced {
+                let name = trimmed.split('=').next().unwrap_or_default().trim();
+                if name.trim_matches('"') == key {
+                    let eq = line.find('=').unwrap_or_default();
+                    let offset = if line[eq + 1..].trim_start().starts_with('{') {
+                        match line[eq..].find("version") {
+                            Some(offset) => eq + offset,
+                            None => return line.to_string(),
+                        }
+                    } else {
+                        0
+                    };
+                    if let Some((start, end)) = quoted_value_span(&line[offset..]) {
+                        let (start, end) = (offset + start, offset + end);
+                        replaced = true;
+                        return format!("{}{value}{}", &line[..start], &line[end..]);
+                    }
+                }
+            }
+            line.to_string()
+        })
+        .collect();
+
+    replaced.then(|| lines.concat())
+}
+
+/// Byte range of the first double-quoted value after `=`, excluding the quotes.
+fn quoted_value_span(line: &str) -> Option<(usize, usize)> {
+    let eq = line.find('=')?;
+    let start = eq + line[eq..].find('"')? + 1;
+    let end = start + line[start..].find('"')?;
+    Some((start, end))
+}
@@ -0,0 +1,288 @@
+use anyhow::{Context, Result};
+use colored::*;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use tracing::info;
+use vtx_cli::cancel::CancelToken;
+
+use crate::config::BuildConfig;
+use crate::registry::{with_retries, ProgressReader};
+use crate::{offline, output, stages};
+
+/// Environment variable holding the build service token.
+pub const BUILD_TOKEN_ENV: &str = "VTX_BUILD_TOKEN";
+
+/// Ignore file read next to `.gitignore` for paths only the build service should skip.
+pub const VTXIGNORE_FILE: &str = ".vtxignore";
+
+/// Directories never uploaded, whatever the ignore files or `build.remote_include` say.
+const ALWAYS_SKIPPED: &[&str] = &[".git", ".vtx-cache"];
+
+/// Longest build id accepted from the build service.
+const MAX_BUILD_ID_LEN: usize = 128;
+
+/// Build job submitted with `POST {url}/v1/builds`.
+#[derive(Serialize, Debug)]
+pub struct RemoteBuildRequest<'a> {
+    pub package: &'a str,
+    pub language: &'a str,
+    pub target: &'a str,
+    pub release: bool,
+    /// `build.features`, as passed to the local toolchain.
+    pub features: Vec<String>,
+    /// `build.env`, with `--env` applied.
+    pub env: BTreeMap<String, String>,
+    pub extra_args: Vec<String>,
+    /// `build.cmd`, run by the service instead of its default toolchain invocation.
+    pub cmd: Option<String>,
+    /// Number of files in the source archive.
+    pub files: usize,
+}
+
+impl<'a> RemoteBuildRequest<'a> {
+    pub fn new(
+        package: &'a str,
+        language: &'a str,
+        target: &'a str,
+        release: bool,
+        build: Option<&BuildConfig>,
+    ) -> Self {
+        Self {
+            package,
+            language,
+            target,
+            release,
+            features: build.and_then(|b| b.features.clone()).unwrap_or_default(),
+            env: build.and_then(|b| b.env.clone()).unwrap_or_default(),
+            extra_args: build.and_then(|b| b.extra_args.clone()).unwrap_or_default(),
+            cmd: build.and_then(|b| b.cmd.clone()),
+            files: 0,
+        }
+    }
+}
+
+/// Answer to `POST {url}/v1/builds`.
+#[derive(Deserialize)]
+struct CreatedBuild {
+    id: String,
+}
+
+/// Served at `GET {url}/v1/builds/{id}` once the log stream has ended.
+#[derive(Deserialize, Debug)]
+struct BuildStatus {
+    /// `succeeded` or `failed`.
+    status: String,
+    /// Why the build failed.
+    error: Option<String>,
+    /// Digest of the wasm artifact, as `<algorithm>:<hex>`.
+    digest: Option<String>,
+}
+
+/// Client for a remote build service.
+///
+/// Protocol, relative to the service URL:
+/// 1. `POST /v1/builds` with a [`RemoteBuildRequest`] returns `{"id": ...}`.
+/// 2. `PUT /v1/builds/{id}/source` uploads the project as a gzipped tar.
+/// 3. `GET /v1/builds/{id}/logs` streams log lines until the build ends.
+/// 4. `GET /v1/builds/{id}` returns the [`BuildStatus`].
+/// 5. `GET /v1/builds/{id}/artifact` downloads the core wasm module.
+pub struct RemoteBuilder {
+    base_url: String,
+    token: Option<String>,
+}
+
+impl RemoteBuilder {
+    pub fn new(url: &str) -> Result<Self> {
+        offline::ensure_online(
+            "Remote builds",
+            "Drop --remote (and build.remote) to build with the local toolchain.",
+        )?;
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            anyhow::bail!("Remote build URL must start with http:// or https://, got '{url}'");
+        }
+        Ok(Self {
+            base_url: url.trim_end_matches('/').to_string(),
+            token: std::env::var(BUILD_TOKEN_ENV).ok(),
+        })
+    }
+
+    /// Upload the sources in `project_dir`, stream the build logs and return
+    /// the compiled wasm. Hidden files are only uploaded when `include` lists
+    /// them (see [`archive_source`]).
+    pub fn build(
+        &self,
+        request: &mut RemoteBuildRequest,
+        project_dir: &Path,
+        include: &[String],
+        cancel: &CancelToken,
+    ) -> Result<Vec<u8>> {
+        let (archive, files) = archive_source(project_dir, include)?;
+        request.files = files;
+        info!(
+            "Uploading {} files ({} KB) to {}",
+            files,
+            archive.len().div_ceil(1024),
+            self.base_url
+        );
+
+        let body = serde_json::to_vec(request)?;
+        let created = with_retries("create remote build", || {
+            self.authorized(ureq::post(&format!("{}/v1/builds", self.base_url)))
+                .header("Content-Type", "application/json")
+                .send(&body[..])?
+                .body_mut()
+                .read_to_string()
+        })?;
+        let created: CreatedBuild = serde_json::from_str(&created)
+            .context("Malformed response from the build service (expected {\"id\": ...})")?;
+        validate_build_id(&created.id)?;
+        let build_url = format!("{}/v1/builds/{}", self.base_url, created.id);
+        with_retries("upload source", || {
+            let reader = ProgressReader::new(
+                std::io::Cursor::new(archive.clone()),
+                "upload",
+                archive.len() as u64,
+            );
+            self.authorized(ureq::put(&format!("{build_url}/source")))
+                .header("Content-Type", "application/gzip")
+                .header("Content-Length", &archive.len().to_string())
+                .send(ureq::SendBody::from_owned_reader(reader))
+                .map(drop)
+        })?;
+        step!("Remote build {} started", created.id);
+
+        self.stream_logs(&build_url, cancel)?;
+
+        let status = with_retries("fetch remote build status", || {
+            self.get(&build_url)?.body_mut().read_to_string()
+        })?;
+        let status: BuildStatus = serde_json::from_str(&status)
+            .with_context(|| format!("Malformed build status from {build_url}"))?;
+        if status.status != "succeeded" {
+            anyhow::bail!(
+                "Remote build {} {}: {}",
+                created.id,
+                status.status,
+                status.error.as_deref().unwrap_or("no error reported")
+            );
+        }
+
+        let wasm = with_retries("download remote artifact", || {
+            let mut response = self.get(&format!("{build_url}/artifact"))?;
+            let total = response.body().content_length().unwrap_or(0);
+            let mut reader =
+                ProgressReader::new(response.body_mut().as_reader(), "download", total);
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            Ok(bytes)
+        })?;
+        if let Some(digest) = &status.digest {
+            if !crate::digest::digest_matches(digest, &wasm)? {
+                anyhow::bail!(
+                    "Checksum mismatch for the artifact of remote build {}: expected {digest}",
+                    created.id
+                );
+            }
+        }
+        Ok(wasm)
+    }
+
+    /// Print log lines as they arrive; JSON output turns each into a `remote_log` event.
+    fn stream_logs(&self, build_url: &str, cancel: &CancelToken) -> Result<()> {
+        let url = format!("{build_url}/logs");
+        let response = self
+            .get(&url)
+            .with_context(|| format!("Failed to stream logs from {url}"))?;
+        let reader = BufReader::new(response.into_body().into_reader());
+        for line in reader.lines() {
+            cancel.check()?;
+            let line = line.context("Remote log stream broke off")?;
+            if output::is_json() {
+                output::emit("remote_log", serde_json::json!({ "line": line }));
+            } else {
+                stages::suspend(|| println!("{} {}", "remote |".dimmed(), line));
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, url: &str) -> Result<ureq::http::Response<ureq::Body>, ureq::Error> {
+        self.authorized(ureq::get(url)).call()
+    }
+
+    fn authorized<B>(&self, request: ureq::RequestBuilder<B>) -> ureq::RequestBuilder<B> {
+        match &self.token {
+            Some(token) => request.header("Authorization", &format!("Bearer {token}")),
+            None => request,
+        }
+    }
+}
+
+/// Reject build ids that are not `[A-Za-z0-9_-]+`: the id goes into
+/// request URLs and log lines.
+fn validate_build_id(id: &str) -> Result<()> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_BUILD_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        anyhow::bail!(
+            "Build service returned an invalid build id {id:?} (expected 1 to {MAX_BUILD_ID_LEN} letters, digits, '-' or '_')"
+        );
+    }
+    Ok(())
+}
+
+/// Gzipped tar of the files under `dir`, honoring `.gitignore`, `.ignore`
+/// and `.vtxignore` (inside or outside a git work tree), and the number of files.
+///
+/// Hidden files and directories (`.env`, `.npmrc`, `.aws/`) are skipped
+/// unless their path relative to `dir`, or a directory containing them, is
+/// in `include`.
+pub fn archive_source(dir: &Path, include: &[String]) -> Result<(Vec<u8>, usize)> {
+    let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), flate2::Compression::default()));
+    let include: Vec<PathBuf> = include
+        .iter()
+        .map(|path| PathBuf::from(path.trim_start_matches("./")))
+        .collect();
+    let root = dir.to_path_buf();
+    let walker = ignore::WalkBuilder::new(dir)
+        .hidden(false)
+        .require_git(false)
+        .add_custom_ignore_filename(VTXIGNORE_FILE)
+        .filter_entry(move |entry| {
+            let Some(name) = entry.file_name().to_str() else {
+                return false;
+            };
+            if ALWAYS_SKIPPED.contains(&name) {
+                return false;
+            }
+            if !name.starts_with('.') || entry.depth() == 0 {
+                return true;
+            }
+            let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+            include.iter().any(|allowed| relative.starts_with(allowed))
+        })
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    let mut files = 0;
+    for entry in walker {
+        let entry = entry.context("Failed to list project files")?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        tar.append_path_with_name(entry.path(), relative)
+            .with_context(|| format!("Failed to archive {}", entry.path().display()))?;
+        files += 1;
+    }
+    let archive = tar
+        .into_inner()
+        .and_then(GzEncoder::finish)
+        .context("Failed to compress the source archive")?;
+    Ok((archive, files))
+}
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Config, Engine, Store};
+use wasmtime_wasi::{WasiCtx, WasiCtxView, WasiView};
+
+use crate::cancel::CancelToken;
+use crate::progress::{Progress, Stage};
+
+/// WASI packages provided by the stock wasmtime host (`wasmtime_wasi::p2`).
+const HOST_WASI_PACKAGES: &[&str] = &["io", "clocks", "random", "filesystem", "sockets", "cli"];
+
+/// Exports that must resolve to functions on the instantiated component.
+const REQUIRED_EXPORTS: &[&str] = &["handle", "get-manifest"];
+
+struct HostState {
+    ctx: WasiCtx,
+    table: ResourceTable,
+}
+
+impl WasiView for HostState {
+    fn ctx(&mut self) -> WasiCtxView<'_> {
+        WasiCtxView {
+            ctx: &mut self.ctx,
+            table: &mut self.table,
+        }
+    }
+}
+
+/// Whether an import can be satisfied (or deliberately stubbed) by the validation host.
+///
+/// `vtx:` interfaces are provided by the kernel at runtime and are stubbed with traps;
+/// everything else must be a WASI package wasmtime ships.
+fn is_known_import(name: &str) -> bool {
+    if name.starts_with("vtx:") {
+        return true;
+    }
+    name.strip_prefix("wasi:")
+        .and_then(|rest| rest.split('/').next())
+        .is_some_and(|package| HOST_WASI_PACKAGES.contains(&package))
+}
+
+/// Instantiate the component under plain wasmtime with only WASI linked.
+///
+/// Checks:
+/// 1. Every import is a `vtx:` kernel interface or a WASI package wasmtime provides.
+/// 2. The component compiles and instantiates (kernel imports trap if called).
+/// 3. Required exports resolve to functions on the live instance.
+pub fn deep_validate(
+    component_bytes: &[u8],
+    progress: &Progress,
+    cancel: &CancelToken,
+) -> Result<()> {
+    progress.stage_started(Stage::Validate);
+    let mut config = Config::new();
+    config.wasm_component_model(true);
+    let engine = Engine::new(&config)
+        .map_err(anyhow::Error::from)
+        .context("Failed to create wasmtime engine")?;
+
+    let component = Component::from_binary(&engine, component_bytes)
+        .map_err(anyhow::Error::from)
+        .context("Deep Validation Failed: wasmtime could not compile the component")?;
+
+    cancel.check()?;
+
+    let unknown: Vec<String> = component
+        .component_type()
+        .imports(&engine)
+        .map(|(name, _)| name.to_string())
+        .filter(|name| !is_known_import(name))
+        .collect();
+    if !unknown.is_empty() {
+        anyhow::bail!(
+            "Deep Validation Failed: Imports not provided by the host: {}\nHint: Plugins may only import vtx:api interfaces and standard WASI.",
+            unknown.join(", ")
+        );
+    }
+
+    let mut linker: Linker<HostState> = Linker::new(&engine);
+    wasmtime_wasi::p2::add_to_linker_sync(&mut linker)
+        .map_err(anyhow::Error::from)
+        .context("Failed to link WASI into the validation host")?;
+    linker
+        .define_unknown_imports_as_traps(&component)
+        .map_err(anyhow::Error::from)
+        .context("Failed to stub kernel imports")?;
+
+    cancel.check()?;
+    let state = HostState {
+        ctx: WasiCtx::builder().build(),
+        table: ResourceTable::new(),
+    };
+    let mut store = Store::new(&engine, state);
+    let instance = linker
+        .instantiate(&mut store, &component)
+        .map_err(anyhow::Error::from)
+        .context("Deep Validation Failed: Component did not instantiate under wasmtime")?;
+
+    for export in REQUIRED_EXPORTS {
+        if instance.get_func(&mut store, *export).is_none() {
+            anyhow::bail!(
+                "Deep Validation Failed: Export '{}' does not resolve to a function",
+                export
+            );
+        }
+    }
+
+    progress.debug("Deep validation: instantiated under wasmtime, exports linked");
+    progress.stage_finished(Stage::Validate);
+
+    Ok(())
+}
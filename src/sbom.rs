@@ -0,0 +1,396 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::warn;
+
+use crate::{ledger, offline, timeutil, toolchain};
+
+/// SBOM formats accepted by `--sbom` and `package.sbom`.
+pub const SBOM_FORMATS: &[&str] = &["cyclonedx", "spdx"];
+
+/// Format used by `--sbom` without a value and `package.sbom = true`.
+pub const DEFAULT_SBOM_FORMAT: &str = "cyclonedx";
+
+/// A third-party package the plugin was built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SbomComponent {
+    pub name: String,
+    pub version: String,
+    /// Package URL, e.g. `pkg:cargo/serde@1.0.200`.
+    pub purl: String,
+    /// SPDX license expression, when the lock data records one.
+    pub license: Option<String>,
+}
+
+/// The plugin an SBOM describes.
+pub struct SbomSubject<'a> {
+    pub name: &'a str,
+    pub version: Option<&'a str>,
+    pub language: &'a str,
+    /// Directory holding the language manifest and lock file.
+    pub project_dir: &'a Path,
+}
+
+/// Write `<artifact>.sha256` and the SBOM next to the `.vtx`.
+///
+/// The SBOM is `<artifact>.cdx.json` (CycloneDX 1.5) or `<artifact>.spdx.json`
+/// (SPDX 2.3). Dependencies come from `cargo metadata` for Rust, package-lock.json
+/// for TypeScript and poetry.lock or uv.lock for Python; other languages get
+/// an SBOM with only the plugin. Returns the checksum and SBOM paths.
+pub fn write(vtx_path: &Path, subject: &SbomSubject, format: &str) -> Result<(PathBuf, PathBuf)> {
+    let artifact = std::fs::read(vtx_path)
+        .with_context(|| format!("Failed to read {}", vtx_path.display()))?;
+    let hex = ledger::sha256_digest(&artifact)
+        .trim_start_matches("sha256:")
+        .to_string();
+    let file_name = vtx_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Artifact path has no file name")?;
+
+    let checksum_path = with_suffix(vtx_path, "sha256");
+    std::fs::write(&checksum_path, format!("{hex}  {file_name}\n"))
+        .with_context(|| format!("Failed to write {}", checksum_path.display()))?;
+
+    let components = collect_components(subject.language, subject.project_dir)?;
+    let (document, suffix) = match format {
+        "cyclonedx" => (cyclonedx(subject, &hex, &components), "cdx.json"),
+        "spdx" => (spdx(subject, file_name, &hex, &components), "spdx.json"),
+        other => anyhow::bail!(
+            "Unknown SBOM format '{other}' (expected one of: {})",
+            SBOM_FORMATS.join(", ")
+        ),
+    };
+    let sbom_path = with_suffix(vtx_path, suffix);
+    std::fs::write(&sbom_path, serde_json::to_vec_pretty(&document)?)
+        .with_context(|| format!("Failed to write {}", sbom_path.display()))?;
+    Ok((checksum_path, sbom_path))
+}
+
+/// The SBOM [`write`] left next to `vtx_path`, with its format, if any.
+pub fn find(vtx_path: &Path) -> Option<(PathBuf, &'static str)> {
+    [("cdx.json", "cyclonedx"), ("spdx.json", "spdx")]
+        .into_iter()
+        .map(|(suffix, format)| (with_suffix(vtx_path, suffix), format))
+        .find(|(path, _)| path.is_file())
+}
+
+/// Artifact digest recorded in `<artifact>.sha256`, as `sha256:<hex>`, if the
+/// file exists.
+pub fn recorded_digest(vtx_path: &Path) -> Result<Option<String>> {
+    let path = with_suffix(vtx_path, "sha256");
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let hex = content
+        .split_whitespace()
+        .next()
+        .with_context(|| format!("{} is empty", path.display()))?;
+    Ok(Some(format!("sha256:{hex}")))
+}
+
+/// Artifact digest an SBOM `document` in `format` records for the plugin it
+/// describes, as `sha256:<hex>`.
+pub fn subject_digest(document: &Value, format: &str) -> Option<String> {
+    let hex = match format {
+        "cyclonedx" => document["metadata"]["component"]["hashes"]
+            .as_array()?
+            .iter()
+            .find(|hash| hash["alg"] == "SHA-256")?["content"]
+            .as_str()?,
+        "spdx" => document["packages"]
+            .as_array()?
+            .iter()
+            .find(|package| package["SPDXID"] == "SPDXRef-Plugin")?["checksums"]
+            .as_array()?
+            .iter()
+            .find(|checksum| checksum["algorithm"] == "SHA256")?["checksumValue"]
+            .as_str()?,
+        _ => return None,
+    };
+    Some(format!("sha256:{hex}"))
+}
+
+/// `demo.vtx` + `sha256` gives `demo.vtx.sha256`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Third-party packages of the project, sorted by name and version.
+pub fn collect_components(language: &str, project_dir: &Path) -> Result<Vec<SbomComponent>> {
+    let mut components = match toolchain::canonical_language(language).as_str() {
+        "rust" => cargo_components(project_dir)?,
+        "ts" => npm_components(project_dir)?,
+        "python" => python_components(project_dir)?,
+        other => {
+            warn!("SBOM: no dependency source for language '{other}'; listing the plugin only");
+            Vec::new()
+        }
+    };
+    components.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    components.dedup();
+    Ok(components)
+}
+
+/// Packages resolved for wasm32-wasip1, excluding workspace members.
+fn cargo_components(project_dir: &Path) -> Result<Vec<SbomComponent>> {
+    let output = Command::new("cargo")
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--filter-platform",
+            "wasm32-wasip1",
+        ])
+        .args(offline::cargo_args())
+        .current_dir(project_dir)
+        .output()
+        .context("Failed to spawn cargo metadata")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).context("Malformed cargo metadata output")?;
+    let members: Vec<&str> = metadata["workspace_members"]
+        .as_array()
+        .map(|ids| ids.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    Ok(metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|p| !p["id"].as_str().is_some_and(|id| members.contains(&id)))
+        .filter_map(|p| {
+            let name = p["name"].as_str()?;
+            let version = p["version"].as_str()?;
+            Some(SbomComponent {
+                name: name.to_string(),
+                version: version.to_string(),
+                purl: format!("pkg:cargo/{name}@{version}"),
+                license: p["license"].as_str().map(str::to_string),
+            })
+        })
+        .collect())
+}
+
+/// Installed packages from package-lock.json (lockfile v2/v3 `packages`, or v1 `dependencies`).
+fn npm_components(project_dir: &Path) -> Result<Vec<SbomComponent>> {
+    let path = project_dir.join("package-lock.json");
+    let Some(lock) = read_optional(&path)? else {
+        warn!(
+            "SBOM: {} not found; run npm install to list dependencies",
+            path.display()
+        );
+        return Ok(Vec::new());
+    };
+    let lock: Value =
+        serde_json::from_str(&lock).with_context(|| format!("Malformed {}", path.display()))?;
+
+    let npm = |name: &str, entry: &Value| {
+        let version = entry["version"].as_str()?;
+        // Scoped names keep their `@`, percent-encoded as the purl spec requires.
+        let purl_name = name.replacen('@', "%40", usize::from(name.starts_with('@')));
+        Some(SbomComponent {
+            name: name.to_string(),
+            version: version.to_string(),
+            purl: format!("pkg:npm/{purl_name}@{version}"),
+            license: entry["license"].as_str().map(str::to_string),
+        })
+    };
+
+    if let Some(packages) = lock["packages"].as_object() {
+        return Ok(packages
+            .iter()
+            .filter(|(path, entry)| !path.is_empty() && !entry["link"].as_bool().unwrap_or(false))
+            .filter_map(|(path, entry)| {
+                let name = path.rsplit("node_modules/").next()?;
+                npm(name, entry)
+            })
+            .collect());
+    }
+
+    let mut components = Vec::new();
+    let mut pending = vec![&lock["dependencies"]];
+    while let Some(dependencies) = pending.pop() {
+        for (name, entry) in dependencies.as_object().into_iter().flatten() {
+            components.extend(npm(name, entry));
+            pending.push(&entry["dependencies"]);
+        }
+    }
+    Ok(components)
+}
+
+/// Locked packages from poetry.lock or uv.lock (`[[package]]` tables).
+fn python_components(project_dir: &Path) -> Result<Vec<SbomComponent>> {
+    for lock_file in ["poetry.lock", "uv.lock"] {
+        let path = project_dir.join(lock_file);
+        let Some(content) = read_optional(&path)? else {
+            continue;
+        };
+        let lock: toml::Table =
+            toml::from_str(&content).with_context(|| format!("Malformed {}", path.display()))?;
+        let project = project_dir
+            .join("pyproject.toml")
+            .exists()
+            .then(|| python_project_name(project_dir))
+            .flatten();
+        return Ok(lock
+            .get("package")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|p| {
+                let name = p.get("name")?.as_str()?;
+                let version = p.get("version")?.as_str()?;
+                // uv.lock lists the project itself as an editable package.
+                if project.as_deref() == Some(name) {
+                    return None;
+                }
+                Some(SbomComponent {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    purl: format!(
+                        "pkg:pypi/{}@{version}",
+                        name.to_lowercase().replace('_', "-")
+                    ),
+                    license: None,
+                })
+            })
+            .collect());
+    }
+    warn!(
+        "SBOM: no poetry.lock or uv.lock in {}; listing the plugin only",
+        project_dir.display()
+    );
+    Ok(Vec::new())
+}
+
+fn python_project_name(project_dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(project_dir.join("pyproject.toml")).ok()?;
+    let table: toml::Table = toml::from_str(&content).ok()?;
+    let name = table.get("project")?.get("name")?.as_str()?;
+    Some(name.to_string())
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Stable identifier for the document, derived from the artifact digest.
+fn document_id(hex: &str) -> String {
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn cyclonedx(subject: &SbomSubject, hex: &str, components: &[SbomComponent]) -> Value {
+    let component = |c: &SbomComponent| {
+        let mut value = json!({
+            "type": "library",
+            "bom-ref": c.purl,
+            "name": c.name,
+            "version": c.version,
+            "purl": c.purl,
+        });
+        if let Some(license) = &c.license {
+            value["licenses"] = json!([{ "expression": license }]);
+        }
+        value
+    };
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", document_id(hex)),
+        "version": 1,
+        "metadata": {
+            "timestamp": timeutil::now_rfc3339(),
+            "tools": [{ "vendor": "Vtxdeo", "name": "vtx-cli", "version": env!("CARGO_PKG_VERSION") }],
+            "component": {
+                "type": "application",
+                "bom-ref": subject.name,
+                "name": subject.name,
+                "version": subject.version.unwrap_or("0.0.0"),
+                "hashes": [{ "alg": "SHA-256", "content": hex }],
+                "properties": [{ "name": "vtx:language", "value": subject.language }],
+            },
+        },
+        "components": components.iter().map(component).collect::<Vec<_>>(),
+        "dependencies": [{
+            "ref": subject.name,
+            "dependsOn": components.iter().map(|c| c.purl.as_str()).collect::<Vec<_>>(),
+        }],
+    })
+}
+
+fn spdx(subject: &SbomSubject, file_name: &str, hex: &str, components: &[SbomComponent]) -> Value {
+    let spdx_id = |i: usize| format!("SPDXRef-Package-{i}");
+    let mut packages = vec![json!({
+        "SPDXID": "SPDXRef-Plugin",
+        "name": subject.name,
+        "versionInfo": subject.version.unwrap_or("0.0.0"),
+        "packageFileName": file_name,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": "NOASSERTION",
+        "checksums": [{ "algorithm": "SHA256", "checksumValue": hex }],
+        "primaryPackagePurpose": "APPLICATION",
+    })];
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": "SPDXRef-Plugin",
+    })];
+    for (i, c) in components.iter().enumerate() {
+        packages.push(json!({
+            "SPDXID": spdx_id(i + 1),
+            "name": c.name,
+            "versionInfo": c.version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": c.license.as_deref().unwrap_or("NOASSERTION"),
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": c.purl,
+            }],
+        }));
+        relationships.push(json!({
+            "spdxElementId": "SPDXRef-Plugin",
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": spdx_id(i + 1),
+        }));
+    }
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": file_name,
+        "documentNamespace": format!("https://spdx.org/spdxdocs/{}-{}", subject.name, document_id(hex)),
+        "creationInfo": {
+            "created": timeutil::now_rfc3339(),
+            "creators": [format!("Tool: vtx-cli-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tempfile::TempDir;
+use tracing::{debug, warn};
+use vtx_cli::cancel::CancelToken;
+
+/// Parent directory for per-build scratch directories.
+const SCRATCH_ROOT: &str = ".vtx-cache/tmp";
+
+/// Scratch directories that must be removed if the process is interrupted.
+static ACTIVE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Token cancelled by the first Ctrl-C.
+static INTERRUPT: OnceLock<CancelToken> = OnceLock::new();
+
+/// Token shared by every pipeline; cancelled when the user presses Ctrl-C.
+pub fn interrupt_token() -> CancelToken {
+    INTERRUPT.get_or_init(CancelToken::new).clone()
+}
+
+/// Per-build temporary directory for intermediate files.
+///
+/// Removed when dropped (success or error), and by the Ctrl-C handler installed
+/// with [`install_interrupt_handler`].
+pub struct ScratchDir {
+    dir: TempDir,
+}
+
+impl ScratchDir {
+    /// Create a fresh scratch directory under `.vtx-cache/tmp`.
+    pub fn create() -> Result<Self> {
+        std::fs::create_dir_all(SCRATCH_ROOT)
+            .with_context(|| format!("Failed to create {SCRATCH_ROOT}"))?;
+        let root = std::fs::canonicalize(SCRATCH_ROOT)
+            .with_context(|| format!("Failed to resolve {SCRATCH_ROOT}"))?;
+        let dir = tempfile::Builder::new()
+            .prefix("build-")
+            .tempdir_in(root)
+            .context("Failed to create scratch directory")?;
+
+        if let Ok(mut active) = ACTIVE.lock() {
+            active.push(dir.path().to_path_buf());
+        }
+        debug!("Scratch directory: {}", dir.path().display());
+
+        Ok(Self { dir })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Ok(mut active) = ACTIVE.lock() {
+            active.retain(|p| p != self.dir.path());
+        }
+    }
+}
+
+/// Handle Ctrl-C through [`interrupt_token`].
+///
+/// The first press cancels the token so the running pipeline stops at its next
+/// checkpoint and unwinds normally (dropping its scratch directory). A second
+/// press removes active scratch directories and exits immediately.
+pub fn install_interrupt_handler() {
+    let token = interrupt_token();
+    let result = ctrlc::set_handler(move || {
+        if !token.is_cancelled() {
+            token.cancel();
+            eprintln!(
+                "{} Interrupt received; stopping after the current step (Ctrl-C again to abort)",
+                "[WARN]".yellow()
+            );
+            return;
+        }
+        if let Ok(active) = ACTIVE.lock() {
+            for dir in active.iter() {
+                let _ = std::fs::remove_dir_all(dir);
+            }
+        }
+        eprintln!("{} Interrupted", "[ERROR]".red().bold());
+        std::process::exit(130);
+    });
+
+    if let Err(e) = result {
+        warn!("Failed to install Ctrl-C handler: {}", e);
+    }
+}
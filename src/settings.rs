@@ -0,0 +1,155 @@
+use anyhow::{Context, Result};
+use jsonschema::Registry;
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::config::{self, SettingsConfig};
+
+/// Load the settings JSON Schema and check that its defaults and examples conform.
+///
+/// Checked instances:
+/// - `default` and `examples` at the schema root;
+/// - `default` and `examples` of each property, against that property's schema;
+/// - the file named by `settings.defaults`, when set.
+///
+/// Every `$ref` resolves against the root document, so property schemas may
+/// reference shared `definitions`/`$defs`.
+pub fn load(settings: &SettingsConfig) -> Result<Value> {
+    let schema_path = Path::new(&settings.schema);
+    let schema = read_json(schema_path)?;
+
+    if schema.get("type").and_then(Value::as_str) != Some("object") {
+        anyhow::bail!(
+            "Settings schema {} must describe an object (\"type\": \"object\")",
+            schema_path.display()
+        );
+    }
+
+    let invalid = || format!("Invalid settings schema: {}", schema_path.display());
+    let root = Root::new(&schema).with_context(invalid)?;
+    let mut problems = Vec::new();
+    root.check_instances(&schema, "", "", &mut problems)
+        .with_context(invalid)?;
+
+    if let Some(defaults) = settings.defaults.as_deref() {
+        let instance = read_json(Path::new(defaults))?;
+        root.collect_errors("", &instance, defaults, &mut problems)
+            .with_context(invalid)?;
+    }
+
+    if !problems.is_empty() {
+        anyhow::bail!(
+            "Settings Validation Failed: values do not match {}.\n  {}",
+            schema_path.display(),
+            problems.join("\n  ")
+        );
+    }
+    Ok(schema)
+}
+
+fn read_json(path: &Path) -> Result<Value> {
+    let content = config::read_text_file(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// The settings schema registered as a resource, so validators built for
+/// any subschema resolve `$ref` against the whole document.
+struct Root {
+    registry: Registry<'static>,
+    base: String,
+}
+
+impl Root {
+    fn new(schema: &Value) -> Result<Self> {
+        let base = schema
+            .get("$id")
+            .and_then(Value::as_str)
+            .unwrap_or("urn:vtx:settings")
+            .trim_end_matches('#')
+            .to_string();
+        let registry = Registry::new()
+            .add(base.as_str(), schema.clone())
+            .and_then(|builder| builder.prepare())
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        Ok(Self { registry, base })
+    }
+
+    /// Validate `default`/`examples` of `schema`, found at `pointer` in the
+    /// root and describing the setting at `path`, then recurse into its
+    /// properties.
+    fn check_instances(
+        &self,
+        schema: &Value,
+        pointer: &str,
+        path: &str,
+        problems: &mut Vec<String>,
+    ) -> Result<()> {
+        let location = if path.is_empty() { "schema" } else { path };
+
+        if let Some(default) = schema.get("default") {
+            self.collect_errors(pointer, default, &format!("{location} default"), problems)?;
+        }
+        if let Some(examples) = schema.get("examples").and_then(Value::as_array) {
+            for (i, example) in examples.iter().enumerate() {
+                self.collect_errors(
+                    pointer,
+                    example,
+                    &format!("{location} examples[{i}]"),
+                    problems,
+                )?;
+            }
+        }
+
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, property) in properties {
+                let escaped = name.replace('~', "~0").replace('/', "~1");
+                self.check_instances(
+                    property,
+                    &format!("{pointer}/properties/{escaped}"),
+                    &format!("{path}/{name}"),
+                    problems,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate `instance` against the subschema at `pointer` in the root.
+    fn collect_errors(
+        &self,
+        pointer: &str,
+        instance: &Value,
+        label: &str,
+        problems: &mut Vec<String>,
+    ) -> Result<()> {
+        let reference = json!({ "$ref": format!("{}#{}", self.base, encode_fragment(pointer)) });
+        let validator = jsonschema::options()
+            .with_registry(&self.registry)
+            .build(&reference)
+            .map_err(|e| anyhow::anyhow!("{}: {e}", e.instance_path()))?;
+        for error in validator.iter_errors(instance) {
+            let path = error.instance_path().to_string();
+            let path = if path.is_empty() {
+                "/".to_string()
+            } else {
+                path
+            };
+            problems.push(format!("{label} at {path}: {error}"));
+        }
+        Ok(())
+    }
+}
+
+/// Percent-encode a JSON pointer for use as a URI fragment.
+fn encode_fragment(pointer: &str) -> String {
+    let mut out = String::with_capacity(pointer.len());
+    for byte in pointer.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~!$&'()*+,;=:@".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
@@ -0,0 +1,416 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::rand::SystemRandom;
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair, UnparsedPublicKey, VerificationAlgorithm,
+    ECDSA_P256_SHA256_FIXED, ECDSA_P256_SHA256_FIXED_SIGNING, ED25519,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::hex;
+
+/// Algorithm of the keys `vtx keygen` generates by default.
+pub const DEFAULT_SIGNATURE_ALGORITHM: SignatureAlgorithm = SignatureAlgorithm::Ed25519;
+
+/// Metadata key holding the embedded [`Signature`].
+pub const SIGNATURE_KEY: &str = "signature";
+
+/// Metadata key holding the embedded manifest [`Signature`].
+pub const MANIFEST_SIGNATURE_KEY: &str = "manifest_signature";
+
+/// Domain separator so a vtx signature cannot be replayed as any other signed message.
+const SIGNATURE_DOMAIN: &[u8] = b"vtx-signature-v1\0";
+
+/// Domain separator of manifest signatures, so they cannot pass for full signatures.
+const MANIFEST_SIGNATURE_DOMAIN: &[u8] = b"vtx-manifest-signature-v1\0";
+
+/// Algorithm of a signature, recorded by name in [`Signature::algorithm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SignatureAlgorithm {
+    /// Ed25519; public keys are 32 bytes.
+    Ed25519,
+    /// ECDSA over P-256 with SHA-256, as a fixed-length `r || s` signature;
+    /// public keys are 65-byte uncompressed SEC1 points.
+    EcdsaP256,
+}
+
+impl SignatureAlgorithm {
+    /// Every supported algorithm.
+    pub const ALL: [SignatureAlgorithm; 2] =
+        [SignatureAlgorithm::Ed25519, SignatureAlgorithm::EcdsaP256];
+
+    /// Name recorded in [`Signature::algorithm`] and key ids.
+    pub fn name(self) -> &'static str {
+        match self {
+            SignatureAlgorithm::Ed25519 => "ed25519",
+            SignatureAlgorithm::EcdsaP256 => "ecdsa-p256",
+        }
+    }
+
+    /// The algorithm of raw public key material, told apart by its length.
+    pub fn of_public_key(public_key: &[u8]) -> Result<Self> {
+        match (public_key.len(), public_key.first()) {
+            (32, _) => Ok(SignatureAlgorithm::Ed25519),
+            (65, Some(0x04)) => Ok(SignatureAlgorithm::EcdsaP256),
+            _ => anyhow::bail!(
+                "Public key must be 32 bytes of ed25519 key material or a 65-byte uncompressed P-256 point (got {} bytes)",
+                public_key.len()
+            ),
+        }
+    }
+
+    fn verification(self) -> &'static dyn VerificationAlgorithm {
+        match self {
+            SignatureAlgorithm::Ed25519 => &ED25519,
+            SignatureAlgorithm::EcdsaP256 => &ECDSA_P256_SHA256_FIXED,
+        }
+    }
+}
+
+impl FromStr for SignatureAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SignatureAlgorithm::ALL
+            .into_iter()
+            .find(|a| a.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown signature algorithm '{s}' (expected one of: {})",
+                    algorithm_names(&SignatureAlgorithm::ALL)
+                )
+            })
+    }
+}
+
+impl fmt::Display for SignatureAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn algorithm_names(algorithms: &[SignatureAlgorithm]) -> String {
+    algorithms
+        .iter()
+        .map(|a| a.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Signature algorithms verification accepts, from `package.signature_algorithms`.
+///
+/// The default accepts every supported algorithm. A signature in any other
+/// algorithm fails verification before its value is looked at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignaturePolicy {
+    allowed: Vec<SignatureAlgorithm>,
+}
+
+impl Default for SignaturePolicy {
+    fn default() -> Self {
+        Self {
+            allowed: SignatureAlgorithm::ALL.to_vec(),
+        }
+    }
+}
+
+impl SignaturePolicy {
+    /// A policy allowing only `names`, which must be known algorithms.
+    pub fn allowing(names: &[String]) -> Result<Self> {
+        if names.is_empty() {
+            anyhow::bail!("A signature policy must allow at least one algorithm");
+        }
+        let mut allowed = names
+            .iter()
+            .map(|name| name.parse().map_err(|e: String| anyhow::anyhow!(e)))
+            .collect::<Result<Vec<SignatureAlgorithm>>>()?;
+        allowed.sort();
+        allowed.dedup();
+        Ok(Self { allowed })
+    }
+
+    /// Whether signatures made with `algorithm` are accepted.
+    pub fn allows(&self, algorithm: SignatureAlgorithm) -> bool {
+        self.allowed.contains(&algorithm)
+    }
+
+    /// The accepted algorithm `signature` was made with.
+    fn check(&self, signature: &Signature) -> Result<SignatureAlgorithm> {
+        let algorithm: SignatureAlgorithm = signature.algorithm.parse().map_err(|_| {
+            anyhow::anyhow!("Unsupported signature algorithm '{}'", signature.algorithm)
+        })?;
+        if !self.allows(algorithm) {
+            anyhow::bail!(
+                "Signature algorithm {algorithm} is not allowed by the signature policy (allowed: {})",
+                algorithm_names(&self.allowed)
+            );
+        }
+        Ok(algorithm)
+    }
+}
+
+/// Signature embedded in the `.vtx` metadata.
+///
+/// The one under `signature` covers the metadata without either signature
+/// key, re-serialized by serde_json, and the component payload. The one under
+/// `manifest_signature` covers the same metadata alone, so name, version and
+/// capabilities can be trusted without hashing the payload.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// Name of the [`SignatureAlgorithm`]: `ed25519` or `ecdsa-p256`.
+    pub algorithm: String,
+
+    /// Identifies the signing key: `<algorithm>:<first 16 hex digits of SHA-256(public key)>`.
+    pub key_id: String,
+
+    /// Base64 signature bytes.
+    pub value: String,
+}
+
+/// A private key loaded from PKCS#8.
+enum SigningKey {
+    Ed25519(Ed25519KeyPair),
+    EcdsaP256(EcdsaKeyPair),
+}
+
+impl SigningKey {
+    /// Load a PKCS#8 key of any supported algorithm.
+    fn from_pkcs8(pkcs8: &[u8]) -> Result<Self> {
+        if let Ok(pair) = Ed25519KeyPair::from_pkcs8(pkcs8) {
+            return Ok(SigningKey::Ed25519(pair));
+        }
+        EcdsaKeyPair::from_pkcs8(
+            &ECDSA_P256_SHA256_FIXED_SIGNING,
+            pkcs8,
+            &SystemRandom::new(),
+        )
+        .map(SigningKey::EcdsaP256)
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "Signing key is not a PKCS#8 key for any of: {}",
+                algorithm_names(&SignatureAlgorithm::ALL)
+            )
+        })
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            SigningKey::Ed25519(_) => SignatureAlgorithm::Ed25519,
+            SigningKey::EcdsaP256(_) => SignatureAlgorithm::EcdsaP256,
+        }
+    }
+
+    fn public_key(&self) -> &[u8] {
+        match self {
+            SigningKey::Ed25519(pair) => pair.public_key().as_ref(),
+            SigningKey::EcdsaP256(pair) => pair.public_key().as_ref(),
+        }
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            SigningKey::Ed25519(pair) => Ok(pair.sign(message).as_ref().to_vec()),
+            SigningKey::EcdsaP256(pair) => pair
+                .sign(&SystemRandom::new(), message)
+                .map(|signature| signature.as_ref().to_vec())
+                .map_err(|_| anyhow::anyhow!("ECDSA signing failed")),
+        }
+    }
+}
+
+/// Generate a key pair for `algorithm`. Returns the PKCS#8 private key and the raw public key.
+pub fn generate_key(algorithm: SignatureAlgorithm) -> Result<(Vec<u8>, Vec<u8>)> {
+    let rng = SystemRandom::new();
+    let pkcs8 = match algorithm {
+        SignatureAlgorithm::Ed25519 => {
+            Ed25519KeyPair::generate_pkcs8(&rng).map(|doc| doc.as_ref().to_vec())
+        }
+        SignatureAlgorithm::EcdsaP256 => {
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+                .map(|doc| doc.as_ref().to_vec())
+        }
+    }
+    .map_err(|_| anyhow::anyhow!("Failed to generate an {algorithm} key"))?;
+    let key = SigningKey::from_pkcs8(&pkcs8).context("Generated key is not valid PKCS#8")?;
+    let public_key = key.public_key().to_vec();
+    Ok((pkcs8, public_key))
+}
+
+/// Key identifier recorded with signatures made by `public_key`.
+pub fn key_id(algorithm: SignatureAlgorithm, public_key: &[u8]) -> String {
+    let digest = Sha256::digest(public_key);
+    format!("{algorithm}:{}", hex::encode(&digest[..8]))
+}
+
+/// Decode a base64 public key, as written by `vtx keygen`.
+pub fn decode_public_key(encoded: &str) -> Result<Vec<u8>> {
+    let key = BASE64
+        .decode(encoded.trim())
+        .context("Public key is not valid base64")?;
+    SignatureAlgorithm::of_public_key(&key)?;
+    Ok(key)
+}
+
+/// Encode a public key as base64.
+pub fn encode_public_key(public_key: &[u8]) -> String {
+    BASE64.encode(public_key)
+}
+
+/// Sign `component` and `metadata_json` with a PKCS#8 key of any
+/// [`SignatureAlgorithm`]; the algorithm is recorded with the signatures.
+///
+/// Returns the metadata JSON with both the full and the manifest signature
+/// embedded; any previous signatures are replaced.
+pub fn sign(metadata_json: &[u8], component: &[u8], pkcs8: &[u8]) -> Result<Vec<u8>> {
+    let key = SigningKey::from_pkcs8(pkcs8)?;
+    let (mut metadata, message) = signed_message(metadata_json, component)?;
+    let manifest_message = signed_manifest(metadata_json)?;
+    let signature = |message: &[u8]| -> Result<Signature> {
+        Ok(Signature {
+            algorithm: key.algorithm().name().to_string(),
+            key_id: key_id(key.algorithm(), key.public_key()),
+            value: BASE64.encode(key.sign(message)?),
+        })
+    };
+    metadata.insert(
+        SIGNATURE_KEY.to_string(),
+        serde_json::to_value(signature(&message)?)?,
+    );
+    metadata.insert(
+        MANIFEST_SIGNATURE_KEY.to_string(),
+        serde_json::to_value(signature(&manifest_message)?)?,
+    );
+    Ok(serde_json::to_vec(&metadata)?)
+}
+
+/// The signature embedded in `metadata_json`, if any.
+pub fn embedded_signature(metadata_json: &[u8]) -> Result<Option<Signature>> {
+    embedded(metadata_json, SIGNATURE_KEY)
+}
+
+/// The manifest signature embedded in `metadata_json`, if any. Artifacts
+/// signed before manifest signatures existed only carry the full one.
+pub fn embedded_manifest_signature(metadata_json: &[u8]) -> Result<Option<Signature>> {
+    embedded(metadata_json, MANIFEST_SIGNATURE_KEY)
+}
+
+fn embedded(metadata_json: &[u8], key: &str) -> Result<Option<Signature>> {
+    let metadata: Value = serde_json::from_slice(metadata_json).context("Malformed metadata")?;
+    match metadata.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => Ok(Some(
+            serde_json::from_value(value.clone())
+                .with_context(|| format!("Malformed {key} in metadata"))?,
+        )),
+    }
+}
+
+/// Check the embedded signature against `public_key`.
+///
+/// Fails when the artifact is unsigned, was signed with an algorithm `policy`
+/// does not allow or by another key, or was modified after signing. Returns
+/// the verified signature.
+pub fn verify(
+    metadata_json: &[u8],
+    component: &[u8],
+    public_key: &[u8],
+    policy: &SignaturePolicy,
+) -> Result<Signature> {
+    let signature = embedded_signature(metadata_json)?
+        .context("Artifact is not signed (no signature in metadata)")?;
+    let (_, message) = signed_message(metadata_json, component)?;
+    if !matches(&signature, &message, public_key, policy)? {
+        anyhow::bail!("Signature does not match; the artifact was modified after signing");
+    }
+    Ok(signature)
+}
+
+/// Check the embedded manifest signature against `public_key`, without
+/// reading the component.
+///
+/// Fails like [`verify`] when the artifact has no manifest signature or its
+/// metadata was modified after signing. Returns the verified signature.
+pub fn verify_manifest(
+    metadata_json: &[u8],
+    public_key: &[u8],
+    policy: &SignaturePolicy,
+) -> Result<Signature> {
+    let signature = embedded_manifest_signature(metadata_json)?.context(
+        "Artifact has no manifest signature (no manifest_signature in metadata); sign it again",
+    )?;
+    let message = signed_manifest(metadata_json)?;
+    if !matches(&signature, &message, public_key, policy)? {
+        anyhow::bail!("Manifest signature does not match; the metadata was modified after signing");
+    }
+    Ok(signature)
+}
+
+/// Whether `signature` over `message` was made by `public_key`, checked with
+/// the algorithm the signature records. Fails when `policy` does not allow
+/// that algorithm, the key is of another algorithm or the signature names
+/// another key.
+fn matches(
+    signature: &Signature,
+    message: &[u8],
+    public_key: &[u8],
+    policy: &SignaturePolicy,
+) -> Result<bool> {
+    let algorithm = policy.check(signature)?;
+    let key_algorithm = SignatureAlgorithm::of_public_key(public_key)?;
+    if key_algorithm != algorithm {
+        anyhow::bail!(
+            "Artifact is signed with {algorithm}, but the given key is an {key_algorithm} key"
+        );
+    }
+    let expected_id = key_id(algorithm, public_key);
+    if signature.key_id != expected_id {
+        anyhow::bail!(
+            "Artifact was signed by {}, not by the given key ({expected_id})",
+            signature.key_id
+        );
+    }
+
+    let value = BASE64
+        .decode(&signature.value)
+        .context("Signature value is not valid base64")?;
+    Ok(UnparsedPublicKey::new(algorithm.verification(), public_key)
+        .verify(message, &value)
+        .is_ok())
+}
+
+/// Metadata object without its signatures, and the bytes a signature covers.
+fn signed_message(
+    metadata_json: &[u8],
+    component: &[u8],
+) -> Result<(serde_json::Map<String, Value>, Vec<u8>)> {
+    let (metadata, canonical) = unsigned_metadata(metadata_json)?;
+    let mut message = SIGNATURE_DOMAIN.to_vec();
+    message.extend_from_slice(&(canonical.len() as u64).to_le_bytes());
+    message.extend_from_slice(&canonical);
+    message.extend_from_slice(component);
+    Ok((metadata, message))
+}
+
+/// The bytes a manifest signature covers.
+fn signed_manifest(metadata_json: &[u8]) -> Result<Vec<u8>> {
+    let (_, canonical) = unsigned_metadata(metadata_json)?;
+    let mut message = MANIFEST_SIGNATURE_DOMAIN.to_vec();
+    message.extend_from_slice(&canonical);
+    Ok(message)
+}
+
+/// Metadata object without either signature key, and its canonical JSON.
+fn unsigned_metadata(metadata_json: &[u8]) -> Result<(serde_json::Map<String, Value>, Vec<u8>)> {
+    let mut metadata: serde_json::Map<String, Value> =
+        serde_json::from_slice(metadata_json).context("Metadata is not a JSON object")?;
+    metadata.remove(SIGNATURE_KEY);
+    metadata.remove(MANIFEST_SIGNATURE_KEY);
+    let canonical = serde_json::to_vec(&metadata)?;
+    Ok((metadata, canonical))
+}
@@ -0,0 +1,132 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, Level};
+
+use crate::output;
+
+/// Stages of the pipeline currently running, and the spinner showing them.
+struct Timeline {
+    /// Time per stage name, in order of first use; repeated stages add up.
+    finished: Vec<(String, Duration)>,
+    current: Option<(String, Instant)>,
+    spinner: Option<ProgressBar>,
+}
+
+static TIMELINE: Mutex<Timeline> = Mutex::new(Timeline {
+    finished: Vec::new(),
+    current: None,
+    spinner: None,
+});
+
+/// Whether stages are drawn as a spinner: human output at the default
+/// verbosity or above, with stderr attached to a terminal. Otherwise stages
+/// are only logged at debug level.
+fn interactive() -> bool {
+    !output::is_json() && tracing::enabled!(Level::INFO) && std::io::stderr().is_terminal()
+}
+
+/// Start timing a new pipeline, dropping the stages of a previous one.
+pub fn reset() {
+    clear();
+    if let Ok(mut timeline) = TIMELINE.lock() {
+        timeline.finished.clear();
+        timeline.current = None;
+    }
+}
+
+/// Enter `name`, ending the current stage.
+pub fn begin(name: &str) {
+    debug!("Stage: {}", name);
+    let Ok(mut timeline) = TIMELINE.lock() else {
+        return;
+    };
+    close_current(&mut timeline);
+    timeline.current = Some((name.to_string(), Instant::now()));
+    if !interactive() {
+        return;
+    }
+    let spinner = timeline.spinner.get_or_insert_with(|| {
+        let spinner = ProgressBar::new_spinner().with_style(
+            ProgressStyle::with_template("{spinner:.green} {msg} {elapsed:.dim}")
+                .expect("valid spinner template"),
+        );
+        spinner.enable_steady_tick(Duration::from_millis(100));
+        spinner
+    });
+    spinner.reset_elapsed();
+    spinner.set_message(name.to_string());
+}
+
+/// End `name` if it is the current stage.
+pub fn end(name: &str) {
+    if let Ok(mut timeline) = TIMELINE.lock() {
+        if timeline.current.as_ref().is_some_and(|(n, _)| n == name) {
+            close_current(&mut timeline);
+        }
+    }
+}
+
+fn close_current(timeline: &mut Timeline) {
+    let Some((name, started)) = timeline.current.take() else {
+        return;
+    };
+    let elapsed = started.elapsed();
+    match timeline.finished.iter_mut().find(|(n, _)| *n == name) {
+        Some((_, total)) => *total += elapsed,
+        None => timeline.finished.push((name, elapsed)),
+    }
+}
+
+/// Remove the spinner, e.g. before an error is printed.
+pub fn clear() {
+    let spinner = TIMELINE.lock().ok().and_then(|mut t| t.spinner.take());
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
+    }
+}
+
+/// Run `f`, which prints to the terminal, with the spinner hidden.
+pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+    let spinner = TIMELINE.lock().ok().and_then(|t| t.spinner.clone());
+    match spinner {
+        Some(spinner) => spinner.suspend(f),
+        None => f(),
+    }
+}
+
+/// End the pipeline: remove the spinner and print the time spent per stage.
+/// Prints nothing in JSON mode, where every stage is an event already, or with `-q`.
+pub fn summary() {
+    clear();
+    let finished = match TIMELINE.lock() {
+        Ok(mut timeline) => {
+            close_current(&mut timeline);
+            std::mem::take(&mut timeline.finished)
+        }
+        Err(_) => return,
+    };
+    if finished.is_empty() || output::is_json() || !tracing::enabled!(Level::INFO) {
+        return;
+    }
+    let total: Duration = finished.iter().map(|(_, d)| *d).sum();
+    println!("\n{:<12} {:>9}", "STAGE", "TIME");
+    for (name, duration) in &finished {
+        println!("{:<12} {:>8.2}s", name, duration.as_secs_f64());
+    }
+    println!("{:<12} {:>8.2}s\n", "total", total.as_secs_f64());
+}
+
+/// Stdout for terminal log lines; hides the spinner while a line is written.
+pub struct TerminalWriter;
+
+impl Write for TerminalWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        suspend(|| std::io::stdout().write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stdout().flush()
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use wasmparser::{DataKind, ExternalKind, Operator, Parser as WasmParser, Payload};
+
+/// Exports that toolchains use to run static constructors before the first call.
+const INITIALIZER_EXPORTS: &[&str] = &["_initialize", "__wasm_call_ctors", "_start"];
+
+/// Active data above this size is reported as heavy instantiation work.
+const ACTIVE_DATA_WARN_BYTES: u64 = 1024 * 1024;
+
+/// A single segment above this size is called out by itself.
+const SEGMENT_WARN_BYTES: u64 = 256 * 1024;
+
+/// Work a module does while it is being instantiated, before any export is called.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StartupReport {
+    /// Start functions, by export name when exported, otherwise `function #N`.
+    pub start_functions: Vec<String>,
+
+    /// Constructor/initializer exports (`_initialize`, `__wasm_call_ctors`, ...).
+    pub initializers: Vec<String>,
+
+    /// Bytes copied into linear memory by active data segments.
+    pub active_data_bytes: u64,
+
+    /// Size of the largest active data segment.
+    pub largest_segment_bytes: u64,
+
+    /// Passive segment bytes, copied later by `memory.init`.
+    pub passive_data_bytes: u64,
+
+    /// `memory.init` instructions in function bodies.
+    pub memory_init_count: u64,
+}
+
+impl StartupReport {
+    /// One-line summary for the packaging report.
+    pub fn summary(&self) -> String {
+        let start = if self.start_functions.is_empty() {
+            "none".to_string()
+        } else {
+            self.start_functions.join(", ")
+        };
+        let initializers = if self.initializers.is_empty() {
+            "none".to_string()
+        } else {
+            self.initializers.join(", ")
+        };
+        format!(
+            "start: {start}; initializers: {initializers}; active data: {} bytes; passive data: {} bytes ({} memory.init)",
+            self.active_data_bytes, self.passive_data_bytes, self.memory_init_count
+        )
+    }
+
+    /// Cold-start concerns worth a warning.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for start in &self.start_functions {
+            out.push(format!(
+                "Start function {start} runs on every instantiation, before the kernel can call the plugin. Move the work behind first use."
+            ));
+        }
+        if self.active_data_bytes > ACTIVE_DATA_WARN_BYTES {
+            out.push(format!(
+                "Active data segments copy {} KiB into memory on every instantiation. Embed large tables lazily (e.g. compress them or load on first use).",
+                self.active_data_bytes / 1024
+            ));
+        } else if self.largest_segment_bytes > SEGMENT_WARN_BYTES {
+            out.push(format!(
+                "A single active data segment is {} KiB and is copied on every instantiation.",
+                self.largest_segment_bytes / 1024
+            ));
+        }
+        out
+    }
+}
+
+/// Audit start functions, initializer exports and data segments of a module
+/// or of every core module inside a component.
+pub fn audit(bytes: &[u8]) -> Result<StartupReport> {
+    let mut report = StartupReport::default();
+    // Function exports of the module being parsed, reset at each module header.
+    let mut exported: HashMap<u32, String> = HashMap::new();
+
+    for payload in WasmParser::new(0).parse_all(bytes) {
+        match payload? {
+            Payload::Version { .. } => exported.clear(),
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    if export.kind != ExternalKind::Func {
+                        continue;
+                    }
+                    if INITIALIZER_EXPORTS.contains(&export.name) {
+                        report.initializers.push(export.name.to_string());
+                    }
+                    exported.insert(export.index, export.name.to_string());
+                }
+            }
+            Payload::StartSection { func, .. } => {
+                let name = exported
+                    .get(&func)
+                    .cloned()
+                    .unwrap_or_else(|| format!("function #{func}"));
+                report.start_functions.push(name);
+            }
+            Payload::DataSection(reader) => {
+                for data in reader {
+                    let data = data?;
+                    let len = data.data.len() as u64;
+                    match data.kind {
+                        DataKind::Active { .. } => {
+                            report.active_data_bytes += len;
+                            report.largest_segment_bytes = report.largest_segment_bytes.max(len);
+                        }
+                        DataKind::Passive => report.passive_data_bytes += len,
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut reader = body.get_operators_reader()?;
+                while !reader.eof() {
+                    if let Operator::MemoryInit { .. } = reader.read()? {
+                        report.memory_init_count += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use super::{entry_key, Storage};
+use crate::registry::{ProgressReader, RegistryEntry};
+
+/// A registry in a local directory or network share:
+/// `<dir>/v1/packages/<name>/<version>/index.json` holds the index entry and
+/// `.../<version>/artifact` the artifact.
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    /// Storage for `file://<dir>`; a relative `<dir>` is taken from the current directory.
+    pub fn new(url: &str) -> Result<Self> {
+        let path = url.strip_prefix("file://").unwrap_or(url);
+        if path.is_empty() {
+            anyhow::bail!("Registry URL '{url}' names no directory");
+        }
+        Ok(Self {
+            root: std::path::absolute(path)?,
+        })
+    }
+
+    fn entry_path(&self, name: &str, version: &str) -> Result<PathBuf> {
+        Ok(self.root.join(entry_key(name, version)?))
+    }
+}
+
+/// Write `bytes` to `path` through a temporary file, so readers never see
+/// half of it.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let temp = tempfile::NamedTempFile::new_in(dir)?;
+    std::fs::write(temp.path(), bytes)
+        .with_context(|| format!("Failed to write {}", temp.path().display()))?;
+    temp.persist(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+impl Storage for FileStorage {
+    fn put_artifact(&self, entry: &RegistryEntry, artifact: &[u8]) -> Result<()> {
+        let dir = self.entry_path(&entry.name, &entry.version)?;
+        write_atomic(&dir.join("artifact"), artifact)
+    }
+
+    fn put_entry(&self, entry: &RegistryEntry) -> Result<()> {
+        let dir = self.entry_path(&entry.name, &entry.version)?;
+        write_atomic(&dir.join("index.json"), &serde_json::to_vec(entry)?)
+    }
+
+    fn get_entry(&self, name: &str, version: &str) -> Result<RegistryEntry> {
+        let path = self.entry_path(name, version)?.join("index.json");
+        let content = std::fs::read(&path).with_context(|| {
+            format!(
+                "{name}@{version} is not published in {}",
+                self.root.display()
+            )
+        })?;
+        serde_json::from_slice(&content)
+            .with_context(|| format!("Malformed registry entry {}", path.display()))
+    }
+
+    fn fetch_artifact(&self, entry: &RegistryEntry, part: &Path) -> Result<()> {
+        let path = self
+            .entry_path(&entry.name, &entry.version)?
+            .join("artifact");
+        let mut source = std::fs::File::open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(part)
+            .with_context(|| format!("Failed to open {}", part.display()))?;
+        let mut offset = file.metadata()?.len();
+        if offset > entry.size {
+            file.set_len(0)?;
+            offset = 0;
+        }
+        source.seek(SeekFrom::Start(offset))?;
+        let mut reader = ProgressReader::new(source, "download", entry.size).resumed_at(offset);
+        std::io::copy(&mut reader, &mut file)
+            .with_context(|| format!("Failed to copy {}", path.display()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::{entry_key, fetch_ranges, Storage};
+use crate::registry::{with_retries, ProgressReader, RegistryEntry, REGISTRY_TOKEN_ENV};
+
+/// A VTX registry over HTTP.
+///
+/// `PUT {url}/v1/packages/{name}/{version}/artifact` and
+/// `PUT {url}/v1/packages/{name}/{version}` upload with
+/// `Authorization: Bearer <token>`; `GET` on the same paths reads them back.
+pub struct HttpStorage {
+    base_url: String,
+    token: Option<String>,
+}
+
+impl HttpStorage {
+    pub fn new(url: &str, token: Option<String>) -> Self {
+        Self {
+            base_url: url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    fn auth(&self) -> Result<String> {
+        let token = self.token.as_deref().with_context(|| {
+            format!(
+                "No registry token: set {REGISTRY_TOKEN_ENV} or add token to ~/.vtx/credentials"
+            )
+        })?;
+        Ok(format!("Bearer {token}"))
+    }
+
+    /// GET `url`, asking for the bytes from `offset` on when it is not zero.
+    fn get(
+        &self,
+        url: &str,
+        range: Option<&str>,
+    ) -> Result<ureq::http::Response<ureq::Body>, ureq::Error> {
+        let mut request = ureq::get(url);
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", &format!("Bearer {token}"));
+        }
+        if let Some(range) = range {
+            request = request.header("Range", range);
+        }
+        request.call()
+    }
+
+    fn url(&self, name: &str, version: &str) -> Result<String> {
+        Ok(format!("{}/{}", self.base_url, entry_key(name, version)?))
+    }
+}
+
+impl Storage for HttpStorage {
+    fn put_artifact(&self, entry: &RegistryEntry, artifact: &[u8]) -> Result<()> {
+        let auth = self.auth()?;
+        let url = format!("{}/artifact", self.url(&entry.name, &entry.version)?);
+        with_retries("upload artifact", || {
+            let reader = ProgressReader::new(
+                std::io::Cursor::new(artifact.to_vec()),
+                "upload",
+                artifact.len() as u64,
+            );
+            ureq::put(&url)
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/octet-stream")
+                .header("Content-Length", &artifact.len().to_string())
+                .header("X-Vtx-Digest", &entry.digest)
+                .send(ureq::SendBody::from_owned_reader(reader))
+                .map(drop)
+        })
+    }
+
+    fn put_entry(&self, entry: &RegistryEntry) -> Result<()> {
+        let auth = self.auth()?;
+        let url = self.url(&entry.name, &entry.version)?;
+        let index = serde_json::to_vec(entry)?;
+        with_retries("publish index entry", || {
+            ureq::put(&url)
+                .header("Authorization", &auth)
+                .header("Content-Type", "application/json")
+                .send(&index[..])
+                .map(drop)
+        })
+    }
+
+    fn get_entry(&self, name: &str, version: &str) -> Result<RegistryEntry> {
+        let url = self.url(name, version)?;
+        let body = with_retries("fetch index entry", || {
+            self.get(&url, None)?.body_mut().read_to_string()
+        })?;
+        serde_json::from_str(&body).with_context(|| format!("Malformed registry entry from {url}"))
+    }
+
+    fn fetch_artifact(&self, entry: &RegistryEntry, part: &Path) -> Result<()> {
+        let url = format!("{}/artifact", self.url(&entry.name, &entry.version)?);
+        fetch_ranges(entry, part, |range| self.get(&url, range))
+    }
+}
@@ -0,0 +1,305 @@
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::debug;
+
+use crate::offline;
+use crate::registry::{with_retries, Progress, ProgressReader, RegistryEntry};
+
+pub mod file;
+pub mod http;
+pub mod oci;
+pub mod s3;
+
+/// Where `vtx publish` stores artifacts and `vtx pull` finds them.
+///
+/// [`crate::registry::Registry`] orders the uploads and verifies downloads;
+/// a backend only moves bytes. The HTTP, `file://` and `s3://` backends
+/// store each version under its [`entry_key`].
+pub trait Storage {
+    /// Store the artifact of `entry`.
+    fn put_artifact(&self, entry: &RegistryEntry, artifact: &[u8]) -> Result<()>;
+
+    /// Store the index entry, after its artifact, making the version visible.
+    fn put_entry(&self, entry: &RegistryEntry) -> Result<()>;
+
+    /// Index entry of a published version.
+    fn get_entry(&self, name: &str, version: &str) -> Result<RegistryEntry>;
+
+    /// Append to `part` the bytes of the artifact of `entry` it does not
+    /// have yet. A backend that cannot resume empties `part` first.
+    fn fetch_artifact(&self, entry: &RegistryEntry, part: &Path) -> Result<()>;
+}
+
+/// Storage for `url`, by scheme:
+///
+/// - `http://`, `https://`: a VTX registry (see [`http::HttpStorage`]).
+/// - `file://<dir>`: a local directory (see [`file::FileStorage`]).
+/// - `s3://<bucket>[/<prefix>]`: an S3 bucket (see [`s3::S3Storage`]).
+/// - `oci://<host>/<namespace>`: an OCI registry (see [`oci::OciStorage`]).
+///
+/// `token` authorizes the HTTP and OCI backends.
+pub fn open(url: &str, token: Option<String>) -> Result<Box<dyn Storage>> {
+    let (scheme, _) = url
+        .split_once("://")
+        .with_context(|| format!("Registry URL '{url}' has no scheme"))?;
+    if scheme != "file" {
+        offline::ensure_online(
+            "The registry",
+            "Install plugins from local .vtx files or a file:// registry instead, or drop --offline.",
+        )?;
+    }
+    match scheme {
+        "http" | "https" => Ok(Box::new(http::HttpStorage::new(url, token))),
+        "file" => Ok(Box::new(file::FileStorage::new(url)?)),
+        "s3" => Ok(Box::new(s3::S3Storage::new(url)?)),
+        "oci" => Ok(Box::new(oci::OciStorage::new(url, token)?)),
+        unsupported => anyhow::bail!(
+            "Unsupported registry URL scheme '{unsupported}://': expected http, https, file, s3 or oci"
+        ),
+    }
+}
+
+/// Key of the index entry of `name@version`, relative to the registry root;
+/// the artifact is at `<key>/artifact`.
+pub fn entry_key(name: &str, version: &str) -> Result<String> {
+    for (what, value) in [("name", name), ("version", version)] {
+        let valid = !value.is_empty()
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._-+".contains(c));
+        if !valid {
+            anyhow::bail!("Invalid package {what} '{value}' for the registry");
+        }
+    }
+    Ok(format!("v1/packages/{name}/{version}"))
+}
+
+/// Chunks an entry with chunk digests downloads at the same time.
+const CHUNK_JOBS: usize = 4;
+
+/// Response to one artifact GET.
+type Fetched = Result<ureq::http::Response<ureq::Body>, ureq::Error>;
+
+/// [`Storage::fetch_artifact`] over HTTP: GET the bytes of the artifact of
+/// `entry` that `part` lacks with `get(range)`, which sends `range` as the
+/// `Range` header when given, and write them to `part`.
+///
+/// An entry with [`RegistryEntry::chunks`] is fetched chunk by chunk, up to
+/// [`CHUNK_JOBS`] at a time, with `Range: bytes=<start>-<end>`. Each chunk is
+/// checked against its digest before it is written at its offset, and
+/// chunks already in `part` that match their digest are kept, so a resumed
+/// pull fetches only the missing or damaged ones. Other entries, and
+/// registries that ignore range requests, are appended to `part` in one
+/// piece from where it ends.
+///
+/// Failed attempts are retried like [`with_retries`], each resuming where the
+/// previous one stopped.
+pub fn fetch_ranges(
+    entry: &RegistryEntry,
+    part: &Path,
+    get: impl Fn(Option<&str>) -> Fetched + Sync,
+) -> Result<()> {
+    match entry
+        .chunk_size
+        .filter(|&size| size > 0 && !entry.chunks.is_empty())
+    {
+        Some(chunk_size) => fetch_chunks(entry, chunk_size, part, &get),
+        None => fetch_appending(part, entry.size, &get),
+    }
+}
+
+/// Append the bytes `part` lacks, from where it ends. A `200` answer or a
+/// `206` for the wrong range starts the file over.
+fn fetch_appending(part: &Path, size: u64, get: &impl Fn(Option<&str>) -> Fetched) -> Result<()> {
+    with_retries("download artifact", || {
+        let mut file = OpenOptions::new().create(true).append(true).open(part)?;
+        let mut offset = file.metadata()?.len();
+        if offset > size {
+            file.set_len(0)?;
+            offset = 0;
+        }
+        if offset == size {
+            return Ok(());
+        }
+        let range = format!("bytes={offset}-");
+        let mut response = get((offset > 0).then_some(range.as_str()))?;
+        let resumed = response.status() == 206;
+        if resumed && content_range_start(&response) != Some(offset) {
+            file.set_len(0)?;
+            return Err(std::io::Error::other("registry answered with the wrong range").into());
+        }
+        if !resumed && offset > 0 {
+            debug!("Registry ignored the range request; downloading from the start");
+            file.set_len(0)?;
+            offset = 0;
+        } else if offset > 0 {
+            debug!("Resuming download at byte {offset}");
+        }
+        let mut reader = ProgressReader::new(response.body_mut().as_reader(), "download", size)
+            .resumed_at(offset);
+        std::io::copy(&mut reader, &mut file)?;
+        Ok(())
+    })
+}
+
+/// Why a chunk was not written.
+enum ChunkError {
+    /// The registry answered the range request with the whole artifact.
+    Unranged,
+    Failed(anyhow::Error),
+}
+
+/// Fetch the chunks of `entry` that `part` lacks, verifying each one.
+fn fetch_chunks(
+    entry: &RegistryEntry,
+    chunk_size: u64,
+    part: &Path,
+    get: &(impl Fn(Option<&str>) -> Fetched + Sync),
+) -> Result<()> {
+    let count = entry.size.div_ceil(chunk_size);
+    if entry.chunks.len() as u64 != count {
+        anyhow::bail!(
+            "The registry entry of {}@{} lists {} chunk digests for {count} chunks of {chunk_size} bytes",
+            entry.name,
+            entry.version,
+            entry.chunks.len()
+        );
+    }
+    let chunk = |index: usize| {
+        let start = index as u64 * chunk_size;
+        start..(start + chunk_size).min(entry.size)
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(part)
+        .with_context(|| format!("Failed to open {}", part.display()))?;
+    file.set_len(entry.size)?;
+    let mut missing = Vec::new();
+    let mut present = 0;
+    for (index, digest) in entry.chunks.iter().enumerate() {
+        let range = chunk(index);
+        let mut bytes = vec![0; (range.end - range.start) as usize];
+        file.seek(SeekFrom::Start(range.start))?;
+        file.read_exact(&mut bytes)?;
+        if crate::digest::digest_matches(digest, &bytes)? {
+            present += bytes.len() as u64;
+        } else {
+            missing.push(index);
+        }
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if present > 0 {
+        debug!(
+            "Resuming download: {} of {count} chunks to fetch",
+            missing.len()
+        );
+    }
+
+    let progress = Progress::new("download", entry.size).resumed_at(present);
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let error: Mutex<Option<ChunkError>> = Mutex::new(None);
+    std::thread::scope(|scope| {
+        for _ in 0..CHUNK_JOBS.min(missing.len()) {
+            scope.spawn(|| {
+                while !stop.load(Ordering::SeqCst) {
+                    let Some(&index) = missing.get(next.fetch_add(1, Ordering::SeqCst)) else {
+                        break;
+                    };
+                    let range = chunk(index);
+                    let action = format!("download chunk {} of {count}", index + 1);
+                    let failure = match fetch_chunk(&range, &entry.chunks[index], &action, get) {
+                        Ok(Some(bytes)) => write_at(part, range.start, &bytes)
+                            .map(|()| progress.advance(bytes.len() as u64))
+                            .err()
+                            .map(ChunkError::Failed),
+                        Ok(None) => Some(ChunkError::Unranged),
+                        Err(e) => Some(ChunkError::Failed(e)),
+                    };
+                    if let Some(failure) = failure {
+                        stop.store(true, Ordering::SeqCst);
+                        error
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .get_or_insert(failure);
+                    }
+                }
+            });
+        }
+    });
+    progress.finish();
+
+    match error.into_inner().unwrap_or_else(|e| e.into_inner()) {
+        None => Ok(()),
+        Some(ChunkError::Failed(e)) => Err(e),
+        Some(ChunkError::Unranged) => {
+            debug!("Registry ignored the range request; downloading in one piece");
+            file.set_len(0)?;
+            fetch_appending(part, entry.size, get)
+        }
+    }
+}
+
+/// The bytes of `range`, checked against `digest`, or `None` when the
+/// registry answers with the whole artifact instead.
+fn fetch_chunk(
+    range: &Range<u64>,
+    digest: &str,
+    action: &str,
+    get: &impl Fn(Option<&str>) -> Fetched,
+) -> Result<Option<Vec<u8>>> {
+    let header = format!("bytes={}-{}", range.start, range.end - 1);
+    with_retries(action, || {
+        let mut response = get(Some(&header))?;
+        if response.status() != 206 {
+            return Ok(None);
+        }
+        if content_range_start(&response) != Some(range.start) {
+            return Err(std::io::Error::other("registry answered with the wrong range").into());
+        }
+        let len = range.end - range.start;
+        let mut bytes = Vec::with_capacity(len as usize);
+        response
+            .body_mut()
+            .as_reader()
+            .take(len)
+            .read_to_end(&mut bytes)?;
+        let matches = bytes.len() as u64 == len
+            && crate::digest::digest_matches(digest, &bytes)
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+        if !matches {
+            return Err(std::io::Error::other(format!("chunk does not match {digest}")).into());
+        }
+        Ok(Some(bytes))
+    })
+}
+
+/// Write `bytes` into `part` at `offset`, through a handle of its own so
+/// chunks can be written from several threads.
+fn write_at(part: &Path, offset: u64, bytes: &[u8]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(part)
+        .with_context(|| format!("Failed to open {}", part.display()))?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(bytes)
+        .with_context(|| format!("Failed to write {}", part.display()))
+}
+
+/// First byte of a `206 Partial Content` response, from `Content-Range: bytes <start>-<end>/<size>`.
+fn content_range_start(response: &ureq::http::Response<ureq::Body>) -> Option<u64> {
+    let range = response.headers().get("content-range")?.to_str().ok()?;
+    let (start, _) = range.strip_prefix("bytes ")?.split_once('-')?;
+    start.trim().parse().ok()
+}
@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use super::{fetch_ranges, Storage};
+use crate::registry::{with_retries, RegistryEntry};
+use crate::{digest, ledger};
+
+/// Media type of the manifest `vtx publish` pushes.
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+
+/// `artifactType` of VTX plugin manifests.
+const ARTIFACT_TYPE: &str = "application/vnd.vtx.plugin.v1";
+
+/// Media type of the config blob holding the index entry.
+const ENTRY_MEDIA_TYPE: &str = "application/vnd.vtx.entry.v1+json";
+
+/// Media type of the layer holding the `.vtx` file.
+const ARTIFACT_MEDIA_TYPE: &str = "application/vnd.vtx.plugin.v1.vtx";
+
+/// An OCI distribution registry, from `oci://<host>[:<port>]/<namespace>`.
+///
+/// Each package is the repository `<namespace>/<name>` (lowercased) and each
+/// version a tag, with `+` replaced by `_` since tags cannot hold it. The
+/// manifest has the `.vtx` file as its only layer and the index entry as its
+/// config blob. Requests go over HTTPS, or HTTP for `localhost` and
+/// `127.0.0.1`, and send `Authorization: Bearer <token>` when a token is set.
+pub struct OciStorage {
+    base_url: String,
+    namespace: String,
+    token: Option<String>,
+}
+
+impl OciStorage {
+    pub fn new(url: &str, token: Option<String>) -> Result<Self> {
+        let rest = url.strip_prefix("oci://").unwrap_or(url);
+        let (host, namespace) = rest.split_once('/').unwrap_or((rest, ""));
+        if host.is_empty() {
+            anyhow::bail!("Registry URL '{url}' names no host");
+        }
+        let local = ["localhost", "127.0.0.1"]
+            .iter()
+            .any(|local| host == *local || host.starts_with(&format!("{local}:")));
+        let scheme = if local { "http" } else { "https" };
+        Ok(Self {
+            base_url: format!("{scheme}://{host}"),
+            namespace: namespace.trim_matches('/').to_string(),
+            token,
+        })
+    }
+
+    fn repository(&self, name: &str) -> String {
+        match self.namespace.as_str() {
+            "" => name.to_ascii_lowercase(),
+            namespace => format!("{namespace}/{}", name.to_ascii_lowercase()),
+        }
+    }
+
+    fn authorized<B>(&self, request: ureq::RequestBuilder<B>) -> ureq::RequestBuilder<B> {
+        match &self.token {
+            Some(token) => request.header("Authorization", &format!("Bearer {token}")),
+            None => request,
+        }
+    }
+
+    /// Upload `bytes` as the blob `digest` of `repository` unless it is there already.
+    fn push_blob(&self, action: &str, repository: &str, digest: &str, bytes: &[u8]) -> Result<()> {
+        let blob_url = format!("{}/v2/{repository}/blobs/{digest}", self.base_url);
+        if self.authorized(ureq::head(&blob_url)).call().is_ok() {
+            return Ok(());
+        }
+        with_retries(action, || {
+            let response = self
+                .authorized(ureq::post(&format!(
+                    "{}/v2/{repository}/blobs/uploads/",
+                    self.base_url
+                )))
+                .send_empty()?;
+            let location = response
+                .headers()
+                .get("location")
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| std::io::Error::other("upload session has no Location"))?;
+            let location = match location.starts_with('/') {
+                true => format!("{}{location}", self.base_url),
+                false => location.to_string(),
+            };
+            let separator = if location.contains('?') { '&' } else { '?' };
+            self.authorized(ureq::put(&format!("{location}{separator}digest={digest}")))
+                .header("Content-Type", "application/octet-stream")
+                .send(bytes)
+                .map(drop)
+        })
+    }
+
+    fn get_blob(
+        &self,
+        repository: &str,
+        digest: &str,
+        range: Option<&str>,
+    ) -> Result<ureq::http::Response<ureq::Body>, ureq::Error> {
+        let mut request = self.authorized(ureq::get(&format!(
+            "{}/v2/{repository}/blobs/{digest}",
+            self.base_url
+        )));
+        if let Some(range) = range {
+            request = request.header("Range", range);
+        }
+        request.call()
+    }
+}
+
+fn tag(version: &str) -> String {
+    version.replace('+', "_")
+}
+
+impl Storage for OciStorage {
+    fn put_artifact(&self, entry: &RegistryEntry, artifact: &[u8]) -> Result<()> {
+        let repository = self.repository(&entry.name);
+        self.push_blob("upload artifact", &repository, &entry.digest, artifact)
+    }
+
+    fn put_entry(&self, entry: &RegistryEntry) -> Result<()> {
+        let repository = self.repository(&entry.name);
+        let config = serde_json::to_vec(entry)?;
+        let config_digest = ledger::sha256_digest(&config);
+        self.push_blob("upload index entry", &repository, &config_digest, &config)?;
+        let manifest = serde_json::to_vec(&json!({
+            "schemaVersion": 2,
+            "mediaType": MANIFEST_MEDIA_TYPE,
+            "artifactType": ARTIFACT_TYPE,
+            "config": {
+                "mediaType": ENTRY_MEDIA_TYPE,
+                "digest": config_digest,
+                "size": config.len(),
+            },
+            "layers": [{
+                "mediaType": ARTIFACT_MEDIA_TYPE,
+                "digest": entry.digest,
+                "size": entry.size,
+                "annotations": {
+                    "org.opencontainers.image.title": format!("{}-{}.vtx", entry.name, entry.version),
+                },
+            }],
+        }))?;
+        let url = format!(
+            "{}/v2/{repository}/manifests/{}",
+            self.base_url,
+            tag(&entry.version)
+        );
+        with_retries("publish manifest", || {
+            self.authorized(ureq::put(&url))
+                .header("Content-Type", MANIFEST_MEDIA_TYPE)
+                .send(&manifest[..])
+                .map(drop)
+        })
+    }
+
+    fn get_entry(&self, name: &str, version: &str) -> Result<RegistryEntry> {
+        let repository = self.repository(name);
+        let url = format!(
+            "{}/v2/{repository}/manifests/{}",
+            self.base_url,
+            tag(version)
+        );
+        let manifest = with_retries("fetch manifest", || {
+            self.authorized(ureq::get(&url))
+                .header("Accept", MANIFEST_MEDIA_TYPE)
+                .call()?
+                .body_mut()
+                .read_to_string()
+        })?;
+        let manifest: Value = serde_json::from_str(&manifest)
+            .with_context(|| format!("Malformed OCI manifest from {url}"))?;
+        let config_digest = manifest["config"]["digest"]
+            .as_str()
+            .with_context(|| format!("OCI manifest from {url} has no config blob"))?;
+        if manifest["config"]["mediaType"] != ENTRY_MEDIA_TYPE {
+            anyhow::bail!("{url} is not a VTX plugin (config is not {ENTRY_MEDIA_TYPE})");
+        }
+        let config = with_retries("fetch index entry", || {
+            self.get_blob(&repository, config_digest, None)?
+                .body_mut()
+                .read_to_vec()
+        })?;
+        if !digest::digest_matches(config_digest, &config)? {
+            anyhow::bail!("Index entry of {name}@{version} does not match its digest");
+        }
+        serde_json::from_slice(&config)
+            .with_context(|| format!("Malformed index entry of {name}@{version}"))
+    }
+
+    fn fetch_artifact(&self, entry: &RegistryEntry, part: &Path) -> Result<()> {
+        let repository = self.repository(&entry.name);
+        fetch_ranges(entry, part, |range| {
+            self.get_blob(&repository, &entry.digest, range)
+        })
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::{Context, Result};
+use ring::hmac;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use super::{entry_key, fetch_ranges, Storage};
+use crate::hex;
+use crate::registry::{with_retries, RegistryEntry};
+use crate::timeutil;
+
+/// An S3 bucket (or S3-compatible store), from `s3://<bucket>[/<prefix>]`.
+///
+/// Objects are stored under `<prefix>/<entry key>` and `.../artifact`, see
+/// [`entry_key`]. Requests are signed with AWS Signature Version 4 using
+/// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and, for temporary
+/// credentials, `AWS_SESSION_TOKEN`. The region comes from `AWS_REGION` or
+/// `AWS_DEFAULT_REGION` (default `us-east-1`). `AWS_ENDPOINT_URL_S3` or
+/// `AWS_ENDPOINT_URL` selects another endpoint, addressed path-style
+/// (`<endpoint>/<bucket>/<key>`), e.g. for MinIO.
+pub struct S3Storage {
+    /// URL of the bucket, without a trailing slash.
+    bucket_url: String,
+    prefix: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl S3Storage {
+    pub fn new(url: &str) -> Result<Self> {
+        let rest = url.strip_prefix("s3://").unwrap_or(url);
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            anyhow::bail!("Registry URL '{url}' names no bucket");
+        }
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let region = env("AWS_REGION")
+            .or_else(|| env("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|| "us-east-1".to_string());
+        let bucket_url = match env("AWS_ENDPOINT_URL_S3").or_else(|| env("AWS_ENDPOINT_URL")) {
+            Some(endpoint) => format!("{}/{}", endpoint.trim_end_matches('/'), uri_encode(bucket)),
+            None => format!("https://{bucket}.s3.{region}.amazonaws.com"),
+        };
+        let credential = |name: &str| {
+            env(name).with_context(|| format!("{name} must be set to use an s3:// registry"))
+        };
+        Ok(Self {
+            bucket_url,
+            prefix: prefix.trim_matches('/').to_string(),
+            region,
+            access_key: credential("AWS_ACCESS_KEY_ID")?,
+            secret_key: credential("AWS_SECRET_ACCESS_KEY")?,
+            session_token: env("AWS_SESSION_TOKEN"),
+        })
+    }
+
+    /// URL of the object `key` below the prefix.
+    fn object_url(&self, key: &str) -> String {
+        let key = match self.prefix.as_str() {
+            "" => key.to_string(),
+            prefix => format!("{prefix}/{key}"),
+        };
+        let path: Vec<String> = key.split('/').map(uri_encode).collect();
+        format!("{}/{}", self.bucket_url, path.join("/"))
+    }
+
+    /// Headers signing `method url` with a body of SHA-256 `payload_hash` (hex).
+    fn signed_headers(&self, method: &str, url: &str, payload_hash: &str) -> Vec<(String, String)> {
+        let amz_date: String = timeutil::now_rfc3339()
+            .chars()
+            .filter(|c| !matches!(c, '-' | ':'))
+            .collect();
+        let date = &amz_date[..8];
+        let after_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let (host, path) =
+            after_scheme.split_at(after_scheme.find('/').unwrap_or(after_scheme.len()));
+        let path = if path.is_empty() { "/" } else { path };
+
+        let mut headers = vec![
+            ("host".to_string(), host.to_string()),
+            ("x-amz-content-sha256".to_string(), payload_hash.to_string()),
+            ("x-amz-date".to_string(), amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        let signed: Vec<&str> = headers.iter().map(|(name, _)| name.as_str()).collect();
+        let signed = signed.join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+            .collect();
+        let canonical_request =
+            format!("{method}\n{path}\n\n{canonical_headers}\n{signed}\n{payload_hash}");
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let mut key = format!("AWS4{}", self.secret_key).into_bytes();
+        for part in [date, &self.region, "s3", "aws4_request"] {
+            key = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &key), part.as_bytes())
+                .as_ref()
+                .to_vec();
+        }
+        let signature = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, &key),
+            string_to_sign.as_bytes(),
+        );
+        headers.push((
+            "authorization".to_string(),
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed}, Signature={}",
+                self.access_key,
+                hex::encode(signature.as_ref())
+            ),
+        ));
+        // ureq sets Host from the URL.
+        headers.remove(0);
+        headers
+    }
+
+    fn put(&self, action: &str, key: &str, content_type: &str, body: &[u8]) -> Result<()> {
+        let url = self.object_url(key);
+        let payload_hash = sha256_hex(body);
+        with_retries(action, || {
+            let mut request = ureq::put(&url).header("Content-Type", content_type);
+            for (name, value) in self.signed_headers("PUT", &url, &payload_hash) {
+                request = request.header(&name, &value);
+            }
+            request.send(body).map(drop)
+        })
+    }
+
+    /// GET `key`, asking for the bytes from `offset` on when it is not zero.
+    fn get(
+        &self,
+        key: &str,
+        range: Option<&str>,
+    ) -> Result<ureq::http::Response<ureq::Body>, ureq::Error> {
+        let url = self.object_url(key);
+        let mut request = ureq::get(&url);
+        for (name, value) in self.signed_headers("GET", &url, &sha256_hex(b"")) {
+            request = request.header(&name, &value);
+        }
+        if let Some(range) = range {
+            request = request.header("Range", range);
+        }
+        request.call()
+    }
+}
+
+impl Storage for S3Storage {
+    fn put_artifact(&self, entry: &RegistryEntry, artifact: &[u8]) -> Result<()> {
+        let key = format!("{}/artifact", entry_key(&entry.name, &entry.version)?);
+        self.put(
+            "upload artifact",
+            &key,
+            "application/octet-stream",
+            artifact,
+        )
+    }
+
+    fn put_entry(&self, entry: &RegistryEntry) -> Result<()> {
+        let key = entry_key(&entry.name, &entry.version)?;
+        let index = serde_json::to_vec(entry)?;
+        self.put("publish index entry", &key, "application/json", &index)
+    }
+
+    fn get_entry(&self, name: &str, version: &str) -> Result<RegistryEntry> {
+        let key = entry_key(name, version)?;
+        let body = with_retries("fetch index entry", || {
+            self.get(&key, None)?.body_mut().read_to_string()
+        })?;
+        serde_json::from_str(&body)
+            .with_context(|| format!("Malformed registry entry at {}", self.object_url(&key)))
+    }
+
+    fn fetch_artifact(&self, entry: &RegistryEntry, part: &Path) -> Result<()> {
+        let key = format!("{}/artifact", entry_key(&entry.name, &entry.version)?);
+        fetch_ranges(entry, part, |range| self.get(&key, range))
+    }
+}
+
+/// Percent-encode everything but the unreserved characters of RFC 3986, as
+/// SigV4 expects of each path segment.
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(&Sha256::digest(bytes))
+}
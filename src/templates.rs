@@ -1,54 +1,283 @@
-pub fn rust_cargo_toml(name: &str) -> String {
-    format!(
-        "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[lib]\ncrate-type = [\"cdylib\"]\n\n[dependencies]\nvtx-sdk = \"0.1.2\"\nserde = {{ version = \"1.0\", features = [\"derive\"] }}\nserde_json = \"1.0\"\nanyhow = \"1.0\"\n"
-    )
-}
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
 
-pub fn rust_lib_rs() -> String {
-    "use vtx_sdk::prelude::*;\n\nmod config;\n\n#[derive(Default)]\nstruct Plugin;\n\nimpl VtxPlugin for Plugin {\n    fn get_manifest() -> Manifest {\n        Manifest {\n            id: config::PLUGIN_ID.to_string(),\n            name: config::PLUGIN_NAME.to_string(),\n            version: env!(\"CARGO_PKG_VERSION\").to_string(),\n            description: config::PLUGIN_DESC.to_string(),\n            entrypoint: config::ENTRYPOINT.to_string(),\n        }\n    }\n\n    fn get_capabilities() -> Capabilities {\n        Capabilities {\n            subscriptions: config::SUBSCRIPTIONS.iter().map(|s| s.to_string()).collect(),\n            permissions: config::PERMISSIONS.iter().map(|p| p.to_string()).collect(),\n            http: None,\n        }\n    }\n}\n\nexport_plugin!(Plugin);\n"
-        .to_string()
+/// Values substituted for `{{var}}` placeholders in template paths and contents.
+pub type TemplateVars = BTreeMap<&'static str, String>;
+
+/// One file of a built-in template; both path and content are rendered.
+pub struct TemplateFile {
+    pub path: &'static str,
+    pub content: &'static str,
 }
 
-pub fn rust_config_rs(name: &str) -> String {
-    format!(
-        "// Centralized plugin configuration.\n\npub const PLUGIN_ID: &str = \"vtx.{name}\";\npub const PLUGIN_NAME: &str = \"{name}\";\npub const PLUGIN_DESC: &str = \"Short plugin summary\";\npub const ENTRYPOINT: &str = \"/\";\n\npub const SUBSCRIPTIONS: &[&str] = &[];\npub const PERMISSIONS: &[&str] = &[];\n"
-    )
+/// A built-in project template: empty directories to create and files to render.
+pub struct Template {
+    pub dirs: &'static [&'static str],
+    pub files: &'static [TemplateFile],
 }
 
-pub fn rust_vtx_toml(name: &str) -> String {
-    format!(
-        "vtx_version = 1\n\n[project]\nname = \"{name}\"\nversion = \"0.1.0\"\nlanguage = \"rust\"\nauthors = [{{ name = \"Your Name\", email = \"you@example.com\" }}]\ndescription = \"Short plugin summary\"\nlicense = \"MIT\"\nhomepage = \"https://example.com\"\nrepository = \"https://example.com/repo\"\nkeywords = [\"vtx\", \"plugin\"]\n"
-    )
+/// Languages with a built-in `vtx init` template.
+pub const BUILTIN_LANGUAGES: &[&str] = &["rust", "ts", "python", "c", "go", "php", "lua"];
+
+/// Built-in template for a normalized language identifier.
+pub fn builtin(language: &str) -> Option<&'static Template> {
+    match language {
+        "rust" => Some(&RUST),
+        "ts" => Some(&TS),
+        "python" => Some(&PYTHON),
+        "c" => Some(&C),
+        "go" => Some(&GO),
+        "php" => Some(&PHP),
+        "lua" => Some(&LUA),
+        _ => None,
+    }
 }
 
-pub fn ts_package_json(name: &str) -> String {
-    format!(
-        "{{\n  \"name\": \"{name}\",\n  \"version\": \"0.1.0\",\n  \"scripts\": {{\n    \"build\": \"echo TODO: build wasm\"\n  }}\n}}\n"
-    )
+/// Replace `{{var}}` placeholders (whitespace inside the braces is allowed).
+///
+/// Placeholders naming an unknown variable are left as written and returned,
+/// so typos can be reported. Braces around anything other than a plain
+/// identifier (e.g. Go's `{{ .Name }}`) are not placeholders and are kept.
+pub fn render(text: &str, vars: &TemplateVars) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(text.len());
+    let mut unknown = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return (out, unknown);
+        };
+        let key = after[..end].trim();
+        let is_identifier = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        match vars.get(key) {
+            Some(value) if is_identifier => out.push_str(value),
+            _ => {
+                if is_identifier && !unknown.iter().any(|k| k == key) {
+                    unknown.push(key.to_string());
+                }
+                out.push_str(&rest[start..start + 2 + end + 2]);
+            }
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    (out, unknown)
 }
 
-pub fn ts_index_ts() -> String {
-    "export {};\n".to_string()
+/// Render a built-in template into `dest`. Returns unknown placeholder names.
+pub fn write_builtin(template: &Template, vars: &TemplateVars, dest: &Path) -> Result<Vec<String>> {
+    let mut unknown = Vec::new();
+    for dir in template.dirs {
+        let (dir, missing) = render(dir, vars);
+        unknown.extend(missing);
+        std::fs::create_dir_all(dest.join(dir))?;
+    }
+    for file in template.files {
+        let (path, missing) = render(file.path, vars);
+        unknown.extend(missing);
+        let (content, missing) = render(file.content, vars);
+        unknown.extend(missing);
+        write_file(&dest.join(path), content.as_bytes())?;
+    }
+    Ok(unknown)
 }
 
-pub fn ts_vtx_toml(name: &str) -> String {
-    format!(
-        "vtx_version = 1\n\n[project]\nname = \"{name}\"\nversion = \"0.1.0\"\nlanguage = \"ts\"\nauthors = [{{ name = \"Your Name\", email = \"you@example.com\" }}]\ndescription = \"Short plugin summary\"\nlicense = \"MIT\"\nhomepage = \"https://example.com\"\nrepository = \"https://example.com/repo\"\nkeywords = [\"vtx\", \"plugin\"]\n"
-    )
+/// Render every file under the template directory `src` into `dest`.
+///
+/// Relative paths and UTF-8 contents are rendered; other files are copied
+/// unchanged. `.git` is skipped and file permissions are kept. Returns
+/// unknown placeholder names.
+pub fn write_dir(src: &Path, vars: &TemplateVars, dest: &Path) -> Result<Vec<String>> {
+    let mut unknown = Vec::new();
+    copy_rendered(src, src, vars, dest, &mut unknown)?;
+    Ok(unknown)
 }
 
-pub fn pyproject_toml(name: &str) -> String {
-    format!(
-        "[build-system]\nrequires = [\"setuptools\"]\nbuild-backend = \"setuptools.build_meta\"\n\n[project]\nname = \"{name}\"\nversion = \"0.1.0\"\n"
-    )
+fn copy_rendered(
+    root: &Path,
+    dir: &Path,
+    vars: &TemplateVars,
+    dest: &Path,
+    unknown: &mut Vec<String>,
+) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read template directory {}", dir.display()))?
+        .collect::<std::io::Result<_>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let relative = path
+            .strip_prefix(root)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let (relative, missing) = render(&relative, vars);
+        unknown.extend(missing);
+        let target = dest.join(relative);
+
+        if entry.file_type()?.is_dir() {
+            std::fs::create_dir_all(&target)?;
+            copy_rendered(root, &path, vars, dest, unknown)?;
+            continue;
+        }
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("Failed to read template file {}", path.display()))?;
+        match String::from_utf8(bytes) {
+            Ok(text) => {
+                let (content, missing) = render(&text, vars);
+                unknown.extend(missing);
+                write_file(&target, content.as_bytes())?;
+            }
+            Err(e) => write_file(&target, e.as_bytes())?,
+        }
+        std::fs::set_permissions(&target, entry.metadata()?.permissions())?;
+    }
+    Ok(())
 }
 
-pub fn python_init_py() -> String {
-    "# plugin entry\n".to_string()
+fn write_file(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, bytes).with_context(|| format!("Failed to write {}", path.display()))
 }
 
-pub fn python_vtx_toml(name: &str) -> String {
-    format!(
-        "vtx_version = 1\n\n[project]\nname = \"{name}\"\nversion = \"0.1.0\"\nlanguage = \"python\"\nauthors = [{{ name = \"Your Name\", email = \"you@example.com\" }}]\ndescription = \"Short plugin summary\"\nlicense = \"MIT\"\nhomepage = \"https://example.com\"\nrepository = \"https://example.com/repo\"\nkeywords = [\"vtx\", \"plugin\"]\n"
-    )
+const RUST: Template = Template {
+    dirs: &[],
+    files: &[
+        TemplateFile {
+            path: "Cargo.toml",
+            content: "[package]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nedition = \"2021\"\nlicense = \"{{license}}\"\n\n[lib]\ncrate-type = [\"cdylib\"]\n\n[dependencies]\nvtx-sdk = \"0.1.2\"\nserde = { version = \"1.0\", features = [\"derive\"] }\nserde_json = \"1.0\"\nanyhow = \"1.0\"\n",
+        },
+        TemplateFile {
+            path: "src/lib.rs",
+            content: "use vtx_sdk::prelude::*;\n\nmod config;\n\n#[derive(Default)]\nstruct Plugin;\n\nimpl VtxPlugin for Plugin {\n    fn get_manifest() -> Manifest {\n        Manifest {\n            id: config::PLUGIN_ID.to_string(),\n            name: config::PLUGIN_NAME.to_string(),\n            version: env!(\"CARGO_PKG_VERSION\").to_string(),\n            description: config::PLUGIN_DESC.to_string(),\n            entrypoint: config::ENTRYPOINT.to_string(),\n        }\n    }\n\n    fn get_capabilities() -> Capabilities {\n        Capabilities {\n            subscriptions: config::SUBSCRIPTIONS.iter().map(|s| s.to_string()).collect(),\n            permissions: config::PERMISSIONS.iter().map(|p| p.to_string()).collect(),\n            http: None,\n        }\n    }\n}\n\nexport_plugin!(Plugin);\n",
+        },
+        TemplateFile {
+            path: "src/config.rs",
+            content: "// Centralized plugin configuration.\n\npub const PLUGIN_ID: &str = \"vtx.{{name}}\";\npub const PLUGIN_NAME: &str = \"{{name}}\";\npub const PLUGIN_DESC: &str = \"Short plugin summary\";\npub const ENTRYPOINT: &str = \"/\";\n\npub const SUBSCRIPTIONS: &[&str] = &[];\npub const PERMISSIONS: &[&str] = &[];\n",
+        },
+        TemplateFile {
+            path: "vtx.toml",
+            content: VTX_TOML,
+        },
+    ],
+};
+
+const TS: Template = Template {
+    dirs: &["dist"],
+    files: &[
+        TemplateFile {
+            path: "package.json",
+            content: "{\n  \"name\": \"{{name}}\",\n  \"version\": \"0.1.0\",\n  \"license\": \"{{license}}\",\n  \"scripts\": {\n    \"build\": \"echo TODO: build wasm\"\n  }\n}\n",
+        },
+        TemplateFile {
+            path: "src/index.ts",
+            content: "export {};\n",
+        },
+        TemplateFile {
+            path: "vtx.toml",
+            content: VTX_TOML,
+        },
+    ],
+};
+
+const PYTHON: Template = Template {
+    dirs: &["dist"],
+    files: &[
+        TemplateFile {
+            path: "pyproject.toml",
+            content: "[build-system]\nrequires = [\"setuptools\"]\nbuild-backend = \"setuptools.build_meta\"\n\n[project]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nlicense = { text = \"{{license}}\" }\n",
+        },
+        TemplateFile {
+            path: "src/{{name_snake}}/__init__.py",
+            content: "# plugin entry\n",
+        },
+        TemplateFile {
+            path: "vtx.toml",
+            content: VTX_TOML,
+        },
+    ],
+};
+
+const C: Template = Template {
+    dirs: &[],
+    files: &[
+        TemplateFile {
+            path: "src/plugin.c",
+            content: "// Plugin entry. Generate bindings for the vtx:api plugin world with\n// `wit-bindgen c`, include them here and implement the exported functions.\n",
+        },
+        TemplateFile {
+            path: "vtx.toml",
+            content: "vtx_version = 1\n\n[project]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nlanguage = \"c\"\nauthors = [{ name = \"{{author}}\", email = \"{{email}}\" }]\ndescription = \"Short plugin summary\"\nlicense = \"{{license}}\"\nhomepage = \"https://example.com\"\nrepository = \"https://example.com/repo\"\nkeywords = [\"vtx\", \"plugin\"]\n\n[build.c]\nsources = [\"src\"]\n",
+        },
+    ],
+};
+
+const GO: Template = Template {
+    dirs: &[],
+    files: &[
+        TemplateFile {
+            path: "go.mod",
+            content: "module {{name}}\n\ngo 1.22\n",
+        },
+        TemplateFile {
+            path: "main.go",
+            content: "// Plugin entry. Generate bindings for the vtx:api plugin world with\n// `wit-bindgen tinygo` and implement the exported functions.\npackage main\n\nfunc main() {}\n",
+        },
+        TemplateFile {
+            path: "vtx.toml",
+            content: VTX_TOML,
+        },
+    ],
+};
+
+const PHP: Template = Template {
+    dirs: &["dist"],
+    files: &[
+        TemplateFile {
+            path: "composer.json",
+            content: "{\n  \"name\": \"vtx/{{name}}\",\n  \"license\": \"{{license}}\",\n  \"authors\": [{ \"name\": \"{{author}}\", \"email\": \"{{email}}\" }],\n  \"scripts\": {\n    \"build\": \"echo TODO: compile src/plugin.php to dist/{{name}}.wasm\"\n  }\n}\n",
+        },
+        TemplateFile {
+            path: "src/plugin.php",
+            content: "<?php\n// plugin entry\n",
+        },
+        TemplateFile {
+            path: "vtx.toml",
+            content: VTX_TOML,
+        },
+    ],
+};
+
+const LUA: Template = Template {
+    dirs: &[],
+    files: &[
+        TemplateFile {
+            path: "Makefile",
+            content: "all:\n\t@echo \"TODO: compile src/main.lua to {{name}}.wasm\"\n",
+        },
+        TemplateFile {
+            path: "src/main.lua",
+            content: "-- plugin entry\n",
+        },
+        TemplateFile {
+            path: "vtx.toml",
+            content: VTX_TOML,
+        },
+    ],
+};
+
+/// vtx.toml shared by the built-in templates.
+const VTX_TOML: &str = "vtx_version = 1\n\n[project]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nlanguage = \"{{language}}\"\nauthors = [{ name = \"{{author}}\", email = \"{{email}}\" }]\ndescription = \"Short plugin summary\"\nlicense = \"{{license}}\"\nhomepage = \"https://example.com\"\nrepository = \"https://example.com/repo\"\nkeywords = [\"vtx\", \"plugin\"]\n";
+
+pub fn release_toml() -> String {
+    "# Release driver for `vtx release`. Steps run in order: bump, check, test, build, sign,\n# sbom, publish. Preview them with `vtx release --dry-run`; leave one out with --skip-<step>.\n# sign needs package.signing_key or VTX_SIGNING_KEY, publish a [registry].\n\n[release]\n# Version component raised in vtx.toml and the language manifest: major|minor|patch\nbump = \"patch\"\nsteps = [\"bump\", \"check\", \"test\", \"build\", \"sign\", \"sbom\", \"publish\"]\n# Steps that ask before running; answer all with `vtx release --yes`\nconfirm = [\"publish\"]\n"
+        .to_string()
 }
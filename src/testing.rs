@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// Tools that get a shim.
+pub const FAKE_TOOLS: &[&str] = &[
+    "cargo",
+    "rustup",
+    "tinygo",
+    "clang",
+    "clang++",
+    "dotnet",
+    "node",
+    "npm",
+    "python",
+    "componentize-py",
+    "wasm-opt",
+];
+
+const CALLS_LOG: &str = "calls.log";
+const OUTPUT_WASM: &str = "output.wasm";
+const CARGO_METADATA: &str = "cargo-metadata.json";
+const VERSION_PROBE: &str =
+    "case \"$1\" in --version|-v|version) echo \"$TOOL 0.0.0-fake\"; exit 0;; esac\n";
+
+/// A directory of toolchain shims for exercising `vtx` pipelines without real compilers.
+///
+/// Put it first on `PATH` (see [`FakeToolchain::path_env`]) when running `vtx`.
+/// Version probes succeed, and builds copy a fixed Wasm file to the output
+/// path each builder expects:
+/// - `cargo build`: `target/<target>/<profile>/<package>.wasm`;
+/// - `cargo metadata`: the workspace set by [`FakeToolchain::cargo_workspace`];
+/// - `tinygo build`, `clang`, `clang++` and `componentize-py`: the `-o` path;
+/// - `dotnet publish`: `<-o dir>/<csproj stem>.wasm`;
+/// - `npm run build`: `dist/index.wasm`;
+/// - `wasm-opt`: copies its input to the `-o` path unchanged;
+/// - `rustup target list`: reports `wasm32-wasip1` and `wasm32-wasip2` installed.
+///
+/// Every invocation is appended to `calls.log` in the shim directory. Shims
+/// are POSIX shell scripts, so this is only available on Unix.
+pub struct FakeToolchain {
+    bin_dir: PathBuf,
+}
+
+impl FakeToolchain {
+    /// Write shims into `bin_dir` (created if missing). Builds emit `wasm`.
+    pub fn install(bin_dir: &Path, wasm: &[u8]) -> Result<Self> {
+        std::fs::create_dir_all(bin_dir)
+            .with_context(|| format!("Failed to create {}", bin_dir.display()))?;
+        let bin_dir = bin_dir
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", bin_dir.display()))?;
+        std::fs::write(bin_dir.join(OUTPUT_WASM), wasm).context("Failed to write fake output")?;
+
+        let toolchain = Self { bin_dir };
+        for tool in FAKE_TOOLS {
+            toolchain.write_shim(tool, &shim_body(tool))?;
+        }
+        Ok(toolchain)
+    }
+
+    /// Make every call to `tool` except version probes exit with status 1.
+    pub fn fail(&self, tool: &str) -> Result<()> {
+        self.write_shim(
+            tool,
+            &format!("{VERSION_PROBE}echo \"fake $TOOL failure\" >&2\nexit 1\n"),
+        )
+    }
+
+    /// Make `cargo metadata` report a workspace with these member packages,
+    /// each in the directory of its name.
+    pub fn cargo_workspace(&self, members: &[&str]) -> Result<()> {
+        let ids: Vec<String> = members.iter().map(|m| format!("{m} 0.1.0")).collect();
+        let packages: Vec<_> = members
+            .iter()
+            .zip(&ids)
+            .map(|(name, id)| {
+                serde_json::json!({ "name": name, "id": id, "manifest_path": format!("{name}/Cargo.toml") })
+            })
+            .collect();
+        let metadata = serde_json::json!({ "packages": packages, "workspace_members": ids });
+        std::fs::write(self.bin_dir.join(CARGO_METADATA), metadata.to_string())
+            .context("Failed to write fake cargo metadata")
+    }
+
+    /// Directory holding the shims.
+    pub fn bin_dir(&self) -> &Path {
+        &self.bin_dir
+    }
+
+    /// `PATH` with the shim directory first.
+    pub fn path_env(&self) -> Result<OsString> {
+        let mut paths = vec![self.bin_dir.clone()];
+        if let Some(path) = std::env::var_os("PATH") {
+            paths.extend(std::env::split_paths(&path));
+        }
+        std::env::join_paths(paths).context("Failed to build PATH")
+    }
+
+    /// Recorded invocations, one `<tool> <args...>` string per call.
+    pub fn calls(&self) -> Result<Vec<String>> {
+        match std::fs::read_to_string(self.bin_dir.join(CALLS_LOG)) {
+            Ok(content) => Ok(content.lines().map(str::to_string).collect()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e).context("Failed to read fake toolchain calls"),
+        }
+    }
+
+    fn write_shim(&self, tool: &str, body: &str) -> Result<()> {
+        let dir = self.bin_dir.display();
+        let script = format!(
+            "#!/bin/sh\nTOOL={tool}\nWASM='{dir}/{OUTPUT_WASM}'\necho \"$TOOL $*\" >> '{dir}/{CALLS_LOG}'\n{body}"
+        );
+        let path = self.bin_dir.join(tool);
+        std::fs::write(&path, script)
+            .with_context(|| format!("Failed to write shim: {}", path.display()))?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make shim executable: {}", path.display()))
+    }
+}
+
+/// Tool-specific part of a shim. `$TOOL` and `$WASM` are set by the preamble.
+fn shim_body(tool: &str) -> String {
+    let build = match tool {
+        "cargo" => {
+            r#"if [ "$1" = "metadata" ]; then cat "$(dirname "$WASM")/cargo-metadata.json"; exit $?; fi
+target=wasm32-wasip1; package=; profile=debug
+while [ $# -gt 0 ]; do
+  case "$1" in
+    --target) target=$2; shift;;
+    -p) package=$2; shift;;
+    --release) profile=release;;
+  esac
+  shift
+done
+mkdir -p "target/$target/$profile" && cp "$WASM" "target/$target/$profile/$package.wasm"
+"#
+        }
+        "tinygo" | "clang" | "clang++" | "componentize-py" => {
+            r#"while [ $# -gt 0 ]; do
+  if [ "$1" = "-o" ]; then mkdir -p "$(dirname "$2")" && cp "$WASM" "$2"; exit $?; fi
+  shift
+done
+echo "fake $TOOL: missing -o" >&2; exit 1
+"#
+        }
+        "dotnet" => {
+            r#"output=; name=app
+while [ $# -gt 0 ]; do
+  case "$1" in
+    -o) output=$2; shift;;
+    *.csproj) name=$(basename "$1" .csproj);;
+  esac
+  shift
+done
+mkdir -p "$output" && cp "$WASM" "$output/$name.wasm"
+"#
+        }
+        "wasm-opt" => {
+            r#"input=; output=
+while [ $# -gt 0 ]; do
+  case "$1" in
+    -o) output=$2; shift;;
+    -*) ;;
+    *) input=$1;;
+  esac
+  shift
+done
+cp "$input" "$output"
+"#
+        }
+        "rustup" => {
+            r#"if [ "$1 $2" = "target list" ]; then printf 'wasm32-wasip1\nwasm32-wasip2\n'; fi
+"#
+        }
+        "npm" => {
+            r#"case "$1" in
+  install) mkdir -p node_modules;;
+  run) mkdir -p dist && cp "$WASM" dist/index.wasm;;
+esac
+"#
+        }
+        _ => "",
+    };
+    format!("{VERSION_PROBE}{build}")
+}
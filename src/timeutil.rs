@@ -0,0 +1,131 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Current UTC date as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let (y, m, d) = civil_from_days((now_secs() / 86_400) as i64);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// Current UTC time as RFC 3339 (`YYYY-MM-DDTHH:MM:SSZ`).
+pub fn now_rfc3339() -> String {
+    rfc3339(now_secs())
+}
+
+/// UTC time `secs` after the Unix epoch as RFC 3339.
+pub fn rfc3339(secs: u64) -> String {
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}Z",
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+/// Time since the Unix epoch of an RFC 3339 UTC time such as
+/// `2024-01-01T00:00:00Z`, with optional fractional seconds.
+pub fn parse_rfc3339(text: &str) -> Result<Duration> {
+    let invalid =
+        || anyhow::anyhow!("expected an RFC 3339 UTC time like 2024-01-01T00:00:00Z, got '{text}'");
+    let (date, time) = text.split_once(['T', 't']).ok_or_else(invalid)?;
+    let time = time.strip_suffix(['Z', 'z']).ok_or_else(invalid)?;
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let fields = |text: &str, lengths: &[usize]| -> Option<Vec<u32>> {
+        let parts: Vec<&str> = text.split(':').flat_map(|part| part.split('-')).collect();
+        (parts.len() == lengths.len()
+            && parts
+                .iter()
+                .zip(lengths)
+                .all(|(part, len)| part.len() == *len && part.bytes().all(|b| b.is_ascii_digit())))
+        .then(|| parts.iter().filter_map(|part| part.parse().ok()).collect())
+    };
+    let date = fields(date, &[4, 2, 2]).ok_or_else(invalid)?;
+    let time = fields(time, &[2, 2, 2]).ok_or_else(invalid)?;
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let (year, month, day) = (i64::from(date[0]), date[1], date[2]);
+    let days = days_from_civil(year, month, day);
+    if !(1..=12).contains(&month)
+        || civil_from_days(days) != (year, month, day)
+        || days < 0
+        || time[0] > 23
+        || time[1] > 59
+        || time[2] > 59
+    {
+        return Err(invalid());
+    }
+    let nanos = format!("{fraction:0<9}").parse().unwrap_or(0);
+    let secs = days as u64 * 86_400
+        + u64::from(time[0]) * 3_600
+        + u64::from(time[1]) * 60
+        + u64::from(time[2]);
+    Ok(Duration::new(secs, nanos))
+}
+
+/// A duration written as one or more `<number><unit>` parts, e.g. `90s`,
+/// `1h30m` or `2d`; units are `ms`, `s`, `m`, `h` and `d`.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let invalid =
+        || anyhow::anyhow!("expected a duration like 30s, 15m, 1h30m or 2d, got '{text}'");
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let value: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let part = match &rest[..unit_len] {
+            "ms" => Duration::from_millis(value),
+            "s" => Duration::from_secs(value),
+            "m" => Duration::from_secs(value.saturating_mul(60)),
+            "h" => Duration::from_secs(value.saturating_mul(3_600)),
+            "d" => Duration::from_secs(value.saturating_mul(86_400)),
+            _ => return Err(invalid()),
+        };
+        total = total.saturating_add(part);
+        rest = &rest[unit_len..];
+    }
+    Ok(total)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * i64::from((month + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Convert days since 1970-01-01 into a proleptic Gregorian date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
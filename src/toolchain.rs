@@ -0,0 +1,89 @@
+use std::process::Command;
+
+/// Canonical language identifiers supported by the builders.
+pub const LANGUAGES: &[&str] = &["rust", "go", "c", "csharp", "ts", "python", "php", "lua"];
+
+/// Oldest TinyGo release with the `wasip1` target used by the Go builder.
+pub const MIN_TINYGO: &str = "0.30.0";
+
+/// Oldest componentize-py release producing components for the current WIT syntax.
+pub const MIN_COMPONENTIZE_PY: &str = "0.13.0";
+
+/// Oldest Python supported by componentize-py.
+pub const MIN_PYTHON: &str = "3.10.0";
+
+/// Oldest Node.js release supported by the component tooling.
+pub const MIN_NODE: &str = "18.0.0";
+
+/// Minimum version `vtx doctor` checks for `tool`, if it has one.
+pub fn minimum_version(tool: &str) -> Option<&'static str> {
+    match tool {
+        "tinygo" => Some(MIN_TINYGO),
+        "componentize-py" => Some(MIN_COMPONENTIZE_PY),
+        "python" => Some(MIN_PYTHON),
+        "node" => Some(MIN_NODE),
+        _ => None,
+    }
+}
+
+/// Normalize language aliases to the identifiers in [`LANGUAGES`].
+pub fn canonical_language(language: &str) -> String {
+    match language.to_lowercase().as_str() {
+        "rust" | "rs" => "rust".to_string(),
+        "go" | "tinygo" => "go".to_string(),
+        "c" | "cpp" | "c++" | "cxx" => "c".to_string(),
+        "csharp" | "c#" | "cs" | "dotnet" => "csharp".to_string(),
+        "ts" | "typescript" | "js" | "node" => "ts".to_string(),
+        "py" | "python" => "python".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Run `tool args...` and return the first non-empty output line.
+///
+/// Some tools (e.g. `lua -v`) print their version to stderr, so both streams are read.
+pub fn probe_version(tool: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(tool).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    first_line(&output)
+}
+
+/// Run `tool args...` and report whether it could be started at all, with the
+/// first output line when it exited successfully. The exit status does not
+/// decide presence: some tools exit non-zero for a plain `--help`.
+pub fn probe_installed(tool: &str, args: &[&str]) -> (bool, Option<String>) {
+    match Command::new(tool).args(args).output() {
+        Ok(output) if output.status.success() => (true, first_line(&output)),
+        Ok(_) => (true, None),
+        Err(_) => (false, None),
+    }
+}
+
+/// First non-empty line of stdout, then stderr.
+fn first_line(output: &std::process::Output) -> Option<String> {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Whether rustup lists `target` as installed; `None` if rustup cannot be queried.
+pub fn rustup_has_target(target: &str) -> Option<bool> {
+    let output = Command::new("rustup")
+        .args(["target", "list", "--installed"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(stdout.lines().any(|line| line.trim() == target))
+}
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use crate::cancel::CancelToken;
+use crate::features::{self, FeaturePolicy};
+
+/// Optimization levels accepted by `wasm-opt -O<level>`.
+pub const OPT_LEVELS: &[&str] = &["0", "1", "2", "3", "4", "s", "z"];
+
+/// Binaryen flag enabling each proposal of a kernel API feature set.
+const BINARYEN_FEATURES: &[(&str, &str)] = &[
+    ("sign_extension", "--enable-sign-ext"),
+    (
+        "saturating_float_to_int",
+        "--enable-nontrapping-float-to-int",
+    ),
+    ("bulk_memory", "--enable-bulk-memory"),
+    ("reference_types", "--enable-reference-types"),
+    ("simd", "--enable-simd"),
+];
+
+/// A Binaryen `wasm-opt` pass over the core module.
+#[derive(Debug, Clone)]
+pub struct WasmOpt {
+    /// One of [`OPT_LEVELS`].
+    pub level: String,
+
+    /// Extra arguments passed to `wasm-opt` verbatim.
+    pub args: Vec<String>,
+}
+
+/// Outcome of [`optimize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptReport {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl OptReport {
+    /// One-line summary for the packaging report.
+    pub fn summary(&self) -> String {
+        let delta = self.after as f64 - self.before as f64;
+        format!(
+            "{} -> {} bytes ({:+.1}%)",
+            self.before,
+            self.after,
+            delta * 100.0 / self.before.max(1) as f64
+        )
+    }
+}
+
+/// Run `wasm-opt` on a core module, using `work_dir` for its input and output files.
+///
+/// Only the proposals the target kernel API supports are enabled, so the
+/// optimizer cannot introduce instructions packaging would reject.
+pub fn optimize(
+    module: &[u8],
+    opt: &WasmOpt,
+    policy: &FeaturePolicy,
+    work_dir: &Path,
+    cancel: &CancelToken,
+) -> Result<(Vec<u8>, OptReport)> {
+    if !OPT_LEVELS.contains(&opt.level.as_str()) {
+        anyhow::bail!(
+            "Unknown optimization level '{}' (expected one of: {})",
+            opt.level,
+            OPT_LEVELS.join(", ")
+        );
+    }
+
+    let input = work_dir.join("pre-opt.wasm");
+    let output = work_dir.join("opt.wasm");
+    std::fs::write(&input, module)
+        .with_context(|| format!("Failed to write {}", input.display()))?;
+
+    let supported = features::kernel_api_features(&policy.kernel_api()).unwrap_or_default();
+    let mut command = Command::new("wasm-opt");
+    command.arg(format!("-O{}", opt.level));
+    for (feature, flag) in BINARYEN_FEATURES {
+        if supported.contains(feature) && !(*feature == "simd" && policy.deny_simd) {
+            command.arg(flag);
+        }
+    }
+    if policy.allow_threads {
+        command.arg("--enable-threads");
+    }
+    command.args(&opt.args).arg(&input).arg("-o").arg(&output);
+
+    let result = command.output();
+    cancel.check()?;
+    let result = result.context(
+        "Failed to run wasm-opt; install Binaryen (https://github.com/WebAssembly/binaryen) or drop the optimization level",
+    )?;
+    if !result.status.success() {
+        anyhow::bail!(
+            "wasm-opt failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        );
+    }
+
+    let optimized =
+        std::fs::read(&output).with_context(|| format!("Failed to read {}", output.display()))?;
+    let report = OptReport {
+        before: module.len(),
+        after: optimized.len(),
+    };
+    Ok((optimized, report))
+}
@@ -18,3 +18,2341 @@ fn test_build_missing_config() {
         .failure()
         .stderr(predicate::str::contains("Unable to resolve package name"));
 }
+
+#[test]
+fn test_check_rejects_unknown_sdk_channel() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "vtx_version = 1\n\n[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[sdk]\nchannel = \"edge\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown sdk.channel 'edge'"));
+}
+
+#[test]
+fn test_check_json_matrix_is_valid_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    let output = cmd
+        .current_dir(dir.path())
+        .args(["check", "--all-languages", "--json"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let reports = report["reports"].as_array().unwrap();
+    assert!(reports.iter().any(|r| r["language"] == "go"));
+    let tinygo = reports
+        .iter()
+        .flat_map(|r| r["checks"].as_array().unwrap())
+        .find(|c| c["tool"] == "tinygo")
+        .unwrap();
+    assert_eq!(tinygo["minimum_version"], "0.30.0");
+}
+
+#[test]
+fn test_check_matrix_lists_required_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["check", "--all-languages"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("REQUIRES"))
+        .stdout(predicate::str::is_match(r"go\s+tinygo\s+\S+\s+>= 0\.30").unwrap())
+        .stdout(predicate::str::is_match(r"python\s+python\s+\S+\s+>= 3\.10").unwrap());
+}
+
+#[test]
+fn test_doctor_fails_only_for_project_language() {
+    let dir = tempfile::tempdir().unwrap();
+    let empty_path = dir.path().join("bin");
+    std::fs::create_dir(&empty_path).unwrap();
+
+    // Without vtx.toml every missing tool is only a warning.
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .env("PATH", &empty_path)
+        .args(["doctor", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diagnoses = report["diagnoses"].as_array().unwrap();
+    assert!(diagnoses.iter().all(|d| d["status"] == "warn"));
+
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "vtx_version = 1\n\n[project]\nname = \"demo\"\nlanguage = \"go\"\n\n[sdk]\nadapter = \"1.0.0\"\n",
+    )
+    .unwrap();
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .env("PATH", &empty_path)
+        .args(["doctor", "--json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let failed: Vec<_> = report["diagnoses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|d| d["status"] == "fail")
+        .map(|d| d["item"].as_str().unwrap())
+        .collect();
+    assert_eq!(failed, ["tinygo", "wasi adapter"]);
+}
+
+#[test]
+fn test_quiet_package_still_writes_debug_log_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["-q", "--log-file", "vtx.log"])
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[WARN] Contract validation failed",
+        ))
+        .stdout(predicate::str::contains("[INFO]").not())
+        .stdout(predicate::str::contains("[DONE]").not());
+
+    let log = std::fs::read_to_string(dir.path().join("vtx.log")).unwrap();
+    assert!(log.contains("DEBUG package{input=demo.wasm}:"));
+    assert!(log.contains("validate:"));
+    assert!(log.contains("Package completed"));
+}
+
+#[test]
+fn test_package_removes_scratch_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let wasm = dir.path().join("demo.wasm");
+    std::fs::write(&wasm, [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force", "--debug"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Scratch directory"));
+
+    assert!(dir.path().join("demo.vtx").exists());
+    let leftovers = std::fs::read_dir(dir.path().join(".vtx-cache/tmp"))
+        .unwrap()
+        .count();
+    assert_eq!(leftovers, 0);
+}
+
+#[test]
+fn test_check_rejects_missing_project_path() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "vtx_version = 1\n\n[project]\nname = \"demo\"\npath = \"plugins/demo\"\nlanguage = \"lua\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("project.path 'plugins/demo'"));
+}
+
+#[test]
+fn test_check_accepts_bom_and_crlf_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "\u{feff}vtx_version = 1\r\n\r\n[project]\r\nname = \"demo\"\r\nlanguage = \"lua\"\r\n\r\n[sdk]\r\nchannel = \"edge\"\r\n",
+    )
+    .unwrap();
+
+    // Parsing succeeds; the failure comes from validation of the parsed content.
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown sdk.channel 'edge'"));
+}
+
+#[test]
+fn test_check_reports_config_parse_location() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "vtx_version = 1\r\n\r\n[project]\r\nname = 42\r\nlanguage = \"lua\"\r\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to parse vtx.toml:4:8"));
+}
+
+#[test]
+fn test_check_resolves_locked_workspace_sdk_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let member = dir.path().join("plugin");
+    std::fs::create_dir(&member).unwrap();
+    std::fs::write(
+        dir.path().join("Cargo.toml"),
+        "[workspace]\nmembers = [\"plugin\"]\n\n[workspace.dependencies]\nvtx-sdk = \"0.1\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        member.join("Cargo.toml"),
+        "[package]\nname = \"plugin\"\nversion = \"0.1.0\"\n\n[dependencies]\nvtx-sdk = { workspace = true }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        member.join("vtx.toml"),
+        "vtx_version = 1\n\n[project]\nname = \"plugin\"\nlanguage = \"rust\"\n",
+    )
+    .unwrap();
+    let lock = |version: &str| {
+        std::fs::write(
+            dir.path().join("Cargo.lock"),
+            format!("version = 3\n\n[[package]]\nname = \"vtx-sdk\"\nversion = \"{version}\"\n"),
+        )
+        .unwrap();
+    };
+
+    // A 0.1 requirement resolved to 0.1.0 is compatible with the bundled 0.1.x SDK.
+    lock("0.1.0");
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(&member)
+        .arg("check")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("SDK compatibility check passed (v0.1.0 locked, requirement 0.1)"),
+        "{stdout}"
+    );
+
+    lock("0.2.0");
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(&member)
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Plugin uses vtx-sdk 0.2.0 (locked in Cargo.lock)",
+        ));
+}
+
+#[test]
+fn test_check_reads_sdk_versions_of_other_languages() {
+    let cases = [
+        (
+            "ts",
+            "package.json",
+            "{\"dependencies\": {\"@vtx/sdk\": \">= 0.1.0 <0.2\"}}",
+            Ok("SDK compatibility check passed (>= 0.1.0 <0.2 accepts v"),
+        ),
+        (
+            "python",
+            "requirements.txt",
+            "# plugin deps\nvtx_sdk[http] ~= 0.1.0 ; python_version >= '3.10'\n",
+            Ok("SDK compatibility check passed (~= 0.1.0 accepts v"),
+        ),
+        (
+            "python",
+            "pyproject.toml",
+            "[project]\nname = \"demo\"\ndependencies = [\"vtx-sdk==0.3.*\"]\n",
+            Err("Plugin uses vtx-sdk ==0.3.*, but this CLI"),
+        ),
+        (
+            "go",
+            "go.mod",
+            "module demo\n\nrequire (\n\tgithub.com/vtxdeo/vtx-sdk-go v0.2.1 // sdk\n)\n",
+            Err("Plugin uses github.com/vtxdeo/vtx-sdk-go 0.2.1 (locked in go.mod)"),
+        ),
+    ];
+    for (language, manifest, content, expected) in cases {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(manifest), content).unwrap();
+        std::fs::write(
+            dir.path().join("vtx.toml"),
+            format!("vtx_version = 1\n\n[project]\nname = \"demo\"\nlanguage = \"{language}\"\n"),
+        )
+        .unwrap();
+
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .arg("check")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match expected {
+            Ok(message) => assert!(stdout.contains(message), "{manifest}: {stdout}"),
+            Err(message) => {
+                assert!(!output.status.success(), "{manifest} should fail");
+                assert!(stderr.contains(message), "{manifest}: {stderr}");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_schema_prints_vtx_toml_schema() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    let output = cmd.args(["schema", "vtx-toml"]).output().unwrap();
+
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["$id"], "urn:vtx:schema:vtx-toml:v1");
+    assert!(schema["properties"]["project"].is_object());
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .args(["schema", "test-fixture"])
+        .output()
+        .unwrap();
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["additionalProperties"], false);
+    for field in [
+        "export", "request", "response", "args", "result", "time", "seed",
+    ] {
+        assert!(schema["properties"][field].is_object(), "{field}");
+    }
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .args(["schema", "event"])
+        .output()
+        .unwrap();
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["$id"], "urn:vtx:schema:event:v1");
+    assert_eq!(schema["required"], serde_json::json!(["event", "time"]));
+}
+
+#[test]
+fn test_completions_and_man_pages_follow_cli_definitions() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("_vtx()"))
+        .stdout(predicate::str::contains("--deep-validate"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.args(["mangen"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(".ie"))
+        .stdout(predicate::str::contains(".TH vtx 1"));
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.args(["mangen", "--out-dir"])
+        .arg(dir.path())
+        .assert()
+        .success();
+    let build = std::fs::read_to_string(dir.path().join("vtx-build.1")).unwrap();
+    assert!(build.contains("vtx build"));
+    assert!(dir.path().join("vtx-ledger-show.1").exists());
+}
+
+#[test]
+fn test_package_emits_bare_component() {
+    let dir = tempfile::tempdir().unwrap();
+    let wasm = dir.path().join("demo.wasm");
+    std::fs::write(&wasm, [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args([
+            "package",
+            "--input",
+            "demo.wasm",
+            "--force",
+            "--emit-component",
+        ])
+        .assert()
+        .success();
+
+    let component = std::fs::read(dir.path().join("demo.component.wasm")).unwrap();
+    let vtx = std::fs::read(dir.path().join("demo.vtx")).unwrap();
+    assert_eq!(&component[..4], b"\0asm");
+    assert!(vtx.ends_with(&component));
+}
+
+#[test]
+fn test_build_rejects_target_past_eol() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "vtx_version = 1\n\n[project]\nname = \"demo\"\nlanguage = \"lua\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["build", "--target", "wasm32-wasi"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Target 'wasm32-wasi' is deprecated",
+        ))
+        .stderr(predicate::str::contains("Migrate to 'wasm32-wasip1'"));
+}
+
+#[test]
+fn test_ledger_detects_tampering() {
+    let dir = tempfile::tempdir().unwrap();
+    let wasm = dir.path().join("demo.wasm");
+    std::fs::write(&wasm, [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).unwrap();
+
+    for _ in 0..2 {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+        cmd.current_dir(dir.path())
+            .args(["package", "--input", "demo.wasm", "--force"])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["ledger", "verify"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 entries verified"));
+
+    let ledger = dir.path().join(".vtx-ledger.jsonl");
+    let content = std::fs::read_to_string(&ledger).unwrap();
+    std::fs::write(&ledger, content.replacen("demo.vtx", "evil.vtx", 1)).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["ledger", "verify"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("entry 0 was modified"));
+}
+
+#[test]
+fn test_release_bumps_project_version() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "vtx_version = 1\n\n[project]\nname = \"demo\"\nversion = \"0.1.9\" # keep\nlanguage = \"lua\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("release.toml"),
+        "[release]\nbump = \"patch\"\nsteps = [\"bump\"]\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("release")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0.1.9 -> 0.1.10"));
+
+    let config = std::fs::read_to_string(dir.path().join("vtx.toml")).unwrap();
+    assert!(config.contains("version = \"0.1.10\" # keep\n"));
+}
+
+#[test]
+fn test_release_dry_run_prints_the_generated_plan() {
+    let dir = tempfile::tempdir().unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["init", "--name", "demo", "--language", "go"])
+        .arg("--with-release-script")
+        .assert()
+        .success();
+    let project = dir.path().join("demo");
+    let release = std::fs::read_to_string(project.join("release.toml")).unwrap();
+    assert!(release.contains(
+        "steps = [\"bump\", \"check\", \"test\", \"build\", \"sign\", \"sbom\", \"publish\"]"
+    ));
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(&project)
+        .args(["release", "--dry-run", "--skip-sign"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let plan: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip_while(|line| !line.contains("Release plan"))
+        .skip(1)
+        .map(|line| line.split_whitespace().take(2).collect())
+        .collect();
+    assert_eq!(
+        plan,
+        [
+            ["bump", "runs"],
+            ["check", "runs"],
+            ["test", "runs"],
+            ["build", "runs"],
+            ["sign", "skipped"],
+            ["sbom", "runs"],
+            ["publish", "asks"],
+        ]
+    );
+    let config = std::fs::read_to_string(project.join("vtx.toml")).unwrap();
+    assert!(config.contains("version = \"0.1.0\""));
+}
+
+#[test]
+fn test_release_resume_skips_completed_steps() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "vtx_version = 1\n\n[project]\nname = \"demo\"\nversion = \"0.1.0\"\nlanguage = \"lua\"\n\n[sdk]\nadapter = \"1.0.0\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("release.toml"),
+        "[release]\nsteps = [\"bump\", \"check\"]\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("release")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Release step 'check' failed"))
+        .stderr(predicate::str::contains("vtx release --resume"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["release", "--resume"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("skipped (done)"));
+
+    let config = std::fs::read_to_string(dir.path().join("vtx.toml")).unwrap();
+    assert!(config.contains("version = \"0.1.1\""));
+}
+
+#[test]
+fn test_assets_add_and_verify() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[assets]\nmax_file_bytes = 16\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.path().join("assets/css")).unwrap();
+    std::fs::write(dir.path().join("assets/index.js"), "console.log(1)").unwrap();
+    std::fs::write(dir.path().join("assets/css/app.css"), "body{}").unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["assets", "add", "index.js", "css"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 assets declared"));
+
+    std::fs::write(dir.path().join("assets/index.js"), "console.log(2)").unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["assets", "verify"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("index.js was modified"));
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["verify", "demo.vtx", "--against", "3.4.2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("index.js").not());
+
+    std::fs::write(dir.path().join("assets/big.json"), "[0,1,2,3,4,5,6,7,8,9]").unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["assets", "add", "assets/big.json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("over assets.max_file_bytes"));
+}
+
+#[test]
+fn test_package_bundles_declared_assets() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"lua\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.path().join("assets/css")).unwrap();
+    let css = "body { margin: 0; }\n".repeat(32);
+    std::fs::write(dir.path().join("assets/css/app.css"), &css).unwrap();
+    std::fs::write(dir.path().join("assets/logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["assets", "add", "css", "logo.png"])
+        .assert()
+        .success();
+
+    // By default the container stays v2, which has no asset section.
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "2 declared assets not bundled: pass --container-sections",
+        ));
+    let bytes = std::fs::read(dir.path().join("demo.vtx")).unwrap();
+    assert_eq!(&bytes[..4], b"VTX\x02");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args([
+            "package",
+            "--input",
+            "demo.wasm",
+            "--force",
+            "--container-sections",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Assets bundled: 2 files"));
+
+    let bytes = std::fs::read(dir.path().join("demo.vtx")).unwrap();
+    assert_eq!(&bytes[..4], b"VTX\x04");
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["inspect", "demo.vtx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "css/app.css (640 bytes; br, gzip, identity)",
+        ))
+        .stdout(predicate::str::contains("logo.png (4 bytes; identity)"));
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["unpack", "demo.vtx", "--out-dir", "out"])
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("out/demo.assets/css/app.css")).unwrap(),
+        css
+    );
+
+    // A modified asset is not packaged.
+    std::fs::write(dir.path().join("assets/logo.png"), b"GIF89a").unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args([
+            "package",
+            "--input",
+            "demo.wasm",
+            "--force",
+            "--container-sections",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("logo.png was modified"));
+}
+
+#[test]
+fn test_check_reports_mismatched_i18n_keys() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[i18n]\nlocales = [\"en\", \"zh\"]\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.path().join("locales")).unwrap();
+    std::fs::write(
+        dir.path().join("locales/en.json"),
+        r#"{"title": "Demo", "menu": {"open": "Open"}}"#,
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("locales/zh.json"), r#"{"title": "演示"}"#).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("zh is missing 1 keys: menu.open"));
+}
+
+#[test]
+fn test_check_validates_settings_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[settings]\nschema = \"settings.schema.json\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("settings.schema.json"),
+        r#"{
+            "type": "object",
+            "properties": {
+                "interval": { "type": "integer", "minimum": 1, "default": 0 }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("/interval default"));
+}
+
+#[test]
+fn test_check_resolves_settings_schema_refs() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[settings]\nschema = \"settings.schema.json\"\n",
+    )
+    .unwrap();
+    let schema = |default: &str| {
+        format!(
+            r##"{{
+                "type": "object",
+                "definitions": {{
+                    "port": {{ "type": "integer", "minimum": 1, "maximum": 65535 }}
+                }},
+                "properties": {{
+                    "ports": {{
+                        "type": "array",
+                        "items": {{ "$ref": "#/definitions/port" }},
+                        "default": {default}
+                    }},
+                    "admin": {{ "$ref": "#/definitions/port", "default": 8081 }}
+                }}
+            }}"##
+        )
+    };
+
+    std::fs::write(dir.path().join("settings.schema.json"), schema("[80, 443]")).unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("check")
+        .assert()
+        .stdout(predicate::str::contains("Settings schema validated"));
+
+    std::fs::write(dir.path().join("settings.schema.json"), schema("[80, 0]")).unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("/ports default at /1"));
+}
+
+#[test]
+fn test_check_workspace_checks_each_member() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"rust\"\n\n[build]\ncmd = \"true\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("Cargo.toml"),
+        "[workspace]\nmembers = [\"alpha\", \"beta\", \"gamma\"]\n",
+    )
+    .unwrap();
+    for (member, deps) in [
+        ("alpha", "vtx-sdk = \"0.0.1\"\n"),
+        ("beta", ""),
+        ("gamma", "vtx-sdk = \"0.0.1\"\n"),
+    ] {
+        std::fs::create_dir_all(dir.path().join(member).join("src")).unwrap();
+        std::fs::write(dir.path().join(member).join("src/lib.rs"), "").unwrap();
+        std::fs::write(
+            dir.path().join(member).join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{member}\"\nversion = \"0.1.0\"\n\n[dependencies]\n{deps}"
+            ),
+        )
+        .unwrap();
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["check", "--workspace", "--exclude", "gamma"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Checking member beta"))
+        .stdout(predicate::str::contains("Checking member gamma").not())
+        .stderr(predicate::str::contains("1 of 2 workspace members failed"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["check", "--package", "beta"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_size_history_lists_recorded_builds() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".vtx-cache")).unwrap();
+    let record = |commit: &str, size: u64| {
+        format!(
+            r#"{{"timestamp":"2026-01-01T00:00:00Z","commit":"{commit}","dirty":false,"package":"demo","profile":"release","artifact_size":{size},"component_size":{size}}}"#
+        )
+    };
+    std::fs::write(
+        dir.path().join(".vtx-cache/size-history.jsonl"),
+        [record("aaa1111", 1000), record("bbb2222", 1500)].join("\n"),
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["size", "history"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bbb2222"))
+        .stdout(predicate::str::contains("+500"))
+        .stdout(predicate::str::contains("2 builds: ▁█"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["size", "history", "--csv", "--limit", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bbb2222"))
+        .stdout(predicate::str::contains("aaa1111").not());
+}
+
+#[test]
+fn test_bench_history_lists_recorded_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".vtx-cache")).unwrap();
+    let record = |commit: &str, file: &str, p99: f64| {
+        format!(
+            r#"{{"timestamp":"2026-01-01T00:00:00Z","commit":"{commit}","dirty":false,"file":"{file}","request":"GET /","iterations":200,"cold_start_ms":20.0,"p50_ms":0.5,"p99_ms":{p99},"memory_bytes":65536}}"#
+        )
+    };
+    std::fs::write(
+        dir.path().join(".vtx-cache/bench-history.jsonl"),
+        [
+            record("aaa1111", "demo.vtx", 1.0),
+            record("bbb2222", "demo.vtx", 1.5),
+            record("ccc3333", "other.vtx", 9.0),
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["bench", "history", "--file", "demo.vtx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bbb2222"))
+        .stdout(predicate::str::contains("+50.0%"))
+        .stdout(predicate::str::contains("ccc3333").not())
+        .stdout(predicate::str::contains("2 runs, p99_ms: ▁█"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["bench", "history", "--metric", "memory_bytes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("3 runs, memory_bytes: ▁▁▁"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["bench", "history", "--csv", "--limit", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "ccc3333,false,other.vtx,GET /,200,20,0.5,9,65536",
+        ))
+        .stdout(predicate::str::contains("bbb2222").not());
+}
+
+#[test]
+fn test_init_renders_builtin_and_git_templates() {
+    let dir = tempfile::tempdir().unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["init", "--name", "go-demo", "--language", "go"])
+        .args(["--author", "Ada", "--license", "Apache-2.0"])
+        .assert()
+        .success();
+    let vtx_toml = std::fs::read_to_string(dir.path().join("go-demo/vtx.toml")).unwrap();
+    assert!(vtx_toml.contains("language = \"go\""));
+    assert!(vtx_toml.contains("name = \"Ada\""));
+    assert!(vtx_toml.contains("license = \"Apache-2.0\""));
+    assert!(dir.path().join("go-demo/main.go").exists());
+
+    // A template repository with placeholders in paths and contents.
+    let template = dir.path().join("template");
+    std::fs::create_dir_all(template.join("src/{{name_snake}}")).unwrap();
+    std::fs::write(
+        template.join("vtx.toml"),
+        "[project]\nname = \"{{ name }}\"\nlanguage = \"lua\"\nlicense = \"{{license}}\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        template.join("src/{{name_snake}}/main.lua"),
+        "-- {{name}}: {{ .Kept }} {{unknown_var}}\n",
+    )
+    .unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(&template)
+            .env("GIT_AUTHOR_NAME", "t")
+            .env("GIT_AUTHOR_EMAIL", "t@example.com")
+            .env("GIT_COMMITTER_NAME", "t")
+            .env("GIT_COMMITTER_EMAIL", "t@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["add", "-A"]);
+    git(&["commit", "-qm", "template"]);
+
+    let url = format!("file://{}", template.display());
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["init", "--name", "lua-demo", "--template", &url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "left unrendered (unknown variables): unknown_var",
+        ));
+    let project = dir.path().join("lua-demo");
+    assert!(!project.join(".git").exists());
+    assert_eq!(
+        std::fs::read_to_string(project.join("vtx.toml")).unwrap(),
+        "[project]\nname = \"lua-demo\"\nlanguage = \"lua\"\nlicense = \"MIT\"\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(project.join("src/lua_demo/main.lua")).unwrap(),
+        "-- lua-demo: {{ .Kept }} {{unknown_var}}\n"
+    );
+}
+
+#[test]
+fn test_bisect_finds_commit_breaking_build() {
+    let dir = tempfile::tempdir().unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir.path())
+            .env("GIT_AUTHOR_NAME", "t")
+            .env("GIT_AUTHOR_EMAIL", "t@example.com")
+            .env("GIT_COMMITTER_NAME", "t")
+            .env("GIT_COMMITTER_EMAIL", "t@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    let write_config = |cmd: &str| {
+        std::fs::write(
+            dir.path().join("vtx.toml"),
+            format!(
+                "[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[build]\ncmd = \"{cmd}\"\noutput_dir = \"out\"\nartifact = \"demo.wasm\"\n"
+            ),
+        )
+        .unwrap();
+    };
+
+    git(&["init", "-q"]);
+    std::fs::write(
+        dir.path().join(".gitignore"),
+        ".vtx-cache/\nout/\n*.vtx\n.vtx-ledger.jsonl\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    write_config("mkdir -p out && cp demo.wasm out/demo.wasm");
+    git(&["add", "-A"]);
+    git(&["commit", "-qm", "good"]);
+    std::fs::write(dir.path().join("README"), "docs").unwrap();
+    git(&["add", "-A"]);
+    git(&["commit", "-qm", "docs"]);
+    write_config("exit 1");
+    git(&["commit", "-qam", "break build"]);
+    std::fs::write(dir.path().join("README"), "more docs").unwrap();
+    git(&["commit", "-qam", "more docs"]);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["bisect", "--good", "HEAD~3", "--build-fails", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("break build"));
+}
+
+#[test]
+fn test_package_applies_artifact_name_template() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nversion = \"1.2.3\"\nlanguage = \"lua\"\n\n[package]\nartifact_name = \"{name}-{version}-{target}-{profile}.vtx\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("input.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "input.wasm", "--force"])
+        .assert()
+        .success();
+    assert!(dir
+        .path()
+        .join("demo-1.2.3-wasm32-wasip1-release.vtx")
+        .exists());
+
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[package]\nartifact_name = \"{name}-{arch}.vtx\"\n",
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "input.wasm", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown placeholder '{arch}'"));
+}
+
+#[test]
+fn test_hash_matches_ledger_and_checks_sums() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    let ledger = std::fs::read_to_string(dir.path().join(".vtx-ledger.jsonl")).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(ledger.lines().next().unwrap()).unwrap();
+    let digest = entry["artifact_digest"].as_str().unwrap().to_string();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["hash", "demo.vtx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("container: {digest}")))
+        .stdout(predicate::str::contains("metadata:  sha256:"));
+
+    let hex = digest.trim_start_matches("sha256:");
+    std::fs::write(
+        dir.path().join("SHA256SUMS"),
+        format!("{hex}  demo.vtx\n{hex}  demo.wasm\n"),
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["hash", "--check", "SHA256SUMS"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("demo.vtx: "))
+        .stderr(predicate::str::contains("1 of 2 files did not match"));
+}
+
+#[test]
+fn test_inspect_provenance_links_ledger_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["inspect", "demo.vtx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("requires: none"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["inspect", "demo.vtx", "--provenance"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("built: "))
+        .stdout(predicate::str::contains("(ledger #0)"))
+        .stdout(predicate::str::contains("signatures: none (unsigned)"));
+}
+
+#[test]
+fn test_unpack_repair_salvages_truncated_artifact() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    let vtx = std::fs::read(dir.path().join("demo.vtx")).unwrap();
+    std::fs::write(dir.path().join("damaged.vtx"), &vtx[..vtx.len() - 3]).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["unpack", "damaged.vtx"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--repair"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["unpack", "damaged.vtx", "--repair", "--out-dir", "out"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Metadata intact"))
+        .stdout(predicate::str::contains("Lost "));
+    assert!(dir.path().join("out/damaged.metadata.json").exists());
+    assert!(dir.path().join("out/damaged.partial.wasm").exists());
+}
+
+#[test]
+fn test_package_digest_algorithm_from_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[package]\ndigest = \"sha512\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    let ledger = std::fs::read_to_string(dir.path().join(".vtx-ledger.jsonl")).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(ledger.lines().next().unwrap()).unwrap();
+    let digest = entry["artifact_digest"].as_str().unwrap().to_string();
+    assert!(digest.starts_with("sha512:"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["hash", "demo.vtx", "--algorithm", "sha512"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("container: {digest}")));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["inspect", "demo.vtx", "--provenance"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(ledger #0)"));
+}
+
+#[test]
+fn test_package_sbom_writes_checksum_and_npm_components() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nversion = \"1.2.0\"\nlanguage = \"ts\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("package-lock.json"),
+        r#"{"lockfileVersion": 3, "packages": {
+            "": {"name": "demo", "version": "1.2.0"},
+            "node_modules/left-pad": {"version": "1.3.0", "license": "WTFPL"},
+            "node_modules/@scope/util": {"version": "2.0.1"}
+        }}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force", "--sbom"])
+        .assert()
+        .success();
+
+    let checksum = std::fs::read_to_string(dir.path().join("demo.vtx.sha256")).unwrap();
+    let (hex, name) = checksum.trim_end().split_once("  ").unwrap();
+    assert_eq!(name, "demo.vtx");
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["hash", "demo.vtx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("container: sha256:{hex}")));
+
+    let sbom: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.path().join("demo.vtx.cdx.json")).unwrap())
+            .unwrap();
+    assert_eq!(sbom["bomFormat"], "CycloneDX");
+    assert_eq!(sbom["metadata"]["component"]["version"], "1.2.0");
+    assert_eq!(sbom["metadata"]["component"]["hashes"][0]["content"], hex);
+    let purls: Vec<&str> = sbom["components"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["purl"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        purls,
+        ["pkg:npm/%40scope/util@2.0.1", "pkg:npm/left-pad@1.3.0"]
+    );
+    assert_eq!(sbom["components"][1]["licenses"][0]["expression"], "WTFPL");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["inspect", "demo.vtx", "--provenance"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("demo.vtx.cdx.json (cyclonedx)"))
+        .stdout(predicate::str::contains(format!(
+            "artifact checksum: sha256:{hex} (matches)"
+        )));
+}
+
+#[test]
+fn test_inspect_json_lists_metadata_and_payload() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    let output = cmd
+        .current_dir(dir.path())
+        .args(["inspect", "demo.vtx", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["version"], 2);
+    assert_eq!(report["metadata"]["tool"]["name"], "vtx-cli");
+    assert!(report["exports"].is_array());
+    assert!(report["payload_size"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn test_compressed_encrypted_payload_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("payload.key"), [7u8; 32]).unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args([
+            "package",
+            "--input",
+            "demo.wasm",
+            "--force",
+            "--compress",
+            "zstd:19",
+            "--encrypt-key",
+            "payload.key",
+        ])
+        .assert()
+        .success();
+
+    // The payload cannot be read without the key, but the metadata can.
+    let bytes = std::fs::read(dir.path().join("demo.vtx")).unwrap();
+    let decoded = vtx_format::decode_with_metadata(&bytes).unwrap();
+    let metadata: serde_json::Value = serde_json::from_slice(decoded.metadata.unwrap()).unwrap();
+    assert_eq!(metadata["payload"]["compression"], "zstd");
+    assert_eq!(metadata["payload"]["encryption"], "aes-256-gcm");
+    assert!(!decoded.component.starts_with(b"\0asm"));
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["inspect", "demo.vtx"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "pass --key-file or set VTX_PAYLOAD_KEY",
+        ));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    let output = cmd
+        .current_dir(dir.path())
+        .args(["inspect", "demo.vtx", "--json", "--key-file", "payload.key"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(report["payload_size"].as_u64().unwrap() > 0);
+    assert_eq!(
+        report["stored_payload_size"].as_u64().unwrap(),
+        decoded.component.len() as u64
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .env("VTX_PAYLOAD_KEY", "payload.key")
+        .args(["validate", "demo.vtx"])
+        .assert()
+        .stdout(predicate::str::contains("zstd + aes-256-gcm"))
+        .stdout(predicate::str::contains("well-formed component"));
+}
+
+#[test]
+fn test_validate_reports_each_check_for_vtx_and_raw_wasm() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    let validate = |args: &[&str]| {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .arg("validate")
+            .args(args)
+            .arg("--json")
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        report["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| {
+                format!(
+                    "{}={}",
+                    c["check"].as_str().unwrap(),
+                    c["status"].as_str().unwrap()
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        validate(&["demo.vtx", "--strict"]),
+        [
+            "container=pass",
+            "component=pass",
+            "contract=fail",
+            "metadata=pass",
+            "metadata schema=pass"
+        ]
+    );
+    assert_eq!(
+        validate(&["demo.wasm"]),
+        ["container=pass", "component=fail", "metadata=warn"]
+    );
+}
+
+#[test]
+fn test_package_embeds_capabilities() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let config = |host: &str| {
+        format!(
+            "vtx_version = 1\n\n[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[capabilities]\npermissions = [\"sql\", \"network\"]\nnetwork = [\"{host}\"]\nfilesystem = [\"/data/demo/**\"]\nsubscriptions = [\"media.uploaded\"]\n"
+        )
+    };
+    std::fs::write(dir.path().join("vtx.toml"), config("*.example.com:443")).unwrap();
+    let inspect = || {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .args(["inspect", "demo.vtx", "--json"])
+            .output()
+            .unwrap();
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    // By default they go into the metadata of a v2 container.
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+    let report = inspect();
+    assert_eq!(report["version"], 2);
+    assert_eq!(
+        report["metadata"]["capabilities"]["subscriptions"],
+        serde_json::json!(["media.uploaded"])
+    );
+    assert!(report["metadata"]["capabilities_digest"].is_null());
+    assert_eq!(
+        report["capabilities"]["permissions"],
+        serde_json::json!(["sql", "network"])
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args([
+            "package",
+            "--input",
+            "demo.wasm",
+            "--force",
+            "--container-sections",
+        ])
+        .assert()
+        .success();
+    let report = inspect();
+    assert_eq!(report["version"], 3);
+    assert!(report["metadata"]["capabilities"].is_null());
+    let capabilities = &report["capabilities"];
+    assert!(report["metadata"]["capabilities_digest"]
+        .as_str()
+        .unwrap()
+        .starts_with("sha256:"));
+    assert_eq!(
+        capabilities["permissions"],
+        serde_json::json!(["sql", "network"])
+    );
+    assert_eq!(
+        capabilities["network"],
+        serde_json::json!(["*.example.com:443"])
+    );
+    assert_eq!(
+        capabilities["filesystem"],
+        serde_json::json!(["/data/demo/**"])
+    );
+
+    // The section is bound to the metadata, so editing it is detected.
+    let path = dir.path().join("demo.vtx");
+    let bytes = std::fs::read(&path).unwrap();
+    let at = bytes
+        .windows(b"media.uploaded".len())
+        .position(|w| w == b"media.uploaded")
+        .unwrap();
+    let mut tampered = bytes.clone();
+    tampered[at] = b'M';
+    std::fs::write(&path, tampered).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["inspect", "demo.vtx"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Capabilities section was modified",
+        ));
+
+    std::fs::write(dir.path().join("vtx.toml"), config("bad host")).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid capabilities.network host 'bad host'",
+        ));
+}
+
+#[test]
+fn test_sign_and_verify_detect_tampering() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["keygen", "release"])
+        .assert()
+        .success();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["verify", "demo.vtx", "--public-key", "release.pub"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not signed"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["sign", "demo.vtx", "--key", "release.pk8"])
+        .assert()
+        .success();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["verify", "demo.vtx", "--public-key", "release.pub"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "demo.vtx manifest is signed by ed25519:",
+        ))
+        .stdout(predicate::str::contains("demo.vtx is signed by ed25519:"));
+
+    let ledger = std::fs::read_to_string(dir.path().join(".vtx-ledger.jsonl")).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(ledger.lines().last().unwrap()).unwrap();
+    assert!(entry["signer"].as_str().unwrap().starts_with("ed25519:"));
+
+    let mut vtx = std::fs::read(dir.path().join("demo.vtx")).unwrap();
+    *vtx.last_mut().unwrap() ^= 0xff;
+    std::fs::write(dir.path().join("demo.vtx"), vtx).unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["verify", "demo.vtx", "--public-key", "release.pub"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("modified after signing"));
+
+    // The manifest signature does not cover the component.
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args([
+            "verify",
+            "demo.vtx",
+            "--public-key",
+            "release.pub",
+            "--manifest-only",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("manifest is signed by ed25519:"))
+        .stdout(predicate::str::contains("demo.vtx is signed").not());
+}
+
+#[test]
+fn test_ecdsa_p256_signatures_round_trip_under_the_signature_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["keygen", "release", "--algorithm", "ecdsa-p256"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Generated ecdsa-p256:"));
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["keygen", "other"])
+        .assert()
+        .success();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["sign", "demo.vtx", "--key", "release.pk8"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("with ecdsa-p256:"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["verify", "demo.vtx", "--public-key", "release.pub"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "demo.vtx manifest is signed by ecdsa-p256:",
+        ))
+        .stdout(predicate::str::contains(
+            "demo.vtx is signed by ecdsa-p256:",
+        ));
+
+    // An ed25519 key cannot vouch for an ecdsa-p256 signature.
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["verify", "demo.vtx", "--public-key", "other.pub"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "signed with ecdsa-p256, but the given key is an ed25519 key",
+        ));
+
+    // A policy that only allows ed25519 rejects the signature outright.
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"rust\"\n\n[package]\nsignature_algorithms = [\"ed25519\"]\n",
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["verify", "demo.vtx", "--public-key", "release.pub"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Signature algorithm ecdsa-p256 is not allowed by the signature policy (allowed: ed25519)",
+        ));
+
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"rust\"\n\n[package]\nsignature_algorithms = [\"rsa\"]\n",
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["verify", "demo.vtx", "--public-key", "release.pub"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "unknown signature algorithm 'rsa'",
+        ));
+}
+
+#[test]
+fn test_attest_combines_signature_sbom_and_provenance() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let vtx = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+        cmd.current_dir(dir.path()).env_remove("VTX_SIGNING_KEY");
+        cmd
+    };
+    let attest = |key: &str| {
+        let output = vtx()
+            .args(["attest", "demo.vtx", "--json", "--public-key", key])
+            .output()
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let failed: Vec<String> = report["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|c| c["status"] == "fail")
+            .map(|c| format!("{}: {}", c["check"].as_str().unwrap(), c["detail"]))
+            .collect();
+        assert_eq!(output.status.success(), failed.is_empty(), "{report}");
+        assert_eq!(report["verified"], failed.is_empty());
+        failed
+    };
+    vtx().args(["keygen", "release"]).assert().success();
+    vtx().args(["keygen", "other"]).assert().success();
+    vtx()
+        .args(["package", "--input", "demo.wasm", "--force", "--sbom"])
+        .env("VTX_SIGNING_KEY", "release.pk8")
+        .assert()
+        .success();
+
+    vtx()
+        .args(["attest", "demo.vtx", "--public-key", "release.pub"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("signed by ed25519:"))
+        .stdout(predicate::str::contains("demo.vtx.cdx.json (cyclonedx"))
+        .stdout(predicate::str::contains("ledger #0"))
+        .stdout(predicate::str::contains(
+            "demo.vtx is attested: signature, SBOM and provenance verified",
+        ));
+    assert!(attest("release.pub").is_empty());
+
+    // Each attestation fails on its own.
+    let failed = attest("other.pub");
+    assert_eq!(failed.len(), 1, "{failed:?}");
+    assert!(failed[0].starts_with("signature: "), "{failed:?}");
+    vtx()
+        .args(["attest", "demo.vtx"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("no public key"))
+        .stderr(predicate::str::contains(
+            "demo.vtx failed 1 attestation check(s)",
+        ));
+
+    let sbom_path = dir.path().join("demo.vtx.cdx.json");
+    let sbom = std::fs::read_to_string(&sbom_path).unwrap();
+    let hex = std::fs::read_to_string(dir.path().join("demo.vtx.sha256")).unwrap();
+    let hex = hex.split_whitespace().next().unwrap();
+    std::fs::write(&sbom_path, sbom.replace(hex, &"0".repeat(64))).unwrap();
+    let failed = attest("release.pub");
+    assert_eq!(failed.len(), 1, "{failed:?}");
+    assert!(
+        failed[0].starts_with("sbom: ") && failed[0].contains("describes sha256:0000"),
+        "{failed:?}"
+    );
+    std::fs::remove_file(&sbom_path).unwrap();
+    let failed = attest("release.pub");
+    assert!(
+        failed[0].contains("no SBOM next to the artifact"),
+        "{failed:?}"
+    );
+    std::fs::write(&sbom_path, &sbom).unwrap();
+    std::fs::remove_file(dir.path().join("demo.vtx.sha256")).unwrap();
+    let failed = attest("release.pub");
+    assert!(failed[0].contains("no .sha256 checksum"), "{failed:?}");
+    vtx()
+        .args(["package", "--input", "demo.wasm", "--force", "--sbom"])
+        .env("VTX_SIGNING_KEY", "release.pk8")
+        .assert()
+        .success();
+    assert!(attest("release.pub").is_empty());
+
+    let ledger_path = dir.path().join(".vtx-ledger.jsonl");
+    let ledger = std::fs::read_to_string(&ledger_path).unwrap();
+    std::fs::write(&ledger_path, ledger.replacen("\"seq\":0", "\"seq\":7", 1)).unwrap();
+    let failed = attest("release.pub");
+    assert_eq!(failed.len(), 1, "{failed:?}");
+    assert!(
+        failed[0].starts_with("provenance: ") && failed[0].contains("Ledger Verification Failed"),
+        "{failed:?}"
+    );
+    std::fs::remove_file(&ledger_path).unwrap();
+    let failed = attest("release.pub");
+    assert!(failed[0].contains("no ledger"), "{failed:?}");
+
+    // An unsigned artifact fails closed even with its SBOM and ledger entry.
+    vtx()
+        .args(["package", "--input", "demo.wasm", "--force", "--sbom"])
+        .assert()
+        .success();
+    let failed = attest("release.pub");
+    assert_eq!(failed.len(), 1, "{failed:?}");
+    assert!(
+        failed[0].starts_with("signature: ") && failed[0].contains("not signed"),
+        "{failed:?}"
+    );
+}
+
+#[test]
+fn test_verify_directory_reports_a_matrix() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["keygen", "release"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["sign", "demo.vtx", "--key", "release.pk8"])
+        .assert()
+        .success();
+    let mirror = dir.path().join("mirror");
+    std::fs::create_dir(&mirror).unwrap();
+    let signed = std::fs::read(dir.path().join("demo.vtx")).unwrap();
+    for name in ["a", "b", "c"] {
+        std::fs::write(mirror.join(format!("{name}.vtx")), &signed).unwrap();
+    }
+    std::fs::write(mirror.join("notes.txt"), "not an artifact").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args([
+            "verify",
+            "mirror",
+            "--public-key",
+            "release.pub",
+            "--jobs",
+            "2",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Verified 3 artifacts"));
+
+    let mut tampered = signed.clone();
+    *tampered.last_mut().unwrap() ^= 0xff;
+    std::fs::write(mirror.join("b.vtx"), tampered).unwrap();
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["verify", "mirror", "--public-key", "release.pub"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 of 3 artifacts failed verification"));
+    let row = |name: &str| {
+        let file = format!("mirror/{name}.vtx ");
+        stdout
+            .lines()
+            .find(|line| line.trim_start().starts_with(&file))
+            .map(|line| line.split_whitespace().skip(1).collect::<Vec<_>>())
+            .unwrap()
+    };
+    let header = stdout
+        .lines()
+        .find(|line| line.trim_start().starts_with("FILE "))
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .unwrap();
+    assert_eq!(header, ["FILE", "MANIFEST", "PAYLOAD", "KERNEL", "RESULT"]);
+    assert_eq!(row("a"), ["ok", "ok", "-", "ok"]);
+    assert_eq!(row("b"), ["ok", "failed", "-", "failed"]);
+    assert!(stdout.contains("b.vtx: Signature does not match"));
+}
+
+#[test]
+fn test_verify_against_checks_required_interfaces() {
+    let dir = tempfile::tempdir().unwrap();
+    let component =
+        wat::parse_str(r#"(component (import "vtx:api/sql@3.4.0" (instance)))"#).unwrap();
+    std::fs::write(dir.path().join("demo.wasm"), component).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["verify", "demo.vtx", "--against", "3.4.2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "demo.vtx is compatible with vtx:api@3.4.2",
+        ));
+    for (kernel, reason) in [
+        ("3.3.9", "vtx:api/sql@3.4.0: kernel provides 3.3.9"),
+        ("4.0.0", "vtx:api/sql@3.4.0: kernel provides 4.0.0"),
+        ("3.4", "Invalid kernel API version '3.4'"),
+    ] {
+        Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .args(["verify", "demo.vtx", "--against", kernel])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(reason));
+    }
+}
+
+#[test]
+fn test_package_reuses_cache_until_cleaned() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"rust\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Packaging cache hit").not());
+    let first = std::fs::read(dir.path().join("demo.vtx")).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Packaging cache hit"));
+    assert_eq!(std::fs::read(dir.path().join("demo.vtx")).unwrap(), first);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force", "--no-cache"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Packaging cache hit").not());
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("clean")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 cached components"));
+    assert!(!dir.path().join(".vtx-cache/packaging").exists());
+}
+
+#[test]
+fn test_clean_scopes_artifacts_intermediates_and_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let manifest =
+        "[project]\nname = \"demo\"\nlanguage = \"ts\"\n\n[package]\nout_dir = \"out\"\n";
+    std::fs::write(root.join("vtx.toml"), manifest).unwrap();
+    std::fs::write(
+        root.join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    // Recorded in the ledger, outside the configured out_dir.
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(root)
+        .args([
+            "package",
+            "--input",
+            "demo.wasm",
+            "--out-dir",
+            "pkg",
+            "--force",
+        ])
+        .assert()
+        .success();
+    let packaged = std::fs::metadata(root.join("pkg/demo.vtx")).unwrap().len();
+    std::fs::write(
+        root.join("vtx.toml"),
+        format!(
+            "{manifest}\n[dependencies]\nauth = {{ path = \"deps/auth.vtx\" }}\ntheme = {{ path = \"dist/theme.vtx\" }}\n"
+        ),
+    )
+    .unwrap();
+    for (path, content) in [
+        ("out/index.vtx", "vtx"),
+        ("out/index.vtx.sha256", "sum"),
+        ("out/index.component.wasm", "component"),
+        ("dist/index.wasm", "wasm"),
+        ("dist/theme.vtx", "dependency"),
+        ("deps/auth.vtx", "dependency"),
+        ("fixtures/golden.vtx", "fixture"),
+        ("demo.vtx", "unrecorded"),
+        ("build/out.js", "js"),
+        (".vtx-cache/size-history.jsonl", "{}"),
+    ] {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    let clean = |args: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+        cmd.current_dir(root)
+            .arg("clean")
+            .args(args)
+            .assert()
+            .success()
+    };
+
+    clean(&["--artifacts-only"]).stdout(predicate::str::contains(format!(
+        "Reclaimed {} bytes",
+        packaged + 15
+    )));
+    for removed in [
+        "pkg/demo.vtx",
+        "out/index.vtx",
+        "out/index.vtx.sha256",
+        "out/index.component.wasm",
+    ] {
+        assert!(!root.join(removed).exists(), "{removed} should be removed");
+    }
+    for kept in [
+        "deps/auth.vtx",
+        "dist/theme.vtx",
+        "fixtures/golden.vtx",
+        "demo.vtx",
+        "demo.wasm",
+        "dist/index.wasm",
+        ".vtx-cache/packaging",
+    ] {
+        assert!(root.join(kept).exists(), "{kept} should be kept");
+    }
+
+    // dist/ holds a declared dependency, so only build/ goes.
+    clean(&[])
+        .stdout(predicate::str::contains("Removed 1 cached components"))
+        .stdout(predicate::str::contains("it holds the declared dependency"));
+    assert!(!root.join("build").exists());
+    assert!(root.join("dist/theme.vtx").exists());
+    assert!(!root.join(".vtx-cache/packaging").exists());
+    assert!(root.join(".vtx-cache/size-history.jsonl").exists());
+
+    clean(&["--all"]).stdout(predicate::str::contains("Reclaimed 2 bytes"));
+    assert!(!root.join(".vtx-cache").exists());
+    assert!(root.join(".vtx-ledger.jsonl").exists());
+    assert!(root.join("deps/auth.vtx").exists());
+    assert!(root.join("fixtures/golden.vtx").exists());
+
+    clean(&[]).stdout(predicate::str::contains("Nothing to clean"));
+}
+
+#[test]
+fn test_clean_requires_vtx_toml() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("golden.vtx"), "fixture").unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("clean")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'vtx.toml' not found"));
+    assert!(dir.path().join("golden.vtx").exists());
+}
+
+#[test]
+fn test_output_json_emits_only_json_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let events = |args: &[&str], success: bool| -> Vec<serde_json::Value> {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+        let assert = cmd.current_dir(dir.path()).args(args).assert();
+        let assert = if success {
+            assert.success()
+        } else {
+            assert.failure()
+        };
+        String::from_utf8(assert.get_output().stdout.clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("stdout line is not JSON"))
+            .collect()
+    };
+
+    let events_ok = events(
+        &[
+            "--output",
+            "json",
+            "package",
+            "--input",
+            "demo.wasm",
+            "--force",
+        ],
+        true,
+    );
+    assert!(events_ok
+        .iter()
+        .any(|e| e["event"] == "stage" && e["stage"] == "encode" && e["status"] == "finished"));
+    assert!(events_ok
+        .iter()
+        .any(|e| e["event"] == "artifact" && e["path"] == "demo.vtx"));
+    let last = events_ok.last().unwrap();
+    assert_eq!(last["event"], "result");
+    assert_eq!(last["status"], "ok");
+
+    let events_err = events(&["inspect", "missing.vtx", "--output", "json"], false);
+    assert!(events_err
+        .iter()
+        .any(|e| e["event"] == "error" && e["message"] == "Failed to read missing.vtx"));
+    assert_eq!(events_err.last().unwrap()["status"], "error");
+}
+
+#[test]
+fn test_build_profile_overrides_build_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n[build]\ncmd = \"exit 1\"\noutput_dir = \".\"\nartifact = \"demo.wasm\"\n\n[profiles.prebuilt]\ncmd = \"exit 0\"\nforce = true\n\n[profiles.prebuilt.optimize]\nlevel = \"9\"\n\n[profiles.ci]\ncmd = \"exit 0\"\nforce = true\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown profiles.prebuilt.optimize.level '9'",
+        ));
+
+    let config = std::fs::read_to_string(dir.path().join("vtx.toml"))
+        .unwrap()
+        .replace("level = \"9\"", "level = \"z\"");
+    std::fs::write(dir.path().join("vtx.toml"), config).unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Custom build command failed"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["build", "--profile", "ci"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using build profile: ci"));
+    assert!(dir.path().join("demo.vtx").exists());
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["build", "--profile", "prod"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown profile 'prod' (defined in vtx.toml: ci, prebuilt)",
+        ));
+}
+
+#[test]
+fn test_lint_reports_findings_and_denies_warnings() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nversion = \"1.0\"\nlanguage = \"ts\"\ndescription = \"\"\n\n[capabilities]\nfilesystem = [\"/\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("package.json"),
+        "{\"version\": \"1.0.0\", \"scripts\": {\"build\": \"echo TODO: build wasm\"}}",
+    )
+    .unwrap();
+    let lint = |args: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .arg("lint")
+            .args(args)
+            .arg("--json")
+            .output()
+            .unwrap()
+    };
+
+    let output = lint(&[]);
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let rules: Vec<String> = report["findings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| {
+            format!(
+                "{}={}",
+                f["rule"].as_str().unwrap(),
+                f["severity"].as_str().unwrap()
+            )
+        })
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            "semver=error",
+            "empty-field=warning",
+            "scaffold-build=error",
+            "version-mismatch=warning",
+            "broad-permission=warning"
+        ]
+    );
+
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nversion = \"1.0.0\"\nlanguage = \"ts\"\n\n[build]\ncmd = \"npm run compile\"\n",
+    )
+    .unwrap();
+    assert!(lint(&[]).status.success());
+    std::fs::write(dir.path().join("package.json"), "{\"version\": \"0.9.0\"}").unwrap();
+    assert!(lint(&[]).status.success());
+    let output = lint(&["--deny", "warnings"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("(warnings denied)"));
+}
+
+#[test]
+fn test_upgrade_rewrites_sdk_dependency_and_boilerplate() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"rust\"\n\n[sdk]\nversion = \"0.1.2\"\n",
+    )
+    .unwrap();
+    let cargo_toml = "[package]\nname = \"demo\"\n\n[dependencies]\n# pinned by the scaffold\nvtx-sdk = { version = \"0.1.2\", features = [\"macros\"] }\n";
+    std::fs::write(dir.path().join("Cargo.toml"), cargo_toml).unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(
+        dir.path().join("src/config.rs"),
+        "// Centralized plugin configuration.\n\npub const PLUGIN_ID: &str = \"vtx.custom\";\n",
+    )
+    .unwrap();
+    let upgrade = |args: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .arg("upgrade")
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = upgrade(&["--dry-run", "--boilerplate"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let target = format!("\"0.1.2\" -> \"{}\"", vtx_sdk::VERSION);
+    assert!(stdout.contains(&format!("vtx-sdk {target}")), "{stdout}");
+    assert!(
+        stdout.contains(&format!("sdk.version {target}")),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("added PLUGIN_NAME, PLUGIN_DESC"),
+        "{stdout}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+        cargo_toml
+    );
+
+    assert!(upgrade(&["--boilerplate"]).status.success());
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+        cargo_toml.replace("0.1.2", vtx_sdk::VERSION)
+    );
+    let config_rs = std::fs::read_to_string(dir.path().join("src/config.rs")).unwrap();
+    assert!(config_rs.contains("PLUGIN_ID: &str = \"vtx.custom\""));
+    assert!(config_rs.contains("pub const PLUGIN_NAME: &str = \"demo\";"));
+    let stdout = String::from_utf8_lossy(&upgrade(&["--boilerplate"]).stdout).to_string();
+    assert!(stdout.contains("Already up to date"), "{stdout}");
+}
+
+#[test]
+fn test_package_writes_to_out_dir_with_name_template() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("target")).unwrap();
+    std::fs::write(
+        dir.path().join("target/demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nversion = \"1.2.0\"\nlanguage = \"lua\"\n\n[package]\nout_dir = \"dist\"\n",
+    )
+    .unwrap();
+    let package = |args: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .args(["package", "--input", "target/demo.wasm", "--force"])
+            .args(args)
+            .assert()
+            .success();
+    };
+
+    package(&[]);
+    assert!(dir.path().join("dist/demo.vtx").is_file());
+    assert!(!dir.path().join("target/demo.vtx").exists());
+
+    package(&[
+        "--out-dir",
+        "out/plugins",
+        "--out-name",
+        "{name}-{version}-{target}.vtx",
+    ]);
+    assert!(dir
+        .path()
+        .join("out/plugins/demo-1.2.0-wasm32-wasip1.vtx")
+        .is_file());
+}
+
+#[test]
+fn test_diff_reports_version_and_metadata_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    for (version, description) in [("1.0.0", "old"), ("1.1.0", "new")] {
+        std::fs::write(
+            dir.path().join("vtx.toml"),
+            format!("[project]\nname = \"demo\"\nversion = \"{version}\"\nlanguage = \"lua\"\ndescription = \"{description}\"\n\n[package]\nartifact_name = \"{{name}}-{{version}}.vtx\"\n"),
+        )
+        .unwrap();
+        Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .args(["package", "--input", "demo.wasm", "--force"])
+            .assert()
+            .success();
+    }
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["diff", "demo-1.0.0.vtx", "demo-1.1.0.vtx", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["versions"][0]["field"], "version");
+    assert_eq!(report["versions"][0]["old"], "1.0.0");
+    assert_eq!(report["versions"][0]["new"], "1.1.0");
+    assert_eq!(
+        report["metadata"],
+        serde_json::json!([{ "field": "description", "old": "old", "new": "new" }])
+    );
+    assert_eq!(report["exports"]["added"], serde_json::json!([]));
+    assert_eq!(report["sections"], serde_json::json!([]));
+}
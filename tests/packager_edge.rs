@@ -1,6 +1,9 @@
 use std::io::Write;
 use tempfile::NamedTempFile;
-use vtx_cli::packager::process_wasm;
+use vtx_cli::packager::{
+    component_exports, component_imports, package_wasm, process_wasm, process_wasm_with,
+    required_interfaces, PackageOptions, WasiPolicy,
+};
 use wit_component::ComponentEncoder;
 
 const CORE_MODULE_HEADER: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
@@ -11,6 +14,25 @@ fn make_component_bytes() -> anyhow::Result<Vec<u8>> {
     Ok(component)
 }
 
+/// Component exporting every function of the `plugin` world in `wit`, with stub bodies.
+fn make_plugin_component(wit: &str) -> anyhow::Result<Vec<u8>> {
+    let mut resolve = wit_parser::Resolve::default();
+    let package = wit_parser::UnresolvedPackage::parse(std::path::Path::new("vtx.wit"), wit)?;
+    let package = resolve.push(package)?;
+    let world = resolve.select_world(package, Some("plugin"))?;
+    let mut module = wit_component::dummy_module(&resolve, world);
+    wit_component::embed_component_metadata(
+        &mut module,
+        &resolve,
+        world,
+        wit_component::StringEncoding::UTF8,
+    )?;
+    ComponentEncoder::default()
+        .module(&module)?
+        .validate(true)
+        .encode()
+}
+
 fn write_temp(bytes: &[u8]) -> anyhow::Result<NamedTempFile> {
     let mut file = NamedTempFile::new()?;
     file.write_all(bytes)?;
@@ -124,3 +146,904 @@ fn process_wasm_encodes_core_module_when_forced() -> anyhow::Result<()> {
     assert!(output.len() > CORE_MODULE_HEADER.len());
     Ok(())
 }
+
+#[test]
+fn component_exports_empty_for_bare_component() -> anyhow::Result<()> {
+    let component = make_component_bytes()?;
+    assert!(component_exports(&component)?.is_empty());
+    assert!(component_imports(&component)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn required_interfaces_maps_imports_to_versions() -> anyhow::Result<()> {
+    let component = wat::parse_str(
+        r#"(component
+            (import "vtx:api/sql@1.2.0" (instance))
+            (import "acme:db/query" (func)))"#,
+    )?;
+    let requires = required_interfaces(&component)?;
+    assert_eq!(
+        requires.get("vtx:api/sql").map(String::as_str),
+        Some("1.2.0")
+    );
+    assert_eq!(requires.get("acme:db/query").map(String::as_str), Some("*"));
+    Ok(())
+}
+
+#[test]
+fn check_requires_matches_kernel_api_versions() {
+    let requires: std::collections::BTreeMap<String, String> = [
+        ("vtx:api/sql", "3.2.0"),
+        ("vtx:api/log", "*"),
+        ("wasi:io/streams", "0.2.6"),
+    ]
+    .into_iter()
+    .map(|(interface, version)| (interface.to_string(), version.to_string()))
+    .collect();
+    assert!(vtx_cli::contract::check_requires(&requires, "3.4.2").is_ok());
+
+    let error = vtx_cli::contract::check_requires(&requires, "3.1.0")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("vtx:api/sql@3.2.0: kernel provides 3.1.0"));
+
+    let mut foreign = requires.clone();
+    foreign.insert("acme:db/query".to_string(), "*".to_string());
+    let error = vtx_cli::contract::check_requires(&foreign, "3.4.2")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("acme:db/query: not provided by vtx kernels"));
+}
+
+#[test]
+fn process_wasm_rejects_denied_wasi_capability() -> anyhow::Result<()> {
+    let module = wat::parse_str(
+        r#"(module
+            (import "wasi_snapshot_preview1" "path_open"
+                (func (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+            (memory (export "memory") 1))"#,
+    )?;
+    let file = write_temp(&module)?;
+    let options = PackageOptions {
+        force: true,
+        wasi_policy: WasiPolicy {
+            allow: None,
+            deny: vec!["filesystem".to_string()],
+        },
+        ..Default::default()
+    };
+    let err = process_wasm_with(file.path(), &options).unwrap_err();
+    assert!(err.to_string().contains("path_open (filesystem)"));
+    Ok(())
+}
+
+#[test]
+fn process_wasm_rejects_unknown_wasi_capability() -> anyhow::Result<()> {
+    let file = write_temp(&CORE_MODULE_HEADER)?;
+    let options = PackageOptions {
+        wasi_policy: WasiPolicy {
+            allow: Some(vec!["network".to_string()]),
+            deny: Vec::new(),
+        },
+        ..Default::default()
+    };
+    let err = process_wasm_with(file.path(), &options).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Unknown WASI capability 'network'"));
+    Ok(())
+}
+
+#[test]
+fn deep_validate_instantiates_component_with_required_exports() -> anyhow::Result<()> {
+    let component = wat::parse_str(
+        r#"(component
+            (core module $m (func (export "f")))
+            (core instance $i (instantiate $m))
+            (func (export "handle") (canon lift (core func $i "f")))
+            (func (export "get-manifest") (canon lift (core func $i "f")))
+        )"#,
+    )?;
+    vtx_cli::runtime::deep_validate(&component, &Default::default(), &Default::default())
+}
+
+#[test]
+fn deep_validate_rejects_non_host_imports() -> anyhow::Result<()> {
+    let component = wat::parse_str(r#"(component (import "acme:db/query" (func)))"#)?;
+    let err = vtx_cli::runtime::deep_validate(&component, &Default::default(), &Default::default())
+        .unwrap_err();
+    assert!(err.to_string().contains("acme:db/query"));
+    Ok(())
+}
+
+#[test]
+fn harness_invokes_handle_and_reads_body_buffer() -> anyhow::Result<()> {
+    // Echoes the request path back as the body via stream-io.create-memory-buffer.
+    let component = wat::parse_str(
+        r#"(component
+            (import "vtx:api/stream-io@3.4.2" (instance $io
+                (export "buffer" (type (sub resource)))
+                (export "create-memory-buffer" (func (param "data" (list u8)) (result (own 0))))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (core module $libc
+                (memory (export "mem") 1)
+                (global $bump (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $p i32)
+                    (local.set $p (global.get $bump))
+                    (global.set $bump (i32.add (global.get $bump) (local.get 3)))
+                    (local.get $p)))
+            (core instance $libc (instantiate $libc))
+            (core func $create (canon lower (func $io "create-memory-buffer") (memory (core memory $libc "mem"))))
+            (core module $m
+                (import "libc" "mem" (memory 1))
+                (import "io" "create" (func $create (param i32 i32) (result i32)))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (i32.store16 (i32.const 64) (i32.const 200))
+                    (i32.store8 (i32.const 68) (i32.const 1))
+                    (i32.store (i32.const 72) (call $create (local.get 2) (local.get 3)))
+                    (i32.const 64)))
+            (core instance $i (instantiate $m
+                (with "libc" (instance $libc))
+                (with "io" (instance (export "create" (func $create))))))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $libc "mem"))
+                    (realloc (core func $libc "realloc"))))
+        )"#,
+    )?;
+    let request = vtx_cli::harness::HttpRequest {
+        path: "/echo".to_string(),
+        ..Default::default()
+    };
+    let response = vtx_cli::harness::invoke_handle(&component, &request, &Default::default())?;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body.as_deref(), Some(&b"/echo"[..]));
+    Ok(())
+}
+
+/// Component whose `get-manifest` returns the given fields from static memory.
+fn make_manifest_component(fields: [&str; 5]) -> anyhow::Result<Vec<u8>> {
+    let mut strings = String::new();
+    let mut pointers = String::new();
+    let mut offset = 0u32;
+    for field in fields {
+        strings.push_str(field);
+        for value in [offset, field.len() as u32] {
+            for byte in value.to_le_bytes() {
+                pointers.push_str(&format!("\\{byte:02x}"));
+            }
+        }
+        offset += field.len() as u32;
+    }
+    Ok(wat::parse_str(format!(
+        r#"(component
+            (core module $m
+                (memory (export "mem") 1)
+                (data (i32.const 0) "{strings}")
+                (data (i32.const 1024) "{pointers}")
+                (func (export "get-manifest") (result i32) (i32.const 1024)))
+            (core instance $i (instantiate $m))
+            (type $manifest (record
+                (field "id" string) (field "name" string) (field "version" string)
+                (field "description" string) (field "entrypoint" string)))
+            (export $manifest-export "manifest" (type $manifest))
+            (func (export "get-manifest") (result $manifest-export)
+                (canon lift (core func $i "get-manifest") (memory (core memory $i "mem"))))
+        )"#
+    ))?)
+}
+
+#[test]
+fn harness_reads_and_validates_manifest() -> anyhow::Result<()> {
+    let component = make_manifest_component(["vtx.demo", "Demo", "1.2.0", "A demo", "/demo"])?;
+    let manifest = vtx_cli::harness::invoke_get_manifest(&component, &Default::default())?;
+    assert_eq!(manifest.id, "vtx.demo");
+    assert_eq!(manifest.entrypoint, "/demo");
+    assert!(manifest.validate().is_empty());
+
+    let component = make_manifest_component(["Demo Plugin", "Demo", "1.2", "", "demo"])?;
+    let problems =
+        vtx_cli::harness::invoke_get_manifest(&component, &Default::default())?.validate();
+    assert_eq!(problems.len(), 3, "{problems:?}");
+    assert!(problems[0].starts_with("id 'Demo Plugin'"));
+    assert!(problems[1].starts_with("version '1.2' is not a semantic version"));
+    assert!(problems[2].starts_with("entrypoint 'demo' must be an absolute path"));
+    Ok(())
+}
+
+#[derive(Default)]
+struct RecordingSink {
+    events: std::sync::Mutex<Vec<String>>,
+}
+
+impl vtx_cli::progress::ProgressSink for RecordingSink {
+    fn stage_started(&self, stage: vtx_cli::progress::Stage) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("start:{}", stage.name()));
+    }
+
+    fn warning(&self, message: &str) {
+        self.events.lock().unwrap().push(format!("warn:{message}"));
+    }
+}
+
+#[test]
+fn process_wasm_reports_progress_to_sink() -> anyhow::Result<()> {
+    let file = write_temp(&CORE_MODULE_HEADER)?;
+    let sink = std::sync::Arc::new(RecordingSink::default());
+    let options = PackageOptions {
+        force: true,
+        progress: vtx_cli::progress::Progress::new(sink.clone()),
+        ..Default::default()
+    };
+    process_wasm_with(file.path(), &options)?;
+
+    let events = sink.events.lock().unwrap();
+    let stages: Vec<&str> = events
+        .iter()
+        .filter_map(|e| e.strip_prefix("start:"))
+        .collect();
+    assert_eq!(stages, ["read", "clean", "encode", "validate"]);
+    assert!(events
+        .iter()
+        .any(|e| e.starts_with("warn:Contract validation failed")));
+    Ok(())
+}
+
+#[test]
+fn process_wasm_stops_when_cancelled() -> anyhow::Result<()> {
+    let file = write_temp(&CORE_MODULE_HEADER)?;
+    let options = PackageOptions {
+        force: true,
+        ..Default::default()
+    };
+    options.cancel.cancel();
+    let err = process_wasm_with(file.path(), &options).unwrap_err();
+    assert!(err.downcast_ref::<vtx_cli::cancel::Cancelled>().is_some());
+    Ok(())
+}
+
+#[test]
+fn process_wasm_reports_memory64_before_encoding() -> anyhow::Result<()> {
+    let module = wat::parse_str("(module (memory i64 1))")?;
+    let file = write_temp(&module)?;
+    let err = process_wasm(file.path(), false, true).unwrap_err();
+    assert!(err.to_string().contains("uses memory64"));
+    Ok(())
+}
+
+#[test]
+fn process_wasm_gates_threads_on_policy() -> anyhow::Result<()> {
+    let module = wat::parse_str(
+        "(module (memory 1 1 shared) (func (drop (i32.atomic.load (i32.const 0)))))",
+    )?;
+    let file = write_temp(&module)?;
+    let err = process_wasm(file.path(), false, true).unwrap_err();
+    assert!(err.to_string().contains("uses wasm threads"));
+
+    let options = PackageOptions {
+        force: true,
+        feature_policy: vtx_cli::features::FeaturePolicy {
+            allow_threads: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let report = vtx_cli::features::scan(&module)?;
+    assert!(report.shared_memory);
+    assert_eq!(report.count("threads"), 1);
+    assert!(vtx_cli::features::ensure_supported(
+        &report,
+        &options.feature_policy,
+        "40.0.0",
+        &options.progress
+    )
+    .is_ok());
+    Ok(())
+}
+
+#[test]
+fn process_wasm_rejects_tail_calls_outside_kernel_api() -> anyhow::Result<()> {
+    let module = wat::parse_str("(module (func $f (return_call $f)))")?;
+    let file = write_temp(&module)?;
+    let err = process_wasm(file.path(), false, true).unwrap_err();
+    assert!(err.to_string().contains("uses tail calls"));
+    assert!(err.to_string().contains("+tail-call"));
+
+    let options = PackageOptions {
+        force: true,
+        feature_policy: vtx_cli::features::FeaturePolicy {
+            kernel_api: Some("9.9".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let err = process_wasm_with(file.path(), &options).unwrap_err();
+    assert!(err.to_string().contains("Unknown kernel API version '9.9'"));
+
+    // Without sdk.kernel_api, an SDK line with no feature table falls back to the newest one.
+    use vtx_cli::features::default_kernel_api;
+    assert_eq!(default_kernel_api("0.1.2"), "0.1");
+    assert_eq!(default_kernel_api("0.2.0"), "0.1");
+    Ok(())
+}
+
+#[test]
+fn process_wasm_reports_and_denies_simd() -> anyhow::Result<()> {
+    let module = wat::parse_str("(module (func (drop (v128.const i64x2 0 0))))")?;
+    let report = vtx_cli::features::scan(&module)?;
+    assert_eq!(report.simd_instructions(), 1);
+
+    let file = write_temp(&module)?;
+    let options = PackageOptions {
+        force: true,
+        feature_policy: vtx_cli::features::FeaturePolicy {
+            deny_simd: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let err = process_wasm_with(file.path(), &options).unwrap_err();
+    assert!(err.to_string().contains("build.no_simd is set"));
+    Ok(())
+}
+
+#[test]
+fn startup_audit_flags_start_function_and_large_data() -> anyhow::Result<()> {
+    let module = wat::parse_str(
+        r#"(module
+            (memory 32)
+            (func $init)
+            (export "_initialize" (func $init))
+            (start $init)
+            (data (i32.const 0) "abc"))"#,
+    )?;
+    let report = vtx_cli::startup::audit(&module)?;
+    assert_eq!(report.start_functions, ["_initialize"]);
+    assert_eq!(report.initializers, ["_initialize"]);
+    assert_eq!(report.active_data_bytes, 3);
+    let warnings = report.warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("Start function _initialize"));
+    Ok(())
+}
+
+#[test]
+fn optimize_data_trims_zero_padding_and_reports_duplicates() -> anyhow::Result<()> {
+    let module = wat::parse_str(
+        r#"(module
+            (memory 1)
+            (data (i32.const 16) "\00\00\00\00abc\00\00")
+            (data (i32.const 64) "duplicated-constant-string")
+            (data (i32.const 128) "duplicated-constant-string"))"#,
+    )?;
+    let (optimized, report) =
+        vtx_cli::dataopt::optimize(&module, &vtx_cli::cancel::CancelToken::new())?;
+    assert_eq!(report.trimmed_bytes, 6);
+    assert_eq!(report.duplicate_segment_bytes, 26);
+    assert_eq!(optimized.len(), module.len() - 6);
+    wasmparser::Validator::new().validate_all(&optimized)?;
+    Ok(())
+}
+
+#[test]
+fn optimize_data_pools_identical_passive_segments() -> anyhow::Result<()> {
+    // Segment 0 is dropped, so segment 1 is kept and 0 and 2 point at it.
+    let module = wat::parse_str(
+        r#"(module
+            (memory (export "memory") 1)
+            (data $a "pooled-constant-string")
+            (data $b "pooled-constant-string")
+            (data $c "pooled-constant-string")
+            (data $d "distinct-constant-string")
+            (func (export "init")
+                (memory.init $a (i32.const 0) (i32.const 0) (i32.const 22))
+                (data.drop $a)
+                (memory.init $b (i32.const 32) (i32.const 0) (i32.const 22))
+                (memory.init $c (i32.const 64) (i32.const 0) (i32.const 22))
+                (memory.init $d (i32.const 96) (i32.const 0) (i32.const 24))))"#,
+    )?;
+    let (optimized, report) =
+        vtx_cli::dataopt::optimize(&module, &vtx_cli::cancel::CancelToken::new())?;
+    assert_eq!(report.deduplicated_bytes, 44);
+    assert_eq!(report.duplicate_segment_bytes, 0);
+    assert_eq!(report.saved_bytes(), 44);
+    assert_eq!(optimized.len(), module.len() - 44);
+    assert!(report.summary().contains("44 bytes saved"));
+
+    let engine = wasmtime::Engine::default();
+    let mut store = wasmtime::Store::new(&engine, ());
+    let instance = wasmtime::Instance::new(
+        &mut store,
+        &wasmtime::Module::new(&engine, &optimized)?,
+        &[],
+    )?;
+    instance
+        .get_typed_func::<(), ()>(&mut store, "init")?
+        .call(&mut store, ())?;
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    let data = memory.data(&store);
+    for offset in [0, 32, 64] {
+        assert_eq!(&data[offset..offset + 22], b"pooled-constant-string");
+    }
+    assert_eq!(&data[96..120], b"distinct-constant-string");
+    Ok(())
+}
+
+#[test]
+fn contract_checks_plugin_world_signatures() -> anyhow::Result<()> {
+    let canonical = vtx_protocol::WIT_CONTENT;
+    vtx_cli::contract::check_plugin_world(&make_plugin_component(canonical)?)?;
+
+    let wrong_result = canonical.replace(") -> http-response;", ") -> u16;");
+    let err =
+        vtx_cli::contract::check_plugin_world(&make_plugin_component(&wrong_result)?).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Contract Violation: export 'handle' does not match vtx:api/plugin: result: expected http-response, found u16"
+    );
+
+    let wrong_field = canonical.replace("        path: string,", "        path: u32,");
+    let err =
+        vtx_cli::contract::check_plugin_world(&make_plugin_component(&wrong_field)?).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Contract Violation: export 'handle' does not match vtx:api/plugin: parameter 'req': http-request: field 'path': expected string, found u32"
+    );
+    Ok(())
+}
+
+#[test]
+fn analysis_breaks_size_down_by_section_and_function_prefix() {
+    let wasm = wat::parse_str(
+        r#"(component
+            (core module
+                (import "env" "log" (func $env.log (param i32)))
+                (func $core::fmt::write (result i32)
+                    i32.const 1 i32.const 2 i32.add i32.const 3 i32.add)
+                (func $core::ptr::drop (result i32) i32.const 0)
+                (func $demo::handle (call $env.log (i32.const 7)))
+                (memory 1)
+                (data (i32.const 0) "hello world")))"#,
+    )
+    .unwrap();
+    let report = vtx_cli::analysis::analyze(&wasm).unwrap();
+
+    assert_eq!(report.total, wasm.len() as u64);
+    let counted: u64 = report.sections.iter().map(|s| s.size).sum();
+    assert_eq!(counted, report.total);
+    for section in ["code", "data", "custom"] {
+        assert!(report.sections.iter().any(|s| s.name == section));
+    }
+    assert!(report.custom_sections.iter().any(|s| s.name == "name"));
+
+    let names: Vec<&str> = report.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names[0], "core::fmt::write");
+    assert!(names.contains(&"demo::handle"));
+    let core = report.prefixes.iter().find(|p| p.name == "core").unwrap();
+    let demo = report.prefixes.iter().find(|p| p.name == "demo").unwrap();
+    assert!(core.size > demo.size);
+}
+
+#[test]
+fn split_debuginfo_moves_dwarf_into_companion_with_shared_build_id() -> anyhow::Result<()> {
+    use vtx_cli::debuginfo;
+
+    let mut module = wat::parse_str(r#"(module (memory (export "memory") 1))"#)?;
+    // Custom section `.debug_info` with 4 bytes of payload.
+    module.extend([0x00, 0x10, 0x0b]);
+    module.extend(b".debug_info");
+    module.extend([0xde, 0xad, 0xbe, 0xef]);
+    let file = write_temp(&module)?;
+    let options = PackageOptions {
+        force: true,
+        split_debuginfo: true,
+        ..Default::default()
+    };
+
+    let packaged = vtx_cli::packager::package_wasm(file.path(), &options)?;
+    let info = packaged
+        .debuginfo
+        .expect("DWARF sections should be split out");
+    assert_eq!(info.sections, 1);
+    assert_eq!(info.stripped_bytes, 16);
+    assert_eq!(
+        debuginfo::build_id(&packaged.component)?,
+        Some(info.build_id.clone())
+    );
+    assert_eq!(debuginfo::DebugInfo::parse(&info.module)?, info);
+
+    let report = vtx_cli::analysis::analyze(&packaged.component)?;
+    assert!(!report
+        .custom_sections
+        .iter()
+        .any(|s| s.name == ".debug_info"));
+    Ok(())
+}
+
+#[test]
+fn dependencies_are_composed_into_the_component() -> anyhow::Result<()> {
+    use vtx_cli::compose::Dependency;
+
+    let root = wat::parse_str(
+        r#"(component
+            (import "acme:auth/verify" (instance (export "check" (func (result u32))))))"#,
+    )?;
+    let auth = wat::parse_str(
+        r#"(component
+            (core module $m (func (export "check") (result i32) i32.const 1))
+            (core instance $i (instantiate $m))
+            (func $check (result u32) (canon lift (core func $i "check")))
+            (instance $verify (export "check" (func $check)))
+            (export "acme:auth/verify" (instance $verify)))"#,
+    )?;
+    let root_file = write_temp(&root)?;
+    let auth_file = write_temp(&auth)?;
+    let unused_file = write_temp(&make_component_bytes()?)?;
+    let dependency = |name: &str, file: &NamedTempFile| Dependency {
+        name: name.to_string(),
+        path: file.path().to_path_buf(),
+    };
+
+    let options = PackageOptions {
+        force: true,
+        dependencies: vec![dependency("auth", &auth_file)],
+        ..Default::default()
+    };
+    let composed = process_wasm_with(root_file.path(), &options)?;
+    assert!(component_imports(&composed)?.is_empty());
+
+    let options = PackageOptions {
+        force: true,
+        dependencies: vec![
+            dependency("auth", &auth_file),
+            dependency("unused", &unused_file),
+        ],
+        ..Default::default()
+    };
+    let err = process_wasm_with(root_file.path(), &options).unwrap_err();
+    assert!(format!("{err:#}").contains("Dependency 'unused' exports none of the imported"));
+    Ok(())
+}
+
+#[test]
+fn adapter_selection_checks_module_abi() -> anyhow::Result<()> {
+    use vtx_cli::adapter::Adapter;
+
+    let module = wat::parse_str(
+        r#"(module
+            (import "wasi_snapshot_preview1" "path_open"
+                (func (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+            (memory (export "memory") 1))"#,
+    )?;
+    let file = write_temp(&module)?;
+    let package = |adapter: Adapter| {
+        let options = PackageOptions {
+            force: true,
+            adapter,
+            ..Default::default()
+        };
+        process_wasm_with(file.path(), &options)
+    };
+
+    let err = package(Adapter::Command).unwrap_err();
+    assert!(err.to_string().contains("`_start` export"));
+    package(Adapter::Reactor)?;
+
+    let custom = write_temp(&Adapter::Reactor.load()?.bytes)?;
+    let renamed = custom.path().with_extension("wasm");
+    std::fs::copy(custom.path(), &renamed)?;
+    let adapter: Adapter = renamed.to_str().unwrap().parse()?;
+    assert_eq!(adapter, Adapter::File(renamed.clone()));
+    let loaded = adapter.load()?;
+    assert!(loaded
+        .wasi
+        .as_deref()
+        .is_some_and(|v| v.starts_with("0.2.")));
+    let digest = adapter.version()?;
+    assert!(digest.starts_with("sha256:") && digest.len() == 71);
+    assert_eq!(
+        Adapter::Reactor.version()?,
+        vtx_cli::packager::BUNDLED_ADAPTER_VERSION
+    );
+    package(adapter)?;
+    std::fs::remove_file(renamed)?;
+
+    // A preview1 call no bundled adapter implements.
+    let sockets = write_temp(&wat::parse_str(
+        r#"(module
+            (import "wasi_snapshot_preview1" "sock_open" (func (param i32 i32 i32) (result i32)))
+            (memory (export "memory") 1))"#,
+    )?)?;
+    let options = PackageOptions {
+        force: true,
+        adapter: Adapter::Proxy,
+        ..Default::default()
+    };
+    let err = process_wasm_with(sockets.path(), &options).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("does not implement wasi_snapshot_preview1::sock_open"));
+
+    assert!("bogus".parse::<Adapter>().is_err());
+    assert!(Adapter::File(file.path().to_path_buf()).load().is_err());
+    Ok(())
+}
+
+#[test]
+fn adapter_follows_detected_abi() -> anyhow::Result<()> {
+    use vtx_cli::adapter::{detect_abi, Abi, Adapter};
+
+    // wasm32-wasip2 core output: no preview1 imports, so no adapter (even a
+    // command adapter that would otherwise demand `_start`).
+    let p2 = wat::parse_str(r#"(module (memory (export "memory") 1))"#)?;
+    assert_eq!(detect_abi(&p2)?, Abi::Preview2);
+    let options = PackageOptions {
+        force: true,
+        adapter: Adapter::Command,
+        ..Default::default()
+    };
+    let packaged = package_wasm(write_temp(&p2)?.path(), &options)?;
+    assert_eq!(packaged.adapter, None);
+
+    let p1 = wat::parse_str(
+        r#"(module
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1))"#,
+    )?;
+    assert_eq!(detect_abi(&p1)?, Abi::Preview1);
+    let options = PackageOptions {
+        force: true,
+        ..Default::default()
+    };
+    let packaged = package_wasm(write_temp(&p1)?.path(), &options)?;
+    assert_eq!(packaged.adapter, Some(Adapter::Reactor));
+
+    let proxy = wat::parse_str(
+        r#"(module
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (func (export "wasi:http/incoming-handler@0.2.0#handle") (param i32 i32))
+            (memory (export "memory") 1))"#,
+    )?;
+    let abi = detect_abi(&proxy)?;
+    assert_eq!(abi, Abi::Preview1Proxy);
+    assert_eq!(abi.adapter(&Adapter::Reactor), Some(Adapter::Proxy));
+    assert_eq!(abi.adapter(&Adapter::Command), Some(Adapter::Command));
+
+    assert_eq!(detect_abi(&make_component_bytes()?)?, Abi::Component);
+    Ok(())
+}
+
+#[test]
+fn reproducible_mode_strips_producers_and_build_id() -> anyhow::Result<()> {
+    fn custom_section(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut body = vec![name.len() as u8];
+        body.extend(name.as_bytes());
+        body.extend(data);
+        let mut section = vec![0x00, body.len() as u8];
+        section.extend(body);
+        section
+    }
+    // Same code; only the toolchain version and the linker's build id differ.
+    let build = |rustc: &str, build_id: [u8; 4]| -> anyhow::Result<Vec<u8>> {
+        let mut module = wat::parse_str(r#"(module (memory (export "memory") 1))"#)?;
+        let mut producers = vec![0x01, 12];
+        producers.extend(b"processed-by");
+        producers.extend([0x01, 5]);
+        producers.extend(b"rustc");
+        producers.push(rustc.len() as u8);
+        producers.extend(rustc.as_bytes());
+        module.extend(custom_section("producers", &producers));
+        module.extend(custom_section("build_id", &[&[4][..], &build_id].concat()));
+        Ok(module)
+    };
+    let first = write_temp(&build("1.80.0", [1, 2, 3, 4])?)?;
+    let second = write_temp(&build("1.81.0", [5, 6, 7, 8])?)?;
+
+    let package = |file: &NamedTempFile, reproducible| {
+        let options = PackageOptions {
+            force: true,
+            reproducible,
+            ..Default::default()
+        };
+        process_wasm_with(file.path(), &options)
+    };
+    assert_ne!(package(&first, false)?, package(&second, false)?);
+    let component = package(&first, true)?;
+    assert_eq!(component, package(&second, true)?);
+    assert_eq!(vtx_cli::debuginfo::build_id(&component)?, None);
+    Ok(())
+}
+
+#[test]
+fn container_decode_rejects_oversized_payload_size() -> anyhow::Result<()> {
+    let stored = zstd::bulk::compress(&CORE_MODULE_HEADER, 3)?;
+    let metadata = br#"{"payload":{"compression":"zstd","size":18446744073709551615}}"#;
+    let bytes = vtx_format::encode_v2(&stored, metadata);
+    let err = vtx_cli::container::decode(&bytes, None)
+        .err()
+        .expect("oversized payload size must be rejected");
+    assert!(format!("{err:#}").contains("the limit is"), "{err:#}");
+
+    // A size under the cap that disagrees with the frame header is rejected too.
+    let metadata = br#"{"payload":{"compression":"zstd","size":1048576}}"#;
+    let bytes = vtx_format::encode_v2(&stored, metadata);
+    let err = vtx_cli::container::decode(&bytes, None)
+        .err()
+        .expect("mismatched payload size must be rejected");
+    assert!(
+        format!("{err:#}").contains("frame declares 8 bytes"),
+        "{err:#}"
+    );
+    Ok(())
+}
+
+#[test]
+fn container_decryption_is_bound_to_metadata() -> anyhow::Result<()> {
+    use vtx_cli::container::{decode, encode_payload, Compression, PayloadKey};
+
+    let key_file = write_temp(&[7u8; 32])?;
+    let key = PayloadKey::from_file(key_file.path())?;
+    let (metadata, stored) = encode_payload(
+        br#"{"name":"demo"}"#,
+        &CORE_MODULE_HEADER,
+        Some(Compression::Zstd(3)),
+        Some(&key),
+    )?;
+    let bytes = vtx_format::encode_v2(&stored, &metadata);
+    assert_eq!(decode(&bytes, Some(&key))?.component, CORE_MODULE_HEADER);
+
+    let tampered = String::from_utf8(metadata)?.replace("demo", "evil");
+    let bytes = vtx_format::encode_v2(&stored, tampered.as_bytes());
+    let err = decode(&bytes, Some(&key))
+        .err()
+        .expect("edited metadata must fail decryption");
+    assert!(format!("{err:#}").contains("decryption failed"), "{err:#}");
+    Ok(())
+}
+
+#[test]
+fn container_v3_carries_a_capabilities_section() -> anyhow::Result<()> {
+    use vtx_cli::container::{
+        capabilities_digest, decode, decode_sections, encode, CAPABILITIES_DIGEST_KEY,
+    };
+
+    let capabilities = br#"{"permissions":["sql"]}"#;
+    let metadata = format!(
+        r#"{{"{CAPABILITIES_DIGEST_KEY}":"{}"}}"#,
+        capabilities_digest("sha512", capabilities)?
+    );
+    let bytes = encode(
+        &CORE_MODULE_HEADER,
+        metadata.as_bytes(),
+        Some(capabilities),
+        None,
+    );
+    assert_eq!(&bytes[..4], b"VTX\x03");
+    let sections = decode_sections(&bytes)?;
+    assert_eq!(sections.metadata, Some(metadata.as_bytes()));
+    assert_eq!(sections.capabilities, Some(&capabilities[..]));
+    assert_eq!(sections.component, CORE_MODULE_HEADER);
+    let decoded = decode(&bytes, None)?;
+    assert_eq!(decoded.capabilities.as_deref(), Some(&capabilities[..]));
+
+    // Without capabilities the container stays v2.
+    let bytes = encode(&CORE_MODULE_HEADER, b"{}", None, None);
+    assert_eq!(bytes, vtx_format::encode_v2(&CORE_MODULE_HEADER, b"{}"));
+
+    // A section the metadata does not record is rejected.
+    let bytes = encode(&CORE_MODULE_HEADER, b"{}", Some(capabilities), None);
+    let err = decode(&bytes, None)
+        .err()
+        .expect("an unrecorded capabilities section must be rejected");
+    assert!(format!("{err:#}").contains("no digest"), "{err:#}");
+
+    let truncated = &encode(
+        &CORE_MODULE_HEADER,
+        metadata.as_bytes(),
+        Some(capabilities),
+        None,
+    )[..14];
+    let err = decode_sections(truncated)
+        .err()
+        .expect("a truncated section must be rejected");
+    assert!(format!("{err:#}").contains("section declares"), "{err:#}");
+    Ok(())
+}
+
+#[test]
+fn container_v4_carries_a_precompressed_asset_section() -> anyhow::Result<()> {
+    use vtx_cli::container::{
+        assets_digest, decode, decode_assets, decode_sections, encode, encode_assets,
+        ASSETS_DIGEST_KEY, BROTLI, GZIP, IDENTITY,
+    };
+
+    let script = "console.log('hello');\n".repeat(64).into_bytes();
+    let image = vec![0x89, b'P', b'N', b'G'];
+    let assets = encode_assets(&[
+        ("js/app.js".to_string(), script.clone()),
+        ("logo.png".to_string(), image.clone()),
+    ])?;
+    let metadata = format!(
+        r#"{{"{ASSETS_DIGEST_KEY}":"{}"}}"#,
+        assets_digest("sha256", &assets)?
+    );
+    let bytes = encode(
+        &CORE_MODULE_HEADER,
+        metadata.as_bytes(),
+        None,
+        Some(&assets),
+    );
+    assert_eq!(&bytes[..4], b"VTX\x04");
+    let sections = decode_sections(&bytes)?;
+    assert_eq!(sections.capabilities, None);
+    assert_eq!(sections.assets, Some(&assets[..]));
+    assert_eq!(sections.component, CORE_MODULE_HEADER);
+
+    let decoded = decode(&bytes, None)?;
+    let bundle = decode_assets(decoded.assets.as_deref().unwrap())?;
+    let paths: Vec<&str> = bundle.index.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["js/app.js", "logo.png"]);
+    assert_eq!(bundle.get("js/app.js", IDENTITY), Some(&script[..]));
+    let gzipped = bundle.get("js/app.js", GZIP).unwrap();
+    assert!(gzipped.len() < script.len());
+    let mut gunzipped = Vec::new();
+    std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(gzipped), &mut gunzipped)?;
+    assert_eq!(gunzipped, script);
+    let brotli = bundle.get("js/app.js", BROTLI).unwrap();
+    let mut unbrotlied = Vec::new();
+    brotli::BrotliDecompress(&mut &brotli[..], &mut unbrotlied)?;
+    assert_eq!(unbrotlied, script);
+    // Binary types are stored as is.
+    assert_eq!(bundle.get("logo.png", IDENTITY), Some(&image[..]));
+    assert_eq!(bundle.get("logo.png", GZIP), None);
+
+    // Capabilities and assets together.
+    let capabilities = br#"{"permissions":["sql"]}"#;
+    let metadata = format!(
+        r#"{{"capabilities_digest":"{}","{ASSETS_DIGEST_KEY}":"{}"}}"#,
+        vtx_cli::container::capabilities_digest("sha256", capabilities)?,
+        assets_digest("sha256", &assets)?
+    );
+    let bytes = encode(
+        &CORE_MODULE_HEADER,
+        metadata.as_bytes(),
+        Some(capabilities),
+        Some(&assets),
+    );
+    let decoded = decode(&bytes, None)?;
+    assert_eq!(decoded.capabilities.as_deref(), Some(&capabilities[..]));
+    assert_eq!(decoded.assets.as_deref(), Some(&assets[..]));
+
+    // A section the metadata does not record is rejected.
+    let bytes = encode(&CORE_MODULE_HEADER, b"{}", None, Some(&assets));
+    let err = decode(&bytes, None)
+        .err()
+        .expect("an unrecorded asset section must be rejected");
+    assert!(format!("{err:#}").contains("no digest"), "{err:#}");
+
+    // Paths leaving the asset root are rejected.
+    let escaping = encode_assets(&[("../evil.js".to_string(), script)])?;
+    let err = decode_assets(&escaping)
+        .err()
+        .expect("an escaping asset path must be rejected");
+    assert!(
+        format!("{err:#}").contains("leaves the asset root"),
+        "{err:#}"
+    );
+    Ok(())
+}
+
+#[test]
+fn hex_encodes_lowercase_two_digits_per_byte() {
+    assert_eq!(vtx_cli::hex::encode(&[0x00, 0x0a, 0xff, 0x10]), "000aff10");
+    assert_eq!(vtx_cli::hex::encode(&[]), "");
+}
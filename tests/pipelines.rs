@@ -0,0 +1,2994 @@
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use sha2::Digest;
+use vtx_cli::testing::FakeToolchain;
+
+const CORE_MODULE: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+fn project(language: &str) -> (tempfile::TempDir, FakeToolchain) {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        format!("[project]\nname = \"demo\"\nlanguage = \"{language}\"\n"),
+    )
+    .unwrap();
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &CORE_MODULE).unwrap();
+    (dir, toolchain)
+}
+
+/// Names of the build comparison summaries under `.vtx-cache/builds`.
+fn build_summaries(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir.join(".vtx-cache/builds"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+fn vtx(dir: &tempfile::TempDir, toolchain: &FakeToolchain) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .env("PATH", toolchain.path_env().unwrap());
+    cmd
+}
+
+#[test]
+fn build_rust_project_with_fake_cargo() {
+    let (dir, toolchain) = project("rust");
+    vtx(&dir, &toolchain)
+        .args(["build", "--force"])
+        .assert()
+        .success();
+
+    assert!(dir
+        .path()
+        .join("target/wasm32-wasip1/release/demo.vtx")
+        .exists());
+    let calls = toolchain.calls().unwrap();
+    assert!(calls
+        .iter()
+        .any(|c| c == "cargo build --target wasm32-wasip1 -p demo --release"));
+}
+
+#[test]
+fn build_simd_matrix_emits_scalar_variant_with_config_rustflags() {
+    let (dir, toolchain) = project("rust");
+    std::fs::create_dir_all(dir.path().join(".cargo")).unwrap();
+    std::fs::write(
+        dir.path().join(".cargo/config.toml"),
+        "[build]\nrustflags = [\"-C\", \"opt-level=s\"]\n",
+    )
+    .unwrap();
+    vtx(&dir, &toolchain)
+        .env("CARGO_HOME", dir.path().join("cargo-home"))
+        .env_remove("RUSTFLAGS")
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .args(["build", "--force", "--simd-matrix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Scalar build: CARGO_ENCODED_RUSTFLAGS=\"-C opt-level=s -C target-feature=-simd128\"",
+        ));
+
+    let release = dir.path().join("target/wasm32-wasip1/release");
+    assert!(release.join("demo.vtx").exists());
+    assert!(release.join("demo.scalar.vtx").exists());
+    assert!(build_summaries(dir.path())
+        .iter()
+        .any(|name| name.starts_with("demo-release-scalar-")));
+    let builds = toolchain
+        .calls()
+        .unwrap()
+        .iter()
+        .filter(|c| c.starts_with("cargo build"))
+        .count();
+    assert_eq!(builds, 2);
+}
+
+#[test]
+fn build_records_adapter_version_only_with_an_adapter() {
+    let metadata = |dir: &tempfile::TempDir| -> serde_json::Value {
+        let bytes =
+            std::fs::read(dir.path().join("target/wasm32-wasip1/release/demo.vtx")).unwrap();
+        let sections = vtx_cli::container::decode_sections(&bytes).unwrap();
+        serde_json::from_slice(sections.metadata.unwrap()).unwrap()
+    };
+
+    // No wasi_snapshot_preview1 imports: encoded without an adapter.
+    let (dir, toolchain) = project("go");
+    vtx(&dir, &toolchain)
+        .args(["build", "--force"])
+        .assert()
+        .success();
+    let meta = metadata(&dir);
+    assert!(meta["adapter"].is_null());
+    assert!(meta["adapter_version"].is_null());
+    vtx(&dir, &toolchain)
+        .args([
+            "validate",
+            "--strict",
+            "target/wasm32-wasip1/release/demo.vtx",
+        ])
+        .assert()
+        .stdout(predicate::str::contains("missing adapter_version").not());
+
+    let preview1 = wat::parse_str(
+        r#"(module
+            (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+            (memory (export "memory") 1))"#,
+    )
+    .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"go\"\n",
+    )
+    .unwrap();
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &preview1).unwrap();
+    vtx(&dir, &toolchain)
+        .args(["build", "--force"])
+        .assert()
+        .success();
+    let meta = metadata(&dir);
+    assert_eq!(meta["adapter"], "reactor");
+    assert_eq!(
+        meta["adapter_version"],
+        vtx_cli::packager::BUNDLED_ADAPTER_VERSION
+    );
+}
+
+#[test]
+fn build_go_project_with_fake_tinygo() {
+    let (dir, toolchain) = project("go");
+    vtx(&dir, &toolchain)
+        .args(["build", "--force"])
+        .assert()
+        .success();
+
+    assert!(dir
+        .path()
+        .join("target/wasm32-wasip1/release/demo.vtx")
+        .exists());
+}
+
+#[test]
+fn build_c_project_with_fake_clang() {
+    let (dir, toolchain) = project("c");
+    std::fs::create_dir_all(dir.path().join("src/util")).unwrap();
+    std::fs::write(dir.path().join("src/plugin.c"), "").unwrap();
+    std::fs::write(dir.path().join("src/util/strings.cpp"), "").unwrap();
+    vtx(&dir, &toolchain)
+        .env_remove("WASI_SDK_PATH")
+        .env_remove("WASI_SYSROOT")
+        .args(["build", "--force"])
+        .assert()
+        .success();
+
+    assert!(dir
+        .path()
+        .join("target/wasm32-wasip1/release/demo.vtx")
+        .exists());
+    let calls = toolchain.calls().unwrap();
+    assert!(calls.iter().any(|c| c.starts_with(
+        "clang++ --target=wasm32-wasip1 -mexec-model=reactor -O2 -fno-exceptions -o target/wasm32-wasip1/release/demo.wasm src/plugin.c src/util/strings.cpp"
+    )));
+}
+
+#[test]
+fn build_c_post_processes_wasm_and_records_builder_metadata() {
+    let linked = wat::parse_str(
+        r#"(module
+            (@producers (processed-by "clang" "17.0.6"))
+            (memory (export "memory") 1))"#,
+    )
+    .unwrap();
+    assert!(linked.windows(9).any(|w| w == b"producers"));
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"c\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/plugin.c"), "").unwrap();
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &linked).unwrap();
+    vtx(&dir, &toolchain)
+        .env_remove("WASI_SDK_PATH")
+        .env_remove("WASI_SYSROOT")
+        .args(["build", "--force"])
+        .assert()
+        .success();
+
+    let release = dir.path().join("target/wasm32-wasip1/release");
+    let wasm = std::fs::read(release.join("demo.wasm")).unwrap();
+    let custom_sections: Vec<String> = wasmparser::Parser::new(0)
+        .parse_all(&wasm)
+        .filter_map(|payload| match payload.unwrap() {
+            wasmparser::Payload::CustomSection(reader) => Some(reader.name().to_string()),
+            _ => None,
+        })
+        .collect();
+    assert!(!custom_sections.iter().any(|name| name == "producers"));
+
+    let bytes = std::fs::read(release.join("demo.vtx")).unwrap();
+    let sections = vtx_cli::container::decode_sections(&bytes).unwrap();
+    let meta: serde_json::Value = serde_json::from_slice(sections.metadata.unwrap()).unwrap();
+    assert_eq!(meta["builder"]["clang_version"], "clang 0.0.0-fake");
+}
+
+#[test]
+fn build_csharp_project_with_fake_dotnet() {
+    let (dir, toolchain) = project("csharp");
+    std::fs::write(
+        dir.path().join("Demo.Plugin.csproj"),
+        "<Project><ItemGroup><PackageReference Include=\"BytecodeAlliance.Componentize.DotNet.Wasm.SDK\" /></ItemGroup></Project>",
+    )
+    .unwrap();
+    vtx(&dir, &toolchain).arg("check").assert().success();
+    vtx(&dir, &toolchain)
+        .args(["build", "--force"])
+        .assert()
+        .success();
+
+    assert!(dir
+        .path()
+        .join("target/wasm32-wasip1/release/dotnet/Demo.Plugin.vtx")
+        .exists());
+    assert!(toolchain.calls().unwrap().iter().any(|c| c
+        == "dotnet publish ./Demo.Plugin.csproj -c Release -r wasi-wasm -o target/wasm32-wasip1/release/dotnet"));
+}
+
+#[test]
+fn build_reports_toolchain_failure() {
+    let (dir, toolchain) = project("rust");
+    toolchain.fail("cargo").unwrap();
+    vtx(&dir, &toolchain)
+        .args(["build", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Source compilation failed"));
+}
+
+const RUST_ARTIFACT: &str = "target/wasm32-wasip1/release/demo.vtx";
+
+/// A Rust project with an ecdsa-p256 key `release` and an ed25519 key
+/// `other`, built with `package.public_key = "release.pub"` and `package`
+/// appended to `[package]`.
+fn signing_project(package: &str) -> (tempfile::TempDir, FakeToolchain) {
+    let (dir, toolchain) = project("rust");
+    vtx(&dir, &toolchain)
+        .args(["keygen", "release", "--algorithm", "ecdsa-p256"])
+        .assert()
+        .success();
+    vtx(&dir, &toolchain)
+        .args(["keygen", "other"])
+        .assert()
+        .success();
+    write_signing_config(&dir, package);
+    vtx(&dir, &toolchain)
+        .env_remove("VTX_SIGNING_KEY")
+        .args(["build", "--force"])
+        .assert()
+        .success();
+    (dir, toolchain)
+}
+
+fn write_signing_config(dir: &tempfile::TempDir, package: &str) {
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        format!("[project]\nname = \"demo\"\nlanguage = \"rust\"\n\n[package]\npublic_key = \"release.pub\"\n{package}"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn verify_requires_a_signature_when_a_public_key_is_configured() {
+    let (dir, toolchain) = signing_project("");
+    vtx(&dir, &toolchain)
+        .args(["verify", RUST_ARTIFACT])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Artifact is not signed"));
+    vtx(&dir, &toolchain)
+        .args(["verify", RUST_ARTIFACT, "--manifest-only"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Artifact has no manifest signature",
+        ));
+}
+
+#[test]
+fn verify_rejects_signatures_of_the_wrong_algorithm() {
+    let (dir, toolchain) = signing_project("signing_key = \"release.pk8\"\n");
+    vtx(&dir, &toolchain)
+        .args(["verify", RUST_ARTIFACT])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is signed by ecdsa-p256:"));
+
+    // A key of another algorithm cannot vouch for the signature.
+    vtx(&dir, &toolchain)
+        .args(["verify", RUST_ARTIFACT, "--public-key", "other.pub"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "signed with ecdsa-p256, but the given key is an ed25519 key",
+        ));
+
+    // Nor is a signature naming an algorithm this CLI does not know.
+    let path = dir.path().join(RUST_ARTIFACT);
+    let mut bytes = std::fs::read(&path).unwrap();
+    let at = bytes
+        .windows(b"\"ecdsa-p256\"".len())
+        .position(|w| w == b"\"ecdsa-p256\"")
+        .unwrap();
+    bytes[at + 8..at + 11].copy_from_slice(b"384");
+    std::fs::write(&path, bytes).unwrap();
+    vtx(&dir, &toolchain)
+        .args(["verify", RUST_ARTIFACT])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unsupported signature algorithm 'ecdsa-p384'",
+        ));
+}
+
+#[test]
+fn verify_rejects_signatures_the_policy_does_not_allow() {
+    let (dir, toolchain) =
+        signing_project("signing_key = \"release.pk8\"\nsignature_algorithms = [\"ed25519\"]\n");
+    vtx(&dir, &toolchain)
+        .args(["verify", RUST_ARTIFACT])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Signature algorithm ecdsa-p256 is not allowed by the signature policy (allowed: ed25519)",
+        ));
+    vtx(&dir, &toolchain)
+        .args(["attest", RUST_ARTIFACT])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "not allowed by the signature policy",
+        ));
+
+    write_signing_config(
+        &dir,
+        "signature_algorithms = [\"ed25519\", \"ecdsa-p256\"]\n",
+    );
+    vtx(&dir, &toolchain)
+        .args(["verify", RUST_ARTIFACT])
+        .assert()
+        .success();
+}
+
+#[test]
+fn check_passes_with_fake_toolchain() {
+    let (dir, toolchain) = project("python");
+    vtx(&dir, &toolchain).arg("check").assert().success();
+    assert!(toolchain
+        .calls()
+        .unwrap()
+        .iter()
+        .any(|c| c == "componentize-py --help"));
+}
+
+#[test]
+fn check_counts_tools_that_start_as_installed() {
+    let (dir, toolchain) = project("ts");
+    // npm exits non-zero even for `-v`; starting it is enough.
+    std::fs::write(
+        toolchain.bin_dir().join("npm"),
+        "#!/bin/sh\necho \"npm $*\" >> \"$(dirname \"$0\")/calls.log\"\nexit 3\n",
+    )
+    .unwrap();
+    vtx(&dir, &toolchain).arg("check").assert().success();
+    let calls = toolchain.calls().unwrap();
+    assert!(calls.iter().any(|c| c == "npm -v"));
+    // TypeScript builds only need npm.
+    assert!(!calls.iter().any(|c| c.starts_with("node")));
+}
+
+#[test]
+fn build_workspace_builds_every_member() {
+    let (dir, toolchain) = project("rust");
+    toolchain.cargo_workspace(&["alpha", "beta"]).unwrap();
+    vtx(&dir, &toolchain)
+        .args(["build", "--workspace", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Workspace summary"))
+        .stdout(predicate::str::contains("Built 2 workspace packages"));
+
+    for member in ["alpha", "beta"] {
+        assert!(dir
+            .path()
+            .join(format!("target/wasm32-wasip1/release/{member}.vtx"))
+            .exists());
+    }
+}
+
+#[test]
+fn build_workspace_jobs_prefix_member_logs() {
+    let (dir, toolchain) = project("rust");
+    toolchain.cargo_workspace(&["alpha", "beta"]).unwrap();
+    vtx(&dir, &toolchain)
+        .args(["build", "--workspace", "--force", "--jobs", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Running up to 2 builds in parallel",
+        ))
+        .stdout(predicate::str::contains(
+            "[alpha] [VTX] Building package: alpha",
+        ))
+        .stdout(predicate::str::contains(
+            "[beta] [VTX] Building package: beta",
+        ))
+        .stdout(predicate::str::contains("Built 2 workspace packages"));
+}
+
+#[test]
+fn lint_workspace_reports_every_member_and_honors_exclude() {
+    let (dir, toolchain) = project("rust");
+    toolchain.cargo_workspace(&["alpha", "beta"]).unwrap();
+    for (member, crate_type) in [("alpha", "cdylib"), ("beta", "rlib")] {
+        std::fs::create_dir(dir.path().join(member)).unwrap();
+        std::fs::write(
+            dir.path().join(member).join("Cargo.toml"),
+            format!("[package]\nname = \"{member}\"\n\n[lib]\ncrate-type = [\"{crate_type}\"]\n"),
+        )
+        .unwrap();
+    }
+
+    vtx(&dir, &toolchain)
+        .args(["lint", "--all", "--jobs", "2"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Running up to 2 lints in parallel",
+        ))
+        .stdout(predicate::str::contains(
+            "error[crate-type] beta/Cargo.toml",
+        ))
+        .stderr(predicate::str::contains(
+            "1 of 2 workspace packages failed lint",
+        ));
+
+    vtx(&dir, &toolchain)
+        .args(["lint", "--all", "--exclude", "beta"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Workspace summary"))
+        .stdout(predicate::str::contains("Linted 1 workspace packages"));
+}
+
+/// Minimal kernel admin API: records each deployed artifact path and size,
+/// and serves one JSON log line per log stream request.
+fn serve_kernel() -> (String, std::sync::mpsc::Receiver<(String, usize)>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (deployed, received) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let path = parts.next().unwrap_or_default().to_string();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let body = if method == "PUT" {
+                let _ = deployed.send((path, body.len()));
+                String::new()
+            } else {
+                "{\"level\":\"info\",\"message\":\"plugin started\"}\n".to_string()
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+    (url, received)
+}
+
+#[test]
+fn dev_deploys_build_and_streams_kernel_logs() {
+    use std::io::{BufRead, BufReader};
+    use std::time::Duration;
+
+    let (dir, toolchain) = project("rust");
+    let (url, deployed) = serve_kernel();
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .env("PATH", toolchain.path_env().unwrap())
+        .args(["dev", "--force", "--kernel-url", &url])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (lines, received) = std::sync::mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            let _ = lines.send(line);
+        }
+    });
+
+    let (path, size) = deployed.recv_timeout(Duration::from_secs(60)).unwrap();
+    assert_eq!(path, "/admin/plugins/demo");
+    assert!(size > 0);
+    let mut saw_log = false;
+    let mut saw_deploy = false;
+    while !(saw_log && saw_deploy) {
+        let Ok(line) = received.recv_timeout(Duration::from_secs(30)) else {
+            break;
+        };
+        saw_log |= line.contains("[kernel] INFO plugin started");
+        saw_deploy |= line.contains("[OK] Deployed");
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(saw_log && saw_deploy);
+}
+
+/// A Rust project whose fake toolchain lives outside it, so the toolchain's
+/// call log does not trigger `vtx watch`. Keep the second directory alive.
+fn watched_project() -> (tempfile::TempDir, tempfile::TempDir, FakeToolchain) {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"rust\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    let bin = tempfile::tempdir().unwrap();
+    let toolchain = FakeToolchain::install(bin.path(), &CORE_MODULE).unwrap();
+    (dir, bin, toolchain)
+}
+
+/// A running `vtx watch`, killed when dropped.
+struct Watching {
+    child: std::process::Child,
+    lines: std::sync::mpsc::Receiver<String>,
+}
+
+impl Watching {
+    /// Start `vtx watch --force --debounce-ms <debounce_ms>` and wait for
+    /// the first build to finish.
+    fn start(dir: &tempfile::TempDir, toolchain: &FakeToolchain, debounce_ms: u64) -> Self {
+        use std::io::{BufRead, BufReader};
+
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .env("PATH", toolchain.path_env().unwrap())
+            .args([
+                "watch",
+                "--force",
+                "--debounce-ms",
+                &debounce_ms.to_string(),
+            ])
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (lines, received) = std::sync::mpsc::channel();
+        let stdout = child.stdout.take().unwrap();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+                let _ = lines.send(line);
+            }
+        });
+        let mut watching = Self {
+            child,
+            lines: received,
+        };
+        watching.wait_for("Rebuilt in");
+        watching
+    }
+
+    /// Output lines up to and including the first one containing `text`,
+    /// failing after 60 seconds.
+    fn wait_for(&mut self, text: &str) -> Vec<String> {
+        let mut seen = Vec::new();
+        loop {
+            let line = self
+                .lines
+                .recv_timeout(std::time::Duration::from_secs(60))
+                .unwrap_or_else(|_| panic!("vtx watch never printed {text:?}; saw {seen:?}"));
+            let found = line.contains(text);
+            seen.push(line);
+            if found {
+                return seen;
+            }
+        }
+    }
+
+    /// Output lines printed within `wait`.
+    fn lines_within(&mut self, wait: std::time::Duration) -> Vec<String> {
+        let deadline = std::time::Instant::now() + wait;
+        let mut seen = Vec::new();
+        while let Some(left) = deadline.checked_duration_since(std::time::Instant::now()) {
+            match self.lines.recv_timeout(left) {
+                Ok(line) => seen.push(line),
+                Err(_) => break,
+            }
+        }
+        seen
+    }
+}
+
+impl Drop for Watching {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn watch_rebuilds_after_a_source_change() {
+    let (dir, _bin, toolchain) = watched_project();
+    let mut watching = Watching::start(&dir, &toolchain, 100);
+
+    std::fs::write(dir.path().join("src/lib.rs"), "// changed\n").unwrap();
+    let lines = watching.wait_for("Rebuilt in");
+    // Some file systems only report the directory that changed.
+    assert!(
+        lines.iter().any(|l| l.contains("Changed: src")),
+        "{lines:?}"
+    );
+}
+
+#[test]
+fn watch_debounces_a_burst_of_changes_into_one_rebuild() {
+    let (dir, _bin, toolchain) = watched_project();
+    let mut watching = Watching::start(&dir, &toolchain, 500);
+
+    for i in 0..5 {
+        std::fs::write(dir.path().join(format!("src/m{i}.rs")), "// new\n").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    watching.wait_for("Rebuilt in");
+    let later = watching.lines_within(std::time::Duration::from_secs(2));
+    assert!(!later.iter().any(|l| l.contains("Rebuilt in")), "{later:?}");
+}
+
+#[test]
+fn watch_ignores_build_outputs_and_dependency_dirs() {
+    let (dir, _bin, toolchain) = watched_project();
+    std::fs::create_dir_all(dir.path().join("node_modules/left-pad")).unwrap();
+    let mut watching = Watching::start(&dir, &toolchain, 100);
+
+    std::fs::write(dir.path().join("target/scratch.txt"), "x").unwrap();
+    std::fs::write(dir.path().join("node_modules/left-pad/index.js"), "x").unwrap();
+    std::fs::write(dir.path().join("plugin.wasm"), CORE_MODULE).unwrap();
+    std::fs::write(dir.path().join("demo.vtx"), "x").unwrap();
+    let quiet = watching.lines_within(std::time::Duration::from_secs(2));
+    assert!(
+        !quiet
+            .iter()
+            .any(|l| l.contains("Changed:") || l.contains("Rebuilt in")),
+        "{quiet:?}"
+    );
+
+    // vtx.toml itself is watched, so config edits are picked up.
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"rust\"\nversion = \"0.2.0\"\n",
+    )
+    .unwrap();
+    let lines = watching.wait_for("Rebuilt in");
+    assert!(
+        lines.iter().any(|l| l.contains("Changed: vtx.toml")),
+        "{lines:?}"
+    );
+}
+
+#[test]
+fn watch_keeps_the_last_artifact_when_a_rebuild_fails() {
+    let (dir, _bin, toolchain) = watched_project();
+    let artifact = dir.path().join("target/wasm32-wasip1/release/demo.vtx");
+    let mut watching = Watching::start(&dir, &toolchain, 100);
+    let built = std::fs::read(&artifact).unwrap();
+
+    toolchain.fail("cargo").unwrap();
+    std::fs::write(dir.path().join("src/lib.rs"), "// broken\n").unwrap();
+    watching.wait_for("keeping the last successful artifact");
+    assert_eq!(std::fs::read(&artifact).unwrap(), built);
+
+    // The watcher keeps running and picks up the next change.
+    std::fs::write(dir.path().join("src/lib.rs"), "// still broken\n").unwrap();
+    watching.wait_for("Rebuild failed after");
+}
+
+#[test]
+fn watch_requires_a_project() {
+    let dir = tempfile::tempdir().unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+        .current_dir(dir.path())
+        .args(["watch"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Configuration file 'vtx.toml' not found",
+        ));
+}
+
+#[test]
+fn build_runs_wasm_opt_at_requested_level() {
+    let (dir, toolchain) = project("rust");
+    vtx(&dir, &toolchain)
+        .args(["build", "--force", "--opt-level", "z"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("wasm-opt -Oz: 8 -> 8 bytes"));
+
+    let calls = toolchain.calls().unwrap();
+    assert!(calls
+        .iter()
+        .any(|c| c.starts_with("wasm-opt -Oz --enable-sign-ext")));
+}
+
+#[test]
+fn build_passes_features_env_and_extra_args_to_the_toolchain() {
+    let (dir, toolchain) = project("go");
+    let config = dir.path().join("vtx.toml");
+    let base = std::fs::read_to_string(&config).unwrap();
+    std::fs::write(
+        &config,
+        format!(
+            "{base}\n[build]\nfeatures = [\"json\"]\nextra_args = [\"-scheduler=none\"]\n\
+             env = {{ GREETING = \"hi\", TARGET_ENV = \"dev\" }}\n"
+        ),
+    )
+    .unwrap();
+    vtx(&dir, &toolchain)
+        .args(["build", "--force", "--feature", "json", "--feature", "http"])
+        .assert()
+        .success();
+
+    let calls = toolchain.calls().unwrap();
+    assert!(calls.iter().any(|c| c.starts_with(
+        "tinygo build -target=wasi -o target/wasm32-wasip1/release/demo.wasm -no-debug -tags=json,http -scheduler=none ."
+    )));
+
+    // build.cmd sees build.env, --env overrides and VTX_FEATURES.
+    std::fs::write(
+        &config,
+        format!(
+            "{base}\n[build]\nfeatures = [\"json\"]\n\
+             env = {{ GREETING = \"hi\", TARGET_ENV = \"dev\" }}\n\
+             cmd = \"echo $GREETING $TARGET_ENV $VTX_FEATURES > env.txt && tinygo build -o target/wasm32-wasip1/release/demo.wasm .\"\n"
+        ),
+    )
+    .unwrap();
+    vtx(&dir, &toolchain)
+        .args(["build", "--force", "--env", "TARGET_ENV=prod"])
+        .assert()
+        .success();
+    let env = std::fs::read_to_string(dir.path().join("env.txt")).unwrap();
+    assert_eq!(env.trim(), "hi prod json");
+}
+
+#[test]
+fn build_compare_enforces_growth_thresholds() {
+    let (dir, toolchain) = project("go");
+    let config = dir.path().join("vtx.toml");
+    let write_config = |description: &str, compare: &str| {
+        std::fs::write(
+            &config,
+            format!(
+                "[project]\nname = \"@acme/demo\"\nlanguage = \"go\"\ndescription = \"{description}\"\n\n[build.compare]\n{compare}"
+            ),
+        )
+        .unwrap();
+    };
+    let build = || {
+        vtx(&dir, &toolchain)
+            .args(["build", "--force"])
+            .assert()
+            .success()
+    };
+
+    write_config("short", "");
+    build();
+    let summaries = build_summaries(dir.path());
+    assert_eq!(summaries.len(), 1);
+    assert!(summaries[0].starts_with("_acme_demo-release-"));
+
+    // Within the limits: the delta is printed without a violation.
+    write_config("short", "max_growth_bytes = 0\nmax_growth_percent = 0.0\n");
+    build()
+        .stdout(predicate::str::contains(
+            "Compared to previous release build: size +0 B",
+        ))
+        .stdout(predicate::str::contains("Build comparison failed").not());
+
+    // Growth past either limit is reported; --force turns it into a warning.
+    let long = "x".repeat(512);
+    write_config(&long, "max_growth_bytes = 100\n");
+    build().stdout(predicate::str::contains(
+        "Build comparison failed: artifact grew by",
+    ));
+    write_config(&format!("{long}{long}"), "max_growth_percent = 1.0\n");
+    build().stdout(predicate::str::is_match(r"artifact grew by \d+\.\d% \(limit 1\.0%\)").unwrap());
+    write_config(&format!("{long}{long}"), "max_growth_bytes = 0\n");
+    build().stdout(predicate::str::contains("Build comparison failed").not());
+
+    // A name that sanitizes to the same stem keeps its own summary.
+    std::fs::write(
+        &config,
+        "[project]\nname = \"_acme_demo\"\nlanguage = \"go\"\n",
+    )
+    .unwrap();
+    build();
+    assert_eq!(build_summaries(dir.path()).len(), 2);
+}
+
+#[test]
+fn build_verify_reproducible_rebuilds_and_compares() {
+    let (dir, toolchain) = project("rust");
+    vtx(&dir, &toolchain)
+        .env("SOURCE_DATE_EPOCH", "86400")
+        .args(["build", "--force", "--verify-reproducible"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[rebuild]"))
+        .stdout(predicate::str::contains(
+            "Reproducible: rebuild is byte-identical (sha256:",
+        ));
+
+    let calls = toolchain.calls().unwrap();
+    let builds = calls
+        .iter()
+        .filter(|c| c.starts_with("cargo build"))
+        .count();
+    assert_eq!(builds, 2);
+    vtx(&dir, &toolchain)
+        .args(["inspect", "target/wasm32-wasip1/release/demo.vtx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1970-01-02T00:00:00Z"));
+}
+
+#[test]
+fn build_runs_hooks_with_artifact_environment() {
+    let (dir, toolchain) = project("go");
+    let config = dir.path().join("vtx.toml");
+    let base = std::fs::read_to_string(&config).unwrap();
+    std::fs::write(
+        &config,
+        format!(
+            "{base}\n[hooks]\npre_build = [\"echo $VTX_HOOK $VTX_PACKAGE $VTX_PROFILE > hooks.txt\"]\n\
+             post_build = [\"test -f $VTX_WASM_PATH && echo $VTX_HOOK >> hooks.txt\"]\n\
+             post_package = [\"test -f $VTX_ARTIFACT_PATH && grep -q '\\\"package\\\":\\\"demo\\\"' $VTX_METADATA_PATH && echo $VTX_HOOK ${{#VTX_ARTIFACT_SHA256}} >> hooks.txt\"]\n"
+        ),
+    )
+    .unwrap();
+    vtx(&dir, &toolchain)
+        .args(["build", "--force"])
+        .assert()
+        .success();
+    let log = std::fs::read_to_string(dir.path().join("hooks.txt")).unwrap();
+    assert_eq!(log, "pre_build demo release\npost_build\npost_package 64\n");
+
+    // A failing pre-build hook stops the build before compiling.
+    std::fs::write(
+        &config,
+        format!("{base}\n[hooks]\npre_build = [\"exit 3\"]\n"),
+    )
+    .unwrap();
+    let builds = || {
+        toolchain
+            .calls()
+            .unwrap()
+            .iter()
+            .filter(|c| c.starts_with("tinygo build"))
+            .count()
+    };
+    let builds_before = builds();
+    vtx(&dir, &toolchain)
+        .args(["build", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("pre_build hook failed"));
+    assert_eq!(builds(), builds_before);
+}
+
+#[test]
+fn build_prints_stage_timing_summary() {
+    let (dir, toolchain) = project("go");
+    let output = vtx(&dir, &toolchain)
+        .args(["build", "--force"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let table = &stdout[stdout.find("STAGE").expect("stage table")..];
+    let stages: Vec<&str> = table
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    assert_eq!(
+        stages,
+        ["env", "compile", "read", "clean", "encode", "validate", "write", "total"]
+    );
+
+    // JSON output reports stages as events instead.
+    let output = vtx(&dir, &toolchain)
+        .args(["--output", "json", "build", "--force"])
+        .output()
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("STAGE"));
+}
+
+/// Build service answering one build `id` that succeeds with `CORE_MODULE`.
+/// Receives the build request and the uploaded source archive.
+fn serve_build_service(id: &'static str) -> (String, std::sync::mpsc::Receiver<(String, Vec<u8>)>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (uploads, received) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let path = parts.next().unwrap_or_default().to_string();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let build = format!("/v1/builds/{id}");
+            let response = match (method.as_str(), path.strip_prefix(&build)) {
+                ("POST", _) if path == "/v1/builds" => {
+                    let _ = uploads.send((path.clone(), body));
+                    serde_json::json!({ "id": id }).to_string().into_bytes()
+                }
+                ("PUT", Some("/source")) => {
+                    let _ = uploads.send((path.clone(), body));
+                    Vec::new()
+                }
+                (_, Some("/logs")) => b"tinygo build -target=wasi\nremote ok\n".to_vec(),
+                (_, Some("")) => b"{\"status\":\"succeeded\"}".to_vec(),
+                (_, Some("/artifact")) => CORE_MODULE.to_vec(),
+                _ => Vec::new(),
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                response.len()
+            );
+            let _ = stream.write_all(&response);
+        }
+    });
+    (url, received)
+}
+
+#[test]
+fn build_remote_uploads_source_and_packages_the_returned_wasm() {
+    let (url, uploads) = serve_build_service("b1");
+    let (dir, toolchain) = project("go");
+    let mut manifest = std::fs::read_to_string(dir.path().join("vtx.toml")).unwrap();
+    manifest.push_str("\n[build]\nremote_include = [\".cargo\"]\n");
+    std::fs::write(dir.path().join("vtx.toml"), manifest).unwrap();
+    for (path, content) in [
+        ("main.go", "package main\n"),
+        (".gitignore", "secret.env\n"),
+        ("secret.env", "TOKEN=1\n"),
+        (".env", "TOKEN=1\n"),
+        (".npmrc", "//registry.npmjs.org/:_authToken=1\n"),
+        (".aws/credentials", "[default]\n"),
+        ("pkg/.env.local", "TOKEN=1\n"),
+        (".cargo/config.toml", "[build]\n"),
+    ] {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    vtx(&dir, &toolchain)
+        .args(["build", "--force", "--remote", &url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("remote ok"));
+
+    assert!(dir
+        .path()
+        .join("target/wasm32-wasip1/release/demo.vtx")
+        .exists());
+    assert!(!toolchain
+        .calls()
+        .unwrap()
+        .iter()
+        .any(|c| c.starts_with("tinygo")));
+
+    let (path, request) = uploads.recv().unwrap();
+    assert_eq!(path, "/v1/builds");
+    let request: serde_json::Value = serde_json::from_slice(&request).unwrap();
+    assert_eq!(request["language"], "go");
+    assert_eq!(request["release"], true);
+    let (_, archive) = uploads.recv().unwrap();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&archive[..]));
+    let files: Vec<String> = archive
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().display().to_string())
+        .collect();
+    assert!(files.contains(&"main.go".to_string()));
+    assert!(files.contains(&"vtx.toml".to_string()));
+    assert!(files.contains(&".cargo/config.toml".to_string()));
+    for secret in [
+        "secret.env",
+        ".env",
+        ".npmrc",
+        ".aws/credentials",
+        "pkg/.env.local",
+        ".gitignore",
+    ] {
+        assert!(
+            !files.contains(&secret.to_string()),
+            "{secret} was uploaded"
+        );
+    }
+}
+
+#[test]
+fn build_remote_rejects_a_malformed_build_id() {
+    let (url, uploads) = serve_build_service("../../admin?x=");
+    let (dir, toolchain) = project("go");
+    std::fs::write(dir.path().join("main.go"), "package main\n").unwrap();
+
+    vtx(&dir, &toolchain)
+        .args(["--output", "json", "build", "--force", "--remote", &url])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            r#"invalid build id \"../../admin?x=\""#,
+        ));
+    let (path, _) = uploads.recv().unwrap();
+    assert_eq!(path, "/v1/builds");
+    // Nothing is uploaded under the rejected id.
+    assert!(uploads
+        .recv_timeout(std::time::Duration::from_millis(200))
+        .is_err());
+}
+
+#[test]
+fn offline_mode_threads_offline_flags_and_fails_fast_on_network() {
+    let (dir, toolchain) = project("rust");
+    vtx(&dir, &toolchain)
+        .args(["--offline", "build", "--force"])
+        .assert()
+        .success();
+    let calls = toolchain.calls().unwrap();
+    assert!(calls
+        .iter()
+        .any(|c| c.starts_with("cargo build") && c.contains("--offline")));
+
+    let (dir, toolchain) = project("ts");
+    std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+    vtx(&dir, &toolchain)
+        .env("VTX_OFFLINE", "1")
+        .args(["build", "--force"])
+        .assert()
+        .success();
+    let calls = toolchain.calls().unwrap();
+    assert!(calls.iter().any(|c| c == "npm install --offline"));
+    assert!(calls.iter().any(|c| c == "npm run build --offline"));
+
+    vtx(&dir, &toolchain)
+        .env("VTX_REGISTRY_URL", "http://127.0.0.1:9")
+        .args(["--offline", "pull", "demo@1.0.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("offline mode is on"));
+}
+
+/// A plugin component that echoes the request path back as the body via
+/// stream-io.create-memory-buffer.
+fn echo_component() -> Vec<u8> {
+    echo_component_at("3.4.2")
+}
+
+/// [`echo_component`] importing `vtx:api` interfaces at `version`.
+fn echo_component_at(version: &str) -> Vec<u8> {
+    echo_component_with(version, 1)
+}
+
+/// [`echo_component_at`] with `pages` of linear memory.
+fn echo_component_with(version: &str, pages: u32) -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (import "vtx:api/stream-io@3.4.2" (instance $io
+                (export "buffer" (type (sub resource)))
+                (export "create-memory-buffer" (func (param "data" (list u8)) (result (own 0))))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (core module $libc
+                (memory (export "mem") 1)
+                (global $bump (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $p i32)
+                    (local.set $p (global.get $bump))
+                    (global.set $bump (i32.add (global.get $bump) (local.get 3)))
+                    (local.get $p)))
+            (core instance $libc (instantiate $libc))
+            (core func $create (canon lower (func $io "create-memory-buffer") (memory (core memory $libc "mem"))))
+            (core module $m
+                (import "libc" "mem" (memory 1))
+                (import "io" "create" (func $create (param i32 i32) (result i32)))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (i32.store16 (i32.const 64) (i32.const 200))
+                    (i32.store8 (i32.const 68) (i32.const 1))
+                    (i32.store (i32.const 72) (call $create (local.get 2) (local.get 3)))
+                    (i32.const 64)))
+            (core instance $i (instantiate $m
+                (with "libc" (instance $libc))
+                (with "io" (instance (export "create" (func $create))))))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $libc "mem"))
+                    (realloc (core func $libc "realloc"))))
+        )"#
+        .replace("3.4.2", version)
+        .replace(
+            r#"(memory (export "mem") 1)"#,
+            &format!(r#"(memory (export "mem") {pages})"#),
+        ),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_runs_cargo_test_and_contract_fixtures() {
+    let component = echo_component();
+    let (dir, _) = project("rust");
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &component).unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    std::fs::create_dir_all(&fixtures).unwrap();
+    std::fs::write(
+        fixtures.join("echo.json"),
+        r#"{"request": {"path": "/hello"}, "response": {"status": 200, "body": "/hello"}}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        fixtures.join("missing.json"),
+        r#"{"request": {"path": "/missing"}, "response": {"status": 404}}"#,
+    )
+    .unwrap();
+
+    let output = vtx(&dir, &toolchain)
+        .args(["--output", "json", "test", "--force"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event["event"] == "report")
+        .expect("report event")["data"]
+        .clone();
+    assert_eq!(report["cargo_test"], true);
+    assert_eq!(report["passed"], 1);
+    assert_eq!(
+        report["fixtures"][1]["failures"][0],
+        "expected status 404, got 200"
+    );
+    let calls = toolchain.calls().unwrap();
+    assert!(calls
+        .iter()
+        .any(|c| c == "cargo build --target wasm32-wasip1 -p demo"));
+    assert!(calls.iter().any(|c| c == "cargo test -p demo"));
+
+    std::fs::remove_file(fixtures.join("missing.json")).unwrap();
+    vtx(&dir, &toolchain)
+        .args(["test", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("PASS echo"));
+}
+
+/// A plugin component that prints "boom: database is down" through
+/// wasi:cli/stdout and answers 500 without a body.
+fn logging_component() -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (import "wasi:io/error@0.2.12" (instance $err
+                (export "error" (type (sub resource)))
+            ))
+            (alias export $err "error" (type $error))
+            (import "wasi:io/streams@0.2.12" (instance $streams
+                (alias outer 1 $error (type $error-export))
+                (type $own-error (own $error-export))
+                (type $stream-error (variant (case "last-operation-failed" $own-error) (case "closed")))
+                (export "stream-error" (type $stream-error-export (eq $stream-error)))
+                (export "output-stream" (type $output (sub resource)))
+                (type $borrow-output (borrow $output))
+                (export "[method]output-stream.blocking-write-and-flush"
+                    (func (param "self" $borrow-output) (param "contents" (list u8))
+                        (result (result (error $stream-error-export)))))
+            ))
+            (alias export $streams "output-stream" (type $output-stream))
+            (import "wasi:cli/stdout@0.2.12" (instance $stdout
+                (alias outer 1 $output-stream (type $os-export))
+                (type $own-os (own $os-export))
+                (export "get-stdout" (func (result $own-os)))
+            ))
+            (import "vtx:api/stream-io@3.4.2" (instance $io
+                (export "buffer" (type (sub resource)))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (core module $libc
+                (memory (export "mem") 1)
+                (data (i32.const 16) "boom: database is down\n")
+                (global $bump (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $p i32)
+                    (local.set $p (global.get $bump))
+                    (global.set $bump (i32.add (global.get $bump) (local.get 3)))
+                    (local.get $p)))
+            (core instance $libc (instantiate $libc))
+            (core func $get-stdout (canon lower (func $stdout "get-stdout")))
+            (core func $write (canon lower (func $streams "[method]output-stream.blocking-write-and-flush")
+                (memory (core memory $libc "mem"))))
+            (core module $m
+                (import "libc" "mem" (memory 1))
+                (import "wasi" "get-stdout" (func $get-stdout (result i32)))
+                (import "wasi" "write" (func $write (param i32 i32 i32 i32)))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (call $write (call $get-stdout) (i32.const 16) (i32.const 23) (i32.const 96))
+                    (i32.store16 (i32.const 64) (i32.const 500))
+                    (i32.store8 (i32.const 68) (i32.const 0))
+                    (i32.const 64)))
+            (core instance $i (instantiate $m
+                (with "libc" (instance $libc))
+                (with "wasi" (instance
+                    (export "get-stdout" (func $get-stdout))
+                    (export "write" (func $write))))))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $libc "mem"))
+                    (realloc (core func $libc "realloc"))))
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_reports_junit_and_tap_with_plugin_output() {
+    let (dir, _) = project("node");
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &logging_component()).unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    std::fs::create_dir_all(&fixtures).unwrap();
+    std::fs::write(
+        fixtures.join("fails.json"),
+        r#"{"response": {"status": 200}}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        fixtures.join("errors.json"),
+        r#"{"response": {"status": 500}}"#,
+    )
+    .unwrap();
+
+    let output = vtx(&dir, &toolchain)
+        .args([
+            "-qq",
+            "test",
+            "--force",
+            "--format",
+            "tap",
+            "--report",
+            "out/junit.xml",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let tap = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = tap.lines().collect();
+    assert_eq!(lines[..2], ["TAP version 13", "1..2"], "{tap}");
+    assert!(lines[2].starts_with("ok 1 - errors # time="), "{tap}");
+    assert!(lines[3].starts_with("not ok 2 - fails # time="), "{tap}");
+    assert!(
+        tap.contains("    - \"expected status 200, got 500\""),
+        "{tap}"
+    );
+    assert!(
+        tap.contains("  output: |\n    boom: database is down\n  ...\n"),
+        "{tap}"
+    );
+
+    let junit = std::fs::read_to_string(dir.path().join("out/junit.xml")).unwrap();
+    assert!(
+        junit.contains(r#"<testsuite name="demo" tests="2" failures="1""#),
+        "{junit}"
+    );
+    assert!(
+        junit.contains(r#"<testcase name="errors" classname="demo" time=""#),
+        "{junit}"
+    );
+    assert!(
+        junit.contains(r#"<failure message="expected status 200, got 500">"#),
+        "{junit}"
+    );
+    assert!(
+        junit.contains("<system-out>boom: database is down\n</system-out>"),
+        "{junit}"
+    );
+
+    // Pretty output shows the plugin output under the failure.
+    vtx(&dir, &toolchain)
+        .args(["test", "--force"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("PASS errors"))
+        .stdout(predicate::str::contains(
+            "plugin output:\n         boom: database is down",
+        ));
+}
+
+/// A plugin component that answers 200 or 500 by the parity of
+/// wasi:random's get-random-u64, without a body.
+fn coin_flip_component() -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (import "wasi:random/random@0.2.12" (instance $random
+                (export "get-random-u64" (func (result u64)))
+            ))
+            (import "vtx:api/stream-io@3.4.2" (instance $io
+                (export "buffer" (type (sub resource)))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (core module $libc
+                (memory (export "mem") 1)
+                (global $bump (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $p i32)
+                    (local.set $p (global.get $bump))
+                    (global.set $bump (i32.add (global.get $bump) (local.get 3)))
+                    (local.get $p)))
+            (core instance $libc (instantiate $libc))
+            (core func $random (canon lower (func $random "get-random-u64")))
+            (core module $m
+                (import "wasi" "random" (func $random (result i64)))
+                (import "libc" "mem" (memory 1))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (i32.store16 (i32.const 64)
+                        (select (i32.const 500) (i32.const 200)
+                            (i32.wrap_i64 (i64.and (call $random) (i64.const 1)))))
+                    (i32.store8 (i32.const 68) (i32.const 0))
+                    (i32.const 64)))
+            (core instance $i (instantiate $m
+                (with "libc" (instance $libc))
+                (with "wasi" (instance (export "random" (func $random))))))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $libc "mem"))
+                    (realloc (core func $libc "realloc"))))
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_retries_report_flaky_fixtures() {
+    let (dir, _) = project("node");
+    let toolchain =
+        FakeToolchain::install(&dir.path().join("bin"), &coin_flip_component()).unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    std::fs::create_dir_all(&fixtures).unwrap();
+    std::fs::write(
+        fixtures.join("coin.json"),
+        r#"{"response": {"status": 200}}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        fixtures.join("never.json"),
+        r#"{"response": {"status": 404}}"#,
+    )
+    .unwrap();
+
+    let output = vtx(&dir, &toolchain)
+        .args(["--output", "json", "test", "--force", "--retries", "40"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event["event"] == "report")
+        .expect("report event")["data"]
+        .clone();
+    // 40 retries all landing on odd numbers is a 2^-41 chance.
+    let coin = &report["fixtures"][0];
+    assert_eq!(coin["passed"], true);
+    let attempts = coin["attempts"].as_u64().unwrap();
+    assert_eq!(coin["flaky"], attempts > 1);
+    assert_eq!(report["flaky"], u64::from(attempts > 1));
+    if attempts > 1 {
+        assert_eq!(coin["flaky_failures"][0], "expected status 200, got 500");
+    }
+    let never = &report["fixtures"][1];
+    assert_eq!(never["passed"], false);
+    assert_eq!(never["attempts"], 41);
+    assert_eq!(never["flaky"], false);
+}
+
+/// A plugin component whose `handle` publishes two events and degrades
+/// gracefully when the second fails: 503 with the error as the body instead
+/// of a trap, or 200 when it succeeds.
+fn publishing_component() -> Vec<u8> {
+    publishing_component_at("3.4.2")
+}
+
+/// [`publishing_component`] importing `vtx:api` interfaces at `version`.
+fn publishing_component_at(version: &str) -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (import "vtx:api/event-bus@3.4.2" (instance $bus
+                (export "publish-event"
+                    (func (param "topic" string) (param "payload" string) (result (result (error string)))))
+            ))
+            (import "vtx:api/stream-io@3.4.2" (instance $io
+                (export "buffer" (type (sub resource)))
+                (export "create-memory-buffer" (func (param "data" (list u8)) (result (own 0))))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (core module $libc
+                (memory (export "mem") 1)
+                (data (i32.const 0) "tp")
+                (global $bump (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $p i32)
+                    (local.set $p (global.get $bump))
+                    (global.set $bump (i32.add (global.get $bump) (local.get 3)))
+                    (local.get $p)))
+            (core instance $libc (instantiate $libc))
+            (core func $publish (canon lower (func $bus "publish-event")
+                (memory (core memory $libc "mem")) (realloc (core func $libc "realloc"))))
+            (core func $create (canon lower (func $io "create-memory-buffer")
+                (memory (core memory $libc "mem"))))
+            (core module $m
+                (import "libc" "mem" (memory 1))
+                (import "host" "publish" (func $publish (param i32 i32 i32 i32 i32)))
+                (import "host" "create" (func $create (param i32 i32) (result i32)))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (call $publish (i32.const 0) (i32.const 1) (i32.const 1) (i32.const 1) (i32.const 128))
+                    (call $publish (i32.const 0) (i32.const 1) (i32.const 1) (i32.const 1) (i32.const 128))
+                    (if (i32.load8_u (i32.const 128))
+                        (then
+                            (i32.store16 (i32.const 64) (i32.const 503))
+                            (i32.store8 (i32.const 68) (i32.const 1))
+                            (i32.store (i32.const 72)
+                                (call $create (i32.load (i32.const 132)) (i32.load (i32.const 136)))))
+                        (else
+                            (i32.store16 (i32.const 64) (i32.const 200))
+                            (i32.store8 (i32.const 68) (i32.const 0))))
+                    (i32.const 64)))
+            (core instance $i (instantiate $m
+                (with "libc" (instance $libc))
+                (with "host" (instance
+                    (export "publish" (func $publish))
+                    (export "create" (func $create))))))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $libc "mem"))
+                    (realloc (core func $libc "realloc"))))
+        )"#
+        .replace("3.4.2", version),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_fixture_faults_fail_chosen_kernel_calls() {
+    let (dir, _) = project("node");
+    let toolchain =
+        FakeToolchain::install(&dir.path().join("bin"), &publishing_component()).unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    std::fs::create_dir_all(&fixtures).unwrap();
+    let fixture = |name: &str, faults: &str, response: &str| {
+        std::fs::write(
+            fixtures.join(format!("{name}.json")),
+            format!(r#"{{"faults": {faults}, "response": {response}}}"#),
+        )
+        .unwrap();
+    };
+    // Only the second publish fails with the injected error; the plugin
+    // answers 503 with it rather than trapping.
+    fixture(
+        "a-second-call",
+        r#"[{"function": "vtx:api/event-bus#publish-event", "call": 2, "message": "event bus is down"}]"#,
+        r#"{"status": 503, "body": "event bus is down"}"#,
+    );
+    // A fault on the first call leaves the second to the usual stub.
+    fixture(
+        "b-first-call",
+        r#"[{"function": "vtx:api/event-bus@3.4.2#publish-event", "call": 1, "message": "event bus is down"}]"#,
+        r#"{"status": 503, "body": "vtx:api/event-bus@3.4.2#publish-event is not available in vtx run"}"#,
+    );
+    fixture(
+        "c-timeout",
+        r#"[{"function": "vtx:api/event-bus#publish-event", "kind": "timeout", "probability": 1.0, "delay_ms": 50}]"#,
+        r#"{"status": 503, "body": "vtx:api/event-bus@3.4.2#publish-event timed out after 50 ms"}"#,
+    );
+    fixture(
+        "d-never",
+        r#"[{"function": "vtx:api/event-bus#publish-event", "probability": 0.0}]"#,
+        r#"{"body_contains": "not available in vtx run"}"#,
+    );
+    // Probabilistic faults draw from the fixture's seed, one draw per call.
+    let mut rng = StdRng::seed_from_u64(7);
+    rng.random_bool(0.5);
+    let body = match rng.random_bool(0.5) {
+        true => "flaky bus",
+        false => "vtx:api/event-bus@3.4.2#publish-event is not available in vtx run",
+    };
+    std::fs::write(
+        fixtures.join("d-seeded.json"),
+        format!(
+            r#"{{"seed": 7, "faults": [{{"function": "vtx:api/event-bus#publish-event", "probability": 0.5, "message": "flaky bus"}}], "response": {{"body": "{body}"}}}}"#
+        ),
+    )
+    .unwrap();
+    fixture(
+        "e-not-imported",
+        r#"[{"function": "vtx:api/sql#execute"}]"#,
+        r#"{"status": 503}"#,
+    );
+
+    let output = vtx(&dir, &toolchain)
+        .args(["--output", "json", "test", "--force"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event["event"] == "report")
+        .expect("report event")["data"]
+        .clone();
+    let fixtures = report["fixtures"].as_array().unwrap();
+    for passed in &fixtures[..5] {
+        assert_eq!(passed["passed"], true, "{passed}");
+    }
+    assert!(fixtures[2]["duration_ms"].as_u64().unwrap() >= 100);
+    assert_eq!(fixtures[5]["passed"], false);
+    assert!(
+        fixtures[5]["failures"][0]
+            .as_str()
+            .unwrap()
+            .contains("Fault on vtx:api/sql#execute matches no stubbed kernel function"),
+        "{}",
+        fixtures[5]
+    );
+
+    // Probability and call cannot both target the fault.
+    std::fs::write(
+        dir.path().join("tests/vtx/e-not-imported.json"),
+        r#"{"faults": [{"function": "vtx:api/event-bus#publish-event", "call": 1, "probability": 0.5}]}"#,
+    )
+    .unwrap();
+    vtx(&dir, &toolchain)
+        .args(["test", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("sets both call and probability"));
+}
+
+/// Writes a kernel API table to `dir` and returns its path.
+fn kernel_api_table(dir: &std::path::Path, versions: serde_json::Value) -> String {
+    let path = dir.join("kernel-api.json");
+    std::fs::write(
+        &path,
+        serde_json::json!({ "versions": versions }).to_string(),
+    )
+    .unwrap();
+    path.display().to_string()
+}
+
+#[test]
+fn test_api_emulates_the_interfaces_and_exports_of_a_kernel_version() {
+    // event-bus arrived in 1.1.0, and 1.2.0 requires handle-event.
+    let (dir, _) = project("node");
+    let table = kernel_api_table(
+        dir.path(),
+        serde_json::json!([
+            { "version": "1.0.0", "interfaces": ["stream-io"], "exports": ["handle"] },
+            { "version": "1.1.0", "interfaces": ["stream-io", "event-bus"], "exports": ["handle"] },
+            {
+                "version": "1.2.0",
+                "interfaces": ["stream-io", "event-bus"],
+                "exports": ["handle", "handle-event"]
+            }
+        ]),
+    );
+    // The plugin claims 1.0.0 for event-bus, so only the table can reject it.
+    let plugin = publishing_component_at("1.0.0");
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &plugin).unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    std::fs::create_dir_all(&fixtures).unwrap();
+    std::fs::write(
+        fixtures.join("publish.json"),
+        r#"{"response": {"status": 503, "body_contains": "not available in vtx run"}}"#,
+    )
+    .unwrap();
+    let with_table = || {
+        let mut cmd = vtx(&dir, &toolchain);
+        cmd.env("VTX_KERNEL_API_TABLE", &table);
+        cmd
+    };
+
+    for api in ["1.1.0", "1.1.7"] {
+        with_table()
+            .args(["test", "--force", "--api", api])
+            .assert()
+            .success();
+    }
+    with_table()
+        .args(["test", "--force", "--api", "1.0.0"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "did not instantiate under a vtx:api@1.0.0 kernel",
+        ))
+        .stdout(predicate::str::contains(
+            "vtx:api/event-bus@1.0.0: vtx:api@1.0.0 has no event-bus interface",
+        ));
+    with_table()
+        .args(["test", "--force", "--api", "1.2.0"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "export handle-event: required by vtx:api@1.2.0",
+        ));
+    with_table()
+        .args(["test", "--force", "--api", "2.0.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "vtx:api 2.0.0 is not in the kernel API table (known versions: 1.0.0, 1.1.0, 1.2.0)",
+        ));
+
+    std::fs::write(dir.path().join("plugin.wasm"), &plugin).unwrap();
+    with_table()
+        .args(["run", "plugin.wasm", "--api", "1.1.0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("not available in vtx run"));
+    with_table()
+        .args(["kernel", "emulate", "plugin.wasm", "--api", "1.1.0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "plugin.wasm loads on a vtx:api@1.1.0 kernel",
+        ));
+    let output = with_table()
+        .args([
+            "kernel",
+            "emulate",
+            "plugin.wasm",
+            "--api",
+            "1.0.3",
+            "--json",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["api"], "1.0.3");
+    assert_eq!(report["entry"], "1.0.0");
+    assert_eq!(report["loads"], false);
+    let statuses: Vec<(&str, &str)> = report["imports"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| (i["name"].as_str().unwrap(), i["status"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("vtx:api/event-bus@1.0.0", "missing"),
+            ("vtx:api/stream-io@1.0.0", "linked")
+        ]
+    );
+    assert_eq!(report["exports"][0]["status"], "present");
+}
+
+/// A plugin component whose `handle` never returns.
+fn spinning_component() -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (import "vtx:api/stream-io@1.0.0" (instance $io
+                (export "buffer" (type (sub resource)))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (core module $m
+                (memory (export "mem") 1)
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (i32.const 1024))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (loop $spin (br $spin))
+                    (i32.const 64)))
+            (core instance $i (instantiate $m))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $i "mem"))
+                    (realloc (core func $i "realloc"))))
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_api_enforces_the_limits_of_a_kernel_version() {
+    let (dir, toolchain) = project("node");
+    let table = kernel_api_table(
+        dir.path(),
+        serde_json::json!([
+            {
+                "version": "1.0.0",
+                "interfaces": ["stream-io"],
+                "exports": ["handle"],
+                "limits": { "max_response_bytes": 8 }
+            },
+            {
+                "version": "1.1.0",
+                "interfaces": ["stream-io"],
+                "exports": ["handle"],
+                "limits": { "max_memory_bytes": 32768 }
+            },
+            {
+                "version": "1.2.0",
+                "interfaces": ["stream-io"],
+                "exports": ["handle"],
+                "limits": { "timeout_ms": 200 }
+            }
+        ]),
+    );
+    std::fs::write(dir.path().join("echo.wasm"), echo_component_at("1.0.0")).unwrap();
+    std::fs::write(dir.path().join("spin.wasm"), spinning_component()).unwrap();
+    let with_table = || {
+        let mut cmd = vtx(&dir, &toolchain);
+        cmd.env("VTX_KERNEL_API_TABLE", &table);
+        cmd
+    };
+
+    with_table()
+        .args(["run", "echo.wasm", "--path", "/short", "--api", "1.0.0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/short"));
+    with_table()
+        .args([
+            "run",
+            "echo.wasm",
+            "--path",
+            "/much-longer",
+            "--api",
+            "1.0.0",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Response body of 12 bytes exceeds the 8-byte limit of vtx:api@1.0.0",
+        ));
+
+    // One 64 KiB page is over the 32 KiB memory limit.
+    with_table()
+        .args(["kernel", "emulate", "echo.wasm", "--api", "1.1.0"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("memory:    32768 bytes"))
+        .stderr(predicate::str::contains(
+            "echo.wasm would not load on a vtx:api@1.1.0 kernel",
+        ))
+        .stderr(predicate::str::contains("exceeds memory limits"));
+
+    let started = std::time::Instant::now();
+    with_table()
+        .args(["--output", "json", "run", "spin.wasm", "--api", "1.2.0"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Plugin exceeded the 200 ms time limit of vtx:api@1.2.0",
+        ));
+    assert!(started.elapsed() < std::time::Duration::from_secs(20));
+
+    // The bundled table describes the kernel of the bundled WIT, which
+    // requires every export of the plugin world.
+    std::fs::write(dir.path().join("echo.wasm"), echo_component()).unwrap();
+    vtx(&dir, &toolchain)
+        .args(["kernel", "emulate", "echo.wasm", "--api", "3.4.2"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("authenticate"));
+}
+
+/// A plugin component whose `handle` answers 204 and which also exports
+/// `double: func(n: u32) -> u32`.
+fn doubling_component() -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (import "vtx:api/stream-io@3.4.2" (instance $io
+                (export "buffer" (type (sub resource)))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (core module $m
+                (memory (export "mem") 1)
+                (global $bump (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $p i32)
+                    (local.set $p (global.get $bump))
+                    (global.set $bump (i32.add (global.get $bump) (local.get 3)))
+                    (local.get $p))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (i32.store16 (i32.const 64) (i32.const 204))
+                    (i32.store8 (i32.const 68) (i32.const 0))
+                    (i32.const 64))
+                (func (export "double") (param i32) (result i32)
+                    (i32.mul (local.get 0) (i32.const 2))))
+            (core instance $i (instantiate $m))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $i "mem"))
+                    (realloc (core func $i "realloc"))))
+            (func (export "double") (param "n" u32) (result u32)
+                (canon lift (core func $i "double")))
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_scaffold_writes_fixtures_and_keeps_edits() {
+    let (dir, _) = project("node");
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &doubling_component()).unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    let scaffold = || {
+        vtx(&dir, &toolchain)
+            .args(["test", "scaffold", "--force"])
+            .assert()
+            .success()
+    };
+
+    scaffold()
+        .stdout(predicate::str::contains("created   tests/vtx/handle.json"))
+        .stdout(predicate::str::contains("created   tests/vtx/double.json"));
+    let read = |name: &str| -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(fixtures.join(name)).unwrap()).unwrap()
+    };
+    assert_eq!(
+        read("double.json"),
+        serde_json::json!({"export": "double", "args": {"n": 0}})
+    );
+    assert_eq!(read("handle.json")["request"]["method"], "GET");
+    vtx(&dir, &toolchain)
+        .args(["test", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("PASS double"));
+
+    // An edited fixture survives regeneration; an untouched one is refreshed.
+    let edited = r#"{"export": "double", "args": {"n": 21}, "result": 42}"#;
+    std::fs::write(fixtures.join("double.json"), edited).unwrap();
+    let stale = "{\"request\": {}}\n";
+    std::fs::write(fixtures.join("handle.json"), stale).unwrap();
+    let digest: String = sha2::Sha256::digest(stale.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    std::fs::write(
+        fixtures.join(".scaffold.sha256"),
+        format!("{digest}  handle.json\n"),
+    )
+    .unwrap();
+    scaffold()
+        .stdout(predicate::str::contains("updated   tests/vtx/handle.json"))
+        .stdout(predicate::str::contains("kept      tests/vtx/double.json"));
+    assert_eq!(
+        std::fs::read_to_string(fixtures.join("double.json")).unwrap(),
+        edited
+    );
+    assert_eq!(read("handle.json")["request"]["path"], "/");
+
+    std::fs::write(
+        fixtures.join("double.json"),
+        r#"{"export": "double", "args": {"n": 21}, "result": 40}"#,
+    )
+    .unwrap();
+    vtx(&dir, &toolchain)
+        .args(["test", "--force"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("expected result 40, got 42"));
+}
+
+#[test]
+fn test_filter_and_shard_select_fixtures() {
+    let (dir, _) = project("rust");
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &echo_component()).unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    std::fs::create_dir_all(&fixtures).unwrap();
+    for name in ["api-a", "api-b", "api-c", "page"] {
+        std::fs::write(
+            fixtures.join(format!("{name}.json")),
+            r#"{"response": {"status": 200}}"#,
+        )
+        .unwrap();
+    }
+    let names = |args: &[&str]| {
+        let output = vtx(&dir, &toolchain)
+            .args(["--output", "json", "test", "--force"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let report: serde_json::Value = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .find(|event| event["event"] == "report")
+            .expect("report event")["data"]
+            .clone();
+        let names: Vec<String> = report["fixtures"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["name"].as_str().unwrap().to_string())
+            .collect();
+        (names, report["cargo_test"].clone())
+    };
+
+    assert_eq!(
+        names(&["--filter", "api"]),
+        (
+            vec!["api-a".into(), "api-b".into(), "api-c".into()],
+            true.into()
+        )
+    );
+    assert_eq!(
+        names(&["--filter", "api", "--shard", "1/2"]),
+        (vec!["api-a".into(), "api-c".into()], true.into())
+    );
+    assert_eq!(
+        names(&["--filter", "api", "--shard", "2/2"]),
+        (vec!["api-b".into()], serde_json::Value::Null)
+    );
+    let calls = toolchain.calls().unwrap();
+    assert_eq!(
+        calls
+            .iter()
+            .filter(|c| *c == "cargo test -p demo api")
+            .count(),
+        2
+    );
+
+    vtx(&dir, &toolchain)
+        .args(["test", "--shard", "3/2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected N/M"));
+}
+
+/// A plugin component whose `handle` answers 400 but traps on an empty
+/// method, and whose `authorize(headers)` counts the headers but traps on an
+/// empty list.
+fn picky_component() -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (import "vtx:api/stream-io@3.4.2" (instance $io
+                (export "buffer" (type (sub resource)))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (core module $m
+                (memory (export "mem") 1)
+                (global $bump (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $p i32)
+                    (drop (memory.grow (i32.add (i32.shr_u (local.get 3) (i32.const 16)) (i32.const 1))))
+                    (local.set $p (global.get $bump))
+                    (global.set $bump (i32.add (global.get $bump) (local.get 3)))
+                    (local.get $p))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (if (i32.eqz (local.get 1)) (then unreachable))
+                    (i32.store16 (i32.const 64) (i32.const 400))
+                    (i32.store8 (i32.const 68) (i32.const 0))
+                    (i32.const 64))
+                (func (export "authorize") (param i32 i32) (result i32)
+                    (if (i32.eqz (local.get 1)) (then unreachable))
+                    (local.get 1)))
+            (core instance $i (instantiate $m))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $i "mem"))
+                    (realloc (core func $i "realloc"))))
+            (func (export "authorize") (param "headers" (list (tuple string string))) (result u32)
+                (canon lift (core func $i "authorize") (memory (core memory $i "mem"))
+                    (realloc (core func $i "realloc"))))
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_mutate_reports_traps_on_mutated_calls() {
+    let (dir, _) = project("node");
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &picky_component()).unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    std::fs::create_dir_all(&fixtures).unwrap();
+    std::fs::write(
+        fixtures.join("authorize.json"),
+        r#"{"export": "authorize", "args": {"headers": [["content-type", "text/plain"], ["authorization", "x"]]}, "result": 2}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        fixtures.join("handle.json"),
+        r#"{"request": {"path": "/items", "query": "page=2"}, "response": {"status": 400}}"#,
+    )
+    .unwrap();
+
+    let output = vtx(&dir, &toolchain)
+        .args(["--output", "json", "test", "--force", "--mutate"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event["event"] == "report")
+        .expect("report event")["data"]
+        .clone();
+    let fixtures = report["fixtures"].as_array().unwrap();
+    let result = |name: &str| {
+        fixtures
+            .iter()
+            .find(|f| f["name"] == name)
+            .unwrap_or_else(|| panic!("no result for {name}"))
+    };
+    assert_eq!(result("authorize")["passed"], true);
+    assert_eq!(result("handle")["passed"], true);
+
+    let failed: Vec<&str> = fixtures
+        .iter()
+        .filter(|f| f["passed"] == false)
+        .map(|f| f["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        failed,
+        ["authorize [headers:empty]", "handle [method:empty]"]
+    );
+    assert!(result("handle [method:empty]")["failures"][0]
+        .as_str()
+        .unwrap()
+        .starts_with("handle failed: "));
+
+    for name in [
+        "authorize [headers:missing authorization]",
+        "authorize [headers:wrong content-type]",
+        "authorize [headers[content-type][1]:oversized]",
+        "handle [method:unknown]",
+        "handle [path:oversized]",
+        "handle [path:truncated]",
+        "handle [query:malformed]",
+        "handle [query:truncated]",
+    ] {
+        assert_eq!(result(name)["passed"], true, "{name}");
+    }
+    assert_eq!(result("handle [path:empty]")["status"], 400);
+    // Dropping an element of a header pair no longer fits the tuple type.
+    assert!(!fixtures
+        .iter()
+        .any(|f| f["name"] == "authorize [headers[content-type]:empty]"));
+}
+
+/// A plugin component that answers with the low 16 bits of
+/// wasi:clocks/wall-clock's seconds as the status, without a body.
+fn clock_component() -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (import "wasi:clocks/wall-clock@0.2.12" (instance $clock
+                (type $dt (record (field "seconds" u64) (field "nanoseconds" u32)))
+                (export "datetime" (type $datetime (eq $dt)))
+                (export "now" (func (result $datetime)))
+            ))
+            (import "vtx:api/stream-io@3.4.2" (instance $io
+                (export "buffer" (type (sub resource)))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (core module $libc
+                (memory (export "mem") 1)
+                (global $bump (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $p i32)
+                    (local.set $p (global.get $bump))
+                    (global.set $bump (i32.add (global.get $bump) (local.get 3)))
+                    (local.get $p)))
+            (core instance $libc (instantiate $libc))
+            (core func $now (canon lower (func $clock "now") (memory (core memory $libc "mem"))))
+            (core module $m
+                (import "wasi" "now" (func $now (param i32)))
+                (import "libc" "mem" (memory 1))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (call $now (i32.const 128))
+                    (i32.store16 (i32.const 64) (i32.wrap_i64 (i64.load (i32.const 128))))
+                    (i32.store8 (i32.const 68) (i32.const 0))
+                    (i32.const 64)))
+            (core instance $i (instantiate $m
+                (with "libc" (instance $libc))
+                (with "wasi" (instance (export "now" (func $now))))))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $libc "mem"))
+                    (realloc (core func $libc "realloc"))))
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_time_freezes_the_plugin_clock() {
+    let (dir, _) = project("node");
+    let toolchain = FakeToolchain::install(&dir.path().join("bin"), &clock_component()).unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"node\"\n\n[test]\ntime = \"2024-01-01T00:00:00Z\"\n",
+    )
+    .unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    std::fs::create_dir_all(&fixtures).unwrap();
+    // 1704067200 seconds; the plugin answers with the low 16 bits.
+    std::fs::write(
+        fixtures.join("configured.json"),
+        r#"{"response": {"status": 128}}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        fixtures.join("pinned.json"),
+        r#"{"time": "1970-01-01T00:03:24Z", "response": {"status": 204}}"#,
+    )
+    .unwrap();
+
+    vtx(&dir, &toolchain)
+        .args(["test", "--force"])
+        .assert()
+        .success();
+    vtx(&dir, &toolchain)
+        .args(["test", "--force", "--time", "1970-01-01T00:03:20Z"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("expected status 128, got 200"))
+        .stdout(predicate::str::contains("PASS pinned"));
+    vtx(&dir, &toolchain)
+        .args(["test", "--force", "--time", "2024-02-30T00:00:00Z"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected an RFC 3339 UTC time"));
+}
+
+/// A running `vtx serve`, killed when dropped.
+struct Served {
+    child: std::process::Child,
+    /// `host:port` it listens on.
+    addr: String,
+    lines: std::sync::mpsc::Receiver<String>,
+}
+
+impl Served {
+    /// Start `vtx serve <args>` on a free port and wait until it listens.
+    fn start(dir: &tempfile::TempDir, toolchain: &FakeToolchain, args: &[&str]) -> Self {
+        use std::io::{BufRead, BufReader};
+
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("vtx"))
+            .current_dir(dir.path())
+            .env("PATH", toolchain.path_env().unwrap())
+            .args(["serve", "--addr", "127.0.0.1:0"])
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (lines, received) = std::sync::mpsc::channel();
+        let stdout = child.stdout.take().unwrap();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+                let _ = lines.send(line);
+            }
+        });
+        let mut served = Self {
+            child,
+            addr: String::new(),
+            lines: received,
+        };
+        let line = served.wait_for("Serving ");
+        let url = line
+            .split_whitespace()
+            .find(|w| w.starts_with("http://"))
+            .unwrap();
+        served.addr = url.trim_start_matches("http://").to_string();
+        served
+    }
+
+    /// The first output line containing `text`, failing after 60 seconds.
+    fn wait_for(&mut self, text: &str) -> String {
+        loop {
+            let line = self
+                .lines
+                .recv_timeout(std::time::Duration::from_secs(60))
+                .unwrap_or_else(|_| panic!("vtx serve never printed {text:?}"));
+            if line.contains(text) {
+                return line;
+            }
+        }
+    }
+
+    /// Send a request and return the response status and body.
+    fn request(&self, method: &str, target: &str, headers: &[(&str, &str)]) -> (u16, String) {
+        use std::io::{Read, Write};
+
+        let mut stream = std::net::TcpStream::connect(&self.addr).unwrap();
+        let mut head = format!("{method} {target} HTTP/1.1\r\nHost: {}\r\n", self.addr);
+        for (name, value) in headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|s| s.parse().ok())
+            .unwrap();
+        let body = response
+            .split_once("\r\n\r\n")
+            .map_or("", |(_, body)| body)
+            .to_string();
+        (status, body)
+    }
+
+    fn get(&self, target: &str) -> (u16, String) {
+        self.request("GET", target, &[])
+    }
+
+    /// Type a console line.
+    fn type_line(&mut self, line: &str) {
+        use std::io::Write;
+
+        let stdin = self.child.stdin.as_mut().unwrap();
+        writeln!(stdin, "{line}").unwrap();
+        stdin.flush().unwrap();
+    }
+}
+
+impl Drop for Served {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn serve_advances_a_manual_clock_on_request() {
+    let (dir, toolchain) = project("node");
+    std::fs::write(dir.path().join("clock.wasm"), clock_component()).unwrap();
+    let mut served = Served::start(
+        &dir,
+        &toolchain,
+        &[
+            "clock.wasm",
+            "--clock",
+            "manual",
+            "--time",
+            "1970-01-01T00:03:20Z",
+        ],
+    );
+
+    // The plugin answers with the clock's seconds, which stand still.
+    assert_eq!(served.get("/").0, 200);
+    assert_eq!(served.get("/").0, 200);
+    served.wait_for("GET / -> 200");
+
+    let (status, body) = served.request("POST", "/_vtx/clock/advance?by=4s", &[]);
+    assert_eq!(status, 200);
+    let clock: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(clock["mode"], "manual");
+    assert_eq!(clock["now"], "1970-01-01T00:03:24Z");
+    served.wait_for("Clock advanced by 4s to 1970-01-01T00:03:24Z");
+    assert_eq!(served.get("/").0, 204);
+
+    served.type_line("+1m");
+    served.wait_for("Clock advanced by 60s to 1970-01-01T00:04:24Z");
+    assert_eq!(served.get("/").0, 264);
+
+    let (status, body) = served.request("POST", "/_vtx/clock/set?to=1970-01-01T00:05:00Z", &[]);
+    assert_eq!((status, body.contains("00:05:00Z")), (200, true));
+    let (status, body) = served.request("POST", "/_vtx/clock/set?to=1970-01-01T00:00:00Z", &[]);
+    assert_eq!(status, 400);
+    assert!(body.contains("only moves forward"), "{body}");
+    assert_eq!(
+        served.request("POST", "/_vtx/clock/advance?by=soon", &[]).0,
+        400
+    );
+    assert_eq!(served.get("/_vtx/clock/advance").0, 405);
+    assert_eq!(served.get("/_vtx/nothing").0, 404);
+    assert_eq!(served.get("/").0, 300);
+}
+
+#[test]
+fn serve_keeps_the_system_clock_unless_asked() {
+    let (dir, toolchain) = project("node");
+    std::fs::write(dir.path().join("echo.wasm"), echo_component()).unwrap();
+    vtx(&dir, &toolchain)
+        .args(["serve", "echo.wasm", "--time", "2024-01-01T00:00:00Z"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--time needs --clock manual"));
+
+    let served = Served::start(&dir, &toolchain, &["echo.wasm"]);
+    let (status, body) = served.get("/hello?x=1");
+    assert_eq!(status, 200);
+    assert!(body.contains("/hello"), "{body}");
+    let (status, body) = served.get("/_vtx/clock");
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""mode":"system""#), "{body}");
+    assert_eq!(
+        served.request("POST", "/_vtx/clock/advance?by=1h", &[]).0,
+        409
+    );
+}
+
+/// A plugin component whose `handle` answers 401 without a current user,
+/// else `200 + <number of groups>` with the username as the body, and whose
+/// `authenticate` accepts any request with an `authorization` header as
+/// "token-user" and rejects others with 401.
+fn whoami_component() -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (import "vtx:api/stream-io@3.4.2" (instance $io
+                (export "buffer" (type (sub resource)))
+                (export "create-memory-buffer" (func (param "data" (list u8)) (result (own 0))))
+            ))
+            (alias export $io "buffer" (type $buffer))
+            (import "vtx:api/context@3.4.2" (instance $ctx
+                (type $cu (record (field "user-id" string) (field "username" string) (field "groups" (list string))))
+                (export "current-user" (type $current-user (eq $cu)))
+                (export "get-current-user" (func (result (option $current-user))))
+            ))
+            (core module $libc
+                (memory (export "mem") 1)
+                (data (i32.const 900) "token-user")
+                (global $bump (mut i32) (i32.const 1024))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $p i32)
+                    (local.set $p (i32.and
+                        (i32.add (global.get $bump) (i32.sub (local.get 2) (i32.const 1)))
+                        (i32.sub (i32.const 0) (local.get 2))))
+                    (global.set $bump (i32.add (local.get $p) (local.get 3)))
+                    (local.get $p)))
+            (core instance $libc (instantiate $libc))
+            (core func $create (canon lower (func $io "create-memory-buffer") (memory (core memory $libc "mem"))))
+            (core func $who (canon lower (func $ctx "get-current-user")
+                (memory (core memory $libc "mem")) (realloc (core func $libc "realloc"))))
+            (core module $m
+                (import "libc" "mem" (memory 1))
+                (import "io" "create" (func $create (param i32 i32) (result i32)))
+                (import "ctx" "who" (func $who (param i32)))
+                (func (export "handle") (param i32 i32 i32 i32 i32 i32) (result i32)
+                    (call $who (i32.const 256))
+                    (if (i32.eqz (i32.load8_u (i32.const 256)))
+                        (then
+                            (i32.store16 (i32.const 64) (i32.const 401))
+                            (i32.store8 (i32.const 68) (i32.const 0))
+                            (return (i32.const 64))))
+                    (i32.store16 (i32.const 64) (i32.add (i32.const 200) (i32.load (i32.const 280))))
+                    (i32.store8 (i32.const 68) (i32.const 1))
+                    (i32.store (i32.const 72)
+                        (call $create (i32.load (i32.const 268)) (i32.load (i32.const 272))))
+                    (i32.const 64))
+                (func (export "authenticate") (param $ptr i32) (param $len i32) (result i32)
+                    (local $i i32)
+                    (local $entry i32)
+                    (block $done
+                        (loop $next
+                            (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                            (local.set $entry (i32.add (local.get $ptr) (i32.mul (local.get $i) (i32.const 16))))
+                            (if (i32.and
+                                    (i32.eq (i32.load offset=4 (local.get $entry)) (i32.const 13))
+                                    (i32.eq (i32.load8_u (i32.load (local.get $entry))) (i32.const 97)))
+                                (then
+                                    (i32.store8 (i32.const 512) (i32.const 0))
+                                    (i32.store (i32.const 516) (i32.const 900))
+                                    (i32.store (i32.const 520) (i32.const 10))
+                                    (i32.store (i32.const 524) (i32.const 900))
+                                    (i32.store (i32.const 528) (i32.const 10))
+                                    (i32.store (i32.const 532) (i32.const 0))
+                                    (i32.store (i32.const 536) (i32.const 0))
+                                    (i32.store (i32.const 540) (i32.const 0))
+                                    (i32.store (i32.const 544) (i32.const 0))
+                                    (return (i32.const 512))))
+                            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                            (br $next)))
+                    (i32.store8 (i32.const 512) (i32.const 1))
+                    (i32.store16 (i32.const 516) (i32.const 401))
+                    (i32.const 512)))
+            (core instance $i (instantiate $m
+                (with "libc" (instance $libc))
+                (with "io" (instance (export "create" (func $create))))
+                (with "ctx" (instance (export "who" (func $who))))))
+            (type $req (record (field "method" string) (field "path" string) (field "query" string)))
+            (export $req-export "http-request" (type $req))
+            (type $resp (record (field "status" u16) (field "body" (option (own $buffer)))))
+            (export $resp-export "http-response" (type $resp))
+            (type $uc (record (field "user-id" string) (field "username" string)
+                (field "groups" (list string)) (field "metadata" string)))
+            (export $uc-export "user-context" (type $uc))
+            (func (export "handle") (param "req" $req-export) (result $resp-export)
+                (canon lift (core func $i "handle") (memory (core memory $libc "mem"))
+                    (realloc (core func $libc "realloc"))))
+            (func (export "authenticate") (param "headers" (list (tuple string string)))
+                (result (result $uc-export (error u16)))
+                (canon lift (core func $i "authenticate") (memory (core memory $libc "mem"))
+                    (realloc (core func $libc "realloc"))))
+        )"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn serve_attaches_dev_auth_users_to_requests() {
+    let (dir, toolchain) = project("node");
+    std::fs::write(dir.path().join("whoami.wasm"), whoami_component()).unwrap();
+    let config = "[project]\nname = \"demo\"\nlanguage = \"node\"\n\n\
+                  [dev.auth]\ndefault_user = \"alice\"\n\n\
+                  [dev.auth.users.alice]\nuser_id = \"u-1\"\ngroups = [\"admin\", \"editor\"]\n\n\
+                  [dev.auth.users.bob]\nuser_id = \"u-2\"\nusername = \"Bob\"\n";
+    std::fs::write(dir.path().join("vtx.toml"), config).unwrap();
+
+    let mut served = Served::start(&dir, &toolchain, &["whoami.wasm"]);
+    served.wait_for("Simulated users: alice, bob (default: alice)");
+    assert_eq!(served.get("/"), (202, "alice".to_string()));
+    assert_eq!(
+        served.request("GET", "/", &[("X-Vtx-User", "bob")]),
+        (200, "Bob".to_string())
+    );
+    served.wait_for("GET / as bob -> 200");
+    assert_eq!(served.request("GET", "/", &[("X-Vtx-User", "")]).0, 401);
+    let (status, body) = served.request("GET", "/", &[("X-Vtx-User", "carol")]);
+    assert_eq!(status, 400);
+    assert!(
+        body.contains("Unknown dev.auth user 'carol' (known: alice, bob)"),
+        "{body}"
+    );
+    drop(served);
+
+    // With authenticate, the plugin decides who the caller is from the
+    // headers, including the mock token added for a configured user.
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        config.replace("[dev.auth]\n", "[dev.auth]\nauthenticate = true\n"),
+    )
+    .unwrap();
+    let mut served = Served::start(&dir, &toolchain, &["whoami.wasm"]);
+    assert_eq!(served.get("/"), (200, "token-user".to_string()));
+    assert_eq!(
+        served.request("GET", "/", &[("X-Vtx-User", "")]),
+        (401, String::new())
+    );
+    served.wait_for("GET / -> 401 (rejected by authenticate");
+    assert_eq!(
+        served.request(
+            "GET",
+            "/",
+            &[("X-Vtx-User", ""), ("Authorization", "Bearer x")]
+        ),
+        (200, "token-user".to_string())
+    );
+    drop(served);
+
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        config.replace("default_user = \"alice\"", "default_user = \"carol\""),
+    )
+    .unwrap();
+    vtx(&dir, &toolchain)
+        .args(["serve", "whoami.wasm", "--addr", "127.0.0.1:0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "dev.auth.default_user 'carol' in vtx.toml is not one of dev.auth.users",
+        ));
+}
+
+#[test]
+fn serve_reloads_dev_settings_when_vtx_toml_changes() {
+    let (dir, toolchain) = project("node");
+    std::fs::write(dir.path().join("whoami.wasm"), whoami_component()).unwrap();
+    let config = "[project]\nname = \"demo\"\nlanguage = \"node\"\n\n\
+                  [dev.auth]\ndefault_user = \"alice\"\n\n\
+                  [dev.auth.users.alice]\nuser_id = \"u-1\"\n\n\
+                  [dev.auth.users.bob]\nuser_id = \"u-2\"\nusername = \"Bob\"\n";
+    std::fs::write(dir.path().join("vtx.toml"), config).unwrap();
+    let mut served = Served::start(
+        &dir,
+        &toolchain,
+        &[
+            "whoami.wasm",
+            "--clock",
+            "manual",
+            "--time",
+            "2024-01-01T00:00:00Z",
+        ],
+    );
+    assert_eq!(served.get("/"), (200, "alice".to_string()));
+    served.request("POST", "/_vtx/clock/advance?by=1h", &[]);
+
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        config.replace("default_user = \"alice\"", "default_user = \"bob\""),
+    )
+    .unwrap();
+    served.wait_for("Reloaded dev.auth users: alice, bob (default: bob)");
+    assert_eq!(served.get("/"), (200, "Bob".to_string()));
+
+    // A broken edit keeps the last good settings.
+    std::fs::write(dir.path().join("vtx.toml"), "[project\n").unwrap();
+    served.wait_for("Ignoring the vtx.toml change, keeping the previous settings");
+    assert_eq!(served.get("/"), (200, "Bob".to_string()));
+
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        config
+            .replace("default_user = \"alice\"", "default_user = \"bob\"")
+            .replace("name = \"demo\"", "name = \"demo\"\nversion = \"1.0.0\""),
+    )
+    .unwrap();
+    served.wait_for("vtx.toml changed; no dev host settings changed");
+
+    // The host kept running: the clock still reads the time it was moved to.
+    let (_, clock) = served.get("/_vtx/clock");
+    assert!(clock.contains("2024-01-01T01:00:00Z"), "{clock}");
+    served.type_line("+1s");
+    let line = served.wait_for("Clock advanced");
+    assert!(line.contains("2024-01-01T01:00:01Z"), "{line}");
+}
+
+#[test]
+fn serve_enforces_dev_limits_with_429_and_counters() {
+    let (dir, toolchain) = project("node");
+    std::fs::write(dir.path().join("whoami.wasm"), whoami_component()).unwrap();
+    let config = "[project]\nname = \"demo\"\nlanguage = \"node\"\n\n\
+                  [dev.auth]\ndefault_user = \"alice\"\n\n\
+                  [dev.auth.users.alice]\nuser_id = \"u-1\"\n\n\
+                  [dev.limits]\nrequests_per_minute = 2\nburst = 2\nquota = 3\nquota_window = \"1h\"\n";
+    std::fs::write(dir.path().join("vtx.toml"), config).unwrap();
+    let mut served = Served::start(
+        &dir,
+        &toolchain,
+        &[
+            "whoami.wasm",
+            "--clock",
+            "manual",
+            "--time",
+            "2024-01-01T00:00:00Z",
+        ],
+    );
+    assert_eq!(served.get("/"), (200, "alice".to_string()));
+    let line = served.wait_for("GET / as alice -> 200");
+    assert!(line.contains("[rate 1/2 left, quota 2/3 left]"), "{line}");
+    assert_eq!(served.get("/").0, 200);
+
+    // The bucket is empty: the kernel answers 429 without calling the plugin.
+    let (status, body) = served.get("/");
+    assert_eq!(status, 429);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"], "rate_limited");
+    assert_eq!(body["retry_after"], 30);
+    let line = served.wait_for("-> 429 (rate_limited");
+    assert!(line.contains("quota 1/3 left"), "{line}");
+
+    // Half a minute refills one request; that uses up the quota.
+    served.request("POST", "/_vtx/clock/advance?by=30s", &[]);
+    assert_eq!(served.get("/").0, 200);
+    served.request("POST", "/_vtx/clock/advance?by=5m", &[]);
+    let (status, body) = served.get("/");
+    assert_eq!(status, 429);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"], "quota_exceeded");
+    assert_eq!(body["retry_after"], 3600 - 330);
+    let (_, limits) = served.get("/_vtx/limits");
+    let limits: serde_json::Value = serde_json::from_str(&limits).unwrap();
+    assert_eq!(limits["quota_remaining"], 0);
+    assert_eq!(limits["rate_remaining"], 2);
+
+    // A new window restores the quota.
+    served.request("POST", "/_vtx/clock/advance?by=1h", &[]);
+    assert_eq!(served.get("/").0, 200);
+
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        config.replace("quota = 3", "quota = 100"),
+    )
+    .unwrap();
+    served.wait_for(
+        "Reloaded dev.limits: 2 requests/min (burst 2), quota 100 per 1h (counters reset)",
+    );
+    let (_, limits) = served.get("/_vtx/limits");
+    let limits: serde_json::Value = serde_json::from_str(&limits).unwrap();
+    assert_eq!(limits["quota_remaining"], 100);
+}
+
+#[test]
+fn dev_limits_are_validated() {
+    let (dir, toolchain) = project("node");
+    std::fs::write(dir.path().join("whoami.wasm"), whoami_component()).unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"node\"\n\n\
+         [dev.limits]\nburst = 5\n",
+    )
+    .unwrap();
+    let output = vtx(&dir, &toolchain)
+        .args(["serve", "whoami.wasm", "--addr", "127.0.0.1:0"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("dev.limits.burst in vtx.toml needs dev.limits.requests_per_minute"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn bench_checks_metrics_against_the_committed_baseline() {
+    let (dir, toolchain) = project("node");
+    std::fs::write(dir.path().join("echo.wasm"), echo_component()).unwrap();
+    let bench = |args: &[&str]| {
+        let mut cmd = vtx(&dir, &toolchain);
+        cmd.args([
+            "bench",
+            "echo.wasm",
+            "--path",
+            "/hello",
+            "--iterations",
+            "20",
+        ])
+        .args(args);
+        cmd
+    };
+    bench(&["--check"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No bench-baseline.json to check against",
+        ));
+
+    bench(&["--update-baseline"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Recorded echo.wasm as the bench baseline",
+        ));
+    let baseline_path = dir.path().join("bench-baseline.json");
+    let mut baseline: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&baseline_path).unwrap()).unwrap();
+    assert_eq!(baseline["request"]["path"], "/hello");
+    assert_eq!(baseline["iterations"], 20);
+    // One 64 KiB page, never grown.
+    assert_eq!(baseline["memory_bytes"], 65536);
+    assert!(baseline["p99_ms"].as_f64().unwrap() >= baseline["p50_ms"].as_f64().unwrap());
+
+    // Generous timing budgets keep the check independent of machine load.
+    baseline["cold_start_ms"] = 1e6.into();
+    baseline["p99_ms"] = 1e6.into();
+    std::fs::write(&baseline_path, baseline.to_string()).unwrap();
+    bench(&["--check"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "echo.wasm is within its bench budgets",
+        ));
+
+    baseline["memory_bytes"] = 32768.into();
+    std::fs::write(&baseline_path, baseline.to_string()).unwrap();
+    bench(&["--check"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "memory_bytes 32768 B -> 65536 B (+100.0%, tolerance 10%)",
+        ));
+    // Without --check a regression is reported, not fatal.
+    let output = bench(&["--output", "json"]).output().unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event["event"] == "report")
+        .expect("report event")["data"]
+        .clone();
+    let memory = report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|check| check["metric"] == "memory_bytes")
+        .unwrap()
+        .clone();
+    assert_eq!(memory["regressed"], true);
+    assert_eq!(report["result"]["memory_bytes"], 65536);
+
+    // A wider tolerance in vtx.toml accepts the growth.
+    let config = std::fs::read_to_string(dir.path().join("vtx.toml")).unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        format!("{config}\n[bench.tolerance]\nmemory_percent = 150\n"),
+    )
+    .unwrap();
+    bench(&["--check"]).assert().success();
+
+    // Every run but the failed first check is in the history.
+    let history =
+        std::fs::read_to_string(dir.path().join(".vtx-cache/bench-history.jsonl")).unwrap();
+    assert_eq!(history.lines().count(), 5, "{history}");
+    assert!(history.contains(r#""request":"GET /hello""#), "{history}");
+    vtx(&dir, &toolchain)
+        .args(["bench", "history", "--metric", "memory_bytes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("5 runs, memory_bytes: ▁▁▁▁▁"));
+
+    vtx(&dir, &toolchain)
+        .args(["bench", "echo.wasm", "--path", "/other", "--check"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "bench-baseline.json was recorded for GET /hello, not GET /other",
+        ));
+}
+
+#[test]
+fn bisect_finds_the_commit_that_grew_a_bench_metric() {
+    let (dir, toolchain) = project("lua");
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir.path())
+            .env("GIT_AUTHOR_NAME", "t")
+            .env("GIT_AUTHOR_EMAIL", "t@example.com")
+            .env("GIT_COMMITTER_NAME", "t")
+            .env("GIT_COMMITTER_EMAIL", "t@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        "[project]\nname = \"demo\"\nlanguage = \"lua\"\n\n\
+         [build]\ncmd = \"mkdir -p out && cp plugin.wasm out/demo.wasm\"\n\
+         output_dir = \"out\"\nartifact = \"demo.wasm\"\n\n\
+         [bench]\niterations = 5\ncold_starts = 1\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join(".gitignore"),
+        ".vtx-cache/\nout/\nbin/\n*.vtx\n.vtx-ledger.jsonl\n",
+    )
+    .unwrap();
+    let echo = echo_component();
+    std::fs::write(dir.path().join("plugin.wasm"), &echo).unwrap();
+    git(&["init", "-q"]);
+    git(&["add", "-A"]);
+    git(&["commit", "-qm", "good"]);
+    std::fs::write(dir.path().join("README"), "docs").unwrap();
+    git(&["add", "-A"]);
+    git(&["commit", "-qm", "docs"]);
+    // Four pages of linear memory instead of one.
+    std::fs::write(
+        dir.path().join("plugin.wasm"),
+        echo_component_with("3.4.2", 4),
+    )
+    .unwrap();
+    git(&["commit", "-qam", "grow memory"]);
+    std::fs::write(dir.path().join("README"), "more docs").unwrap();
+    git(&["commit", "-qam", "more docs"]);
+
+    vtx(&dir, &toolchain)
+        .args(["bisect", "--good", "HEAD~3", "--bench", "p42=1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown bench metric 'p42'"));
+
+    vtx(&dir, &toolchain)
+        .args([
+            "bisect",
+            "--good",
+            "HEAD~3",
+            "--bench",
+            "memory_bytes=100000",
+            "--force",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "out/demo.vtx: memory_bytes 262144",
+        ))
+        .stdout(predicate::str::contains("grow memory"));
+    // Each step's bench is in the history with its commit.
+    let history =
+        std::fs::read_to_string(dir.path().join(".vtx-cache/bench-history.jsonl")).unwrap();
+    assert!(history.lines().count() >= 2, "{history}");
+}
+
+#[test]
+fn test_seed_makes_random_reproducible() {
+    let (dir, _) = project("node");
+    let toolchain =
+        FakeToolchain::install(&dir.path().join("bin"), &coin_flip_component()).unwrap();
+    let fixtures = dir.path().join("tests/vtx");
+    std::fs::create_dir_all(&fixtures).unwrap();
+    for i in 0..16 {
+        std::fs::write(fixtures.join(format!("flip{i:02}.json")), "{}").unwrap();
+    }
+    let statuses = |seed: &str| -> Vec<serde_json::Value> {
+        let output = vtx(&dir, &toolchain)
+            .args(["--output", "json", "test", "--force", "--seed", seed])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let report = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .find(|event| event["event"] == "report")
+            .expect("report event")["data"]
+            .clone();
+        report["fixtures"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|fixture| fixture["status"].clone())
+            .collect()
+    };
+    // Every fixture draws from the same seed, so all flips agree, and again
+    // on the next run; 16 unseeded flips would agree by a 2^-15 chance.
+    let first = statuses("42");
+    assert!(first.iter().all(|status| *status == first[0]));
+    assert_eq!(statuses("42"), first);
+}
+
+#[test]
+fn release_runs_test_build_sign_sbom_and_publish() {
+    let (dir, toolchain) = project("rust");
+    toolchain.cargo_workspace(&["demo"]).unwrap();
+    vtx(&dir, &toolchain)
+        .args(["keygen", "release"])
+        .assert()
+        .success();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        format!(
+            "[project]\nname = \"demo\"\nversion = \"1.0.0\"\nlanguage = \"rust\"\n\n[package]\nsigning_key = '{}'\n\n[registry]\nurl = 'file://{}'\n\n[profiles.ci]\nforce = true\n",
+            dir.path().join("release.pk8").display(),
+            dir.path().join("registry").display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("release.toml"),
+        "[release]\nprofile = \"ci\"\nsteps = [\"bump\", \"test\", \"build\", \"sign\", \"sbom\", \"publish\"]\n",
+    )
+    .unwrap();
+
+    vtx(&dir, &toolchain)
+        .args(["release", "--skip-test"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("skipped (flag)"))
+        .stdout(predicate::str::contains("Release completed"));
+
+    assert!(!toolchain
+        .calls()
+        .unwrap()
+        .iter()
+        .any(|c| c.starts_with("cargo test")));
+    let artifact = dir.path().join("target/wasm32-wasip1/release/demo.vtx");
+    assert!(artifact.with_extension("vtx.sha256").exists());
+    assert!(artifact.with_extension("vtx.cdx.json").exists());
+    vtx(&dir, &toolchain)
+        .args([
+            "verify",
+            artifact.to_str().unwrap(),
+            "--public-key",
+            "release.pub",
+        ])
+        .assert()
+        .success();
+    assert!(dir
+        .path()
+        .join("registry/v1/packages/demo/1.0.1/artifact")
+        .exists());
+}
+
+#[test]
+fn release_rejects_artifact_steps_before_build() {
+    let (dir, toolchain) = project("rust");
+    std::fs::write(
+        dir.path().join("release.toml"),
+        "[release]\nsteps = [\"sign\", \"build\"]\n",
+    )
+    .unwrap();
+    vtx(&dir, &toolchain)
+        .arg("release")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Release step 'sign' needs the artifact of a 'build' step before it",
+        ));
+}
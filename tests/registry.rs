@@ -0,0 +1,423 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use sha2::Digest;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+type Store = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// `Range` headers of the GET requests the registry received.
+type Ranges = Arc<Mutex<Vec<String>>>;
+
+/// Minimal registry: PUT stores the body under the path, GET returns it, or
+/// the bytes from N on for `Range: bytes=N-` and from N to M for
+/// `Range: bytes=N-M`. The first artifact download answers 503 to exercise
+/// retries.
+fn serve_registry() -> (String, Store, Ranges) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let store = Store::default();
+    let shared = store.clone();
+    let ranges = Ranges::default();
+    let seen = ranges.clone();
+    std::thread::spawn(move || {
+        let mut failed_once = false;
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let path = parts.next().unwrap_or_default().to_string();
+            let mut length = 0;
+            let mut range: Option<(usize, Option<usize>)> = None;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    if name.eq_ignore_ascii_case("range") {
+                        seen.lock().unwrap().push(value.trim().to_string());
+                        range = value
+                            .trim()
+                            .strip_prefix("bytes=")
+                            .and_then(|r| r.split_once('-'))
+                            .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok())));
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut content_range = String::new();
+            let (status, body) = match method.as_str() {
+                "PUT" => {
+                    shared.lock().unwrap().insert(path, body);
+                    ("200 OK", Vec::new())
+                }
+                _ if path.ends_with("/artifact") && !failed_once => {
+                    failed_once = true;
+                    ("503 Service Unavailable", Vec::new())
+                }
+                _ => match (shared.lock().unwrap().get(&path), range) {
+                    (Some(stored), Some((start, end))) if start < stored.len() => {
+                        let end = end.map_or(stored.len() - 1, |end| end.min(stored.len() - 1));
+                        content_range =
+                            format!("Content-Range: bytes {start}-{end}/{}\r\n", stored.len());
+                        ("206 Partial Content", stored[start..=end].to_vec())
+                    }
+                    (Some(stored), _) => ("200 OK", stored.clone()),
+                    (None, _) => ("404 Not Found", Vec::new()),
+                },
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\n{content_range}Connection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        }
+    });
+    (url, store, ranges)
+}
+
+#[test]
+fn test_publish_and_pull_verify_checksum() {
+    let (url, store, _) = serve_registry();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        format!(
+            "[project]\nname = \"demo\"\nversion = \"1.2.0\"\nlanguage = \"lua\"\n\n[registry]\nurl = \"{url}\"\n"
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .env("HOME", dir.path())
+        .env_remove("VTX_REGISTRY_TOKEN")
+        .args(["publish", "demo.vtx"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No registry token"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .env("VTX_REGISTRY_TOKEN", "secret")
+        .args(["publish", "demo.vtx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Published demo@1.2.0"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["pull", "demo@1.2.0", "--out-dir", "pulled"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("retrying"));
+    assert_eq!(
+        std::fs::read(dir.path().join("pulled/demo-1.2.0.vtx")).unwrap(),
+        std::fs::read(dir.path().join("demo.vtx")).unwrap()
+    );
+
+    let artifact_path = "/v1/packages/demo/1.2.0/artifact".to_string();
+    *store
+        .lock()
+        .unwrap()
+        .get_mut(&artifact_path)
+        .unwrap()
+        .last_mut()
+        .unwrap() ^= 0xff;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .args(["pull", "demo@1.2.0", "--out-dir", "tampered"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("chunk does not match sha256:"));
+    assert!(!dir.path().join("tampered/demo-1.2.0.vtx").exists());
+}
+
+#[test]
+fn test_pull_resumes_partial_download() {
+    let (url, store, ranges) = serve_registry();
+    let artifact: Vec<u8> = (0..=255).cycle().take(4096).collect();
+    let digest = format!(
+        "sha256:{}",
+        sha2::Sha256::digest(&artifact)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>()
+    );
+    let entry = serde_json::json!({
+        "name": "demo",
+        "version": "1.2.0",
+        "digest": digest,
+        "size": artifact.len(),
+    });
+    store.lock().unwrap().insert(
+        "/v1/packages/demo/1.2.0".to_string(),
+        serde_json::to_vec(&entry).unwrap(),
+    );
+    store.lock().unwrap().insert(
+        "/v1/packages/demo/1.2.0/artifact".to_string(),
+        artifact.clone(),
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let pulled = dir.path().join("pulled");
+    std::fs::create_dir_all(&pulled).unwrap();
+    let part = pulled.join("demo-1.2.0.vtx.part");
+    let pull = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+        cmd.current_dir(dir.path())
+            .env("VTX_REGISTRY_URL", &url)
+            .args(["pull", "demo@1.2.0", "--out-dir", "pulled"])
+            .assert()
+    };
+
+    // An earlier pull stopped after 1000 bytes.
+    std::fs::write(&part, &artifact[..1000]).unwrap();
+    pull().success();
+    // Both the attempt the registry answers with 503 and the retry resume.
+    assert_eq!(
+        ranges.lock().unwrap().as_slice(),
+        ["bytes=1000-", "bytes=1000-"]
+    );
+    assert_eq!(
+        std::fs::read(pulled.join("demo-1.2.0.vtx")).unwrap(),
+        artifact
+    );
+    assert!(!part.exists());
+
+    // A .part that does not match the artifact fails the check and is
+    // discarded, so the next pull starts over.
+    std::fs::remove_file(pulled.join("demo-1.2.0.vtx")).unwrap();
+    std::fs::write(&part, vec![0u8; 1000]).unwrap();
+    pull()
+        .failure()
+        .stderr(predicate::str::contains("Checksum mismatch"));
+    assert!(!part.exists());
+    pull().success();
+    assert_eq!(
+        std::fs::read(pulled.join("demo-1.2.0.vtx")).unwrap(),
+        artifact
+    );
+}
+
+#[test]
+fn test_pull_fetches_and_verifies_chunks() {
+    let (url, store, ranges) = serve_registry();
+    let artifact: Vec<u8> = (0..=255).cycle().take(3500).collect();
+    let sha256 = |bytes: &[u8]| {
+        format!(
+            "sha256:{}",
+            sha2::Sha256::digest(bytes)
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<String>()
+        )
+    };
+    let entry = serde_json::json!({
+        "name": "demo",
+        "version": "1.2.0",
+        "digest": sha256(&artifact),
+        "size": artifact.len(),
+        "chunk_size": 1024,
+        "chunks": artifact.chunks(1024).map(sha256).collect::<Vec<_>>(),
+    });
+    store.lock().unwrap().insert(
+        "/v1/packages/demo/1.2.0".to_string(),
+        serde_json::to_vec(&entry).unwrap(),
+    );
+    store.lock().unwrap().insert(
+        "/v1/packages/demo/1.2.0/artifact".to_string(),
+        artifact.clone(),
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let pulled = dir.path().join("pulled");
+    std::fs::create_dir_all(&pulled).unwrap();
+    let part = pulled.join("demo-1.2.0.vtx.part");
+    let pull = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+        cmd.current_dir(dir.path())
+            .env("VTX_REGISTRY_URL", &url)
+            .args(["pull", "demo@1.2.0", "--out-dir", "pulled"])
+            .assert()
+    };
+
+    // An earlier pull left all chunks but the third, which is damaged:
+    // only that one is fetched again, and retried after the 503.
+    let mut damaged = artifact.clone();
+    damaged[2100] ^= 0xff;
+    std::fs::write(&part, &damaged).unwrap();
+    pull().success();
+    assert_eq!(
+        ranges.lock().unwrap().as_slice(),
+        ["bytes=2048-3071", "bytes=2048-3071"]
+    );
+    assert_eq!(
+        std::fs::read(pulled.join("demo-1.2.0.vtx")).unwrap(),
+        artifact
+    );
+    assert!(!part.exists());
+
+    // A fresh pull asks for every chunk by its range.
+    std::fs::remove_file(pulled.join("demo-1.2.0.vtx")).unwrap();
+    ranges.lock().unwrap().clear();
+    pull().success();
+    let mut asked = ranges.lock().unwrap().clone();
+    asked.sort();
+    assert_eq!(
+        asked,
+        [
+            "bytes=0-1023",
+            "bytes=1024-2047",
+            "bytes=2048-3071",
+            "bytes=3072-3499"
+        ]
+    );
+
+    // A chunk the registry serves damaged fails its digest on every attempt,
+    // and the good chunks stay in the .part for the next pull.
+    std::fs::remove_file(pulled.join("demo-1.2.0.vtx")).unwrap();
+    store
+        .lock()
+        .unwrap()
+        .get_mut("/v1/packages/demo/1.2.0/artifact")
+        .unwrap()[10] ^= 0xff;
+    pull()
+        .failure()
+        .stderr(predicate::str::contains("Failed to download chunk 1 of 4"))
+        .stderr(predicate::str::contains("chunk does not match sha256:"));
+    assert!(!pulled.join("demo-1.2.0.vtx").exists());
+    assert_eq!(std::fs::read(&part).unwrap()[3072..], artifact[3072..]);
+}
+
+#[test]
+fn test_file_registry_publish_and_pull() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = dir.path().join("store");
+    std::fs::write(
+        dir.path().join("vtx.toml"),
+        format!(
+            "[project]\nname = \"demo\"\nversion = \"1.2.0\"\nlanguage = \"lua\"\n\n[registry]\nurl = 'file://{}'\n",
+            store.display()
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("demo.wasm"),
+        [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00],
+    )
+    .unwrap();
+    let vtx = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+        cmd.current_dir(dir.path()).env_remove("VTX_REGISTRY_TOKEN");
+        cmd
+    };
+    vtx()
+        .args(["package", "--input", "demo.wasm", "--force"])
+        .assert()
+        .success();
+
+    // A file registry needs neither a token nor the network.
+    vtx()
+        .args(["--offline", "publish", "demo.vtx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Published demo@1.2.0"));
+    let version_dir = store.join("v1/packages/demo/1.2.0");
+    let entry: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(version_dir.join("index.json")).unwrap()).unwrap();
+    assert_eq!(entry["name"], "demo");
+    assert_eq!(
+        std::fs::read(version_dir.join("artifact")).unwrap(),
+        std::fs::read(dir.path().join("demo.vtx")).unwrap()
+    );
+
+    vtx()
+        .args(["--offline", "pull", "demo@1.2.0", "--out-dir", "pulled"])
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read(dir.path().join("pulled/demo-1.2.0.vtx")).unwrap(),
+        std::fs::read(dir.path().join("demo.vtx")).unwrap()
+    );
+
+    // A partial download resumes from the bytes already there.
+    let artifact = std::fs::read(dir.path().join("demo.vtx")).unwrap();
+    std::fs::write(
+        dir.path().join("pulled/demo-1.2.0.vtx.part"),
+        &artifact[..artifact.len() / 2],
+    )
+    .unwrap();
+    std::fs::remove_file(dir.path().join("pulled/demo-1.2.0.vtx")).unwrap();
+    vtx()
+        .args(["pull", "demo@1.2.0", "--out-dir", "pulled"])
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read(dir.path().join("pulled/demo-1.2.0.vtx")).unwrap(),
+        artifact
+    );
+
+    vtx()
+        .args(["pull", "demo@9.9.9", "--out-dir", "pulled"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("demo@9.9.9 is not published in"));
+
+    let mut tampered = artifact.clone();
+    *tampered.last_mut().unwrap() ^= 0xff;
+    std::fs::write(version_dir.join("artifact"), tampered).unwrap();
+    vtx()
+        .args(["pull", "demo@1.2.0", "--out-dir", "tampered"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Checksum mismatch"));
+    assert!(!dir.path().join("tampered/demo-1.2.0.vtx").exists());
+}
+
+#[test]
+fn test_registry_url_scheme_selects_backend() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .env("VTX_REGISTRY_URL", "ftp://example.com/plugins")
+        .args(["pull", "demo@1.2.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unsupported registry URL scheme 'ftp://'",
+        ));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("vtx"));
+    cmd.current_dir(dir.path())
+        .env("VTX_REGISTRY_URL", "s3://plugins/vtx")
+        .env_remove("AWS_ACCESS_KEY_ID")
+        .args(["pull", "demo@1.2.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "AWS_ACCESS_KEY_ID must be set to use an s3:// registry",
+        ));
+}